@@ -0,0 +1,331 @@
+//! Byte encodings of states that are fed to the state hash
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+use serde::ser::{self, Serialize};
+
+/// Error raised while encoding a state
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EncodeError {
+    /// The value's `Serialize` implementation reported an error
+    Custom(String),
+    /// A sequence or map was serialized without announcing its length
+    UnknownLength,
+    /// Conversion to JSON failed
+    Json(String),
+}
+
+impl fmt::Display for EncodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EncodeError::Custom(reason) | EncodeError::Json(reason) => f.write_str(reason),
+            EncodeError::UnknownLength => f.write_str("Sequences and maps must have a known length"),
+        }
+    }
+}
+
+impl core::error::Error for EncodeError {}
+
+impl ser::Error for EncodeError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        EncodeError::Custom(msg.to_string())
+    }
+}
+
+/// Encode a value in the layout `bincode::serialize` from bincode 1.3 produces
+/// 
+/// Numbers are fixed-width little-endian, lengths are `u64`, enum variants
+/// are `u32` indices and an `Option` is a `0` or `1` tag byte followed by
+/// the value, so the output matches `bincode::serialize` byte for byte.
+pub fn to_bincode_bytes<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>, EncodeError> {
+    let mut encoder = BincodeEncoder { output: Vec::new() };
+    value.serialize(&mut encoder)?;
+    Ok(encoder.output)
+}
+
+/// Serialize a value to canonical JSON
+/// 
+/// The value is serialized with `serde_json`, every object's keys are sorted
+/// recursively in byte order of their UTF-8 encoding, and the result is
+/// written compactly with no whitespace between tokens. See
+/// `State::canonical_json` for the full description.
+pub fn to_canonical_json<T: Serialize + ?Sized>(value: &T) -> Result<String, EncodeError> {
+    let value = serde_json::to_value(value).map_err(|e| EncodeError::Json(e.to_string()))?;
+    serde_json::to_string(&sort_json_keys(value)).map_err(|e| EncodeError::Json(e.to_string()))
+}
+
+/// Rebuild a JSON value with every object's keys in sorted order
+fn sort_json_keys(value: serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::Object(map) => {
+            let mut entries: Vec<(String, serde_json::Value)> = map.into_iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(&b.0));
+            serde_json::Value::Object(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| (key, sort_json_keys(value)))
+                    .collect(),
+            )
+        }
+        serde_json::Value::Array(items) => {
+            serde_json::Value::Array(items.into_iter().map(sort_json_keys).collect())
+        }
+        other => other,
+    }
+}
+
+/// Serializer writing the bincode 1.3 layout into a buffer
+struct BincodeEncoder {
+    output: Vec<u8>,
+}
+
+impl BincodeEncoder {
+    fn write_len(&mut self, len: usize) {
+        self.output.extend_from_slice(&(len as u64).to_le_bytes());
+    }
+    
+    fn write_variant(&mut self, variant_index: u32) {
+        self.output.extend_from_slice(&variant_index.to_le_bytes());
+    }
+}
+
+macro_rules! serialize_le_bytes {
+    ($($method:ident: $ty:ty),* $(,)?) => {
+        $(
+            fn $method(self, v: $ty) -> Result<(), EncodeError> {
+                self.output.extend_from_slice(&v.to_le_bytes());
+                Ok(())
+            }
+        )*
+    };
+}
+
+impl ser::Serializer for &mut BincodeEncoder {
+    type Ok = ();
+    type Error = EncodeError;
+    type SerializeSeq = Self;
+    type SerializeTuple = Self;
+    type SerializeTupleStruct = Self;
+    type SerializeTupleVariant = Self;
+    type SerializeMap = Self;
+    type SerializeStruct = Self;
+    type SerializeStructVariant = Self;
+    
+    serialize_le_bytes! {
+        serialize_i8: i8,
+        serialize_i16: i16,
+        serialize_i32: i32,
+        serialize_i64: i64,
+        serialize_i128: i128,
+        serialize_u8: u8,
+        serialize_u16: u16,
+        serialize_u32: u32,
+        serialize_u64: u64,
+        serialize_u128: u128,
+        serialize_f32: f32,
+        serialize_f64: f64,
+    }
+    
+    fn serialize_bool(self, v: bool) -> Result<(), EncodeError> {
+        self.output.push(u8::from(v));
+        Ok(())
+    }
+    
+    fn serialize_char(self, v: char) -> Result<(), EncodeError> {
+        let mut buffer = [0u8; 4];
+        self.output.extend_from_slice(v.encode_utf8(&mut buffer).as_bytes());
+        Ok(())
+    }
+    
+    fn serialize_str(self, v: &str) -> Result<(), EncodeError> {
+        self.serialize_bytes(v.as_bytes())
+    }
+    
+    fn serialize_bytes(self, v: &[u8]) -> Result<(), EncodeError> {
+        self.write_len(v.len());
+        self.output.extend_from_slice(v);
+        Ok(())
+    }
+    
+    fn serialize_none(self) -> Result<(), EncodeError> {
+        self.output.push(0);
+        Ok(())
+    }
+    
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<(), EncodeError> {
+        self.output.push(1);
+        value.serialize(self)
+    }
+    
+    fn serialize_unit(self) -> Result<(), EncodeError> {
+        Ok(())
+    }
+    
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<(), EncodeError> {
+        Ok(())
+    }
+    
+    fn serialize_unit_variant(self, _name: &'static str, variant_index: u32, _variant: &'static str) -> Result<(), EncodeError> {
+        self.write_variant(variant_index);
+        Ok(())
+    }
+    
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(self, _name: &'static str, value: &T) -> Result<(), EncodeError> {
+        value.serialize(self)
+    }
+    
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        variant_index: u32,
+        _variant: &'static str,
+        value: &T,
+    ) -> Result<(), EncodeError> {
+        self.write_variant(variant_index);
+        value.serialize(self)
+    }
+    
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self, EncodeError> {
+        self.write_len(len.ok_or(EncodeError::UnknownLength)?);
+        Ok(self)
+    }
+    
+    fn serialize_tuple(self, _len: usize) -> Result<Self, EncodeError> {
+        Ok(self)
+    }
+    
+    fn serialize_tuple_struct(self, _name: &'static str, _len: usize) -> Result<Self, EncodeError> {
+        Ok(self)
+    }
+    
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self, EncodeError> {
+        self.write_variant(variant_index);
+        Ok(self)
+    }
+    
+    fn serialize_map(self, len: Option<usize>) -> Result<Self, EncodeError> {
+        self.write_len(len.ok_or(EncodeError::UnknownLength)?);
+        Ok(self)
+    }
+    
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self, EncodeError> {
+        Ok(self)
+    }
+    
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self, EncodeError> {
+        self.write_variant(variant_index);
+        Ok(self)
+    }
+    
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+}
+
+impl ser::SerializeSeq for &mut BincodeEncoder {
+    type Ok = ();
+    type Error = EncodeError;
+    
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), EncodeError> {
+        value.serialize(&mut **self)
+    }
+    
+    fn end(self) -> Result<(), EncodeError> {
+        Ok(())
+    }
+}
+
+impl ser::SerializeTuple for &mut BincodeEncoder {
+    type Ok = ();
+    type Error = EncodeError;
+    
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), EncodeError> {
+        value.serialize(&mut **self)
+    }
+    
+    fn end(self) -> Result<(), EncodeError> {
+        Ok(())
+    }
+}
+
+impl ser::SerializeTupleStruct for &mut BincodeEncoder {
+    type Ok = ();
+    type Error = EncodeError;
+    
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), EncodeError> {
+        value.serialize(&mut **self)
+    }
+    
+    fn end(self) -> Result<(), EncodeError> {
+        Ok(())
+    }
+}
+
+impl ser::SerializeTupleVariant for &mut BincodeEncoder {
+    type Ok = ();
+    type Error = EncodeError;
+    
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), EncodeError> {
+        value.serialize(&mut **self)
+    }
+    
+    fn end(self) -> Result<(), EncodeError> {
+        Ok(())
+    }
+}
+
+impl ser::SerializeMap for &mut BincodeEncoder {
+    type Ok = ();
+    type Error = EncodeError;
+    
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), EncodeError> {
+        key.serialize(&mut **self)
+    }
+    
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), EncodeError> {
+        value.serialize(&mut **self)
+    }
+    
+    fn end(self) -> Result<(), EncodeError> {
+        Ok(())
+    }
+}
+
+impl ser::SerializeStruct for &mut BincodeEncoder {
+    type Ok = ();
+    type Error = EncodeError;
+    
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, _key: &'static str, value: &T) -> Result<(), EncodeError> {
+        value.serialize(&mut **self)
+    }
+    
+    fn end(self) -> Result<(), EncodeError> {
+        Ok(())
+    }
+}
+
+impl ser::SerializeStructVariant for &mut BincodeEncoder {
+    type Ok = ();
+    type Error = EncodeError;
+    
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, _key: &'static str, value: &T) -> Result<(), EncodeError> {
+        value.serialize(&mut **self)
+    }
+    
+    fn end(self) -> Result<(), EncodeError> {
+        Ok(())
+    }
+}
@@ -0,0 +1,69 @@
+//! State hashes and their computation
+
+use crate::encoding::{to_bincode_bytes, to_canonical_json, EncodeError};
+use core::fmt;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+#[cfg(feature = "wasm")]
+use alloc::string::{String, ToString};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::wasm_bindgen;
+
+/// Order in which a state's map entries are fed to the state hash
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum IterationStrategy {
+    /// Hash a canonical form with map entries sorted by key, independent of insertion order
+    Sorted,
+    /// Hash the serialized state as-is, in whatever order its maps iterate
+    Insertion,
+}
+
+/// Cryptographic hash of a state
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct StateHash(#[cfg_attr(feature = "wasm", wasm_bindgen(skip))] pub [u8; 32]);
+
+impl StateHash {
+    /// Compute the canonical hash of canonical JSON bytes
+    /// 
+    /// This is the SHA-256 digest of `bytes`, which should be the UTF-8
+    /// encoding of a string produced by `State::canonical_json`. It differs
+    /// from the Blake3 hash produced by `StateHasher::hash`.
+    pub fn from_canonical_json_bytes(bytes: &[u8]) -> StateHash {
+        StateHash(Sha256::digest(bytes).into())
+    }
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl StateHash {
+    /// Get the hash as lowercase hex
+    #[wasm_bindgen(js_name = toHex)]
+    pub fn to_hex(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl fmt::Display for StateHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.iter().try_for_each(|byte| write!(f, "{:02x}", byte))
+    }
+}
+
+/// Compute the Blake3 hash of encoded state bytes
+pub fn hash_bytes(bytes: &[u8]) -> StateHash {
+    StateHash(*blake3::hash(bytes).as_bytes())
+}
+
+/// Compute the hash of a normalized state
+/// 
+/// `Insertion` hashes the layout from `to_bincode_bytes` and `Sorted` hashes
+/// `to_canonical_json`, so the result equals `StateHasher::hash` for a state
+/// that `State::normalize` leaves unchanged.
+pub fn hash_state<S: Serialize + ?Sized>(state: &S, order: IterationStrategy) -> Result<StateHash, EncodeError> {
+    let encoded = match order {
+        IterationStrategy::Insertion => to_bincode_bytes(state)?,
+        IterationStrategy::Sorted => to_canonical_json(state)?.into_bytes(),
+    };
+    Ok(hash_bytes(&encoded))
+}
@@ -0,0 +1,18 @@
+//! Core types of the DTRE that build without `std`
+//! 
+//! Rule set versions, state hashes and the state encodings that are hashed
+//! only need `alloc`, so they also work on targets without `std` such as
+//! WebAssembly. The `dtre` crate re-exports them and hashes states through
+//! `hash_state`.
+
+#![no_std]
+
+extern crate alloc;
+
+mod encoding;
+mod hash;
+mod version;
+
+pub use encoding::{to_bincode_bytes, to_canonical_json, EncodeError};
+pub use hash::{hash_bytes, hash_state, IterationStrategy, StateHash};
+pub use version::{Version, VersionConstraint};
@@ -0,0 +1,154 @@
+//! Semantic versions of rule sets and the constraints they are checked against
+
+use alloc::string::{String, ToString};
+use core::fmt;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::wasm_bindgen;
+
+/// Semantic version for rule sets
+/// 
+/// Versions are ordered by major, then minor, then patch.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct Version {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl Version {
+    /// Create a new version
+    pub fn new(major: u32, minor: u32, patch: u32) -> Self {
+        Self { major, minor, patch }
+    }
+    
+    /// Check if this version is compatible with another version
+    pub fn is_compatible_with(&self, other: &Version) -> bool {
+        self.major == other.major
+    }
+    
+    /// Parse a `major.minor.patch` version string, as produced by `Display`
+    pub fn parse(version: &str) -> Option<Self> {
+        let mut parts = version.trim().split('.').map(|part| part.parse::<u32>().ok());
+        match (parts.next(), parts.next(), parts.next(), parts.next()) {
+            (Some(Some(major)), Some(Some(minor)), Some(Some(patch)), None) => Some(Self::new(major, minor, patch)),
+            _ => None,
+        }
+    }
+    
+    /// Check whether this version is inside the range a constraint allows
+    pub fn satisfies(&self, constraint: &VersionConstraint) -> bool {
+        *self >= constraint.lower && constraint.upper.as_ref().is_none_or(|upper| self < upper)
+    }
+    
+    /// The next version in `Version` ordering
+    fn successor(&self) -> Version {
+        Version::new(self.major, self.minor, self.patch.saturating_add(1))
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// Semver range a version can be checked against
+/// 
+/// Parsed from space-separated comparators that must all hold:
+/// `^1.2.3` (`>=1.2.3 <2.0.0`, or up to the next minor or patch for `0.x`
+/// versions), `~1.2.3` (`>=1.2.3 <1.3.0`), `>=`, `>`, `<=`, `<`, `=` or a bare
+/// version for an exact match, and `*` for any version. Every comparator
+/// narrows one contiguous range, so a constraint is stored as that range.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VersionConstraint {
+    source: String,
+    /// Smallest allowed version
+    lower: Version,
+    /// First version above the range, if the range is bounded
+    upper: Option<Version>,
+}
+
+impl VersionConstraint {
+    /// Parse a constraint such as `">=1.0.0 <2.0.0"` or `"^1.2.3"`
+    pub fn parse(constraint: &str) -> Option<Self> {
+        let mut parsed = Self {
+            source: constraint.trim().to_string(),
+            lower: Version::new(0, 0, 0),
+            upper: None,
+        };
+        let mut comparators = constraint.split_whitespace().peekable();
+        comparators.peek()?;
+        
+        for comparator in comparators {
+            if comparator == "*" {
+                continue;
+            }
+            let (operator, version) = match comparator.find(|c: char| c.is_ascii_digit()) {
+                Some(start) => comparator.split_at(start),
+                None => return None,
+            };
+            let version = Version::parse(version)?;
+            let (lower, upper) = match operator {
+                "^" => {
+                    let upper = if version.major > 0 {
+                        Version::new(version.major.saturating_add(1), 0, 0)
+                    } else if version.minor > 0 {
+                        Version::new(0, version.minor.saturating_add(1), 0)
+                    } else {
+                        version.successor()
+                    };
+                    (Some(version), Some(upper))
+                }
+                "~" => {
+                    let upper = Version::new(version.major, version.minor.saturating_add(1), 0);
+                    (Some(version), Some(upper))
+                }
+                ">=" => (Some(version), None),
+                ">" => (Some(version.successor()), None),
+                "<=" => (None, Some(version.successor())),
+                "<" => (None, Some(version)),
+                "=" | "" => {
+                    let upper = version.successor();
+                    (Some(version), Some(upper))
+                }
+                _ => return None,
+            };
+            parsed.narrow(lower, upper);
+        }
+        
+        Some(parsed)
+    }
+    
+    /// Check whether some version satisfies both constraints
+    pub fn is_compatible_with(&self, other: &VersionConstraint) -> bool {
+        let mut intersection = self.clone();
+        intersection.narrow(Some(other.lower.clone()), other.upper.clone());
+        !intersection.is_empty()
+    }
+    
+    /// Check whether no version satisfies the constraint, as in `">2.0.0 <1.0.0"`
+    pub fn is_empty(&self) -> bool {
+        self.upper.as_ref().is_some_and(|upper| *upper <= self.lower)
+    }
+    
+    /// Intersect the range with another one
+    fn narrow(&mut self, lower: Option<Version>, upper: Option<Version>) {
+        if let Some(lower) = lower {
+            self.lower = self.lower.clone().max(lower);
+        }
+        if let Some(upper) = upper {
+            self.upper = Some(match self.upper.take() {
+                Some(current) => current.min(upper),
+                None => upper,
+            });
+        }
+    }
+}
+
+impl fmt::Display for VersionConstraint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
@@ -0,0 +1,79 @@
+//! Derive macros for the DTRE
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Fields, Index, Type};
+
+/// Derive `std::hash::Hash` with `HashMap` fields hashed in sorted key order
+/// 
+/// Every other field is hashed with its own `Hash` implementation, in declaration order.
+/// A `HashMap` field is hashed as if its entries were sorted by key and each key and
+/// value hashed in turn, so the result does not depend on insertion order. Keys of
+/// such fields must implement `Ord`.
+/// 
+/// Only structs are supported.
+#[proc_macro_derive(DeterministicHash)]
+pub fn derive_deterministic_hash(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input).unwrap_or_else(syn::Error::into_compile_error).into()
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let fields = match &input.data {
+        Data::Struct(data) => &data.fields,
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "DeterministicHash can only be derived for structs",
+            ))
+        }
+    };
+    
+    let statements = fields.iter().enumerate().map(|(index, field)| {
+        let access = match &field.ident {
+            Some(ident) => quote!(self.#ident),
+            None => {
+                let index = Index::from(index);
+                quote!(self.#index)
+            }
+        };
+        if is_hash_map(&field.ty) {
+            quote! {
+                for (__key, __value) in ::dtre::hasher::iter_sorted(&#access) {
+                    ::std::hash::Hash::hash(__key, state);
+                    ::std::hash::Hash::hash(__value, state);
+                }
+            }
+        } else {
+            quote!(::std::hash::Hash::hash(&#access, state);)
+        }
+    });
+    
+    let name = &input.ident;
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+    let body = match fields {
+        Fields::Unit => quote!(let _ = state;),
+        _ => quote!(#(#statements)*),
+    };
+    
+    Ok(quote! {
+        impl #impl_generics ::std::hash::Hash for #name #type_generics #where_clause {
+            fn hash<__H: ::std::hash::Hasher>(&self, state: &mut __H) {
+                #body
+            }
+        }
+    })
+}
+
+/// Whether a field type names `HashMap`, with or without a module path
+fn is_hash_map(ty: &Type) -> bool {
+    match ty {
+        Type::Path(path) => path
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "HashMap"),
+        _ => false,
+    }
+}
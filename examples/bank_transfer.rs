@@ -101,9 +101,7 @@ impl State for BankingState {
         }
         
         if self.total_fees_collected < 0 {
-            return Err(ValidationError::InvalidState {
-                reason: "Total fees cannot be negative".to_string(),
-            });
+            return Err(ValidationError::invalid_field("total_fees_collected", "Total fees cannot be negative"));
         }
         
         Ok(())
@@ -180,73 +178,108 @@ impl Transaction for TransferTransaction {
 // Rule Sets - Version Evolution
 // ============================================================================
 
+/// Summarize a transfer for the audit trail, e.g. "Transfer $100.00 from ACC001 to ACC002 (fee: $1.00)"
+fn describe_transfer(transaction: &TransferTransaction, fee: i64) -> String {
+    format!(
+        "Transfer ${:.2} from {} to {} (fee: ${:.2})",
+        transaction.amount as f64 / 100.0,
+        transaction.from_account,
+        transaction.to_account,
+        fee as f64 / 100.0
+    )
+}
+
 /// Version 1.0.0: Basic transfer rules with fixed fee
 pub struct TransferRulesV1;
 
+impl TransferRulesV1 {
+    /// Flat fee charged per transfer
+    const FEE: i64 = 100;
+}
+
 impl RuleSet<BankingState, TransferTransaction> for TransferRulesV1 {
     fn version(&self) -> Version {
         Version::new(1, 0, 0)
     }
     
-    fn apply(
+    fn pre_validate(
         &self,
         state: &BankingState,
         transaction: &TransferTransaction,
         _context: &ExecutionContext,
-    ) -> Result<BankingState, ProcessingError> {
-        transaction.validate().map_err(|e| ProcessingError::TransactionFailed {
-            transaction_id: transaction.id.clone(),
-            reason: format!("{:?}", e),
-        })?;
-        
-        let mut new_state = state.clone();
-        
-        let from_account = new_state.accounts.get(&transaction.from_account)
-            .ok_or_else(|| ProcessingError::TransactionFailed {
-                transaction_id: transaction.id.clone(),
-                reason: format!("Source account {} not found", transaction.from_account),
+    ) -> Result<(), ValidationError> {
+        let from_account = state.accounts.get(&transaction.from_account)
+            .ok_or_else(|| ValidationError::RuleViolated {
+                rule: format!("Source account {} not found", transaction.from_account),
             })?;
         
-        let to_account = new_state.accounts.get(&transaction.to_account)
-            .ok_or_else(|| ProcessingError::TransactionFailed {
-                transaction_id: transaction.id.clone(),
-                reason: format!("Destination account {} not found", transaction.to_account),
+        let to_account = state.accounts.get(&transaction.to_account)
+            .ok_or_else(|| ValidationError::RuleViolated {
+                rule: format!("Destination account {} not found", transaction.to_account),
             })?;
         
         if from_account.status != AccountStatus::Active {
-            return Err(ProcessingError::TransactionFailed {
-                transaction_id: transaction.id.clone(),
-                reason: format!("Source account {} is not active", transaction.from_account),
+            return Err(ValidationError::RuleViolated {
+                rule: format!("Source account {} is not active", transaction.from_account),
             });
         }
         
         if to_account.status != AccountStatus::Active {
-            return Err(ProcessingError::TransactionFailed {
-                transaction_id: transaction.id.clone(),
-                reason: format!("Destination account {} is not active", transaction.to_account),
+            return Err(ValidationError::RuleViolated {
+                rule: format!("Destination account {} is not active", transaction.to_account),
             });
         }
         
         if from_account.currency != transaction.currency || to_account.currency != transaction.currency {
-            return Err(ProcessingError::TransactionFailed {
-                transaction_id: transaction.id.clone(),
-                reason: "Currency mismatch".to_string(),
+            return Err(ValidationError::RuleViolated {
+                rule: "Currency mismatch".to_string(),
             });
         }
         
-        let fee = 100;
-        let total_debit = transaction.amount + fee;
-        
+        let total_debit = transaction.amount + Self::FEE;
         if from_account.balance < total_debit {
-            return Err(ProcessingError::TransactionFailed {
-                transaction_id: transaction.id.clone(),
-                reason: format!(
+            return Err(ValidationError::RuleViolated {
+                rule: format!(
                     "Insufficient balance: have {}, need {}",
                     from_account.balance, total_debit
                 ),
             });
         }
         
+        Ok(())
+    }
+    
+    fn describe_transaction(
+        &self,
+        _state: &BankingState,
+        transaction: &TransferTransaction,
+        _context: &ExecutionContext,
+    ) -> String {
+        describe_transfer(transaction, Self::FEE)
+    }
+    
+    fn apply(
+        &self,
+        state: &BankingState,
+        transaction: &TransferTransaction,
+        context: &ExecutionContext,
+    ) -> Result<BankingState, ProcessingError> {
+        transaction.validate().map_err(|e| ProcessingError::TransactionFailed {
+            transaction_id: transaction.id.clone(),
+            reason: format!("{:?}", e),
+        })?;
+        
+        // Callers of `apply` do not always run `pre_validate` first
+        self.pre_validate(state, transaction, context).map_err(|e| ProcessingError::TransactionFailed {
+            transaction_id: transaction.id.clone(),
+            reason: e.to_string(),
+        })?;
+        
+        let mut new_state = state.clone();
+        
+        let fee = Self::FEE;
+        let total_debit = transaction.amount + fee;
+        
         new_state.accounts.get_mut(&transaction.from_account).unwrap().balance -= total_debit;
         new_state.accounts.get_mut(&transaction.to_account).unwrap().balance += transaction.amount;
         new_state.total_fees_collected += fee;
@@ -268,11 +301,27 @@ impl RuleSet<BankingState, TransferTransaction> for TransferRulesV1 {
 /// Version 1.1.0: Percentage-based fee
 pub struct TransferRulesV1_1;
 
+impl TransferRulesV1_1 {
+    /// 1% fee with a $0.50 minimum
+    fn fee(amount: i64) -> i64 {
+        std::cmp::max(50, amount / 100)
+    }
+}
+
 impl RuleSet<BankingState, TransferTransaction> for TransferRulesV1_1 {
     fn version(&self) -> Version {
         Version::new(1, 1, 0)
     }
     
+    fn describe_transaction(
+        &self,
+        _state: &BankingState,
+        transaction: &TransferTransaction,
+        _context: &ExecutionContext,
+    ) -> String {
+        describe_transfer(transaction, Self::fee(transaction.amount))
+    }
+    
     fn apply(
         &self,
         state: &BankingState,
@@ -319,7 +368,7 @@ impl RuleSet<BankingState, TransferTransaction> for TransferRulesV1_1 {
             });
         }
         
-        let fee = std::cmp::max(50, transaction.amount / 100);
+        let fee = Self::fee(transaction.amount);
         let total_debit = transaction.amount + fee;
         
         if from_account.balance < total_debit {
@@ -353,11 +402,33 @@ impl RuleSet<BankingState, TransferTransaction> for TransferRulesV1_1 {
 /// Version 2.0.0: Transfer limits and tiered fees
 pub struct TransferRulesV2;
 
+impl TransferRulesV2 {
+    /// $0.50 below $100, 1% below $1,000, 0.5% above
+    fn fee(amount: i64) -> i64 {
+        if amount < 10_000 {
+            50
+        } else if amount < 100_000 {
+            amount / 100
+        } else {
+            amount / 200
+        }
+    }
+}
+
 impl RuleSet<BankingState, TransferTransaction> for TransferRulesV2 {
     fn version(&self) -> Version {
         Version::new(2, 0, 0)
     }
     
+    fn describe_transaction(
+        &self,
+        _state: &BankingState,
+        transaction: &TransferTransaction,
+        _context: &ExecutionContext,
+    ) -> String {
+        describe_transfer(transaction, Self::fee(transaction.amount))
+    }
+    
     fn apply(
         &self,
         state: &BankingState,
@@ -414,13 +485,7 @@ impl RuleSet<BankingState, TransferTransaction> for TransferRulesV2 {
             });
         }
         
-        let fee = if transaction.amount < 10_000 {
-            50
-        } else if transaction.amount < 100_000 {
-            transaction.amount / 100
-        } else {
-            transaction.amount / 200
-        };
+        let fee = Self::fee(transaction.amount);
         
         let total_debit = transaction.amount + fee;
         
@@ -549,7 +614,12 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("  ACC003: ${:.2}", result_v1.final_state.accounts["ACC003"].balance as f64 / 100.0);
     println!("  Total Fees: ${:.2}", result_v1.final_state.total_fees_collected as f64 / 100.0);
     println!("  Final Hash: {}", result_v1.final_hash);
-    println!("  Transactions Processed: {}\n", result_v1.execution_trace.transactions_processed);
+    println!("  Transactions Processed: {}", result_v1.execution_trace.transactions_processed);
+    println!("  Audit Trail:");
+    for description in result_v1.execution_trace.descriptions() {
+        println!("    {}", description);
+    }
+    println!();
     
     // Replay with Version 1.1.0 (percentage fee)
     println!("=== Replay with Version 1.1.0 (1% fee, min $0.50) ===");
@@ -0,0 +1,80 @@
+//! Adapters that expose replay results to other systems
+
+use crate::types::{ExecutionTrace, StateTransition};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+
+/// A domain event paired with the transaction that produced it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TimestampedEvent<E> {
+    pub transaction_id: String,
+    /// Timestamp of the transaction, as recorded in the execution trace
+    pub timestamp: DateTime<Utc>,
+    pub event: E,
+}
+
+/// Function diffing the states before and after a transaction into domain events
+type StateToEvents<S, E> = Box<dyn Fn(&S, &S) -> Vec<E> + Send + Sync>;
+
+/// Converts state transitions into named domain events for event-sourced systems
+/// 
+/// The conversion function receives the state before and after each transaction
+/// and returns the events describing the difference. It should be a pure function
+/// so that replaying the same transitions yields the same event stream.
+pub struct EventSourcingAdapter<S, E> {
+    state_to_events: StateToEvents<S, E>,
+}
+
+impl<S, E> EventSourcingAdapter<S, E> {
+    /// Create an adapter from a function diffing two consecutive states into events
+    pub fn new(state_to_events: impl Fn(&S, &S) -> Vec<E> + Send + Sync + 'static) -> Self {
+        Self {
+            state_to_events: Box::new(state_to_events),
+        }
+    }
+    
+    /// Convert every transition into its domain events, in transition order
+    /// 
+    /// Timestamps are taken from the rule applications in `execution_trace`;
+    /// transitions without a matching rule application are skipped.
+    pub fn extract_events(
+        &self,
+        transitions: &[StateTransition<S>],
+        execution_trace: &ExecutionTrace,
+    ) -> Vec<TimestampedEvent<E>> {
+        self.event_stream(transitions, execution_trace).collect()
+    }
+    
+    /// Lazily convert transitions into domain events, one transition at a time
+    pub fn event_stream<'a>(
+        &'a self,
+        transitions: &'a [StateTransition<S>],
+        execution_trace: &ExecutionTrace,
+    ) -> impl Iterator<Item = TimestampedEvent<E>> + 'a {
+        let timestamps: HashMap<String, DateTime<Utc>> = execution_trace
+            .rule_applications
+            .iter()
+            .map(|application| (application.transaction_id.clone(), application.timestamp))
+            .collect();
+        
+        transitions.iter().flat_map(move |transition| match timestamps.get(&transition.transaction_id) {
+            Some(&timestamp) => (self.state_to_events)(&transition.from_state, &transition.to_state)
+                .into_iter()
+                .map(|event| TimestampedEvent {
+                    transaction_id: transition.transaction_id.clone(),
+                    timestamp,
+                    event,
+                })
+                .collect(),
+            None => Vec::new(),
+        })
+    }
+}
+
+impl<S, E> fmt::Debug for EventSourcingAdapter<S, E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EventSourcingAdapter").finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,165 @@
+//! Aggregate values computed over the fields of a state
+//! 
+//! `StateAggregator` works on the serialized form of a state, so totals such
+//! as "sum of all balances" can be computed without writing a traversal for
+//! every state type. Paths use the same dot-separated form as
+//! `ReplayResult::reconcile`, with `*` matching every entry of an object or
+//! array, e.g. `accounts.*.balance`.
+
+use crate::traits::State;
+use serde_json::Value;
+use std::cmp::Ordering;
+use std::marker::PhantomData;
+
+/// Sums, counts and extremes over the fields of one state
+#[derive(Debug, Clone)]
+pub struct StateAggregator<S> {
+    value: Value,
+    _state: PhantomData<S>,
+}
+
+impl<S: State> StateAggregator<S> {
+    /// Serialize a state for aggregation
+    /// 
+    /// A state that cannot be represented as JSON aggregates as `null`, so
+    /// every path matches nothing.
+    pub fn new(state: &S) -> Self {
+        Self {
+            value: serde_json::to_value(state).unwrap_or(Value::Null),
+            _state: PhantomData,
+        }
+    }
+    
+    /// Sum the integer values at `json_path`, ignoring values that are not integers
+    /// 
+    /// The sum saturates at `i64::MIN` and `i64::MAX` instead of overflowing.
+    pub fn sum(&self, json_path: &str) -> i64 {
+        self.select(json_path)
+            .iter()
+            .filter_map(|value| value.as_i64())
+            .fold(0i64, |total, value| total.saturating_add(value))
+    }
+    
+    /// Count the values at `json_path`
+    pub fn count(&self, json_path: &str) -> usize {
+        self.select(json_path).len()
+    }
+    
+    /// Find the smallest value at `json_path`
+    /// 
+    /// Numbers are compared numerically and strings lexicographically, with
+    /// numbers ordered before strings; values of other types are ignored.
+    pub fn min(&self, json_path: &str) -> Option<Value> {
+        self.select(json_path).into_iter().filter(|value| is_comparable(value)).min_by(|a, b| compare(a, b)).cloned()
+    }
+    
+    /// Find the largest value at `json_path`, ordered as for `min`
+    pub fn max(&self, json_path: &str) -> Option<Value> {
+        self.select(json_path).into_iter().filter(|value| is_comparable(value)).max_by(|a, b| compare(a, b)).cloned()
+    }
+    
+    /// Collect every value matched by a path, in document order
+    fn select(&self, json_path: &str) -> Vec<&Value> {
+        json_path.split('.').fold(vec![&self.value], |current, segment| {
+            current
+                .into_iter()
+                .flat_map(|value| -> Vec<&Value> {
+                    match (value, segment) {
+                        (Value::Object(map), "*") => map.values().collect(),
+                        (Value::Array(items), "*") => items.iter().collect(),
+                        (Value::Object(map), key) => map.get(key).into_iter().collect(),
+                        (Value::Array(items), index) => {
+                            index.parse::<usize>().ok().and_then(|i| items.get(i)).into_iter().collect()
+                        }
+                        _ => Vec::new(),
+                    }
+                })
+                .collect()
+        })
+    }
+}
+
+fn is_comparable(value: &Value) -> bool {
+    value.is_number() || value.is_string()
+}
+
+fn compare(a: &Value, b: &Value) -> Ordering {
+    match (a, b) {
+        (Value::Number(x), Value::Number(y)) => match (x.as_i64(), y.as_i64()) {
+            (Some(x), Some(y)) => x.cmp(&y),
+            _ => x.as_f64().unwrap_or(f64::NAN).total_cmp(&y.as_f64().unwrap_or(f64::NAN)),
+        },
+        (Value::String(x), Value::String(y)) => x.cmp(y),
+        (Value::Number(_), _) => Ordering::Less,
+        (_, Value::Number(_)) => Ordering::Greater,
+        _ => Ordering::Equal,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ValidationError;
+    use serde::{Deserialize, Serialize};
+    use std::collections::BTreeMap;
+    
+    #[derive(Debug, Clone, Hash, Serialize, Deserialize)]
+    struct Ledger {
+        accounts: BTreeMap<String, Account>,
+        tags: Vec<String>,
+    }
+    
+    #[derive(Debug, Clone, Hash, Serialize, Deserialize)]
+    struct Account {
+        balance: i64,
+        active: bool,
+    }
+    
+    impl State for Ledger {
+        fn validate(&self) -> Result<(), ValidationError> {
+            Ok(())
+        }
+    }
+    
+    fn ledger() -> Ledger {
+        let mut accounts = BTreeMap::new();
+        accounts.insert("A".to_string(), Account { balance: 30, active: true });
+        accounts.insert("B".to_string(), Account { balance: -5, active: false });
+        accounts.insert("C".to_string(), Account { balance: 12, active: true });
+        Ledger { accounts, tags: vec!["beta".to_string(), "alpha".to_string()] }
+    }
+    
+    #[test]
+    fn test_sum_and_count_with_wildcards() {
+        let aggregator = StateAggregator::new(&ledger());
+        
+        assert_eq!(aggregator.sum("accounts.*.balance"), 37);
+        assert_eq!(aggregator.count("accounts.*"), 3);
+        assert_eq!(aggregator.count("tags.*"), 2);
+        assert_eq!(aggregator.sum("accounts.A.balance"), 30);
+        assert_eq!(aggregator.count("accounts.Z.balance"), 0);
+        assert_eq!(aggregator.sum("accounts.*.active"), 0);
+    }
+    
+    #[test]
+    fn test_sum_saturates() {
+        let mut ledger = ledger();
+        ledger.accounts.get_mut("A").unwrap().balance = i64::MAX;
+        assert_eq!(StateAggregator::new(&ledger).sum("accounts.*.balance"), i64::MAX);
+        
+        ledger.accounts.get_mut("A").unwrap().balance = i64::MIN;
+        ledger.accounts.get_mut("C").unwrap().balance = -1;
+        assert_eq!(StateAggregator::new(&ledger).sum("accounts.*.balance"), i64::MIN);
+    }
+    
+    #[test]
+    fn test_min_and_max() {
+        let aggregator = StateAggregator::new(&ledger());
+        
+        assert_eq!(aggregator.min("accounts.*.balance"), Some(serde_json::json!(-5)));
+        assert_eq!(aggregator.max("accounts.*.balance"), Some(serde_json::json!(30)));
+        assert_eq!(aggregator.min("tags.*"), Some(serde_json::json!("alpha")));
+        assert_eq!(aggregator.max("tags.1"), Some(serde_json::json!("alpha")));
+        assert_eq!(aggregator.max("accounts.*.active"), None);
+    }
+}
@@ -0,0 +1,188 @@
+//! Detection of unusual patterns in execution traces
+
+use crate::types::ExecutionTrace;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// Thresholds for `ExecutionTrace::detect_anomalies`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AnomalyDetectionConfig {
+    /// Largest share of processed transactions, from 0.0 to 1.0, that may change the state hash
+    /// 
+    /// Every transaction changing the hash can point to data corruption, such
+    /// as a timestamp or counter leaking into the state. The default of 1.0
+    /// never reports.
+    pub max_state_hash_change_rate: f64,
+    /// Smallest gap between the timestamps of consecutive processed transactions; 0 never reports
+    pub min_transaction_interval_ms: u64,
+    /// Longest run of skipped transactions before it is reported
+    pub max_consecutive_failures: u32,
+    /// Largest factor by which the state may grow from one checkpoint to the next
+    pub unexpected_state_growth_factor: f64,
+}
+
+impl Default for AnomalyDetectionConfig {
+    fn default() -> Self {
+        Self {
+            max_state_hash_change_rate: 1.0,
+            min_transaction_interval_ms: 0,
+            max_consecutive_failures: 3,
+            unexpected_state_growth_factor: 10.0,
+        }
+    }
+}
+
+/// Kind of pattern an anomaly matched
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum AnomalyType {
+    /// More transactions changed the state hash than `max_state_hash_change_rate` allows
+    ExcessiveHashChanges,
+    /// A transaction followed the previous one sooner than `min_transaction_interval_ms`
+    TransactionBurst,
+    /// More transactions in a row were skipped than `max_consecutive_failures` allows
+    ConsecutiveFailures,
+    /// The state grew by more than `unexpected_state_growth_factor` between checkpoints
+    UnexpectedStateGrowth,
+}
+
+/// How urgently an anomaly should be looked at
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum AnomalySeverity {
+    Low,
+    Medium,
+    High,
+}
+
+/// An unusual pattern found in an execution trace
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReplayAnomaly {
+    pub anomaly_type: AnomalyType,
+    /// Position in the replayed sequence of the transaction where the anomaly shows
+    pub transaction_index: usize,
+    pub description: String,
+    pub severity: AnomalySeverity,
+}
+
+pub(crate) fn detect_anomalies(trace: &ExecutionTrace, config: &AnomalyDetectionConfig) -> Vec<ReplayAnomaly> {
+    // Processed and skipped transactions share the positions of the replayed sequence
+    let first_index = trace.transactions_processed.saturating_sub(trace.rule_applications.len());
+    let total = trace.rule_applications.len() + trace.skipped_indices.len();
+    let skipped: HashSet<usize> = trace.skipped_indices.iter().copied().collect();
+    let processed_positions: Vec<usize> = (0..total)
+        .filter(|position| !skipped.contains(position))
+        .map(|position| first_index + position)
+        .collect();
+    
+    let mut anomalies = Vec::new();
+    detect_hash_changes(trace, config, &processed_positions, &mut anomalies);
+    detect_bursts(trace, config, &processed_positions, &mut anomalies);
+    detect_consecutive_failures(trace, config, first_index, &mut anomalies);
+    detect_state_growth(trace, config, first_index, &processed_positions, &mut anomalies);
+    anomalies.sort_by_key(|anomaly| anomaly.transaction_index);
+    anomalies
+}
+
+fn detect_hash_changes(
+    trace: &ExecutionTrace,
+    config: &AnomalyDetectionConfig,
+    processed_positions: &[usize],
+    anomalies: &mut Vec<ReplayAnomaly>,
+) {
+    let transitions: Vec<_> = trace.state_transitions.iter().filter(|t| !trace.is_mutation(&t.transaction_id)).collect();
+    let Some(&last_position) = transitions.len().checked_sub(1).and_then(|last| processed_positions.get(last)) else {
+        return;
+    };
+    let changed = transitions.iter().filter(|t| t.from_hash != t.to_hash).count();
+    let rate = changed as f64 / transitions.len() as f64;
+    if rate > config.max_state_hash_change_rate {
+        anomalies.push(ReplayAnomaly {
+            anomaly_type: AnomalyType::ExcessiveHashChanges,
+            transaction_index: last_position,
+            description: format!(
+                "{} of {} transactions changed the state hash, more than the allowed rate {}",
+                changed, transitions.len(), config.max_state_hash_change_rate
+            ),
+            severity: AnomalySeverity::High,
+        });
+    }
+}
+
+fn detect_bursts(
+    trace: &ExecutionTrace,
+    config: &AnomalyDetectionConfig,
+    processed_positions: &[usize],
+    anomalies: &mut Vec<ReplayAnomaly>,
+) {
+    if config.min_transaction_interval_ms == 0 {
+        return;
+    }
+    for (position, pair) in processed_positions.iter().skip(1).zip(trace.rule_applications.windows(2)) {
+        let interval_ms = (pair[1].timestamp - pair[0].timestamp).num_milliseconds();
+        if interval_ms < config.min_transaction_interval_ms as i64 {
+            anomalies.push(ReplayAnomaly {
+                anomaly_type: AnomalyType::TransactionBurst,
+                transaction_index: *position,
+                description: format!(
+                    "Transaction {} followed {} after {} ms, less than {} ms",
+                    pair[1].transaction_id, pair[0].transaction_id, interval_ms, config.min_transaction_interval_ms
+                ),
+                severity: AnomalySeverity::Low,
+            });
+        }
+    }
+}
+
+fn detect_consecutive_failures(
+    trace: &ExecutionTrace,
+    config: &AnomalyDetectionConfig,
+    first_index: usize,
+    anomalies: &mut Vec<ReplayAnomaly>,
+) {
+    let mut run = 0u32;
+    let mut previous = None;
+    for (&index, transaction_id) in trace.skipped_indices.iter().zip(&trace.skipped_transactions) {
+        run = if previous.is_some_and(|previous| previous + 1 == index) { run + 1 } else { 1 };
+        previous = Some(index);
+        // Report each run once, where it first exceeds the limit
+        if run == config.max_consecutive_failures.saturating_add(1) {
+            anomalies.push(ReplayAnomaly {
+                anomaly_type: AnomalyType::ConsecutiveFailures,
+                transaction_index: first_index + index,
+                description: format!(
+                    "Transaction {} is failure {} in a row, more than {}",
+                    transaction_id, run, config.max_consecutive_failures
+                ),
+                severity: AnomalySeverity::High,
+            });
+        }
+    }
+}
+
+fn detect_state_growth(
+    trace: &ExecutionTrace,
+    config: &AnomalyDetectionConfig,
+    first_index: usize,
+    processed_positions: &[usize],
+    anomalies: &mut Vec<ReplayAnomaly>,
+) {
+    for pair in trace.checkpoints.windows(2) {
+        let (before, after) = (pair[0].state_size_bytes, pair[1].state_size_bytes);
+        if before == 0 || after as f64 <= before as f64 * config.unexpected_state_growth_factor {
+            continue;
+        }
+        // A checkpoint's index counts processed transactions; report the last one it includes
+        let transaction_index = pair[1].transaction_index
+            .checked_sub(first_index + 1)
+            .and_then(|processed| processed_positions.get(processed).copied())
+            .unwrap_or(pair[1].transaction_index);
+        anomalies.push(ReplayAnomaly {
+            anomaly_type: AnomalyType::UnexpectedStateGrowth,
+            transaction_index,
+            description: format!(
+                "State grew from {} to {} bytes between checkpoints, more than {}x",
+                before, after, config.unexpected_state_growth_factor
+            ),
+            severity: AnomalySeverity::Medium,
+        });
+    }
+}
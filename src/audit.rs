@@ -0,0 +1,152 @@
+//! Signed audit bundles proving the outcome of a replay
+
+use crate::error::ProcessingError;
+use crate::hasher::StateHasher;
+use crate::serialization::to_canonical_json;
+use crate::types::{ReplayResult, StateHash};
+use chrono::{DateTime, Utc};
+use ed25519_dalek::{Signer, Verifier};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::Sha256;
+use std::fmt;
+use std::io;
+use std::path::Path;
+
+/// Algorithm used to sign an audit bundle
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SigningAlgorithm {
+    /// HMAC-SHA256 with a shared secret of any length
+    HmacSha256,
+    /// Ed25519 with a 32-byte secret key seed
+    Ed25519,
+}
+
+/// Key material and algorithm for signing and verifying audit bundles
+#[derive(Clone)]
+pub struct AuditBundleConfig {
+    pub signing_key: Vec<u8>,
+    pub algorithm: SigningAlgorithm,
+}
+
+impl AuditBundleConfig {
+    /// Sign a payload with the configured key
+    fn sign(&self, payload: &[u8]) -> Result<Vec<u8>, ProcessingError> {
+        match self.algorithm {
+            SigningAlgorithm::HmacSha256 => {
+                let mut mac = self.hmac()?;
+                mac.update(payload);
+                Ok(mac.finalize().into_bytes().to_vec())
+            }
+            SigningAlgorithm::Ed25519 => Ok(self.ed25519_key()?.sign(payload).to_bytes().to_vec()),
+        }
+    }
+    
+    /// Check a signature over a payload, returning false for malformed keys or signatures
+    fn verify(&self, payload: &[u8], signature: &[u8]) -> bool {
+        match self.algorithm {
+            SigningAlgorithm::HmacSha256 => self.hmac().is_ok_and(|mut mac| {
+                mac.update(payload);
+                mac.verify_slice(signature).is_ok()
+            }),
+            SigningAlgorithm::Ed25519 => {
+                let (Ok(key), Ok(signature)) = (self.ed25519_key(), ed25519_dalek::Signature::from_slice(signature)) else {
+                    return false;
+                };
+                key.verifying_key().verify(payload, &signature).is_ok()
+            }
+        }
+    }
+    
+    fn hmac(&self) -> Result<Hmac<Sha256>, ProcessingError> {
+        Hmac::<Sha256>::new_from_slice(&self.signing_key).map_err(|e| ProcessingError::SigningFailed {
+            reason: format!("Invalid HMAC key: {}", e),
+        })
+    }
+    
+    fn ed25519_key(&self) -> Result<ed25519_dalek::SigningKey, ProcessingError> {
+        let seed: [u8; 32] = self.signing_key.as_slice().try_into().map_err(|_| ProcessingError::SigningFailed {
+            reason: format!("Ed25519 keys must be 32 bytes, got {}", self.signing_key.len()),
+        })?;
+        Ok(ed25519_dalek::SigningKey::from_bytes(&seed))
+    }
+}
+
+impl fmt::Debug for AuditBundleConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AuditBundleConfig")
+            .field("signing_key", &"<redacted>")
+            .field("algorithm", &self.algorithm)
+            .finish()
+    }
+}
+
+/// A replay result together with a signed summary of its outcome
+/// 
+/// The signed payload is the canonical JSON of the final hash, the Merkle root
+/// of all intermediate state hashes, the number of transactions processed and
+/// the bundle timestamp. The timestamp is the replay context's time, so two
+/// identical replays produce byte-identical payloads and signatures.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditBundle<S> {
+    pub replay_result: ReplayResult<S>,
+    /// Merkle root of the state hash after each transaction
+    pub merkle_root: StateHash,
+    pub signature: Vec<u8>,
+    pub signed_payload_json: String,
+    pub timestamp: DateTime<Utc>,
+}
+
+impl<S> AuditBundle<S> {
+    /// Summarize and sign a replay result
+    pub(crate) fn seal(
+        replay_result: ReplayResult<S>,
+        timestamp: DateTime<Utc>,
+        config: &AuditBundleConfig,
+    ) -> Result<Self, ProcessingError> {
+        let transition_hashes: Vec<StateHash> = replay_result
+            .execution_trace
+            .state_transitions
+            .iter()
+            .map(|transition| transition.to_hash)
+            .collect();
+        let merkle_root = StateHasher::new().merkle_root(&transition_hashes);
+        
+        let signed_payload_json = to_canonical_json(&json!({
+            "final_hash": replay_result.final_hash.to_string(),
+            "merkle_root": merkle_root.to_string(),
+            "transactions_processed": replay_result.execution_trace.transactions_processed,
+            "timestamp": timestamp,
+        }))
+        .map_err(|e| ProcessingError::SigningFailed {
+            reason: format!("Failed to serialize audit payload: {}", e),
+        })?;
+        let signature = config.sign(signed_payload_json.as_bytes())?;
+        
+        Ok(Self {
+            replay_result,
+            merkle_root,
+            signature,
+            signed_payload_json,
+            timestamp,
+        })
+    }
+    
+    /// Check that the signature matches the signed payload under the given key
+    /// 
+    /// Only the payload is signed; a verified bundle proves the final hash and
+    /// Merkle root recorded in the payload, not the unsigned `replay_result`.
+    pub fn verify_signature(&self, config: &AuditBundleConfig) -> bool {
+        config.verify(self.signed_payload_json.as_bytes(), &self.signature)
+    }
+    
+    /// Write the bundle to a file as pretty-printed JSON
+    pub fn to_file(&self, path: &Path) -> io::Result<()>
+    where
+        S: Serialize,
+    {
+        let json = serde_json::to_vec_pretty(self).map_err(io::Error::other)?;
+        std::fs::write(path, json)
+    }
+}
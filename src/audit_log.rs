@@ -0,0 +1,108 @@
+//! Verbose log of every internal operation, for debugging determinism failures
+//!
+//! Enabled by the `debug-audit` feature and switched on per processor with
+//! `TransactionProcessor::enable_audit_mode`. Two replays of the same inputs
+//! should record the same operations in the same order; `find_first_divergence`
+//! points at the first one that differs.
+
+use crate::types::StateHash;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// An internal operation and its outcome
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditOperation {
+    /// `Transaction::validate` was called
+    EvaluateTransactionValidate { ok: bool },
+    /// `RuleSet::pre_validate` was called
+    EvaluateRuleGuard { ok: bool },
+    /// `RuleSet::apply_with_audit` was called
+    EvaluateRuleApply { ok: bool },
+    /// `State::validate` was called on a new state
+    EvaluateInvariants { ok: bool },
+    /// A state hash was computed
+    ComputeHash { hash: StateHash },
+    /// A checkpoint was stored at the given transaction index
+    CheckpointCreated { index: usize },
+}
+
+/// A recorded operation with its position in the log
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditLogEntry {
+    /// Position in the log, starting at 0
+    pub sequence: u64,
+    /// Wall-clock time the operation was recorded; differs between runs
+    pub recorded_at: DateTime<Utc>,
+    pub operation: AuditOperation,
+}
+
+/// Ordered log of internal operations
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AuditLog {
+    entries: Vec<AuditLogEntry>,
+}
+
+impl AuditLog {
+    /// Create an empty log
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Append an operation, stamped with the next sequence number and the current time
+    pub fn record(&mut self, operation: AuditOperation) {
+        self.entries.push(AuditLogEntry {
+            sequence: self.entries.len() as u64,
+            recorded_at: Utc::now(),
+            operation,
+        });
+    }
+    
+    /// Get the recorded entries in order
+    pub fn entries(&self) -> &[AuditLogEntry] {
+        &self.entries
+    }
+    
+    /// Find the sequence number of the first operation that differs from `other`
+    /// 
+    /// Wall-clock times are ignored. If one log is a prefix of the other, the
+    /// divergence is at the end of the shorter one; identical logs give `None`.
+    pub fn find_first_divergence(&self, other: &AuditLog) -> Option<usize> {
+        let common = self.entries.len().min(other.entries.len());
+        (0..common)
+            .find(|&i| self.entries[i].operation != other.entries[i].operation)
+            .or_else(|| (self.entries.len() != other.entries.len()).then_some(common))
+    }
+}
+
+impl From<&[AuditLogEntry]> for AuditLog {
+    fn from(entries: &[AuditLogEntry]) -> Self {
+        Self {
+            entries: entries.to_vec(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_divergence_ignores_wall_clock() {
+        let mut a = AuditLog::new();
+        let mut b = AuditLog::new();
+        for log in [&mut a, &mut b] {
+            log.record(AuditOperation::EvaluateTransactionValidate { ok: true });
+            log.record(AuditOperation::CheckpointCreated { index: 1 });
+        }
+        b.entries[0].recorded_at = DateTime::<Utc>::UNIX_EPOCH;
+        assert_eq!(a.find_first_divergence(&b), None);
+        
+        b.record(AuditOperation::EvaluateRuleGuard { ok: false });
+        assert_eq!(a.find_first_divergence(&b), Some(2));
+        
+        a.record(AuditOperation::EvaluateRuleGuard { ok: true });
+        assert_eq!(a.find_first_divergence(&b), Some(2));
+        assert_eq!(a.entries()[2].sequence, 2);
+    }
+}
@@ -0,0 +1,168 @@
+//! Upgrading stored checkpoints to the current state schema
+
+use crate::error::StateError;
+use crate::hasher::StateHasher;
+use crate::state_manager::Checkpoint;
+use crate::traits::State;
+use crate::types::StateHash;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A serialized checkpoint whose state has not been decoded into a concrete type
+/// 
+/// Used to read checkpoints written under an older state schema, which may no
+/// longer deserialize into the current state type.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RawCheckpoint {
+    pub state: serde_json::Value,
+    pub hash: StateHash,
+    pub transaction_index: usize,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    /// Schema version of the state when the checkpoint was taken
+    #[serde(default = "default_schema_version")]
+    pub state_schema_version: u32,
+}
+
+impl RawCheckpoint {
+    /// Parse a checkpoint from its JSON encoding
+    pub fn from_json_bytes(bytes: &[u8]) -> Result<Self, StateError> {
+        serde_json::from_slice(bytes).map_err(|e| StateError::CheckpointError {
+            reason: format!("Failed to parse checkpoint: {}", e),
+        })
+    }
+}
+
+/// Checkpoints serialized before schema versioning existed are treated as version 1
+fn default_schema_version() -> u32 {
+    1
+}
+
+/// Outcome of migrating every checkpoint in a directory
+#[derive(Debug, Clone, Default)]
+pub struct MigrationReport {
+    /// Checkpoints rewritten under the current schema
+    pub migrated: Vec<PathBuf>,
+    /// Checkpoints already at the current schema, left untouched
+    pub up_to_date: Vec<PathBuf>,
+    pub failed: Vec<(PathBuf, StateError)>,
+}
+
+impl MigrationReport {
+    /// Check whether every checkpoint was migrated or already current
+    pub fn is_success(&self) -> bool {
+        self.failed.is_empty()
+    }
+}
+
+/// Function upgrading a state serialized under an older schema version
+type MigrationFn<S> = Box<dyn Fn(u32, serde_json::Value) -> Result<S, StateError> + Send + Sync>;
+
+/// Upgrades checkpoints taken under an older state schema to `S::SCHEMA_VERSION`
+pub struct CheckpointMigrator<S> {
+    migration_fn: MigrationFn<S>,
+}
+
+impl<S: State> CheckpointMigrator<S> {
+    /// Create a migrator from a function taking the old schema version and raw state JSON
+    pub fn new(
+        migration_fn: impl Fn(u32, serde_json::Value) -> Result<S, StateError> + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            migration_fn: Box::new(migration_fn),
+        }
+    }
+    
+    /// Create a migrator that uses `State::migrate`
+    pub fn from_state_migrations() -> Self
+    where
+        S: 'static,
+    {
+        Self::new(S::migrate)
+    }
+    
+    /// Upgrade a raw checkpoint to the current schema
+    /// 
+    /// Checkpoints already at the current schema are decoded directly. The
+    /// migrated state is validated and rehashed; the transaction index and
+    /// timestamp are kept.
+    pub fn migrate(&self, old_checkpoint: &RawCheckpoint) -> Result<Checkpoint<S>, StateError> {
+        let state = if old_checkpoint.state_schema_version == S::SCHEMA_VERSION {
+            serde_json::from_value(old_checkpoint.state.clone()).map_err(|e| StateError::CheckpointError {
+                reason: format!("Failed to decode checkpoint state: {}", e),
+            })?
+        } else {
+            (self.migration_fn)(old_checkpoint.state_schema_version, old_checkpoint.state.clone())?
+        };
+        
+        state.validate().map_err(|e| StateError::CheckpointError {
+            reason: format!("Migrated checkpoint state validation failed: {}", e),
+        })?;
+        
+        Ok(Checkpoint {
+            hash: StateHasher::new().hash(&state),
+            state,
+            transaction_index: old_checkpoint.transaction_index,
+            timestamp: old_checkpoint.timestamp,
+            state_schema_version: S::SCHEMA_VERSION,
+        })
+    }
+    
+    /// Migrate every `.json` checkpoint file in a directory, in file name order
+    /// 
+    /// Each file under an older schema is copied to `<file>.backup` before being
+    /// overwritten with the migrated checkpoint; backups are kept afterwards.
+    /// A failure for one file is recorded in the report and leaves that file
+    /// unchanged. Fails only if the directory itself cannot be read.
+    pub fn migrate_directory(&self, directory: &Path) -> Result<MigrationReport, StateError> {
+        let entries = fs::read_dir(directory).map_err(|e| StateError::CheckpointError {
+            reason: format!("Failed to read checkpoint directory {}: {}", directory.display(), e),
+        })?;
+        let mut paths: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "json"))
+            .collect();
+        paths.sort();
+        
+        let mut report = MigrationReport::default();
+        for path in paths {
+            match self.migrate_file(&path) {
+                Ok(true) => report.migrated.push(path),
+                Ok(false) => report.up_to_date.push(path),
+                Err(error) => report.failed.push((path, error)),
+            }
+        }
+        Ok(report)
+    }
+    
+    /// Migrate one checkpoint file, returning whether it was rewritten
+    fn migrate_file(&self, path: &Path) -> Result<bool, StateError> {
+        let io_error = |action: &str, e: std::io::Error| StateError::CheckpointError {
+            reason: format!("Failed to {} {}: {}", action, path.display(), e),
+        };
+        
+        let bytes = fs::read(path).map_err(|e| io_error("read", e))?;
+        let raw = RawCheckpoint::from_json_bytes(&bytes)?;
+        if raw.state_schema_version == S::SCHEMA_VERSION {
+            return Ok(false);
+        }
+        
+        let checkpoint = self.migrate(&raw)?;
+        let migrated = serde_json::to_vec(&checkpoint).map_err(|e| StateError::CheckpointError {
+            reason: format!("Failed to serialize migrated checkpoint: {}", e),
+        })?;
+        
+        let mut backup = path.as_os_str().to_owned();
+        backup.push(".backup");
+        fs::copy(path, &backup).map_err(|e| io_error("back up", e))?;
+        fs::write(path, migrated).map_err(|e| io_error("write", e))?;
+        Ok(true)
+    }
+}
+
+impl<S> fmt::Debug for CheckpointMigrator<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CheckpointMigrator").finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,146 @@
+//! Compaction of transaction logs by dropping transactions that change nothing
+
+use crate::context::ExecutionContext;
+use crate::error::ProcessingError;
+use crate::traits::{RuleSet, State, Transaction};
+use crate::transaction_processor::TransactionProcessor;
+use crate::types::StateHash;
+
+/// A transaction log with its no-op transactions removed
+#[derive(Debug, Clone)]
+pub struct CompactedLog<T> {
+    /// The remaining transactions, in their original order
+    pub transactions: Vec<T>,
+    /// Number of transactions that were removed
+    pub removed_count: usize,
+    /// Final state hash of both the original and the compacted log
+    pub final_hash: StateHash,
+}
+
+/// Operations on whole transaction logs
+pub struct TransactionLog;
+
+impl TransactionLog {
+    /// Remove the transactions that leave the state hash unchanged
+    /// 
+    /// Replays `transactions` from `initial_state` and drops every transaction
+    /// whose resulting state hash equals the hash before it, such as
+    /// heartbeats or repeated status updates. As a safety check the compacted
+    /// log is replayed again from the same initial state; if it does not reach
+    /// the original final hash, for example because the rule set depends on
+    /// how many transactions it has seen, compaction fails with
+    /// `ProcessingError::CompactionMismatch`.
+    /// 
+    /// # Errors
+    /// Returns the processing error of the first transaction that fails to apply.
+    pub fn compact<S, T, R>(
+        transactions: &[T],
+        rule_set: &R,
+        context: &ExecutionContext,
+        initial_state: &S,
+    ) -> Result<CompactedLog<T>, ProcessingError>
+    where
+        S: State,
+        T: Transaction,
+        R: RuleSet<S, T>,
+    {
+        // The check starts from the same time as the first replay
+        let verifier_context = context.with_detached_clock();
+        let mut processor = TransactionProcessor::new(initial_state.clone())?;
+        let mut kept = Vec::with_capacity(transactions.len());
+        for transaction in transactions {
+            let transition = processor.process_transaction(transaction, rule_set, context)?;
+            if transition.from_hash != transition.to_hash {
+                kept.push(transaction.clone());
+            }
+        }
+        let original_hash = processor.current_hash();
+        
+        let mut verifier = TransactionProcessor::new(initial_state.clone())?;
+        verifier.process_transactions(&kept, rule_set, &verifier_context)?;
+        let compacted_hash = verifier.current_hash();
+        if compacted_hash != original_hash {
+            return Err(ProcessingError::CompactionMismatch { original_hash, compacted_hash });
+        }
+        
+        Ok(CompactedLog {
+            removed_count: transactions.len() - kept.len(),
+            transactions: kept,
+            final_hash: original_hash,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ValidationError;
+    use crate::types::Version;
+    use chrono::{DateTime, Utc};
+    use serde::{Deserialize, Serialize};
+    use std::sync::atomic::{AtomicI64, Ordering};
+    
+    #[derive(Debug, Clone, Hash, Serialize, Deserialize)]
+    struct Counter {
+        value: i64,
+    }
+    
+    impl State for Counter {
+        fn validate(&self) -> Result<(), ValidationError> {
+            Ok(())
+        }
+    }
+    
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    struct Add {
+        id: String,
+        amount: i64,
+    }
+    
+    impl Transaction for Add {
+        fn id(&self) -> &str {
+            &self.id
+        }
+        
+        fn timestamp(&self) -> DateTime<Utc> {
+            DateTime::<Utc>::UNIX_EPOCH
+        }
+        
+        fn validate(&self) -> Result<(), ValidationError> {
+            Ok(())
+        }
+    }
+    
+    /// Adds the number of transactions it has seen, so removing any of them changes later results
+    struct CallCountingRules {
+        calls: AtomicI64,
+    }
+    
+    impl RuleSet<Counter, Add> for CallCountingRules {
+        fn version(&self) -> Version {
+            Version::new(1, 0, 0)
+        }
+        
+        fn apply(&self, state: &Counter, transaction: &Add, _context: &ExecutionContext) -> Result<Counter, ProcessingError> {
+            let calls = self.calls.fetch_add(1, Ordering::SeqCst);
+            if transaction.amount == 0 {
+                return Ok(state.clone());
+            }
+            Ok(Counter { value: state.value + transaction.amount + calls })
+        }
+    }
+    
+    #[test]
+    fn test_compaction_rejects_order_dependent_rules() {
+        let transactions = vec![
+            Add { id: "tx1".to_string(), amount: 1 },
+            Add { id: "noop".to_string(), amount: 0 },
+            Add { id: "tx2".to_string(), amount: 1 },
+        ];
+        let rules = CallCountingRules { calls: AtomicI64::new(0) };
+        let context = ExecutionContext::new(DateTime::<Utc>::UNIX_EPOCH, 0);
+        
+        let result = TransactionLog::compact(&transactions, &rules, &context, &Counter { value: 0 });
+        assert!(matches!(result, Err(ProcessingError::CompactionMismatch { .. })));
+    }
+}
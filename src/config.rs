@@ -0,0 +1,89 @@
+//! Declarative replay configuration that can be loaded from configuration files
+
+use std::collections::HashMap;
+use chrono::{DateTime, Utc};
+use serde::{Serialize, Deserialize};
+#[cfg(any(feature = "toml", feature = "yaml"))]
+use crate::error::SerializationError;
+use crate::logging::LogLevel;
+
+/// Replay configuration that can be kept outside of compiled code
+/// 
+/// TOML support is enabled by the default `toml` feature and YAML support by
+/// the optional `yaml` feature.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReplayConfig {
+    /// Interval at which checkpoints are created
+    #[serde(default)]
+    pub checkpoint_interval: Option<usize>,
+    /// Seed for the context random number generator
+    pub random_seed: u64,
+    /// Frozen time used by the execution context
+    pub replay_time: DateTime<Utc>,
+    /// Upper bound on the serialized size of the state
+    #[serde(default)]
+    pub max_state_size_bytes: Option<usize>,
+    /// Whether duplicate transactions should be deduplicated
+    #[serde(default)]
+    pub deduplication_enabled: bool,
+    /// Minimum level for deterministic logging
+    #[serde(default = "default_log_level")]
+    pub log_level: LogLevel,
+    /// External facts made available through the execution context
+    #[serde(default)]
+    pub external_facts: HashMap<String, serde_json::Value>,
+    /// Whether to resume from every checkpoint after a replay and compare final hashes
+    #[serde(default)]
+    pub dry_run_checkpoints: bool,
+}
+
+fn default_log_level() -> LogLevel {
+    LogLevel::Info
+}
+
+impl ReplayConfig {
+    /// Create a configuration with the required fields and defaults for the rest
+    pub fn new(replay_time: DateTime<Utc>, random_seed: u64) -> Self {
+        Self {
+            checkpoint_interval: None,
+            random_seed,
+            replay_time,
+            max_state_size_bytes: None,
+            deduplication_enabled: false,
+            log_level: default_log_level(),
+            external_facts: HashMap::new(),
+            dry_run_checkpoints: false,
+        }
+    }
+    
+    /// Parse a configuration from a TOML string
+    #[cfg(feature = "toml")]
+    pub fn from_toml(s: &str) -> Result<ReplayConfig, SerializationError> {
+        toml::from_str(s).map_err(|e| SerializationError::DeserializationFailed {
+            reason: format!("TOML deserialization failed: {}", e),
+        })
+    }
+    
+    /// Render the configuration as a TOML string
+    /// 
+    /// # Panics
+    /// Panics if an external fact contains a JSON `null`, which TOML cannot represent
+    #[cfg(feature = "toml")]
+    pub fn to_toml(&self) -> String {
+        toml::to_string(self).expect("ReplayConfig should serialize to TOML")
+    }
+    
+    /// Parse a configuration from a YAML string
+    #[cfg(feature = "yaml")]
+    pub fn from_yaml(s: &str) -> Result<ReplayConfig, SerializationError> {
+        serde_yaml::from_str(s).map_err(|e| SerializationError::DeserializationFailed {
+            reason: format!("YAML deserialization failed: {}", e),
+        })
+    }
+    
+    /// Render the configuration as a YAML string
+    #[cfg(feature = "yaml")]
+    pub fn to_yaml(&self) -> String {
+        serde_yaml::to_string(self).expect("ReplayConfig should serialize to YAML")
+    }
+}
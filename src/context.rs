@@ -1,17 +1,32 @@
 //! Execution context providing controlled access to external dependencies
 
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, Utc};
 use rand::{Rng, SeedableRng};
 use rand_chacha::ChaCha8Rng;
-use serde::{Serialize, Deserialize};
-use std::collections::HashMap;
-use std::any::Any;
-use crate::error::ProcessingError;
+use serde::{Serialize, Deserialize, de::DeserializeOwned};
+use std::collections::{hash_map::Entry, BTreeMap, HashMap, VecDeque};
+use std::any::{Any, TypeId};
+use crate::error::{ProcessingError, SerializationError, ValidationError};
+use crate::types::{CausalityRecord, ExecutionTrace};
+use std::sync::{Arc, Mutex};
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+#[cfg(target_arch = "wasm32")]
+use web_time::Instant;
 
 /// Deterministic time provider with frozen time values
+/// 
+/// For testing time-dependent rules the time can instead come from a shared
+/// `ManualClock`, optionally advanced by a fixed tick once per transaction.
+/// The clock is not serialized; a deserialized value is frozen at the time
+/// the clock showed.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DeterministicTime {
     current_time: DateTime<Utc>,
+    #[serde(skip)]
+    clock: Option<ManualClock>,
+    #[serde(skip)]
+    tick_per_transaction: Option<Duration>,
 }
 
 impl DeterministicTime {
@@ -19,22 +34,105 @@ impl DeterministicTime {
     pub fn new(time: DateTime<Utc>) -> Self {
         Self {
             current_time: time,
+            clock: None,
+            tick_per_transaction: None,
         }
     }
     
-    /// Get the current frozen time
+    /// Get the current time: the frozen value, or the clock's time if one is attached
     pub fn current(&self) -> DateTime<Utc> {
-        self.current_time
+        match &self.clock {
+            Some(clock) => clock.now(),
+            None => self.current_time,
+        }
     }
     
     /// Create a new DeterministicTime with an updated time value
+    /// 
+    /// The result is frozen at `time`, detached from any clock.
     pub fn with_time(&self, time: DateTime<Utc>) -> Self {
+        Self::new(time)
+    }
+    
+    /// Create a time that advances by `tick_per_transaction` each time `advance` is called
+    /// 
+    /// `TransactionProcessor` calls `advance` once after each transaction, so
+    /// the first transaction sees the starting time and each later one a tick
+    /// more. Reading the time with `current` never advances it, so rules may
+    /// read it freely. The clock is shared with `manual_clock` and every copy
+    /// of the value, including cloned contexts, but not with `detached` copies.
+    pub fn with_tick(&self, tick_per_transaction: Duration) -> Self {
         Self {
-            current_time: time,
+            current_time: self.current_time,
+            clock: Some(self.manual_clock()),
+            tick_per_transaction: Some(tick_per_transaction),
+        }
+    }
+    
+    /// Get a handle to the clock driving this time
+    /// 
+    /// A frozen time has no clock yet, so a new one starting at `current` is
+    /// returned; attach it with `ExecutionContextBuilder::with_manual_clock`.
+    pub fn manual_clock(&self) -> ManualClock {
+        self.clock.clone().unwrap_or_else(|| ManualClock::new(self.current()))
+    }
+    
+    /// Copy the time with a clock of its own, starting at the time the shared clock shows
+    /// 
+    /// Frozen time is copied as it is.
+    pub fn detached(&self) -> Self {
+        Self {
+            current_time: self.current(),
+            clock: self.clock.as_ref().map(|clock| ManualClock::new(clock.now())),
+            tick_per_transaction: self.tick_per_transaction,
+        }
+    }
+    
+    /// Advance the clock by the configured tick, if there is one
+    pub fn advance(&self) {
+        if let (Some(clock), Some(tick)) = (&self.clock, self.tick_per_transaction) {
+            clock.tick(tick);
         }
     }
 }
 
+/// Shared, manually controlled clock for tests of time-dependent rules
+/// 
+/// Clones share the same time, so a test can keep one handle and move the
+/// clock while a context built with the other handle is in use.
+#[derive(Debug, Clone)]
+pub struct ManualClock {
+    time: Arc<Mutex<DateTime<Utc>>>,
+}
+
+impl ManualClock {
+    /// Create a clock showing `time`
+    pub fn new(time: DateTime<Utc>) -> Self {
+        Self {
+            time: Arc::new(Mutex::new(time)),
+        }
+    }
+    
+    /// Get the time the clock shows
+    pub fn now(&self) -> DateTime<Utc> {
+        *self.lock()
+    }
+    
+    /// Move the clock forward by `duration`
+    pub fn tick(&self, duration: Duration) {
+        *self.lock() += duration;
+    }
+    
+    /// Set the clock to `time`
+    pub fn set(&self, time: DateTime<Utc>) {
+        *self.lock() = time;
+    }
+    
+    fn lock(&self) -> std::sync::MutexGuard<'_, DateTime<Utc>> {
+        self.time.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
 /// Seeded random number generator for reproducible randomness
 #[derive(Debug)]
 pub struct SeededRandom {
@@ -91,6 +189,7 @@ impl Clone for SeededRandom {
 /// Container for immutable external data
 pub struct ExternalFacts {
     facts: HashMap<String, FactWrapper>,
+    codecs: HashMap<TypeId, FactCodec>,
 }
 
 struct FactWrapper {
@@ -98,6 +197,43 @@ struct FactWrapper {
     type_id: std::any::TypeId,
 }
 
+/// JSON conversion functions for a fact type registered with `ExternalFacts::register_type`
+#[derive(Clone)]
+struct FactCodec {
+    type_name: String,
+    to_json: fn(&dyn Any) -> Option<Result<serde_json::Value, serde_json::Error>>,
+    from_json: fn(serde_json::Value) -> Result<FactWrapper, serde_json::Error>,
+}
+
+fn fact_to_json<T: Serialize + 'static>(value: &dyn Any) -> Option<Result<serde_json::Value, serde_json::Error>> {
+    value.downcast_ref::<T>().map(serde_json::to_value)
+}
+
+fn fact_from_json<T: ExternalFact + DeserializeOwned>(value: serde_json::Value) -> Result<FactWrapper, serde_json::Error> {
+    let fact: T = serde_json::from_value(value)?;
+    Ok(FactWrapper {
+        value: Box::new(fact),
+        type_id: TypeId::of::<T>(),
+    })
+}
+
+/// A single fact in an `ExternalFactsSnapshot`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FactSnapshotEntry {
+    /// Name the fact's type was registered under
+    pub type_name: String,
+    pub value: serde_json::Value,
+}
+
+/// Serializable record of the external facts used during a replay
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExternalFactsSnapshot {
+    /// Facts keyed by fact key, each tagged with its registered type name
+    pub facts: BTreeMap<String, FactSnapshotEntry>,
+    /// Keys of facts that could not be captured because their type was not registered
+    pub skipped: Vec<String>,
+}
+
 impl Clone for FactWrapper {
     fn clone(&self) -> Self {
         Self {
@@ -115,6 +251,7 @@ impl Clone for ExternalFacts {
         }
         Self {
             facts: new_facts,
+            codecs: self.codecs.clone(),
         }
     }
 }
@@ -132,7 +269,123 @@ impl ExternalFacts {
     pub fn new() -> Self {
         Self {
             facts: HashMap::new(),
+            codecs: HashMap::new(),
+        }
+    }
+    
+    /// Register a fact type so facts of that type can be persisted and restored
+    /// 
+    /// `type_name` is written into snapshots and must be registered under the
+    /// same name wherever the snapshot is loaded. Each name identifies one
+    /// type, so registering a name already taken by another type fails.
+    /// Registering a type again replaces its previous name.
+    pub fn register_type<T: ExternalFact + Serialize + DeserializeOwned>(&mut self, type_name: &str) -> Result<(), ValidationError> {
+        let type_id = TypeId::of::<T>();
+        if self.codec_for_name(type_name).is_some_and(|(registered_id, _)| registered_id != type_id) {
+            return Err(ValidationError::DuplicateFactType { type_name: type_name.to_string() });
+        }
+        
+        self.codecs.insert(type_id, FactCodec {
+            type_name: type_name.to_string(),
+            to_json: fact_to_json::<T>,
+            from_json: fact_from_json::<T>,
+        });
+        Ok(())
+    }
+    
+    /// Find the codec registered under a type name
+    fn codec_for_name(&self, type_name: &str) -> Option<(TypeId, &FactCodec)> {
+        self.codecs
+            .iter()
+            .find(|(_, codec)| codec.type_name == type_name)
+            .map(|(type_id, codec)| (*type_id, codec))
+    }
+    
+    /// Capture all facts whose type has been registered
+    /// 
+    /// Facts of unregistered types are listed in `skipped` rather than failing
+    /// the snapshot.
+    pub fn snapshot(&self) -> Result<ExternalFactsSnapshot, SerializationError> {
+        let mut snapshot = ExternalFactsSnapshot {
+            facts: BTreeMap::new(),
+            skipped: Vec::new(),
+        };
+        
+        for (key, wrapper) in &self.facts {
+            let codec = match self.codecs.get(&wrapper.type_id) {
+                Some(codec) => codec,
+                None => {
+                    snapshot.skipped.push(key.clone());
+                    continue;
+                }
+            };
+            
+            let any_ref: &dyn Any = &*wrapper.value;
+            let value = (codec.to_json)(any_ref)
+                .expect("codec is registered under the fact's type id")
+                .map_err(|e| SerializationError::SerializationFailed {
+                    reason: format!("External fact '{}' could not be serialized: {}", key, e),
+                })?;
+            snapshot.facts.insert(key.clone(), FactSnapshotEntry {
+                type_name: codec.type_name.clone(),
+                value,
+            });
+        }
+        
+        snapshot.skipped.sort();
+        Ok(snapshot)
+    }
+    
+    /// Serialize all facts to JSON
+    /// 
+    /// Fails if any fact's type is not registered; use `snapshot` to capture
+    /// the registered facts and list the rest.
+    pub fn to_json(&self) -> Result<serde_json::Value, SerializationError> {
+        let snapshot = self.snapshot()?;
+        if let Some(key) = snapshot.skipped.first() {
+            return Err(SerializationError::SerializationFailed {
+                reason: format!("External fact '{}' has no registered type", key),
+            });
+        }
+        serde_json::to_value(snapshot.facts).map_err(|e| SerializationError::SerializationFailed {
+            reason: format!("External facts could not be serialized: {}", e),
+        })
+    }
+    
+    /// Reconstruct facts from JSON produced by `to_json`, using the types registered on `self`
+    /// 
+    /// The returned container keeps the same type registrations.
+    pub fn from_json(&self, value: serde_json::Value) -> Result<ExternalFacts, SerializationError> {
+        let facts: BTreeMap<String, FactSnapshotEntry> = serde_json::from_value(value)
+            .map_err(|e| SerializationError::DeserializationFailed {
+                reason: format!("Invalid external facts document: {}", e),
+            })?;
+        
+        self.from_snapshot(ExternalFactsSnapshot {
+            facts,
+            skipped: Vec::new(),
+        })
+    }
+    
+    /// Reconstruct facts from a snapshot, using the types registered on `self`
+    pub fn from_snapshot(&self, snapshot: ExternalFactsSnapshot) -> Result<ExternalFacts, SerializationError> {
+        let mut restored = ExternalFacts {
+            facts: HashMap::new(),
+            codecs: self.codecs.clone(),
+        };
+        
+        for (key, entry) in snapshot.facts {
+            let (_, codec) = self.codec_for_name(&entry.type_name)
+                .ok_or_else(|| SerializationError::DeserializationFailed {
+                    reason: format!("External fact '{}' has unregistered type '{}'", key, entry.type_name),
+                })?;
+            let wrapper = (codec.from_json)(entry.value).map_err(|e| SerializationError::DeserializationFailed {
+                reason: format!("External fact '{}' could not be deserialized: {}", key, e),
+            })?;
+            restored.facts.insert(key, wrapper);
         }
+        
+        Ok(restored)
     }
     
     /// Add an external fact with a key
@@ -210,6 +463,13 @@ impl ExternalEntityResolver {
         }
     }
     
+    /// Create an empty entity resolver with room for `capacity` entities
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            entities: HashMap::with_capacity(capacity),
+        }
+    }
+    
     /// Register an external entity with a unique identifier
     pub fn register<T: ExternalEntity>(&mut self, entity_id: String, entity: T) {
         let type_id = std::any::TypeId::of::<T>();
@@ -244,6 +504,44 @@ impl ExternalEntityResolver {
             })
     }
     
+    /// Register several external entities at once
+    pub fn batch_register<T: ExternalEntity>(&mut self, entities: impl IntoIterator<Item = (String, T)>) {
+        let entities = entities.into_iter();
+        self.entities.reserve(entities.size_hint().0);
+        for (entity_id, entity) in entities {
+            self.register(entity_id, entity);
+        }
+    }
+    
+    /// Resolve several external entities of the same type in one call
+    /// 
+    /// IDs that are not registered, or are registered with a different type,
+    /// are listed in `not_found` in the order they were requested.
+    pub fn batch_resolve<'a, T: ExternalEntity>(&'a self, entity_ids: &[&'a str]) -> BatchResolveResult<'a, T> {
+        let mut result = BatchResolveResult {
+            resolved: HashMap::with_capacity(entity_ids.len()),
+            not_found: Vec::new(),
+        };
+        for &entity_id in entity_ids {
+            match self.resolve::<T>(entity_id) {
+                Ok(entity) => {
+                    result.resolved.insert(entity_id, entity);
+                }
+                Err(_) => result.not_found.push(entity_id),
+            }
+        }
+        result
+    }
+    
+    /// Check if every entity is registered with type `T`
+    pub fn contains_all<T: ExternalEntity>(&self, entity_ids: &[&str]) -> bool {
+        let requested_type_id = std::any::TypeId::of::<T>();
+        entity_ids.iter().all(|entity_id| {
+            self.entities.get(*entity_id)
+                .is_some_and(|wrapper| wrapper.type_id == requested_type_id)
+        })
+    }
+    
     /// Check if an entity is registered
     pub fn contains(&self, entity_id: &str) -> bool {
         self.entities.contains_key(entity_id)
@@ -266,6 +564,22 @@ impl Default for ExternalEntityResolver {
     }
 }
 
+/// Outcome of `ExternalEntityResolver::batch_resolve`
+#[derive(Debug)]
+pub struct BatchResolveResult<'a, T> {
+    /// Entities found with the requested type, keyed by ID
+    pub resolved: HashMap<&'a str, &'a T>,
+    /// Requested IDs with no entity of the requested type
+    pub not_found: Vec<&'a str>,
+}
+
+impl<T> BatchResolveResult<'_, T> {
+    /// Check if every requested entity was resolved
+    pub fn is_complete(&self) -> bool {
+        self.not_found.is_empty()
+    }
+}
+
 /// Trait for external entities that can be resolved
 pub trait ExternalEntity: Any + Send + Sync {
     fn clone_box(&self) -> Box<dyn ExternalEntity>;
@@ -282,7 +596,7 @@ where
 }
 
 /// Ordering rules for deterministic collection iteration
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OrderingRules {
     /// Enforce stable ordering for all collections
     enforce_stable_ordering: bool,
@@ -291,6 +605,12 @@ pub struct OrderingRules {
 }
 
 impl OrderingRules {
+    /// Entity type under which the processing order of transactions is recorded
+    /// 
+    /// When present, `ReplayEngine` rejects replays whose transactions appear
+    /// in a different relative order; see `validate_relative_ordering`.
+    pub const TRANSACTIONS: &'static str = "dtre.transactions";
+    
     /// Create new ordering rules with stable ordering enforced
     pub fn new() -> Self {
         Self {
@@ -322,6 +642,16 @@ impl OrderingRules {
         self.enforce_stable_ordering
     }
     
+    /// Get the number of custom orderings defined
+    pub fn len(&self) -> usize {
+        self.custom_orderings.len()
+    }
+    
+    /// Check if no custom orderings are defined
+    pub fn is_empty(&self) -> bool {
+        self.custom_orderings.is_empty()
+    }
+    
     /// Validate that a collection follows the required ordering
     pub fn validate_ordering<T>(&self, entity_type: &str, items: &[T], get_id: impl Fn(&T) -> &str) -> Result<(), ProcessingError> {
         if !self.enforce_stable_ordering {
@@ -344,6 +674,102 @@ impl OrderingRules {
         Ok(())
     }
     
+    /// Validate that the items listed in an ordering appear in that relative order
+    /// 
+    /// Unlike `validate_ordering`, items missing from the collection or from
+    /// the ordering are allowed, so a subset of the original sequence passes.
+    pub fn validate_relative_ordering<T>(&self, entity_type: &str, items: &[T], get_id: impl Fn(&T) -> &str) -> Result<(), ProcessingError> {
+        if !self.enforce_stable_ordering {
+            return Ok(());
+        }
+        
+        if let Some(expected_order) = self.custom_orderings.get(entity_type) {
+            let positions = position_map(expected_order);
+            let mut previous = None;
+            for item in items {
+                if let Some(&position) = positions.get(get_id(item)) {
+                    if previous.is_some_and(|previous| position < previous) {
+                        return Err(ProcessingError::OrderingViolation {
+                            entity_type: entity_type.to_string(),
+                            expected_order: expected_order.clone(),
+                            actual_order: items.iter().map(|item| get_id(item).to_string()).collect(),
+                        });
+                    }
+                    previous = Some(position);
+                }
+            }
+        }
+        
+        Ok(())
+    }
+    
+    /// Learn the transaction processing order from a completed replay
+    /// 
+    /// The order of the successfully applied transactions is recorded under
+    /// `OrderingRules::TRANSACTIONS`, keeping the first occurrence of a
+    /// repeated ID. Stable ordering is enforced.
+    pub fn from_execution_trace(trace: &ExecutionTrace) -> Self {
+        let mut rules = Self::new();
+        rules.add_ordering(Self::TRANSACTIONS.to_string(), transaction_order(trace));
+        rules
+    }
+    
+    /// Learn one transaction processing order from several completed replays
+    /// 
+    /// Transactions seen by only some traces are placed after the transaction
+    /// that preceded them in their trace.
+    /// 
+    /// # Errors
+    /// Fails if two traces process the same pair of transactions in opposite orders.
+    pub fn merge_from_traces(traces: &[&ExecutionTrace]) -> Result<Self, ValidationError> {
+        let mut merged: Vec<String> = Vec::new();
+        for trace in traces {
+            let order = transaction_order(trace);
+            let positions = position_map(&merged);
+            let mut previous: Option<(usize, &str)> = None;
+            for id in &order {
+                if let Some(&position) = positions.get(id.as_str()) {
+                    if let Some((previous_position, previous_id)) = previous {
+                        if position < previous_position {
+                            return Err(ValidationError::RuleViolated {
+                                rule: format!("traces disagree on the order of transactions {} and {}", previous_id, id),
+                            });
+                        }
+                    }
+                    previous = Some((position, id));
+                }
+            }
+            
+            // Insert transactions not seen before right after their predecessor in this trace
+            let mut insert_at = 0;
+            for id in order {
+                match merged.iter().position(|merged_id| *merged_id == id) {
+                    Some(position) => insert_at = position + 1,
+                    None => {
+                        merged.insert(insert_at, id);
+                        insert_at += 1;
+                    }
+                }
+            }
+        }
+        
+        let mut rules = Self::new();
+        rules.add_ordering(Self::TRANSACTIONS.to_string(), merged);
+        Ok(rules)
+    }
+    
+    /// Serialize the ordering rules, for example to learn them once and reuse them later
+    pub fn save_to_json(&self) -> serde_json::Value {
+        serde_json::to_value(self).unwrap_or(serde_json::Value::Null)
+    }
+    
+    /// Deserialize ordering rules produced by `save_to_json`
+    pub fn load_from_json(value: serde_json::Value) -> Result<Self, SerializationError> {
+        serde_json::from_value(value).map_err(|e| SerializationError::DeserializationFailed {
+            reason: format!("Failed to deserialize ordering rules: {}", e),
+        })
+    }
+    
     /// Sort a collection according to the defined ordering rules
     pub fn sort_by_ordering<T>(&self, entity_type: &str, items: &mut [T], get_id: impl Fn(&T) -> &str) {
         if let Some(expected_order) = self.custom_orderings.get(entity_type) {
@@ -365,6 +791,25 @@ impl OrderingRules {
     }
 }
 
+/// Map each ID to its position, keeping the first position of a repeated ID
+fn position_map(ordered_ids: &[String]) -> HashMap<&str, usize> {
+    let mut positions = HashMap::with_capacity(ordered_ids.len());
+    for (position, id) in ordered_ids.iter().enumerate() {
+        positions.entry(id.as_str()).or_insert(position);
+    }
+    positions
+}
+
+/// IDs of the transactions a trace applied, in order and without repeats
+fn transaction_order(trace: &ExecutionTrace) -> Vec<String> {
+    let mut seen = std::collections::HashSet::new();
+    trace.rule_applications
+        .iter()
+        .filter(|application| seen.insert(application.transaction_id.as_str()))
+        .map(|application| application.transaction_id.clone())
+        .collect()
+}
+
 impl Default for OrderingRules {
     fn default() -> Self {
         Self::new()
@@ -392,14 +837,53 @@ where
     }
 }
 
+/// Processing phase a transaction is currently in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ExecutionPhase {
+    /// Business precondition checks (`RuleSet::pre_validate`)
+    #[default]
+    PreProcessing,
+    /// Rule application (`RuleSet::apply`)
+    MainProcessing,
+    /// Work after a transaction has been applied, such as audit recording
+    PostProcessing,
+}
+
+impl ExecutionPhase {
+    /// Get the phase that follows this one; `PostProcessing` is final
+    pub fn next(self) -> Self {
+        match self {
+            ExecutionPhase::PreProcessing => ExecutionPhase::MainProcessing,
+            ExecutionPhase::MainProcessing => ExecutionPhase::PostProcessing,
+            ExecutionPhase::PostProcessing => ExecutionPhase::PostProcessing,
+        }
+    }
+}
+
+/// Whether `ExecutionContext::debug_println` captures messages
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DebugMode {
+    /// Debug output is discarded
+    #[default]
+    Disabled,
+    /// Debug output is captured in the context's debug log
+    Enabled,
+}
+
 /// Execution context providing controlled access to external dependencies
+#[cfg_attr(feature = "wasm", wasm_bindgen::prelude::wasm_bindgen)]
 #[derive(Debug, Clone)]
 pub struct ExecutionContext {
     deterministic_time: DeterministicTime,
     seeded_random: SeededRandom,
-    external_facts: ExternalFacts,
-    entity_resolver: ExternalEntityResolver,
-    ordering_rules: OrderingRules,
+    // Shared by every copy of the context, so phase changes do not copy facts
+    external_facts: Arc<ExternalFacts>,
+    entity_resolver: Arc<ExternalEntityResolver>,
+    ordering_rules: Arc<OrderingRules>,
+    phase: ExecutionPhase,
+    causality: Option<Arc<Mutex<CausalityRecord>>>,
+    /// Captured debug output; `None` in `DebugMode::Disabled`
+    debug_log: Option<Arc<Mutex<Vec<String>>>>,
 }
 
 impl ExecutionContext {
@@ -408,9 +892,12 @@ impl ExecutionContext {
         Self {
             deterministic_time: DeterministicTime::new(time),
             seeded_random: SeededRandom::new(random_seed),
-            external_facts: ExternalFacts::new(),
-            entity_resolver: ExternalEntityResolver::new(),
-            ordering_rules: OrderingRules::new(),
+            external_facts: Arc::new(ExternalFacts::new()),
+            entity_resolver: Arc::new(ExternalEntityResolver::new()),
+            ordering_rules: Arc::new(OrderingRules::new()),
+            phase: ExecutionPhase::default(),
+            causality: None,
+            debug_log: None,
         }
     }
     
@@ -424,13 +911,65 @@ impl ExecutionContext {
         self.deterministic_time.current()
     }
     
+    /// Advance a ticking clock by its tick; a no-op for frozen time
+    /// 
+    /// Called by `TransactionProcessor` once per transaction; see
+    /// `DeterministicTime::with_tick`.
+    pub fn tick_clock(&self) {
+        self.deterministic_time.advance();
+    }
+    
+    /// Copy the context with a clock that no other copy shares
+    /// 
+    /// `ReplayEngine` replays with such a copy, so a ticking clock starts each
+    /// replay at the time the shared clock shows and replaying twice gives the
+    /// same result. See `DeterministicTime::detached`.
+    pub fn with_detached_clock(&self) -> Self {
+        Self {
+            deterministic_time: self.deterministic_time.detached(),
+            ..self.clone()
+        }
+    }
+    
     /// Get mutable access to the random number generator
     pub fn random(&mut self) -> &mut SeededRandom {
+        self.record_causality(CausalityRecord::record_random_draw);
         &mut self.seeded_random
     }
     
+    /// Get the seed the random number generator was created with
+    pub fn random_seed(&self) -> u64 {
+        self.seeded_random.seed()
+    }
+    
+    /// Generate a UUID from the seeded random number generator
+    /// 
+    /// The result is a well-formed version 4 UUID whose bytes come from the
+    /// context's `SeededRandom`, so the same seed and the same sequence of
+    /// calls always produce the same UUIDs. Rules must use this instead of
+    /// `uuid::Uuid::new_v4()`, which draws from the operating system.
+    #[cfg(feature = "uuid")]
+    pub fn generate_uuid(&mut self) -> uuid::Uuid {
+        let mut bytes = [0u8; 16];
+        bytes[..8].copy_from_slice(&self.seeded_random.next_u64().to_le_bytes());
+        bytes[8..].copy_from_slice(&self.seeded_random.next_u64().to_le_bytes());
+        uuid::Builder::from_random_bytes(bytes).into_uuid()
+    }
+    
+    /// Generate a version 5 UUID for `name` within a named namespace
+    /// 
+    /// The namespace UUID is itself derived from `namespace` under the OID
+    /// namespace, so the result depends only on the two strings and does not
+    /// advance the random number generator.
+    #[cfg(feature = "uuid")]
+    pub fn generate_uuid_namespaced(&self, namespace: &str, name: &str) -> uuid::Uuid {
+        let namespace = uuid::Uuid::new_v5(&uuid::Uuid::NAMESPACE_OID, namespace.as_bytes());
+        uuid::Uuid::new_v5(&namespace, name.as_bytes())
+    }
+    
     /// Get an external fact by key
     pub fn get_external_fact<T: ExternalFact>(&self, key: &str) -> Option<&T> {
+        self.record_causality(|record| record.record_fact(key));
         self.external_facts.get(key)
     }
     
@@ -441,6 +980,7 @@ impl ExecutionContext {
     
     /// Resolve an external entity by its identifier
     pub fn resolve_entity<T: ExternalEntity>(&self, entity_id: &str) -> Result<&T, ProcessingError> {
+        self.record_causality(|record| record.record_entity(entity_id));
         self.entity_resolver.resolve(entity_id)
     }
     
@@ -472,13 +1012,321 @@ impl ExecutionContext {
             external_facts: self.external_facts.clone(),
             entity_resolver: self.entity_resolver.clone(),
             ordering_rules: self.ordering_rules.clone(),
+            phase: self.phase,
+            causality: self.causality.clone(),
+            debug_log: self.debug_log.clone(),
         }
     }
+    
+    /// Create a child context whose random seed is this context's seed plus `advance_by`
+    /// 
+    /// The child shares time, facts, entities and ordering rules with this
+    /// context but starts a fresh random stream. The parent's random number
+    /// generator is not advanced.
+    pub fn clone_with_advanced_seed(&self, advance_by: u64) -> Self {
+        self.clone_with_seed(self.random_seed().wrapping_add(advance_by))
+    }
+    
+    /// Create a child context whose random seed is derived from this context's seed and a namespace
+    /// 
+    /// The child seed is taken from `blake3(parent_seed || namespace)`, so the
+    /// same namespace always yields the same child seed while different
+    /// namespaces yield independent streams. The parent's random number
+    /// generator is not advanced.
+    pub fn clone_with_namespaced_seed(&self, namespace: &str) -> Self {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(&self.random_seed().to_le_bytes());
+        hasher.update(namespace.as_bytes());
+        
+        let mut seed_bytes = [0u8; 8];
+        seed_bytes.copy_from_slice(&hasher.finalize().as_bytes()[..8]);
+        self.clone_with_seed(u64::from_le_bytes(seed_bytes))
+    }
+    
+    /// Create a copy of this context with a fresh random number generator
+    fn clone_with_seed(&self, seed: u64) -> Self {
+        let mut context = self.clone();
+        context.seeded_random = SeededRandom::new(seed);
+        context
+    }
+    
+    /// Create a copy of this context that records which inputs are accessed
+    /// 
+    /// Fact reads, entity resolutions and random draws made through this
+    /// context, or any context cloned from it, are collected into a fresh
+    /// record readable with `causality`. Reads through `external_facts()` or
+    /// `entity_resolver()` bypass recording.
+    pub fn with_causality_recording(&self) -> Self {
+        self.clone().into_causality_recording()
+    }
+    
+    /// Start recording accessed inputs into a fresh record, consuming this context
+    pub(crate) fn into_causality_recording(mut self) -> Self {
+        self.causality = Some(Arc::new(Mutex::new(CausalityRecord::default())));
+        self
+    }
+    
+    /// Get the inputs accessed so far, if recording is enabled
+    pub fn causality(&self) -> Option<CausalityRecord> {
+        self.causality.as_ref().map(|record| {
+            record.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).clone()
+        })
+    }
+    
+    /// Apply an update to the causality record, if recording is enabled
+    fn record_causality(&self, update: impl FnOnce(&mut CausalityRecord)) {
+        if let Some(record) = &self.causality {
+            update(&mut record.lock().unwrap_or_else(|poisoned| poisoned.into_inner()));
+        }
+    }
+    
+    /// Create a copy of this context that captures or discards `debug_println` output
+    /// 
+    /// Enabling starts a fresh log shared by this copy and every context
+    /// cloned from it, including the ones a replay hands to the rule set.
+    pub fn with_debug_mode(&self, mode: DebugMode) -> Self {
+        let mut context = self.clone();
+        context.debug_log = match mode {
+            DebugMode::Enabled => Some(Arc::new(Mutex::new(Vec::new()))),
+            DebugMode::Disabled => None,
+        };
+        context
+    }
+    
+    /// Get whether debug output is captured
+    pub fn debug_mode(&self) -> DebugMode {
+        match self.debug_log {
+            Some(_) => DebugMode::Enabled,
+            None => DebugMode::Disabled,
+        }
+    }
+    
+    /// Record a debug message from a rule set without touching stdout
+    /// 
+    /// Messages never affect the state or its hash. In `DebugMode::Disabled`
+    /// this is a single branch and nothing is stored; build messages with
+    /// `format!` only behind a `debug_mode` check to avoid paying for them.
+    ///
+    /// To flag leftover calls before shipping, add this to the rule crate's
+    /// `clippy.toml`:
+    ///
+    /// ```toml
+    /// disallowed-methods = [
+    ///     { path = "dtre::ExecutionContext::debug_println", reason = "remove debug output before production" },
+    /// ]
+    /// ```
+    #[inline]
+    pub fn debug_println(&self, message: &str) {
+        if let Some(log) = &self.debug_log {
+            log.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).push(message.to_string());
+        }
+    }
+    
+    /// Get the debug messages captured so far, in order
+    /// 
+    /// Always empty in `DebugMode::Disabled`. The log is shared with other
+    /// contexts, so a copy is returned.
+    pub fn debug_log(&self) -> Vec<String> {
+        self.debug_log.as_ref().map_or_else(Vec::new, |log| {
+            log.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).clone()
+        })
+    }
+    
+    /// Get the processing phase this context is in
+    pub fn current_phase(&self) -> ExecutionPhase {
+        self.phase
+    }
+    
+    /// Create a new context in the given phase
+    pub fn with_phase(&self, phase: ExecutionPhase) -> Self {
+        let mut context = self.clone();
+        context.phase = phase;
+        context
+    }
+    
+    /// Create a new context in the phase following the current one
+    pub fn advance_phase(&self) -> Self {
+        self.with_phase(self.phase.next())
+    }
+    
+    /// Compute a summary fingerprint that identifies this context configuration
+    /// 
+    /// The `context_hash` covers the time, seed, external fact keys, entity
+    /// identifiers and ordering rules. Fact and entity values are type-erased
+    /// and therefore only contribute through their keys.
+    pub fn fingerprint(&self) -> ContextFingerprint {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(self.now().to_rfc3339().as_bytes());
+        hasher.update(&self.random_seed().to_le_bytes());
+        
+        let mut fact_keys: Vec<&String> = self.external_facts.facts.keys().collect();
+        fact_keys.sort();
+        for key in fact_keys {
+            hasher.update(b"fact:");
+            hasher.update(key.as_bytes());
+        }
+        
+        let mut entity_ids: Vec<&String> = self.entity_resolver.entities.keys().collect();
+        entity_ids.sort();
+        for id in entity_ids {
+            hasher.update(b"entity:");
+            hasher.update(id.as_bytes());
+        }
+        
+        hasher.update(&[self.ordering_rules.enforce_stable_ordering as u8]);
+        let orderings: BTreeMap<&String, &Vec<String>> = self.ordering_rules.custom_orderings.iter().collect();
+        for (entity_type, ids) in orderings {
+            hasher.update(b"ordering:");
+            hasher.update(entity_type.as_bytes());
+            for id in ids {
+                hasher.update(b",");
+                hasher.update(id.as_bytes());
+            }
+        }
+        
+        let digest = hasher.finalize();
+        let mut hash_bytes = [0u8; 8];
+        hash_bytes.copy_from_slice(&digest.as_bytes()[..8]);
+        
+        ContextFingerprint {
+            time: self.now(),
+            random_seed: self.random_seed(),
+            external_fact_count: self.external_facts.len(),
+            entity_count: self.entity_resolver.len(),
+            ordering_rule_count: self.ordering_rules.len(),
+            context_hash: u64::from_le_bytes(hash_bytes),
+        }
+    }
+    
+    /// Export the configuration needed to reproduce this context
+    /// 
+    /// External facts and entities are type-erased and cannot be serialized,
+    /// so they must be registered again on the reconstructed context.
+    pub fn to_reproducibility_config(&self) -> ReproducibilityConfig {
+        ReproducibilityConfig {
+            time: self.now(),
+            random_seed: self.random_seed(),
+            enforce_stable_ordering: self.ordering_rules.enforce_stable_ordering,
+            custom_orderings: self.ordering_rules.custom_orderings
+                .iter()
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect(),
+        }
+    }
+    
+    /// Reconstruct a context from a reproducibility configuration
+    pub fn from_reproducibility_config(config: ReproducibilityConfig) -> ExecutionContext {
+        let mut context = ExecutionContext::new(config.time, config.random_seed);
+        context.ordering_rules = Arc::new(OrderingRules {
+            enforce_stable_ordering: config.enforce_stable_ordering,
+            custom_orderings: config.custom_orderings.into_iter().collect(),
+        });
+        context
+    }
+    
+    /// Combine a base context with an override context
+    /// 
+    /// The result keeps the time, random seed and phase of `override_ctx`.
+    /// External facts and entities of both contexts are kept, and keys present
+    /// in both are resolved according to `options`. Custom orderings are
+    /// combined, with `override_ctx` winning for an entity type defined in
+    /// both, and stable ordering stays enforced if either context enforces it.
+    pub fn merge(
+        base: ExecutionContext,
+        override_ctx: ExecutionContext,
+        options: MergeOptions,
+    ) -> Result<ExecutionContext, ValidationError> {
+        let mut merged = override_ctx;
+        let base_external_facts = Arc::unwrap_or_clone(base.external_facts);
+        let merged_facts = Arc::make_mut(&mut merged.external_facts);
+        
+        // Visit base keys in sorted order so the reported conflict is deterministic
+        let base_facts: BTreeMap<String, FactWrapper> = base_external_facts.facts.into_iter().collect();
+        for (key, wrapper) in base_facts {
+            match merged_facts.facts.entry(key) {
+                Entry::Vacant(entry) => {
+                    entry.insert(wrapper);
+                }
+                Entry::Occupied(entry) if options.fact_conflict == ConflictResolution::Error => {
+                    return Err(ValidationError::DuplicateFact { key: entry.key().clone() });
+                }
+                Entry::Occupied(_) => {}
+            }
+        }
+        for (type_id, codec) in base_external_facts.codecs {
+            if merged_facts.codec_for_name(&codec.type_name).is_some_and(|(registered_id, _)| registered_id != type_id) {
+                return Err(ValidationError::DuplicateFactType { type_name: codec.type_name });
+            }
+            merged_facts.codecs.entry(type_id).or_insert(codec);
+        }
+        
+        let merged_entities = Arc::make_mut(&mut merged.entity_resolver);
+        let base_entities: BTreeMap<String, EntityWrapper> = Arc::unwrap_or_clone(base.entity_resolver).entities.into_iter().collect();
+        for (entity_id, wrapper) in base_entities {
+            match merged_entities.entities.entry(entity_id) {
+                Entry::Vacant(entry) => {
+                    entry.insert(wrapper);
+                }
+                Entry::Occupied(entry) if options.entity_conflict == ConflictResolution::Error => {
+                    return Err(ValidationError::DuplicateEntity { entity_id: entry.key().clone() });
+                }
+                Entry::Occupied(_) => {}
+            }
+        }
+        
+        let merged_orderings = Arc::make_mut(&mut merged.ordering_rules);
+        let base_orderings = Arc::unwrap_or_clone(base.ordering_rules);
+        merged_orderings.enforce_stable_ordering |= base_orderings.enforce_stable_ordering;
+        for (entity_type, ordered_ids) in base_orderings.custom_orderings {
+            merged_orderings.custom_orderings.entry(entity_type).or_insert(ordered_ids);
+        }
+        
+        Ok(merged)
+    }
+}
+
+/// How `ExecutionContext::merge` handles a key defined in both contexts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConflictResolution {
+    /// Keep the value from the override context
+    Override,
+    /// Fail the merge with a `ValidationError`
+    #[default]
+    Error,
+}
+
+/// Conflict handling for `ExecutionContext::merge`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MergeOptions {
+    pub fact_conflict: ConflictResolution,
+    pub entity_conflict: ConflictResolution,
+}
+
+/// Summary that identifies an execution context configuration for audit purposes
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContextFingerprint {
+    pub time: DateTime<Utc>,
+    pub random_seed: u64,
+    pub external_fact_count: usize,
+    pub entity_count: usize,
+    pub ordering_rule_count: usize,
+    pub context_hash: u64,
+}
+
+/// Serializable configuration needed to reproduce an execution context
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReproducibilityConfig {
+    pub time: DateTime<Utc>,
+    pub random_seed: u64,
+    pub enforce_stable_ordering: bool,
+    pub custom_orderings: BTreeMap<String, Vec<String>>,
 }
 
 /// Builder for constructing execution contexts
 pub struct ExecutionContextBuilder {
     time: Option<DateTime<Utc>>,
+    clock: Option<ManualClock>,
+    tick_per_transaction: Option<Duration>,
     random_seed: Option<u64>,
     external_facts: ExternalFacts,
     entity_resolver: ExternalEntityResolver,
@@ -490,6 +1338,8 @@ impl ExecutionContextBuilder {
     pub fn new() -> Self {
         Self {
             time: None,
+            clock: None,
+            tick_per_transaction: None,
             random_seed: None,
             external_facts: ExternalFacts::new(),
             entity_resolver: ExternalEntityResolver::new(),
@@ -503,6 +1353,20 @@ impl ExecutionContextBuilder {
         self
     }
     
+    /// Drive the context's time from a clock the caller controls
+    /// 
+    /// Takes precedence over `with_time`.
+    pub fn with_manual_clock(mut self, clock: ManualClock) -> Self {
+        self.clock = Some(clock);
+        self
+    }
+    
+    /// Advance the time by `tick` after each transaction; see `DeterministicTime::with_tick`
+    pub fn with_tick_per_transaction(mut self, tick: Duration) -> Self {
+        self.tick_per_transaction = Some(tick);
+        self
+    }
+    
     /// Set the random seed
     pub fn with_random_seed(mut self, seed: u64) -> Self {
         self.random_seed = Some(seed);
@@ -537,13 +1401,21 @@ impl ExecutionContextBuilder {
     pub fn build(self) -> ExecutionContext {
         let time = self.time.unwrap_or_else(|| Utc::now());
         let random_seed = self.random_seed.unwrap_or(0);
+        let deterministic_time = DeterministicTime {
+            current_time: time,
+            clock: self.clock.or_else(|| self.tick_per_transaction.map(|_| ManualClock::new(time))),
+            tick_per_transaction: self.tick_per_transaction,
+        };
         
         ExecutionContext {
-            deterministic_time: DeterministicTime::new(time),
+            deterministic_time,
             seeded_random: SeededRandom::new(random_seed),
-            external_facts: self.external_facts,
-            entity_resolver: self.entity_resolver,
-            ordering_rules: self.ordering_rules,
+            external_facts: Arc::new(self.external_facts),
+            entity_resolver: Arc::new(self.entity_resolver),
+            ordering_rules: Arc::new(self.ordering_rules),
+            phase: ExecutionPhase::default(),
+            causality: None,
+            debug_log: None,
         }
     }
 }
@@ -573,26 +1445,111 @@ pub enum Operation {
     ThreadSpawn,
     /// Process spawning
     ProcessSpawn,
+    /// UUID generation without a seed (e.g. `uuid::Uuid::new_v4()`)
+    UuidWithoutSeed,
 }
 
 /// Guard to detect and prevent non-deterministic operations
+/// 
+/// Code that wraps third-party helpers can route them through
+/// [`NonDeterminismGuard::validate`] so that unseeded sources are rejected.
+/// For example, a rule that would call `uuid::Uuid::new_v4()` directly should
+/// check `Operation::UuidWithoutSeed`, which fails in strict mode; the
+/// deterministic alternative is `ExecutionContext::generate_uuid`.
 #[derive(Debug, Clone)]
 pub struct NonDeterminismGuard {
     strict_mode: bool,
+    /// Most recent detections, kept only when enabled through `with_audit_trail`
+    audit_trail: VecDeque<NonDeterministicAttempt>,
+    audit_trail_capacity: usize,
+}
+
+/// A non-deterministic result detected by `NonDeterminismGuard::instrument`
+#[derive(Debug, Clone)]
+pub struct NonDeterministicAttempt {
+    /// Operation name passed to `instrument`
+    pub operation: String,
+    pub detected_at: Instant,
+    /// `file:line:column` of the `instrument` call
+    pub call_site: String,
+    /// Debug representations of the two differing results, in call order
+    pub detected_values: (String, String),
 }
 
 impl NonDeterminismGuard {
     /// Create a new non-determinism guard in strict mode
     pub fn new() -> Self {
-        Self {
-            strict_mode: true,
-        }
+        Self::with_strict_mode(true)
     }
     
     /// Create a guard with custom strictness settings
     pub fn with_strict_mode(strict: bool) -> Self {
         Self {
             strict_mode: strict,
+            audit_trail: VecDeque::new(),
+            audit_trail_capacity: 0,
+        }
+    }
+    
+    /// Record detections by `instrument`, keeping the most recent `capacity` of them
+    pub fn with_audit_trail(mut self, capacity: usize) -> Self {
+        self.audit_trail = VecDeque::with_capacity(capacity);
+        self.audit_trail_capacity = capacity;
+        self
+    }
+    
+    /// Get the recorded detections, oldest first
+    /// 
+    /// Empty unless the trail was enabled with `with_audit_trail`.
+    pub fn audit_trail(&self) -> &VecDeque<NonDeterministicAttempt> {
+        &self.audit_trail
+    }
+    
+    /// Run `f` twice and check that both calls return the same result
+    /// 
+    /// This is a heuristic: an operation that reads the clock or an unseeded
+    /// random source almost always returns a different value the second
+    /// time, while a deterministic one cannot. Differing results are recorded
+    /// in the audit trail and, in strict mode, rejected with
+    /// `ProcessingError::NonDeterministicOperation` whose location is the call
+    /// site. Otherwise the first result is returned. `f` must be free of side
+    /// effects, since it runs twice.
+    #[track_caller]
+    pub fn instrument<T, F>(&mut self, op_name: &str, f: F) -> Result<T, ProcessingError>
+    where
+        T: PartialEq + std::fmt::Debug,
+        F: Fn() -> T,
+    {
+        let call_site = std::panic::Location::caller().to_string();
+        let first = f();
+        let second = f();
+        if first == second {
+            return Ok(first);
+        }
+        
+        if self.audit_trail_capacity > 0 {
+            if self.audit_trail.len() == self.audit_trail_capacity {
+                self.audit_trail.pop_front();
+            }
+            self.audit_trail.push_back(NonDeterministicAttempt {
+                operation: op_name.to_string(),
+                detected_at: Instant::now(),
+                call_site: call_site.clone(),
+                detected_values: (format!("{:?}", first), format!("{:?}", second)),
+            });
+        }
+        self.reject_instrumented(op_name, call_site, first)
+    }
+    
+    /// Fail an instrumented operation in strict mode, or let its first result through
+    fn reject_instrumented<T>(&self, op_name: &str, call_site: String, first: T) -> Result<T, ProcessingError> {
+        if self.strict_mode {
+            Err(ProcessingError::NonDeterministicOperation {
+                operation: op_name.to_string(),
+                location: call_site,
+            })
+        } else {
+            Ok(first)
         }
     }
     
@@ -635,6 +1592,10 @@ impl NonDeterminismGuard {
                 operation: "process_spawn".to_string(),
                 location: "process management".to_string(),
             }),
+            Operation::UuidWithoutSeed => Err(ProcessingError::NonDeterministicOperation {
+                operation: "unseeded_uuid".to_string(),
+                location: "identifier generation".to_string(),
+            }),
         }
     }
     
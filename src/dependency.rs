@@ -0,0 +1,153 @@
+//! Field-level dependency analysis between rule sets
+//! 
+//! Rule sets can declare the state fields they read and write as JSON paths in
+//! the form used by `StateAggregator`: dot-separated segments, with `*`
+//! matching any single segment, e.g. `accounts.*.balance`. A path covers every
+//! field nested inside it. Undeclared access is treated as touching everything.
+
+use crate::rule_set::RuleSetMetadata;
+use serde::{Deserialize, Serialize};
+
+/// Fields a single rule set reads and writes
+/// 
+/// `None` means the access is unknown and could involve any field.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RuleFieldAccess {
+    pub rule: String,
+    pub reads: Option<Vec<String>>,
+    pub writes: Option<Vec<String>>,
+}
+
+/// A later rule that touches fields an earlier rule writes
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RuleDependency {
+    /// Rule whose writes the dependent rule observes or overwrites
+    pub writer: String,
+    /// Rule that runs after the writer
+    pub dependent: String,
+    /// Overlapping paths as declared by the writer, or `None` if either access is unknown
+    pub fields: Option<Vec<String>>,
+}
+
+/// Computes which rules in a sequence depend on each other's writes
+#[derive(Debug, Clone, Default)]
+pub struct DependencyAnalyzer {
+    rules: Vec<RuleFieldAccess>,
+}
+
+impl DependencyAnalyzer {
+    /// Create an analyzer with no rules
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Append a rule with explicitly declared reads and writes
+    pub fn with_rule(mut self, rule: impl Into<String>, reads: Option<Vec<String>>, writes: Option<Vec<String>>) -> Self {
+        self.rules.push(RuleFieldAccess { rule: rule.into(), reads, writes });
+        self
+    }
+    
+    /// Append a rule described by its metadata
+    /// 
+    /// Writes come from `RuleSetMetadata::affected_fields`; reads are unknown.
+    pub fn with_metadata(self, metadata: &RuleSetMetadata) -> Self {
+        let writes = metadata.affected_fields.clone();
+        self.with_rule(metadata.name.clone(), None, writes)
+    }
+    
+    /// Get the declared access of every rule, in sequence order
+    pub fn rules(&self) -> &[RuleFieldAccess] {
+        &self.rules
+    }
+    
+    /// Find every pair of rules where the later one reads or writes a field the earlier one writes
+    /// 
+    /// Pairs are listed by writer, then by dependent, in sequence order. Rules
+    /// missing from the result can run in either order.
+    pub fn analyze(&self) -> Vec<RuleDependency> {
+        let mut dependencies = Vec::new();
+        for (index, writer) in self.rules.iter().enumerate() {
+            for dependent in &self.rules[index + 1..] {
+                let touched = [&dependent.reads, &dependent.writes];
+                let fields = match &writer.writes {
+                    Some(writes) if writes.is_empty() => continue,
+                    Some(writes) if touched.iter().all(|paths| paths.is_some()) => {
+                        let overlapping: Vec<String> = writes
+                            .iter()
+                            .filter(|write| {
+                                touched.iter().flat_map(|paths| paths.iter().flatten()).any(|path| paths_overlap(write, path))
+                            })
+                            .cloned()
+                            .collect();
+                        if overlapping.is_empty() {
+                            continue;
+                        }
+                        Some(overlapping)
+                    }
+                    _ => None,
+                };
+                dependencies.push(RuleDependency {
+                    writer: writer.rule.clone(),
+                    dependent: dependent.rule.clone(),
+                    fields,
+                });
+            }
+        }
+        dependencies
+    }
+}
+
+/// Check whether two field paths can refer to the same field or one contains the other
+pub fn paths_overlap(a: &str, b: &str) -> bool {
+    a.split('.')
+        .zip(b.split('.'))
+        .all(|(x, y)| x == "*" || y == "*" || x == y)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn paths(paths: &[&str]) -> Option<Vec<String>> {
+        Some(paths.iter().map(|p| p.to_string()).collect())
+    }
+    
+    #[test]
+    fn test_paths_overlap() {
+        assert!(paths_overlap("accounts.*.balance", "accounts.ACC001.balance"));
+        assert!(paths_overlap("accounts", "accounts.ACC001.balance"));
+        assert!(!paths_overlap("accounts.*.balance", "accounts.ACC001.status"));
+        assert!(!paths_overlap("accounts", "total_fees_collected"));
+    }
+    
+    #[test]
+    fn test_analyze_dependencies() {
+        let analyzer = DependencyAnalyzer::new()
+            .with_rule("transfer", paths(&["accounts"]), paths(&["accounts.*.balance", "total_fees_collected"]))
+            .with_rule("freeze", paths(&["accounts.*.status"]), paths(&["accounts.*.status"]))
+            .with_rule("fee_report", paths(&["total_fees_collected"]), paths(&[]))
+            .with_rule("legacy", None, None);
+        
+        assert_eq!(analyzer.analyze(), vec![
+            RuleDependency {
+                writer: "transfer".to_string(),
+                dependent: "fee_report".to_string(),
+                fields: paths(&["total_fees_collected"]),
+            },
+            RuleDependency { writer: "transfer".to_string(), dependent: "legacy".to_string(), fields: None },
+            RuleDependency { writer: "freeze".to_string(), dependent: "legacy".to_string(), fields: None },
+        ]);
+    }
+    
+    #[test]
+    fn test_metadata_writes_are_used() {
+        let metadata = RuleSetMetadata::new("transfer".to_string(), "Transfers".to_string())
+            .with_affected_fields(vec!["accounts.*.balance".to_string()]);
+        let analyzer = DependencyAnalyzer::new()
+            .with_metadata(&metadata)
+            .with_rule("audit", paths(&["accounts.*.status"]), paths(&[]));
+        
+        assert_eq!(analyzer.rules()[0].writes, paths(&["accounts.*.balance"]));
+        assert!(analyzer.analyze().is_empty());
+    }
+}
@@ -0,0 +1,625 @@
+//! Dispatch of heterogeneous transaction types to per-type rule sets
+
+use crate::context::ExecutionContext;
+use crate::error::{ProcessingError, SerializationError, ValidationError};
+use crate::rule_contract::RuleCondition;
+use crate::side_effects::SideEffectQueue;
+use crate::traits::{RuleSet, State, Transaction};
+use crate::types::{AuditRecord, ReplayCostEstimate, Version};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::any::Any;
+use std::collections::HashMap;
+use std::fmt;
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+/// Object-safe view of a concrete transaction type
+trait ErasedTransaction: Send + Sync {
+    fn transaction_id(&self) -> &str;
+    fn transaction_timestamp(&self) -> DateTime<Utc>;
+    fn validate_transaction(&self) -> Result<(), ValidationError>;
+    fn to_json(&self) -> Result<serde_json::Value, serde_json::Error>;
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl<T: Transaction + Send + Sync + 'static> ErasedTransaction for T {
+    fn transaction_id(&self) -> &str {
+        Transaction::id(self)
+    }
+    
+    fn transaction_timestamp(&self) -> DateTime<Utc> {
+        Transaction::timestamp(self)
+    }
+    
+    fn validate_transaction(&self) -> Result<(), ValidationError> {
+        Transaction::validate(self)
+    }
+    
+    fn to_json(&self) -> Result<serde_json::Value, serde_json::Error> {
+        serde_json::to_value(self)
+    }
+    
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// A transaction loaded from its serialized form, before it is decoded to a concrete type
+struct SerializedTransaction {
+    id: String,
+    timestamp: DateTime<Utc>,
+    payload: serde_json::Value,
+}
+
+impl ErasedTransaction for SerializedTransaction {
+    fn transaction_id(&self) -> &str {
+        &self.id
+    }
+    
+    fn transaction_timestamp(&self) -> DateTime<Utc> {
+        self.timestamp
+    }
+    
+    fn validate_transaction(&self) -> Result<(), ValidationError> {
+        let header = PayloadHeader::deserialize(&self.payload).map_err(|e| ValidationError::InvalidTransaction {
+            reason: format!("Transaction {} has an unreadable payload: {}", self.id, e),
+        })?;
+        check_payload_header(&self.id, self.timestamp, header.id.as_deref(), header.timestamp)
+    }
+    
+    fn to_json(&self) -> Result<serde_json::Value, serde_json::Error> {
+        Ok(self.payload.clone())
+    }
+    
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// The `id` and `timestamp` fields of a serialized inner transaction, where it has them
+#[derive(Deserialize)]
+struct PayloadHeader {
+    id: Option<String>,
+    timestamp: Option<DateTime<Utc>>,
+}
+
+/// Check that the ID and timestamp an inner transaction carries match the outer ones
+fn check_payload_header(
+    id: &str,
+    timestamp: DateTime<Utc>,
+    payload_id: Option<&str>,
+    payload_timestamp: Option<DateTime<Utc>>,
+) -> Result<(), ValidationError> {
+    if let Some(payload_id) = payload_id.filter(|payload_id| *payload_id != id) {
+        return Err(ValidationError::InvalidTransaction {
+            reason: format!("Transaction {} carries a payload with ID {}", id, payload_id),
+        });
+    }
+    if let Some(payload_timestamp) = payload_timestamp.filter(|payload_timestamp| *payload_timestamp != timestamp) {
+        return Err(ValidationError::InvalidTransaction {
+            reason: format!("Transaction {} is timestamped {} but its payload {}", id, timestamp, payload_timestamp),
+        });
+    }
+    Ok(())
+}
+
+/// Serialized layout of an [`AnyTransaction`]
+#[derive(Serialize, Deserialize)]
+struct AnyTransactionRepr {
+    type_tag: String,
+    id: String,
+    timestamp: DateTime<Utc>,
+    transaction: serde_json::Value,
+}
+
+/// A transaction of any concrete type, tagged with the name of its type
+/// 
+/// Lets a single sequence mix transfers, deposits, closures and so on. The
+/// tag selects the rule set in a [`TransactionDispatcher`]. Serializes as
+/// `{"type_tag", "id", "timestamp", "transaction"}` with the inner
+/// transaction as JSON.
+/// 
+/// A deserialized `AnyTransaction` holds only the JSON form of the inner
+/// transaction. `validate` cannot run the inner transaction's validation;
+/// it checks that the payload's `id` and `timestamp` fields, where present,
+/// match the outer ones. `decode` restores the concrete type and checks its
+/// ID and timestamp the same way.
+pub struct AnyTransaction<S> {
+    type_tag: String,
+    inner: Arc<dyn ErasedTransaction>,
+    _state: PhantomData<fn() -> S>,
+}
+
+impl<S> AnyTransaction<S> {
+    /// Wrap a concrete transaction under a type tag
+    pub fn new<T: Transaction + Send + Sync + 'static>(type_tag: &str, transaction: T) -> Self {
+        Self {
+            type_tag: type_tag.to_string(),
+            inner: Arc::new(transaction),
+            _state: PhantomData,
+        }
+    }
+    
+    /// Get the type tag used to dispatch this transaction
+    pub fn type_tag(&self) -> &str {
+        &self.type_tag
+    }
+    
+    /// Borrow the inner transaction if it was wrapped in memory as type `T`
+    /// 
+    /// Returns `None` for a different type and for deserialized transactions;
+    /// use `decode` to handle both.
+    pub fn downcast_ref<T: 'static>(&self) -> Option<&T> {
+        self.inner.as_any().downcast_ref::<T>()
+    }
+    
+    /// Get the inner transaction as type `T`
+    /// 
+    /// Clones the inner value when it was wrapped in memory as `T`, otherwise
+    /// deserializes it from its JSON form.
+    pub fn decode<T: Transaction + 'static>(&self) -> Result<T, ProcessingError> {
+        if let Some(transaction) = self.downcast_ref::<T>() {
+            return Ok(transaction.clone());
+        }
+        
+        let payload = self.payload().map_err(|e| self.decode_error(e.to_string()))?;
+        let transaction: T = serde_json::from_value(payload).map_err(|e| self.decode_error(e.to_string()))?;
+        check_payload_header(
+            self.inner.transaction_id(),
+            self.inner.transaction_timestamp(),
+            Some(transaction.id()),
+            Some(transaction.timestamp()),
+        )
+        .map_err(|e| self.decode_error(e.to_string()))?;
+        Ok(transaction)
+    }
+    
+    /// Get the JSON form of the inner transaction
+    pub fn payload(&self) -> Result<serde_json::Value, SerializationError> {
+        self.inner.to_json().map_err(|e| SerializationError::SerializationFailed {
+            reason: format!("Failed to serialize {} transaction {}: {}", self.type_tag, self.inner.transaction_id(), e),
+        })
+    }
+    
+    fn decode_error(&self, reason: String) -> ProcessingError {
+        ProcessingError::TransactionFailed {
+            transaction_id: self.inner.transaction_id().to_string(),
+            reason: format!("Failed to decode {} transaction: {}", self.type_tag, reason),
+        }
+    }
+}
+
+impl<S> Clone for AnyTransaction<S> {
+    fn clone(&self) -> Self {
+        Self {
+            type_tag: self.type_tag.clone(),
+            inner: Arc::clone(&self.inner),
+            _state: PhantomData,
+        }
+    }
+}
+
+impl<S> fmt::Debug for AnyTransaction<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AnyTransaction")
+            .field("type_tag", &self.type_tag)
+            .field("id", &self.inner.transaction_id())
+            .field("timestamp", &self.inner.transaction_timestamp())
+            .finish()
+    }
+}
+
+impl<S> Serialize for AnyTransaction<S> {
+    fn serialize<Ser: Serializer>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error> {
+        let transaction = self.inner.to_json().map_err(serde::ser::Error::custom)?;
+        AnyTransactionRepr {
+            type_tag: self.type_tag.clone(),
+            id: self.inner.transaction_id().to_string(),
+            timestamp: self.inner.transaction_timestamp(),
+            transaction,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de, S> Deserialize<'de> for AnyTransaction<S> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let repr = AnyTransactionRepr::deserialize(deserializer)?;
+        Ok(Self {
+            type_tag: repr.type_tag,
+            inner: Arc::new(SerializedTransaction {
+                id: repr.id,
+                timestamp: repr.timestamp,
+                payload: repr.transaction,
+            }),
+            _state: PhantomData,
+        })
+    }
+}
+
+impl<S> Transaction for AnyTransaction<S> {
+    fn id(&self) -> &str {
+        self.inner.transaction_id()
+    }
+    
+    fn timestamp(&self) -> DateTime<Utc> {
+        self.inner.transaction_timestamp()
+    }
+    
+    fn validate(&self) -> Result<(), ValidationError> {
+        self.inner.validate_transaction()
+    }
+    
+    fn as_any(&self) -> Option<&dyn Any> {
+        Some(self.inner.as_any())
+    }
+}
+
+/// Rule set that routes each [`AnyTransaction`] to the rule set registered for its type tag
+/// 
+/// The dispatcher is itself a `RuleSet`, so a `ReplayEngine` or
+/// `TransactionProcessor` can replay mixed sequences through it.
+pub struct TransactionDispatcher<S: State> {
+    version: Version,
+    rule_sets: HashMap<String, Box<dyn RuleSet<S, AnyTransaction<S>> + Send + Sync>>,
+}
+
+impl<S: State> TransactionDispatcher<S> {
+    /// Create an empty dispatcher reporting the given rule set version
+    pub fn new(version: Version) -> Self {
+        Self {
+            version,
+            rule_sets: HashMap::new(),
+        }
+    }
+    
+    /// Register the rule set for a transaction type tag, replacing any previous one
+    pub fn register_type(
+        &mut self,
+        tag: &str,
+        rule_set: impl RuleSet<S, AnyTransaction<S>> + Send + Sync + 'static,
+    ) {
+        self.rule_sets.insert(tag.to_string(), Box::new(rule_set));
+    }
+    
+    /// Check whether a rule set is registered for a type tag
+    pub fn contains_type(&self, tag: &str) -> bool {
+        self.rule_sets.contains_key(tag)
+    }
+    
+    /// Get the registered type tags in sorted order
+    pub fn registered_types(&self) -> Vec<&str> {
+        let mut tags: Vec<&str> = self.rule_sets.keys().map(String::as_str).collect();
+        tags.sort_unstable();
+        tags
+    }
+    
+    /// Apply the rule set registered for the transaction's type tag
+    pub fn dispatch(
+        &self,
+        transaction: &AnyTransaction<S>,
+        state: &S,
+        context: &ExecutionContext,
+    ) -> Result<S, ProcessingError> {
+        self.rule_set_for(transaction)?.apply(state, transaction, context)
+    }
+    
+    fn rule_set_for(
+        &self,
+        transaction: &AnyTransaction<S>,
+    ) -> Result<&(dyn RuleSet<S, AnyTransaction<S>> + Send + Sync), ProcessingError> {
+        self.rule_sets
+            .get(transaction.type_tag())
+            .map(|rule_set| rule_set.as_ref())
+            .ok_or_else(|| ProcessingError::UnregisteredTransactionType {
+                transaction_id: transaction.id().to_string(),
+                type_tag: transaction.type_tag().to_string(),
+            })
+    }
+    
+    /// Collect the conditions of every registered rule set, in tag order
+    /// 
+    /// Each condition is prefixed with its tag and holds trivially for
+    /// transactions of other types.
+    fn routed_conditions(
+        &self,
+        conditions_of: impl Fn(&(dyn RuleSet<S, AnyTransaction<S>> + Send + Sync)) -> Vec<RuleCondition<S, AnyTransaction<S>>>,
+    ) -> Vec<RuleCondition<S, AnyTransaction<S>>>
+    where
+        S: 'static,
+    {
+        self.registered_types()
+            .into_iter()
+            .flat_map(|tag| {
+                conditions_of(self.rule_sets[tag].as_ref()).into_iter().map(move |condition| {
+                    let tag = tag.to_string();
+                    let check = condition.check;
+                    RuleCondition {
+                        description: format!("{}: {}", tag, condition.description),
+                        check: Box::new(move |state: &S, transaction: &AnyTransaction<S>| {
+                            transaction.type_tag() != tag || check(state, transaction)
+                        }),
+                    }
+                })
+            })
+            .collect()
+    }
+}
+
+impl<S: State> fmt::Debug for TransactionDispatcher<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TransactionDispatcher")
+            .field("version", &self.version)
+            .field("registered_types", &self.registered_types())
+            .finish()
+    }
+}
+
+impl<S: State + 'static> RuleSet<S, AnyTransaction<S>> for TransactionDispatcher<S> {
+    fn version(&self) -> Version {
+        self.version.clone()
+    }
+    
+    /// Forwards to the registered rule set; unregistered types are reported by `apply`
+    fn pre_validate(
+        &self,
+        state: &S,
+        transaction: &AnyTransaction<S>,
+        context: &ExecutionContext,
+    ) -> Result<(), ValidationError> {
+        match self.rule_set_for(transaction) {
+            Ok(rule_set) => rule_set.pre_validate(state, transaction, context),
+            Err(_) => Ok(()),
+        }
+    }
+    
+    /// Conditions of every registered rule set, each checked only for transactions of its type
+    fn pre_conditions(&self) -> Vec<RuleCondition<S, AnyTransaction<S>>> {
+        self.routed_conditions(|rule_set| rule_set.pre_conditions())
+    }
+    
+    /// Conditions of every registered rule set, each checked only for transactions of its type
+    fn post_conditions(&self) -> Vec<RuleCondition<S, AnyTransaction<S>>> {
+        self.routed_conditions(|rule_set| rule_set.post_conditions())
+    }
+    
+    /// The registered rule set's key, mixed with the type tag so types never share cached results
+    fn idempotency_key(&self, state: &S, transaction: &AnyTransaction<S>, context: &ExecutionContext) -> Option<u64> {
+        let key = self.rule_set_for(transaction).ok()?.idempotency_key(state, transaction, context)?;
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(&key.to_le_bytes());
+        hasher.update(transaction.type_tag().as_bytes());
+        
+        let mut key_bytes = [0u8; 8];
+        key_bytes.copy_from_slice(&hasher.finalize().as_bytes()[..8]);
+        Some(u64::from_le_bytes(key_bytes))
+    }
+    
+    fn apply(
+        &self,
+        state: &S,
+        transaction: &AnyTransaction<S>,
+        context: &ExecutionContext,
+    ) -> Result<S, ProcessingError> {
+        self.dispatch(transaction, state, context)
+    }
+    
+    fn apply_with_audit(
+        &self,
+        state: &S,
+        transaction: &AnyTransaction<S>,
+        context: &ExecutionContext,
+    ) -> Result<(S, AuditRecord), ProcessingError> {
+        self.rule_set_for(transaction)?.apply_with_audit(state, transaction, context)
+    }
+    
+    fn describe_transaction(&self, state: &S, transaction: &AnyTransaction<S>, context: &ExecutionContext) -> String {
+        match self.rule_set_for(transaction) {
+            Ok(rule_set) => rule_set.describe_transaction(state, transaction, context),
+            Err(_) => format!("Applied rule {} to transaction {}", self.version, transaction.id()),
+        }
+    }
+    
+    fn enqueue_side_effects(&self, state: &S, transaction: &AnyTransaction<S>, queue: &SideEffectQueue) {
+        if let Ok(rule_set) = self.rule_set_for(transaction) {
+            rule_set.enqueue_side_effects(state, transaction, queue);
+        }
+    }
+    
+    fn enqueue_transition_side_effects(
+        &self,
+        before: &S,
+        after: &S,
+        transaction: &AnyTransaction<S>,
+        context: &ExecutionContext,
+        queue: &SideEffectQueue,
+    ) {
+        if let Ok(rule_set) = self.rule_set_for(transaction) {
+            rule_set.enqueue_transition_side_effects(before, after, transaction, context, queue);
+        }
+    }
+    
+    /// The sum of each registered rule set's estimate for the transactions of its type
+    fn replay_cost_estimate(&self, transactions: &[AnyTransaction<S>], state: &S) -> ReplayCostEstimate {
+        self.registered_types()
+            .into_iter()
+            .map(|tag| {
+                let routed: Vec<AnyTransaction<S>> = transactions
+                    .iter()
+                    .filter(|transaction| transaction.type_tag() == tag)
+                    .cloned()
+                    .collect();
+                self.rule_sets[tag].replay_cost_estimate(&routed, state)
+            })
+            .fold(ReplayCostEstimate::default(), |total, estimate| total.saturating_add(&estimate))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use std::hash::Hash;
+    
+    #[derive(Debug, Clone, Hash, Serialize, Deserialize, PartialEq)]
+    struct TestState {
+        balance: i64,
+    }
+    
+    impl State for TestState {
+        fn validate(&self) -> Result<(), ValidationError> {
+            Ok(())
+        }
+    }
+    
+    #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+    struct Deposit {
+        id: String,
+        amount: i64,
+        timestamp: DateTime<Utc>,
+    }
+    
+    impl Transaction for Deposit {
+        fn id(&self) -> &str {
+            &self.id
+        }
+        
+        fn timestamp(&self) -> DateTime<Utc> {
+            self.timestamp
+        }
+        
+        fn validate(&self) -> Result<(), ValidationError> {
+            if self.amount <= 0 {
+                return Err(ValidationError::InvalidTransaction {
+                    reason: "Deposit amount must be positive".to_string(),
+                });
+            }
+            Ok(())
+        }
+    }
+    
+    fn deposit(amount: i64) -> Deposit {
+        Deposit {
+            id: "dep1".to_string(),
+            amount,
+            timestamp: Utc.timestamp_opt(1000, 0).unwrap(),
+        }
+    }
+    
+    #[test]
+    fn test_wrapped_transaction_delegates_to_inner() {
+        let tx: AnyTransaction<TestState> = AnyTransaction::new("deposit", deposit(50));
+        
+        assert_eq!(tx.type_tag(), "deposit");
+        assert_eq!(tx.id(), "dep1");
+        assert_eq!(tx.timestamp(), Utc.timestamp_opt(1000, 0).unwrap());
+        assert!(tx.validate().is_ok());
+        assert_eq!(tx.downcast_ref::<Deposit>(), Some(&deposit(50)));
+        
+        let invalid: AnyTransaction<TestState> = AnyTransaction::new("deposit", deposit(-1));
+        assert!(invalid.validate().is_err());
+    }
+    
+    #[test]
+    fn test_serialization_round_trip() {
+        let tx: AnyTransaction<TestState> = AnyTransaction::new("deposit", deposit(50));
+        let json = serde_json::to_value(&tx).unwrap();
+        
+        assert_eq!(json["type_tag"], "deposit");
+        assert_eq!(json["id"], "dep1");
+        assert_eq!(json["transaction"]["amount"], 50);
+        
+        let restored: AnyTransaction<TestState> = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(restored.type_tag(), "deposit");
+        assert_eq!(restored.id(), "dep1");
+        assert!(restored.downcast_ref::<Deposit>().is_none());
+        assert_eq!(restored.decode::<Deposit>().unwrap(), deposit(50));
+        assert_eq!(serde_json::to_value(&restored).unwrap(), json);
+    }
+    
+    #[test]
+    fn test_unregistered_type_is_rejected() {
+        let dispatcher = TransactionDispatcher::<TestState>::new(Version::new(1, 0, 0));
+        let tx = AnyTransaction::new("deposit", deposit(50));
+        let context = ExecutionContext::new(Utc.timestamp_opt(0, 0).unwrap(), 0);
+        
+        let result = dispatcher.dispatch(&tx, &TestState { balance: 0 }, &context);
+        assert!(matches!(
+            result,
+            Err(ProcessingError::UnregisteredTransactionType { ref type_tag, .. }) if type_tag == "deposit"
+        ));
+    }
+    
+    /// Deposit rules that audit, cache, declare a contract and cost 7 ms per transaction
+    struct DepositRules;
+    
+    impl RuleSet<TestState, AnyTransaction<TestState>> for DepositRules {
+        fn version(&self) -> Version {
+            Version::new(1, 0, 0)
+        }
+        
+        fn pre_conditions(&self) -> Vec<RuleCondition<TestState, AnyTransaction<TestState>>> {
+            vec![RuleCondition::new("balance is non-negative", |state: &TestState, _| state.balance >= 0)]
+        }
+        
+        fn idempotency_key(&self, state: &TestState, _transaction: &AnyTransaction<TestState>, _context: &ExecutionContext) -> Option<u64> {
+            Some(state.balance as u64)
+        }
+        
+        fn apply(&self, state: &TestState, transaction: &AnyTransaction<TestState>, _context: &ExecutionContext) -> Result<TestState, ProcessingError> {
+            let deposit: Deposit = transaction.decode()?;
+            Ok(TestState { balance: state.balance + deposit.amount })
+        }
+        
+        fn apply_with_audit(
+            &self,
+            state: &TestState,
+            transaction: &AnyTransaction<TestState>,
+            context: &ExecutionContext,
+        ) -> Result<(TestState, AuditRecord), ProcessingError> {
+            let mut audit = AuditRecord::default();
+            audit.rule_clauses_fired.push("credit".to_string());
+            Ok((self.apply(state, transaction, context)?, audit))
+        }
+        
+        fn replay_cost_estimate(&self, transactions: &[AnyTransaction<TestState>], _state: &TestState) -> ReplayCostEstimate {
+            ReplayCostEstimate {
+                estimated_cpu_ms: 7 * transactions.len() as u64,
+                ..ReplayCostEstimate::default()
+            }
+        }
+    }
+    
+    #[test]
+    fn test_dispatcher_forwards_to_routed_rule_set() {
+        let mut dispatcher = TransactionDispatcher::<TestState>::new(Version::new(2, 0, 0));
+        dispatcher.register_type("deposit", DepositRules);
+        let context = ExecutionContext::new(Utc.timestamp_opt(0, 0).unwrap(), 0);
+        let state = TestState { balance: -5 };
+        let deposit_tx = AnyTransaction::new("deposit", deposit(50));
+        let other_tx = AnyTransaction::new("withdrawal", deposit(50));
+        
+        let (new_state, audit) = dispatcher.apply_with_audit(&state, &deposit_tx, &context).unwrap();
+        assert_eq!(new_state.balance, 45);
+        assert_eq!(audit.rule_clauses_fired, vec!["credit"]);
+        
+        // Keys are routed but tagged, and unregistered types are never cached
+        let key = dispatcher.idempotency_key(&state, &deposit_tx, &context);
+        assert!(key.is_some());
+        assert_ne!(key, DepositRules.idempotency_key(&state, &deposit_tx, &context));
+        assert_eq!(dispatcher.idempotency_key(&state, &other_tx, &context), None);
+        
+        // Conditions only apply to transactions of their own type
+        let conditions = dispatcher.pre_conditions();
+        assert_eq!(conditions.len(), 1);
+        assert_eq!(conditions[0].description, "deposit: balance is non-negative");
+        assert!(!conditions[0].holds(&state, &deposit_tx));
+        assert!(conditions[0].holds(&state, &other_tx));
+        assert_eq!(dispatcher.documented_contract().pre_conditions, vec!["deposit: balance is non-negative"]);
+        
+        // Only the routed transactions count toward the estimate
+        let estimate = dispatcher.replay_cost_estimate(&[deposit_tx.clone(), other_tx, deposit_tx], &state);
+        assert_eq!(estimate.estimated_cpu_ms, 14);
+    }
+}
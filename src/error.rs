@@ -67,6 +67,50 @@ impl Default for ErrorContext {
     }
 }
 
+/// Context attached to a rule error to trace which check produced it
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RuleErrorContext {
+    /// Transaction being processed when the error occurred
+    pub transaction_id: Option<String>,
+    /// Version of the rule set that produced the error
+    pub rule_version: Option<Version>,
+    /// Hash of the state the transaction was applied to
+    pub state_snapshot_hash: Option<StateHash>,
+    /// Rule clause that failed, e.g. `"limits.daily_maximum"`
+    pub rule_clause: Option<String>,
+}
+
+impl RuleErrorContext {
+    /// Create an empty rule error context
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Add transaction context
+    pub fn with_transaction_id(mut self, id: String) -> Self {
+        self.transaction_id = Some(id);
+        self
+    }
+    
+    /// Add rule context
+    pub fn with_rule_version(mut self, version: Version) -> Self {
+        self.rule_version = Some(version);
+        self
+    }
+    
+    /// Add state context
+    pub fn with_state_snapshot_hash(mut self, hash: StateHash) -> Self {
+        self.state_snapshot_hash = Some(hash);
+        self
+    }
+    
+    /// Add the clause that failed
+    pub fn with_rule_clause(mut self, clause: String) -> Self {
+        self.rule_clause = Some(clause);
+        self
+    }
+}
+
 /// Detailed information about a state mismatch
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StateMismatchDetail {
@@ -108,7 +152,34 @@ pub struct ValidationDetail {
     pub context: ErrorContext,
 }
 
-#[derive(Debug, Error)]
+/// Whether a rule condition is checked before or after the rule set applies
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConditionType {
+    Pre,
+    Post,
+}
+
+impl std::fmt::Display for ConditionType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConditionType::Pre => write!(f, "pre"),
+            ConditionType::Post => write!(f, "post"),
+        }
+    }
+}
+
+/// A rule set's declared pre- or post-condition that a transaction broke
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Error)]
+#[error("{condition_type}-condition \"{description}\" violated by transaction {transaction_id}")]
+pub struct ContractViolationError {
+    pub condition_type: ConditionType,
+    pub description: String,
+    pub transaction_id: String,
+}
+
+/// Top-level error wrapping every DTRE error type
+#[derive(Debug, Clone, Error)]
 pub enum DTREError {
     #[error("Processing error: {0}")]
     Processing(#[from] ProcessingError),
@@ -126,7 +197,102 @@ pub enum DTREError {
     Serialization(#[from] SerializationError),
 }
 
-#[derive(Debug, Error)]
+impl DTREError {
+    /// Stable code identifying the error variant, for logs and metrics
+    pub fn error_code(&self) -> &'static str {
+        match self {
+            Self::Processing(error) => match error {
+                ProcessingError::NonDeterministicOperation { .. } => "PROCESSING_NON_DETERMINISTIC_OPERATION",
+                ProcessingError::TransactionFailed { .. } => "PROCESSING_TRANSACTION_FAILED",
+                ProcessingError::RuleApplicationFailed { .. } => "PROCESSING_RULE_APPLICATION_FAILED",
+                ProcessingError::ExternalEntityNotFound { .. } => "PROCESSING_EXTERNAL_ENTITY_NOT_FOUND",
+                ProcessingError::ExternalEntityTypeMismatch { .. } => "PROCESSING_EXTERNAL_ENTITY_TYPE_MISMATCH",
+                ProcessingError::OrderingViolation { .. } => "PROCESSING_ORDERING_VIOLATION",
+                ProcessingError::PreValidationFailed { .. } => "PROCESSING_PRE_VALIDATION_FAILED",
+                ProcessingError::PreFlightValidationFailed { .. } => "PROCESSING_PRE_FLIGHT_VALIDATION_FAILED",
+                ProcessingError::InvalidRange { .. } => "PROCESSING_INVALID_RANGE",
+                ProcessingError::UnregisteredTransactionType { .. } => "PROCESSING_UNREGISTERED_TRANSACTION_TYPE",
+                ProcessingError::TransactionLimitExceeded { .. } => "PROCESSING_TRANSACTION_LIMIT_EXCEEDED",
+                ProcessingError::StateSizeLimitExceeded { .. } => "PROCESSING_STATE_SIZE_LIMIT_EXCEEDED",
+                ProcessingError::SigningFailed { .. } => "PROCESSING_SIGNING_FAILED",
+                ProcessingError::ExcessiveTimestampDrift { .. } => "PROCESSING_EXCESSIVE_TIMESTAMP_DRIFT",
+                ProcessingError::CompactionMismatch { .. } => "PROCESSING_COMPACTION_MISMATCH",
+                ProcessingError::InvalidTraceLog { .. } => "PROCESSING_INVALID_TRACE_LOG",
+                ProcessingError::TraceLogHashMismatch { .. } => "PROCESSING_TRACE_LOG_HASH_MISMATCH",
+                ProcessingError::StreamClosed { .. } => "PROCESSING_STREAM_CLOSED",
+                ProcessingError::PostConditionFailed { .. } => "PROCESSING_POST_CONDITION_FAILED",
+                ProcessingError::UnsatisfiedDependency { .. } => "PROCESSING_UNSATISFIED_DEPENDENCY",
+                ProcessingError::CostBudgetExceeded { .. } => "PROCESSING_COST_BUDGET_EXCEEDED",
+                ProcessingError::Rule(_) => "PROCESSING_RULE_ERROR",
+                ProcessingError::ContractViolation(_) => "PROCESSING_CONTRACT_VIOLATION",
+                ProcessingError::WithContext { .. } => "PROCESSING_WITH_CONTEXT",
+            },
+            Self::Validation(error) => match error {
+                ValidationError::InvalidState { .. } => "VALIDATION_INVALID_STATE",
+                ValidationError::InvalidTransaction { .. } => "VALIDATION_INVALID_TRANSACTION",
+                ValidationError::RuleViolated { .. } => "VALIDATION_RULE_VIOLATED",
+                ValidationError::WithDetails { .. } => "VALIDATION_WITH_DETAILS",
+                ValidationError::DuplicateFact { .. } => "VALIDATION_DUPLICATE_FACT",
+                ValidationError::DuplicateEntity { .. } => "VALIDATION_DUPLICATE_ENTITY",
+                ValidationError::DuplicateFactType { .. } => "VALIDATION_DUPLICATE_FACT_TYPE",
+                ValidationError::FieldValidation { .. } => "VALIDATION_FIELD_VALIDATION",
+            },
+            Self::State(error) => match error {
+                StateError::TransitionFailed { .. } => "STATE_TRANSITION_FAILED",
+                StateError::Mismatch { .. } => "STATE_MISMATCH",
+                StateError::CheckpointError { .. } => "STATE_CHECKPOINT_ERROR",
+                StateError::MismatchWithDetail { .. } => "STATE_MISMATCH_WITH_DETAIL",
+                StateError::SchemaMismatch { .. } => "STATE_SCHEMA_MISMATCH",
+                StateError::CheckpointIntegrityFailed { .. } => "STATE_CHECKPOINT_INTEGRITY_FAILED",
+                StateError::MergeConflict { .. } => "STATE_MERGE_CONFLICT",
+            },
+            Self::Rule(error) => match error {
+                RuleError::NotFound { .. } => "RULE_NOT_FOUND",
+                RuleError::VersionConflict { .. } => "RULE_VERSION_CONFLICT",
+                RuleError::RegistrationFailed { .. } => "RULE_REGISTRATION_FAILED",
+                RuleError::InvariantViolated { .. } => "RULE_INVARIANT_VIOLATED",
+                RuleError::GuardFailed { .. } => "RULE_GUARD_FAILED",
+                RuleError::WithContext { .. } => "RULE_WITH_CONTEXT",
+            },
+            Self::Serialization(error) => match error {
+                SerializationError::SerializationFailed { .. } => "SERIALIZATION_FAILED",
+                SerializationError::DeserializationFailed { .. } => "DESERIALIZATION_FAILED",
+            },
+        }
+    }
+    
+    /// Whether retrying the same operation later could succeed
+    /// 
+    /// Replays are deterministic, so only failures caused by data outside the replay
+    /// (a missing external entity, or checkpoint storage) are retryable.
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            Self::Processing(ProcessingError::ExternalEntityNotFound { .. })
+                | Self::State(StateError::CheckpointError { .. })
+        )
+    }
+    
+    /// Whether the error would be reproduced by replaying the same inputs
+    pub fn is_deterministic(&self) -> bool {
+        !matches!(self, Self::Processing(ProcessingError::NonDeterministicOperation { .. }))
+    }
+    
+    /// This error followed by the errors nested inside it, outermost first
+    /// 
+    /// A pre-validation failure nests the validation error that caused it.
+    pub fn chain(&self) -> Vec<DTREError> {
+        let mut chain = vec![self.clone()];
+        if let Self::Processing(error) = self {
+            if let Ok(validation) = error.clone().into_validation_error() {
+                chain.extend(DTREError::from(validation).chain());
+            }
+        }
+        chain
+    }
+}
+
+#[derive(Debug, Clone, Error)]
 pub enum ProcessingError {
     #[error("Non-deterministic operation detected: {operation} at {location}")]
     NonDeterministicOperation { operation: String, location: String },
@@ -150,17 +316,75 @@ pub enum ProcessingError {
         actual_order: Vec<String> 
     },
     
+    #[error("Pre-validation failed for rule version {rule_version}: {}", .detail.violated_rules.join(", "))]
+    PreValidationFailed {
+        rule_version: Version,
+        detail: Box<ValidationDetail>,
+    },
+    
+    #[error("Pre-flight validation failed: {}", .errors.join("; "))]
+    PreFlightValidationFailed { errors: Vec<String> },
+    
+    #[error("Invalid transaction range {start}..{end} for a log of {len} transactions")]
+    InvalidRange { start: usize, end: usize, len: usize },
+    
+    #[error("No rule set registered for transaction type {type_tag} (transaction {transaction_id})")]
+    UnregisteredTransactionType { transaction_id: String, type_tag: String },
+    
+    #[error("Transaction limit of {limit} exceeded by transaction {attempted}; last state hash {last_state_hash}")]
+    TransactionLimitExceeded { limit: usize, attempted: usize, last_state_hash: StateHash },
+    
+    #[error("State after {transaction_id} is {size} bytes, over the limit of {limit} bytes")]
+    StateSizeLimitExceeded { transaction_id: String, size: u64, limit: usize },
+    
+    #[error("Audit bundle signing failed: {reason}")]
+    SigningFailed { reason: String },
+    
+    #[error("Transaction {transaction_id} is {drift_duration} behind the previous transaction, more than twice the drift tolerance {tolerance}")]
+    ExcessiveTimestampDrift {
+        transaction_id: String,
+        drift_duration: chrono::Duration,
+        tolerance: chrono::Duration,
+    },
+    
+    #[error("Post-condition {condition_name} failed; the transition was rolled back")]
+    PostConditionFailed { condition_name: String },
+    
+    #[error("Transaction {transaction_id} depends on {depends_on}, which does not precede it")]
+    UnsatisfiedDependency { transaction_id: String, depends_on: String },
+    
+    #[error("Estimated replay cost exceeds the budget: {resource} {estimated} > {limit}")]
+    CostBudgetExceeded { resource: String, estimated: u64, limit: u64 },
+    
+    #[error("Rule error: {0}")]
+    Rule(#[from] RuleError),
+    
+    #[error("Rule contract violated: {0}")]
+    ContractViolation(#[from] ContractViolationError),
+    
+    #[error("Compacted log replays to {compacted_hash} instead of the original final hash {original_hash}")]
+    CompactionMismatch { original_hash: StateHash, compacted_hash: StateHash },
+    
+    #[error("Trace log cannot be used: {reason}")]
+    InvalidTraceLog { reason: String },
+    
+    #[error("State hash {actual_hash} does not match the final hash {logged_hash} in the trace log")]
+    TraceLogHashMismatch { logged_hash: StateHash, actual_hash: StateHash },
+    
+    #[error("Result receiver disconnected before the result of {transaction_id} was sent")]
+    StreamClosed { transaction_id: String },
+    
     #[error("Processing failed with context: {message}")]
     WithContext {
         message: String,
-        context: ErrorContext,
+        context: Box<ErrorContext>,
     },
 }
 
 impl ProcessingError {
     /// Create a processing error with full context
     pub fn with_context(message: String, context: ErrorContext) -> Self {
-        Self::WithContext { message, context }
+        Self::WithContext { message, context: Box::new(context) }
     }
     
     /// Get the error context if available
@@ -170,9 +394,112 @@ impl ProcessingError {
             _ => None,
         }
     }
+    
+    /// Convert a pre-validation failure into the validation error it wraps
+    /// 
+    /// Returns the original error unchanged for every other variant.
+    pub fn into_validation_error(self) -> Result<ValidationError, Self> {
+        match self {
+            Self::PreValidationFailed { detail, .. } => Ok(ValidationError::WithDetails { details: detail }),
+            other => Err(other),
+        }
+    }
+    
+    /// Suggest how automated tooling could recover from this error
+    /// 
+    /// Returns `None` when the failure needs a person to look at it, such as
+    /// a signing or compaction error. Errors caused by the rule set itself
+    /// only get a hint from `recovery_hint_with_versions`, which knows the
+    /// versions there are to upgrade to.
+    pub fn recovery_hint(&self) -> Option<RecoveryHint> {
+        self.recovery_hint_with_versions(std::iter::empty())
+    }
+    
+    /// Suggest how automated tooling could recover from this error, upgrading to one of `registered`
+    /// 
+    /// Errors caused by the rule set suggest the earliest registered version
+    /// after the failing one, or the latest registered version when the
+    /// failing version is unknown. They get no hint when no registered
+    /// version qualifies. Pass `RuleSetRegistry::versions` as `registered`.
+    pub fn recovery_hint_with_versions<'a>(&self, registered: impl IntoIterator<Item = &'a Version>) -> Option<RecoveryHint> {
+        let registered: Vec<&Version> = registered.into_iter().collect();
+        match self {
+            Self::NonDeterministicOperation { .. } => upgrade_hint(None, &registered),
+            Self::TransactionFailed { reason, .. } => Some(RecoveryHint::FixTransaction {
+                suggestion: reason.clone(),
+            }),
+            Self::RuleApplicationFailed { rule_version, .. } => upgrade_hint(Some(rule_version), &registered),
+            Self::ExternalEntityNotFound { .. } => Some(RecoveryHint::RetryWithDelay(std::time::Duration::from_secs(1))),
+            Self::ExternalEntityTypeMismatch { entity_id, expected_type } => Some(RecoveryHint::FixTransaction {
+                suggestion: format!("reference an entity of type {} instead of {}", expected_type, entity_id),
+            }),
+            Self::OrderingViolation { entity_type, expected_order, .. } => Some(RecoveryHint::FixTransaction {
+                suggestion: format!("order {} as {:?}", entity_type, expected_order),
+            }),
+            Self::PreValidationFailed { detail, .. } => Some(RecoveryHint::FixTransaction {
+                suggestion: format!("satisfy {}", detail.violated_rules.join(", ")),
+            }),
+            Self::PreFlightValidationFailed { errors } => Some(RecoveryHint::FixTransaction {
+                suggestion: errors.join("; "),
+            }),
+            Self::UnregisteredTransactionType { transaction_id, type_tag } => Some(RecoveryHint::SkipAndContinue {
+                warning: format!("transaction {} has unhandled type {}", transaction_id, type_tag),
+            }),
+            Self::TransactionLimitExceeded { .. } | Self::CostBudgetExceeded { .. } => Some(RecoveryHint::CheckpointAndRetry),
+            Self::ExcessiveTimestampDrift { transaction_id, .. } => Some(RecoveryHint::FixTransaction {
+                suggestion: format!("correct the timestamp of transaction {}", transaction_id),
+            }),
+            Self::PostConditionFailed { condition_name } => Some(RecoveryHint::SkipAndContinue {
+                warning: format!("post-condition {} failed and the transition was rolled back", condition_name),
+            }),
+            Self::UnsatisfiedDependency { transaction_id, depends_on } => Some(RecoveryHint::FixTransaction {
+                suggestion: format!("process {} before {}", depends_on, transaction_id),
+            }),
+            Self::Rule(error) => error.recovery_hint(&registered),
+            Self::ContractViolation(violation) => match violation.condition_type {
+                ConditionType::Pre => Some(RecoveryHint::FixTransaction {
+                    suggestion: format!("satisfy {}", violation.description),
+                }),
+                // The rule set broke its own promise
+                ConditionType::Post => upgrade_hint(None, &registered),
+            },
+            Self::InvalidRange { .. }
+            | Self::StateSizeLimitExceeded { .. }
+            | Self::SigningFailed { .. }
+            | Self::CompactionMismatch { .. }
+            | Self::InvalidTraceLog { .. }
+            | Self::TraceLogHashMismatch { .. }
+            | Self::StreamClosed { .. }
+            | Self::WithContext { .. } => None,
+        }
+    }
+}
+
+/// Suggest upgrading from `failing` to a registered version, see `ProcessingError::recovery_hint_with_versions`
+fn upgrade_hint(failing: Option<&Version>, registered: &[&Version]) -> Option<RecoveryHint> {
+    let to_version = match failing {
+        Some(failing) => registered.iter().filter(|version| **version > failing).min(),
+        None => registered.iter().max(),
+    };
+    to_version.map(|to_version| RecoveryHint::UpgradeRuleSet { to_version: (*to_version).clone() })
 }
 
-#[derive(Debug, Error)]
+/// Structured suggestion for recovering from a `ProcessingError`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RecoveryHint {
+    /// The failure depends on data outside the replay; retry after the delay
+    RetryWithDelay(std::time::Duration),
+    /// The transaction can be dropped without invalidating the rest of the replay
+    SkipAndContinue { warning: String },
+    /// Save a checkpoint and resume from it in a fresh replay
+    CheckpointAndRetry,
+    /// The transaction itself is wrong and needs correcting
+    FixTransaction { suggestion: String },
+    /// The rule set is at fault; `to_version` is a registered version to replace it with
+    UpgradeRuleSet { to_version: Version },
+}
+
+#[derive(Debug, Clone, Error)]
 pub enum ValidationError {
     #[error("Invalid state: {reason}")]
     InvalidState { reason: String },
@@ -185,14 +512,26 @@ pub enum ValidationError {
     
     #[error("Validation failed with details")]
     WithDetails {
-        details: ValidationDetail,
+        details: Box<ValidationDetail>,
     },
+    
+    #[error("External fact {key} is defined in both contexts")]
+    DuplicateFact { key: String },
+    
+    #[error("External entity {entity_id} is registered in both contexts")]
+    DuplicateEntity { entity_id: String },
+    
+    #[error("Fact type name {type_name} is already registered for another type")]
+    DuplicateFactType { type_name: String },
+    
+    #[error("Invalid field {field_path}: {reason}")]
+    FieldValidation { field_path: String, reason: String },
 }
 
 impl ValidationError {
     /// Create a validation error with detailed information
     pub fn with_details(details: ValidationDetail) -> Self {
-        Self::WithDetails { details }
+        Self::WithDetails { details: Box::new(details) }
     }
     
     /// Get the validation details if available
@@ -202,9 +541,26 @@ impl ValidationError {
             _ => None,
         }
     }
+    
+    /// Create a validation error located at a dot-separated state field path
+    pub fn invalid_field(path: impl Into<String>, reason: impl Into<String>) -> Self {
+        Self::FieldValidation {
+            field_path: path.into(),
+            reason: reason.into(),
+        }
+    }
+    
+    /// Get the path of the field that failed validation, if known
+    pub fn field_path(&self) -> Option<&str> {
+        match self {
+            Self::FieldValidation { field_path, .. } => Some(field_path),
+            Self::WithDetails { details } => details.field.as_deref(),
+            _ => None,
+        }
+    }
 }
 
-#[derive(Debug, Error)]
+#[derive(Debug, Clone, Error)]
 pub enum StateError {
     #[error("State transition failed: {reason}")]
     TransitionFailed { reason: String },
@@ -217,14 +573,23 @@ pub enum StateError {
     
     #[error("State mismatch with detailed diff")]
     MismatchWithDetail {
-        detail: StateMismatchDetail,
+        detail: Box<StateMismatchDetail>,
     },
+    
+    #[error("State schema mismatch: checkpoint has version {checkpoint_version}, current version is {current_version}")]
+    SchemaMismatch { checkpoint_version: u32, current_version: u32 },
+    
+    #[error("Checkpoint integrity check failed: recorded hash {recorded_hash}, computed hash {computed_hash}")]
+    CheckpointIntegrityFailed { recorded_hash: StateHash, computed_hash: StateHash },
+    
+    #[error("State diffs conflict at field {field_path}")]
+    MergeConflict { field_path: String },
 }
 
 impl StateError {
     /// Create a state mismatch error with detailed diff information
     pub fn mismatch_with_detail(detail: StateMismatchDetail) -> Self {
-        Self::MismatchWithDetail { detail }
+        Self::MismatchWithDetail { detail: Box::new(detail) }
     }
     
     /// Get the mismatch details if available
@@ -236,7 +601,7 @@ impl StateError {
     }
 }
 
-#[derive(Debug, Error)]
+#[derive(Debug, Clone, Error)]
 pub enum RuleError {
     #[error("Rule not found: version {version}")]
     NotFound { version: Version },
@@ -246,9 +611,61 @@ pub enum RuleError {
     
     #[error("Rule registration failed: {reason}")]
     RegistrationFailed { reason: String },
+    
+    #[error("State invariant violated after rule {rule_version}: {reason}")]
+    InvariantViolated { rule_version: Version, reason: String },
+    
+    #[error("Rule guard {clause} failed: {reason}")]
+    GuardFailed { clause: String, reason: String },
+    
+    #[error("{error}")]
+    WithContext {
+        error: Box<RuleError>,
+        context: RuleErrorContext,
+    },
+}
+
+impl RuleError {
+    /// Suggest how automated tooling could recover from this error, upgrading to one of `registered`
+    fn recovery_hint(&self, registered: &[&Version]) -> Option<RecoveryHint> {
+        match self {
+            Self::InvariantViolated { rule_version, .. } => upgrade_hint(Some(rule_version), registered),
+            Self::GuardFailed { clause, reason } => Some(RecoveryHint::FixTransaction {
+                suggestion: format!("{}: {}", clause, reason),
+            }),
+            Self::WithContext { error, .. } => error.recovery_hint(registered),
+            Self::NotFound { .. } | Self::VersionConflict { .. } | Self::RegistrationFailed { .. } => None,
+        }
+    }
+    
+    /// Attach context to a rule error, replacing any context it already has
+    pub fn with_context(self, context: RuleErrorContext) -> Self {
+        let error = match self {
+            Self::WithContext { error, .. } => error,
+            other => Box::new(other),
+        };
+        Self::WithContext { error, context }
+    }
+    
+    /// Get the error context if available
+    pub fn context(&self) -> Option<&RuleErrorContext> {
+        match self {
+            Self::WithContext { context, .. } => Some(context),
+            _ => None,
+        }
+    }
+    
+    /// Get the clause named by a failed guard, looking through any context
+    pub fn failed_clause(&self) -> Option<&str> {
+        match self {
+            Self::GuardFailed { clause, .. } => Some(clause),
+            Self::WithContext { error, .. } => error.failed_clause(),
+            _ => None,
+        }
+    }
 }
 
-#[derive(Debug, Error)]
+#[derive(Debug, Clone, Error)]
 pub enum SerializationError {
     #[error("Serialization failed: {reason}")]
     SerializationFailed { reason: String },
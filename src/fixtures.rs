@@ -0,0 +1,271 @@
+//! Generation of self-contained Rust test modules from a replay
+
+use crate::context::ExecutionContext;
+use crate::error::SerializationError;
+use crate::logging::LogLevel;
+use crate::traits::{State, Transaction};
+use crate::types::{ReplayCostBudget, ReplayResult, Version};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::io::{self, Write};
+
+/// What `ReplayEngine::generate_test_fixtures_with_options` adds to a fixture besides the final hash
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FixtureGenerationOptions {
+    /// Assert the number of processed transactions and the order they were applied in
+    pub include_trace: bool,
+    /// Record the replay's performance metrics as constants, without asserting them
+    pub include_performance: bool,
+    /// How the fixture names the replay's types, derived from `std::any::type_name` if `None`
+    /// 
+    /// `type_name` output is not guaranteed to stay the same between compiler
+    /// versions, so set this for fixtures that are checked in.
+    pub type_paths: Option<FixtureTypePaths>,
+}
+
+/// Paths a fixture uses for the state, transaction and rule set types
+/// 
+/// Each path is written as given and must resolve where the fixture is
+/// compiled, such as `crate::BankingState` for a module of the defining crate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FixtureTypePaths {
+    pub state: &'static str,
+    pub transaction: &'static str,
+    pub rule_set: &'static str,
+}
+
+/// Parse a JSON value embedded in a generated fixture
+pub fn parse_fixture_json<V: DeserializeOwned>(json: &str) -> Result<V, SerializationError> {
+    serde_json::from_str(json).map_err(|e| SerializationError::DeserializationFailed {
+        reason: format!("Invalid fixture JSON: {}", e),
+    })
+}
+
+/// Inputs and expected outcome of the replay written as a fixture
+pub(crate) struct Fixture<'a, S, T> {
+    pub(crate) initial_state: &'a S,
+    pub(crate) transactions: &'a [T],
+    pub(crate) context: &'a ExecutionContext,
+    pub(crate) settings: FixtureEngineSettings,
+    pub(crate) rule_set_version: Version,
+    pub(crate) result: &'a ReplayResult<S>,
+}
+
+/// Replay engine settings the fixture rebuilds its engine with
+/// 
+/// Pre-flight validators are code rather than data, so they are left out.
+pub(crate) struct FixtureEngineSettings {
+    pub(crate) checkpoint_interval: Option<usize>,
+    pub(crate) max_state_size_bytes: Option<usize>,
+    pub(crate) max_transaction_count: Option<usize>,
+    pub(crate) deduplication_enabled: bool,
+    pub(crate) log_level: LogLevel,
+    pub(crate) dry_run_checkpoints: bool,
+    pub(crate) cost_budget: Option<ReplayCostBudget>,
+}
+
+impl FixtureEngineSettings {
+    /// Get the `ReplayEngineBuilder` calls for every setting that differs from the default
+    fn builder_calls(&self) -> Vec<String> {
+        let mut calls = Vec::new();
+        if let Some(interval) = self.checkpoint_interval {
+            calls.push(format!(".with_checkpoint_interval({})", interval));
+        }
+        if let Some(max_bytes) = self.max_state_size_bytes {
+            calls.push(format!(".with_max_state_size_bytes({})", max_bytes));
+        }
+        if let Some(limit) = self.max_transaction_count {
+            calls.push(format!(".with_max_transaction_count({})", limit));
+        }
+        if self.deduplication_enabled {
+            calls.push(".with_deduplication(true)".to_string());
+        }
+        if self.log_level != LogLevel::Info {
+            calls.push(format!(".with_log_level(LogLevel::{:?})", self.log_level));
+        }
+        if self.dry_run_checkpoints {
+            calls.push(".with_dry_run_checkpoints(true)".to_string());
+        }
+        if let Some(budget) = self.cost_budget {
+            calls.push(format!(".with_cost_budget({:?})", budget));
+        }
+        calls
+    }
+}
+
+impl<S: State, T: Transaction> Fixture<'_, S, T> {
+    /// Write the fixture as a Rust module with `R` as its rule set type
+    pub(crate) fn write<R, W: Write>(&self, writer: &mut W, options: FixtureGenerationOptions) -> io::Result<()> {
+        let type_paths = match options.type_paths {
+            Some(paths) => [paths.state.to_string(), paths.transaction.to_string(), paths.rule_set.to_string()],
+            None => {
+                let local_crate = crate_name::<S>();
+                [local_type_path::<S>(local_crate), local_type_path::<T>(local_crate), local_type_path::<R>(local_crate)]
+            }
+        };
+        let builder_calls = self.settings.builder_calls();
+        let mut imports = vec!["parse_fixture_json", "ExecutionContext"];
+        if self.settings.log_level != LogLevel::Info {
+            imports.push("LogLevel");
+        }
+        if self.settings.cost_budget.is_some() {
+            imports.push("ReplayCostBudget");
+        }
+        imports.extend(["ReplayEngine", "ReplayEngineBuilder", "ReproducibilityConfig", "State"]);
+        let initial_state = to_json(self.initial_state)?;
+        let transactions = to_json(&self.transactions)?;
+        let context = to_json(&self.context.to_reproducibility_config())?;
+        
+        writeln!(writer, "//! Replay fixture generated by `ReplayEngine::generate_test_fixtures`")?;
+        writeln!(writer, "//!")?;
+        writeln!(writer, "//! Rule set version {}. Regenerate the fixture instead of editing it.", self.rule_set_version)?;
+        writeln!(writer)?;
+        writeln!(writer, "use dtre::{{{}}};", imports.join(", "))?;
+        writeln!(writer)?;
+        writeln!(writer, "type FixtureState = {};", type_paths[0])?;
+        writeln!(writer, "type FixtureTransaction = {};", type_paths[1])?;
+        writeln!(writer, "type FixtureRuleSet = {};", type_paths[2])?;
+        writeln!(writer)?;
+        writeln!(writer, "const INITIAL_STATE_JSON: &str = {};", raw_string(&initial_state))?;
+        writeln!(writer)?;
+        writeln!(writer, "const TRANSACTIONS_JSON: &str = {};", raw_string(&transactions))?;
+        writeln!(writer)?;
+        writeln!(writer, "const CONTEXT_JSON: &str = {};", raw_string(&context))?;
+        writeln!(writer)?;
+        writeln!(writer, "pub const EXPECTED_FINAL_HASH: &str = \"{}\";", self.result.final_hash)?;
+        
+        let trace = &self.result.execution_trace;
+        if options.include_trace {
+            writeln!(writer)?;
+            writeln!(writer, "pub const EXPECTED_TRANSACTIONS_PROCESSED: usize = {};", trace.transactions_processed)?;
+            writeln!(writer)?;
+            writeln!(writer, "pub const EXPECTED_APPLICATION_ORDER: &[&str] = &[")?;
+            for application in &trace.rule_applications {
+                writeln!(writer, "    {:?},", application.transaction_id)?;
+            }
+            writeln!(writer, "];")?;
+        }
+        if options.include_performance {
+            let metrics = &self.result.performance_metrics;
+            writeln!(writer)?;
+            writeln!(writer, "// Measured when the fixture was generated; not asserted")?;
+            writeln!(writer, "#[allow(dead_code)]")?;
+            writeln!(writer, "pub const RECORDED_TOTAL_DURATION_MS: u64 = {};", metrics.total_duration_ms)?;
+            writeln!(writer, "#[allow(dead_code)]")?;
+            writeln!(writer, "pub const RECORDED_TRANSACTIONS_PER_SECOND: f64 = {:?};", metrics.transactions_per_second)?;
+        }
+        
+        write_lines(writer, FIXTURE_FUNCTIONS)?;
+        writeln!(writer)?;
+        writeln!(writer, "fn engine() -> ReplayEngine<FixtureState, FixtureTransaction, FixtureRuleSet> {{")?;
+        writeln!(writer, "    let config: ReproducibilityConfig = parse_fixture_json(CONTEXT_JSON).expect(\"fixture context\");")?;
+        writeln!(writer, "    ReplayEngineBuilder::new()")?;
+        writeln!(writer, "        .with_initial_state(initial_state())")?;
+        writeln!(writer, "        .with_rule_set(FixtureRuleSet::default())")?;
+        writeln!(writer, "        .with_context(ExecutionContext::from_reproducibility_config(config))")?;
+        for call in &builder_calls {
+            writeln!(writer, "        {}", call)?;
+        }
+        writeln!(writer, "        .build()")?;
+        writeln!(writer, "        .expect(\"fixture engine\")")?;
+        writeln!(writer, "}}")?;
+        write_lines(writer, FIXTURE_TESTS)?;
+        if options.include_trace {
+            write_lines(writer, FIXTURE_TRACE_TEST)?;
+        }
+        Ok(())
+    }
+}
+
+/// Write each line of `text` ending in `\n`, whatever line endings this source file has
+fn write_lines<W: Write>(writer: &mut W, text: &str) -> io::Result<()> {
+    for line in text.lines() {
+        writeln!(writer, "{}", line)?;
+    }
+    Ok(())
+}
+
+const FIXTURE_FUNCTIONS: &str = r#"
+fn initial_state() -> FixtureState {
+    parse_fixture_json(INITIAL_STATE_JSON).expect("fixture initial state")
+}
+
+fn transactions() -> Vec<FixtureTransaction> {
+    parse_fixture_json(TRANSACTIONS_JSON).expect("fixture transactions")
+}
+"#;
+
+const FIXTURE_TESTS: &str = r#"
+#[test]
+fn fixture_replay_is_deterministic() {
+    let engine = engine();
+    let first = engine.replay(&transactions()).unwrap();
+    let second = engine.replay(&transactions()).unwrap();
+    assert_eq!(first.final_hash, second.final_hash);
+    assert_eq!(first.execution_trace.state_transitions.len(), second.execution_trace.state_transitions.len());
+}
+
+#[test]
+fn fixture_states_are_valid() {
+    initial_state().validate().unwrap();
+    let result = engine().replay(&transactions()).unwrap();
+    result.final_state.validate().unwrap();
+}
+
+#[test]
+fn fixture_final_hash_matches() {
+    let result = engine().replay(&transactions()).unwrap();
+    assert_eq!(result.final_hash.to_string(), EXPECTED_FINAL_HASH);
+}
+"#;
+
+const FIXTURE_TRACE_TEST: &str = r#"
+#[test]
+fn fixture_trace_matches() {
+    let trace = engine().replay(&transactions()).unwrap().execution_trace;
+    assert_eq!(trace.transactions_processed, EXPECTED_TRANSACTIONS_PROCESSED);
+    let order: Vec<&str> = trace.rule_applications.iter().map(|a| a.transaction_id.as_str()).collect();
+    assert_eq!(order, EXPECTED_APPLICATION_ORDER);
+}
+"#;
+
+/// Pretty-print `value` with object keys sorted, so maps give the same fixture every time
+fn to_json<V: Serialize + ?Sized>(value: &V) -> io::Result<String> {
+    serde_json::to_value(value)
+        .and_then(|value| serde_json::to_string_pretty(&value))
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// Quote `contents` as a raw string literal with enough `#`s to hold it
+fn raw_string(contents: &str) -> String {
+    let mut hashes = String::from("#");
+    while contents.contains(&format!("\"{}", hashes)) {
+        hashes.push('#');
+    }
+    format!("r{hashes}\"{contents}\"{hashes}")
+}
+
+/// Name of the crate defining `V`
+fn crate_name<V: ?Sized>() -> &'static str {
+    let name = std::any::type_name::<V>();
+    name.split("::").next().unwrap_or(name)
+}
+
+/// Path of `V` from inside `local_crate`, which the fixture is expected to be a module of
+fn local_type_path<V: ?Sized>(local_crate: &str) -> String {
+    let name = std::any::type_name::<V>();
+    let prefix = format!("{}::", local_crate);
+    let mut path = String::with_capacity(name.len());
+    let mut index = 0;
+    while let Some(c) = name[index..].chars().next() {
+        let starts_path = !name[..index].ends_with(|c: char| c.is_alphanumeric() || c == '_' || c == ':');
+        if starts_path && name[index..].starts_with(&prefix) {
+            path.push_str("crate::");
+            index += prefix.len();
+        } else {
+            path.push(c);
+            index += c.len_utf8();
+        }
+    }
+    path
+}
@@ -1,8 +1,40 @@
 //! Cryptographic state hashing using Blake3
 
-use crate::traits::State;
-use crate::types::StateHash;
+use crate::context::ExecutionContext;
+use crate::error::{ProcessingError, SerializationError};
+use crate::state_manager::StateManager;
+use crate::traits::{RuleSet, State, Transaction};
+use crate::types::{ExecutionTrace, StateHash, StateTransition};
 use blake3::Hasher as Blake3Hasher;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Domain prefix of a Merkle leaf
+const MERKLE_LEAF_PREFIX: u8 = 0x00;
+/// Domain prefix of an inner Merkle node
+const MERKLE_NODE_PREFIX: u8 = 0x01;
+
+/// Hash a state as it is, without normalizing it
+fn hash_in_place<S: State>(state: &S) -> StateHash {
+    dtre_core::hash_state(state, S::iteration_order()).expect("State serialization should never fail")
+}
+
+/// Hash a leaf of a Merkle tree
+fn merkle_leaf(hash: &StateHash) -> StateHash {
+    let mut hasher = Blake3Hasher::new();
+    hasher.update(&[MERKLE_LEAF_PREFIX]);
+    hasher.update(&hash.0);
+    StateHash(*hasher.finalize().as_bytes())
+}
+
+/// Hash two child nodes of a Merkle tree into their parent
+fn merkle_node(left: &StateHash, right: &StateHash) -> StateHash {
+    let mut hasher = Blake3Hasher::new();
+    hasher.update(&[MERKLE_NODE_PREFIX]);
+    hasher.update(&left.0);
+    hasher.update(&right.0);
+    StateHash(*hasher.finalize().as_bytes())
+}
 
 /// StateHasher provides cryptographic hashing for state objects
 /// 
@@ -27,17 +59,39 @@ impl StateHasher {
     /// # Returns
     /// A StateHash containing the 32-byte Blake3 hash
     /// 
+    /// States whose `iteration_order` is `Sorted` are hashed through their canonical
+    /// JSON form, so map insertion order does not affect the result. When
+    /// `State::NORMALIZES` is true, a copy of the state is passed
+    /// through `State::normalize` first; use `hash_normalized` to hash a state
+    /// that has already been normalized without copying it. Other states are
+    /// hashed without being copied.
+    /// 
     /// # Panics
-    /// Panics if state serialization fails (which should never happen for valid State implementations)
+    /// Panics if state serialization fails (which should never happen for valid State implementations).
+    /// In debug builds, also panics if `normalize` changes the hash of a state
+    /// whose `NORMALIZES` is false.
     pub fn hash<S: State>(&self, state: &S) -> StateHash {
-        let serialized = bincode::serialize(state)
-            .expect("State serialization should never fail");
-        
-        let mut hasher = Blake3Hasher::new();
-        hasher.update(&serialized);
-        let hash = hasher.finalize();
+        if S::NORMALIZES {
+            return self.hash_normalized(&NormalizedState::new(state.clone()));
+        }
         
-        StateHash(*hash.as_bytes())
+        let hash = hash_in_place(state);
+        debug_assert!(
+            hash == self.hash_normalized(&NormalizedState::new(state.clone())),
+            "{} overrides State::normalize without setting State::NORMALIZES",
+            std::any::type_name::<S>()
+        );
+        hash
+    }
+    
+    /// Compute the cryptographic hash of a normalized state
+    /// 
+    /// Produces the same hash as `hash` on the original state.
+    /// 
+    /// # Panics
+    /// Panics if state serialization fails (which should never happen for valid State implementations)
+    pub fn hash_normalized<S: State>(&self, state: &NormalizedState<S>) -> StateHash {
+        hash_in_place(state.state())
     }
     
     /// Compute a hash chain from a sequence of state hashes
@@ -61,6 +115,37 @@ impl StateHasher {
         StateHash(*hash.as_bytes())
     }
     
+    /// Compute the Merkle root of a sequence of state hashes
+    /// 
+    /// Each leaf is hashed with Blake3 behind a `0x00` prefix, then nodes are
+    /// paired left to right and each pair is hashed behind a `0x01` prefix, so
+    /// a leaf can never be passed off as an inner node. An unpaired last node
+    /// is carried up to the next level unchanged.
+    /// 
+    /// # Arguments
+    /// * `hashes` - The leaf hashes, in order
+    /// 
+    /// # Returns
+    /// The root hash, or the Blake3 hash of no input when empty
+    pub fn merkle_root(&self, hashes: &[StateHash]) -> StateHash {
+        if hashes.is_empty() {
+            return StateHash(*blake3::hash(&[]).as_bytes());
+        }
+        
+        let mut level: Vec<StateHash> = hashes.iter().map(merkle_leaf).collect();
+        while level.len() > 1 {
+            level = level
+                .chunks(2)
+                .map(|pair| match pair {
+                    [left, right] => merkle_node(left, right),
+                    [single] => *single,
+                    _ => unreachable!("chunks(2) yields one or two hashes"),
+                })
+                .collect();
+        }
+        level[0]
+    }
+    
     /// Compute an incremental hash chain by extending an existing chain
     /// 
     /// This allows efficient incremental hashing without re-hashing the entire chain.
@@ -79,6 +164,163 @@ impl StateHasher {
         let hash = hasher.finalize();
         StateHash(*hash.as_bytes())
     }
+    
+    /// Compute the canonical hash of a canonical JSON string
+    /// 
+    /// External tools can reproduce this hash by computing the SHA-256 digest
+    /// of the UTF-8 bytes of the same string.
+    /// 
+    /// # Arguments
+    /// * `json` - Canonical JSON, as produced by `State::canonical_json`
+    /// 
+    /// # Returns
+    /// A StateHash containing the 32-byte SHA-256 digest
+    pub fn hash_canonical_json(&self, json: &str) -> StateHash {
+        StateHash::from_canonical_json_bytes(json.as_bytes())
+    }
+    
+    /// Compute the canonical hash of a state
+    /// 
+    /// # Errors
+    /// Returns a serialization error if the state cannot be represented as JSON
+    pub fn hash_canonical<S: State>(&self, state: &S) -> Result<StateHash, SerializationError> {
+        Ok(self.hash_canonical_json(&state.canonical_json()?))
+    }
+    
+    /// Verify that a state transition was produced by applying a transaction
+    /// 
+    /// Re-applies `transaction` to `transition.from_state` and checks that both the
+    /// recorded `from_hash` and `to_hash` match the recomputed hashes.
+    /// 
+    /// # Returns
+    /// `Ok(true)` if the transition is reproducible, `Ok(false)` if a hash differs
+    /// 
+    /// # Errors
+    /// Returns the processing error if the transaction cannot be re-applied
+    pub fn verify_transition<S, T, R>(
+        &self,
+        transition: &StateTransition<S>,
+        transaction: &T,
+        rule_set: &R,
+        context: &ExecutionContext,
+    ) -> Result<bool, ProcessingError>
+    where
+        S: State,
+        T: Transaction,
+        R: RuleSet<S, T>,
+    {
+        if transition.transaction_id != transaction.id()
+            || self.hash(&transition.from_state) != transition.from_hash
+        {
+            return Ok(false);
+        }
+        
+        let mut manager = reapplication_manager(transition.from_state.clone())?;
+        let replayed = manager.apply_transaction(transaction, rule_set, context)?;
+        
+        Ok(replayed.to_hash == transition.to_hash)
+    }
+    
+    /// Verify every transition in an execution trace by replaying the transactions
+    /// 
+    /// Replays `transactions` from `initial_state` and compares each recomputed
+    /// transition against the recorded one. Replay continues from the recomputed
+    /// state after a mismatch, so each tampered entry is reported individually.
+    /// 
+    /// # Errors
+    /// Returns the processing error if a transaction cannot be re-applied
+    pub fn verify_trace<S, T, R>(
+        &self,
+        trace: &ExecutionTrace,
+        transactions: &[T],
+        rule_set: &R,
+        context: &ExecutionContext,
+        initial_state: &S,
+    ) -> Result<TraceVerificationReport, ProcessingError>
+    where
+        S: State,
+        T: Transaction,
+        R: RuleSet<S, T>,
+    {
+        let mut manager = reapplication_manager(initial_state.clone())?;
+        let mut report = TraceVerificationReport {
+            verified_transitions: 0,
+            failures: Vec::new(),
+        };
+        
+        if trace.state_transitions.len() != transactions.len() {
+            report.failures.push(TransitionVerificationFailure {
+                transition_index: trace.state_transitions.len().min(transactions.len()),
+                transaction_id: None,
+                reason: format!(
+                    "trace records {} transitions but {} transactions were supplied",
+                    trace.state_transitions.len(),
+                    transactions.len()
+                ),
+            });
+        }
+        
+        for (index, (recorded, transaction)) in trace.state_transitions.iter().zip(transactions).enumerate() {
+            let replayed = manager.apply_transaction(transaction, rule_set, context)?;
+            
+            let reason = if recorded.transaction_id != replayed.transaction_id {
+                Some(format!(
+                    "recorded transaction {} but replayed {}",
+                    recorded.transaction_id, replayed.transaction_id
+                ))
+            } else if recorded.from_hash != replayed.from_hash {
+                Some(format!("from_hash {} does not match recomputed {}", recorded.from_hash, replayed.from_hash))
+            } else if recorded.to_hash != replayed.to_hash {
+                Some(format!("to_hash {} does not match recomputed {}", recorded.to_hash, replayed.to_hash))
+            } else {
+                None
+            };
+            
+            match reason {
+                Some(reason) => report.failures.push(TransitionVerificationFailure {
+                    transition_index: index,
+                    transaction_id: Some(recorded.transaction_id.clone()),
+                    reason,
+                }),
+                None => report.verified_transitions += 1,
+            }
+        }
+        
+        Ok(report)
+    }
+}
+
+/// Create a state manager for re-applying recorded transactions
+fn reapplication_manager<S: State>(state: S) -> Result<StateManager<S>, ProcessingError> {
+    StateManager::new(state).map_err(|e| ProcessingError::TransactionFailed {
+        transaction_id: "verification".to_string(),
+        reason: format!("Failed to initialize state manager for verification: {}", e),
+    })
+}
+
+/// Result of verifying an execution trace against its transactions
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TraceVerificationReport {
+    /// Number of transitions whose hashes were reproduced exactly
+    pub verified_transitions: usize,
+    /// Transitions that could not be reproduced
+    pub failures: Vec<TransitionVerificationFailure>,
+}
+
+impl TraceVerificationReport {
+    /// Check whether every transition in the trace was verified
+    pub fn is_valid(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+/// A recorded transition that does not match its recomputed counterpart
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TransitionVerificationFailure {
+    pub transition_index: usize,
+    /// ID recorded in the trace, if the trace has an entry at this index
+    pub transaction_id: Option<String>,
+    pub reason: String,
 }
 
 impl Default for StateHasher {
@@ -87,6 +329,363 @@ impl Default for StateHasher {
     }
 }
 
+/// Hashes of each top-level field of a state's JSON form
+/// 
+/// Kept up to date by a `TransactionProcessor` with subtree hashing enabled.
+/// After each transaction only the fields named by the rule set's
+/// `RuleSet::affects_fields` are rehashed, so unchanged subtrees keep their
+/// hash and comparing two snapshots field by field stays cheap. Each hash is
+/// the canonical hash (see `StateHash::from_canonical_json_bytes`) of the
+/// field's value. The state hash itself always covers the whole state.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SubtreeHashes {
+    hashes: std::collections::BTreeMap<String, StateHash>,
+    last_recomputed: Vec<String>,
+}
+
+impl SubtreeHashes {
+    /// Hash every top-level field of a state
+    /// 
+    /// A state whose JSON form is not an object has no fields.
+    pub fn new<S: State>(state: &S) -> Self {
+        let mut subtrees = Self::default();
+        subtrees.update(state, None);
+        subtrees
+    }
+    
+    /// Rehash the top-level fields touched by `affected_fields`, or all fields for `None`
+    /// 
+    /// A path starting with `*` touches every field. Only the touched fields
+    /// are serialized, unless the state is not a struct or map with plain keys.
+    pub fn update<S: State>(&mut self, state: &S, affected_fields: Option<&[String]>) {
+        let touches_all = affected_fields.is_none_or(|paths| paths.iter().any(|path| path.split('.').next() == Some("*")));
+        
+        let (fields, recomputed) = if touches_all {
+            let fields = top_level_fields(state, None);
+            let mut recomputed: Vec<String> = self.hashes.keys().chain(fields.keys()).cloned().collect();
+            recomputed.sort();
+            recomputed.dedup();
+            (fields, recomputed)
+        } else {
+            let mut recomputed: Vec<String> = affected_fields
+                .into_iter()
+                .flatten()
+                .filter_map(|path| path.split('.').next())
+                .map(str::to_string)
+                .collect();
+            recomputed.sort();
+            recomputed.dedup();
+            (top_level_fields(state, Some(&recomputed)), recomputed)
+        };
+        
+        for field in &recomputed {
+            match fields.get(field) {
+                Some(value) => {
+                    let json = crate::serialization::to_canonical_json(value)
+                        .expect("JSON values always serialize");
+                    self.hashes.insert(field.clone(), StateHash::from_canonical_json_bytes(json.as_bytes()));
+                }
+                None => {
+                    self.hashes.remove(field);
+                }
+            }
+        }
+        self.last_recomputed = recomputed;
+    }
+    
+    /// Get the hash of a top-level field
+    pub fn get(&self, field: &str) -> Option<&StateHash> {
+        self.hashes.get(field)
+    }
+    
+    /// Get the top-level fields rehashed by the most recent update, in name order
+    pub fn last_recomputed(&self) -> &[String] {
+        &self.last_recomputed
+    }
+}
+
+/// Get the JSON form of a state's top-level fields, limited to the sorted `wanted` names
+/// 
+/// A state whose JSON form is not an object has no fields.
+fn top_level_fields<S: Serialize>(
+    state: &S,
+    wanted: Option<&[String]>,
+) -> serde_json::Map<String, serde_json::Value> {
+    match state.serialize(FieldCollector { wanted }) {
+        Ok(fields) => fields,
+        // Shapes the collector does not handle are serialized whole
+        Err(_) => match serde_json::to_value(state) {
+            Ok(serde_json::Value::Object(fields)) => fields,
+            _ => serde_json::Map::new(),
+        },
+    }
+}
+
+/// Serializer that converts only the wanted top-level fields of a struct or map to JSON
+/// 
+/// Fails for every other shape, so the caller can fall back to `serde_json::to_value`.
+struct FieldCollector<'a> {
+    wanted: Option<&'a [String]>,
+}
+
+impl FieldCollector<'_> {
+    fn wants(&self, field: &str) -> bool {
+        self.wanted
+            .is_none_or(|wanted| wanted.binary_search_by(|name| name.as_str().cmp(field)).is_ok())
+    }
+}
+
+/// Map in progress in a `FieldCollector`
+struct CollectedFields<'a> {
+    collector: FieldCollector<'a>,
+    fields: serde_json::Map<String, serde_json::Value>,
+    /// Key of the entry whose value comes next, if it is wanted
+    pending_key: Option<String>,
+}
+
+type Unsupported = serde::ser::Impossible<serde_json::Map<String, serde_json::Value>, serde_json::Error>;
+
+fn unsupported<T>() -> Result<T, serde_json::Error> {
+    Err(serde::ser::Error::custom("not a struct or map"))
+}
+
+impl<'a> serde::Serializer for FieldCollector<'a> {
+    type Ok = serde_json::Map<String, serde_json::Value>;
+    type Error = serde_json::Error;
+    type SerializeSeq = Unsupported;
+    type SerializeTuple = Unsupported;
+    type SerializeTupleStruct = Unsupported;
+    type SerializeTupleVariant = Unsupported;
+    type SerializeMap = CollectedFields<'a>;
+    type SerializeStruct = CollectedFields<'a>;
+    type SerializeStructVariant = Unsupported;
+    
+    fn serialize_bool(self, _: bool) -> Result<Self::Ok, Self::Error> {
+        unsupported()
+    }
+    
+    fn serialize_i8(self, _: i8) -> Result<Self::Ok, Self::Error> {
+        unsupported()
+    }
+    
+    fn serialize_i16(self, _: i16) -> Result<Self::Ok, Self::Error> {
+        unsupported()
+    }
+    
+    fn serialize_i32(self, _: i32) -> Result<Self::Ok, Self::Error> {
+        unsupported()
+    }
+    
+    fn serialize_i64(self, _: i64) -> Result<Self::Ok, Self::Error> {
+        unsupported()
+    }
+    
+    fn serialize_u8(self, _: u8) -> Result<Self::Ok, Self::Error> {
+        unsupported()
+    }
+    
+    fn serialize_u16(self, _: u16) -> Result<Self::Ok, Self::Error> {
+        unsupported()
+    }
+    
+    fn serialize_u32(self, _: u32) -> Result<Self::Ok, Self::Error> {
+        unsupported()
+    }
+    
+    fn serialize_u64(self, _: u64) -> Result<Self::Ok, Self::Error> {
+        unsupported()
+    }
+    
+    fn serialize_f32(self, _: f32) -> Result<Self::Ok, Self::Error> {
+        unsupported()
+    }
+    
+    fn serialize_f64(self, _: f64) -> Result<Self::Ok, Self::Error> {
+        unsupported()
+    }
+    
+    fn serialize_char(self, _: char) -> Result<Self::Ok, Self::Error> {
+        unsupported()
+    }
+    
+    fn serialize_str(self, _: &str) -> Result<Self::Ok, Self::Error> {
+        unsupported()
+    }
+    
+    fn serialize_bytes(self, _: &[u8]) -> Result<Self::Ok, Self::Error> {
+        unsupported()
+    }
+    
+    fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
+        unsupported()
+    }
+    
+    fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
+        unsupported()
+    }
+    
+    fn serialize_unit_struct(self, _: &'static str) -> Result<Self::Ok, Self::Error> {
+        unsupported()
+    }
+    
+    fn serialize_some<V: ?Sized + Serialize>(self, value: &V) -> Result<Self::Ok, Self::Error> {
+        value.serialize(self)
+    }
+    
+    fn serialize_unit_variant(self, _: &'static str, _: u32, _: &'static str) -> Result<Self::Ok, Self::Error> {
+        unsupported()
+    }
+    
+    fn serialize_newtype_struct<V: ?Sized + Serialize>(self, _: &'static str, value: &V) -> Result<Self::Ok, Self::Error> {
+        value.serialize(self)
+    }
+    
+    fn serialize_newtype_variant<V: ?Sized + Serialize>(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: &V,
+    ) -> Result<Self::Ok, Self::Error> {
+        unsupported()
+    }
+    
+    fn serialize_seq(self, _: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
+        unsupported()
+    }
+    
+    fn serialize_tuple(self, _: usize) -> Result<Self::SerializeTuple, Self::Error> {
+        unsupported()
+    }
+    
+    fn serialize_tuple_struct(self, _: &'static str, _: usize) -> Result<Self::SerializeTupleStruct, Self::Error> {
+        unsupported()
+    }
+    
+    fn serialize_tuple_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeTupleVariant, Self::Error> {
+        unsupported()
+    }
+    
+    fn serialize_map(self, _: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+        Ok(CollectedFields { collector: self, fields: serde_json::Map::new(), pending_key: None })
+    }
+    
+    fn serialize_struct(self, _: &'static str, _: usize) -> Result<Self::SerializeStruct, Self::Error> {
+        Ok(CollectedFields { collector: self, fields: serde_json::Map::new(), pending_key: None })
+    }
+    
+    fn serialize_struct_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeStructVariant, Self::Error> {
+        unsupported()
+    }
+}
+
+impl serde::ser::SerializeMap for CollectedFields<'_> {
+    type Ok = serde_json::Map<String, serde_json::Value>;
+    type Error = serde_json::Error;
+    
+    fn serialize_key<K: ?Sized + Serialize>(&mut self, key: &K) -> Result<(), Self::Error> {
+        // Keys become strings the way serde_json writes them
+        let key = match serde_json::to_value(key)? {
+            serde_json::Value::String(key) => key,
+            serde_json::Value::Number(key) => key.to_string(),
+            serde_json::Value::Bool(key) => key.to_string(),
+            _ => return unsupported(),
+        };
+        self.pending_key = self.collector.wants(&key).then_some(key);
+        Ok(())
+    }
+    
+    fn serialize_value<V: ?Sized + Serialize>(&mut self, value: &V) -> Result<(), Self::Error> {
+        if let Some(key) = self.pending_key.take() {
+            self.fields.insert(key, serde_json::to_value(value)?);
+        }
+        Ok(())
+    }
+    
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        Ok(self.fields)
+    }
+}
+
+impl serde::ser::SerializeStruct for CollectedFields<'_> {
+    type Ok = serde_json::Map<String, serde_json::Value>;
+    type Error = serde_json::Error;
+    
+    fn serialize_field<V: ?Sized + Serialize>(&mut self, key: &'static str, value: &V) -> Result<(), Self::Error> {
+        if self.collector.wants(key) {
+            self.fields.insert(key.to_string(), serde_json::to_value(value)?);
+        }
+        Ok(())
+    }
+    
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        Ok(self.fields)
+    }
+}
+
+/// A state that has been passed through `State::normalize`
+/// 
+/// The only way to build one is `NormalizedState::new`, so a `NormalizedState`
+/// can be hashed with `StateHasher::hash_normalized` without re-normalizing it
+/// and without the risk of hashing the raw state. It derefs to the state for
+/// reading.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NormalizedState<S>(S);
+
+impl<S: State> NormalizedState<S> {
+    /// Normalize a state
+    pub fn new(mut state: S) -> Self {
+        state.normalize();
+        Self(state)
+    }
+    
+    /// Get the normalized state
+    pub fn state(&self) -> &S {
+        &self.0
+    }
+    
+    /// Unwrap the normalized state
+    pub fn into_inner(self) -> S {
+        self.0
+    }
+}
+
+impl<S> std::ops::Deref for NormalizedState<S> {
+    type Target = S;
+    
+    fn deref(&self) -> &S {
+        &self.0
+    }
+}
+
+/// Iterate over a map's entries in ascending key order
+/// 
+/// Used by `#[derive(DeterministicHash)]`, and by hand-written `Hash` implementations,
+/// to hash `HashMap` fields independently of insertion order.
+/// 
+/// # Arguments
+/// * `map` - The map to iterate over
+/// 
+/// # Returns
+/// An iterator over the map's entries, sorted by key
+pub fn iter_sorted<K: Ord, V>(map: &HashMap<K, V>) -> impl Iterator<Item = (&K, &V)> {
+    let mut entries: Vec<(&K, &V)> = map.iter().collect();
+    entries.sort_by(|a, b| a.0.cmp(b.0));
+    entries.into_iter()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -212,4 +811,74 @@ mod tests {
         assert_eq!(chain3.0.len(), 32);
         assert_eq!(full_chain.0.len(), 32);
     }
+    
+    #[test]
+    fn test_merkle_root() {
+        let hasher = StateHasher::new();
+        let leaves: Vec<StateHash> = (0..5).map(|value| hasher.hash(&TestState { value })).collect();
+        
+        assert_eq!(hasher.merkle_root(&leaves[..1]), merkle_leaf(&leaves[0]));
+        assert_eq!(
+            hasher.merkle_root(&leaves[..2]),
+            merkle_node(&merkle_leaf(&leaves[0]), &merkle_leaf(&leaves[1]))
+        );
+        
+        // An odd last node is carried up unchanged
+        let left = merkle_node(&merkle_leaf(&leaves[0]), &merkle_leaf(&leaves[1]));
+        let right = merkle_node(&merkle_leaf(&leaves[2]), &merkle_leaf(&leaves[3]));
+        let expected = merkle_node(&merkle_node(&left, &right), &merkle_leaf(&leaves[4]));
+        assert_eq!(hasher.merkle_root(&leaves), expected);
+        
+        // Leaves and nodes are hashed in separate domains
+        assert_ne!(merkle_leaf(&leaves[0]), leaves[0]);
+        assert_ne!(hasher.merkle_root(&[left, right]), hasher.merkle_root(&leaves[..4]));
+        assert_ne!(hasher.merkle_root(&leaves[..2]), hasher.extend_chain(&leaves[0], &leaves[1]));
+        
+        let mut reordered = leaves.clone();
+        reordered.swap(0, 1);
+        assert_ne!(hasher.merkle_root(&reordered), hasher.merkle_root(&leaves));
+        assert_eq!(hasher.merkle_root(&[]), hasher.merkle_root(&[]));
+    }
+    
+    thread_local! {
+        static TRACKED_SERIALIZATIONS: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+    }
+    
+    /// Value that counts how often it is serialized
+    #[derive(Debug, Clone, Deserialize, Hash)]
+    struct Tracked(i64);
+    
+    impl Serialize for Tracked {
+        fn serialize<Z: serde::Serializer>(&self, serializer: Z) -> Result<Z::Ok, Z::Error> {
+            TRACKED_SERIALIZATIONS.with(|count| count.set(count.get() + 1));
+            self.0.serialize(serializer)
+        }
+    }
+    
+    #[derive(Debug, Clone, Serialize, Deserialize, Hash)]
+    struct SplitState {
+        touched: i64,
+        tracked: Tracked,
+    }
+    
+    impl State for SplitState {
+        fn validate(&self) -> Result<(), ValidationError> {
+            Ok(())
+        }
+    }
+    
+    #[test]
+    fn test_subtree_update_serializes_only_affected_fields() {
+        let mut state = SplitState { touched: 1, tracked: Tracked(5) };
+        let mut subtrees = SubtreeHashes::new(&state);
+        let tracked_hash = subtrees.get("tracked").copied();
+        
+        state.touched = 2;
+        let before = TRACKED_SERIALIZATIONS.with(|count| count.get());
+        subtrees.update(&state, Some(&["touched".to_string()]));
+        assert_eq!(TRACKED_SERIALIZATIONS.with(|count| count.get()), before);
+        
+        assert_eq!(subtrees, SubtreeHashes { last_recomputed: vec!["touched".to_string()], ..SubtreeHashes::new(&state) });
+        assert_eq!(subtrees.get("tracked").copied(), tracked_hash);
+    }
 }
@@ -0,0 +1,28 @@
+//! Normalization of transaction IDs from systems with inconsistent formatting
+
+use serde::{Deserialize, Serialize};
+
+/// How `TransactionProcessor` rewrites transaction IDs before comparing and recording them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TransactionIdNormalizer {
+    /// Convert the ID to lowercase
+    Lowercase,
+    /// Convert the ID to uppercase
+    Uppercase,
+    /// Remove leading and trailing whitespace
+    Trim,
+    /// Remove leading and trailing whitespace, then convert to lowercase
+    TrimLowercase,
+}
+
+impl TransactionIdNormalizer {
+    /// Get the normalized form of an ID
+    pub fn normalize(&self, id: &str) -> String {
+        match self {
+            TransactionIdNormalizer::Lowercase => id.to_lowercase(),
+            TransactionIdNormalizer::Uppercase => id.to_uppercase(),
+            TransactionIdNormalizer::Trim => id.trim().to_string(),
+            TransactionIdNormalizer::TrimLowercase => id.trim().to_lowercase(),
+        }
+    }
+}
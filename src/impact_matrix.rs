@@ -0,0 +1,208 @@
+//! Pairwise impact analysis across several rule set versions
+
+use crate::types::{ImpactAnalysis, ReplayResult, StateDifference, StateHash, Version};
+
+/// Outcome of one transaction in a replay that skips rejected transactions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct TransactionOutcome {
+    /// State hash after the transaction, unchanged from before if it was rejected
+    pub(crate) hash: StateHash,
+    pub(crate) accepted: bool,
+}
+
+/// Replay of a transaction sequence under one rule set version
+#[derive(Debug, Clone)]
+pub(crate) struct VersionReplay<S> {
+    pub(crate) version: Version,
+    pub(crate) result: ReplayResult<S>,
+    pub(crate) outcomes: Vec<TransactionOutcome>,
+}
+
+/// Total breaking changes, differences and steps along an upgrade path
+type PathCost = (usize, usize, usize);
+
+/// Grid of impact analyses comparing a baseline and `N` alternative rule sets
+/// 
+/// Index 0 is the baseline rule set and index `k` is the `k`-th alternative;
+/// cell `(i, j)` compares version `i` (as the baseline) against version `j`.
+/// A transaction accepted by one version and rejected by another counts as a
+/// breaking change; any other difference in the resulting state is reported
+/// only as a difference.
+#[derive(Debug, Clone)]
+pub struct ImpactMatrix<S, const N: usize> {
+    versions: Vec<Version>,
+    cells: Vec<ImpactAnalysis<S>>,
+    breaking_changes: Vec<usize>,
+}
+
+impl<S: Clone + PartialEq, const N: usize> ImpactMatrix<S, N> {
+    /// Compare every pair of replays of the same transactions
+    pub(crate) fn from_replays(transaction_ids: &[String], replays: Vec<VersionReplay<S>>) -> Self {
+        let size = replays.len();
+        let mut cells = Vec::with_capacity(size * size);
+        let mut breaking_changes = Vec::with_capacity(size * size);
+        
+        for baseline in &replays {
+            for comparison in &replays {
+                let mut differences = Vec::new();
+                let mut breaking = 0;
+                
+                for (index, (before, after)) in baseline.outcomes.iter().zip(&comparison.outcomes).enumerate() {
+                    if before == after {
+                        continue;
+                    }
+                    let description = if before.accepted != after.accepted {
+                        breaking += 1;
+                        let (accepted_by, rejected_by) = if before.accepted {
+                            (&baseline.version, &comparison.version)
+                        } else {
+                            (&comparison.version, &baseline.version)
+                        };
+                        format!(
+                            "Transaction {} (index {}) is accepted by {} but rejected by {}",
+                            transaction_ids[index], index, accepted_by, rejected_by
+                        )
+                    } else {
+                        format!(
+                            "State diverged after transaction {} (index {})",
+                            transaction_ids[index], index
+                        )
+                    };
+                    differences.push(StateDifference {
+                        transaction_id: transaction_ids[index].clone(),
+                        transaction_index: index,
+                        baseline_hash: before.hash,
+                        comparison_hash: after.hash,
+                        description,
+                    });
+                }
+                
+                cells.push(ImpactAnalysis {
+                    baseline_version: baseline.version.clone(),
+                    comparison_version: comparison.version.clone(),
+                    identical_final_state: baseline.result.final_state == comparison.result.final_state,
+                    identical_final_hash: baseline.result.final_hash == comparison.result.final_hash,
+                    baseline_result: baseline.result.clone(),
+                    comparison_result: comparison.result.clone(),
+                    differences,
+                });
+                breaking_changes.push(breaking);
+            }
+        }
+        
+        Self {
+            versions: replays.into_iter().map(|replay| replay.version).collect(),
+            cells,
+            breaking_changes,
+        }
+    }
+}
+
+impl<S, const N: usize> ImpactMatrix<S, N> {
+    /// Number of rows and columns, i.e. `N + 1`
+    pub fn size(&self) -> usize {
+        self.versions.len()
+    }
+    
+    /// Versions in matrix order, starting with the baseline
+    pub fn versions(&self) -> &[Version] {
+        &self.versions
+    }
+    
+    /// Get the impact analysis of moving from version `i` to version `j`
+    /// 
+    /// # Panics
+    /// Panics if `i` or `j` is not less than `size()`
+    pub fn pairwise_comparison(&self, i: usize, j: usize) -> &ImpactAnalysis<S> {
+        &self.cells[self.cell_index(i, j)]
+    }
+    
+    /// Number of transactions accepted by exactly one of versions `i` and `j`
+    /// 
+    /// # Panics
+    /// Panics if `i` or `j` is not less than `size()`
+    pub fn breaking_changes(&self, i: usize, j: usize) -> usize {
+        self.breaking_changes[self.cell_index(i, j)]
+    }
+    
+    /// Find the sequence of upgrades from the baseline to the newest version with the fewest breaking changes
+    /// 
+    /// Each step moves to a strictly newer version. Paths are compared by their
+    /// total breaking changes, then by their total differences, then by their
+    /// number of steps. The returned path starts with the baseline version and
+    /// ends with the newest version; it is just the baseline when no version
+    /// is newer.
+    pub fn safest_upgrade_path(&self) -> Vec<Version> {
+        let size = self.size();
+        let mut order: Vec<usize> = (0..size).filter(|&k| k == 0 || self.versions[k] > self.versions[0]).collect();
+        order.sort_by(|&a, &b| self.versions[a].cmp(&self.versions[b]));
+        
+        // Cheapest known cost and predecessor of each index
+        let mut best: Vec<Option<(PathCost, Option<usize>)>> = vec![None; size];
+        best[0] = Some(((0, 0, 0), None));
+        for (position, &from) in order.iter().enumerate() {
+            let Some(((breaking, differences, steps), _)) = best[from] else {
+                continue;
+            };
+            for &to in &order[position + 1..] {
+                if self.versions[to] <= self.versions[from] {
+                    continue;
+                }
+                let cost = (
+                    breaking + self.breaking_changes(from, to),
+                    differences + self.pairwise_comparison(from, to).differences.len(),
+                    steps + 1,
+                );
+                if best[to].is_none_or(|(current, _)| cost < current) {
+                    best[to] = Some((cost, Some(from)));
+                }
+            }
+        }
+        
+        let mut path = Vec::new();
+        let mut current = order.last().copied();
+        while let Some(index) = current {
+            path.push(self.versions[index].clone());
+            current = best[index].and_then(|(_, previous)| previous);
+        }
+        path.reverse();
+        path
+    }
+    
+    /// Render the matrix as a Markdown table with baseline versions as rows
+    /// 
+    /// Each cell shows the number of differing and of breaking transactions,
+    /// or `identical` when the two versions produce the same results.
+    pub fn to_markdown_table(&self) -> String {
+        let mut table = String::from("| from \\ to |");
+        for version in &self.versions {
+            table.push_str(&format!(" {} |", version));
+        }
+        table.push_str("\n|---|");
+        table.push_str(&"---|".repeat(self.size()));
+        table.push('\n');
+        
+        for (i, version) in self.versions.iter().enumerate() {
+            table.push_str(&format!("| {} |", version));
+            for j in 0..self.size() {
+                let analysis = self.pairwise_comparison(i, j);
+                if analysis.is_safe_migration() {
+                    table.push_str(" identical |");
+                } else {
+                    table.push_str(&format!(
+                        " {} differences, {} breaking |",
+                        analysis.differences.len(),
+                        self.breaking_changes(i, j)
+                    ));
+                }
+            }
+            table.push('\n');
+        }
+        table
+    }
+    
+    fn cell_index(&self, i: usize, j: usize) -> usize {
+        assert!(i < self.size() && j < self.size(), "impact matrix index ({}, {}) out of range", i, j);
+        i * self.size() + j
+    }
+}
@@ -0,0 +1,75 @@
+//! Reconciliation of replayed states with external ledgers
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+
+/// An external system of record, such as a core banking system, holding balances by account
+pub trait ExternalLedger<S> {
+    /// Get the balances the external system reports, by account ID
+    fn balances(&self) -> HashMap<String, i64>;
+    
+    /// Extract the balances a replayed state holds, by the same account IDs
+    fn extract_balances(&self, state: &S) -> HashMap<String, i64>;
+}
+
+/// An account whose replayed balance disagrees with the external ledger
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LedgerDiscrepancy {
+    pub account_id: String,
+    /// Balance in the replayed state, or 0 if the state has no such account
+    pub dtre_value: i64,
+    /// Balance in the external ledger, or 0 if the ledger has no such account
+    pub ledger_value: i64,
+    /// `dtre_value` minus `ledger_value`
+    pub difference: i64,
+}
+
+/// Outcome of `ReplayEngine::verify_against_external_ledger`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LedgerVerificationResult {
+    /// True when every account balance matches
+    pub matches: bool,
+    /// Accounts whose balances differ, in account ID order
+    pub discrepancies: Vec<LedgerDiscrepancy>,
+}
+
+impl LedgerVerificationResult {
+    /// Compare the balances of a replayed state with an external ledger's
+    /// 
+    /// Accounts known to only one side are compared against a balance of 0.
+    pub(crate) fn compare(dtre_balances: &HashMap<String, i64>, ledger_balances: &HashMap<String, i64>) -> Self {
+        let accounts: BTreeSet<&String> = dtre_balances.keys().chain(ledger_balances.keys()).collect();
+        let discrepancies: Vec<LedgerDiscrepancy> = accounts
+            .into_iter()
+            .filter_map(|account_id| {
+                let dtre_value = dtre_balances.get(account_id).copied().unwrap_or(0);
+                let ledger_value = ledger_balances.get(account_id).copied().unwrap_or(0);
+                (dtre_value != ledger_value).then(|| LedgerDiscrepancy {
+                    account_id: account_id.clone(),
+                    dtre_value,
+                    ledger_value,
+                    difference: dtre_value.saturating_sub(ledger_value),
+                })
+            })
+            .collect();
+        
+        Self {
+            matches: discrepancies.is_empty(),
+            discrepancies,
+        }
+    }
+    
+    /// Get the sum of the absolute differences of all accounts
+    /// 
+    /// Differences in opposite directions do not cancel out.
+    pub fn total_discrepancy(&self) -> i64 {
+        self.discrepancies
+            .iter()
+            .fold(0i64, |total, d| total.saturating_add(d.difference.saturating_abs()))
+    }
+    
+    /// Check whether the total discrepancy is at most `epsilon`
+    pub fn is_within_tolerance(&self, epsilon: i64) -> bool {
+        self.total_discrepancy() <= epsilon
+    }
+}
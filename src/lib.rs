@@ -2,40 +2,122 @@
 //!
 //! A library for deterministic execution of financial transactions through pure functional programming.
 
+pub mod adapters;
+pub mod aggregate;
+pub mod anomaly;
+pub mod audit;
+#[cfg(feature = "debug-audit")]
+pub mod audit_log;
+pub mod checkpoint_migration;
+pub mod compaction;
+pub mod config;
 pub mod context;
+pub mod dependency;
+pub mod dispatch;
 pub mod error;
+pub mod fixtures;
 pub mod hasher;
+pub mod id_normalizer;
+pub mod impact_matrix;
+pub mod ledger;
 pub mod logging;
+#[cfg(feature = "profiling")]
+pub mod profiling;
+pub mod rate_limit;
 pub mod replay_engine;
+pub mod reproducibility;
+pub mod rule_audit;
+pub mod rule_cache;
+pub mod rule_contract;
 pub mod result_comparison;
 pub mod rule_set;
+#[cfg(feature = "signing")]
+pub mod security;
+pub mod sequence_splitter;
+pub mod sequence_validator;
 pub mod serialization;
+pub mod shadow;
+pub mod side_effects;
+pub mod snapshot;
 pub mod state_manager;
+pub mod statistics;
+#[cfg(feature = "test-utils")]
+pub mod testing;
+pub mod timeline;
 pub mod traits;
+pub mod transaction_dependency;
 pub mod transaction_processor;
 pub mod types;
+pub mod validation_registry;
+#[cfg(feature = "wasm")]
+pub mod wasm_api;
 
 // Re-export core types and traits
+pub use adapters::{EventSourcingAdapter, TimestampedEvent};
+pub use aggregate::StateAggregator;
+pub use anomaly::{AnomalyDetectionConfig, AnomalySeverity, AnomalyType, ReplayAnomaly};
+pub use audit::{AuditBundle, AuditBundleConfig, SigningAlgorithm};
+#[cfg(feature = "debug-audit")]
+pub use audit_log::{AuditLog, AuditLogEntry, AuditOperation};
+pub use checkpoint_migration::{CheckpointMigrator, MigrationReport, RawCheckpoint};
+pub use compaction::{CompactedLog, TransactionLog};
+pub use config::ReplayConfig;
 pub use context::{
-    ExecutionContext, DeterministicTime, SeededRandom, ExternalFacts, ExternalFact, 
-    ExternalEntityResolver, ExternalEntity, OrderingRules, NonDeterminismGuard, Operation
+    ExecutionContext, DeterministicTime, ManualClock, SeededRandom, ExternalFacts, ExternalFact, 
+    ExternalEntityResolver, ExternalEntity, BatchResolveResult, OrderingRules, NonDeterminismGuard, NonDeterministicAttempt, Operation,
+    ContextFingerprint, ReproducibilityConfig, ExecutionPhase, ExternalFactsSnapshot, FactSnapshotEntry,
+    ConflictResolution, MergeOptions, DebugMode
 };
+pub use dependency::{DependencyAnalyzer, RuleDependency, RuleFieldAccess};
+pub use dispatch::{AnyTransaction, TransactionDispatcher};
+pub use dtre_derive::DeterministicHash;
 pub use error::{
     DTREError, ProcessingError, ValidationError, StateError, RuleError, SerializationError,
-    ErrorContext, StateMismatchDetail, FieldDiff, ValidationDetail
+    ErrorContext, StateMismatchDetail, FieldDiff, ValidationDetail, RuleErrorContext, RecoveryHint,
+    ConditionType, ContractViolationError
 };
-pub use hasher::StateHasher;
+pub use fixtures::{parse_fixture_json, FixtureGenerationOptions, FixtureTypePaths};
+pub use dtre_core::{hash_bytes, hash_state, to_bincode_bytes, EncodeError};
+pub use hasher::{iter_sorted, NormalizedState, StateHasher, SubtreeHashes, TraceVerificationReport, TransitionVerificationFailure};
+pub use id_normalizer::TransactionIdNormalizer;
+pub use impact_matrix::ImpactMatrix;
+pub use ledger::{ExternalLedger, LedgerDiscrepancy, LedgerVerificationResult};
 pub use logging::{
-    DeterministicLogger, LogEntry, LogLevel, ExecutionTraceLog, TraceEvent, TraceEventType
+    DeterministicLogger, LogEntry, LogLevel, ExecutionTraceLog, TraceEvent, TraceEventType, SimulatedTransaction
 };
-pub use replay_engine::{ReplayEngine, ReplayEngineBuilder};
+#[cfg(feature = "profiling")]
+pub use profiling::{ProfilingReport, SlowApplication, PROFILED_PERCENTILES};
+pub use rate_limit::TokenBucket;
+pub use replay_engine::{RecoveryAction, ReplayEngine, ReplayEngineBuilder, ReplayItem};
+pub use reproducibility::{BundleSchemaVersions, ReproducibilityBundle};
 pub use result_comparison::{
     ResultComparator, ResultComparison, TransitionDifference, PerformanceComparison,
-    FieldComparison, BalanceDifference, DiffAnalyzer
+    FieldComparison, BalanceDifference, DiffAnalyzer, ComparisonTolerance, RegressionReport,
+    ReplayResultComparator, FunctionalEquivalenceResult
 };
-pub use rule_set::{VersionedRuleSet, RuleSetRegistry, RuleSetMetadata};
-pub use serialization::{StateSerializer, BincodeSerializer, JsonSerializer, SerializationContext};
-pub use state_manager::{StateManager, Checkpoint, StateDiff};
+pub use rule_audit::{InstrumentedRuleSet, RuleAuditRecorder};
+pub use rule_cache::{LruCache, RuleApplicationCache};
+pub use rule_contract::{RuleCondition, ConditionCheck, ContractDocumentation};
+pub use rule_set::{VersionedRuleSet, RuleSetRegistry, RuleSetMetadata, HotReloadableRuleSet, SequentialRuleSet, UpgradePathValidation, HopResult, MigrationContract, ContractViolation, ContractVerificationResult};
+#[cfg(feature = "signing")]
+pub use security::{HmacKey, SignedReplayResult};
+pub use sequence_splitter::{IndependentPartition, PostWatermark, PreWatermark, TransactionSequenceSplitter};
+pub use sequence_validator::{TransactionSequenceValidator, ValidationReport};
+pub use shadow::{ShadowDiscrepancy, ShadowReplayEngine};
+pub use serialization::{StateSerializer, BincodeSerializer, JsonSerializer, SerializationContext, to_canonical_json};
+pub use side_effects::{SideEffect, SideEffectQueue, SideEffectResult};
+pub use snapshot::{VersionedSnapshot, SNAPSHOT_SCHEMA_VERSION};
+pub use state_manager::{StateManager, Checkpoint, CheckpointPurgePolicy, StateDiff, StatePatch, StateChangeEvent, MergeStrategy, FieldMerger, StateHistory, HistoryEntry, PhaseTimings};
+pub use statistics::{ProcessingStatistics, VersionStatistics};
+pub use timeline::{Timeline, TimelineEvent, TimelineEventType};
 pub use traits::{State, Transaction, RuleSet};
-pub use transaction_processor::TransactionProcessor;
-pub use types::{Version, StateHash, ReplayResult, ExecutionTrace, StateTransition, CheckpointInfo, ImpactAnalysis, StateDifference, PerformanceMetrics};
+pub use transaction_dependency::TransactionDependencyGraph;
+pub use transaction_processor::{TransactionProcessor, ExplanationTrace, ProcessorSnapshot, ForkedProcessor, TransactionResult};
+pub use types::{
+    Version, VersionConstraint, StateHash, ReplayResult, ExecutionTrace, StateTransition, CheckpointInfo, ImpactAnalysis,
+    StateDifference, PerformanceMetrics, OverheadBreakdown, PerformanceHistory, DurationEstimate, ReconciliationReport, ReconciliationMismatch, WatermarkTracker,
+    CausalityRecord, IterationStrategy, CheckpointValidationReport, FieldChange, ChangeKind,
+    AuditRecord, StateMutationRecord, PaginatedResult, ReplayCostEstimate, ReplayCostBudget,
+    IncrementalResult, MigrationDryRunResult, StateInvariant, InvariantCheck, InvariantSeverity, InvariantViolation
+};
+pub use validation_registry::{ValidationRegistry, ValidationSummary};
@@ -4,10 +4,24 @@
 //! All logging operations are side-effect free from the perspective of the
 //! deterministic execution.
 
-use serde::{Serialize, Deserialize};
+use serde::{Serialize, Deserialize, de::DeserializeOwned};
 use chrono::{DateTime, Utc};
+use std::borrow::Cow;
+use std::path::Path;
+use crate::context::ExecutionContext;
+use crate::error::{SerializationError, ValidationError};
+use crate::traits::Transaction;
 use crate::types::{Version, StateHash};
 
+/// Metadata key holding the random seed of the context an entry was logged under
+pub const RANDOM_SEED_METADATA_KEY: &str = "random_seed";
+
+/// Prefix of metadata keys holding external facts, e.g. `fact.exchange_rate`
+pub const FACT_METADATA_PREFIX: &str = "fact.";
+
+/// Event data key holding the JSON of the transaction a `TransactionStarted` event belongs to
+pub const TRANSACTION_DATA_KEY: &str = "transaction";
+
 /// Log level for deterministic logging
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum LogLevel {
@@ -83,6 +97,30 @@ impl LogEntry {
         self.metadata.push((key, value));
         self
     }
+    
+    /// Rebuild the execution context the entry was logged under
+    /// 
+    /// The context's time is the entry's timestamp and its seed comes from the
+    /// `random_seed` metadata; metadata keys starting with `fact.` become
+    /// external facts of type `String` under the rest of the key. Returns
+    /// `None` if the seed is missing or not a `u64`.
+    pub fn to_simulated_context(&self) -> Option<ExecutionContext> {
+        let seed = self.metadata
+            .iter()
+            .find(|(key, _)| key == RANDOM_SEED_METADATA_KEY)
+            .and_then(|(_, value)| value.parse::<u64>().ok())?;
+        
+        let builder = ExecutionContext::builder()
+            .with_time(self.timestamp)
+            .with_random_seed(seed);
+        let builder = self.metadata.iter().fold(builder, |builder, (key, value)| {
+            match key.strip_prefix(FACT_METADATA_PREFIX) {
+                Some(fact) => builder.with_external_fact(fact.to_string(), value.clone()),
+                None => builder,
+            }
+        });
+        Some(builder.build())
+    }
 }
 
 /// Deterministic logger that collects log entries without side effects
@@ -183,6 +221,11 @@ impl DeterministicLogger {
             .filter(|e| e.transaction_id.as_deref() == Some(transaction_id))
             .collect()
     }
+    
+    /// Pass every event of a captured trace to `handler`, in recorded order
+    pub fn replay_log(log: &ExecutionTraceLog, handler: impl Fn(&TraceEvent)) {
+        log.events.iter().for_each(handler);
+    }
 }
 
 impl Default for DeterministicLogger {
@@ -289,6 +332,207 @@ impl ExecutionTraceLog {
             .filter(|e| e.transaction_id.as_deref() == Some(transaction_id))
             .collect()
     }
+    
+    /// Record a processed transaction as a `TransactionStarted` and a `TransactionCompleted` event
+    /// 
+    /// The started event stores the transaction's JSON under
+    /// `TRANSACTION_DATA_KEY`, so `transactions` can restore it.
+    pub fn record_transaction<T: Transaction + Serialize>(
+        &mut self,
+        index: usize,
+        transaction: &T,
+        from_hash: StateHash,
+        to_hash: StateHash,
+    ) -> Result<(), SerializationError> {
+        let json = serde_json::to_string(transaction).map_err(|e| SerializationError::SerializationFailed {
+            reason: format!("Failed to serialize transaction {}: {}", transaction.id(), e),
+        })?;
+        let event = |event_type, data| TraceEvent {
+            timestamp: transaction.timestamp(),
+            event_type,
+            transaction_id: Some(transaction.id().to_string()),
+            transaction_index: Some(index),
+            state_hash_before: Some(from_hash),
+            state_hash_after: None,
+            data,
+        };
+        self.add_event(event(TraceEventType::TransactionStarted, vec![(TRANSACTION_DATA_KEY.to_string(), json)]));
+        self.add_event(TraceEvent {
+            state_hash_after: Some(to_hash),
+            ..event(TraceEventType::TransactionCompleted, Vec::new())
+        });
+        Ok(())
+    }
+    
+    /// Restore the transactions stored by `record_transaction`, in recorded order
+    /// 
+    /// # Errors
+    /// Fails if a `TransactionStarted` event has no transaction JSON or it does
+    /// not deserialize into `T`.
+    pub fn transactions<T: DeserializeOwned>(&self) -> Result<Vec<T>, SerializationError> {
+        self.events_by_type(TraceEventType::TransactionStarted)
+            .into_iter()
+            .map(|event| {
+                let json = event.data
+                    .iter()
+                    .find(|(key, _)| key == TRANSACTION_DATA_KEY)
+                    .map(|(_, value)| value)
+                    .ok_or_else(|| SerializationError::DeserializationFailed {
+                        reason: format!("Event for transaction {:?} has no transaction data", event.transaction_id),
+                    })?;
+                serde_json::from_str(json).map_err(|e| SerializationError::DeserializationFailed {
+                    reason: format!("Failed to deserialize transaction {:?}: {}", event.transaction_id, e),
+                })
+            })
+            .collect()
+    }
+    
+    /// Get the last state hash recorded after an event
+    pub fn final_hash(&self) -> Option<StateHash> {
+        self.events.iter().rev().find_map(|event| event.state_hash_after)
+    }
+    
+    /// Serialize the trace as newline-delimited JSON
+    /// 
+    /// The first line holds the start and end time; each log entry and event
+    /// follows on its own line, logs first.
+    pub fn to_ndjson(&self) -> Result<String, SerializationError> {
+        let header = TraceLogLine::Header {
+            start_time: self.start_time,
+            end_time: self.end_time,
+        };
+        let lines = std::iter::once(header)
+            .chain(self.logs.iter().map(|entry| TraceLogLine::Log(Cow::Borrowed(entry))))
+            .chain(self.events.iter().map(|event| TraceLogLine::Event(Cow::Borrowed(event))));
+        
+        let mut ndjson = String::new();
+        for line in lines {
+            let json = serde_json::to_string(&line).map_err(|e| SerializationError::SerializationFailed {
+                reason: format!("Failed to serialize trace log line: {}", e),
+            })?;
+            ndjson.push_str(&json);
+            ndjson.push('\n');
+        }
+        Ok(ndjson)
+    }
+    
+    /// Parse a trace produced by `to_ndjson`
+    pub fn from_ndjson(ndjson: &str) -> Result<Self, SerializationError> {
+        let mut lines = ndjson.lines().filter(|line| !line.trim().is_empty()).enumerate().map(|(number, line)| {
+            serde_json::from_str::<TraceLogLine>(line).map_err(|e| SerializationError::DeserializationFailed {
+                reason: format!("Invalid trace log line {}: {}", number + 1, e),
+            })
+        });
+        
+        let mut log = match lines.next().transpose()? {
+            Some(TraceLogLine::Header { start_time, end_time }) => Self {
+                start_time,
+                end_time,
+                logs: Vec::new(),
+                events: Vec::new(),
+            },
+            _ => {
+                return Err(SerializationError::DeserializationFailed {
+                    reason: "Trace log does not start with a header line".to_string(),
+                })
+            }
+        };
+        for line in lines {
+            match line? {
+                TraceLogLine::Log(entry) => log.add_log(entry.into_owned()),
+                TraceLogLine::Event(event) => log.add_event(event.into_owned()),
+                TraceLogLine::Header { .. } => {
+                    return Err(SerializationError::DeserializationFailed {
+                        reason: "Trace log has more than one header line".to_string(),
+                    })
+                }
+            }
+        }
+        Ok(log)
+    }
+    
+    /// Write the trace to a file as newline-delimited JSON
+    pub fn save_ndjson(&self, path: &Path) -> Result<(), SerializationError> {
+        std::fs::write(path, self.to_ndjson()?).map_err(|e| SerializationError::SerializationFailed {
+            reason: format!("Failed to write {}: {}", path.display(), e),
+        })
+    }
+    
+    /// Read a trace written by `save_ndjson`
+    pub fn load_ndjson(path: &Path) -> Result<Self, SerializationError> {
+        let ndjson = std::fs::read_to_string(path).map_err(|e| SerializationError::DeserializationFailed {
+            reason: format!("Failed to read {}: {}", path.display(), e),
+        })?;
+        Self::from_ndjson(&ndjson)
+    }
+    
+    /// Turn the captured transactions back into replayable input
+    /// 
+    /// Every `TransactionStarted` event with a transaction ID becomes one
+    /// transaction carrying the event's timestamp and data, in recorded order.
+    /// The capture must put whatever the rule set needs into that event's data.
+    pub fn to_simulated_transactions(&self) -> Vec<SimulatedTransaction> {
+        self.events_by_type(TraceEventType::TransactionStarted)
+            .into_iter()
+            .filter_map(|event| {
+                Some(SimulatedTransaction {
+                    id: event.transaction_id.clone()?,
+                    timestamp: event.timestamp,
+                    transaction_index: event.transaction_index,
+                    data: event.data.clone(),
+                })
+            })
+            .collect()
+    }
+}
+
+/// One line of an `ExecutionTraceLog` in newline-delimited JSON
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum TraceLogLine<'a> {
+    Header {
+        start_time: DateTime<Utc>,
+        end_time: Option<DateTime<Utc>>,
+    },
+    Log(Cow<'a, LogEntry>),
+    Event(Cow<'a, TraceEvent>),
+}
+
+/// A transaction rebuilt from a `TransactionStarted` trace event
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SimulatedTransaction {
+    pub id: String,
+    pub timestamp: DateTime<Utc>,
+    /// Index of the transaction in the captured run
+    pub transaction_index: Option<usize>,
+    /// Event data, as recorded
+    pub data: Vec<(String, String)>,
+}
+
+impl SimulatedTransaction {
+    /// Get the first data value recorded under `key`
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.data.iter().find(|(k, _)| k == key).map(|(_, value)| value.as_str())
+    }
+}
+
+impl Transaction for SimulatedTransaction {
+    fn id(&self) -> &str {
+        &self.id
+    }
+    
+    fn timestamp(&self) -> DateTime<Utc> {
+        self.timestamp
+    }
+    
+    fn validate(&self) -> Result<(), ValidationError> {
+        if self.id.is_empty() {
+            return Err(ValidationError::InvalidTransaction {
+                reason: "Simulated transaction has an empty ID".to_string(),
+            });
+        }
+        Ok(())
+    }
 }
 
 #[cfg(test)]
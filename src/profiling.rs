@@ -0,0 +1,85 @@
+//! Wall-clock profiling of rule applications during a replay
+
+use crate::traits::Transaction;
+use crate::types::{StateHash, Version};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Percentiles reported in `ProfilingReport::percentile_durations`
+pub const PROFILED_PERCENTILES: [u8; 4] = [50, 90, 95, 99];
+
+/// One rule application measured by `ReplayEngine::profile`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SlowApplication<T> {
+    pub transaction_id: String,
+    pub rule_version: Version,
+    pub duration_us: u64,
+    /// Hash of the state the rule set was applied to
+    pub state_hash_before: StateHash,
+    /// The transaction itself, for reproducing the application in isolation
+    pub transaction: T,
+}
+
+/// Timings of a profiled replay
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProfilingReport<T> {
+    /// Wall-clock duration of the whole replay
+    pub total_duration_ms: u64,
+    /// The slowest successful applications, slowest first; equal durations keep replay order
+    pub slowest_applications: Vec<SlowApplication<T>>,
+    /// Application durations in microseconds at each of `PROFILED_PERCENTILES`, empty if nothing was applied
+    pub percentile_durations: HashMap<u8, u64>,
+}
+
+impl<T: Transaction> ProfilingReport<T> {
+    /// Build a report from every successful application, keeping the `top_n` slowest
+    pub(crate) fn new(total_duration_ms: u64, mut applications: Vec<SlowApplication<T>>, top_n: usize) -> Self {
+        let mut durations: Vec<u64> = applications.iter().map(|a| a.duration_us).collect();
+        durations.sort_unstable();
+        // Nearest-rank percentiles
+        let percentile_durations = if durations.is_empty() {
+            HashMap::new()
+        } else {
+            PROFILED_PERCENTILES
+                .iter()
+                .map(|&p| {
+                    let rank = (usize::from(p) * durations.len()).div_ceil(100).max(1);
+                    (p, durations[rank - 1])
+                })
+                .collect()
+        };
+        
+        applications.sort_by_key(|a| std::cmp::Reverse(a.duration_us));
+        applications.truncate(top_n);
+        Self {
+            total_duration_ms,
+            slowest_applications: applications,
+            percentile_durations,
+        }
+    }
+    
+    /// Render the slowest applications in the collapsed stack format of Brendan Gregg's FlameGraph
+    /// 
+    /// Each application becomes a `replay;rule_set_<version>;<transaction id>`
+    /// stack weighted by its duration in microseconds. Only the applications
+    /// in `slowest_applications` are included, so profile with a `top_n` of
+    /// at least the transaction count to cover the whole run. Semicolons and
+    /// whitespace in transaction IDs are replaced with underscores.
+    pub fn to_flamegraph_data(&self) -> String {
+        self.slowest_applications
+            .iter()
+            .map(|application| {
+                let transaction_id: String = application.transaction_id
+                    .chars()
+                    .map(|c| if c == ';' || c.is_whitespace() { '_' } else { c })
+                    .collect();
+                format!(
+                    "replay;rule_set_{};{} {}\n",
+                    application.rule_version,
+                    transaction_id,
+                    application.duration_us
+                )
+            })
+            .collect()
+    }
+}
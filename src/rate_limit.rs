@@ -0,0 +1,153 @@
+//! Throughput limiting for transaction processing
+//!
+//! Rate limiting only affects *when* transactions are processed, never the
+//! resulting states, so it does not interfere with determinism. The clock and
+//! sleep functions are injected so that tests and simulated replays can run
+//! against a virtual time source, which makes token consumption reproducible.
+
+use std::fmt;
+use std::time::Duration;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+#[cfg(target_arch = "wasm32")]
+use web_time::Instant;
+
+type Clock = Box<dyn Fn() -> Instant + Send + Sync>;
+type Sleep = Box<dyn Fn(Duration) + Send + Sync>;
+
+/// Token bucket limiting processing to a maximum number of transactions per second
+/// 
+/// The bucket holds at most one token and starts full, so no bursts above the
+/// configured rate are possible: consecutive transactions are spaced at least
+/// `1 / max_tps` seconds apart.
+pub struct TokenBucket {
+    max_tps: f64,
+    tokens: f64,
+    last_refill: Instant,
+    clock: Clock,
+    sleep: Sleep,
+}
+
+impl TokenBucket {
+    /// Maximum number of tokens the bucket can hold
+    const CAPACITY: f64 = 1.0;
+    
+    /// Create a token bucket that blocks the current thread while waiting
+    /// 
+    /// # Panics
+    /// Panics if `max_tps` is not a positive, finite number
+    pub fn new(max_tps: f64, clock: impl Fn() -> Instant + Send + Sync + 'static) -> Self {
+        Self::with_sleep(max_tps, clock, std::thread::sleep)
+    }
+    
+    /// Create a token bucket with a custom sleep function
+    /// 
+    /// With a simulated clock, `sleep` should advance that clock by the given
+    /// duration.
+    /// 
+    /// # Panics
+    /// Panics if `max_tps` is not a positive, finite number
+    pub fn with_sleep(
+        max_tps: f64,
+        clock: impl Fn() -> Instant + Send + Sync + 'static,
+        sleep: impl Fn(Duration) + Send + Sync + 'static,
+    ) -> Self {
+        assert!(max_tps.is_finite() && max_tps > 0.0, "max_tps must be positive and finite");
+        
+        let last_refill = clock();
+        Self {
+            max_tps,
+            tokens: Self::CAPACITY,
+            last_refill,
+            clock: Box::new(clock),
+            sleep: Box::new(sleep),
+        }
+    }
+    
+    /// Get the configured maximum transactions per second
+    pub fn max_tps(&self) -> f64 {
+        self.max_tps
+    }
+    
+    /// Get the number of tokens available as of the last refill
+    pub fn tokens(&self) -> f64 {
+        self.tokens
+    }
+    
+    /// Take one token, sleeping until one is available
+    pub fn acquire(&mut self) {
+        loop {
+            self.refill();
+            if self.tokens >= 1.0 {
+                self.tokens -= 1.0;
+                return;
+            }
+            
+            let wait = Duration::from_secs_f64((1.0 - self.tokens) / self.max_tps);
+            (self.sleep)(wait);
+        }
+    }
+    
+    fn refill(&mut self) {
+        let now = (self.clock)();
+        let elapsed = now.saturating_duration_since(self.last_refill);
+        self.tokens = (self.tokens + elapsed.as_secs_f64() * self.max_tps).min(Self::CAPACITY);
+        self.last_refill = now;
+    }
+}
+
+impl fmt::Debug for TokenBucket {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TokenBucket")
+            .field("max_tps", &self.max_tps)
+            .field("tokens", &self.tokens)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    
+    fn simulated_bucket(max_tps: f64) -> (TokenBucket, Arc<Mutex<Duration>>) {
+        let base = Instant::now();
+        let elapsed = Arc::new(Mutex::new(Duration::ZERO));
+        let clock_elapsed = Arc::clone(&elapsed);
+        let sleep_elapsed = Arc::clone(&elapsed);
+        
+        let bucket = TokenBucket::with_sleep(
+            max_tps,
+            move || base + *clock_elapsed.lock().unwrap(),
+            move |wait| *sleep_elapsed.lock().unwrap() += wait,
+        );
+        (bucket, elapsed)
+    }
+    
+    #[test]
+    fn test_first_token_is_immediate() {
+        let (mut bucket, elapsed) = simulated_bucket(10.0);
+        
+        bucket.acquire();
+        assert_eq!(*elapsed.lock().unwrap(), Duration::ZERO);
+        assert_eq!(bucket.tokens(), 0.0);
+    }
+    
+    #[test]
+    fn test_tokens_are_spaced_by_rate() {
+        let (mut bucket, elapsed) = simulated_bucket(4.0);
+        
+        for _ in 0..5 {
+            bucket.acquire();
+        }
+        
+        let seconds = elapsed.lock().unwrap().as_secs_f64();
+        assert!((seconds - 1.0).abs() < 1e-6, "elapsed {}", seconds);
+    }
+    
+    #[test]
+    #[should_panic(expected = "max_tps must be positive")]
+    fn test_rejects_non_positive_rate() {
+        TokenBucket::new(0.0, Instant::now);
+    }
+}
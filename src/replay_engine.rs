@@ -1,14 +1,68 @@
 //! Core replay engine with builder pattern for deterministic transaction replay
 
-use crate::context::ExecutionContext;
-use crate::error::ProcessingError;
+use crate::audit::{AuditBundle, AuditBundleConfig};
+use crate::config::ReplayConfig;
+use crate::context::{DebugMode, ExecutionContext, OrderingRules};
+use crate::error::{ProcessingError, SerializationError, StateError};
+use crate::fixtures::{Fixture, FixtureEngineSettings, FixtureGenerationOptions};
+use crate::hasher::StateHasher;
+use crate::impact_matrix::{ImpactMatrix, TransactionOutcome, VersionReplay};
+use crate::ledger::{ExternalLedger, LedgerVerificationResult};
+use crate::logging::LogLevel;
+#[cfg(feature = "profiling")]
+use crate::profiling::{ProfilingReport, SlowApplication};
+use crate::reproducibility::ReproducibilityBundle;
+use crate::result_comparison::{ComparisonTolerance, PerformanceComparison, RegressionReport};
+use crate::sequence_validator::TransactionSequenceValidator;
+use crate::shadow::ShadowReplayEngine;
+use crate::state_manager::Checkpoint;
 use crate::transaction_processor::TransactionProcessor;
 use crate::traits::{RuleSet, State, Transaction};
-use crate::types::{PerformanceMetrics, ReplayResult};
+use crate::types::{CheckpointValidationReport, DurationEstimate, IncrementalResult, MigrationDryRunResult, PerformanceHistory, PerformanceMetrics, ReplayCostBudget, ReplayResult, RuleApplication, StateHash, StateTransition, StateTransitionInfo};
 use chrono::Utc;
+use futures::channel::mpsc;
+use futures::SinkExt;
 use rayon::prelude::*;
 use std::marker::PhantomData;
+#[cfg(not(target_arch = "wasm32"))]
 use std::time::Instant;
+#[cfg(target_arch = "wasm32")]
+use web_time::Instant;
+
+/// One step of a replay that can interleave out-of-band state changes with transactions
+pub enum ReplayItem<S, T> {
+    /// A transaction processed through the engine's rule set
+    Transaction(T),
+    /// A forced state change, such as a manual adjustment by an operator, applied without a rule set
+    StateMutation {
+        description: String,
+        mutate: Box<dyn Fn(S) -> Result<S, StateError>>,
+    },
+}
+
+impl<S, T> ReplayItem<S, T> {
+    /// Create a state mutation item
+    pub fn mutation(description: &str, mutate: impl Fn(S) -> Result<S, StateError> + 'static) -> Self {
+        Self::StateMutation {
+            description: description.to_string(),
+            mutate: Box::new(mutate),
+        }
+    }
+}
+
+/// Final state and hash of a simulated replay, or the `(index, transaction_id, error)` that stopped it
+type SimulationOutcome<S> = Result<(S, StateHash), (usize, String, ProcessingError)>;
+
+/// What `ReplayEngine::replay_with_recovery_strategy` does with a failed transaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecoveryAction {
+    /// Stop the replay and return the error
+    Abort,
+    /// Leave the state unchanged, record the transaction as skipped and continue
+    Skip,
+    /// Process the transaction again up to this many times, then abort
+    Retry(usize),
+}
 
 /// Core replay engine for deterministic transaction processing
 #[derive(Debug)]
@@ -22,6 +76,13 @@ where
     rule_set: R,
     context: ExecutionContext,
     checkpoint_interval: Option<usize>,
+    max_state_size_bytes: Option<usize>,
+    max_transaction_count: Option<usize>,
+    deduplication_enabled: bool,
+    log_level: LogLevel,
+    pre_flight_validator: Option<TransactionSequenceValidator<T>>,
+    dry_run_checkpoints: bool,
+    cost_budget: Option<ReplayCostBudget>,
     _phantom_t: PhantomData<T>,
 }
 
@@ -38,6 +99,13 @@ where
             rule_set,
             context,
             checkpoint_interval: None,
+            max_state_size_bytes: None,
+            max_transaction_count: None,
+            deduplication_enabled: false,
+            log_level: LogLevel::Info,
+            pre_flight_validator: None,
+            dry_run_checkpoints: false,
+            cost_budget: None,
             _phantom_t: PhantomData,
         }
     }
@@ -54,6 +122,13 @@ where
             rule_set,
             context,
             checkpoint_interval: Some(checkpoint_interval),
+            max_state_size_bytes: None,
+            max_transaction_count: None,
+            deduplication_enabled: false,
+            log_level: LogLevel::Info,
+            pre_flight_validator: None,
+            dry_run_checkpoints: false,
+            cost_budget: None,
             _phantom_t: PhantomData,
         }
     }
@@ -65,21 +140,256 @@ where
     
     /// Replay a sequence of transactions and return the comprehensive result
     pub fn replay(&self, transactions: &[T]) -> Result<ReplayResult<S>, ProcessingError> {
+        self.replay_keeping_checkpoints(transactions, false).map(|(result, _)| result)
+    }
+    
+    /// Run a shadow rule set next to this engine's rule set for canary testing, see `ShadowReplayEngine`
+    pub fn with_shadow<R2: RuleSet<S, T>>(self, shadow_rule_set: R2) -> ShadowReplayEngine<S, T, R, R2> {
+        ShadowReplayEngine::new(self, shadow_rule_set)
+    }
+    
+    /// Replay a sequence of transactions, letting `strategy` decide what to do with each failure
+    /// 
+    /// Pre-flight validation and cost budget failures are returned without
+    /// consulting the strategy. Skipped transactions are listed in
+    /// `ExecutionTrace::skipped_transactions` and not counted as processed.
+    /// A retried transaction sees the same state and context, so retrying only
+    /// helps when the rule set depends on something outside the replay.
+    pub fn replay_with_recovery_strategy(
+        &self,
+        transactions: &[T],
+        strategy: impl Fn(&ProcessingError) -> RecoveryAction,
+    ) -> Result<ReplayResult<S>, ProcessingError> {
+        self.run_pre_flight_validation(transactions)?;
+        self.check_cost_budget(&self.rule_set, transactions, &self.initial_state)?;
+        let start_time = Instant::now();
+        
+        let mut processor = self.new_processor()?;
+        let context = self.context.with_detached_clock();
+        let mut skipped_transactions = Vec::new();
+        let mut skipped_indices = Vec::new();
+        for (index, transaction) in transactions.iter().enumerate() {
+            if let Err(error) = processor.process_transaction(transaction, &self.rule_set, &context) {
+                match strategy(&error) {
+                    RecoveryAction::Abort => return Err(error),
+                    RecoveryAction::Skip => {
+                        skipped_transactions.push(transaction.id().to_string());
+                        skipped_indices.push(index);
+                        continue;
+                    }
+                    RecoveryAction::Retry(retries) => {
+                        let mut outcome = Err(error);
+                        for _ in 0..retries {
+                            outcome = processor.process_transaction(transaction, &self.rule_set, &context);
+                            if outcome.is_ok() {
+                                break;
+                            }
+                        }
+                        outcome?;
+                    }
+                }
+            }
+            let processed = processor.transactions_processed();
+            if self.checkpoint_interval.is_some_and(|interval| interval > 0 && processed % interval == 0) {
+                processor.record_checkpoint(transaction.timestamp());
+            }
+        }
+        
+        let duration_ms = start_time.elapsed().as_millis() as u64;
+        let performance_metrics = PerformanceMetrics {
+            total_duration_ms: duration_ms,
+            transactions_per_second: if duration_ms > 0 {
+                transactions.len() as f64 / (duration_ms as f64 / 1000.0)
+            } else {
+                0.0
+            },
+            average_transaction_time_ms: if !transactions.is_empty() {
+                duration_ms as f64 / transactions.len() as f64
+            } else {
+                0.0
+            },
+            breakdown: processor.overhead_breakdown(),
+        };
+        
+        let final_hash = processor.current_hash();
+        let (final_state, mut execution_trace) = processor.into_result();
+        execution_trace.skipped_transactions = skipped_transactions;
+        execution_trace.skipped_indices = skipped_indices;
+        Ok(ReplayResult {
+            final_state,
+            final_hash,
+            execution_trace,
+            performance_metrics,
+        })
+    }
+    
+    /// Replay transactions lazily, yielding the new state after each one
+    /// 
+    /// Pre-flight validation and the cost budget are checked on the first
+    /// call to `next`. After the last transaction a terminal increment with an
+    /// empty `transaction_id` carries the final state and hash. The first error
+    /// is yielded and ends the iteration. No checkpoints are created.
+    pub fn replay_streaming<'a>(
+        &'a self,
+        transactions: &'a [T],
+    ) -> impl Iterator<Item = Result<IncrementalResult<S>, ProcessingError>> + 'a {
+        ReplayStream {
+            engine: self,
+            transactions,
+            progress: StreamProgress::Pending,
+        }
+    }
+    
+    /// Drive `replay_streaming`, sending each increment into `sender`
+    /// 
+    /// Runs on any executor; pair it with the channel's receiver, for example
+    /// with `futures::join!`. Stops early once the receiver is dropped.
+    pub async fn replay_streaming_async(
+        &self,
+        transactions: &[T],
+        mut sender: mpsc::Sender<Result<IncrementalResult<S>, ProcessingError>>,
+    ) {
+        for increment in self.replay_streaming(transactions) {
+            if sender.send(increment).await.is_err() {
+                break;
+            }
+        }
+    }
+    
+    /// Replay transactions interleaved with out-of-band state mutations
+    /// 
+    /// Mutations are applied with `TransactionProcessor::apply_mutation`, so
+    /// they appear in the trace as `MUTATION_{n}` transitions and in
+    /// `ExecutionTrace::mutations`, but never reach the rule set. Pre-flight
+    /// validation and checkpoint intervals only count the transactions.
+    pub fn replay_with_mutations(&self, sequence: Vec<ReplayItem<S, T>>) -> Result<ReplayResult<S>, ProcessingError> {
+        let transactions: Vec<T> = sequence
+            .iter()
+            .filter_map(|item| match item {
+                ReplayItem::Transaction(transaction) => Some(transaction.clone()),
+                ReplayItem::StateMutation { .. } => None,
+            })
+            .collect();
+        self.run_pre_flight_validation(&transactions)?;
+        self.check_cost_budget(&self.rule_set, &transactions, &self.initial_state)?;
+        let start_time = Instant::now();
+        
+        let mut processor = self.new_processor()?;
+        let context = self.context.with_detached_clock();
+        for item in sequence {
+            match item {
+                ReplayItem::Transaction(transaction) => {
+                    processor.process_transaction(&transaction, &self.rule_set, &context)?;
+                    let processed = processor.transactions_processed();
+                    if self.checkpoint_interval.is_some_and(|interval| interval > 0 && processed % interval == 0) {
+                        processor.record_checkpoint(transaction.timestamp());
+                    }
+                }
+                ReplayItem::StateMutation { description, mutate } => {
+                    processor.apply_mutation(&description, mutate)?;
+                }
+            }
+        }
+        
+        let duration_ms = start_time.elapsed().as_millis() as u64;
+        let performance_metrics = PerformanceMetrics {
+            total_duration_ms: duration_ms,
+            transactions_per_second: if duration_ms > 0 {
+                transactions.len() as f64 / (duration_ms as f64 / 1000.0)
+            } else {
+                0.0
+            },
+            average_transaction_time_ms: if !transactions.is_empty() {
+                duration_ms as f64 / transactions.len() as f64
+            } else {
+                0.0
+            },
+            breakdown: processor.overhead_breakdown(),
+        };
+        
+        let final_hash = processor.current_hash();
+        let (final_state, execution_trace) = processor.into_result();
+        Ok(ReplayResult {
+            final_state,
+            final_hash,
+            execution_trace,
+            performance_metrics,
+        })
+    }
+    
+    /// Replay a sequence of transactions, then check that every checkpoint it created can be resumed from
+    /// 
+    /// The report is only produced when `dry_run_checkpoints` is enabled; see
+    /// `validate_checkpoints` for what is checked.
+    pub fn replay_with_checkpoint_validation(
+        &self,
+        transactions: &[T],
+    ) -> Result<(ReplayResult<S>, Option<CheckpointValidationReport>), ProcessingError> {
+        let (result, checkpoints) = self.replay_keeping_checkpoints(transactions, self.dry_run_checkpoints)?;
+        let report = self.dry_run_checkpoints
+            .then(|| self.validate_checkpoints(&checkpoints, transactions, result.final_hash));
+        Ok((result, report))
+    }
+    
+    /// Resume from each checkpoint with the transactions after it and compare final hashes
+    /// 
+    /// A checkpoint is valid when `replay_from_checkpoint` with
+    /// `transactions[checkpoint.transaction_index..]` reaches `expected_final_hash`.
+    /// This catches checkpoints whose state was corrupted when it was taken or
+    /// stored. If resuming from a checkpoint fails, its entry records the hash
+    /// of the checkpoint's state in place of a final hash.
+    pub fn validate_checkpoints(
+        &self,
+        checkpoints: &[Checkpoint<S>],
+        transactions: &[T],
+        expected_final_hash: StateHash,
+    ) -> CheckpointValidationReport {
+        let mut report = CheckpointValidationReport {
+            valid_count: 0,
+            invalid: Vec::new(),
+        };
+        
+        for checkpoint in checkpoints {
+            let remaining = transactions.get(checkpoint.transaction_index..).unwrap_or(&[]);
+            let actual_hash = match self.replay_from_checkpoint(checkpoint, remaining) {
+                Ok(result) => result.final_hash,
+                Err(_) => StateHasher::new().hash(&checkpoint.state),
+            };
+            
+            if actual_hash == expected_final_hash {
+                report.valid_count += 1;
+            } else {
+                report.invalid.push((checkpoint.transaction_index, expected_final_hash, actual_hash));
+            }
+        }
+        
+        report
+    }
+    
+    /// Replay from the initial state, optionally returning the checkpoints taken along the way
+    fn replay_keeping_checkpoints(
+        &self,
+        transactions: &[T],
+        keep_checkpoints: bool,
+    ) -> Result<(ReplayResult<S>, Vec<Checkpoint<S>>), ProcessingError> {
+        self.run_pre_flight_validation(transactions)?;
+        self.check_cost_budget(&self.rule_set, transactions, &self.initial_state)?;
         let start_time = Instant::now();
         
         // Create a transaction processor with the initial state
-        let mut processor = TransactionProcessor::new(self.initial_state.clone())?;
+        let mut processor = self.new_processor()?;
+        let context = self.context.with_detached_clock();
         
         // Process all transactions in order with optional checkpointing
         if let Some(interval) = self.checkpoint_interval {
             processor.process_transactions_with_checkpoints(
                 transactions,
                 &self.rule_set,
-                &self.context,
+                &context,
                 interval,
             )?;
         } else {
-            processor.process_transactions(transactions, &self.rule_set, &self.context)?;
+            processor.process_transactions(transactions, &self.rule_set, &context)?;
         }
         
         // Calculate performance metrics
@@ -100,43 +410,121 @@ where
             total_duration_ms: duration_ms,
             transactions_per_second,
             average_transaction_time_ms,
+            breakdown: processor.overhead_breakdown(),
         };
         
-        // Get the final hash before consuming the processor
+        // Get the final hash and checkpoints before consuming the processor
         let final_hash = processor.current_hash();
+        let checkpoints = if keep_checkpoints {
+            processor.state_manager().checkpoints().to_vec()
+        } else {
+            Vec::new()
+        };
         
         // Get the final state and execution trace
         let (final_state, execution_trace) = processor.into_result();
         
-        Ok(ReplayResult {
-            final_state,
-            final_hash,
-            execution_trace,
-            performance_metrics,
-        })
+        Ok((
+            ReplayResult {
+                final_state,
+                final_hash,
+                execution_trace,
+                performance_metrics,
+            },
+            checkpoints,
+        ))
+    }
+    
+    /// Replay transactions while timing each rule application, reporting the `top_n` slowest
+    /// 
+    /// Failing transactions are skipped and leave the state unchanged; they
+    /// count towards the total duration but are not reported as applications.
+    /// Nothing is checkpointed. The report is empty if the initial state
+    /// cannot be hashed.
+    #[cfg(feature = "profiling")]
+    pub fn profile(&self, transactions: &[T], top_n: usize) -> ProfilingReport<T> {
+        let start_time = Instant::now();
+        let mut applications = Vec::new();
+        if let Ok(mut processor) = self.new_processor() {
+            let context = self.context.with_detached_clock();
+            for transaction in transactions {
+                let state_hash_before = processor.current_hash();
+                let started = Instant::now();
+                let outcome = processor.process_transaction(transaction, &self.rule_set, &context);
+                let duration_us = u64::try_from(started.elapsed().as_micros()).unwrap_or(u64::MAX);
+                if outcome.is_ok() {
+                    applications.push(SlowApplication {
+                        transaction_id: transaction.id().to_string(),
+                        rule_version: self.rule_set.version(),
+                        duration_us,
+                        state_hash_before,
+                        transaction: transaction.clone(),
+                    });
+                }
+            }
+        }
+        ProfilingReport::new(start_time.elapsed().as_millis() as u64, applications, top_n)
+    }
+    
+    /// Replay a sequence of transactions and sign a summary of the outcome for auditors
+    /// 
+    /// The bundle is timestamped with the execution context's time rather than
+    /// the wall clock, so the same replay always produces the same bundle.
+    pub fn audit_replay(
+        &self,
+        transactions: &[T],
+        config: &AuditBundleConfig,
+    ) -> Result<AuditBundle<S>, ProcessingError> {
+        let result = self.replay(transactions)?;
+        AuditBundle::seal(result, self.context.now(), config)
+    }
+    
+    /// Replay a sequence of transactions and package it for independent reproduction
+    /// 
+    /// The bundle holds the initial state, the transactions, the context
+    /// configuration and the final hash of this replay; see `ReproducibilityBundle`
+    /// for its layout and `ReproducibilityBundle::verify` for checking it.
+    /// 
+    /// # Errors
+    /// Fails if any entry cannot be serialized, or if the replay itself fails
+    /// and there is no final hash to record.
+    pub fn export_reproducibility_bundle(&self, transactions: &[T]) -> Result<ReproducibilityBundle, SerializationError> {
+        let result = self.replay(transactions).map_err(|e| SerializationError::SerializationFailed {
+            reason: format!("Replay failed, so the bundle has no final hash: {}", e),
+        })?;
+        ReproducibilityBundle::package(
+            &self.initial_state,
+            transactions,
+            &self.context,
+            &self.rule_set.version().to_string(),
+            result.final_hash,
+        )
     }
     
     /// Resume replay from a checkpoint
     pub fn replay_from_checkpoint(
         &self,
-        checkpoint: &crate::state_manager::Checkpoint<S>,
+        checkpoint: &Checkpoint<S>,
         remaining_transactions: &[T],
     ) -> Result<ReplayResult<S>, ProcessingError> {
+        self.run_pre_flight_validation(remaining_transactions)?;
+        self.check_cost_budget(&self.rule_set, remaining_transactions, &checkpoint.state)?;
         let start_time = Instant::now();
         
         // Create a transaction processor from the checkpoint state
-        let mut processor = TransactionProcessor::from_checkpoint(checkpoint)?;
+        let mut processor = self.limit_processor(TransactionProcessor::from_checkpoint(checkpoint)?);
+        let context = self.context.with_detached_clock();
         
         // Process remaining transactions with optional checkpointing
         if let Some(interval) = self.checkpoint_interval {
             processor.process_transactions_with_checkpoints(
                 remaining_transactions,
                 &self.rule_set,
-                &self.context,
+                &context,
                 interval,
             )?;
         } else {
-            processor.process_transactions(remaining_transactions, &self.rule_set, &self.context)?;
+            processor.process_transactions(remaining_transactions, &self.rule_set, &context)?;
         }
         
         // Calculate performance metrics
@@ -157,6 +545,7 @@ where
             total_duration_ms: duration_ms,
             transactions_per_second,
             average_transaction_time_ms,
+            breakdown: processor.overhead_breakdown(),
         };
         
         // Get the final hash before consuming the processor
@@ -173,77 +562,135 @@ where
         })
     }
     
+    /// Replay the half-open range `transactions[start..end]` from the initial state
+    /// 
+    /// Use `replay_range_from_checkpoint` when the range does not start at the
+    /// beginning of the log and a checkpoint for `start` is available.
+    pub fn replay_range(
+        &self,
+        transactions: &[T],
+        start: usize,
+        end: usize,
+    ) -> Result<ReplayResult<S>, ProcessingError> {
+        let range = Self::checked_range(transactions, start, end)?;
+        self.replay(range)
+    }
+    
+    /// Replay the half-open range `transactions[start..end]` starting from a checkpoint
+    /// 
+    /// The checkpoint is expected to hold the state after `transactions[..start]`.
+    pub fn replay_range_from_checkpoint(
+        &self,
+        checkpoint: &Checkpoint<S>,
+        transactions: &[T],
+        start: usize,
+        end: usize,
+    ) -> Result<ReplayResult<S>, ProcessingError> {
+        let range = Self::checked_range(transactions, start, end)?;
+        self.replay_from_checkpoint(checkpoint, range)
+    }
+    
+    /// Slice `transactions[start..end]`, rejecting ranges outside the log
+    fn checked_range(transactions: &[T], start: usize, end: usize) -> Result<&[T], ProcessingError> {
+        if start > end || end > transactions.len() {
+            return Err(ProcessingError::InvalidRange {
+                start,
+                end,
+                len: transactions.len(),
+            });
+        }
+        Ok(&transactions[start..end])
+    }
+    
     /// Replay a sequence of transactions in parallel and return the comprehensive result
     /// 
-    /// This method processes transactions in parallel while maintaining deterministic ordering.
-    /// The parallel execution is guaranteed to produce identical results to sequential execution.
+    /// Equivalent to `replay_parallel_chunked` with a chunk size of zero.
+    #[deprecated(note = "runs one full replay per worker; use `replay_parallel_chunked` instead")]
+    pub fn replay_parallel(&self, transactions: &[T]) -> Result<ReplayResult<S>, ProcessingError>
+    where
+        S: Send + Sync,
+        T: Send + Sync,
+        R: Send + Sync,
+    {
+        self.replay_parallel_chunked(transactions, 0)
+    }
+    
+    /// Replay a sequence of transactions and verify the result by re-executing chunks in parallel
     /// 
-    /// # Implementation Details
+    /// State is sequential, so the replay itself runs once, in order, snapshotting
+    /// the state at the start of every chunk of `chunk_size` transactions. Each chunk
+    /// is then re-executed on its own worker from its snapshot. A chunk size of zero
+    /// splits the sequence into `ceil(n / num_workers)`-sized chunks. Only the
+    /// verification is parallel, and it costs one extra replay in total rather
+    /// than one per worker.
     /// 
-    /// The parallel replay works by:
-    /// 1. Dividing transactions into chunks
-    /// 2. Processing each chunk in parallel from the same initial state
-    /// 3. Verifying that all parallel executions produce identical results
-    /// 4. Returning the result from one of the parallel executions
+    /// # Correctness
     /// 
-    /// This approach ensures determinism by verifying that all parallel paths
-    /// produce the same final state and hash.
-    pub fn replay_parallel(&self, transactions: &[T]) -> Result<ReplayResult<S>, ProcessingError>
+    /// The XOR of every intermediate state hash from the chunked re-execution must
+    /// equal the XOR from the sequential replay, otherwise the replay fails with
+    /// `ProcessingError::NonDeterministicOperation`. The returned result is the
+    /// sequential one, so when verification passes it is identical to `replay`.
+    /// The check detects rule sets whose output depends on anything besides the
+    /// state, transaction and context, such as thread-local or global state; XOR
+    /// cannot detect two reordered transitions, which the sequential replay rules out.
+    pub fn replay_parallel_chunked(
+        &self,
+        transactions: &[T],
+        chunk_size: usize,
+    ) -> Result<ReplayResult<S>, ProcessingError>
     where
         S: Send + Sync,
         T: Send + Sync,
         R: Send + Sync,
     {
+        self.run_pre_flight_validation(transactions)?;
+        self.check_cost_budget(&self.rule_set, transactions, &self.initial_state)?;
         let start_time = Instant::now();
         
-        // For small transaction sets, use sequential processing
-        if transactions.len() < 100 {
-            return self.replay(transactions);
-        }
+        let chunk_size = if chunk_size == 0 {
+            transactions.len().div_ceil(rayon::current_num_threads().max(1)).max(1)
+        } else {
+            chunk_size
+        };
         
-        // Determine the number of parallel workers (use available parallelism)
-        let num_workers = rayon::current_num_threads().max(2);
-        
-        // Process the same transaction sequence in parallel multiple times
-        // to verify determinism
-        let results: Vec<Result<ReplayResult<S>, ProcessingError>> = (0..num_workers)
-            .into_par_iter()
-            .map(|_| {
-                // Each worker processes the full sequence independently
-                let mut processor = TransactionProcessor::new(self.initial_state.clone())?;
-                processor.process_transactions(transactions, &self.rule_set, &self.context)?;
-                
-                let final_hash = processor.current_hash();
-                let (final_state, execution_trace) = processor.into_result();
-                
-                Ok(ReplayResult {
-                    final_state,
-                    final_hash,
-                    execution_trace,
-                    performance_metrics: PerformanceMetrics {
-                        total_duration_ms: 0,
-                        transactions_per_second: 0.0,
-                        average_transaction_time_ms: 0.0,
-                    },
-                })
+        // Replay sequentially, snapshotting the state at the start of each chunk
+        let mut processor = self.new_processor()?;
+        let context = self.context.with_detached_clock();
+        let mut chunk_starts = Vec::with_capacity(transactions.len().div_ceil(chunk_size));
+        for (index, transaction) in transactions.iter().enumerate() {
+            if index % chunk_size == 0 {
+                // Each chunk gets a clock showing the time its first transaction saw
+                chunk_starts.push((processor.snapshot().to_checkpoint(transaction.timestamp()), context.with_detached_clock()));
+            }
+            processor.process_transaction(transaction, &self.rule_set, &context)?;
+            if let Some(interval) = self.checkpoint_interval {
+                if interval > 0 && (index + 1) % interval == 0 {
+                    processor.record_checkpoint(transaction.timestamp());
+                }
+            }
+        }
+        let sequential_xor = xor_hashes(processor.execution_trace().state_transitions.iter().map(|t| t.to_hash));
+        
+        // Re-execute every chunk from its snapshot in parallel
+        let chunk_xors: Vec<Result<StateHash, ProcessingError>> = chunk_starts
+            .par_iter()
+            .zip(transactions.par_chunks(chunk_size))
+            .map(|((checkpoint, chunk_context), chunk)| {
+                let mut chunk_processor = TransactionProcessor::from_checkpoint(checkpoint)?;
+                let transitions = chunk_processor.process_transactions(chunk, &self.rule_set, chunk_context)?;
+                Ok(xor_hashes(transitions.iter().map(|t| t.to_hash)))
             })
             .collect();
-        
-        // Verify all results are identical
-        let mut final_results = Vec::new();
-        for result in results {
-            final_results.push(result?);
+        let mut parallel_hashes = Vec::with_capacity(chunk_xors.len());
+        for chunk_xor in chunk_xors {
+            parallel_hashes.push(chunk_xor?);
         }
         
-        // Check that all parallel executions produced identical results
-        let first_hash = &final_results[0].final_hash;
-        for result in &final_results[1..] {
-            if &result.final_hash != first_hash {
-                return Err(ProcessingError::NonDeterministicOperation {
-                    operation: "parallel_replay".to_string(),
-                    location: "Parallel execution produced different results".to_string(),
-                });
-            }
+        if xor_hashes(parallel_hashes) != sequential_xor {
+            return Err(ProcessingError::NonDeterministicOperation {
+                operation: "parallel_replay".to_string(),
+                location: "Chunked re-execution produced different intermediate states".to_string(),
+            });
         }
         
         // Calculate performance metrics
@@ -260,15 +707,152 @@ where
             0.0
         };
         
-        // Return the first result with updated performance metrics
-        let mut result = final_results.into_iter().next().unwrap();
-        result.performance_metrics = PerformanceMetrics {
-            total_duration_ms: duration_ms,
-            transactions_per_second,
-            average_transaction_time_ms,
-        };
+        let breakdown = processor.overhead_breakdown();
+        let final_hash = processor.current_hash();
+        let (final_state, execution_trace) = processor.into_result();
         
-        Ok(result)
+        Ok(ReplayResult {
+            final_state,
+            final_hash,
+            execution_trace,
+            performance_metrics: PerformanceMetrics {
+                total_duration_ms: duration_ms,
+                transactions_per_second,
+                average_transaction_time_ms,
+                breakdown,
+            },
+        })
+    }
+    
+    /// Create a processor for the initial state, applying the configured limits
+    pub(crate) fn new_processor(&self) -> Result<TransactionProcessor<S>, ProcessingError> {
+        Ok(self.limit_processor(TransactionProcessor::new(self.initial_state.clone())?))
+    }
+    
+    /// Apply the configured limits, deduplication and log level to a processor
+    fn limit_processor(&self, processor: TransactionProcessor<S>) -> TransactionProcessor<S> {
+        let processor = processor
+            .with_log_level(self.log_level)
+            .with_deduplication(self.deduplication_enabled);
+        let processor = match self.max_state_size_bytes {
+            Some(limit) => processor.with_max_state_size_bytes(limit),
+            None => processor,
+        };
+        match self.max_transaction_count {
+            Some(limit) => processor.with_max_transaction_count(limit),
+            None => processor,
+        }
+    }
+    
+    /// Rough upper bound, in bytes, on the memory a replay of `transaction_count` transactions needs
+    /// 
+    /// The serialized size of the initial state is taken as the average state size.
+    /// Each transaction is counted as two full states in its returned transition plus
+    /// its trace entries, and each checkpoint as one more state.
+    pub fn estimated_memory_usage(&self, transaction_count: usize) -> usize {
+        let state_size = bincode::serialized_size(&self.initial_state)
+            .map_or(0, |size| size as usize)
+            .max(std::mem::size_of::<S>());
+        let per_transaction = 2 * state_size
+            + std::mem::size_of::<StateTransition<S>>()
+            + std::mem::size_of::<StateTransitionInfo>()
+            + std::mem::size_of::<RuleApplication>();
+        let checkpoints = self.checkpoint_interval
+            .filter(|interval| *interval > 0)
+            .map_or(0, |interval| transaction_count / interval);
+        
+        state_size
+            .saturating_add(transaction_count.saturating_mul(per_transaction))
+            .saturating_add(checkpoints.saturating_mul(state_size))
+    }
+    
+    /// Check any learned transaction ordering, then run the pre-flight validator, if configured
+    pub(crate) fn run_pre_flight_validation(&self, transactions: &[T]) -> Result<(), ProcessingError> {
+        self.context.ordering_rules().validate_relative_ordering(OrderingRules::TRANSACTIONS, transactions, |t| t.id())?;
+        if let Some(validator) = &self.pre_flight_validator {
+            let report = validator.validate_all(transactions);
+            if !report.is_valid() {
+                return Err(ProcessingError::PreFlightValidationFailed {
+                    errors: report.errors.iter().map(|e| e.to_string()).collect(),
+                });
+            }
+        }
+        Ok(())
+    }
+    
+    /// Check a rule set's cost estimate for replaying `transactions` from `state` against the budget
+    pub(crate) fn check_cost_budget<R2>(&self, rule_set: &R2, transactions: &[T], state: &S) -> Result<(), ProcessingError>
+    where
+        R2: RuleSet<S, T> + ?Sized,
+    {
+        match &self.cost_budget {
+            Some(budget) => budget.check(&rule_set.replay_cost_estimate(transactions, state)),
+            None => Ok(()),
+        }
+    }
+    
+    /// Get the configured cost budget
+    pub fn cost_budget(&self) -> Option<&ReplayCostBudget> {
+        self.cost_budget.as_ref()
+    }
+    
+    /// Estimate how long replaying `transaction_count` transactions will take
+    /// 
+    /// The estimate only scales the throughput of the replays in `history`,
+    /// which should have been run with this engine's configuration; with an
+    /// empty history every duration is 0 and so is the confidence.
+    pub fn estimate_duration(&self, transaction_count: usize, history: &PerformanceHistory) -> DurationEstimate {
+        DurationEstimate::from_history(transaction_count, history)
+    }
+    
+    /// Write a Rust test module that replays `transactions` and checks it reaches `result`
+    /// 
+    /// See `generate_test_fixtures_with_options`; the fixture only checks the final hash.
+    pub fn generate_test_fixtures<W: std::io::Write>(&self, transactions: &[T], result: &ReplayResult<S>, writer: &mut W) -> std::io::Result<()>
+    where
+        R: Default,
+    {
+        self.generate_test_fixtures_with_options(transactions, result, writer, FixtureGenerationOptions::default())
+    }
+    
+    /// Write a Rust test module that replays `transactions` and checks it reaches `result`
+    /// 
+    /// The initial state, transactions and context are embedded as JSON, and
+    /// the module has tests replaying them twice for determinism, validating
+    /// the initial and final states and comparing the final hash with
+    /// `result`'s. The fixture's engine is rebuilt with this engine's
+    /// settings, except for the pre-flight validator, and its rule set is
+    /// created with `Default`. Types are named by `options.type_paths`, or
+    /// else by their `std::any::type_name` with the state's crate replaced by
+    /// `crate`, in which case the fixture must be compiled as a module of the
+    /// crate defining the state. External facts and entities are not embedded.
+    pub fn generate_test_fixtures_with_options<W: std::io::Write>(
+        &self,
+        transactions: &[T],
+        result: &ReplayResult<S>,
+        writer: &mut W,
+        options: FixtureGenerationOptions,
+    ) -> std::io::Result<()>
+    where
+        R: Default,
+    {
+        let fixture = Fixture {
+            initial_state: &self.initial_state,
+            transactions,
+            context: &self.context,
+            settings: FixtureEngineSettings {
+                checkpoint_interval: self.checkpoint_interval,
+                max_state_size_bytes: self.max_state_size_bytes,
+                max_transaction_count: self.max_transaction_count,
+                deduplication_enabled: self.deduplication_enabled,
+                log_level: self.log_level,
+                dry_run_checkpoints: self.dry_run_checkpoints,
+                cost_budget: self.cost_budget,
+            },
+            rule_set_version: self.rule_set.version(),
+            result,
+        };
+        fixture.write::<R, W>(writer, options)
     }
     
     /// Get the initial state
@@ -286,6 +870,36 @@ where
         &self.context
     }
     
+    /// Get the checkpoint interval, if checkpointing is enabled
+    pub fn checkpoint_interval(&self) -> Option<usize> {
+        self.checkpoint_interval
+    }
+    
+    /// Get the configured upper bound on serialized state size
+    pub fn max_state_size_bytes(&self) -> Option<usize> {
+        self.max_state_size_bytes
+    }
+    
+    /// Get the configured limit on transactions per replay
+    pub fn max_transaction_count(&self) -> Option<usize> {
+        self.max_transaction_count
+    }
+    
+    /// Check if transaction deduplication is enabled
+    pub fn deduplication_enabled(&self) -> bool {
+        self.deduplication_enabled
+    }
+    
+    /// Get the configured minimum log level
+    pub fn log_level(&self) -> LogLevel {
+        self.log_level
+    }
+    
+    /// Check whether `replay_with_checkpoint_validation` validates checkpoints
+    pub fn dry_run_checkpoints(&self) -> bool {
+        self.dry_run_checkpoints
+    }
+    
     /// Replay transactions with a different rule set for migration impact analysis
     /// 
     /// This method replays the same transaction sequence with a different rule version
@@ -298,21 +912,24 @@ where
     where
         R2: RuleSet<S, T>,
     {
-        let start_time = std::time::Instant::now();
+        self.run_pre_flight_validation(transactions)?;
+        self.check_cost_budget(new_rule_set, transactions, &self.initial_state)?;
+        let start_time = Instant::now();
         
         // Create a transaction processor with the initial state
-        let mut processor = TransactionProcessor::new(self.initial_state.clone())?;
+        let mut processor = self.new_processor()?;
+        let context = self.context.with_detached_clock();
         
         // Process all transactions with the new rule set
         if let Some(interval) = self.checkpoint_interval {
             processor.process_transactions_with_checkpoints(
                 transactions,
                 new_rule_set,
-                &self.context,
+                &context,
                 interval,
             )?;
         } else {
-            processor.process_transactions(transactions, new_rule_set, &self.context)?;
+            processor.process_transactions(transactions, new_rule_set, &context)?;
         }
         
         // Calculate performance metrics
@@ -333,6 +950,7 @@ where
             total_duration_ms: duration_ms,
             transactions_per_second,
             average_transaction_time_ms,
+            breakdown: processor.overhead_breakdown(),
         };
         
         // Get the final hash before consuming the processor
@@ -407,6 +1025,97 @@ where
         })
     }
     
+    /// Compare the baseline rule set and `N` alternatives against each other in one call
+    /// 
+    /// Every version replays the full sequence once. Unlike `replay`, a transaction
+    /// rejected by a version is skipped rather than aborting that version's
+    /// replay, so the matrix can report it as a breaking change. Errors other
+    /// than rejected transactions, such as pre-flight validation failures or the
+    /// transaction count limit, still fail the whole call.
+    pub fn compute_impact_matrix<const N: usize>(
+        &self,
+        transactions: &[T],
+        alternatives: [&dyn RuleSet<S, T>; N],
+    ) -> Result<ImpactMatrix<S, N>, ProcessingError>
+    where
+        S: PartialEq,
+    {
+        self.run_pre_flight_validation(transactions)?;
+        self.check_cost_budget(&self.rule_set, transactions, &self.initial_state)?;
+        for rule_set in alternatives {
+            self.check_cost_budget(rule_set, transactions, &self.initial_state)?;
+        }
+        
+        let mut replays = Vec::with_capacity(N + 1);
+        replays.push(self.replay_skipping_rejections(transactions, &self.rule_set)?);
+        for rule_set in alternatives {
+            replays.push(self.replay_skipping_rejections(transactions, &rule_set)?);
+        }
+        
+        let transaction_ids: Vec<String> = transactions.iter().map(|t| t.id().to_string()).collect();
+        Ok(ImpactMatrix::from_replays(&transaction_ids, replays))
+    }
+    
+    /// Replay with a rule set, leaving the state unchanged for each rejected transaction
+    fn replay_skipping_rejections<R2>(&self, transactions: &[T], rule_set: &R2) -> Result<VersionReplay<S>, ProcessingError>
+    where
+        R2: RuleSet<S, T>,
+    {
+        let start_time = Instant::now();
+        let mut processor = self.new_processor()?;
+        let context = self.context.with_detached_clock();
+        let mut outcomes = Vec::with_capacity(transactions.len());
+        
+        for transaction in transactions {
+            let accepted = match processor.process_transaction(transaction, rule_set, &context) {
+                Ok(_) => true,
+                Err(error @ ProcessingError::TransactionLimitExceeded { .. }) => return Err(error),
+                Err(_) => false,
+            };
+            outcomes.push(TransactionOutcome {
+                hash: processor.current_hash(),
+                accepted,
+            });
+        }
+        
+        let duration_ms = start_time.elapsed().as_millis() as u64;
+        let breakdown = processor.overhead_breakdown();
+        let final_hash = processor.current_hash();
+        let (final_state, execution_trace) = processor.into_result();
+        
+        Ok(VersionReplay {
+            version: rule_set.version(),
+            result: ReplayResult {
+                final_state,
+                final_hash,
+                execution_trace,
+                performance_metrics: PerformanceMetrics {
+                    total_duration_ms: duration_ms,
+                    transactions_per_second: if duration_ms > 0 {
+                        transactions.len() as f64 / (duration_ms as f64 / 1000.0)
+                    } else {
+                        0.0
+                    },
+                    average_transaction_time_ms: if transactions.is_empty() {
+                        0.0
+                    } else {
+                        duration_ms as f64 / transactions.len() as f64
+                    },
+                    breakdown,
+                },
+            },
+            outcomes,
+        })
+    }
+    
+    /// Reconcile the final state of a replay with an external ledger
+    /// 
+    /// Every account reported by either the ledger or the replayed state is
+    /// compared, so balances the other side lacks count as discrepancies.
+    pub fn verify_against_external_ledger<L: ExternalLedger<S>>(&self, result: &ReplayResult<S>, ledger: &L) -> LedgerVerificationResult {
+        LedgerVerificationResult::compare(&ledger.extract_balances(&result.final_state), &ledger.balances())
+    }
+    
     /// Verify that a rule migration is safe by checking if it produces identical results
     /// 
     /// This is a convenience method that performs impact analysis and returns
@@ -423,6 +1132,274 @@ where
         let analysis = self.analyze_migration_impact(transactions, new_rule_set)?;
         Ok(analysis.is_safe_migration())
     }
+    
+    /// Try a new rule set on upcoming transactions, starting from the engine's initial state
+    /// 
+    /// Nothing is persisted: the simulation runs on a copy of the state and no
+    /// checkpoints are created. A failing transaction ends the simulation and
+    /// is reported in the result rather than as an error.
+    /// 
+    /// # Errors
+    /// Fails only if the replay cannot start, for example when pre-flight
+    /// validation or the cost budget rejects the transactions.
+    pub fn dry_run_migration<R2>(
+        &self,
+        upcoming_transactions: &[T],
+        new_rule_set: &R2,
+    ) -> Result<MigrationDryRunResult<S>, ProcessingError>
+    where
+        R2: RuleSet<S, T>,
+    {
+        self.run_pre_flight_validation(upcoming_transactions)?;
+        self.check_cost_budget(new_rule_set, upcoming_transactions, &self.initial_state)?;
+        
+        Ok(match self.simulate(upcoming_transactions, new_rule_set)? {
+            Ok((final_state, final_hash)) => MigrationDryRunResult {
+                would_succeed: true,
+                simulated_final_state: Some(final_state),
+                simulated_final_hash: Some(final_hash),
+                first_failing_transaction: None,
+                fee_delta: None,
+            },
+            Err(failure) => MigrationDryRunResult {
+                would_succeed: false,
+                simulated_final_state: None,
+                simulated_final_hash: None,
+                first_failing_transaction: Some(failure),
+                fee_delta: None,
+            },
+        })
+    }
+    
+    /// Like `dry_run_migration`, also reporting how the fees users pay would change
+    /// 
+    /// The current rule set is simulated on the same transactions and
+    /// `fee_extractor` reads the total fees from each final state.
+    pub fn dry_run_migration_with_fees<R2>(
+        &self,
+        upcoming_transactions: &[T],
+        new_rule_set: &R2,
+        fee_extractor: impl Fn(&S) -> i64,
+    ) -> Result<MigrationDryRunResult<S>, ProcessingError>
+    where
+        R2: RuleSet<S, T>,
+    {
+        let mut result = self.dry_run_migration(upcoming_transactions, new_rule_set)?;
+        if let Some(new_state) = &result.simulated_final_state {
+            self.check_cost_budget(&self.rule_set, upcoming_transactions, &self.initial_state)?;
+            if let Ok((current_state, _)) = self.simulate(upcoming_transactions, &self.rule_set)? {
+                result.fee_delta = Some(fee_extractor(new_state) - fee_extractor(&current_state));
+            }
+        }
+        Ok(result)
+    }
+    
+    /// Process transactions on a fresh processor, stopping at the first failure
+    /// 
+    /// The outer error means the processor could not be created; the inner one
+    /// identifies the failing transaction.
+    fn simulate<R2>(&self, transactions: &[T], rule_set: &R2) -> Result<SimulationOutcome<S>, ProcessingError>
+    where
+        R2: RuleSet<S, T>,
+    {
+        let mut processor = self.new_processor()?;
+        let context = self.context.with_detached_clock();
+        for (index, transaction) in transactions.iter().enumerate() {
+            if let Err(error) = processor.process_transaction(transaction, rule_set, &context) {
+                return Ok(Err((index, transaction.id().to_string(), error)));
+            }
+        }
+        Ok(Ok((processor.current_state().clone(), processor.current_hash())))
+    }
+    
+    /// Check a new rule set for regressions against the current one on recorded transactions
+    /// 
+    /// Both rule sets process the sequence side by side. Unlike `replay`, a failing
+    /// transaction does not abort the run: it is recorded and leaves that side's
+    /// state unchanged. States are compared after every transaction using `tolerance`.
+    pub fn compare_with_baseline<R2>(
+        &self,
+        transactions: &[T],
+        new_rules: &R2,
+        tolerance: ComparisonTolerance,
+    ) -> Result<RegressionReport<S>, ProcessingError>
+    where
+        R2: RuleSet<S, T>,
+    {
+        self.run_pre_flight_validation(transactions)?;
+        self.check_cost_budget(&self.rule_set, transactions, &self.initial_state)?;
+        self.check_cost_budget(new_rules, transactions, &self.initial_state)?;
+        
+        let mut baseline = TransactionProcessor::new(self.initial_state.clone())?;
+        let mut comparison = TransactionProcessor::new(self.initial_state.clone())?;
+        let mut baseline_duration = std::time::Duration::ZERO;
+        let mut comparison_duration = std::time::Duration::ZERO;
+        // Each side ticks its own clock
+        let baseline_context = self.context.with_detached_clock();
+        let comparison_context = self.context.with_detached_clock();
+        
+        let mut diverging_transactions = Vec::new();
+        let mut new_failures = Vec::new();
+        let mut resolved_failures = Vec::new();
+        
+        for (index, transaction) in transactions.iter().enumerate() {
+            let start = Instant::now();
+            let baseline_ok = baseline.process_transaction(transaction, &self.rule_set, &baseline_context).is_ok();
+            baseline_duration += start.elapsed();
+            
+            let start = Instant::now();
+            let comparison_ok = comparison.process_transaction(transaction, new_rules, &comparison_context).is_ok();
+            comparison_duration += start.elapsed();
+            
+            match (baseline_ok, comparison_ok) {
+                (true, false) => new_failures.push(transaction.id().to_string()),
+                (false, true) => resolved_failures.push(transaction.id().to_string()),
+                _ => {}
+            }
+            
+            if !tolerance.states_match(baseline.current_state(), comparison.current_state()) {
+                diverging_transactions.push(index);
+            }
+        }
+        
+        let performance_comparison = if tolerance.includes_performance() {
+            let tps = |duration: std::time::Duration| {
+                if duration.as_secs_f64() > 0.0 {
+                    transactions.len() as f64 / duration.as_secs_f64()
+                } else {
+                    0.0
+                }
+            };
+            let baseline_duration_ms = baseline_duration.as_millis() as u64;
+            let comparison_duration_ms = comparison_duration.as_millis() as u64;
+            
+            Some(PerformanceComparison {
+                baseline_duration_ms,
+                comparison_duration_ms,
+                duration_difference_ms: comparison_duration_ms as i64 - baseline_duration_ms as i64,
+                baseline_tps: tps(baseline_duration),
+                comparison_tps: tps(comparison_duration),
+                tps_difference: tps(comparison_duration) - tps(baseline_duration),
+            })
+        } else {
+            None
+        };
+        
+        Ok(RegressionReport {
+            passed: diverging_transactions.is_empty() && new_failures.is_empty(),
+            diverging_transactions,
+            new_failures,
+            resolved_failures,
+            baseline_final_state: baseline.current_state().clone(),
+            comparison_final_state: comparison.current_state().clone(),
+            performance_comparison,
+        })
+    }
+}
+
+/// Where a streaming replay has got to
+enum StreamProgress<S: State> {
+    Pending,
+    // Boxed so the other variants stay small
+    Running(Box<RunningStream<S>>),
+    Done,
+}
+
+/// Replay state of a stream that has started
+struct RunningStream<S: State> {
+    processor: TransactionProcessor<S>,
+    /// Context with a clock of its own, see `ExecutionContext::with_detached_clock`
+    context: ExecutionContext,
+    position: usize,
+    start_time: Instant,
+}
+
+/// Iterator behind `ReplayEngine::replay_streaming`
+struct ReplayStream<'a, S, T, R>
+where
+    S: State,
+    T: Transaction,
+    R: RuleSet<S, T>,
+{
+    engine: &'a ReplayEngine<S, T, R>,
+    transactions: &'a [T],
+    progress: StreamProgress<S>,
+}
+
+impl<S, T, R> Iterator for ReplayStream<'_, S, T, R>
+where
+    S: State,
+    T: Transaction,
+    R: RuleSet<S, T>,
+{
+    type Item = Result<IncrementalResult<S>, ProcessingError>;
+    
+    fn next(&mut self) -> Option<Self::Item> {
+        let engine = self.engine;
+        if let StreamProgress::Pending = self.progress {
+            let started = engine.run_pre_flight_validation(self.transactions)
+                .and_then(|_| engine.check_cost_budget(&engine.rule_set, self.transactions, &engine.initial_state))
+                .and_then(|_| engine.new_processor());
+            match started {
+                Ok(processor) => {
+                    self.progress = StreamProgress::Running(Box::new(RunningStream {
+                        processor,
+                        context: engine.context.with_detached_clock(),
+                        position: 0,
+                        start_time: Instant::now(),
+                    }));
+                }
+                Err(e) => {
+                    self.progress = StreamProgress::Done;
+                    return Some(Err(e));
+                }
+            }
+        }
+        
+        let StreamProgress::Running(running) = &mut self.progress else {
+            return None;
+        };
+        let RunningStream { processor, context, position, start_time } = running.as_mut();
+        let Some(transaction) = self.transactions.get(*position) else {
+            let increment = IncrementalResult {
+                transaction_id: String::new(),
+                new_hash: processor.current_hash(),
+                new_state: processor.current_state().clone(),
+                rule_applied: engine.rule_set.version(),
+                elapsed_total_ms: start_time.elapsed().as_millis() as u64,
+            };
+            self.progress = StreamProgress::Done;
+            return Some(Ok(increment));
+        };
+        
+        match processor.process_transaction(transaction, &engine.rule_set, context) {
+            Ok(transition) => {
+                *position += 1;
+                Some(Ok(IncrementalResult {
+                    transaction_id: transition.transaction_id,
+                    new_hash: transition.to_hash,
+                    new_state: transition.to_state,
+                    rule_applied: engine.rule_set.version(),
+                    elapsed_total_ms: start_time.elapsed().as_millis() as u64,
+                }))
+            }
+            Err(e) => {
+                self.progress = StreamProgress::Done;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+/// XOR a sequence of state hashes together, byte by byte
+fn xor_hashes(hashes: impl IntoIterator<Item = StateHash>) -> StateHash {
+    let mut combined = [0u8; 32];
+    for hash in hashes {
+        for (byte, other) in combined.iter_mut().zip(hash.0) {
+            *byte ^= other;
+        }
+    }
+    StateHash(combined)
 }
 
 /// Builder for constructing replay engines with a fluent API
@@ -436,6 +1413,14 @@ where
     rule_set: Option<R>,
     context: Option<ExecutionContext>,
     checkpoint_interval: Option<usize>,
+    max_state_size_bytes: Option<usize>,
+    max_transaction_count: Option<usize>,
+    deduplication_enabled: bool,
+    log_level: LogLevel,
+    pre_flight_validator: Option<TransactionSequenceValidator<T>>,
+    dry_run_checkpoints: bool,
+    cost_budget: Option<ReplayCostBudget>,
+    debug_mode: Option<DebugMode>,
     _phantom_t: PhantomData<T>,
 }
 
@@ -452,10 +1437,40 @@ where
             rule_set: None,
             context: None,
             checkpoint_interval: None,
+            max_state_size_bytes: None,
+            max_transaction_count: None,
+            deduplication_enabled: false,
+            log_level: LogLevel::Info,
+            pre_flight_validator: None,
+            dry_run_checkpoints: false,
+            cost_budget: None,
+            debug_mode: None,
             _phantom_t: PhantomData,
         }
     }
     
+    /// Create a builder pre-filled from a declarative replay configuration
+    /// 
+    /// The initial state and rule set still have to be provided. External
+    /// facts are registered as `serde_json::Value` facts.
+    pub fn from_config(config: &ReplayConfig) -> Self {
+        let mut context_builder = ExecutionContext::builder()
+            .with_time(config.replay_time)
+            .with_random_seed(config.random_seed);
+        for (key, value) in &config.external_facts {
+            context_builder = context_builder.with_external_fact(key.clone(), value.clone());
+        }
+        
+        let mut builder = Self::new()
+            .with_context(context_builder.build())
+            .with_deduplication(config.deduplication_enabled)
+            .with_log_level(config.log_level)
+            .with_dry_run_checkpoints(config.dry_run_checkpoints);
+        builder.checkpoint_interval = config.checkpoint_interval;
+        builder.max_state_size_bytes = config.max_state_size_bytes;
+        builder
+    }
+    
     /// Set the initial state
     pub fn with_initial_state(mut self, state: S) -> Self {
         self.initial_state = Some(state);
@@ -486,17 +1501,98 @@ where
         self
     }
     
+    /// Set an upper bound on the serialized state size
+    /// 
+    /// A transaction producing a larger state fails the replay with
+    /// `ProcessingError::StateSizeLimitExceeded`.
+    pub fn with_max_state_size_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_state_size_bytes = Some(max_bytes);
+        self
+    }
+    
+    /// Limit each replay to `limit` transactions
+    /// 
+    /// Replays of longer sequences fail with `ProcessingError::TransactionLimitExceeded`.
+    pub fn with_max_transaction_count(mut self, limit: usize) -> Self {
+        self.max_transaction_count = Some(limit);
+        self
+    }
+    
+    /// Enable or disable transaction deduplication
+    /// 
+    /// Replays then fail on a transaction whose ID was already processed in
+    /// the same replay; see `TransactionProcessor::with_deduplication`.
+    pub fn with_deduplication(mut self, enabled: bool) -> Self {
+        self.deduplication_enabled = enabled;
+        self
+    }
+    
+    /// Set the minimum level of the log entries recorded while replaying
+    pub fn with_log_level(mut self, level: LogLevel) -> Self {
+        self.log_level = level;
+        self
+    }
+    
+    /// Validate every transaction sequence before replaying it
+    /// 
+    /// Replays fail fast with `ProcessingError::PreFlightValidationFailed`
+    /// when the validator reports errors; warnings do not stop the replay.
+    pub fn with_pre_flight_validation(mut self, validator: TransactionSequenceValidator<T>) -> Self {
+        self.pre_flight_validator = Some(validator);
+        self
+    }
+    
+    /// Resume from every checkpoint after a replay and compare final hashes
+    /// 
+    /// Only affects `ReplayEngine::replay_with_checkpoint_validation`.
+    pub fn with_dry_run_checkpoints(mut self, enabled: bool) -> Self {
+        self.dry_run_checkpoints = enabled;
+        self
+    }
+    
+    /// Reject replays whose estimated cost exceeds `budget` before they start
+    /// 
+    /// The estimate comes from `RuleSet::replay_cost_estimate`; replays fail
+    /// with `ProcessingError::CostBudgetExceeded` without processing anything.
+    /// Calls that replay several rule sets check each of them.
+    pub fn with_cost_budget(mut self, budget: ReplayCostBudget) -> Self {
+        self.cost_budget = Some(budget);
+        self
+    }
+    
+    /// Capture or discard the rule set's `ExecutionContext::debug_println` output
+    /// 
+    /// Applied to the execution context when the engine is built; read the
+    /// captured messages with `engine.context().debug_log()`.
+    pub fn with_debug_mode(mut self, mode: DebugMode) -> Self {
+        self.debug_mode = Some(mode);
+        self
+    }
+    
     /// Build the replay engine
     pub fn build(self) -> Result<ReplayEngine<S, T, R>, String> {
         let initial_state = self.initial_state.ok_or("Initial state is required")?;
         let rule_set = self.rule_set.ok_or("Rule set is required")?;
         let context = self.context.ok_or("Execution context is required")?;
+        let context = match self.debug_mode {
+            Some(mode) => context.with_debug_mode(mode),
+            None => context,
+        };
         
-        if let Some(interval) = self.checkpoint_interval {
-            Ok(ReplayEngine::with_checkpointing(initial_state, rule_set, context, interval))
+        let mut engine = if let Some(interval) = self.checkpoint_interval {
+            ReplayEngine::with_checkpointing(initial_state, rule_set, context, interval)
         } else {
-            Ok(ReplayEngine::new(initial_state, rule_set, context))
-        }
+            ReplayEngine::new(initial_state, rule_set, context)
+        };
+        engine.max_state_size_bytes = self.max_state_size_bytes;
+        engine.max_transaction_count = self.max_transaction_count;
+        engine.deduplication_enabled = self.deduplication_enabled;
+        engine.log_level = self.log_level;
+        engine.pre_flight_validator = self.pre_flight_validator;
+        engine.dry_run_checkpoints = self.dry_run_checkpoints;
+        engine.cost_budget = self.cost_budget;
+        
+        Ok(engine)
     }
 }
 
@@ -0,0 +1,191 @@
+//! Self-contained bundles for reproducing a replay elsewhere
+//! 
+//! A reproducibility bundle is a ZIP archive holding everything a third party
+//! needs to rerun a replay and confirm its final hash:
+//! 
+//! - `initial_state.json`: the initial state
+//! - `transactions.ndjson`: one JSON transaction per line, in replay order
+//! - `context.json`: the `ReproducibilityConfig` of the execution context
+//! - `rule_set_version.txt`: the version of the rule set that produced the result
+//! - `expected_final_hash.txt`: the hex-encoded final state hash
+//! - `schema_versions.json`: the state schema and bundle format versions
+//! 
+//! External facts and entities cannot be serialized; rule sets that depend on
+//! them cannot be verified from a bundle. The state must also hash the same
+//! after a JSON round trip, so states holding a `HashMap` should hash with
+//! `IterationStrategy::Sorted`.
+
+use crate::context::{ExecutionContext, ReproducibilityConfig};
+use crate::error::{ProcessingError, SerializationError};
+use crate::replay_engine::ReplayEngine;
+use crate::traits::{RuleSet, State, Transaction};
+use crate::types::StateHash;
+use serde::{Deserialize, Serialize};
+use std::io::{Cursor, Read, Write};
+use std::path::Path;
+use zip::write::SimpleFileOptions;
+use zip::{ZipArchive, ZipWriter};
+
+/// Version of the bundle layout, bumped whenever an entry is added or changed
+pub const BUNDLE_FORMAT_VERSION: u32 = 1;
+
+const INITIAL_STATE: &str = "initial_state.json";
+const TRANSACTIONS: &str = "transactions.ndjson";
+const CONTEXT: &str = "context.json";
+const RULE_SET_VERSION: &str = "rule_set_version.txt";
+const EXPECTED_FINAL_HASH: &str = "expected_final_hash.txt";
+const SCHEMA_VERSIONS: &str = "schema_versions.json";
+
+/// Schema versions recorded in `schema_versions.json`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BundleSchemaVersions {
+    pub state_schema_version: u32,
+    pub bundle_format_version: u32,
+}
+
+/// A ZIP archive packaging a replay for independent verification
+#[derive(Debug, Clone)]
+pub struct ReproducibilityBundle {
+    archive: Vec<u8>,
+    expected_final_hash: StateHash,
+}
+
+impl ReproducibilityBundle {
+    /// Package a replay's inputs and its final hash
+    pub(crate) fn package<S: State, T: Transaction>(
+        initial_state: &S,
+        transactions: &[T],
+        context: &ExecutionContext,
+        rule_set_version: &str,
+        expected_final_hash: StateHash,
+    ) -> Result<Self, SerializationError> {
+        let mut ndjson = String::new();
+        for transaction in transactions {
+            ndjson.push_str(&to_json(transaction)?);
+            ndjson.push('\n');
+        }
+        let schema_versions = BundleSchemaVersions {
+            state_schema_version: S::SCHEMA_VERSION,
+            bundle_format_version: BUNDLE_FORMAT_VERSION,
+        };
+        
+        let entries = [
+            (INITIAL_STATE, to_json(initial_state)?),
+            (TRANSACTIONS, ndjson),
+            (CONTEXT, to_json(&context.to_reproducibility_config())?),
+            (RULE_SET_VERSION, rule_set_version.to_string()),
+            (EXPECTED_FINAL_HASH, expected_final_hash.to_string()),
+            (SCHEMA_VERSIONS, to_json(&schema_versions)?),
+        ];
+        
+        let zip_error = |e: zip::result::ZipError| SerializationError::SerializationFailed {
+            reason: format!("Failed to write reproducibility bundle: {}", e),
+        };
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        // A fixed modification time keeps bundles of the same replay byte-identical
+        let options = SimpleFileOptions::default().last_modified_time(zip::DateTime::default());
+        for (name, contents) in entries {
+            writer.start_file(name, options).map_err(zip_error)?;
+            writer.write_all(contents.as_bytes()).map_err(|e| SerializationError::SerializationFailed {
+                reason: format!("Failed to write {} to the reproducibility bundle: {}", name, e),
+            })?;
+        }
+        let archive = writer.finish().map_err(zip_error)?.into_inner();
+        
+        Ok(Self { archive, expected_final_hash })
+    }
+    
+    /// Get the final hash the bundled replay is expected to reach
+    pub fn expected_final_hash(&self) -> StateHash {
+        self.expected_final_hash
+    }
+    
+    /// Get the ZIP archive bytes
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.archive
+    }
+    
+    /// Write the ZIP archive to a file
+    pub fn write_to(&self, path: &Path) -> std::io::Result<()> {
+        std::fs::write(path, &self.archive)
+    }
+    
+    /// Load a bundle from disk, replay it with `rule_set` and check the final hash
+    /// 
+    /// Returns `Ok(false)` if the final hash differs or `rule_set` has a
+    /// different version from the one recorded in the bundle. An initial state
+    /// saved under an older schema version is upgraded with `State::migrate`.
+    /// 
+    /// # Errors
+    /// Returns `ProcessingError::TransactionFailed` for the pseudo-transaction
+    /// `bundle` if the bundle cannot be read, and the replay's error if a
+    /// transaction fails.
+    pub fn verify<S, T, R>(bundle_path: &Path, rule_set: &R) -> Result<bool, ProcessingError>
+    where
+        S: State,
+        T: Transaction,
+        R: RuleSet<S, T>,
+    {
+        let bytes = std::fs::read(bundle_path)
+            .map_err(|e| bundle_error(format!("Failed to read {}: {}", bundle_path.display(), e)))?;
+        let mut archive = ZipArchive::new(Cursor::new(bytes))
+            .map_err(|e| bundle_error(format!("Not a ZIP archive: {}", e)))?;
+        
+        if read_entry(&mut archive, RULE_SET_VERSION)?.trim() != rule_set.version().to_string() {
+            return Ok(false);
+        }
+        
+        let schema_versions: BundleSchemaVersions = from_json(&read_entry(&mut archive, SCHEMA_VERSIONS)?, SCHEMA_VERSIONS)?;
+        let raw_state: serde_json::Value = from_json(&read_entry(&mut archive, INITIAL_STATE)?, INITIAL_STATE)?;
+        let initial_state = if schema_versions.state_schema_version == S::SCHEMA_VERSION {
+            from_json_value(raw_state, INITIAL_STATE)?
+        } else {
+            S::migrate(schema_versions.state_schema_version, raw_state)
+                .map_err(|e| bundle_error(format!("Failed to migrate the initial state: {}", e)))?
+        };
+        
+        let transactions = read_entry(&mut archive, TRANSACTIONS)?
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| from_json::<T>(line, TRANSACTIONS))
+            .collect::<Result<Vec<T>, ProcessingError>>()?;
+        let config: ReproducibilityConfig = from_json(&read_entry(&mut archive, CONTEXT)?, CONTEXT)?;
+        let expected_final_hash = read_entry(&mut archive, EXPECTED_FINAL_HASH)?;
+        
+        let engine = ReplayEngine::new(initial_state, rule_set, ExecutionContext::from_reproducibility_config(config));
+        let result = engine.replay(&transactions)?;
+        Ok(result.final_hash.to_string() == expected_final_hash.trim())
+    }
+}
+
+fn to_json<V: Serialize>(value: &V) -> Result<String, SerializationError> {
+    serde_json::to_string(value).map_err(|e| SerializationError::SerializationFailed {
+        reason: format!("Failed to serialize reproducibility bundle entry: {}", e),
+    })
+}
+
+fn bundle_error(reason: String) -> ProcessingError {
+    ProcessingError::TransactionFailed {
+        transaction_id: "bundle".to_string(),
+        reason,
+    }
+}
+
+fn read_entry(archive: &mut ZipArchive<Cursor<Vec<u8>>>, name: &str) -> Result<String, ProcessingError> {
+    let mut entry = archive
+        .by_name(name)
+        .map_err(|e| bundle_error(format!("Missing {}: {}", name, e)))?;
+    let mut contents = String::new();
+    entry
+        .read_to_string(&mut contents)
+        .map_err(|e| bundle_error(format!("Failed to read {}: {}", name, e)))?;
+    Ok(contents)
+}
+
+fn from_json<V: serde::de::DeserializeOwned>(json: &str, name: &str) -> Result<V, ProcessingError> {
+    serde_json::from_str(json).map_err(|e| bundle_error(format!("Invalid {}: {}", name, e)))
+}
+
+fn from_json_value<V: serde::de::DeserializeOwned>(value: serde_json::Value, name: &str) -> Result<V, ProcessingError> {
+    serde_json::from_value(value).map_err(|e| bundle_error(format!("Invalid {}: {}", name, e)))
+}
@@ -5,8 +5,11 @@ use crate::traits::State;
 use crate::types::{
     ImpactAnalysis, ReplayResult, StateDifference, StateHash, StateTransitionInfo, Version,
 };
+use crate::error::ValidationError;
+use regex::Regex;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 
 /// Comprehensive comparison of two replay results
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -270,6 +273,270 @@ impl<S> ResultComparison<S> {
     }
 }
 
+/// How strictly `ReplayEngine::compare_with_baseline` compares two runs
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ComparisonTolerance {
+    /// States must match exactly; a performance comparison is included in the report
+    ExactMatch,
+    /// States must match exactly; timing is neither collected nor reported
+    IgnorePerformance,
+    /// States must match except for the listed dot-separated field paths
+    IgnoreFields(Vec<String>),
+}
+
+impl ComparisonTolerance {
+    /// Check whether two states are equal under this tolerance
+    pub fn states_match<S: State>(&self, baseline: &S, comparison: &S) -> bool {
+        match self {
+            ComparisonTolerance::ExactMatch | ComparisonTolerance::IgnorePerformance => {
+                let hasher = StateHasher::new();
+                hasher.hash(baseline) == hasher.hash(comparison)
+            }
+            ComparisonTolerance::IgnoreFields(paths) => {
+                match (serde_json::to_value(baseline), serde_json::to_value(comparison)) {
+                    (Ok(mut baseline), Ok(mut comparison)) => {
+                        for path in paths {
+                            remove_field(&mut baseline, path);
+                            remove_field(&mut comparison, path);
+                        }
+                        baseline == comparison
+                    }
+                    // States that cannot be represented as JSON fall back to exact comparison
+                    _ => ComparisonTolerance::ExactMatch.states_match(baseline, comparison),
+                }
+            }
+        }
+    }
+
+    /// Whether timing should be collected and reported
+    pub fn includes_performance(&self) -> bool {
+        !matches!(self, ComparisonTolerance::IgnorePerformance)
+    }
+}
+
+/// Remove a dot-separated field path from a JSON value, if present
+fn remove_field(value: &mut serde_json::Value, path: &str) {
+    let mut segments: Vec<&str> = path.split('.').collect();
+    let last = match segments.pop() {
+        Some(last) => last,
+        None => return,
+    };
+
+    let mut current = value;
+    for segment in segments {
+        match current.get_mut(segment) {
+            Some(next) => current = next,
+            None => return,
+        }
+    }
+
+    if let Some(object) = current.as_object_mut() {
+        object.remove(last);
+    }
+}
+
+/// Compares replay results while ignoring fields that are expected to differ
+///
+/// Both results are serialized to JSON and compared field by field. Fields are
+/// named by dot-separated paths such as `execution_trace.checkpoints.0.timestamp`;
+/// numeric segments index into arrays.
+#[derive(Debug, Clone, Default)]
+pub struct ReplayResultComparator {
+    ignored_fields: Vec<String>,
+    ignored_patterns: Vec<Regex>,
+    ignored_types: Vec<IgnoredType>,
+}
+
+/// A type whose values `ReplayResultComparator` skips
+#[derive(Debug, Clone)]
+struct IgnoredType {
+    name: &'static str,
+    matches: fn(&serde_json::Value) -> bool,
+}
+
+/// Outcome of `ReplayResultComparator::compare_functionally`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FunctionalEquivalenceResult {
+    /// True when no compared field differs
+    pub equivalent: bool,
+    /// Paths that were excluded from the comparison, in path order
+    pub ignored_fields: Vec<String>,
+    /// Paths whose values differ, in path order
+    pub differing_fields: Vec<String>,
+}
+
+impl ReplayResultComparator {
+    /// Create a comparator that compares every field
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ignore the given paths and everything below them
+    pub fn ignore_fields(mut self, paths: &[&str]) -> Self {
+        self.ignored_fields.extend(paths.iter().map(|path| path.to_string()));
+        self
+    }
+
+    /// Ignore every path matching one of the regular expressions
+    pub fn ignore_patterns(mut self, regexes: &[&str]) -> Result<Self, ValidationError> {
+        for pattern in regexes {
+            let regex = Regex::new(pattern).map_err(|e| ValidationError::RuleViolated {
+                rule: format!("Invalid ignore pattern {}: {}", pattern, e),
+            })?;
+            self.ignored_patterns.push(regex);
+        }
+        Ok(self)
+    }
+
+    /// Ignore every field holding a value of type `T`, e.g. `DateTime<Utc>`
+    ///
+    /// A JSON value counts as a `T` when it deserializes as one and serializes
+    /// back unchanged, so ignoring a primitive such as `u64` ignores every
+    /// matching number.
+    pub fn ignore_type<T: Serialize + DeserializeOwned>(mut self) -> Self {
+        self.ignored_types.push(IgnoredType {
+            name: std::any::type_name::<T>(),
+            matches: round_trips_as::<T>,
+        });
+        self
+    }
+
+    /// Get the names of the ignored types
+    pub fn ignored_type_names(&self) -> Vec<&'static str> {
+        self.ignored_types.iter().map(|ignored| ignored.name).collect()
+    }
+
+    /// Compare two replay results, skipping ignored fields
+    ///
+    /// A result that cannot be serialized to JSON is reported as differing at
+    /// the empty root path.
+    pub fn compare_functionally<S: Serialize>(
+        &self,
+        a: &ReplayResult<S>,
+        b: &ReplayResult<S>,
+    ) -> FunctionalEquivalenceResult {
+        let mut result = FunctionalEquivalenceResult {
+            equivalent: true,
+            ignored_fields: Vec::new(),
+            differing_fields: Vec::new(),
+        };
+        match (serde_json::to_value(a), serde_json::to_value(b)) {
+            (Ok(a), Ok(b)) => self.compare_values(String::new(), Some(&a), Some(&b), &mut result),
+            _ => result.differing_fields.push(String::new()),
+        }
+        result.ignored_fields.sort();
+        result.differing_fields.sort();
+        result.equivalent = result.differing_fields.is_empty();
+        result
+    }
+
+    /// Check whether two replay results match in every compared field
+    pub fn functionally_equivalent<S: Serialize>(&self, a: &ReplayResult<S>, b: &ReplayResult<S>) -> bool {
+        self.compare_functionally(a, b).equivalent
+    }
+
+    fn compare_values(
+        &self,
+        path: String,
+        a: Option<&serde_json::Value>,
+        b: Option<&serde_json::Value>,
+        result: &mut FunctionalEquivalenceResult,
+    ) {
+        if !path.is_empty() && self.is_ignored(&path, a, b) {
+            result.ignored_fields.push(path);
+            return;
+        }
+
+        let child = |segment: &str| {
+            if path.is_empty() {
+                segment.to_string()
+            } else {
+                format!("{}.{}", path, segment)
+            }
+        };
+        match (a, b) {
+            (Some(serde_json::Value::Object(a)), Some(serde_json::Value::Object(b))) => {
+                let keys: BTreeSet<&String> = a.keys().chain(b.keys()).collect();
+                for key in keys {
+                    self.compare_values(child(key), a.get(key), b.get(key), result);
+                }
+            }
+            (Some(serde_json::Value::Array(a)), Some(serde_json::Value::Array(b))) => {
+                for index in 0..a.len().max(b.len()) {
+                    self.compare_values(child(&index.to_string()), a.get(index), b.get(index), result);
+                }
+            }
+            _ if a != b => result.differing_fields.push(path),
+            _ => {}
+        }
+    }
+
+    fn is_ignored(&self, path: &str, a: Option<&serde_json::Value>, b: Option<&serde_json::Value>) -> bool {
+        self.ignored_fields
+            .iter()
+            .any(|field| path == field || path.strip_prefix(field.as_str()).is_some_and(|rest| rest.starts_with('.')))
+            || self.ignored_patterns.iter().any(|regex| regex.is_match(path))
+            || a.into_iter()
+                .chain(b)
+                .any(|value| self.ignored_types.iter().any(|ignored| (ignored.matches)(value)))
+    }
+}
+
+/// Check whether a JSON value deserializes as a `T` and serializes back unchanged
+fn round_trips_as<T: Serialize + DeserializeOwned>(value: &serde_json::Value) -> bool {
+    serde_json::from_value::<T>(value.clone())
+        .ok()
+        .and_then(|typed| serde_json::to_value(typed).ok())
+        .is_some_and(|round_tripped| &round_tripped == value)
+}
+
+/// Outcome of replaying production transactions against a new rule set
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegressionReport<S> {
+    /// True when no transaction diverged and no transaction newly failed
+    pub passed: bool,
+    /// Indices of transactions after which the states no longer match
+    pub diverging_transactions: Vec<usize>,
+    /// IDs of transactions that now fail but succeeded under the baseline rules
+    pub new_failures: Vec<String>,
+    /// IDs of transactions that failed under the baseline rules but now succeed
+    pub resolved_failures: Vec<String>,
+    pub baseline_final_state: S,
+    pub comparison_final_state: S,
+    /// Timing comparison, absent under `ComparisonTolerance::IgnorePerformance`
+    pub performance_comparison: Option<PerformanceComparison>,
+}
+
+impl<S> RegressionReport<S> {
+    /// Process exit code for CI pipelines
+    ///
+    /// Returns 0 if the check passed, 2 if any transaction newly fails, and 1
+    /// if states diverged without new failures.
+    pub fn exit_code(&self) -> i32 {
+        if !self.new_failures.is_empty() {
+            2
+        } else if !self.passed {
+            1
+        } else {
+            0
+        }
+    }
+
+    /// Generate a summary report
+    pub fn summary(&self) -> String {
+        if self.passed {
+            "No regressions detected".to_string()
+        } else {
+            format!(
+                "Regressions detected: {} diverging transactions, {} new failures, {} resolved failures",
+                self.diverging_transactions.len(),
+                self.new_failures.len(),
+                self.resolved_failures.len()
+            )
+        }
+    }
+}
+
 /// Diff analyzer for detailed state comparison
 pub struct DiffAnalyzer;
 
@@ -333,7 +600,7 @@ mod tests {
     use super::*;
     use crate::error::ValidationError;
     use crate::traits::State;
-    use crate::types::{ExecutionTrace, PerformanceMetrics};
+    use crate::types::{ExecutionTrace, PerformanceMetrics, WatermarkTracker};
     use std::hash::{Hash, Hasher};
 
     #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -366,11 +633,16 @@ mod tests {
                 state_transitions: vec![],
                 rule_applications: vec![],
                 checkpoints: vec![],
+                watermark: WatermarkTracker::new(),
+                mutations: Vec::new(),
+                skipped_transactions: Vec::new(),
+                skipped_indices: Vec::new(),
             },
             performance_metrics: PerformanceMetrics {
                 total_duration_ms: 100,
                 transactions_per_second: 10.0,
                 average_transaction_time_ms: 10.0,
+                breakdown: Default::default(),
             },
         }
     }
@@ -0,0 +1,253 @@
+//! Recording which clauses of a rule set fired while applying a transaction
+//! 
+//! Rule sets mark their branches with `RuleAuditRecorder::record_clause` or
+//! `RuleAuditRecorder::evaluate`. The calls are cheap no-ops unless the rule
+//! set is wrapped in an `InstrumentedRuleSet`, which captures them into the
+//! `AuditRecord` returned by `RuleSet::apply_with_audit`.
+
+use crate::context::ExecutionContext;
+use crate::error::{ProcessingError, RuleError, ValidationError};
+use crate::rule_contract::RuleCondition;
+use crate::side_effects::SideEffectQueue;
+use crate::traits::{RuleSet, State, Transaction};
+use crate::types::{AuditRecord, ReplayCostEstimate, Version};
+use std::cell::RefCell;
+
+thread_local! {
+    static ACTIVE_RECORD: RefCell<Option<AuditRecord>> = const { RefCell::new(None) };
+}
+
+/// Thread-local collector for the clauses a rule set evaluates and fires
+/// 
+/// Clause identifiers are free-form strings; a dotted `area.branch` form such
+/// as `"fee.percentage_path"` keeps audit logs easy to filter. Each identifier
+/// is listed once per record, in the order it was first seen.
+pub struct RuleAuditRecorder;
+
+impl RuleAuditRecorder {
+    /// Record that the branch `clause` was taken
+    pub fn record_clause(clause: &str) {
+        Self::with_active(|record| {
+            push_unique(&mut record.rule_clauses_evaluated, clause);
+            push_unique(&mut record.rule_clauses_fired, clause);
+        });
+    }
+    
+    /// Record that `clause` was evaluated, and that it fired if `condition` holds
+    /// 
+    /// Returns `condition`, so it can wrap the condition of an `if`.
+    pub fn evaluate(clause: &str, condition: bool) -> bool {
+        Self::with_active(|record| {
+            push_unique(&mut record.rule_clauses_evaluated, clause);
+            if condition {
+                push_unique(&mut record.rule_clauses_fired, clause);
+            }
+        });
+        condition
+    }
+    
+    /// Fail with `RuleError::GuardFailed` unless `condition` holds
+    /// 
+    /// The clause is recorded like `evaluate`. Rule sets return the error with
+    /// `?`, since `ProcessingError` converts from `RuleError`, and the
+    /// processor fills in the transaction, rule version and `clause` as the
+    /// error's `RuleErrorContext`.
+    pub fn guard(clause: &str, condition: bool, reason: impl Into<String>) -> Result<(), RuleError> {
+        if Self::evaluate(clause, condition) {
+            Ok(())
+        } else {
+            Err(RuleError::GuardFailed {
+                clause: clause.to_string(),
+                reason: reason.into(),
+            })
+        }
+    }
+    
+    /// Check whether clauses recorded on this thread are currently being captured
+    pub fn is_recording() -> bool {
+        ACTIVE_RECORD.with(|active| active.borrow().is_some())
+    }
+    
+    /// Run `f`, capturing the clauses it records on this thread
+    /// 
+    /// Captures nest: an enclosing capture resumes once `f` returns and does
+    /// not see the clauses recorded inside it.
+    pub fn capture<R>(f: impl FnOnce() -> R) -> (R, AuditRecord) {
+        /// Restores the enclosing capture even if `f` panics
+        struct Restore(Option<AuditRecord>);
+        
+        impl Drop for Restore {
+            fn drop(&mut self) {
+                let previous = self.0.take();
+                ACTIVE_RECORD.with(|active| *active.borrow_mut() = previous);
+            }
+        }
+        
+        let _restore = Restore(ACTIVE_RECORD.with(|active| active.borrow_mut().replace(AuditRecord::default())));
+        let result = f();
+        let record = ACTIVE_RECORD.with(|active| active.borrow_mut().take()).unwrap_or_default();
+        (result, record)
+    }
+    
+    fn with_active(f: impl FnOnce(&mut AuditRecord)) {
+        ACTIVE_RECORD.with(|active| {
+            if let Some(record) = active.borrow_mut().as_mut() {
+                f(record);
+            }
+        });
+    }
+}
+
+fn push_unique(clauses: &mut Vec<String>, clause: &str) {
+    if !clauses.iter().any(|existing| existing == clause) {
+        clauses.push(clause.to_string());
+    }
+}
+
+/// Rule set wrapper that records the clauses fired by the inner rule set
+/// 
+/// Behaves exactly like the inner rule set, except that `apply_with_audit`
+/// captures the clauses recorded through `RuleAuditRecorder` during `apply`.
+#[derive(Debug, Clone)]
+pub struct InstrumentedRuleSet<R> {
+    inner: R,
+}
+
+impl<R> InstrumentedRuleSet<R> {
+    /// Wrap a rule set
+    pub fn new(inner: R) -> Self {
+        Self { inner }
+    }
+    
+    /// Get the wrapped rule set
+    pub fn inner(&self) -> &R {
+        &self.inner
+    }
+    
+    /// Unwrap the rule set
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R, S, T> RuleSet<S, T> for InstrumentedRuleSet<R>
+where
+    S: State,
+    T: Transaction,
+    R: RuleSet<S, T>,
+{
+    fn version(&self) -> Version {
+        self.inner.version()
+    }
+    
+    fn supports_version_range(&self) -> Option<(Version, Version)> {
+        self.inner.supports_version_range()
+    }
+    
+    fn supports_version(&self, version: &Version) -> bool {
+        self.inner.supports_version(version)
+    }
+    
+    fn affects_fields(&self) -> Option<Vec<String>> {
+        self.inner.affects_fields()
+    }
+    
+    fn pre_validate(&self, state: &S, transaction: &T, context: &ExecutionContext) -> Result<(), ValidationError> {
+        self.inner.pre_validate(state, transaction, context)
+    }
+    
+    fn pre_conditions(&self) -> Vec<RuleCondition<S, T>> {
+        self.inner.pre_conditions()
+    }
+    
+    fn post_conditions(&self) -> Vec<RuleCondition<S, T>> {
+        self.inner.post_conditions()
+    }
+    
+    fn idempotency_key(&self, state: &S, transaction: &T, context: &ExecutionContext) -> Option<u64> {
+        self.inner.idempotency_key(state, transaction, context)
+    }
+    
+    fn apply(&self, state: &S, transaction: &T, context: &ExecutionContext) -> Result<S, ProcessingError> {
+        self.inner.apply(state, transaction, context)
+    }
+    
+    fn apply_with_audit(&self, state: &S, transaction: &T, context: &ExecutionContext) -> Result<(S, AuditRecord), ProcessingError> {
+        let (result, record) = RuleAuditRecorder::capture(|| self.inner.apply(state, transaction, context));
+        result.map(|new_state| (new_state, record))
+    }
+    
+    fn describe_transaction(&self, state: &S, transaction: &T, context: &ExecutionContext) -> String {
+        self.inner.describe_transaction(state, transaction, context)
+    }
+    
+    fn enqueue_side_effects(&self, state: &S, transaction: &T, queue: &SideEffectQueue) {
+        self.inner.enqueue_side_effects(state, transaction, queue)
+    }
+    
+    fn enqueue_transition_side_effects(&self, before: &S, after: &S, transaction: &T, context: &ExecutionContext, queue: &SideEffectQueue) {
+        self.inner.enqueue_transition_side_effects(before, after, transaction, context, queue)
+    }
+    
+    fn replay_cost_estimate(&self, transactions: &[T], state: &S) -> ReplayCostEstimate {
+        self.inner.replay_cost_estimate(transactions, state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_clauses_ignored_outside_capture() {
+        assert!(!RuleAuditRecorder::is_recording());
+        RuleAuditRecorder::record_clause("ignored");
+        let ((), record) = RuleAuditRecorder::capture(|| ());
+        assert_eq!(record, AuditRecord::default());
+    }
+    
+    #[test]
+    fn test_capture_records_evaluated_and_fired() {
+        let (fee, record) = RuleAuditRecorder::capture(|| {
+            if RuleAuditRecorder::evaluate("fee.flat_path", false) {
+                50
+            } else if RuleAuditRecorder::evaluate("fee.percentage_path", true) {
+                RuleAuditRecorder::record_clause("fee.percentage_path");
+                100
+            } else {
+                0
+            }
+        });
+        
+        assert_eq!(fee, 100);
+        assert_eq!(record.rule_clauses_evaluated, vec!["fee.flat_path", "fee.percentage_path"]);
+        assert_eq!(record.rule_clauses_fired, vec!["fee.percentage_path"]);
+        assert!(!RuleAuditRecorder::is_recording());
+    }
+    
+    #[test]
+    fn test_nested_capture_restores_outer() {
+        let ((), outer) = RuleAuditRecorder::capture(|| {
+            RuleAuditRecorder::record_clause("outer.before");
+            let ((), inner) = RuleAuditRecorder::capture(|| RuleAuditRecorder::record_clause("inner"));
+            assert_eq!(inner.rule_clauses_fired, vec!["inner"]);
+            RuleAuditRecorder::record_clause("outer.after");
+        });
+        
+        assert_eq!(outer.rule_clauses_fired, vec!["outer.before", "outer.after"]);
+    }
+    
+    #[test]
+    fn test_guard_records_clause_and_fails() {
+        let (result, record) = RuleAuditRecorder::capture(|| {
+            RuleAuditRecorder::guard("limits.ok", true, "unused")?;
+            RuleAuditRecorder::guard("limits.max", false, "too large")
+        });
+        
+        let error = result.unwrap_err();
+        assert_eq!(error.failed_clause(), Some("limits.max"));
+        assert!(error.context().is_none());
+        assert_eq!(record.rule_clauses_evaluated, vec!["limits.ok", "limits.max"]);
+        assert_eq!(record.rule_clauses_fired, vec!["limits.ok"]);
+    }
+}
@@ -0,0 +1,266 @@
+//! Caching rule application results across identical (state, transaction) pairs
+
+use crate::context::ExecutionContext;
+use crate::serialization::to_canonical_json;
+use crate::traits::{RuleSet, State, Transaction};
+use crate::types::StateHash;
+use blake3::Hasher as Blake3Hasher;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::hash::Hash;
+
+/// Results of successful rule applications, keyed by state hash and transaction hash
+/// 
+/// Attach one to a processor with `TransactionProcessor::with_state_cache`;
+/// sharing it behind an `Arc<Mutex<_>>` lets replays of the same sequence
+/// reuse each other's results. The transaction hash covers the serialized
+/// transaction, the rule set version, the context's `context_hash` (see
+/// `ExecutionContext::fingerprint`) and the serialized external facts.
+/// Results are not cached under contexts with entities or with facts of
+/// unregistered types, whose values cannot be serialized. Once
+/// `max_entries` is reached the oldest entry is evicted.
+#[derive(Debug, Clone)]
+pub struct RuleApplicationCache<S> {
+    entries: HashMap<(StateHash, [u8; 32]), (S, StateHash)>,
+    insertion_order: VecDeque<(StateHash, [u8; 32])>,
+    max_entries: usize,
+    hits: u64,
+    misses: u64,
+}
+
+impl<S: State> RuleApplicationCache<S> {
+    /// Create an empty cache holding at most `max_entries` results
+    pub fn new(max_entries: usize) -> Self {
+        Self {
+            entries: HashMap::new(),
+            insertion_order: VecDeque::new(),
+            max_entries,
+            hits: 0,
+            misses: 0,
+        }
+    }
+    
+    /// Hash a transaction together with the rule set version and the context applying it
+    /// 
+    /// Returns `None` if the transaction or the context's external facts
+    /// cannot be serialized, or if the context has entities, in which case
+    /// the result is not cached.
+    pub fn transaction_hash<T, R>(transaction: &T, rule_set: &R, context: &ExecutionContext) -> Option<[u8; 32]>
+    where
+        T: Transaction,
+        R: RuleSet<S, T>,
+    {
+        if !context.entity_resolver().is_empty() {
+            return None;
+        }
+        let facts = context.external_facts().snapshot().ok()?;
+        if !facts.skipped.is_empty() {
+            return None;
+        }
+        let facts_json = to_canonical_json(&facts.facts).ok()?;
+        let json = to_canonical_json(transaction).ok()?;
+        let mut hasher = Blake3Hasher::new();
+        hasher.update(rule_set.version().to_string().as_bytes());
+        hasher.update(b"\0");
+        hasher.update(&context.fingerprint().context_hash.to_le_bytes());
+        hasher.update(facts_json.as_bytes());
+        hasher.update(b"\0");
+        hasher.update(json.as_bytes());
+        Some(*hasher.finalize().as_bytes())
+    }
+    
+    /// Look up the new state and its hash, counting a hit or a miss
+    pub fn get(&mut self, state_hash: StateHash, transaction_hash: [u8; 32]) -> Option<(S, StateHash)> {
+        match self.entries.get(&(state_hash, transaction_hash)) {
+            Some(cached) => {
+                self.hits += 1;
+                Some(cached.clone())
+            }
+            None => {
+                self.misses += 1;
+                None
+            }
+        }
+    }
+    
+    /// Store the result of applying a transaction to the state with `state_hash`
+    pub fn insert(&mut self, state_hash: StateHash, transaction_hash: [u8; 32], new_state: S, new_hash: StateHash) {
+        if self.max_entries == 0 {
+            return;
+        }
+        let key = (state_hash, transaction_hash);
+        if self.entries.insert(key, (new_state, new_hash)).is_none() {
+            self.insertion_order.push_back(key);
+        }
+        while self.entries.len() > self.max_entries {
+            match self.insertion_order.pop_front() {
+                Some(oldest) => {
+                    self.entries.remove(&oldest);
+                }
+                None => break,
+            }
+        }
+    }
+    
+    /// Get the fraction of lookups that were hits, or 0.0 before the first lookup
+    pub fn hit_rate(&self) -> f64 {
+        let lookups = self.hits + self.misses;
+        if lookups == 0 {
+            0.0
+        } else {
+            self.hits as f64 / lookups as f64
+        }
+    }
+    
+    /// Get the number of lookups that found a cached result
+    pub fn hits(&self) -> u64 {
+        self.hits
+    }
+    
+    /// Get the number of lookups that found nothing
+    pub fn misses(&self) -> u64 {
+        self.misses
+    }
+    
+    /// Get the number of cached results
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+    
+    /// Check whether the cache holds no results
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// Map holding at most `capacity` entries, evicting the least recently used one
+/// 
+/// Used by `TransactionProcessor::with_rule_cache_capacity` to remember rule
+/// outputs by `RuleSet::idempotency_key`. Both `get` and `put` count as uses.
+#[derive(Debug, Clone)]
+pub struct LruCache<K, V> {
+    entries: HashMap<K, (V, u64)>,
+    /// Keys by the tick of their last use
+    recency: BTreeMap<u64, K>,
+    tick: u64,
+    capacity: usize,
+}
+
+impl<K: Hash + Eq + Clone, V> LruCache<K, V> {
+    /// Create an empty cache; a capacity of 0 caches nothing
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: HashMap::new(),
+            recency: BTreeMap::new(),
+            tick: 0,
+            capacity,
+        }
+    }
+    
+    /// Look up a value, marking it as the most recently used
+    pub fn get(&mut self, key: &K) -> Option<&V> {
+        let tick = self.next_tick();
+        let (value, last_used) = self.entries.get_mut(key)?;
+        self.recency.remove(last_used);
+        self.recency.insert(tick, key.clone());
+        *last_used = tick;
+        Some(value)
+    }
+    
+    /// Store a value, evicting the least recently used entry when the cache is full
+    pub fn put(&mut self, key: K, value: V) {
+        if self.capacity == 0 {
+            return;
+        }
+        let tick = self.next_tick();
+        if let Some((_, last_used)) = self.entries.insert(key.clone(), (value, tick)) {
+            self.recency.remove(&last_used);
+        }
+        self.recency.insert(tick, key);
+        while self.entries.len() > self.capacity {
+            match self.recency.pop_first() {
+                Some((_, oldest)) => {
+                    self.entries.remove(&oldest);
+                }
+                None => break,
+            }
+        }
+    }
+    
+    /// Get the largest number of entries the cache holds
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+    
+    /// Get the number of cached entries
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+    
+    /// Check whether the cache holds no entries
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+    
+    fn next_tick(&mut self) -> u64 {
+        self.tick += 1;
+        self.tick
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ValidationError;
+    use serde::{Deserialize, Serialize};
+    
+    #[derive(Debug, Clone, Hash, PartialEq, Serialize, Deserialize)]
+    struct Counter {
+        value: i64,
+    }
+    
+    impl State for Counter {
+        fn validate(&self) -> Result<(), ValidationError> {
+            Ok(())
+        }
+    }
+    
+    #[test]
+    fn test_oldest_entry_is_evicted() {
+        let mut cache = RuleApplicationCache::new(2);
+        let hash = |value: u8| StateHash([value; 32]);
+        for key in 0..3u8 {
+            cache.insert(hash(key), [7; 32], Counter { value: key as i64 }, hash(key + 100));
+        }
+        
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get(hash(0), [7; 32]).is_none());
+        assert_eq!(cache.get(hash(2), [7; 32]), Some((Counter { value: 2 }, hash(102))));
+        assert!(cache.get(hash(2), [8; 32]).is_none());
+        assert_eq!((cache.hits(), cache.misses()), (1, 2));
+        assert!((cache.hit_rate() - 1.0 / 3.0).abs() < f64::EPSILON);
+        
+        let mut disabled = RuleApplicationCache::new(0);
+        disabled.insert(hash(0), [7; 32], Counter { value: 0 }, hash(1));
+        assert!(disabled.is_empty());
+        assert_eq!(disabled.hit_rate(), 0.0);
+    }
+    
+    #[test]
+    fn test_least_recently_used_entry_is_evicted() {
+        let mut cache = LruCache::new(2);
+        cache.put(1u64, "one");
+        cache.put(2, "two");
+        // Reading 1 makes 2 the least recently used entry
+        assert_eq!(cache.get(&1), Some(&"one"));
+        cache.put(3, "three");
+        
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get(&2), None);
+        assert_eq!(cache.get(&1), Some(&"one"));
+        assert_eq!(cache.get(&3), Some(&"three"));
+        
+        let mut disabled = LruCache::new(0);
+        disabled.put(1u64, "one");
+        assert!(disabled.is_empty());
+    }
+}
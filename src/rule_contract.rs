@@ -0,0 +1,126 @@
+//! Declarative pre- and post-conditions of rule sets
+//! 
+//! Rule sets list their conditions with `RuleSet::pre_conditions` and
+//! `RuleSet::post_conditions`. With the `debug-contracts` feature, debug
+//! builds check them around every `apply` and fail the transaction with a
+//! `ContractViolationError`; release builds and builds without the feature
+//! never evaluate them.
+
+#[cfg(all(feature = "debug-contracts", debug_assertions))]
+use crate::error::{ConditionType, ContractViolationError};
+#[cfg(all(feature = "debug-contracts", debug_assertions))]
+use crate::traits::Transaction;
+use crate::types::Version;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Predicate of a `RuleCondition`
+pub type ConditionCheck<S, T> = Box<dyn Fn(&S, &T) -> bool>;
+
+/// A condition a rule set promises about a state and a transaction
+/// 
+/// Pre-conditions are checked against the state the transaction is applied
+/// to, post-conditions against the state the rule set produced.
+pub struct RuleCondition<S, T> {
+    pub description: String,
+    pub check: ConditionCheck<S, T>,
+}
+
+impl<S, T> RuleCondition<S, T> {
+    /// Create a condition
+    pub fn new(description: &str, check: impl Fn(&S, &T) -> bool + 'static) -> Self {
+        Self {
+            description: description.to_string(),
+            check: Box::new(check),
+        }
+    }
+    
+    /// Check whether the condition holds
+    pub fn holds(&self, state: &S, transaction: &T) -> bool {
+        (self.check)(state, transaction)
+    }
+}
+
+impl<S, T> fmt::Debug for RuleCondition<S, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RuleCondition")
+            .field("description", &self.description)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Find the first condition that does not hold
+#[cfg(all(feature = "debug-contracts", debug_assertions))]
+pub(crate) fn check_conditions<S, T: Transaction>(
+    condition_type: ConditionType,
+    conditions: &[RuleCondition<S, T>],
+    state: &S,
+    transaction: &T,
+) -> Result<(), ContractViolationError> {
+    match conditions.iter().find(|condition| !condition.holds(state, transaction)) {
+        Some(condition) => Err(ContractViolationError {
+            condition_type,
+            description: condition.description.clone(),
+            transaction_id: transaction.id().to_string(),
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Human-readable contract of a rule set, from `RuleSet::documented_contract`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContractDocumentation {
+    pub rule_version: Version,
+    /// Descriptions of the pre-conditions in declaration order
+    pub pre_conditions: Vec<String>,
+    /// Descriptions of the post-conditions in declaration order
+    pub post_conditions: Vec<String>,
+}
+
+impl ContractDocumentation {
+    /// Document a rule set's conditions
+    pub fn new<S, T>(
+        rule_version: Version,
+        pre_conditions: &[RuleCondition<S, T>],
+        post_conditions: &[RuleCondition<S, T>],
+    ) -> Self {
+        let descriptions = |conditions: &[RuleCondition<S, T>]| {
+            conditions.iter().map(|condition| condition.description.clone()).collect()
+        };
+        Self {
+            rule_version,
+            pre_conditions: descriptions(pre_conditions),
+            post_conditions: descriptions(post_conditions),
+        }
+    }
+    
+    /// Check whether the rule set declares no conditions
+    pub fn is_empty(&self) -> bool {
+        self.pre_conditions.is_empty() && self.post_conditions.is_empty()
+    }
+}
+
+/// Renders the contract as two bulleted lists, e.g.
+/// 
+/// ```text
+/// Rule set 1.0.0
+/// Pre-conditions:
+///   - sender account must be active
+/// Post-conditions:
+///   (none)
+/// ```
+impl fmt::Display for ContractDocumentation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Rule set {}", self.rule_version)?;
+        for (heading, conditions) in [("Pre-conditions", &self.pre_conditions), ("Post-conditions", &self.post_conditions)] {
+            writeln!(f, "{}:", heading)?;
+            if conditions.is_empty() {
+                writeln!(f, "  (none)")?;
+            }
+            for condition in conditions {
+                writeln!(f, "  - {}", condition)?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -1,9 +1,14 @@
 //! Rule set management and versioning
 
 use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::sync::{Arc, RwLock};
+use crate::context::ExecutionContext;
 use crate::traits::{RuleSet, State, Transaction};
-use crate::types::Version;
-use crate::error::RuleError;
+use crate::types::{AuditRecord, ReplayCostEstimate, Version, VersionConstraint};
+use crate::error::{ErrorContext, ProcessingError, RuleError, ValidationDetail, ValidationError};
+use crate::rule_contract::{ContractDocumentation, RuleCondition};
+use crate::side_effects::SideEffectQueue;
 use serde::{Serialize, Deserialize};
 
 /// Metadata about a rule set
@@ -13,6 +18,9 @@ pub struct RuleSetMetadata {
     pub description: String,
     pub author: Option<String>,
     pub created_at: chrono::DateTime<chrono::Utc>,
+    /// JSON paths of every state field the rule set may write, or `None` if unknown
+    #[serde(default)]
+    pub affected_fields: Option<Vec<String>>,
 }
 
 impl RuleSetMetadata {
@@ -22,8 +30,15 @@ impl RuleSetMetadata {
             description,
             author: None,
             created_at: chrono::Utc::now(),
+            affected_fields: None,
         }
     }
+    
+    /// Declare the state fields the rule set may write; see `RuleSet::affects_fields`
+    pub fn with_affected_fields(mut self, fields: Vec<String>) -> Self {
+        self.affected_fields = Some(fields);
+        self
+    }
 }
 
 /// A versioned rule set with metadata
@@ -83,6 +98,7 @@ where
     T: Transaction,
 {
     rule_sets: HashMap<Version, VersionedRuleSet<S, T>>,
+    migration_contracts: HashMap<(Version, Version), MigrationContract<S>>,
 }
 
 impl<S, T> RuleSetRegistry<S, T>
@@ -94,6 +110,7 @@ where
     pub fn new() -> Self {
         Self {
             rule_sets: HashMap::new(),
+            migration_contracts: HashMap::new(),
         }
     }
     
@@ -143,6 +160,28 @@ where
             .collect()
     }
     
+    /// Find the rule sets able to process transactions created with a given version
+    /// 
+    /// Uses `RuleSet::supports_version`, so rule sets advertising a version range
+    /// match any version inside it. Results are ordered by rule set version.
+    pub fn find_compatible(&self, transaction_created_with_version: &Version) -> Vec<&dyn RuleSet<S, T>> {
+        let mut compatible: Vec<&VersionedRuleSet<S, T>> = self.rule_sets
+            .values()
+            .filter(|rs| rs.rules().supports_version(transaction_created_with_version))
+            .collect();
+        compatible.sort_by(|a, b| a.version().cmp(b.version()));
+        compatible.into_iter().map(|rs| rs.rules()).collect()
+    }
+    
+    /// Find the highest registered rule set whose version satisfies a constraint
+    pub fn resolve_satisfying(&self, constraint: &VersionConstraint) -> Option<&dyn RuleSet<S, T>> {
+        self.rule_sets
+            .iter()
+            .filter(|(version, _)| version.satisfies(constraint))
+            .max_by(|(a, _), (b, _)| a.cmp(b))
+            .map(|(_, rs)| rs.rules())
+    }
+    
     /// Remove a rule set by version
     pub fn remove(&mut self, version: &Version) -> Option<VersionedRuleSet<S, T>> {
         self.rule_sets.remove(version)
@@ -184,6 +223,309 @@ where
         self.latest_version()
             .and_then(|v| self.get(v))
     }
+    
+    /// Check each hop of a multi-hop upgrade against sample transactions
+    /// 
+    /// Every hop applies the samples under both of its versions, starting from
+    /// `initial_state` for the first hop and from the state the previous hop's
+    /// newer version produced for later ones, so intermediate states are
+    /// carried forward as in a real upgrade. Transactions a rule set rejects
+    /// are skipped. A hop whose versions are not both registered is reported
+    /// as not holding its invariants.
+    pub fn validate_upgrade_path(
+        &self,
+        path: &[Version],
+        sample_transactions: &[T],
+        initial_state: S,
+        context: &ExecutionContext,
+    ) -> UpgradePathValidation
+    where
+        S: PartialEq,
+    {
+        let mut per_hop_results = Vec::new();
+        let mut state = initial_state;
+        
+        for hop in path.windows(2) {
+            let (from_version, to_version) = (hop[0].clone(), hop[1].clone());
+            let (Some(from_rules), Some(to_rules)) = (self.get(&from_version), self.get(&to_version)) else {
+                per_hop_results.push(HopResult {
+                    from_version,
+                    to_version,
+                    invariants_held: false,
+                    state_size_change: 0,
+                    behavior_changed: true,
+                    newly_rejected: Vec::new(),
+                });
+                continue;
+            };
+            
+            let before = replay_samples(from_rules.rules(), &state, sample_transactions, context);
+            let after = replay_samples(to_rules.rules(), &state, sample_transactions, context);
+            let size = |state: &S| bincode::serialized_size(state).unwrap_or(0) as i64;
+            per_hop_results.push(HopResult {
+                from_version,
+                to_version,
+                invariants_held: before.invariants_held && after.invariants_held,
+                state_size_change: size(&after.state) - size(&before.state),
+                behavior_changed: before.state != after.state,
+                newly_rejected: before.accepted
+                    .into_iter()
+                    .filter(|id| !after.accepted.contains(id))
+                    .collect(),
+            });
+            state = after.state;
+        }
+        
+        UpgradePathValidation {
+            all_valid: per_hop_results.iter().all(|hop| hop.invariants_held),
+            first_breaking_hop: per_hop_results.iter().position(HopResult::is_breaking),
+            per_hop_results,
+        }
+    }
+    
+    /// Set the contract an upgrade from `from` to `to` must keep, replacing any previous one
+    pub fn set_migration_contract(&mut self, from: &Version, to: &Version, contract: MigrationContract<S>) {
+        self.migration_contracts.insert((from.clone(), to.clone()), contract);
+    }
+    
+    /// Get the contract set for an upgrade from `from` to `to`
+    pub fn migration_contract(&self, from: &Version, to: &Version) -> Option<&MigrationContract<S>> {
+        self.migration_contracts.get(&(from.clone(), to.clone()))
+    }
+    
+    /// Check the contract from `from` to `to` against sample transactions
+    /// 
+    /// The transactions are applied in order under `to`, starting from
+    /// `initial_state`; each is also applied under `from` to the same state to
+    /// learn whether the older version would have processed it. Invariants
+    /// are checked on every state `to` produces. A state failing
+    /// `State::validate` is reported as a violation of its own and is not
+    /// carried on to the next transaction, since a processor would reject it.
+    /// Without a contract nothing is checked; a version that is not
+    /// registered is reported as a violation by the pseudo-transaction
+    /// `registry`.
+    pub fn verify_migration_contract(
+        &self,
+        from: &Version,
+        to: &Version,
+        transactions: &[T],
+        initial_state: S,
+        context: &ExecutionContext,
+    ) -> ContractVerificationResult {
+        let mut violations = Vec::new();
+        let contract = match self.migration_contract(from, to) {
+            Some(contract) => contract,
+            None => return ContractVerificationResult { all_passed: true, violations },
+        };
+        let (Some(from_rules), Some(to_rules)) = (self.get(from), self.get(to)) else {
+            let missing = if self.contains(from) { to } else { from };
+            violations.push(ContractViolation {
+                invariant: format!("Rule set version {} is registered", missing),
+                transaction_id: "registry".to_string(),
+            });
+            return ContractVerificationResult { all_passed: false, violations };
+        };
+        
+        let mut state = initial_state;
+        for transaction in transactions {
+            let processed_before = matches!(
+                apply_sample(from_rules.rules(), &state, transaction, context),
+                SampleOutcome::Accepted(_)
+            );
+            let violation = |invariant: &str| ContractViolation {
+                invariant: invariant.to_string(),
+                transaction_id: transaction.id().to_string(),
+            };
+            let (new_state, valid) = match apply_sample(to_rules.rules(), &state, transaction, context) {
+                SampleOutcome::Accepted(new_state) => (new_state, true),
+                SampleOutcome::Invalid(new_state) => {
+                    violations.push(violation(&format!("States produced by rule set version {} are valid", to)));
+                    if processed_before {
+                        violations.extend(contract.processable.iter().map(|description| violation(description)));
+                    }
+                    (new_state, false)
+                }
+                SampleOutcome::Rejected => {
+                    if processed_before {
+                        violations.extend(contract.processable.iter().map(|description| violation(description)));
+                    }
+                    continue;
+                }
+            };
+            violations.extend(
+                contract.invariants
+                    .iter()
+                    .filter(|(_, check)| !check(&new_state))
+                    .map(|(description, _)| violation(description)),
+            );
+            if valid {
+                state = new_state;
+            }
+        }
+        
+        ContractVerificationResult {
+            all_passed: violations.is_empty(),
+            violations,
+        }
+    }
+}
+
+type StateCheck<S> = Box<dyn Fn(&S) -> bool>;
+
+/// Guarantees an upgrade between two rule set versions must keep
+/// 
+/// Checked by `RuleSetRegistry::verify_migration_contract`.
+pub struct MigrationContract<S> {
+    invariants: Vec<(String, StateCheck<S>)>,
+    /// Descriptions of the requirements that the newer version processes whatever the older one does
+    processable: Vec<String>,
+}
+
+impl<S> MigrationContract<S> {
+    /// Create a contract without guarantees
+    pub fn new() -> Self {
+        Self {
+            invariants: Vec::new(),
+            processable: Vec::new(),
+        }
+    }
+    
+    /// Require `check` to hold for every state the newer version produces
+    pub fn add_invariant(&mut self, description: &str, check: impl Fn(&S) -> bool + 'static) -> &mut Self {
+        self.invariants.push((description.to_string(), Box::new(check)));
+        self
+    }
+    
+    /// Require the newer version to process every transaction the older version processes
+    pub fn require_processable(&mut self, description: &str) -> &mut Self {
+        self.processable.push(description.to_string());
+        self
+    }
+    
+    /// Check whether the contract has no guarantees
+    pub fn is_empty(&self) -> bool {
+        self.invariants.is_empty() && self.processable.is_empty()
+    }
+}
+
+impl<S> Default for MigrationContract<S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S> std::fmt::Debug for MigrationContract<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let invariants: Vec<&str> = self.invariants.iter().map(|(description, _)| description.as_str()).collect();
+        f.debug_struct("MigrationContract")
+            .field("invariants", &invariants)
+            .field("processable", &self.processable)
+            .finish()
+    }
+}
+
+/// A guarantee of a `MigrationContract` that a transaction broke
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContractViolation {
+    /// Description of the invariant or requirement
+    pub invariant: String,
+    pub transaction_id: String,
+}
+
+/// Result of `RuleSetRegistry::verify_migration_contract`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContractVerificationResult {
+    pub all_passed: bool,
+    /// Violations in transaction order
+    pub violations: Vec<ContractViolation>,
+}
+
+/// Outcome of applying sample transactions under one rule set version
+struct SampleReplay<S> {
+    state: S,
+    /// IDs of the transactions the rule set accepted, in order
+    accepted: Vec<String>,
+    invariants_held: bool,
+}
+
+fn replay_samples<S: State, T: Transaction>(
+    rules: &dyn RuleSet<S, T>,
+    initial_state: &S,
+    transactions: &[T],
+    context: &ExecutionContext,
+) -> SampleReplay<S> {
+    let mut replay = SampleReplay { state: initial_state.clone(), accepted: Vec::new(), invariants_held: true };
+    for transaction in transactions {
+        match apply_sample(rules, &replay.state, transaction, context) {
+            SampleOutcome::Rejected => {}
+            SampleOutcome::Invalid(_) => replay.invariants_held = false,
+            SampleOutcome::Accepted(new_state) => {
+                replay.state = new_state;
+                replay.accepted.push(transaction.id().to_string());
+            }
+        }
+    }
+    replay
+}
+
+/// Outcome of applying one sample transaction outside a processor
+enum SampleOutcome<S> {
+    /// The transaction, the rule set's guard or the rule set itself rejected it
+    Rejected,
+    /// The rule set produced a state that fails `State::validate`
+    Invalid(S),
+    Accepted(S),
+}
+
+fn apply_sample<S: State, T: Transaction>(
+    rules: &dyn RuleSet<S, T>,
+    state: &S,
+    transaction: &T,
+    context: &ExecutionContext,
+) -> SampleOutcome<S> {
+    if transaction.validate().is_err() || rules.pre_validate(state, transaction, context).is_err() {
+        return SampleOutcome::Rejected;
+    }
+    match rules.apply(state, transaction, context) {
+        Ok(new_state) if new_state.validate().is_ok() => SampleOutcome::Accepted(new_state),
+        Ok(new_state) => SampleOutcome::Invalid(new_state),
+        Err(_) => SampleOutcome::Rejected,
+    }
+}
+
+/// Result of `RuleSetRegistry::validate_upgrade_path`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpgradePathValidation {
+    /// Whether every state produced along the path was valid
+    pub all_valid: bool,
+    pub per_hop_results: Vec<HopResult>,
+    /// Index of the first hop for which `HopResult::is_breaking` holds
+    pub first_breaking_hop: Option<usize>,
+}
+
+/// Effect of upgrading from one rule set version to the next
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HopResult {
+    pub from_version: Version,
+    pub to_version: Version,
+    /// Whether both versions only produced states that pass `State::validate`
+    pub invariants_held: bool,
+    /// Serialized size of the newer version's final state minus the older one's, in bytes
+    pub state_size_change: i64,
+    /// Whether the two versions reached different final states
+    pub behavior_changed: bool,
+    /// Transactions the older version accepted but the newer one rejected
+    pub newly_rejected: Vec<String>,
+}
+
+impl HopResult {
+    /// Check whether the hop produces an invalid state or rejects previously accepted transactions
+    /// 
+    /// A hop that only changes the resulting state, such as a new fee
+    /// schedule, is not breaking.
+    pub fn is_breaking(&self) -> bool {
+        !self.invariants_held || !self.newly_rejected.is_empty()
+    }
 }
 
 impl<S, T> Default for RuleSetRegistry<S, T>
@@ -196,6 +538,362 @@ where
     }
 }
 
+/// Active rule set shared by the clones of a [`HotReloadableRuleSet`]
+type SharedRuleSet<S, T> = Arc<RwLock<Arc<dyn RuleSet<S, T> + Send + Sync>>>;
+
+/// A rule set that can be replaced while the processor using it keeps running
+/// 
+/// Clones share the same inner rule set, so one handle can be given to a
+/// `TransactionProcessor` or `ReplayEngine` while another is kept to call
+/// `reload`. Processors pin the active rules through `pinned_snapshot` once
+/// per transaction, so a transaction in flight during a reload finishes with
+/// the old rules and the next one sees the new rules.
+pub struct HotReloadableRuleSet<S, T>
+where
+    S: State,
+    T: Transaction,
+{
+    inner: SharedRuleSet<S, T>,
+    history: Arc<RwLock<Vec<Version>>>,
+}
+
+impl<S, T> HotReloadableRuleSet<S, T>
+where
+    S: State,
+    T: Transaction,
+{
+    /// Create a reloadable wrapper around an initial rule set
+    pub fn new(rule_set: impl RuleSet<S, T> + Send + Sync + 'static) -> Self {
+        let version = rule_set.version();
+        Self {
+            inner: Arc::new(RwLock::new(Arc::new(rule_set))),
+            history: Arc::new(RwLock::new(vec![version])),
+        }
+    }
+    
+    /// Replace the active rule set, returning the new version
+    /// 
+    /// Rejects a rule set whose version was already active at some point, since
+    /// the execution trace would then attribute different rules to one version.
+    pub fn reload(&self, new_rule_set: impl RuleSet<S, T> + Send + Sync + 'static) -> Result<Version, RuleError> {
+        let version = new_rule_set.version();
+        let mut history = self.history.write().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if history.contains(&version) {
+            return Err(RuleError::VersionConflict {
+                reason: format!("Version {} has already been loaded", version),
+            });
+        }
+        
+        *self.inner.write().unwrap_or_else(|poisoned| poisoned.into_inner()) = Arc::new(new_rule_set);
+        history.push(version.clone());
+        Ok(version)
+    }
+    
+    /// Get every version that has been active, oldest first
+    pub fn version_history(&self) -> Vec<Version> {
+        self.history.read().unwrap_or_else(|poisoned| poisoned.into_inner()).clone()
+    }
+    
+    /// Get the active rule set, which stays usable after a reload replaces it
+    pub fn active(&self) -> Arc<dyn RuleSet<S, T> + Send + Sync> {
+        Arc::clone(&self.inner.read().unwrap_or_else(|poisoned| poisoned.into_inner()))
+    }
+    
+    /// Run a closure against the active rule set
+    fn with_active<U>(&self, f: impl FnOnce(&dyn RuleSet<S, T>) -> U) -> U {
+        f(self.active().as_ref())
+    }
+}
+
+impl<S, T> Clone for HotReloadableRuleSet<S, T>
+where
+    S: State,
+    T: Transaction,
+{
+    fn clone(&self) -> Self {
+        Self {
+            inner: Arc::clone(&self.inner),
+            history: Arc::clone(&self.history),
+        }
+    }
+}
+
+impl<S, T> std::fmt::Debug for HotReloadableRuleSet<S, T>
+where
+    S: State,
+    T: Transaction,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HotReloadableRuleSet")
+            .field("version_history", &self.version_history())
+            .finish()
+    }
+}
+
+impl<S, T> RuleSet<S, T> for HotReloadableRuleSet<S, T>
+where
+    S: State,
+    T: Transaction,
+{
+    fn version(&self) -> Version {
+        self.with_active(|rules| rules.version())
+    }
+    
+    fn supports_version_range(&self) -> Option<(Version, Version)> {
+        self.with_active(|rules| rules.supports_version_range())
+    }
+    
+    fn affects_fields(&self) -> Option<Vec<String>> {
+        self.with_active(|rules| rules.affects_fields())
+    }
+    
+    fn pre_validate(&self, state: &S, transaction: &T, context: &ExecutionContext) -> Result<(), ValidationError> {
+        self.with_active(|rules| rules.pre_validate(state, transaction, context))
+    }
+    
+    fn pre_conditions(&self) -> Vec<RuleCondition<S, T>> {
+        self.with_active(|rules| rules.pre_conditions())
+    }
+    
+    fn post_conditions(&self) -> Vec<RuleCondition<S, T>> {
+        self.with_active(|rules| rules.post_conditions())
+    }
+    
+    fn idempotency_key(&self, state: &S, transaction: &T, context: &ExecutionContext) -> Option<u64> {
+        self.with_active(|rules| rules.idempotency_key(state, transaction, context))
+    }
+    
+    fn apply(&self, state: &S, transaction: &T, context: &ExecutionContext) -> Result<S, ProcessingError> {
+        self.with_active(|rules| rules.apply(state, transaction, context))
+    }
+    
+    fn apply_with_audit(&self, state: &S, transaction: &T, context: &ExecutionContext) -> Result<(S, AuditRecord), ProcessingError> {
+        self.with_active(|rules| rules.apply_with_audit(state, transaction, context))
+    }
+    
+    fn describe_transaction(&self, state: &S, transaction: &T, context: &ExecutionContext) -> String {
+        self.with_active(|rules| rules.describe_transaction(state, transaction, context))
+    }
+    
+    fn enqueue_side_effects(&self, state: &S, transaction: &T, queue: &SideEffectQueue) {
+        self.with_active(|rules| rules.enqueue_side_effects(state, transaction, queue))
+    }
+    
+    fn enqueue_transition_side_effects(&self, before: &S, after: &S, transaction: &T, context: &ExecutionContext, queue: &SideEffectQueue) {
+        self.with_active(|rules| rules.enqueue_transition_side_effects(before, after, transaction, context, queue))
+    }
+    
+    fn replay_cost_estimate(&self, transactions: &[T], state: &S) -> ReplayCostEstimate {
+        self.with_active(|rules| rules.replay_cost_estimate(transactions, state))
+    }
+    
+    fn pinned_snapshot(&self) -> Option<Arc<dyn RuleSet<S, T> + Send + Sync>> {
+        Some(self.active())
+    }
+}
+
+/// Two rule sets applied one after the other, see `RuleSet::compose_sequential`
+/// 
+/// `apply` runs the first rule set, checks the second one's `pre_validate`
+/// against the intermediate state and then applies the second rule set to
+/// it. A failure in either step fails the whole transaction, so the second
+/// rule set never runs after the first fails.
+pub struct SequentialRuleSet<S, T, R1, R2> {
+    first: R1,
+    second: R2,
+    _marker: PhantomData<fn(&S, &T)>,
+}
+
+impl<S, T, R1, R2> SequentialRuleSet<S, T, R1, R2>
+where
+    S: State,
+    T: Transaction,
+    R1: RuleSet<S, T>,
+    R2: RuleSet<S, T>,
+{
+    /// Compose two rule sets; `second` sees the state produced by `first`
+    pub fn new(first: R1, second: R2) -> Self {
+        Self {
+            first,
+            second,
+            _marker: PhantomData,
+        }
+    }
+    
+    /// Get the version of the rule set applied first
+    pub fn first_version(&self) -> Version {
+        self.first.version()
+    }
+    
+    /// Get the version of the rule set applied second
+    pub fn second_version(&self) -> Version {
+        self.second.version()
+    }
+    
+    /// Apply the first rule set and check the second one's preconditions on its result
+    fn apply_first(&self, state: &S, transaction: &T, context: &ExecutionContext) -> Result<(S, AuditRecord), ProcessingError> {
+        let (intermediate, audit) = self.first.apply_with_audit(state, transaction, context)?;
+        self.second.pre_validate(&intermediate, transaction, context).map_err(|e| {
+            let detail = match e {
+                ValidationError::WithDetails { details } => details,
+                other => Box::new(ValidationDetail {
+                    violated_rules: vec![other.to_string()],
+                    field: None,
+                    expected_constraint: None,
+                    actual_value: None,
+                    context: ErrorContext::new().with_rule(self.second.version()),
+                }),
+            };
+            ProcessingError::PreValidationFailed {
+                rule_version: self.second.version(),
+                detail,
+            }
+        })?;
+        Ok((intermediate, audit))
+    }
+}
+
+impl<S, T, R1: Clone, R2: Clone> Clone for SequentialRuleSet<S, T, R1, R2> {
+    fn clone(&self) -> Self {
+        Self {
+            first: self.first.clone(),
+            second: self.second.clone(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<S, T, R1: std::fmt::Debug, R2: std::fmt::Debug> std::fmt::Debug for SequentialRuleSet<S, T, R1, R2> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SequentialRuleSet")
+            .field("first", &self.first)
+            .field("second", &self.second)
+            .finish()
+    }
+}
+
+impl<S, T, R1, R2> RuleSet<S, T> for SequentialRuleSet<S, T, R1, R2>
+where
+    S: State,
+    T: Transaction,
+    R1: RuleSet<S, T>,
+    R2: RuleSet<S, T>,
+{
+    /// The higher of the two versions
+    fn version(&self) -> Version {
+        std::cmp::max(self.first.version(), self.second.version())
+    }
+    
+    /// Both rule sets' fields, or `None` if either may write any field
+    fn affects_fields(&self) -> Option<Vec<String>> {
+        let mut fields = self.first.affects_fields()?;
+        for field in self.second.affects_fields()? {
+            if !fields.contains(&field) {
+                fields.push(field);
+            }
+        }
+        Some(fields)
+    }
+    
+    /// Only the first rule set's preconditions; the second's are checked in `apply`
+    fn pre_validate(&self, state: &S, transaction: &T, context: &ExecutionContext) -> Result<(), ValidationError> {
+        self.first.pre_validate(state, transaction, context)
+    }
+    
+    /// Only the first rule set's pre-conditions, which hold for the state before both
+    fn pre_conditions(&self) -> Vec<RuleCondition<S, T>> {
+        self.first.pre_conditions()
+    }
+    
+    /// Only the second rule set's post-conditions, which hold for the state after both
+    fn post_conditions(&self) -> Vec<RuleCondition<S, T>> {
+        self.second.post_conditions()
+    }
+    
+    /// The first rule set's documented pre-conditions and the second's post-conditions
+    fn documented_contract(&self) -> ContractDocumentation {
+        ContractDocumentation {
+            rule_version: self.version(),
+            pre_conditions: self.first.documented_contract().pre_conditions,
+            post_conditions: self.second.documented_contract().post_conditions,
+        }
+    }
+    
+    /// Both rule sets' keys mixed together, or `None` unless both have one
+    /// 
+    /// The second key is taken for the intermediate state, so this applies
+    /// the first rule set; caching pays off only when the second is the
+    /// expensive part.
+    fn idempotency_key(&self, state: &S, transaction: &T, context: &ExecutionContext) -> Option<u64> {
+        let first_key = self.first.idempotency_key(state, transaction, context)?;
+        let intermediate = self.first.apply(state, transaction, context).ok()?;
+        let second_key = self.second.idempotency_key(&intermediate, transaction, context)?;
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(&first_key.to_le_bytes());
+        hasher.update(&second_key.to_le_bytes());
+        
+        let mut key_bytes = [0u8; 8];
+        key_bytes.copy_from_slice(&hasher.finalize().as_bytes()[..8]);
+        Some(u64::from_le_bytes(key_bytes))
+    }
+    
+    fn apply(&self, state: &S, transaction: &T, context: &ExecutionContext) -> Result<S, ProcessingError> {
+        let (intermediate, _) = self.apply_first(state, transaction, context)?;
+        self.second.apply(&intermediate, transaction, context)
+    }
+    
+    fn apply_with_audit(&self, state: &S, transaction: &T, context: &ExecutionContext) -> Result<(S, AuditRecord), ProcessingError> {
+        let (intermediate, mut audit) = self.apply_first(state, transaction, context)?;
+        let (new_state, second_audit) = self.second.apply_with_audit(&intermediate, transaction, context)?;
+        for clause in second_audit.rule_clauses_evaluated {
+            if !audit.rule_clauses_evaluated.contains(&clause) {
+                audit.rule_clauses_evaluated.push(clause);
+            }
+        }
+        for clause in second_audit.rule_clauses_fired {
+            if !audit.rule_clauses_fired.contains(&clause) {
+                audit.rule_clauses_fired.push(clause);
+            }
+        }
+        Ok((new_state, audit))
+    }
+    
+    /// Both rule sets' descriptions, the second's for the intermediate state
+    /// 
+    /// Applies the first rule set again to get the intermediate state.
+    fn describe_transaction(&self, state: &S, transaction: &T, context: &ExecutionContext) -> String {
+        let first = self.first.describe_transaction(state, transaction, context);
+        match self.first.apply(state, transaction, context) {
+            Ok(intermediate) => format!("{}; {}", first, self.second.describe_transaction(&intermediate, transaction, context)),
+            Err(_) => first,
+        }
+    }
+    
+    /// Only the second rule set's effects, since the first one's need the state before it
+    fn enqueue_side_effects(&self, state: &S, transaction: &T, queue: &SideEffectQueue) {
+        self.second.enqueue_side_effects(state, transaction, queue);
+    }
+    
+    /// Both rule sets' effects, each given the state it produced
+    /// 
+    /// Applies the first rule set again to get the intermediate state.
+    fn enqueue_transition_side_effects(&self, before: &S, after: &S, transaction: &T, context: &ExecutionContext, queue: &SideEffectQueue) {
+        match self.first.apply(before, transaction, context) {
+            Ok(intermediate) => {
+                self.first.enqueue_transition_side_effects(before, &intermediate, transaction, context, queue);
+                self.second.enqueue_transition_side_effects(&intermediate, after, transaction, context, queue);
+            }
+            Err(_) => self.second.enqueue_transition_side_effects(before, after, transaction, context, queue),
+        }
+    }
+    
+    /// The sum of both rule sets' estimates
+    fn replay_cost_estimate(&self, transactions: &[T], state: &S) -> ReplayCostEstimate {
+        let first = self.first.replay_cost_estimate(transactions, state);
+        first.saturating_add(&self.second.replay_cost_estimate(transactions, state))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -296,4 +994,63 @@ mod tests {
         assert!(registry.register(versioned1).is_ok());
         assert!(registry.register(versioned2).is_err());
     }
+    
+    struct Noop;
+    
+    #[async_trait::async_trait]
+    impl crate::side_effects::SideEffect for Noop {
+        async fn execute(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+            Ok(())
+        }
+    }
+    
+    // Adds one, reporting the value it saw in every hook
+    struct CountingRuleSet;
+    
+    impl RuleSet<TestState, TestTransaction> for CountingRuleSet {
+        fn version(&self) -> Version {
+            Version::new(1, 0, 0)
+        }
+        
+        fn idempotency_key(&self, state: &TestState, _transaction: &TestTransaction, _context: &ExecutionContext) -> Option<u64> {
+            u64::try_from(state.value).ok()
+        }
+        
+        fn apply(
+            &self,
+            state: &TestState,
+            _transaction: &TestTransaction,
+            _context: &ExecutionContext,
+        ) -> Result<TestState, ProcessingError> {
+            Ok(TestState { value: state.value + 1 })
+        }
+        
+        fn describe_transaction(&self, state: &TestState, _transaction: &TestTransaction, _context: &ExecutionContext) -> String {
+            format!("from {}", state.value)
+        }
+        
+        fn enqueue_side_effects(&self, state: &TestState, transaction: &TestTransaction, queue: &SideEffectQueue) {
+            queue.enqueue(&format!("{}@{}", transaction.id, state.value), Noop);
+        }
+    }
+    
+    #[test]
+    fn test_sequential_hooks_see_the_intermediate_state() {
+        let rules = CountingRuleSet.compose_sequential(CountingRuleSet);
+        let context = ExecutionContext::new(chrono::DateTime::UNIX_EPOCH, 0);
+        let transaction = TestTransaction { id: "tx1".to_string(), timestamp: context.now() };
+        let before = TestState { value: 0 };
+        let after = rules.apply(&before, &transaction, &context).unwrap();
+        assert_eq!(after, TestState { value: 2 });
+        
+        let queue = SideEffectQueue::new();
+        rules.enqueue_transition_side_effects(&before, &after, &transaction, &context, &queue);
+        assert_eq!(queue.pending_transactions(), vec!["tx1@1", "tx1@2"]);
+        assert_eq!(rules.describe_transaction(&before, &transaction, &context), "from 0; from 1");
+        
+        let key = rules.idempotency_key(&before, &transaction, &context);
+        assert!(key.is_some());
+        assert_ne!(key, rules.idempotency_key(&after, &transaction, &context));
+        assert_eq!(rules.idempotency_key(&TestState { value: -1 }, &transaction, &context), None);
+    }
 }
@@ -0,0 +1,114 @@
+//! Keyed signatures over replay result checksums
+//!
+//! Enabled by the `signing` feature. Where `ReplayResult::checksum` only
+//! catches accidental changes, a signature made with a secret key also shows
+//! that whoever changed the result could not have produced a matching one.
+
+use crate::error::SerializationError;
+use crate::types::ReplayResult;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::fmt;
+
+/// Shared secret for HMAC-SHA256 signatures
+#[derive(Clone)]
+pub struct HmacKey {
+    key: Vec<u8>,
+}
+
+impl HmacKey {
+    /// Create a key from secret bytes of any length
+    pub fn new(key: impl Into<Vec<u8>>) -> Self {
+        Self { key: key.into() }
+    }
+    
+    fn mac(&self, checksum: &[u8; 32]) -> Hmac<Sha256> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(&self.key).expect("HMAC accepts keys of any length");
+        mac.update(checksum);
+        mac
+    }
+}
+
+impl fmt::Debug for HmacKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HmacKey").field("key", &"<redacted>").finish()
+    }
+}
+
+/// A replay result with an HMAC-SHA256 signature over its checksum
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedReplayResult<S> {
+    pub replay_result: ReplayResult<S>,
+    /// Checksum of `replay_result` when it was signed
+    pub checksum: [u8; 32],
+    pub signature: Vec<u8>,
+}
+
+impl<S: Serialize> SignedReplayResult<S> {
+    /// Check that the result is unchanged since signing and the signature was made with `key`
+    pub fn verify(&self, key: &HmacKey) -> bool {
+        self.replay_result.verify_checksum(self.checksum)
+            && key.mac(&self.checksum).verify_slice(&self.signature).is_ok()
+    }
+}
+
+impl<S: Serialize + Clone> ReplayResult<S> {
+    /// Sign the result's checksum with a shared secret
+    /// 
+    /// Fails if the final state cannot be serialized.
+    pub fn sign(&self, key: &HmacKey) -> Result<SignedReplayResult<S>, SerializationError> {
+        let checksum = self.checksum()?;
+        Ok(SignedReplayResult {
+            replay_result: self.clone(),
+            signature: key.mac(&checksum).finalize().into_bytes().to_vec(),
+            checksum,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{ExecutionTrace, PerformanceMetrics, StateHash};
+    
+    fn result(balance: i64) -> ReplayResult<i64> {
+        ReplayResult {
+            final_state: balance,
+            final_hash: StateHash([7; 32]),
+            execution_trace: ExecutionTrace {
+                transactions_processed: 3,
+                state_transitions: Vec::new(),
+                rule_applications: Vec::new(),
+                checkpoints: Vec::new(),
+                watermark: Default::default(),
+                mutations: Vec::new(),
+                skipped_transactions: Vec::new(),
+                skipped_indices: Vec::new(),
+            },
+            performance_metrics: PerformanceMetrics {
+                total_duration_ms: 0,
+                transactions_per_second: 0.0,
+                average_transaction_time_ms: 0.0,
+                breakdown: Default::default(),
+            },
+        }
+    }
+    
+    #[test]
+    fn test_signature_detects_tampering_and_wrong_key() {
+        let key = HmacKey::new(b"site-a-secret".to_vec());
+        let signed = result(100).sign(&key).unwrap();
+        assert!(signed.verify(&key));
+        assert!(!signed.verify(&HmacKey::new(b"other".to_vec())));
+        
+        let mut tampered = signed.clone();
+        tampered.replay_result.final_state = 101;
+        assert!(!tampered.verify(&key));
+        
+        // Re-checksumming without the key does not help
+        let mut forged = tampered;
+        forged.checksum = forged.replay_result.checksum().unwrap();
+        assert!(!forged.verify(&key));
+    }
+}
@@ -0,0 +1,201 @@
+//! Splitting transaction sequences into segments that can be replayed separately
+
+use crate::traits::Transaction;
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// Transactions timestamped strictly before a watermark, in their original order
+#[derive(Debug, Clone)]
+pub struct PreWatermark<T> {
+    pub transactions: Vec<T>,
+    pub watermark: DateTime<Utc>,
+}
+
+/// Transactions timestamped at or after a watermark, in their original order
+#[derive(Debug, Clone)]
+pub struct PostWatermark<T> {
+    pub transactions: Vec<T>,
+    pub watermark: DateTime<Utc>,
+}
+
+/// A group of transactions sharing no entity with any other group
+#[derive(Debug, Clone)]
+pub struct IndependentPartition<T> {
+    /// The transactions, in their original relative order
+    pub transactions: Vec<T>,
+    /// Every entity accessed by the transactions
+    pub entities: BTreeSet<String>,
+}
+
+impl<T> IndependentPartition<T> {
+    /// Check that no entity is accessed by more than one partition
+    /// 
+    /// Partitions that pass can be replayed in parallel, each from the same
+    /// initial state, without one observing the other's changes.
+    pub fn verify_independence(all: &[IndependentPartition<T>]) -> bool {
+        let mut seen: BTreeSet<&str> = BTreeSet::new();
+        all.iter()
+            .flat_map(|partition| partition.entities.iter())
+            .all(|entity| seen.insert(entity.as_str()))
+    }
+}
+
+/// Operations splitting a transaction sequence for parallel replay
+pub struct TransactionSequenceSplitter;
+
+impl TransactionSequenceSplitter {
+    /// Split transactions into those before `watermark` and those at or after it
+    /// 
+    /// Relative order is kept within each segment. The split is only safe to
+    /// replay segment by segment when no pre-watermark transaction depends on
+    /// a post-watermark one, as with sequences sorted by timestamp.
+    pub fn split_at_watermark<T: Transaction>(
+        transactions: &[T],
+        watermark: DateTime<Utc>,
+    ) -> (PreWatermark<T>, PostWatermark<T>) {
+        let (pre, post) = transactions
+            .iter()
+            .cloned()
+            .partition(|transaction| transaction.timestamp() < watermark);
+        (
+            PreWatermark { transactions: pre, watermark },
+            PostWatermark { transactions: post, watermark },
+        )
+    }
+    
+    /// Group transactions so that no two groups access the same entity
+    /// 
+    /// `entity_fn` returns the entities a transaction reads or writes, such as
+    /// account IDs. Transactions sharing an entity, directly or through a
+    /// chain of other transactions, land in the same partition, which keeps
+    /// their original order so dependent transactions still run in sequence.
+    /// Partitions are ordered by their first transaction; a transaction with
+    /// no entities gets a partition of its own.
+    pub fn split_by_entity_independence<T: Transaction>(
+        transactions: &[T],
+        entity_fn: impl Fn(&T) -> Vec<String>,
+    ) -> Vec<IndependentPartition<T>> {
+        let entities: Vec<Vec<String>> = transactions.iter().map(&entity_fn).collect();
+        
+        // Union-find over transaction indices, rooted at the smallest index
+        let mut parent: Vec<usize> = (0..transactions.len()).collect();
+        let mut first_access: HashMap<&str, usize> = HashMap::new();
+        for (index, accessed) in entities.iter().enumerate() {
+            for entity in accessed {
+                match first_access.get(entity.as_str()) {
+                    Some(&other) => union(&mut parent, index, other),
+                    None => {
+                        first_access.insert(entity.as_str(), index);
+                    }
+                }
+            }
+        }
+        
+        let mut groups: BTreeMap<usize, IndependentPartition<T>> = BTreeMap::new();
+        for (index, (transaction, accessed)) in transactions.iter().zip(entities).enumerate() {
+            let partition = groups.entry(find(&mut parent, index)).or_insert_with(|| IndependentPartition {
+                transactions: Vec::new(),
+                entities: BTreeSet::new(),
+            });
+            partition.transactions.push(transaction.clone());
+            partition.entities.extend(accessed);
+        }
+        groups.into_values().collect()
+    }
+}
+
+fn find(parent: &mut [usize], index: usize) -> usize {
+    let mut root = index;
+    while parent[root] != root {
+        root = parent[root];
+    }
+    let mut current = index;
+    while parent[current] != root {
+        let next = parent[current];
+        parent[current] = root;
+        current = next;
+    }
+    root
+}
+
+fn union(parent: &mut [usize], a: usize, b: usize) {
+    let (root_a, root_b) = (find(parent, a), find(parent, b));
+    if root_a != root_b {
+        parent[root_a.max(root_b)] = root_a.min(root_b);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ValidationError;
+    use chrono::Duration;
+    use serde::{Deserialize, Serialize};
+    
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    struct Touch {
+        id: String,
+        entities: Vec<String>,
+        offset_secs: i64,
+    }
+    
+    impl Transaction for Touch {
+        fn id(&self) -> &str {
+            &self.id
+        }
+        
+        fn timestamp(&self) -> DateTime<Utc> {
+            DateTime::<Utc>::UNIX_EPOCH + Duration::seconds(self.offset_secs)
+        }
+        
+        fn validate(&self) -> Result<(), ValidationError> {
+            Ok(())
+        }
+    }
+    
+    fn touch(id: &str, entities: &[&str], offset_secs: i64) -> Touch {
+        Touch {
+            id: id.to_string(),
+            entities: entities.iter().map(|e| e.to_string()).collect(),
+            offset_secs,
+        }
+    }
+    
+    fn ids<T: Transaction>(transactions: &[T]) -> Vec<&str> {
+        transactions.iter().map(|t| t.id()).collect()
+    }
+    
+    #[test]
+    fn test_split_at_watermark_keeps_order() {
+        let transactions = vec![touch("a", &[], 5), touch("b", &[], 20), touch("c", &[], 10), touch("d", &[], 30)];
+        let watermark = DateTime::<Utc>::UNIX_EPOCH + Duration::seconds(20);
+        
+        let (pre, post) = TransactionSequenceSplitter::split_at_watermark(&transactions, watermark);
+        assert_eq!(ids(&pre.transactions), vec!["a", "c"]);
+        assert_eq!(ids(&post.transactions), vec!["b", "d"]);
+        assert_eq!(pre.watermark, watermark);
+    }
+    
+    #[test]
+    fn test_transitively_shared_entities_join_one_partition() {
+        let transactions = vec![
+            touch("t1", &["x", "y"], 0),
+            touch("t2", &["z"], 1),
+            touch("t3", &["w"], 2),
+            touch("t4", &["y", "z"], 3),
+            touch("t5", &[], 4),
+        ];
+        
+        let partitions = TransactionSequenceSplitter::split_by_entity_independence(&transactions, |t| t.entities.clone());
+        assert_eq!(partitions.len(), 3);
+        assert_eq!(ids(&partitions[0].transactions), vec!["t1", "t2", "t4"]);
+        assert_eq!(partitions[0].entities.iter().collect::<Vec<_>>(), vec!["x", "y", "z"]);
+        assert_eq!(ids(&partitions[1].transactions), vec!["t3"]);
+        assert_eq!(ids(&partitions[2].transactions), vec!["t5"]);
+        assert!(IndependentPartition::verify_independence(&partitions));
+        
+        let mut overlapping = partitions.clone();
+        overlapping[1].entities.insert("x".to_string());
+        assert!(!IndependentPartition::verify_independence(&overlapping));
+    }
+}
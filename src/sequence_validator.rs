@@ -0,0 +1,241 @@
+//! Pre-flight integrity checks for transaction sequences
+
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use regex::Regex;
+use crate::error::{ErrorContext, ValidationDetail, ValidationError};
+use crate::traits::Transaction;
+
+/// Structured outcome of running every sequence check
+#[derive(Debug)]
+pub struct ValidationReport {
+    /// Names of the checks that passed
+    pub passed: Vec<String>,
+    /// Anomalies that do not prevent replay
+    pub warnings: Vec<ValidationDetail>,
+    /// Problems that make the sequence unsafe to replay
+    pub errors: Vec<ValidationError>,
+}
+
+impl ValidationReport {
+    /// Check if no errors were found
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+/// Validator that checks a transaction sequence for anomalies before replay
+#[derive(Debug, Clone)]
+pub struct TransactionSequenceValidator<T: Transaction> {
+    id_pattern: Option<String>,
+    _phantom_t: PhantomData<T>,
+}
+
+impl<T: Transaction> TransactionSequenceValidator<T> {
+    /// Create a validator that checks for duplicates and chronological order
+    pub fn new() -> Self {
+        Self {
+            id_pattern: None,
+            _phantom_t: PhantomData,
+        }
+    }
+    
+    /// Also require transaction IDs to match a regular expression in `validate_all`
+    pub fn with_id_pattern(mut self, pattern: &str) -> Self {
+        self.id_pattern = Some(pattern.to_string());
+        self
+    }
+    
+    /// Check that no transaction ID appears more than once
+    pub fn validate_no_duplicates(&self, transactions: &[T]) -> Result<(), Vec<ValidationError>> {
+        let mut first_seen: HashMap<&str, usize> = HashMap::new();
+        let mut errors = Vec::new();
+        
+        for (index, transaction) in transactions.iter().enumerate() {
+            if let Some(first_index) = first_seen.get(transaction.id()) {
+                errors.push(ValidationError::InvalidTransaction {
+                    reason: format!(
+                        "Duplicate transaction ID {} at index {} (first seen at index {})",
+                        transaction.id(), index, first_index
+                    ),
+                });
+            } else {
+                first_seen.insert(transaction.id(), index);
+            }
+        }
+        
+        if errors.is_empty() { Ok(()) } else { Err(errors) }
+    }
+    
+    /// Check that transaction timestamps never decrease
+    pub fn validate_chronological_order(&self, transactions: &[T]) -> Result<(), Vec<ValidationError>> {
+        let errors: Vec<ValidationError> = self.chronological_violations(transactions)
+            .into_iter()
+            .map(ValidationError::with_details)
+            .collect();
+        
+        if errors.is_empty() { Ok(()) } else { Err(errors) }
+    }
+    
+    /// Check that every transaction ID matches a regular expression
+    pub fn validate_id_format(&self, transactions: &[T], pattern: &str) -> Result<(), Vec<ValidationError>> {
+        let regex = Regex::new(pattern).map_err(|e| {
+            vec![ValidationError::RuleViolated {
+                rule: format!("Invalid transaction ID pattern {}: {}", pattern, e),
+            }]
+        })?;
+        
+        let errors: Vec<ValidationError> = transactions
+            .iter()
+            .enumerate()
+            .filter(|(_, transaction)| !regex.is_match(transaction.id()))
+            .map(|(index, transaction)| ValidationError::InvalidTransaction {
+                reason: format!(
+                    "Transaction ID {} at index {} does not match pattern {}",
+                    transaction.id(), index, pattern
+                ),
+            })
+            .collect();
+        
+        if errors.is_empty() { Ok(()) } else { Err(errors) }
+    }
+    
+    /// Run every check and collect the results into a report
+    /// 
+    /// Duplicate IDs and malformed IDs are errors. Out-of-order timestamps are
+    /// reported as warnings because replay still processes transactions in the
+    /// order given.
+    pub fn validate_all(&self, transactions: &[T]) -> ValidationReport {
+        let mut report = ValidationReport {
+            passed: Vec::new(),
+            warnings: Vec::new(),
+            errors: Vec::new(),
+        };
+        
+        match self.validate_no_duplicates(transactions) {
+            Ok(()) => report.passed.push("no_duplicates".to_string()),
+            Err(errors) => report.errors.extend(errors),
+        }
+        
+        let violations = self.chronological_violations(transactions);
+        if violations.is_empty() {
+            report.passed.push("chronological_order".to_string());
+        } else {
+            report.warnings.extend(violations);
+        }
+        
+        if let Some(pattern) = &self.id_pattern {
+            match self.validate_id_format(transactions, pattern) {
+                Ok(()) => report.passed.push("id_format".to_string()),
+                Err(errors) => report.errors.extend(errors),
+            }
+        }
+        
+        report
+    }
+    
+    /// Collect a detail entry for every timestamp that precedes its predecessor
+    fn chronological_violations(&self, transactions: &[T]) -> Vec<ValidationDetail> {
+        transactions
+            .windows(2)
+            .enumerate()
+            .filter(|(_, pair)| pair[1].timestamp() < pair[0].timestamp())
+            .map(|(i, pair)| ValidationDetail {
+                violated_rules: vec!["chronological_order".to_string()],
+                field: Some("timestamp".to_string()),
+                expected_constraint: Some(format!(">= {}", pair[0].timestamp())),
+                actual_value: Some(pair[1].timestamp().to_string()),
+                context: ErrorContext::new().with_transaction(pair[1].id().to_string(), i + 1),
+            })
+            .collect()
+    }
+}
+
+impl<T: Transaction> Default for TransactionSequenceValidator<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{DateTime, TimeZone, Utc};
+    use serde::{Deserialize, Serialize};
+    
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    struct TestTransaction {
+        id: String,
+        timestamp: DateTime<Utc>,
+    }
+    
+    impl Transaction for TestTransaction {
+        fn id(&self) -> &str {
+            &self.id
+        }
+        
+        fn timestamp(&self) -> DateTime<Utc> {
+            self.timestamp
+        }
+        
+        fn validate(&self) -> Result<(), ValidationError> {
+            Ok(())
+        }
+    }
+    
+    fn tx(id: &str, secs: i64) -> TestTransaction {
+        TestTransaction {
+            id: id.to_string(),
+            timestamp: Utc.timestamp_opt(secs, 0).unwrap(),
+        }
+    }
+    
+    #[test]
+    fn test_clean_sequence_passes_all_checks() {
+        let validator = TransactionSequenceValidator::new().with_id_pattern(r"^tx\d+$");
+        let transactions = vec![tx("tx1", 100), tx("tx2", 100), tx("tx3", 200)];
+        
+        let report = validator.validate_all(&transactions);
+        assert!(report.is_valid());
+        assert!(report.warnings.is_empty());
+        assert_eq!(report.passed, vec!["no_duplicates", "chronological_order", "id_format"]);
+    }
+    
+    #[test]
+    fn test_out_of_order_timestamp_is_warning_not_error() {
+        let validator = TransactionSequenceValidator::new();
+        let transactions = vec![tx("tx1", 100), tx("tx2", 50), tx("tx3", 200)];
+        
+        assert!(validator.validate_chronological_order(&transactions).is_err());
+        
+        let report = validator.validate_all(&transactions);
+        assert!(report.is_valid());
+        assert_eq!(report.warnings.len(), 1);
+        assert_eq!(report.warnings[0].context.transaction_id.as_deref(), Some("tx2"));
+        assert_eq!(report.warnings[0].context.transaction_index, Some(1));
+    }
+    
+    #[test]
+    fn test_duplicates_are_errors() {
+        let validator = TransactionSequenceValidator::new();
+        let transactions = vec![tx("tx1", 100), tx("tx2", 200), tx("tx1", 300)];
+        
+        let errors = validator.validate_no_duplicates(&transactions).unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].to_string().contains("first seen at index 0"));
+        
+        assert!(!validator.validate_all(&transactions).is_valid());
+    }
+    
+    #[test]
+    fn test_id_format() {
+        let validator: TransactionSequenceValidator<TestTransaction> = TransactionSequenceValidator::new();
+        let transactions = vec![tx("tx1", 100), tx("bad id", 200)];
+        
+        let errors = validator.validate_id_format(&transactions, r"^tx\d+$").unwrap_err();
+        assert_eq!(errors.len(), 1);
+        
+        // An invalid regular expression is reported rather than panicking
+        assert!(validator.validate_id_format(&transactions, "(").is_err());
+    }
+}
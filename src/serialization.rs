@@ -2,6 +2,7 @@
 
 use crate::error::SerializationError;
 use crate::traits::State;
+use serde::Serialize;
 
 /// Trait for pluggable state serialization
 pub trait StateSerializer: Send + Sync {
@@ -141,6 +142,18 @@ impl SerializationContext {
     }
 }
 
+/// Serialize a value to canonical JSON
+/// 
+/// The value is serialized with `serde_json`, every object's keys are sorted
+/// recursively in byte order of their UTF-8 encoding, and the result is
+/// written compactly with no whitespace between tokens. See
+/// `State::canonical_json` for the full description.
+pub fn to_canonical_json<T: Serialize>(value: &T) -> Result<String, SerializationError> {
+    dtre_core::to_canonical_json(value).map_err(|e| SerializationError::SerializationFailed {
+        reason: format!("Canonical JSON serialization failed: {}", e),
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -235,6 +248,19 @@ mod tests {
         assert!(!json_ctx.matches(&bincode));
     }
     
+    #[test]
+    fn test_canonical_json_sorts_nested_keys() {
+        let value = serde_json::json!({
+            "zeta": 1,
+            "alpha": { "b": [ { "y": true, "x": null } ], "a": "text" },
+        });
+        
+        assert_eq!(
+            to_canonical_json(&value).unwrap(),
+            r#"{"alpha":{"a":"text","b":[{"x":null,"y":true}]},"zeta":1}"#
+        );
+    }
+    
     #[test]
     fn test_serializer_names_and_versions() {
         let bincode = BincodeSerializer::new();
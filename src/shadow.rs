@@ -0,0 +1,178 @@
+//! Canary testing of a rule set alongside the one a replay engine runs
+
+use crate::error::ProcessingError;
+use crate::replay_engine::ReplayEngine;
+use crate::traits::{RuleSet, State, Transaction};
+use crate::types::{PerformanceMetrics, ReplayResult, StateHash};
+use std::cell::Cell;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+#[cfg(target_arch = "wasm32")]
+use web_time::Instant;
+
+/// Receiver of the discrepancies found by a `ShadowReplayEngine`
+type DiscrepancySink<S> = Box<dyn Fn(ShadowDiscrepancy<S>) + Send>;
+
+/// A transaction for which the shadow rule set reached a different state than the primary one
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShadowDiscrepancy<S> {
+    pub transaction_id: String,
+    pub primary_hash: StateHash,
+    /// `None` when the shadow rule set rejected the transaction
+    pub shadow_hash: Option<StateHash>,
+    pub primary_state: S,
+    /// `None` when the shadow rule set rejected the transaction
+    pub shadow_state: Option<S>,
+}
+
+/// Replay engine that also runs a shadow rule set on every transaction, see `ReplayEngine::with_shadow`
+/// 
+/// The primary rule set is authoritative: its results are the replay's
+/// result and its errors stop the replay. The shadow rule set is applied to
+/// the primary state and context before each transaction, so every
+/// discrepancy reflects that transaction alone, and its errors are reported
+/// as discrepancies.
+pub struct ShadowReplayEngine<S, T, R, R2>
+where
+    S: State,
+    T: Transaction,
+    R: RuleSet<S, T>,
+    R2: RuleSet<S, T>,
+{
+    engine: ReplayEngine<S, T, R>,
+    shadow_rule_set: R2,
+    sink: Option<DiscrepancySink<S>>,
+    compared: Cell<usize>,
+    discrepancies: Cell<usize>,
+}
+
+impl<S, T, R, R2> ShadowReplayEngine<S, T, R, R2>
+where
+    S: State,
+    T: Transaction,
+    R: RuleSet<S, T>,
+    R2: RuleSet<S, T>,
+{
+    pub(crate) fn new(engine: ReplayEngine<S, T, R>, shadow_rule_set: R2) -> Self {
+        Self {
+            engine,
+            shadow_rule_set,
+            sink: None,
+            compared: Cell::new(0),
+            discrepancies: Cell::new(0),
+        }
+    }
+    
+    /// Send every discrepancy to `sink` as it is found
+    /// 
+    /// Without a sink, discrepancies are only counted.
+    pub fn with_discrepancy_sink(mut self, sink: impl Fn(ShadowDiscrepancy<S>) + Send + 'static) -> Self {
+        self.sink = Some(Box::new(sink));
+        self
+    }
+    
+    /// Get the engine running the primary rule set
+    pub fn primary(&self) -> &ReplayEngine<S, T, R> {
+        &self.engine
+    }
+    
+    /// Get the shadow rule set
+    pub fn shadow_rule_set(&self) -> &R2 {
+        &self.shadow_rule_set
+    }
+    
+    /// Replay transactions under the primary rule set, comparing each one with the shadow rule set
+    /// 
+    /// Pre-flight validation, the cost budget, the transaction count limit and
+    /// checkpointing apply to the primary replay as in `ReplayEngine::replay`.
+    pub fn replay(&self, transactions: &[T]) -> Result<ReplayResult<S>, ProcessingError> {
+        self.engine.run_pre_flight_validation(transactions)?;
+        self.engine.check_cost_budget(self.engine.rule_set(), transactions, self.engine.initial_state())?;
+        let start_time = Instant::now();
+        
+        let mut processor = self.engine.new_processor()?;
+        let context = self.engine.context().with_detached_clock();
+        for (index, transaction) in transactions.iter().enumerate() {
+            // Snapshot the context too, since processing the transaction ticks its clock
+            let mut shadow = processor.state_manager().fork_state();
+            let shadow_context = context.with_detached_clock();
+            let transition = processor.process_transaction(transaction, self.engine.rule_set(), &context)?;
+            if let Some(interval) = self.engine.checkpoint_interval() {
+                if interval > 0 && (index + 1) % interval == 0 {
+                    processor.record_checkpoint(transaction.timestamp());
+                }
+            }
+            
+            let shadow_transition = shadow
+                .apply_transaction(transaction, &self.shadow_rule_set, &shadow_context)
+                .ok();
+            self.compared.set(self.compared.get() + 1);
+            if shadow_transition.as_ref().is_some_and(|shadow| shadow.to_hash == transition.to_hash) {
+                continue;
+            }
+            self.discrepancies.set(self.discrepancies.get() + 1);
+            if let Some(sink) = &self.sink {
+                sink(ShadowDiscrepancy {
+                    transaction_id: transaction.id().to_string(),
+                    primary_hash: transition.to_hash,
+                    shadow_hash: shadow_transition.as_ref().map(|shadow| shadow.to_hash),
+                    primary_state: transition.to_state,
+                    shadow_state: shadow_transition.map(|shadow| shadow.to_state),
+                });
+            }
+        }
+        
+        let duration_ms = start_time.elapsed().as_millis() as u64;
+        let performance_metrics = PerformanceMetrics {
+            total_duration_ms: duration_ms,
+            transactions_per_second: if duration_ms > 0 {
+                transactions.len() as f64 / (duration_ms as f64 / 1000.0)
+            } else {
+                0.0
+            },
+            average_transaction_time_ms: if !transactions.is_empty() {
+                duration_ms as f64 / transactions.len() as f64
+            } else {
+                0.0
+            },
+            breakdown: processor.overhead_breakdown(),
+        };
+        
+        let final_hash = processor.current_hash();
+        let (final_state, execution_trace) = processor.into_result();
+        Ok(ReplayResult {
+            final_state,
+            final_hash,
+            execution_trace,
+            performance_metrics,
+        })
+    }
+    
+    /// Get the share of compared transactions with a discrepancy, across all replays
+    /// 
+    /// Returns 0.0 before any transaction was compared.
+    pub fn discrepancy_rate(&self) -> f64 {
+        match self.compared.get() {
+            0 => 0.0,
+            compared => self.discrepancies.get() as f64 / compared as f64,
+        }
+    }
+}
+
+impl<S, T, R, R2> std::fmt::Debug for ShadowReplayEngine<S, T, R, R2>
+where
+    S: State + std::fmt::Debug,
+    T: Transaction + std::fmt::Debug,
+    R: RuleSet<S, T> + std::fmt::Debug,
+    R2: RuleSet<S, T> + std::fmt::Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ShadowReplayEngine")
+            .field("engine", &self.engine)
+            .field("shadow_rule_set", &self.shadow_rule_set)
+            .field("has_sink", &self.sink.is_some())
+            .field("compared", &self.compared.get())
+            .field("discrepancies", &self.discrepancies.get())
+            .finish()
+    }
+}
@@ -0,0 +1,161 @@
+//! Post-transaction side effects that run outside the deterministic engine
+//!
+//! Rule sets describe side effects (notifications, external writes, emitted
+//! events) by enqueueing them; nothing is executed while transactions are being
+//! applied. Once a replay has finished, the caller drains the queue to run the
+//! effects. Side effects are never recorded or replayed: a replay simply
+//! enqueues them again.
+
+use async_trait::async_trait;
+use futures::stream::{self, Stream, StreamExt};
+use std::error::Error;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+/// An effect to be executed after successful transaction processing
+#[async_trait]
+pub trait SideEffect: Send + Sync {
+    /// Execute the side effect
+    async fn execute(&self) -> Result<(), Box<dyn Error + Send + Sync>>;
+}
+
+/// Outcome of executing a single side effect
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SideEffectResult {
+    /// ID of the transaction that enqueued the effect
+    pub transaction_id: String,
+    /// Position of the effect in the queue when it was drained
+    pub sequence: usize,
+    /// `Err` carries the rendered error returned by the effect
+    pub outcome: Result<(), String>,
+}
+
+impl SideEffectResult {
+    /// Check whether the effect executed successfully
+    pub fn is_success(&self) -> bool {
+        self.outcome.is_ok()
+    }
+}
+
+struct QueuedEffect {
+    transaction_id: String,
+    effect: Box<dyn SideEffect>,
+}
+
+/// Queue collecting side effects during replay
+/// 
+/// Cloning the queue yields another handle to the same underlying storage, so a
+/// caller can keep a handle after attaching the queue to a processor.
+#[derive(Clone, Default)]
+pub struct SideEffectQueue {
+    effects: Arc<Mutex<Vec<QueuedEffect>>>,
+}
+
+impl SideEffectQueue {
+    /// Create an empty side effect queue
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Enqueue an effect on behalf of a transaction
+    pub fn enqueue(&self, transaction_id: &str, effect: impl SideEffect + 'static) {
+        self.lock().push(QueuedEffect {
+            transaction_id: transaction_id.to_string(),
+            effect: Box::new(effect),
+        });
+    }
+    
+    /// Get the number of queued effects
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+    
+    /// Check if the queue is empty
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+    
+    /// Get the transaction IDs of the queued effects, in enqueue order
+    pub fn pending_transactions(&self) -> Vec<String> {
+        self.lock().iter().map(|queued| queued.transaction_id.clone()).collect()
+    }
+    
+    /// Discard all queued effects without executing them
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+    
+    /// Remove all queued effects and execute them in enqueue order
+    /// 
+    /// The queue is emptied immediately; effects run one at a time as the
+    /// returned stream is polled. A failing effect does not stop later ones.
+    pub fn drain(&self) -> impl Stream<Item = SideEffectResult> {
+        let effects = std::mem::take(&mut *self.lock());
+        
+        stream::iter(effects.into_iter().enumerate()).then(|(sequence, queued)| async move {
+            let outcome = queued.effect.execute().await.map_err(|e| e.to_string());
+            SideEffectResult {
+                transaction_id: queued.transaction_id,
+                sequence,
+                outcome,
+            }
+        })
+    }
+    
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<QueuedEffect>> {
+        // A panic while holding the lock cannot leave the Vec half-updated
+        self.effects.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl fmt::Debug for SideEffectQueue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SideEffectQueue")
+            .field("pending", &self.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+    
+    struct Succeed;
+    
+    #[async_trait]
+    impl SideEffect for Succeed {
+        async fn execute(&self) -> Result<(), Box<dyn Error + Send + Sync>> {
+            Ok(())
+        }
+    }
+    
+    struct Fail;
+    
+    #[async_trait]
+    impl SideEffect for Fail {
+        async fn execute(&self) -> Result<(), Box<dyn Error + Send + Sync>> {
+            Err("downstream unavailable".into())
+        }
+    }
+    
+    #[test]
+    fn test_drain_executes_in_order_and_empties_queue() {
+        let queue = SideEffectQueue::new();
+        queue.enqueue("tx1", Succeed);
+        queue.enqueue("tx2", Fail);
+        queue.enqueue("tx3", Succeed);
+        
+        let handle = queue.clone();
+        assert_eq!(handle.len(), 3);
+        
+        let results: Vec<_> = block_on(queue.drain().collect());
+        assert!(queue.is_empty());
+        
+        let ids: Vec<_> = results.iter().map(|r| r.transaction_id.as_str()).collect();
+        assert_eq!(ids, vec!["tx1", "tx2", "tx3"]);
+        assert!(results[0].is_success());
+        assert_eq!(results[1].outcome, Err("downstream unavailable".to_string()));
+        assert!(results[2].is_success());
+    }
+}
@@ -0,0 +1,194 @@
+//! Named, versioned snapshots for disaster recovery
+//! 
+//! A snapshot archive is a ZIP file holding:
+//! 
+//! - `state.json`: the state
+//! - `metadata.json`: the checkpoint and snapshot fields other than the state
+//! - `checksum.sha256`: SHA-256 checksums of both files in `sha256sum` format
+//! 
+//! The state must hash the same after a JSON round trip, so states holding a
+//! `HashMap` should hash with `IterationStrategy::Sorted`.
+
+use crate::error::StateError;
+use crate::state_manager::Checkpoint;
+use crate::traits::State;
+use crate::types::{StateHash, Version};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::io::{self, Cursor, Read, Write};
+use std::path::Path;
+use zip::write::SimpleFileOptions;
+use zip::{ZipArchive, ZipWriter};
+
+/// Version of the snapshot archive layout, bumped whenever an entry is added or changed
+pub const SNAPSHOT_SCHEMA_VERSION: u32 = 1;
+
+const STATE: &str = "state.json";
+const METADATA: &str = "metadata.json";
+const CHECKSUM: &str = "checksum.sha256";
+
+/// A checkpoint with the information needed to restore it after a disaster
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VersionedSnapshot<S> {
+    pub checkpoint: Checkpoint<S>,
+    pub snapshot_name: String,
+    /// Version of the rule set that produced the state
+    pub rule_set_version: Version,
+    /// `SNAPSHOT_SCHEMA_VERSION` of the code that took the snapshot
+    pub schema_version: u32,
+    /// Host name and process ID of the process that took the snapshot, e.g. `db-1:4242`
+    pub created_by: String,
+    pub signature: Option<Vec<u8>>,
+}
+
+/// Contents of `metadata.json`
+#[derive(Serialize, Deserialize)]
+struct SnapshotMetadata {
+    snapshot_name: String,
+    rule_set_version: Version,
+    schema_version: u32,
+    created_by: String,
+    /// Hex-encoded signature
+    signature: Option<String>,
+    hash: StateHash,
+    transaction_index: usize,
+    timestamp: chrono::DateTime<chrono::Utc>,
+    state_schema_version: u32,
+}
+
+impl<S> std::ops::Deref for VersionedSnapshot<S> {
+    type Target = Checkpoint<S>;
+    
+    fn deref(&self) -> &Checkpoint<S> {
+        &self.checkpoint
+    }
+}
+
+impl<S: State> VersionedSnapshot<S> {
+    /// Write the snapshot to a ZIP archive
+    pub fn to_archive(&self, path: &Path) -> io::Result<()> {
+        let metadata = SnapshotMetadata {
+            snapshot_name: self.snapshot_name.clone(),
+            rule_set_version: self.rule_set_version.clone(),
+            schema_version: self.schema_version,
+            created_by: self.created_by.clone(),
+            signature: self.signature.as_ref().map(hex::encode),
+            hash: self.checkpoint.hash,
+            transaction_index: self.checkpoint.transaction_index,
+            timestamp: self.checkpoint.timestamp,
+            state_schema_version: self.checkpoint.state_schema_version,
+        };
+        let state = serde_json::to_string_pretty(&self.checkpoint.state).map_err(invalid_data)?;
+        let metadata = serde_json::to_string_pretty(&metadata).map_err(invalid_data)?;
+        let checksum = format!("{}  {}\n{}  {}\n", sha256_hex(&state), STATE, sha256_hex(&metadata), METADATA);
+        
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        // A fixed modification time keeps archives of the same snapshot byte-identical
+        let options = SimpleFileOptions::default().last_modified_time(zip::DateTime::default());
+        for (name, contents) in [(STATE, state), (METADATA, metadata), (CHECKSUM, checksum)] {
+            writer.start_file(name, options).map_err(io::Error::other)?;
+            writer.write_all(contents.as_bytes())?;
+        }
+        let archive = writer.finish().map_err(io::Error::other)?.into_inner();
+        std::fs::write(path, archive)
+    }
+    
+    /// Read a snapshot written by `to_archive`
+    /// 
+    /// # Errors
+    /// Returns `StateError::CheckpointError` if the archive cannot be read or
+    /// a checksum does not match, `StateError::SchemaMismatch` if the state
+    /// was saved under a different state schema version, and
+    /// `StateError::CheckpointIntegrityFailed` if the state no longer hashes
+    /// to the recorded hash.
+    pub fn from_archive(path: &Path) -> Result<Self, StateError> {
+        let bytes = std::fs::read(path)
+            .map_err(|e| archive_error(format!("Failed to read {}: {}", path.display(), e)))?;
+        let mut archive = ZipArchive::new(Cursor::new(bytes))
+            .map_err(|e| archive_error(format!("Not a ZIP archive: {}", e)))?;
+        let state = read_entry(&mut archive, STATE)?;
+        let metadata = read_entry(&mut archive, METADATA)?;
+        let checksum = read_entry(&mut archive, CHECKSUM)?;
+        
+        for (name, contents) in [(STATE, &state), (METADATA, &metadata)] {
+            let recorded = checksum
+                .lines()
+                .find_map(|line| line.strip_suffix(name)?.strip_suffix("  "))
+                .ok_or_else(|| archive_error(format!("No checksum for {}", name)))?;
+            if recorded != sha256_hex(contents) {
+                return Err(archive_error(format!("Checksum mismatch for {}", name)));
+            }
+        }
+        
+        let metadata: SnapshotMetadata = serde_json::from_str(&metadata)
+            .map_err(|e| archive_error(format!("Invalid {}: {}", METADATA, e)))?;
+        if metadata.state_schema_version != S::SCHEMA_VERSION {
+            return Err(StateError::SchemaMismatch {
+                checkpoint_version: metadata.state_schema_version,
+                current_version: S::SCHEMA_VERSION,
+            });
+        }
+        let signature = metadata.signature
+            .map(|signature| hex::decode(signature).map_err(|e| archive_error(format!("Invalid signature: {}", e))))
+            .transpose()?;
+        let checkpoint = Checkpoint {
+            state: serde_json::from_str(&state).map_err(|e| archive_error(format!("Invalid {}: {}", STATE, e)))?,
+            hash: metadata.hash,
+            transaction_index: metadata.transaction_index,
+            timestamp: metadata.timestamp,
+            state_schema_version: metadata.state_schema_version,
+        };
+        checkpoint.verify_integrity()?;
+        
+        Ok(Self {
+            checkpoint,
+            snapshot_name: metadata.snapshot_name,
+            rule_set_version: metadata.rule_set_version,
+            schema_version: metadata.schema_version,
+            created_by: metadata.created_by,
+            signature,
+        })
+    }
+}
+
+/// Describe the running process as `hostname:pid`
+pub(crate) fn process_identity() -> String {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let hostname = std::env::var("HOSTNAME")
+            .or_else(|_| std::env::var("COMPUTERNAME"))
+            .ok()
+            .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+            .map(|hostname| hostname.trim().to_string())
+            .filter(|hostname| !hostname.is_empty())
+            .unwrap_or_else(|| "unknown".to_string());
+        format!("{}:{}", hostname, std::process::id())
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+        "wasm".to_string()
+    }
+}
+
+fn sha256_hex(contents: &str) -> String {
+    hex::encode(Sha256::digest(contents.as_bytes()))
+}
+
+fn invalid_data(error: serde_json::Error) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
+
+fn archive_error(reason: String) -> StateError {
+    StateError::CheckpointError { reason }
+}
+
+fn read_entry(archive: &mut ZipArchive<Cursor<Vec<u8>>>, name: &str) -> Result<String, StateError> {
+    let mut entry = archive
+        .by_name(name)
+        .map_err(|e| archive_error(format!("Missing {}: {}", name, e)))?;
+    let mut contents = String::new();
+    entry
+        .read_to_string(&mut contents)
+        .map_err(|e| archive_error(format!("Failed to read {}: {}", name, e)))?;
+    Ok(contents)
+}
@@ -1,11 +1,37 @@
 //! State management and transition tracking
 
-use crate::context::ExecutionContext;
-use crate::error::{ProcessingError, StateError};
-use crate::hasher::StateHasher;
+#[cfg(feature = "debug-audit")]
+use crate::audit_log::{AuditLog, AuditLogEntry, AuditOperation};
+use crate::checkpoint_migration::RawCheckpoint;
+use crate::context::{ExecutionContext, ExecutionPhase};
+#[cfg(all(feature = "debug-contracts", debug_assertions))]
+use crate::error::ConditionType;
+use crate::error::{ErrorContext, ProcessingError, SerializationError, StateError, ValidationDetail, ValidationError};
+use crate::hasher::{NormalizedState, StateHasher};
+use crate::snapshot::{process_identity, VersionedSnapshot, SNAPSHOT_SCHEMA_VERSION};
+#[cfg(all(feature = "debug-contracts", debug_assertions))]
+use crate::rule_contract::check_conditions;
 use crate::traits::{RuleSet, State, Transaction};
-use crate::types::{StateHash, StateTransition};
+use crate::types::{ChangeKind, CheckpointInfo, FieldChange, InvariantSeverity, InvariantViolation, OverheadBreakdown, PaginatedResult, StateHash, StateTransition};
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::marker::PhantomData;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+#[cfg(target_arch = "wasm32")]
+use web_time::Instant;
+
+/// Magic number at the start of every binary checkpoint ("DTRE")
+pub const CHECKPOINT_MAGIC: [u8; 4] = [0x44, 0x54, 0x52, 0x45];
+
+/// Version of the binary checkpoint layout, bumped whenever the encoding changes
+pub const CHECKPOINT_FORMAT_VERSION: u16 = 1;
+
+/// Length of the magic number and format version preceding the bincode payload
+const CHECKPOINT_HEADER_LEN: usize = CHECKPOINT_MAGIC.len() + std::mem::size_of::<u16>();
 
 /// Checkpoint representing a state at a specific point in time
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -14,6 +40,89 @@ pub struct Checkpoint<S> {
     pub hash: StateHash,
     pub transaction_index: usize,
     pub timestamp: chrono::DateTime<chrono::Utc>,
+    /// Schema version of the state when the checkpoint was taken
+    #[serde(default = "default_schema_version")]
+    pub state_schema_version: u32,
+}
+
+impl<S: State> Checkpoint<S> {
+    /// Recompute the state hash and check it against the recorded hash
+    /// 
+    /// Detects checkpoints corrupted in storage or transit.
+    pub fn verify_integrity(&self) -> Result<(), StateError> {
+        let computed_hash = StateHasher::new().hash(&self.state);
+        if computed_hash != self.hash {
+            return Err(StateError::CheckpointIntegrityFailed {
+                recorded_hash: self.hash,
+                computed_hash,
+            });
+        }
+        Ok(())
+    }
+    
+    /// Summarize the checkpoint without its state
+    pub fn info(&self) -> CheckpointInfo {
+        CheckpointInfo {
+            transaction_index: self.transaction_index,
+            hash: self.hash,
+            timestamp: self.timestamp,
+            state_schema_version: self.state_schema_version,
+            state_size_bytes: bincode::serialized_size(&self.state).unwrap_or(0),
+        }
+    }
+    
+    /// Encode the checkpoint in a compact binary form
+    /// 
+    /// The output is `CHECKPOINT_MAGIC`, `CHECKPOINT_FORMAT_VERSION` as two
+    /// little-endian bytes, then the bincode encoding of the checkpoint. Unlike
+    /// JSON, field names are not repeated, which keeps large states small.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, SerializationError> {
+        let payload = bincode::serialize(self).map_err(|e| SerializationError::SerializationFailed {
+            reason: format!("Checkpoint could not be encoded: {}", e),
+        })?;
+        let mut bytes = Vec::with_capacity(CHECKPOINT_HEADER_LEN + payload.len());
+        bytes.extend_from_slice(&CHECKPOINT_MAGIC);
+        bytes.extend_from_slice(&CHECKPOINT_FORMAT_VERSION.to_le_bytes());
+        bytes.extend_from_slice(&payload);
+        Ok(bytes)
+    }
+    
+    /// Decode a checkpoint written by `to_bytes`
+    /// 
+    /// Fails if the magic number is missing or the format version differs
+    /// from `CHECKPOINT_FORMAT_VERSION`. The hash is not rechecked; call
+    /// `verify_integrity` for that.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SerializationError> {
+        if bytes.len() < CHECKPOINT_HEADER_LEN || bytes[..CHECKPOINT_MAGIC.len()] != CHECKPOINT_MAGIC {
+            return Err(SerializationError::DeserializationFailed {
+                reason: "Data is not a binary checkpoint: missing DTRE magic number".to_string(),
+            });
+        }
+        let format_version = u16::from_le_bytes([bytes[4], bytes[5]]);
+        if format_version != CHECKPOINT_FORMAT_VERSION {
+            return Err(SerializationError::DeserializationFailed {
+                reason: format!(
+                    "Unsupported binary checkpoint format version {} (expected {})",
+                    format_version, CHECKPOINT_FORMAT_VERSION
+                ),
+            });
+        }
+        bincode::deserialize(&bytes[CHECKPOINT_HEADER_LEN..]).map_err(|e| SerializationError::DeserializationFailed {
+            reason: format!("Checkpoint could not be decoded: {}", e),
+        })
+    }
+    
+    /// Get the length of the `to_bytes` encoding without building it
+    /// 
+    /// Returns just the header length if the state cannot be encoded.
+    pub fn binary_size(&self) -> usize {
+        CHECKPOINT_HEADER_LEN + bincode::serialized_size(self).map_or(0, |size| size as usize)
+    }
+}
+
+/// Checkpoints serialized before schema versioning existed are treated as version 1
+fn default_schema_version() -> u32 {
+    1
 }
 
 /// Difference between two states
@@ -23,18 +132,644 @@ pub struct StateDiff<S> {
     pub to_state: S,
     pub from_hash: StateHash,
     pub to_hash: StateHash,
+    /// Number of transactions processed between the two states, when known
+    #[serde(default)]
+    pub index_diff: i64,
+    /// Transaction indices of the two states, when both came from a `StateManager`
+    #[serde(default)]
+    pub transaction_range: Option<(usize, usize)>,
+}
+
+impl<S: State> StateDiff<S> {
+    /// Merge two diffs taken from the same starting state
+    /// 
+    /// Each diff is turned into a JSON merge patch (RFC 7386) of its `to_state`
+    /// against the shared `from_state`, and both patches are applied to the
+    /// starting state. Arrays and other non-object values are patched whole,
+    /// so two diffs that both change one array conflict. Fields removed or set
+    /// to `null` are removed from the merged JSON before it is deserialized.
+    /// 
+    /// # Errors
+    /// Returns `StateError::MergeConflict` for the first field both diffs
+    /// modified, and `StateError::Mismatch` if the diffs start from different states
+    pub fn merge(a: &StateDiff<S>, b: &StateDiff<S>) -> Result<StateDiff<S>, StateError> {
+        if a.from_hash != b.from_hash {
+            return Err(StateError::Mismatch {
+                expected: a.from_hash.to_string(),
+                actual: b.from_hash.to_string(),
+            });
+        }
+        
+        let (from, patch_a, patch_b) = Self::patches(a, b)?;
+        if let Some(field_path) = conflicting_paths(&patch_a, &patch_b).into_iter().next() {
+            return Err(StateError::MergeConflict { field_path });
+        }
+        
+        let mut merged = from;
+        apply_merge_patch(&mut merged, patch_a);
+        apply_merge_patch(&mut merged, patch_b);
+        let to_state: S = serde_json::from_value(merged).map_err(|e| StateError::TransitionFailed {
+            reason: format!("Merged state could not be deserialized: {}", e),
+        })?;
+        to_state.validate().map_err(|e| StateError::TransitionFailed {
+            reason: format!("Merged state validation failed: {}", e),
+        })?;
+        
+        Ok(StateDiff {
+            from_state: a.from_state.clone(),
+            to_hash: StateHasher::new().hash(&to_state),
+            to_state,
+            from_hash: a.from_hash,
+            index_diff: a.index_diff + b.index_diff,
+            transaction_range: None,
+        })
+    }
+    
+    /// Get the dot-separated paths of fields modified by both this diff and `other`
+    /// 
+    /// Paths are sorted. A change to a whole object conflicts with a change to
+    /// any field inside it. Returns an empty list if either diff cannot be
+    /// serialized to JSON.
+    pub fn conflicts_with(&self, other: &StateDiff<S>) -> Vec<String> {
+        match Self::patches(self, other) {
+            Ok((_, patch_a, patch_b)) => conflicting_paths(&patch_a, &patch_b),
+            Err(_) => Vec::new(),
+        }
+    }
+    
+    /// List the fields that differ between `from_state` and `to_state`
+    pub fn fields_changed(&self) -> Vec<FieldChange> {
+        self.from_state.diff_fields(&self.to_state)
+    }
+    
+    /// Check whether any of the given dot-separated paths changed
+    /// 
+    /// A path matches a change to that field or to any field nested inside it,
+    /// so `"accounts"` matches a change at `"accounts.ACC001.balance"`.
+    pub fn has_changed_fields(&self, paths: &[&str]) -> bool {
+        self.fields_changed().iter().any(|change| {
+            paths.iter().any(|path| {
+                change.path == *path
+                    || change.path.strip_prefix(path).is_some_and(|rest| rest.starts_with('.'))
+            })
+        })
+    }
+    
+    /// Summarize the diff in one line, grouping changes by top-level field
+    /// 
+    /// For example `[tx 10 → tx 50]: 2 accounts modified, 40 transaction_history
+    /// entries added, total_fees_collected changed by +400`. Entries of a map or
+    /// array field are counted as added, removed or modified; a changed
+    /// number reports its delta. Without a `transaction_range` the prefix shows
+    /// the first eight hex digits of both hashes.
+    pub fn summary_line(&self) -> String {
+        let prefix = match self.transaction_range {
+            Some((from, to)) => format!("[tx {} → tx {}]", from, to),
+            None => format!("[{} → {}]", &self.from_hash.to_string()[..8], &self.to_hash.to_string()[..8]),
+        };
+        
+        // Per top-level field: entry keys added, removed and modified, or a whole-field change
+        let mut fields: Vec<(String, FieldSummary)> = Vec::new();
+        for change in self.fields_changed() {
+            let mut segments = change.path.splitn(3, '.');
+            let field = segments.next().unwrap_or_default().to_string();
+            let entry = segments.next().map(str::to_string);
+            let nested = segments.next().is_some();
+            let summary = match fields.iter().position(|(name, _)| *name == field) {
+                Some(index) => &mut fields[index].1,
+                None => {
+                    fields.push((field, FieldSummary::default()));
+                    &mut fields.last_mut().unwrap().1
+                }
+            };
+            match (entry, &change.kind) {
+                (None, kind) => summary.whole = Some(kind.clone()),
+                (Some(entry), ChangeKind::Added { .. }) if !nested => summary.added.push(entry),
+                (Some(entry), ChangeKind::Removed { .. }) if !nested => summary.removed.push(entry),
+                (Some(entry), _) => {
+                    if !summary.modified.contains(&entry) {
+                        summary.modified.push(entry);
+                    }
+                }
+            }
+        }
+        
+        let mut parts = Vec::new();
+        for (field, summary) in &fields {
+            if !summary.modified.is_empty() {
+                parts.push(format!("{} {} modified", summary.modified.len(), field));
+            }
+            if !summary.added.is_empty() {
+                parts.push(format!("{} {} entries added", summary.added.len(), field));
+            }
+            if !summary.removed.is_empty() {
+                parts.push(format!("{} {} entries removed", summary.removed.len(), field));
+            }
+            match &summary.whole {
+                Some(ChangeKind::Modified { old_value, new_value }) => match (old_value.as_i64(), new_value.as_i64()) {
+                    (Some(old), Some(new)) => parts.push(format!("{} changed by {:+}", field, new - old)),
+                    _ => match (old_value.as_f64(), new_value.as_f64()) {
+                        (Some(old), Some(new)) => parts.push(format!("{} changed by {:+}", field, new - old)),
+                        _ => parts.push(format!("{} changed", field)),
+                    },
+                },
+                Some(ChangeKind::Added { .. }) => parts.push(format!("{} added", field)),
+                Some(ChangeKind::Removed { .. }) => parts.push(format!("{} removed", field)),
+                None => {}
+            }
+        }
+        
+        if parts.is_empty() {
+            format!("{}: no changes", prefix)
+        } else {
+            format!("{}: {}", prefix, parts.join(", "))
+        }
+    }
+    
+    /// Serialize the shared starting state and compute both diffs' merge patches
+    fn patches(a: &StateDiff<S>, b: &StateDiff<S>) -> Result<(serde_json::Value, serde_json::Value, serde_json::Value), StateError> {
+        let to_json = |state: &S| serde_json::to_value(state).map_err(|e| StateError::TransitionFailed {
+            reason: format!("State could not be serialized for merging: {}", e),
+        });
+        let from = to_json(&a.from_state)?;
+        let patch_a = merge_patch(&from, &to_json(&a.to_state)?);
+        let patch_b = merge_patch(&from, &to_json(&b.to_state)?);
+        Ok((from, patch_a, patch_b))
+    }
+}
+
+/// Field-level changes turning one state into another, as a JSON merge patch (RFC 7386)
+/// 
+/// Only changed fields are included, so a patch for a transaction touching a
+/// few fields of a large state is much smaller than the state. Applied with
+/// `State::apply_patch`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StatePatch {
+    patch: serde_json::Value,
+    /// Why the patch could not be computed, when either state has no JSON representation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl StatePatch {
+    /// Compute the minimal merge patch turning `before` into `after`
+    /// 
+    /// Unchanged fields are left out and removed fields are set to `null`. If
+    /// either state cannot be represented as JSON, the patch fails to apply.
+    pub fn from_diff<S: State>(before: &S, after: &S) -> Self {
+        match (serde_json::to_value(before), serde_json::to_value(after)) {
+            (Ok(before), Ok(after)) => Self {
+                patch: merge_patch(&before, &after),
+                error: None,
+            },
+            (Err(e), _) | (_, Err(e)) => Self {
+                patch: serde_json::Value::Null,
+                error: Some(e.to_string()),
+            },
+        }
+    }
+    
+    /// Apply the patch to the JSON representation of a state
+    /// 
+    /// Whether the result is still a valid state is checked when it is
+    /// deserialized.
+    /// 
+    /// # Errors
+    /// 
+    /// Returns `SerializationError::SerializationFailed`, leaving `target`
+    /// unchanged, if the patch was computed from a state without a JSON
+    /// representation.
+    pub fn apply_to_json(&self, target: &mut serde_json::Value) -> Result<(), SerializationError> {
+        if let Some(reason) = &self.error {
+            return Err(SerializationError::SerializationFailed {
+                reason: format!("Patch could not be computed: {}", reason),
+            });
+        }
+        apply_merge_patch(target, self.patch.clone());
+        Ok(())
+    }
+    
+    /// Check whether the patch changes nothing
+    pub fn is_empty(&self) -> bool {
+        self.patch.as_object().is_some_and(serde_json::Map::is_empty)
+    }
+    
+    /// Get the size of the patch serialized as JSON
+    pub fn size_bytes(&self) -> usize {
+        self.patch.to_string().len()
+    }
+}
+
+/// Changes under one top-level field, collected by `StateDiff::summary_line`
+#[derive(Default)]
+struct FieldSummary {
+    added: Vec<String>,
+    removed: Vec<String>,
+    modified: Vec<String>,
+    whole: Option<ChangeKind>,
+}
+
+/// Compute the JSON merge patch that turns `from` into `to`
+fn merge_patch(from: &serde_json::Value, to: &serde_json::Value) -> serde_json::Value {
+    use serde_json::{Map, Value};
+    
+    match (from, to) {
+        (Value::Object(from_map), Value::Object(to_map)) => {
+            let mut patch = Map::new();
+            for (key, from_value) in from_map {
+                match to_map.get(key) {
+                    Some(to_value) if to_value == from_value => {}
+                    Some(to_value) => {
+                        patch.insert(key.clone(), merge_patch(from_value, to_value));
+                    }
+                    None => {
+                        patch.insert(key.clone(), Value::Null);
+                    }
+                }
+            }
+            for (key, to_value) in to_map {
+                if !from_map.contains_key(key) {
+                    patch.insert(key.clone(), to_value.clone());
+                }
+            }
+            Value::Object(patch)
+        }
+        _ => to.clone(),
+    }
+}
+
+/// Apply a JSON merge patch in place
+fn apply_merge_patch(target: &mut serde_json::Value, patch: serde_json::Value) {
+    use serde_json::Value;
+    
+    match patch {
+        Value::Object(patch_map) => {
+            if !target.is_object() {
+                *target = Value::Object(serde_json::Map::new());
+            }
+            if let Value::Object(target_map) = target {
+                for (key, value) in patch_map {
+                    if value.is_null() {
+                        target_map.remove(&key);
+                    } else {
+                        apply_merge_patch(target_map.entry(key).or_insert(Value::Null), value);
+                    }
+                }
+            }
+        }
+        other => *target = other,
+    }
+}
+
+/// Collect the paths of the values a merge patch replaces
+fn patched_paths(patch: &serde_json::Value, prefix: &str, paths: &mut Vec<String>) {
+    match patch {
+        serde_json::Value::Object(map) if !map.is_empty() => {
+            for (key, value) in map {
+                let path = if prefix.is_empty() { key.clone() } else { format!("{}.{}", prefix, key) };
+                patched_paths(value, &path, paths);
+            }
+        }
+        _ => paths.push(prefix.to_string()),
+    }
+}
+
+/// Find the paths patched by both patches, treating a parent path as overlapping its children
+fn conflicting_paths(patch_a: &serde_json::Value, patch_b: &serde_json::Value) -> Vec<String> {
+    let paths = |patch: &serde_json::Value| {
+        let mut paths = Vec::new();
+        // An empty patch of an object means the diff changed nothing
+        if patch.as_object().is_none_or(|map| !map.is_empty()) {
+            patched_paths(patch, "", &mut paths);
+        }
+        paths
+    };
+    let paths_a = paths(patch_a);
+    let paths_b = paths(patch_b);
+    
+    let overlaps = |a: &str, b: &str| {
+        a == b
+            || a.is_empty()
+            || b.is_empty()
+            || a.strip_prefix(b).is_some_and(|rest| rest.starts_with('.'))
+            || b.strip_prefix(a).is_some_and(|rest| rest.starts_with('.'))
+    };
+    
+    let mut conflicts: Vec<String> = paths_a
+        .iter()
+        .filter_map(|a| {
+            paths_b
+                .iter()
+                .find(|b| overlaps(a, b))
+                .map(|b| if a.len() <= b.len() { a.clone() } else { b.clone() })
+        })
+        .collect();
+    conflicts.sort();
+    conflicts.dedup();
+    conflicts
+}
+
+/// Combines one field that differs between two states being merged
+/// 
+/// `path` is the dot-separated path of the field, with array indices as
+/// segments; the whole state has the empty path.
+pub trait FieldMerger {
+    fn merge_field(&self, path: &str, a: serde_json::Value, b: serde_json::Value) -> serde_json::Value;
+}
+
+impl<F> FieldMerger for F
+where
+    F: Fn(&str, serde_json::Value, serde_json::Value) -> serde_json::Value,
+{
+    fn merge_field(&self, path: &str, a: serde_json::Value, b: serde_json::Value) -> serde_json::Value {
+        self(path, a, b)
+    }
+}
+
+/// How `StateManager::merge_states` reconciles two diverged states
+/// 
+/// The states are compared as JSON. Fields equal in both are kept as they
+/// are, and a field present in only one state is taken from it, so only the
+/// conflicting values reach the `FieldMerger`. Objects and equal-length
+/// arrays are merged field by field; any other pair of differing values,
+/// including arrays of different lengths, is passed to the merger whole.
+pub struct MergeStrategy<S> {
+    merger: Box<dyn FieldMerger>,
+    merge_validate: bool,
+    _state: PhantomData<fn() -> S>,
+}
+
+impl<S> MergeStrategy<S> {
+    /// Merge conflicting fields with a custom merger
+    pub fn custom(merger: impl FieldMerger + 'static) -> Self {
+        Self {
+            merger: Box::new(merger),
+            merge_validate: false,
+            _state: PhantomData,
+        }
+    }
+    
+    /// Take the value from the second state
+    pub fn last_write_wins() -> Self {
+        Self::custom(|_: &str, _a: serde_json::Value, b: serde_json::Value| b)
+    }
+    
+    /// Add conflicting numbers; other values are taken from the second state
+    /// 
+    /// Integers are added exactly; if the sum overflows, or either value is
+    /// a float, the numbers are added as floats.
+    pub fn sum_numerics() -> Self {
+        Self::custom(|_: &str, a: serde_json::Value, b: serde_json::Value| {
+            let sum = match (a.as_i64(), b.as_i64()) {
+                (Some(x), Some(y)) => x.checked_add(y).map(serde_json::Value::from),
+                _ => None,
+            };
+            sum.or_else(|| Some(serde_json::Value::from(a.as_f64()? + b.as_f64()?)))
+                .unwrap_or(b)
+        })
+    }
+    
+    /// Keep the larger of conflicting numbers; other values are taken from the second state
+    pub fn max_numerics() -> Self {
+        Self::custom(|_: &str, a: serde_json::Value, b: serde_json::Value| {
+            let a_larger = match (a.as_i64(), b.as_i64(), a.as_f64(), b.as_f64()) {
+                (Some(x), Some(y), _, _) => x > y,
+                (_, _, Some(x), Some(y)) => x > y,
+                _ => false,
+            };
+            if a_larger { a } else { b }
+        })
+    }
+    
+    /// Validate the merged state with `State::validate` before returning it
+    pub fn with_merge_validate(mut self, merge_validate: bool) -> Self {
+        self.merge_validate = merge_validate;
+        self
+    }
+    
+    /// Merge two JSON values, descending into objects and equal-length arrays
+    fn merge_value(&self, path: &str, a: serde_json::Value, b: serde_json::Value) -> serde_json::Value {
+        use serde_json::Value;
+        
+        if a == b {
+            return a;
+        }
+        let child = |segment: &str| if path.is_empty() { segment.to_string() } else { format!("{}.{}", path, segment) };
+        match (a, b) {
+            (Value::Object(mut a_map), Value::Object(b_map)) => {
+                for (key, b_value) in b_map {
+                    let merged = match a_map.remove(&key) {
+                        Some(a_value) => self.merge_value(&child(&key), a_value, b_value),
+                        None => b_value,
+                    };
+                    a_map.insert(key, merged);
+                }
+                Value::Object(a_map)
+            }
+            (Value::Array(a_items), Value::Array(b_items)) if a_items.len() == b_items.len() => Value::Array(
+                a_items
+                    .into_iter()
+                    .zip(b_items)
+                    .enumerate()
+                    .map(|(i, (a_item, b_item))| self.merge_value(&child(&i.to_string()), a_item, b_item))
+                    .collect(),
+            ),
+            (a, b) => self.merger.merge_field(path, a, b),
+        }
+    }
+}
+
+/// A single recovered state in a [`StateHistory`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry<S> {
+    pub transaction_index: usize,
+    pub state: S,
+    pub hash: StateHash,
+}
+
+/// Ordered sequence of states recovered at each checkpoint boundary
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StateHistory<S> {
+    entries: Vec<HistoryEntry<S>>,
+}
+
+impl<S> StateHistory<S> {
+    /// Get all history entries in transaction order
+    pub fn entries(&self) -> &[HistoryEntry<S>] {
+        &self.entries
+    }
+    
+    /// Iterate over the recovered states in transaction order
+    pub fn iter_states(&self) -> impl Iterator<Item = &S> {
+        self.entries.iter().map(|entry| &entry.state)
+    }
+    
+    /// Get the recovered state whose transaction index is closest to `tx_index`
+    /// 
+    /// Ties are resolved in favour of the earlier entry.
+    /// 
+    /// # Panics
+    /// Panics if the history is empty
+    pub fn state_at_closest_to(&self, tx_index: usize) -> &S {
+        &self.entries
+            .iter()
+            .min_by_key(|entry| entry.transaction_index.abs_diff(tx_index))
+            .expect("State history is empty")
+            .state
+    }
+    
+    /// Get the number of history entries
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+    
+    /// Check if the history is empty
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// Retention policy applied to stored checkpoints
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum CheckpointPurgePolicy {
+    /// Never purge checkpoints
+    #[default]
+    KeepAll,
+    /// Keep only the N most recently created checkpoints
+    KeepLatestN(usize),
+    /// Keep checkpoints whose timestamp is within the given duration of the newest checkpoint
+    KeepNewerThan(chrono::Duration),
+    /// Keep the N most recently created checkpoints for each state schema version
+    KeepAtMostNPerVersion(usize),
+}
+
+/// Time accumulated in each processing phase, unrounded
+/// 
+/// The phases are the ones of `OverheadBreakdown`. Compare these rather than
+/// the breakdown when a phase may take less than a millisecond in total.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PhaseTimings {
+    pub rule_application: Duration,
+    pub state_hashing: Duration,
+    pub state_validation: Duration,
+    pub checkpoint_creation: Duration,
+    pub observer_callbacks: Duration,
+    pub serialization: Duration,
+}
+
+impl PhaseTimings {
+    /// Round the accumulated times down to whole milliseconds
+    pub fn breakdown(&self) -> OverheadBreakdown {
+        let ms = |duration: Duration| duration.as_millis() as u64;
+        OverheadBreakdown {
+            rule_application_ms: ms(self.rule_application),
+            state_hashing_ms: ms(self.state_hashing),
+            state_validation_ms: ms(self.state_validation),
+            checkpoint_creation_ms: ms(self.checkpoint_creation),
+            observer_callbacks_ms: ms(self.observer_callbacks),
+            serialization_ms: ms(self.serialization),
+        }
+    }
+}
+
+/// Notification sent to `StateManager::watch` receivers when the current state changes
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StateChangeEvent {
+    pub new_hash: StateHash,
+    /// ID of the transaction or state mutation that changed the state
+    pub transaction_id: String,
+    /// Wall-clock time the change was committed
+    pub changed_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Condition a new state must meet to be sent to a watcher
+type WatchPredicate<S> = Box<dyn Fn(&S) -> bool + Send>;
+
+/// Description and check of a condition a new state must meet to be committed
+type NamedPostCondition<'a, S> = (&'a str, &'a dyn Fn(&S) -> bool);
+
+struct StateWatcher<S> {
+    sender: Sender<StateChangeEvent>,
+    predicate: Option<WatchPredicate<S>>,
+}
+
+/// Watchers registered with a `StateManager`
+/// 
+/// Clones start without watchers, so a forked manager does not report its
+/// changes to the original's receivers.
+struct StateWatchers<S>(Mutex<Vec<StateWatcher<S>>>);
+
+impl<S> StateWatchers<S> {
+    fn new() -> Self {
+        Self(Mutex::new(Vec::new()))
+    }
+    
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<StateWatcher<S>>> {
+        self.0.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+    
+    fn register(&self, predicate: Option<WatchPredicate<S>>) -> Receiver<StateChangeEvent> {
+        let (sender, receiver) = mpsc::channel();
+        self.lock().push(StateWatcher { sender, predicate });
+        receiver
+    }
+    
+    /// Send the event to every watcher whose predicate accepts the new state
+    /// 
+    /// Watchers whose receiver was dropped are removed once a send to them fails.
+    fn notify(&self, new_state: &S, new_hash: StateHash, transaction_id: &str) {
+        let mut watchers = self.lock();
+        if watchers.is_empty() {
+            return;
+        }
+        let event = StateChangeEvent {
+            new_hash,
+            transaction_id: transaction_id.to_string(),
+            changed_at: chrono::Utc::now(),
+        };
+        watchers.retain(|watcher| {
+            watcher.predicate.as_ref().is_some_and(|predicate| !predicate(new_state))
+                || watcher.sender.send(event.clone()).is_ok()
+        });
+    }
+}
+
+impl<S> Clone for StateWatchers<S> {
+    fn clone(&self) -> Self {
+        Self::new()
+    }
+}
+
+impl<S> std::fmt::Debug for StateWatchers<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StateWatchers").field("count", &self.lock().len()).finish()
+    }
 }
 
 /// StateManager manages state transitions and checkpoints
 #[derive(Debug, Clone)]
 pub struct StateManager<S: State> {
-    current_state: S,
+    // Shared with forks until either side commits a new state
+    current_state: Arc<S>,
     hasher: StateHasher,
     checkpoints: Vec<Checkpoint<S>>,
     transaction_count: usize,
+    purge_policy: CheckpointPurgePolicy,
+    protected_checkpoints: HashSet<StateHash>,
+    /// Largest serialized size, in bytes, a new state may have
+    max_state_size_bytes: Option<usize>,
+    phase_timings: PhaseTimings,
+    watchers: StateWatchers<S>,
+    /// Soft invariants broken by committed transitions, with the transaction or mutation ID
+    invariant_warnings: Vec<(String, InvariantViolation)>,
+    #[cfg(feature = "debug-audit")]
+    audit_log: Option<AuditLog>,
 }
 
 impl<S: State> StateManager<S> {
+    /// Schema version of the managed state type
+    pub const SCHEMA_VERSION: u32 = S::SCHEMA_VERSION;
+    
     /// Create a new StateManager with an initial state
     pub fn new(initial_state: S) -> Result<Self, StateError> {
         // Validate the initial state
@@ -43,21 +778,163 @@ impl<S: State> StateManager<S> {
         })?;
         
         Ok(Self {
-            current_state: initial_state,
+            current_state: Arc::new(initial_state),
             hasher: StateHasher::new(),
             checkpoints: Vec::new(),
             transaction_count: 0,
+            purge_policy: CheckpointPurgePolicy::KeepAll,
+            protected_checkpoints: HashSet::new(),
+            max_state_size_bytes: None,
+            phase_timings: PhaseTimings::default(),
+            watchers: StateWatchers::new(),
+            invariant_warnings: Vec::new(),
+            #[cfg(feature = "debug-audit")]
+            audit_log: None,
         })
     }
     
+    /// Copy the settings into a manager with no checkpoints, sharing the current state
+    /// 
+    /// The state was validated when it became current, so it is not validated
+    /// again. It is not copied either: each manager replaces its shared state
+    /// when it commits a new one.
+    pub(crate) fn fork_state(&self) -> Self {
+        Self {
+            current_state: Arc::clone(&self.current_state),
+            hasher: self.hasher.clone(),
+            checkpoints: Vec::new(),
+            transaction_count: self.transaction_count,
+            purge_policy: self.purge_policy.clone(),
+            protected_checkpoints: HashSet::new(),
+            max_state_size_bytes: self.max_state_size_bytes,
+            phase_timings: PhaseTimings::default(),
+            watchers: StateWatchers::new(),
+            invariant_warnings: Vec::new(),
+            #[cfg(feature = "debug-audit")]
+            audit_log: None,
+        }
+    }
+    
+    /// Create a manager for a state taken from a trusted source, without validating it
+    pub(crate) fn trusted(state: S, transaction_count: usize) -> Self {
+        Self {
+            current_state: Arc::new(state),
+            hasher: StateHasher::new(),
+            checkpoints: Vec::new(),
+            transaction_count,
+            purge_policy: CheckpointPurgePolicy::KeepAll,
+            protected_checkpoints: HashSet::new(),
+            max_state_size_bytes: None,
+            phase_timings: PhaseTimings::default(),
+            watchers: StateWatchers::new(),
+            invariant_warnings: Vec::new(),
+            #[cfg(feature = "debug-audit")]
+            audit_log: None,
+        }
+    }
+    
+    /// Get the time spent in each phase of applying transactions and creating checkpoints
+    pub fn overhead_breakdown(&self) -> OverheadBreakdown {
+        self.phase_timings.breakdown()
+    }
+    
+    /// Get the unrounded time spent in each phase, see `PhaseTimings`
+    pub fn phase_timings(&self) -> PhaseTimings {
+        self.phase_timings
+    }
+    
+    /// Get mutable access to the accumulated phase times
+    pub(crate) fn phase_timings_mut(&mut self) -> &mut PhaseTimings {
+        &mut self.phase_timings
+    }
+    
+    /// Receive an event for every change of the current state
+    /// 
+    /// Transactions, cached transaction results and state mutations all send
+    /// events; restoring a checkpoint does not. Any number of watchers can be
+    /// registered, and a watcher is removed after its receiver is dropped.
+    pub fn watch(&self) -> Receiver<StateChangeEvent> {
+        self.watchers.register(None)
+    }
+    
+    /// Receive an event for every change of the current state that `predicate` accepts, see `watch`
+    pub fn watch_for(&self, predicate: impl Fn(&S) -> bool + Send + 'static) -> Receiver<StateChangeEvent> {
+        self.watchers.register(Some(Box::new(predicate)))
+    }
+    
+    /// Reject transitions whose new state serializes to more than `limit` bytes
+    /// 
+    /// The size is measured with bincode, as in `CheckpointInfo::state_size_bytes`.
+    /// A rejected transition fails with `ProcessingError::StateSizeLimitExceeded`
+    /// and leaves the state unchanged.
+    pub fn set_max_state_size_bytes(&mut self, limit: usize) {
+        self.max_state_size_bytes = Some(limit);
+    }
+    
+    /// Check the serialized size of a new state against the configured limit
+    fn check_state_size(&mut self, state: &S, transaction_id: &str) -> Result<(), ProcessingError> {
+        let Some(limit) = self.max_state_size_bytes else {
+            return Ok(());
+        };
+        let started = Instant::now();
+        let size = bincode::serialized_size(state).map_or(u64::MAX, |size| size);
+        self.phase_timings.serialization += started.elapsed();
+        if size > limit as u64 {
+            return Err(ProcessingError::StateSizeLimitExceeded {
+                transaction_id: transaction_id.to_string(),
+                size,
+                limit,
+            });
+        }
+        Ok(())
+    }
+    
+    /// Start recording every internal operation in an `AuditLog`
+    #[cfg(feature = "debug-audit")]
+    pub fn enable_audit_mode(&mut self) {
+        self.audit_log.get_or_insert_with(AuditLog::new);
+    }
+    
+    /// Get the operations recorded since audit mode was enabled
+    /// 
+    /// Empty when audit mode is off.
+    #[cfg(feature = "debug-audit")]
+    pub fn audit_log(&self) -> &[AuditLogEntry] {
+        self.audit_log.as_ref().map_or(&[], AuditLog::entries)
+    }
+    
+    /// Record an operation if audit mode is enabled
+    #[cfg(feature = "debug-audit")]
+    pub(crate) fn record_audit(&mut self, operation: AuditOperation) {
+        if let Some(log) = &mut self.audit_log {
+            log.record(operation);
+        }
+    }
+    
+    /// Set the retention policy applied after each checkpoint is created
+    pub fn with_purge_policy(mut self, policy: CheckpointPurgePolicy) -> Self {
+        self.purge_policy = policy;
+        self
+    }
+    
+    /// Get the active checkpoint retention policy
+    pub fn purge_policy(&self) -> &CheckpointPurgePolicy {
+        &self.purge_policy
+    }
+    
     /// Get the current state
     pub fn current_state(&self) -> &S {
         &self.current_state
     }
     
+    /// Get the current state without copying it
+    pub(crate) fn shared_state(&self) -> Arc<S> {
+        Arc::clone(&self.current_state)
+    }
+    
     /// Get the current state hash
     pub fn current_hash(&self) -> StateHash {
-        self.hasher.hash(&self.current_state)
+        self.hasher.hash(self.current_state.as_ref())
     }
     
     /// Apply a transaction to the current state using the provided rule set
@@ -67,35 +944,254 @@ impl<S: State> StateManager<S> {
         rules: &R,
         context: &ExecutionContext,
     ) -> Result<StateTransition<S>, ProcessingError>
+    where
+        T: Transaction,
+        R: RuleSet<S, T>,
+    {
+        self.apply_checked(transaction, rules, context, None)
+    }
+    
+    /// Apply a transaction, committing the new state only if `post_condition` holds for it
+    /// 
+    /// Use this for invariants that span rules, such as conservation of the
+    /// total balance. If the post-condition fails the state is left unchanged
+    /// and `ProcessingError::PostConditionFailed` is returned; see
+    /// `apply_conditional_named` to report a specific condition name.
+    pub fn apply_conditional<T, R, F>(
+        &mut self,
+        transaction: &T,
+        rules: &R,
+        context: &ExecutionContext,
+        post_condition: F,
+    ) -> Result<StateTransition<S>, ProcessingError>
+    where
+        T: Transaction,
+        R: RuleSet<S, T>,
+        F: Fn(&S) -> bool,
+    {
+        self.apply_conditional_named(transaction, rules, context, "post_condition", post_condition)
+    }
+    
+    /// Apply a transaction like `apply_conditional`, naming the condition in the error
+    pub fn apply_conditional_named<T, R, F>(
+        &mut self,
+        transaction: &T,
+        rules: &R,
+        context: &ExecutionContext,
+        condition_name: &str,
+        post_condition: F,
+    ) -> Result<StateTransition<S>, ProcessingError>
+    where
+        T: Transaction,
+        R: RuleSet<S, T>,
+        F: Fn(&S) -> bool,
+    {
+        self.apply_checked(transaction, rules, context, Some((condition_name, &post_condition)))
+    }
+    
+    /// Replace the current state with the result of an out-of-band mutation
+    /// 
+    /// No rule set is involved and the transaction count is unchanged. The
+    /// mutated state is validated, normalized and hashed like the result of a
+    /// transaction, and `mutation_id` becomes the transition's `transaction_id`.
+    pub fn apply_mutation(
+        &mut self,
+        mutation_id: &str,
+        mutate: impl FnOnce(S) -> Result<S, StateError>,
+    ) -> Result<StateTransition<S>, ProcessingError> {
+        let from_state = S::clone(&self.current_state);
+        let from_hash = self.hasher.hash(&from_state);
+        
+        let new_state = mutate(from_state.clone()).map_err(|e| ProcessingError::TransactionFailed {
+            transaction_id: mutation_id.to_string(),
+            reason: format!("State mutation failed: {}", e),
+        })?;
+        new_state.validate().map_err(|e| ProcessingError::TransactionFailed {
+            transaction_id: mutation_id.to_string(),
+            reason: format!("Mutated state validation failed: {}", e),
+        })?;
+        let soft_violations = check_state_invariants(&new_state, mutation_id)?;
+        self.check_state_size(&new_state, mutation_id)?;
+        
+        let new_state = NormalizedState::new(new_state);
+        let to_hash = self.hasher.hash_normalized(&new_state);
+        let new_state = new_state.into_inner();
+        #[cfg(feature = "debug-audit")]
+        self.record_audit(AuditOperation::ComputeHash { hash: to_hash });
+        self.current_state = Arc::new(new_state.clone());
+        self.record_invariant_warnings(mutation_id, soft_violations);
+        self.watchers.notify(&new_state, to_hash, mutation_id);
+        
+        Ok(StateTransition {
+            from_state,
+            to_state: new_state,
+            from_hash,
+            to_hash,
+            transaction_id: mutation_id.to_string(),
+            causality: Default::default(),
+            audit: Default::default(),
+        })
+    }
+    
+    /// Commit a transaction's result taken from a `RuleApplicationCache`
+    /// 
+    /// The cached state is trusted as is: it is neither validated nor rehashed,
+    /// and the transition carries no causality or audit record.
+    pub(crate) fn apply_cached(&mut self, transaction_id: &str, from_hash: StateHash, new_state: S, to_hash: StateHash) -> StateTransition<S> {
+        let from_state = Arc::unwrap_or_clone(std::mem::replace(&mut self.current_state, Arc::new(new_state.clone())));
+        self.transaction_count += 1;
+        self.watchers.notify(&new_state, to_hash, transaction_id);
+        
+        StateTransition {
+            from_state,
+            to_state: new_state,
+            from_hash,
+            to_hash,
+            transaction_id: transaction_id.to_string(),
+            causality: Default::default(),
+            audit: Default::default(),
+        }
+    }
+    
+    /// Get the soft invariants broken by committed transitions, in commit order
+    /// 
+    /// Each violation is paired with the ID of the transaction or mutation
+    /// whose resulting state broke it.
+    pub fn invariant_warnings(&self) -> &[(String, InvariantViolation)] {
+        &self.invariant_warnings
+    }
+    
+    fn record_invariant_warnings(&mut self, transaction_id: &str, violations: Vec<InvariantViolation>) {
+        self.invariant_warnings
+            .extend(violations.into_iter().map(|violation| (transaction_id.to_string(), violation)));
+    }
+    
+    /// Apply a transaction, checking an optional named post-condition before committing
+    fn apply_checked<T, R>(
+        &mut self,
+        transaction: &T,
+        rules: &R,
+        context: &ExecutionContext,
+        post_condition: Option<NamedPostCondition<'_, S>>,
+    ) -> Result<StateTransition<S>, ProcessingError>
     where
         T: Transaction,
         R: RuleSet<S, T>,
     {
         // Validate the transaction
-        transaction.validate().map_err(|e| ProcessingError::TransactionFailed {
+        let started = Instant::now();
+        let validation = transaction.validate();
+        self.phase_timings.state_validation += started.elapsed();
+        #[cfg(feature = "debug-audit")]
+        self.record_audit(AuditOperation::EvaluateTransactionValidate { ok: validation.is_ok() });
+        validation.map_err(|e| ProcessingError::TransactionFailed {
             transaction_id: transaction.id().to_string(),
             reason: format!("Transaction validation failed: {}", e),
         })?;
         
         // Store the old state and hash
-        let from_state = self.current_state.clone();
+        let from_state = S::clone(&self.current_state);
+        let started = Instant::now();
         let from_hash = self.hasher.hash(&from_state);
+        self.phase_timings.state_hashing += started.elapsed();
+        #[cfg(feature = "debug-audit")]
+        self.record_audit(AuditOperation::ComputeHash { hash: from_hash });
+        
+        // Check business preconditions before touching the state
+        let pre_context = context.with_phase(ExecutionPhase::PreProcessing);
+        let started = Instant::now();
+        let pre_validation = rules.pre_validate(&self.current_state, transaction, &pre_context);
+        self.phase_timings.rule_application += started.elapsed();
+        #[cfg(feature = "debug-audit")]
+        self.record_audit(AuditOperation::EvaluateRuleGuard { ok: pre_validation.is_ok() });
+        pre_validation.map_err(|e| {
+            let detail = match e {
+                ValidationError::WithDetails { details } => details,
+                other => Box::new(ValidationDetail {
+                    violated_rules: vec![other.to_string()],
+                    field: None,
+                    expected_constraint: None,
+                    actual_value: None,
+                    context: ErrorContext::new()
+                        .with_transaction(transaction.id().to_string(), self.transaction_count)
+                        .with_rule(rules.version())
+                        .with_state_hashes(from_hash, None),
+                }),
+            };
+            ProcessingError::PreValidationFailed {
+                rule_version: rules.version(),
+                detail,
+            }
+        })?;
+        #[cfg(all(feature = "debug-contracts", debug_assertions))]
+        check_conditions(ConditionType::Pre, &rules.pre_conditions(), &self.current_state, transaction)?;
         
         // Apply the rule set to get the new state
-        let new_state = rules.apply(&self.current_state, transaction, context)?;
+        let main_context = pre_context.advance_phase().into_causality_recording();
+        let started = Instant::now();
+        let applied = rules.apply_with_audit(&self.current_state, transaction, &main_context);
+        self.phase_timings.rule_application += started.elapsed();
+        #[cfg(feature = "debug-audit")]
+        self.record_audit(AuditOperation::EvaluateRuleApply { ok: applied.is_ok() });
+        let (new_state, audit) = applied?;
+        let causality = main_context.causality().unwrap_or_default();
         
-        // Validate the new state
-        new_state.validate().map_err(|e| ProcessingError::TransactionFailed {
+        // Write only the changed fields into the previous state when the rule set declares the fields it writes,
+        // keeping the full new state if the patch cannot be applied
+        let new_state = match rules.affects_fields() {
+            Some(_) => {
+                let started = Instant::now();
+                let mut patched = S::clone(&from_state);
+                let patch = patched.apply_patch(&StatePatch::from_diff(&from_state, &new_state));
+                self.phase_timings.serialization += started.elapsed();
+                if patch.is_ok() { patched } else { new_state }
+            }
+            None => new_state,
+        };
+        
+        // Validate the new state, only where the rule set says it writes if the state validates by field
+        let started = Instant::now();
+        let declared_fields = if S::FIELD_VALIDATION { rules.affects_fields() } else { None };
+        let invariants = match declared_fields {
+            Some(paths) => paths.iter().try_for_each(|path| new_state.validate_field(path)),
+            None => new_state.validate(),
+        }
+        .map_err(|e| ProcessingError::TransactionFailed {
             transaction_id: transaction.id().to_string(),
             reason: format!("New state validation failed: {}", e),
-        })?;
+        })
+        .and_then(|()| check_state_invariants(&new_state, transaction.id()));
+        self.phase_timings.state_validation += started.elapsed();
+        #[cfg(feature = "debug-audit")]
+        self.record_audit(AuditOperation::EvaluateInvariants { ok: invariants.is_ok() });
+        let soft_violations = invariants?;
+        self.check_state_size(&new_state, transaction.id())?;
+        #[cfg(all(feature = "debug-contracts", debug_assertions))]
+        check_conditions(ConditionType::Post, &rules.post_conditions(), &new_state, transaction)?;
         
-        // Compute the new hash
-        let to_hash = self.hasher.hash(&new_state);
+        // Normalize the new state so the stored state is the one that was hashed
+        let started = Instant::now();
+        let new_state = NormalizedState::new(new_state);
+        let to_hash = self.hasher.hash_normalized(&new_state);
+        let new_state = new_state.into_inner();
+        self.phase_timings.state_hashing += started.elapsed();
+        #[cfg(feature = "debug-audit")]
+        self.record_audit(AuditOperation::ComputeHash { hash: to_hash });
         
-        // Update the current state
-        self.current_state = new_state.clone();
+        // Roll back by not committing when the post-condition rejects the new state
+        if let Some((condition_name, post_condition)) = post_condition {
+            if !post_condition(&new_state) {
+                return Err(ProcessingError::PostConditionFailed {
+                    condition_name: condition_name.to_string(),
+                });
+            }
+        }
+        
+        // Commit the state that was hashed
+        self.current_state = Arc::new(new_state.clone());
         self.transaction_count += 1;
+        self.record_invariant_warnings(transaction.id(), soft_violations);
+        self.watchers.notify(&new_state, to_hash, transaction.id());
         
         // Create and return the transition
         Ok(StateTransition {
@@ -104,43 +1200,182 @@ impl<S: State> StateManager<S> {
             from_hash,
             to_hash,
             transaction_id: transaction.id().to_string(),
+            causality,
+            audit,
         })
     }
     
     /// Create a checkpoint at the current state
     pub fn create_checkpoint(&mut self, timestamp: chrono::DateTime<chrono::Utc>) -> Checkpoint<S> {
+        let started = Instant::now();
+        let hash = self.current_hash();
+        self.phase_timings.state_hashing += started.elapsed();
+        let started = Instant::now();
         let checkpoint = Checkpoint {
-            state: self.current_state.clone(),
-            hash: self.current_hash(),
+            state: S::clone(&self.current_state),
+            hash,
             transaction_index: self.transaction_count,
             timestamp,
+            state_schema_version: S::SCHEMA_VERSION,
         };
+        #[cfg(feature = "debug-audit")]
+        {
+            self.record_audit(AuditOperation::ComputeHash { hash: checkpoint.hash });
+            self.record_audit(AuditOperation::CheckpointCreated { index: checkpoint.transaction_index });
+        }
         
         self.checkpoints.push(checkpoint.clone());
+        self.purge_now();
+        self.phase_timings.checkpoint_creation += started.elapsed();
         checkpoint
     }
     
+    /// Take a named snapshot of the current state for disaster recovery
+    /// 
+    /// Unlike `create_checkpoint`, the snapshot is not stored in the manager
+    /// and is timestamped with the wall-clock time. `transaction_count` is
+    /// recorded as the checkpoint's transaction index.
+    pub fn take_versioned_snapshot<T, R>(&self, name: &str, rule_set: &R, transaction_count: usize) -> VersionedSnapshot<S>
+    where
+        T: Transaction,
+        R: RuleSet<S, T>,
+    {
+        VersionedSnapshot {
+            checkpoint: Checkpoint {
+                state: S::clone(&self.current_state),
+                hash: self.current_hash(),
+                transaction_index: transaction_count,
+                timestamp: chrono::Utc::now(),
+                state_schema_version: S::SCHEMA_VERSION,
+            },
+            snapshot_name: name.to_string(),
+            rule_set_version: rule_set.version(),
+            schema_version: SNAPSHOT_SCHEMA_VERSION,
+            created_by: process_identity(),
+            signature: None,
+        }
+    }
+    
+    /// Apply the purge policy to the stored checkpoints
+    /// 
+    /// Checkpoints whose hash is protected are always retained.
+    /// Returns the number of checkpoints removed.
+    pub fn purge_now(&mut self) -> usize {
+        let retained = self.retained_by_policy();
+        let protected = &self.protected_checkpoints;
+        let before = self.checkpoints.len();
+        
+        let mut index = 0;
+        self.checkpoints.retain(|checkpoint| {
+            let keep = retained[index] || protected.contains(&checkpoint.hash);
+            index += 1;
+            keep
+        });
+        
+        before - self.checkpoints.len()
+    }
+    
+    /// Decide, per stored checkpoint, whether the purge policy retains it
+    fn retained_by_policy(&self) -> Vec<bool> {
+        let count = self.checkpoints.len();
+        match &self.purge_policy {
+            CheckpointPurgePolicy::KeepAll => vec![true; count],
+            CheckpointPurgePolicy::KeepLatestN(n) => {
+                (0..count).map(|index| index + n >= count).collect()
+            }
+            CheckpointPurgePolicy::KeepNewerThan(max_age) => {
+                let newest = self.checkpoints.iter().map(|checkpoint| checkpoint.timestamp).max();
+                self.checkpoints
+                    .iter()
+                    .map(|checkpoint| newest.is_none_or(|newest| newest - checkpoint.timestamp <= *max_age))
+                    .collect()
+            }
+            CheckpointPurgePolicy::KeepAtMostNPerVersion(n) => {
+                let mut kept_per_version: HashMap<u32, usize> = HashMap::new();
+                let mut retained = vec![false; count];
+                for (index, checkpoint) in self.checkpoints.iter().enumerate().rev() {
+                    let kept = kept_per_version.entry(checkpoint.state_schema_version).or_insert(0);
+                    if *kept < *n {
+                        *kept += 1;
+                        retained[index] = true;
+                    }
+                }
+                retained
+            }
+        }
+    }
+    
+    /// Protect a checkpoint from being purged
+    pub fn protect_checkpoint(&mut self, hash: StateHash) {
+        self.protected_checkpoints.insert(hash);
+    }
+    
+    /// Remove purge protection from a checkpoint
+    /// 
+    /// Returns true if the checkpoint was protected.
+    pub fn unprotect_checkpoint(&mut self, hash: &StateHash) -> bool {
+        self.protected_checkpoints.remove(hash)
+    }
+    
+    /// Get the hashes of checkpoints that are never purged
+    pub fn protected_checkpoints(&self) -> &HashSet<StateHash> {
+        &self.protected_checkpoints
+    }
+    
     /// Restore state from a checkpoint
+    /// 
+    /// Fails with `StateError::SchemaMismatch` for checkpoints taken under an
+    /// older state schema: their state was decoded with the current layout, so
+    /// it cannot be migrated. Restore those with `restore_raw_checkpoint`.
     pub fn restore_checkpoint(&mut self, checkpoint: &Checkpoint<S>) -> Result<(), StateError> {
-        // Validate the checkpoint state
-        checkpoint.state.validate().map_err(|e| StateError::CheckpointError {
-            reason: format!("Checkpoint state validation failed: {}", e),
-        })?;
+        // Refuse corrupted checkpoints before touching the current state
+        checkpoint.verify_integrity()?;
         
-        // Verify the checkpoint hash matches
-        let computed_hash = self.hasher.hash(&checkpoint.state);
-        if computed_hash != checkpoint.hash {
-            return Err(StateError::CheckpointError {
-                reason: format!(
-                    "Checkpoint hash mismatch: expected {}, got {}",
-                    checkpoint.hash, computed_hash
-                ),
+        if checkpoint.state_schema_version != S::SCHEMA_VERSION {
+            return Err(StateError::SchemaMismatch {
+                checkpoint_version: checkpoint.state_schema_version,
+                current_version: S::SCHEMA_VERSION,
             });
         }
         
+        self.restore_state(checkpoint.state.clone(), checkpoint.transaction_index)
+    }
+    
+    /// Restore state from a checkpoint whose state has not been decoded
+    /// 
+    /// The raw state JSON of a checkpoint taken under an older state schema is
+    /// passed to `State::migrate`, so states that no longer deserialize into
+    /// `S` can still be restored. The recorded hash of such a checkpoint covers
+    /// the old layout and is not verified. Checkpoints at the current schema
+    /// are decoded and restored as with `restore_checkpoint`.
+    pub fn restore_raw_checkpoint(&mut self, checkpoint: &RawCheckpoint) -> Result<(), StateError> {
+        if checkpoint.state_schema_version == S::SCHEMA_VERSION {
+            let state = serde_json::from_value(checkpoint.state.clone()).map_err(|e| StateError::CheckpointError {
+                reason: format!("Failed to decode checkpoint state: {}", e),
+            })?;
+            return self.restore_checkpoint(&Checkpoint {
+                state,
+                hash: checkpoint.hash,
+                transaction_index: checkpoint.transaction_index,
+                timestamp: checkpoint.timestamp,
+                state_schema_version: checkpoint.state_schema_version,
+            });
+        }
+        
+        let state = S::migrate(checkpoint.state_schema_version, checkpoint.state.clone())?;
+        self.restore_state(state, checkpoint.transaction_index)
+    }
+    
+    /// Validate a restored state and make it current
+    fn restore_state(&mut self, state: S, transaction_index: usize) -> Result<(), StateError> {
+        // Validate the checkpoint state
+        state.validate().map_err(|e| StateError::CheckpointError {
+            reason: format!("Checkpoint state validation failed: {}", e),
+        })?;
+        
         // Restore the state
-        self.current_state = checkpoint.state.clone();
-        self.transaction_count = checkpoint.transaction_index;
+        self.current_state = Arc::new(state);
+        self.transaction_count = transaction_index;
         
         Ok(())
     }
@@ -150,6 +1385,38 @@ impl<S: State> StateManager<S> {
         &self.checkpoints
     }
     
+    /// Get the number of stored checkpoints
+    pub fn checkpoint_count(&self) -> usize {
+        self.checkpoints.len()
+    }
+    
+    /// Summarize every stored checkpoint, sorted by transaction index
+    pub fn list_checkpoints(&self) -> Vec<CheckpointInfo> {
+        let mut infos: Vec<CheckpointInfo> = self.checkpoints.iter().map(Checkpoint::info).collect();
+        infos.sort_by_key(|info| info.transaction_index);
+        infos
+    }
+    
+    /// Get one page of `list_checkpoints`, skipping `offset` summaries and returning at most `limit`
+    pub fn list_checkpoints_paginated(&self, offset: usize, limit: usize) -> PaginatedResult<CheckpointInfo> {
+        let all = self.list_checkpoints();
+        let total = all.len();
+        let items: Vec<CheckpointInfo> = all.into_iter().skip(offset).take(limit).collect();
+        let has_next = offset.saturating_add(items.len()) < total;
+        PaginatedResult {
+            items,
+            total,
+            offset,
+            limit,
+            has_next,
+        }
+    }
+    
+    /// Verify the integrity of every stored checkpoint, in storage order
+    pub fn verify_all_checkpoints(&self) -> Vec<Result<(), StateError>> {
+        self.checkpoints.iter().map(Checkpoint::verify_integrity).collect()
+    }
+    
     /// Calculate the difference between two states
     pub fn calculate_diff(&self, from_state: &S, to_state: &S) -> StateDiff<S> {
         let from_hash = self.hasher.hash(from_state);
@@ -160,9 +1427,83 @@ impl<S: State> StateManager<S> {
             to_state: to_state.clone(),
             from_hash,
             to_hash,
+            index_diff: 0,
+            transaction_range: None,
         }
     }
     
+    /// Diff the states of two stored checkpoints, from `hash_a` to `hash_b`
+    /// 
+    /// # Errors
+    /// Returns `StateError::CheckpointError` if either checkpoint is not stored
+    pub fn diff_checkpoints(&self, hash_a: StateHash, hash_b: StateHash) -> Result<StateDiff<S>, StateError> {
+        let a = self.stored_checkpoint(&hash_a)?;
+        let b = self.stored_checkpoint(&hash_b)?;
+        Ok(Self::indexed_diff(&a.state, a.hash, a.transaction_index, &b.state, b.hash, b.transaction_index))
+    }
+    
+    /// Diff the state of a stored checkpoint against the current state
+    /// 
+    /// # Errors
+    /// Returns `StateError::CheckpointError` if the checkpoint is not stored
+    pub fn diff_checkpoint_and_current(&self, hash: StateHash) -> Result<StateDiff<S>, StateError> {
+        let checkpoint = self.stored_checkpoint(&hash)?;
+        Ok(Self::indexed_diff(
+            &checkpoint.state,
+            checkpoint.hash,
+            checkpoint.transaction_index,
+            &self.current_state,
+            self.current_hash(),
+            self.transaction_count,
+        ))
+    }
+    
+    /// Find a stored checkpoint by its hash
+    fn stored_checkpoint(&self, hash: &StateHash) -> Result<&Checkpoint<S>, StateError> {
+        self.checkpoints
+            .iter()
+            .find(|checkpoint| checkpoint.hash == *hash)
+            .ok_or_else(|| StateError::CheckpointError {
+                reason: format!("No checkpoint stored with hash {}", hash),
+            })
+    }
+    
+    fn indexed_diff(from_state: &S, from_hash: StateHash, from_index: usize, to_state: &S, to_hash: StateHash, to_index: usize) -> StateDiff<S> {
+        StateDiff {
+            from_state: from_state.clone(),
+            to_state: to_state.clone(),
+            from_hash,
+            to_hash,
+            index_diff: to_index as i64 - from_index as i64,
+            transaction_range: Some((from_index, to_index)),
+        }
+    }
+    
+    /// Merge two states that diverged, such as the results of two replay branches
+    /// 
+    /// Conflicting fields are resolved by `merge_strategy`; see `MergeStrategy`
+    /// for how the states are walked. Merging a state with itself returns it
+    /// unchanged under every strategy.
+    /// 
+    /// # Errors
+    /// Returns `StateError::TransitionFailed` if a state cannot be converted to
+    /// or from JSON, or if validation is enabled and the merged state fails it
+    pub fn merge_states(&self, state_a: &S, state_b: &S, merge_strategy: MergeStrategy<S>) -> Result<S, StateError> {
+        let to_json = |state: &S| serde_json::to_value(state).map_err(|e| StateError::TransitionFailed {
+            reason: format!("State could not be serialized for merging: {}", e),
+        });
+        let merged = merge_strategy.merge_value("", to_json(state_a)?, to_json(state_b)?);
+        let merged: S = serde_json::from_value(merged).map_err(|e| StateError::TransitionFailed {
+            reason: format!("Merged state could not be deserialized: {}", e),
+        })?;
+        if merge_strategy.merge_validate {
+            merged.validate().map_err(|e| StateError::TransitionFailed {
+                reason: format!("Merged state validation failed: {}", e),
+            })?;
+        }
+        Ok(merged)
+    }
+    
     /// Compare two states and return whether they are identical
     pub fn compare_states(&self, state1: &S, state2: &S) -> bool {
         let hash1 = self.hasher.hash(state1);
@@ -179,6 +1520,97 @@ impl<S: State> StateManager<S> {
     pub fn clear_checkpoints(&mut self) {
         self.checkpoints.clear();
     }
+    
+    /// Reconstruct the state at every stored checkpoint by replaying the transaction log
+    /// 
+    /// Replay starts from the earliest stored checkpoint and re-applies
+    /// `all_transactions` (indexed from the start of the log) up to the last
+    /// checkpoint. The replayed state at each checkpoint boundary is verified
+    /// against the stored checkpoint hash. The current state is not modified.
+    pub fn history_replay<T, R>(
+        &self,
+        all_transactions: &[T],
+        rule_set: &R,
+        context: &ExecutionContext,
+    ) -> Result<StateHistory<S>, ProcessingError>
+    where
+        T: Transaction,
+        R: RuleSet<S, T>,
+    {
+        let mut checkpoints: Vec<&Checkpoint<S>> = self.checkpoints.iter().collect();
+        checkpoints.sort_by_key(|checkpoint| checkpoint.transaction_index);
+        
+        let mut entries = Vec::with_capacity(checkpoints.len());
+        let first = match checkpoints.first() {
+            Some(first) => first,
+            None => return Ok(StateHistory { entries }),
+        };
+        
+        let mut scratch = Self {
+            current_state: Arc::new(first.state.clone()),
+            hasher: self.hasher.clone(),
+            checkpoints: Vec::new(),
+            transaction_count: first.transaction_index,
+            purge_policy: CheckpointPurgePolicy::KeepAll,
+            protected_checkpoints: HashSet::new(),
+            max_state_size_bytes: self.max_state_size_bytes,
+            phase_timings: PhaseTimings::default(),
+            watchers: StateWatchers::new(),
+            invariant_warnings: Vec::new(),
+            #[cfg(feature = "debug-audit")]
+            audit_log: None,
+        };
+        
+        for checkpoint in checkpoints {
+            while scratch.transaction_count < checkpoint.transaction_index {
+                let index = scratch.transaction_count;
+                let transaction = all_transactions.get(index).ok_or_else(|| {
+                    ProcessingError::with_context(
+                        format!(
+                            "Transaction log ends at {} but checkpoint requires index {}",
+                            all_transactions.len(),
+                            checkpoint.transaction_index
+                        ),
+                        ErrorContext::new(),
+                    )
+                })?;
+                scratch.apply_transaction(transaction, rule_set, context)?;
+            }
+            
+            let hash = scratch.current_hash();
+            if hash != checkpoint.hash {
+                return Err(ProcessingError::with_context(
+                    format!(
+                        "Replayed state diverged from checkpoint at index {}",
+                        checkpoint.transaction_index
+                    ),
+                    ErrorContext::new()
+                        .with_rule(rule_set.version())
+                        .with_state_hashes(checkpoint.hash, Some(hash)),
+                ));
+            }
+            
+            entries.push(HistoryEntry {
+                transaction_index: checkpoint.transaction_index,
+                state: S::clone(&scratch.current_state),
+                hash,
+            });
+        }
+        
+        Ok(StateHistory { entries })
+    }
+}
+
+/// Check the state's invariants, failing on a hard violation and returning the soft ones
+fn check_state_invariants<S: State>(state: &S, transaction_id: &str) -> Result<Vec<InvariantViolation>, ProcessingError> {
+    let violations = state.check_invariants();
+    if let Some(hard) = violations.iter().find(|v| v.severity == InvariantSeverity::Hard) {
+        return Err(ProcessingError::TransactionFailed {
+            transaction_id: transaction_id.to_string(),
+            reason: format!("Hard invariant {} violated", hard.invariant_name),
+        });
+    }
+    Ok(violations)
 }
 
 #[cfg(test)]
@@ -186,7 +1618,7 @@ mod tests {
     use super::*;
     use crate::types::Version;
     use crate::error::ValidationError;
-    use chrono::Utc;
+    use chrono::{TimeZone, Utc};
     use serde::{Deserialize, Serialize};
     use std::hash::{Hash, Hasher};
     
@@ -292,6 +1724,31 @@ mod tests {
         assert_eq!(manager.current_state().balance, 150);
     }
     
+    #[test]
+    fn test_apply_conditional_rolls_back_on_failed_post_condition() {
+        let mut manager = StateManager::new(TestState { balance: 100 }).unwrap();
+        let context = ExecutionContext::new(Utc::now(), 42);
+        let transaction = TestTransaction {
+            id: "tx1".to_string(),
+            amount: 50,
+            timestamp: Utc::now(),
+        };
+        let initial_hash = manager.current_hash();
+        
+        let result = manager.apply_conditional_named(&transaction, &TestRuleSet, &context, "at_most_120", |s| s.balance <= 120);
+        assert!(matches!(
+            result,
+            Err(ProcessingError::PostConditionFailed { ref condition_name }) if condition_name == "at_most_120"
+        ));
+        assert_eq!(manager.current_state().balance, 100);
+        assert_eq!(manager.current_hash(), initial_hash);
+        assert_eq!(manager.transaction_count(), 0);
+        
+        let transition = manager.apply_conditional(&transaction, &TestRuleSet, &context, |s| s.balance <= 200).unwrap();
+        assert_eq!(transition.to_state.balance, 150);
+        assert_eq!(manager.transaction_count(), 1);
+    }
+    
     #[test]
     fn test_checkpoint_creation_and_restoration() {
         let state = TestState { balance: 100 };
@@ -318,6 +1775,149 @@ mod tests {
         assert_eq!(manager.current_state().balance, 100);
     }
     
+    fn manager_with_checkpoints(policy: CheckpointPurgePolicy, count: i64) -> StateManager<TestState> {
+        let mut manager = StateManager::new(TestState { balance: 0 }).unwrap().with_purge_policy(policy);
+        let context = ExecutionContext::new(Utc.timestamp_opt(0, 0).unwrap(), 42);
+        
+        for i in 0..count {
+            let transaction = TestTransaction {
+                id: format!("tx{}", i),
+                amount: 10,
+                timestamp: Utc.timestamp_opt(i * 60, 0).unwrap(),
+            };
+            manager.apply_transaction(&transaction, &TestRuleSet, &context).unwrap();
+            manager.create_checkpoint(Utc.timestamp_opt(i * 60, 0).unwrap());
+        }
+        manager
+    }
+    
+    #[test]
+    fn test_keep_all_retains_every_checkpoint() {
+        let mut manager = manager_with_checkpoints(CheckpointPurgePolicy::KeepAll, 10);
+        assert_eq!(manager.checkpoints().len(), 10);
+        assert_eq!(manager.purge_now(), 0);
+    }
+    
+    #[test]
+    fn test_keep_latest_n_purges_automatically() {
+        let manager = manager_with_checkpoints(CheckpointPurgePolicy::KeepLatestN(3), 10);
+        
+        let balances: Vec<i64> = manager.checkpoints().iter().map(|c| c.state.balance).collect();
+        assert_eq!(balances, vec![80, 90, 100]);
+    }
+    
+    #[test]
+    fn test_keep_latest_n_retains_protected_checkpoints() {
+        let mut manager = StateManager::new(TestState { balance: 0 }).unwrap();
+        let context = ExecutionContext::new(Utc.timestamp_opt(0, 0).unwrap(), 42);
+        let transaction = TestTransaction {
+            id: "tx".to_string(),
+            amount: 10,
+            timestamp: Utc.timestamp_opt(0, 0).unwrap(),
+        };
+        
+        let mut protected = None;
+        for i in 0..10 {
+            manager.apply_transaction(&transaction, &TestRuleSet, &context).unwrap();
+            let checkpoint = manager.create_checkpoint(Utc.timestamp_opt(i * 60, 0).unwrap());
+            if i == 1 {
+                manager.protect_checkpoint(checkpoint.hash);
+                protected = Some(checkpoint.hash);
+            }
+        }
+        
+        manager = manager.with_purge_policy(CheckpointPurgePolicy::KeepLatestN(3));
+        assert_eq!(manager.purge_now(), 6);
+        assert_eq!(manager.checkpoints().len(), 4);
+        assert_eq!(manager.checkpoints()[0].hash, protected.unwrap());
+        
+        // Dropping the protection lets the next purge remove it
+        assert!(manager.unprotect_checkpoint(&protected.unwrap()));
+        assert_eq!(manager.purge_now(), 1);
+        assert_eq!(manager.checkpoints().len(), 3);
+    }
+    
+    #[test]
+    fn test_keep_newer_than_is_relative_to_newest_checkpoint() {
+        let manager = manager_with_checkpoints(CheckpointPurgePolicy::KeepNewerThan(chrono::Duration::minutes(2)), 10);
+        
+        let timestamps: Vec<i64> = manager.checkpoints().iter().map(|c| c.timestamp.timestamp()).collect();
+        assert_eq!(timestamps, vec![420, 480, 540]);
+    }
+    
+    #[test]
+    fn test_keep_at_most_n_per_version() {
+        let mut manager = manager_with_checkpoints(CheckpointPurgePolicy::KeepAll, 5);
+        
+        // Simulate checkpoints carried over from an older schema
+        for checkpoint in manager.checkpoints.iter_mut().take(3) {
+            checkpoint.state_schema_version = 0;
+        }
+        
+        manager = manager.with_purge_policy(CheckpointPurgePolicy::KeepAtMostNPerVersion(1));
+        assert_eq!(manager.purge_now(), 3);
+        
+        let kept: Vec<(u32, i64)> = manager
+            .checkpoints()
+            .iter()
+            .map(|c| (c.state_schema_version, c.state.balance))
+            .collect();
+        assert_eq!(kept, vec![(0, 30), (1, 50)]);
+    }
+    
+    #[test]
+    fn test_checkpoint_binary_round_trip() {
+        let mut manager = StateManager::new(TestState { balance: 100 }).unwrap();
+        let checkpoint = manager.create_checkpoint(Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap());
+        
+        let bytes = checkpoint.to_bytes().unwrap();
+        assert_eq!(&bytes[..4], &CHECKPOINT_MAGIC);
+        assert_eq!(bytes.len(), checkpoint.binary_size());
+        
+        let decoded = Checkpoint::<TestState>::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded.state, checkpoint.state);
+        assert_eq!(decoded.hash, checkpoint.hash);
+        assert_eq!(decoded.timestamp, checkpoint.timestamp);
+        assert!(decoded.verify_integrity().is_ok());
+        
+        let mut wrong_version = bytes.clone();
+        wrong_version[4] = 0xFF;
+        assert!(Checkpoint::<TestState>::from_bytes(&wrong_version).is_err());
+        assert!(Checkpoint::<TestState>::from_bytes(&bytes[4..]).is_err());
+        assert!(Checkpoint::<TestState>::from_bytes(b"DT").is_err());
+    }
+    
+    #[test]
+    fn test_list_checkpoints_paginated() {
+        let mut manager = StateManager::new(TestState { balance: 100 }).unwrap();
+        let context = ExecutionContext::new(Utc::now(), 42);
+        let base_time = Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap();
+        for i in 0..5 {
+            manager.create_checkpoint(base_time + chrono::Duration::minutes(i));
+            let transaction = TestTransaction {
+                id: format!("tx{}", i),
+                amount: 10,
+                timestamp: base_time,
+            };
+            manager.apply_transaction(&transaction, &TestRuleSet, &context).unwrap();
+        }
+        assert_eq!(manager.checkpoint_count(), 5);
+        
+        let page = manager.list_checkpoints_paginated(1, 2);
+        let indices: Vec<usize> = page.items.iter().map(|info| info.transaction_index).collect();
+        assert_eq!(indices, vec![1, 2]);
+        assert_eq!((page.total, page.offset, page.limit, page.has_next), (5, 1, 2, true));
+        
+        let last = manager.list_checkpoints_paginated(4, 2);
+        assert_eq!(last.items.len(), 1);
+        assert!(!last.has_next);
+        assert!(manager.list_checkpoints_paginated(9, 2).items.is_empty());
+        
+        let summary = page.items[0].to_summary_string();
+        let hash = page.items[0].hash.to_string();
+        assert_eq!(summary, format!("[1] {} @ 2025-01-01T00:01:00+00:00 (1)", &hash[..8]));
+    }
+    
     #[test]
     fn test_calculate_diff() {
         let state1 = TestState { balance: 100 };
@@ -331,6 +1931,25 @@ mod tests {
         assert_ne!(diff.from_hash, diff.to_hash);
     }
     
+    #[test]
+    fn test_fields_changed() {
+        let state1 = TestState { balance: 100 };
+        let state2 = TestState { balance: 150 };
+        
+        let manager = StateManager::new(state1.clone()).unwrap();
+        let diff = manager.calculate_diff(&state1, &state2);
+        
+        assert_eq!(diff.fields_changed(), vec![FieldChange {
+            path: "balance".to_string(),
+            kind: crate::types::ChangeKind::Modified {
+                old_value: serde_json::json!(100),
+                new_value: serde_json::json!(150),
+            },
+        }]);
+        assert!(diff.has_changed_fields(&["balance"]));
+        assert!(manager.calculate_diff(&state1, &state1).fields_changed().is_empty());
+    }
+    
     #[test]
     fn test_compare_states() {
         let state1 = TestState { balance: 100 };
@@ -342,4 +1961,19 @@ mod tests {
         assert!(manager.compare_states(&state1, &state2));
         assert!(!manager.compare_states(&state1, &state3));
     }
+    
+    #[test]
+    fn test_merge_states_strategies() {
+        let manager = StateManager::new(TestState { balance: 0 }).unwrap();
+        let (a, b) = (TestState { balance: 150 }, TestState { balance: 100 });
+        
+        assert_eq!(manager.merge_states(&a, &b, MergeStrategy::max_numerics()).unwrap().balance, 150);
+        assert_eq!(manager.merge_states(&a, &b, MergeStrategy::last_write_wins()).unwrap().balance, 100);
+        
+        let strategy = MergeStrategy::custom(|path: &str, a: serde_json::Value, _b: serde_json::Value| {
+            assert_eq!(path, "balance");
+            a
+        });
+        assert_eq!(manager.merge_states(&a, &b, strategy).unwrap().balance, 150);
+    }
 }
@@ -0,0 +1,243 @@
+//! Aggregated processing metrics collected by the transaction processor
+
+use crate::types::Version;
+use chrono::Duration;
+use serde_json::json;
+use std::collections::HashMap;
+
+/// Summary of transaction processing outcomes and latencies
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProcessingStatistics {
+    /// Number of transactions submitted for processing, including failures
+    pub total_processed: usize,
+    pub total_failed: usize,
+    /// Fraction of submitted transactions that succeeded, or 0.0 if none were submitted
+    pub success_rate: f64,
+    pub processing_time_p50_us: u64,
+    pub processing_time_p95_us: u64,
+    pub processing_time_p99_us: u64,
+    pub by_rule_version: HashMap<Version, VersionStatistics>,
+    /// Number of submitted transactions with a timestamp before the previous processed transaction's
+    pub out_of_order_count: usize,
+    /// Largest backwards drift seen, or `None` if every transaction was in order
+    pub max_observed_drift: Option<Duration>,
+    /// Fraction of idempotency key lookups answered by the rule cache, or 0.0 before the first lookup
+    pub rule_cache_hit_rate: f64,
+}
+
+/// Processing outcomes and latencies for a single rule set version
+#[derive(Debug, Clone, PartialEq)]
+pub struct VersionStatistics {
+    pub total_processed: usize,
+    pub total_failed: usize,
+    pub success_rate: f64,
+    pub processing_time_p50_us: u64,
+    pub processing_time_p95_us: u64,
+    pub processing_time_p99_us: u64,
+}
+
+impl ProcessingStatistics {
+    /// Convert the statistics to JSON, keying per-version entries by version string
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut versions: Vec<(&Version, &VersionStatistics)> = self.by_rule_version.iter().collect();
+        versions.sort_by(|a, b| a.0.cmp(b.0));
+        
+        let by_rule_version: serde_json::Map<String, serde_json::Value> = versions
+            .into_iter()
+            .map(|(version, stats)| {
+                (
+                    version.to_string(),
+                    json!({
+                        "total_processed": stats.total_processed,
+                        "total_failed": stats.total_failed,
+                        "success_rate": stats.success_rate,
+                        "processing_time_p50_us": stats.processing_time_p50_us,
+                        "processing_time_p95_us": stats.processing_time_p95_us,
+                        "processing_time_p99_us": stats.processing_time_p99_us,
+                    }),
+                )
+            })
+            .collect();
+        
+        json!({
+            "total_processed": self.total_processed,
+            "total_failed": self.total_failed,
+            "success_rate": self.success_rate,
+            "processing_time_p50_us": self.processing_time_p50_us,
+            "processing_time_p95_us": self.processing_time_p95_us,
+            "processing_time_p99_us": self.processing_time_p99_us,
+            "by_rule_version": by_rule_version,
+            "out_of_order_count": self.out_of_order_count,
+            "max_observed_drift_ms": self.max_observed_drift.map(|drift| drift.num_milliseconds()),
+            "rule_cache_hit_rate": self.rule_cache_hit_rate,
+        })
+    }
+}
+
+/// Per-version outcome counts and raw durations
+#[derive(Debug, Clone, Default)]
+struct VersionRecord {
+    failed: usize,
+    durations_us: Vec<u64>,
+}
+
+/// Collects per-transaction durations while processing
+/// 
+/// Each duration is stored once, under the rule version that processed it;
+/// overall percentiles are computed from the union when statistics are requested.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct StatisticsRecorder {
+    by_rule_version: HashMap<Version, VersionRecord>,
+    out_of_order_count: usize,
+    max_observed_drift: Option<Duration>,
+    rule_cache_hits: usize,
+    rule_cache_lookups: usize,
+}
+
+impl StatisticsRecorder {
+    /// Record the outcome of one transaction
+    pub(crate) fn record(&mut self, rule_version: Version, duration_us: u64, succeeded: bool) {
+        let record = self.by_rule_version.entry(rule_version).or_default();
+        record.durations_us.push(duration_us);
+        if !succeeded {
+            record.failed += 1;
+        }
+    }
+    
+    /// Record a transaction whose timestamp is `drift` before the previous one
+    pub(crate) fn record_drift(&mut self, drift: Duration) {
+        self.out_of_order_count += 1;
+        self.max_observed_drift = Some(self.max_observed_drift.map_or(drift, |max| max.max(drift)));
+    }
+    
+    /// Record a rule cache lookup by idempotency key
+    pub(crate) fn record_rule_cache_lookup(&mut self, hit: bool) {
+        self.rule_cache_lookups += 1;
+        if hit {
+            self.rule_cache_hits += 1;
+        }
+    }
+    
+    /// Aggregate the recorded outcomes
+    pub(crate) fn statistics(&self) -> ProcessingStatistics {
+        let mut all_durations = Vec::with_capacity(self.by_rule_version.values().map(|r| r.durations_us.len()).sum());
+        let mut total_failed = 0;
+        let mut by_rule_version = HashMap::with_capacity(self.by_rule_version.len());
+        
+        for (version, record) in &self.by_rule_version {
+            let mut durations = record.durations_us.clone();
+            durations.sort_unstable();
+            by_rule_version.insert(version.clone(), VersionStatistics {
+                total_processed: durations.len(),
+                total_failed: record.failed,
+                success_rate: success_rate(durations.len(), record.failed),
+                processing_time_p50_us: percentile(&durations, 50),
+                processing_time_p95_us: percentile(&durations, 95),
+                processing_time_p99_us: percentile(&durations, 99),
+            });
+            
+            all_durations.extend_from_slice(&record.durations_us);
+            total_failed += record.failed;
+        }
+        
+        all_durations.sort_unstable();
+        ProcessingStatistics {
+            total_processed: all_durations.len(),
+            total_failed,
+            success_rate: success_rate(all_durations.len(), total_failed),
+            processing_time_p50_us: percentile(&all_durations, 50),
+            processing_time_p95_us: percentile(&all_durations, 95),
+            processing_time_p99_us: percentile(&all_durations, 99),
+            by_rule_version,
+            out_of_order_count: self.out_of_order_count,
+            max_observed_drift: self.max_observed_drift,
+            rule_cache_hit_rate: if self.rule_cache_lookups == 0 {
+                0.0
+            } else {
+                self.rule_cache_hits as f64 / self.rule_cache_lookups as f64
+            },
+        }
+    }
+}
+
+fn success_rate(total: usize, failed: usize) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        (total - failed) as f64 / total as f64
+    }
+}
+
+/// Nearest-rank percentile of sorted values, or 0 when there are none
+fn percentile(sorted: &[u64], pct: usize) -> u64 {
+    if sorted.is_empty() {
+        return 0;
+    }
+    let rank = (pct * sorted.len()).div_ceil(100).max(1);
+    sorted[rank - 1]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_percentile_nearest_rank() {
+        let values: Vec<u64> = (1..=100).collect();
+        assert_eq!(percentile(&values, 50), 50);
+        assert_eq!(percentile(&values, 95), 95);
+        assert_eq!(percentile(&values, 99), 99);
+        
+        assert_eq!(percentile(&[7], 99), 7);
+        assert_eq!(percentile(&[], 50), 0);
+    }
+    
+    #[test]
+    fn test_recorder_aggregates_versions() {
+        let mut recorder = StatisticsRecorder::default();
+        let v1 = Version::new(1, 0, 0);
+        let v2 = Version::new(2, 0, 0);
+        
+        recorder.record(v1.clone(), 10, true);
+        recorder.record(v1.clone(), 30, false);
+        recorder.record(v2.clone(), 20, true);
+        recorder.record(v2.clone(), 40, true);
+        
+        let stats = recorder.statistics();
+        assert_eq!(stats.total_processed, 4);
+        assert_eq!(stats.total_failed, 1);
+        assert_eq!(stats.success_rate, 0.75);
+        assert_eq!(stats.processing_time_p50_us, 20);
+        assert_eq!(stats.processing_time_p99_us, 40);
+        
+        assert_eq!(stats.by_rule_version[&v1].total_failed, 1);
+        assert_eq!(stats.by_rule_version[&v1].success_rate, 0.5);
+        assert_eq!(stats.by_rule_version[&v2].processing_time_p50_us, 20);
+        
+        let json = stats.to_json();
+        assert_eq!(json["total_processed"], 4);
+        assert_eq!(json["by_rule_version"]["2.0.0"]["total_processed"], 2);
+    }
+    
+    #[test]
+    fn test_recorder_tracks_largest_drift() {
+        let mut recorder = StatisticsRecorder::default();
+        recorder.record_drift(Duration::seconds(5));
+        recorder.record_drift(Duration::seconds(12));
+        recorder.record_drift(Duration::seconds(3));
+        
+        let stats = recorder.statistics();
+        assert_eq!(stats.out_of_order_count, 3);
+        assert_eq!(stats.max_observed_drift, Some(Duration::seconds(12)));
+        assert_eq!(stats.to_json()["max_observed_drift_ms"], 12_000);
+    }
+    
+    #[test]
+    fn test_empty_recorder() {
+        let stats = StatisticsRecorder::default().statistics();
+        assert_eq!(stats.total_processed, 0);
+        assert_eq!(stats.success_rate, 0.0);
+        assert!(stats.by_rule_version.is_empty());
+        assert_eq!(stats.max_observed_drift, None);
+    }
+}
@@ -0,0 +1,481 @@
+//! Utilities for unit-testing rule sets
+//!
+//! `RuleSetTestHarness` runs transactions through the same validation,
+//! pre-validation and state checks as a replay, but one at a time and with
+//! assertions in between, so rule sets can be tested without building a
+//! `ReplayEngine`. Enabled by the `test-utils` feature.
+//!
+//! `assert_deterministic!` and `assert_invariant_preserving!` generate
+//! property tests that apply a rule set to states and transactions drawn
+//! from `proptest` strategies.
+
+use crate::context::ExecutionContext;
+use crate::error::ProcessingError;
+use crate::hasher::StateHasher;
+use crate::state_manager::StateManager;
+use crate::traits::{RuleSet, State, Transaction};
+use crate::types::StateHash;
+use chrono::{DateTime, Utc};
+use proptest::strategy::Strategy;
+use proptest::test_runner::{Config, TestCaseError, TestRunner};
+use std::marker::PhantomData;
+
+/// Step-by-step test driver for a single rule set
+/// 
+/// The harness keeps the state produced by each successful transaction, so
+/// calls can be chained to walk through a scenario. All checks panic on
+/// failure, like the standard `assert!` macros.
+pub struct RuleSetTestHarness<S, T, R>
+where
+    S: State,
+    T: Transaction,
+    R: RuleSet<S, T>,
+{
+    rule_set: R,
+    state_manager: Option<StateManager<S>>,
+    context: ExecutionContext,
+    /// State hash before the most recent transaction
+    previous_hash: Option<StateHash>,
+    _transaction: PhantomData<T>,
+}
+
+impl<S, T, R> RuleSetTestHarness<S, T, R>
+where
+    S: State,
+    T: Transaction,
+    R: RuleSet<S, T>,
+{
+    /// Create a harness for a rule set
+    /// 
+    /// The context defaults to the Unix epoch with random seed 0.
+    pub fn new(rule_set: R) -> Self {
+        Self {
+            rule_set,
+            state_manager: None,
+            context: ExecutionContext::new(DateTime::<Utc>::UNIX_EPOCH, 0),
+            previous_hash: None,
+            _transaction: PhantomData,
+        }
+    }
+    
+    /// Set the state the first transaction is applied to
+    /// 
+    /// # Panics
+    /// Panics if the state fails validation.
+    pub fn with_initial_state(mut self, initial_state: S) -> Self {
+        let state_manager = StateManager::new(initial_state)
+            .unwrap_or_else(|e| panic!("Initial state is invalid: {}", e));
+        self.state_manager = Some(state_manager);
+        self.previous_hash = None;
+        self
+    }
+    
+    /// Set the execution context passed to the rule set
+    pub fn with_context(mut self, context: ExecutionContext) -> Self {
+        self.context = context;
+        self
+    }
+    
+    /// Apply a transaction as a setup step, keeping the resulting state
+    /// 
+    /// # Panics
+    /// Panics if the transaction fails.
+    pub fn apply_transaction(&mut self, transaction: T) -> &mut Self {
+        if let Err(e) = self.apply(&transaction) {
+            panic!("Failed to apply transaction {}: {}", transaction.id(), e);
+        }
+        self
+    }
+    
+    /// Assert that a transaction succeeds, keeping the resulting state
+    /// 
+    /// # Panics
+    /// Panics if the transaction fails.
+    pub fn expect_success(&mut self, transaction: T) -> &mut Self {
+        if let Err(e) = self.apply(&transaction) {
+            panic!("Expected transaction {} to succeed, but it failed: {}", transaction.id(), e);
+        }
+        self
+    }
+    
+    /// Assert that a transaction fails with an error accepted by `matcher`
+    /// 
+    /// The state is left unchanged.
+    /// 
+    /// # Panics
+    /// Panics if the transaction succeeds or `matcher` rejects the error.
+    pub fn expect_failure(&mut self, transaction: T, matcher: impl Fn(&ProcessingError) -> bool) -> &mut Self {
+        match self.apply(&transaction) {
+            Ok(()) => panic!("Expected transaction {} to fail, but it succeeded", transaction.id()),
+            Err(e) if !matcher(&e) => panic!("Transaction {} failed with an unexpected error: {}", transaction.id(), e),
+            Err(_) => {}
+        }
+        self
+    }
+    
+    /// Assert that the current state satisfies `matcher`
+    /// 
+    /// # Panics
+    /// Panics if `matcher` returns false.
+    pub fn assert_state(&mut self, matcher: impl Fn(&S) -> bool) -> &mut Self {
+        assert!(matcher(self.state()), "State does not match: {}", self.describe_state());
+        self
+    }
+    
+    /// Assert that the most recent transaction left the state hash unchanged
+    /// 
+    /// # Panics
+    /// Panics if no transaction has been applied yet or the hash changed.
+    pub fn assert_hash_unchanged(&mut self) -> &mut Self {
+        let previous = self.previous_hash
+            .expect("No transaction has been applied, so there is no previous hash to compare with");
+        let current = self.current_hash();
+        assert_eq!(previous, current, "State hash changed from {} to {}", previous, current);
+        self
+    }
+    
+    /// Get the current state
+    /// 
+    /// # Panics
+    /// Panics if no initial state has been set.
+    pub fn state(&self) -> &S {
+        self.state_manager().current_state()
+    }
+    
+    /// Get the hash of the current state
+    /// 
+    /// # Panics
+    /// Panics if no initial state has been set.
+    pub fn current_hash(&self) -> StateHash {
+        self.state_manager().current_hash()
+    }
+    
+    /// Run a transaction through validation and the rule set
+    fn apply(&mut self, transaction: &T) -> Result<(), ProcessingError> {
+        let state_manager = self.state_manager.as_mut()
+            .expect("RuleSetTestHarness needs an initial state; call with_initial_state first");
+        self.previous_hash = Some(state_manager.current_hash());
+        state_manager.apply_transaction(transaction, &self.rule_set, &self.context)?;
+        Ok(())
+    }
+    
+    fn state_manager(&self) -> &StateManager<S> {
+        self.state_manager.as_ref()
+            .expect("RuleSetTestHarness needs an initial state; call with_initial_state first")
+    }
+    
+    fn describe_state(&self) -> String {
+        serde_json::to_string(self.state()).unwrap_or_else(|e| format!("<unserializable state: {}>", e))
+    }
+}
+
+/// Generate a `#[test]` function checking that a rule set is deterministic
+/// 
+/// ```ignore
+/// assert_deterministic!(transfer_rules_are_deterministic, TransferRulesV1, states(), transfers());
+/// assert_deterministic!(fee_rules_are_deterministic, FeeRules, states(), transfers(), iterations = 200);
+/// ```
+/// 
+/// The first argument names the generated test. For each state and
+/// transaction drawn from the two `proptest` strategies, `check_deterministic`
+/// must pass; `iterations` defaults to 50 cases. The rule set sees
+/// `default_context`.
+#[macro_export]
+macro_rules! assert_deterministic {
+    ($name:ident, $rule_set:expr, $states:expr, $transactions:expr $(,)?) => {
+        $crate::assert_deterministic!($name, $rule_set, $states, $transactions, iterations = 50);
+    };
+    ($name:ident, $rule_set:expr, $states:expr, $transactions:expr, iterations = $iterations:expr $(,)?) => {
+        #[test]
+        fn $name() {
+            let rule_set = $rule_set;
+            let context = $crate::testing::default_context();
+            $crate::testing::run_property($iterations, $states, $transactions, |state, transaction| {
+                $crate::testing::check_deterministic(&rule_set, state, transaction, &context)
+            });
+        }
+    };
+}
+
+/// Generate a `#[test]` function checking that a rule set preserves an invariant
+/// 
+/// ```ignore
+/// assert_invariant_preserving!(transfers_conserve_money, TransferRulesV1, balance_conservation, states(), transfers());
+/// ```
+/// 
+/// The invariant is called as `invariant(&before, &after)` after every
+/// successful `apply`, so it can relate the new state to the old one. Takes
+/// the same test name, strategies and `iterations` as `assert_deterministic!`.
+#[macro_export]
+macro_rules! assert_invariant_preserving {
+    ($name:ident, $rule_set:expr, $invariant:expr, $states:expr, $transactions:expr $(,)?) => {
+        $crate::assert_invariant_preserving!($name, $rule_set, $invariant, $states, $transactions, iterations = 50);
+    };
+    ($name:ident, $rule_set:expr, $invariant:expr, $states:expr, $transactions:expr, iterations = $iterations:expr $(,)?) => {
+        #[test]
+        fn $name() {
+            let rule_set = $rule_set;
+            let context = $crate::testing::default_context();
+            $crate::testing::run_property($iterations, $states, $transactions, |state, transaction| {
+                $crate::testing::check_invariant_preserving(&rule_set, state, transaction, &context, $invariant)
+            });
+        }
+    };
+}
+
+/// The context used by the property test macros: the Unix epoch with random seed 0
+pub fn default_context() -> ExecutionContext {
+    ExecutionContext::new(DateTime::<Utc>::UNIX_EPOCH, 0)
+}
+
+/// Run `check` on `iterations` pairs drawn from the two strategies
+/// 
+/// # Panics
+/// Panics with the shrunk failing input if `check` returns an error.
+pub fn run_property<S: std::fmt::Debug, T: std::fmt::Debug>(
+    iterations: u32,
+    states: impl Strategy<Value = S>,
+    transactions: impl Strategy<Value = T>,
+    check: impl Fn(&S, &T) -> Result<(), String>,
+) {
+    let mut runner = TestRunner::new(Config {
+        cases: iterations,
+        ..Config::default()
+    });
+    if let Err(e) = runner.run(&(states, transactions), |(state, transaction)| {
+        check(&state, &transaction).map_err(TestCaseError::fail)
+    }) {
+        panic!("{}", e);
+    }
+}
+
+/// Check that applying a transaction to a state is deterministic
+/// 
+/// Verifies that:
+/// 1. applying the same inputs twice gives the same state hash, or the same error
+/// 2. `apply` is a pure function of its inputs: the result is unchanged after
+///    an unrelated `apply` in between and for a state restored from JSON
+/// 3. `version()` returns the same version before and after applying
+/// 4. the input state's hash is unchanged by `apply`, in particular when it fails
+pub fn check_deterministic<S, T, R>(rule_set: &R, state: &S, transaction: &T, context: &ExecutionContext) -> Result<(), String>
+where
+    S: State,
+    T: Transaction,
+    R: RuleSet<S, T>,
+{
+    let hasher = StateHasher::new();
+    let version = rule_set.version();
+    let hash_before = hasher.hash(state);
+    
+    let first = rule_set.apply(state, transaction, context);
+    let first_outcome = outcome(&hasher, &first);
+    if hasher.hash(state) != hash_before {
+        let outcome = if first.is_ok() { "Applying" } else { "Failed" };
+        return Err(format!("{} transaction {} changed the input state", outcome, transaction.id()));
+    }
+    
+    let second_outcome = outcome(&hasher, &rule_set.apply(state, transaction, context));
+    if second_outcome != first_outcome {
+        return Err(format!(
+            "Applying transaction {} twice gave {:?}, then {:?}",
+            transaction.id(),
+            first_outcome,
+            second_outcome
+        ));
+    }
+    
+    // Feed the rule set something else in between to expose hidden internal state
+    if let Ok(next_state) = &first {
+        let _ = rule_set.apply(next_state, transaction, context);
+    }
+    let interleaved_outcome = outcome(&hasher, &rule_set.apply(state, transaction, context));
+    if interleaved_outcome != first_outcome {
+        return Err(format!(
+            "Transaction {} gave {:?} after another apply, but {:?} before",
+            transaction.id(),
+            interleaved_outcome,
+            first_outcome
+        ));
+    }
+    
+    let restored: S = serde_json::to_string(state)
+        .and_then(|json| serde_json::from_str(&json))
+        .map_err(|e| format!("State does not round-trip through JSON: {}", e))?;
+    let restored_outcome = outcome(&hasher, &rule_set.apply(&restored, transaction, context));
+    if restored_outcome != first_outcome {
+        return Err(format!(
+            "Transaction {} gave {:?} for a state restored from JSON, but {:?} for the original",
+            transaction.id(),
+            restored_outcome,
+            first_outcome
+        ));
+    }
+    
+    if rule_set.version() != version {
+        return Err(format!("Rule set version changed from {} to {}", version, rule_set.version()));
+    }
+    Ok(())
+}
+
+/// Check that `invariant(&state, &after)` holds if the transaction applies successfully
+pub fn check_invariant_preserving<S, T, R>(
+    rule_set: &R,
+    state: &S,
+    transaction: &T,
+    context: &ExecutionContext,
+    invariant: impl Fn(&S, &S) -> bool,
+) -> Result<(), String>
+where
+    S: State,
+    T: Transaction,
+    R: RuleSet<S, T>,
+{
+    match rule_set.apply(state, transaction, context) {
+        Ok(after) if !invariant(state, &after) => {
+            Err(format!("Transaction {} broke the invariant", transaction.id()))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Reduce an `apply` result to something comparable between runs
+fn outcome<S: State>(hasher: &StateHasher, result: &Result<S, ProcessingError>) -> Result<StateHash, String> {
+    match result {
+        Ok(state) => Ok(hasher.hash(state)),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ValidationError;
+    use crate::types::Version;
+    use serde::{Deserialize, Serialize};
+    
+    #[derive(Debug, Clone, Hash, Serialize, Deserialize)]
+    struct Counter {
+        value: i64,
+    }
+    
+    impl State for Counter {
+        fn validate(&self) -> Result<(), ValidationError> {
+            Ok(())
+        }
+    }
+    
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    struct Add {
+        id: String,
+        amount: i64,
+    }
+    
+    impl Transaction for Add {
+        fn id(&self) -> &str {
+            &self.id
+        }
+        
+        fn timestamp(&self) -> DateTime<Utc> {
+            DateTime::<Utc>::UNIX_EPOCH
+        }
+        
+        fn validate(&self) -> Result<(), ValidationError> {
+            Ok(())
+        }
+    }
+    
+    struct AddRules;
+    
+    impl RuleSet<Counter, Add> for AddRules {
+        fn version(&self) -> Version {
+            Version::new(1, 0, 0)
+        }
+        
+        fn apply(&self, state: &Counter, transaction: &Add, _context: &ExecutionContext) -> Result<Counter, ProcessingError> {
+            if transaction.amount < 0 {
+                return Err(ProcessingError::TransactionFailed {
+                    transaction_id: transaction.id.clone(),
+                    reason: "Negative amount".to_string(),
+                });
+            }
+            Ok(Counter { value: state.value + transaction.amount })
+        }
+    }
+    
+    fn add(id: &str, amount: i64) -> Add {
+        Add { id: id.to_string(), amount }
+    }
+    
+    #[test]
+    fn test_harness_tracks_state_between_steps() {
+        RuleSetTestHarness::new(AddRules)
+            .with_initial_state(Counter { value: 1 })
+            .apply_transaction(add("tx1", 2))
+            .expect_success(add("tx2", 3))
+            .assert_state(|state| state.value == 6)
+            .expect_failure(add("tx3", -1), |e| matches!(e, ProcessingError::TransactionFailed { .. }))
+            .assert_hash_unchanged()
+            .assert_state(|state| state.value == 6);
+    }
+    
+    #[test]
+    #[should_panic(expected = "State hash changed")]
+    fn test_assert_hash_unchanged_panics_after_change() {
+        RuleSetTestHarness::new(AddRules)
+            .with_initial_state(Counter { value: 1 })
+            .expect_success(add("tx1", 2))
+            .assert_hash_unchanged();
+    }
+    
+    #[test]
+    #[should_panic(expected = "to fail")]
+    fn test_expect_failure_panics_on_success() {
+        RuleSetTestHarness::new(AddRules)
+            .with_initial_state(Counter { value: 1 })
+            .expect_failure(add("tx1", 2), |_| true);
+    }
+    
+    /// Adds one more on every call, so the result depends on the call history
+    #[derive(Default)]
+    struct DriftingRules {
+        calls: std::sync::atomic::AtomicI64,
+    }
+    
+    impl RuleSet<Counter, Add> for DriftingRules {
+        fn version(&self) -> Version {
+            Version::new(1, 0, 0)
+        }
+        
+        fn apply(&self, state: &Counter, transaction: &Add, _context: &ExecutionContext) -> Result<Counter, ProcessingError> {
+            let calls = self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Ok(Counter { value: state.value + transaction.amount + calls })
+        }
+    }
+    
+    #[test]
+    fn test_check_deterministic_detects_hidden_state() {
+        let context = default_context();
+        assert!(check_deterministic(&AddRules, &Counter { value: 1 }, &add("tx1", 2), &context).is_ok());
+        assert!(check_deterministic(&AddRules, &Counter { value: 1 }, &add("tx1", -2), &context).is_ok());
+        
+        let error = check_deterministic(&DriftingRules::default(), &Counter { value: 1 }, &add("tx1", 2), &context).unwrap_err();
+        assert!(error.contains("twice"), "{}", error);
+    }
+    
+    #[test]
+    fn test_check_invariant_preserving_ignores_failed_transactions() {
+        let context = default_context();
+        let grows = |before: &Counter, after: &Counter| after.value > before.value;
+        assert!(check_invariant_preserving(&AddRules, &Counter { value: 1 }, &add("tx1", 2), &context, grows).is_ok());
+        assert!(check_invariant_preserving(&AddRules, &Counter { value: 1 }, &add("tx1", -2), &context, grows).is_ok());
+        assert!(check_invariant_preserving(&AddRules, &Counter { value: 1 }, &add("tx1", 0), &context, grows).is_err());
+    }
+    
+    crate::assert_deterministic!(
+        test_add_rules_are_deterministic,
+        AddRules,
+        (0i64..100).prop_map(|value| Counter { value }),
+        (-5i64..5).prop_map(|amount| add("tx", amount)),
+        iterations = 20,
+    );
+}
@@ -0,0 +1,200 @@
+//! Chronological view of an execution trace
+
+use crate::types::{ExecutionTrace, StateHash};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Kind of event shown on a timeline
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TimelineEventType {
+    /// A transaction was applied by the rule set
+    TransactionProcessed,
+    /// A transaction was skipped instead of applied
+    TransactionRejected,
+    /// A checkpoint of the state was taken
+    CheckpointCreated,
+    /// An invariant was checked against the state
+    InvariantChecked,
+    /// A transaction was applied by a different rule set version than the one before it
+    RuleChanged,
+}
+
+impl TimelineEventType {
+    /// Character marking the event in `Timeline::to_ascii_chart`
+    pub fn marker(self) -> char {
+        match self {
+            TimelineEventType::TransactionProcessed => '*',
+            TimelineEventType::TransactionRejected => 'x',
+            TimelineEventType::CheckpointCreated => '#',
+            TimelineEventType::InvariantChecked => '+',
+            TimelineEventType::RuleChanged => '^',
+        }
+    }
+}
+
+/// One event on a timeline
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TimelineEvent {
+    pub timestamp: DateTime<Utc>,
+    pub event_type: TimelineEventType,
+    pub transaction_id: Option<String>,
+    pub description: String,
+    /// State hash once the event happened
+    pub state_hash: StateHash,
+}
+
+/// Events of a replay ordered by timestamp
+/// 
+/// Events with equal timestamps keep the order they happened in.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Timeline {
+    events: Vec<TimelineEvent>,
+}
+
+impl Timeline {
+    /// Create an empty timeline
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Build the timeline of an execution trace
+    /// 
+    /// Each rule application becomes a `TransactionProcessed` event, preceded
+    /// by a `RuleChanged` event when its version differs from the previous
+    /// application's, and each checkpoint a `CheckpointCreated` event after the
+    /// transaction it was taken at. The trace records neither when skipped
+    /// transactions were skipped nor invariant checks: skipped transactions
+    /// become `TransactionRejected` events at the end of the timeline (none if
+    /// nothing else happened), and `InvariantChecked` events can only be added
+    /// with `push`. State mutations are not shown.
+    pub fn from_trace(trace: &ExecutionTrace) -> Self {
+        let transitions = trace.state_transitions.iter().filter(|t| !trace.is_mutation(&t.transaction_id));
+        // A processor restored from a checkpoint starts counting at the checkpoint's index
+        let first_index = trace.transactions_processed.saturating_sub(trace.rule_applications.len());
+        let mut checkpoints = trace.checkpoints.iter().peekable();
+        let mut events = Vec::new();
+        let mut previous_version = None;
+        
+        for (position, (application, transition)) in trace.rule_applications.iter().zip(transitions).enumerate() {
+            while let Some(checkpoint) = checkpoints.next_if(|c| c.transaction_index <= first_index + position) {
+                events.push(checkpoint_event(checkpoint.timestamp, checkpoint.hash, checkpoint.transaction_index));
+            }
+            if previous_version.is_some_and(|version| version != &application.rule_version) {
+                events.push(TimelineEvent {
+                    timestamp: application.timestamp,
+                    event_type: TimelineEventType::RuleChanged,
+                    transaction_id: Some(application.transaction_id.clone()),
+                    description: format!("Rule set version {}", application.rule_version),
+                    state_hash: transition.from_hash,
+                });
+            }
+            previous_version = Some(&application.rule_version);
+            
+            let description = if application.description.is_empty() {
+                format!("Processed {}", application.transaction_id)
+            } else {
+                application.description.clone()
+            };
+            events.push(TimelineEvent {
+                timestamp: application.timestamp,
+                event_type: TimelineEventType::TransactionProcessed,
+                transaction_id: Some(application.transaction_id.clone()),
+                description,
+                state_hash: transition.to_hash,
+            });
+        }
+        for checkpoint in checkpoints {
+            events.push(checkpoint_event(checkpoint.timestamp, checkpoint.hash, checkpoint.transaction_index));
+        }
+        
+        let mut timeline = Self { events };
+        timeline.events.sort_by_key(|event| event.timestamp);
+        
+        if let Some(last) = timeline.events.last().cloned() {
+            let timestamp = trace.watermark.high_watermark_timestamp.map_or(last.timestamp, |w| w.max(last.timestamp));
+            for transaction_id in &trace.skipped_transactions {
+                timeline.events.push(TimelineEvent {
+                    timestamp,
+                    event_type: TimelineEventType::TransactionRejected,
+                    transaction_id: Some(transaction_id.clone()),
+                    description: format!("Skipped {}", transaction_id),
+                    state_hash: last.state_hash,
+                });
+            }
+        }
+        timeline
+    }
+    
+    /// Add an event after every event with the same or an earlier timestamp
+    pub fn push(&mut self, event: TimelineEvent) {
+        let position = self.events.partition_point(|e| e.timestamp <= event.timestamp);
+        self.events.insert(position, event);
+    }
+    
+    /// Get all events in chronological order
+    pub fn events(&self) -> &[TimelineEvent] {
+        &self.events
+    }
+    
+    /// Get the events from `start` to `end`, both inclusive
+    pub fn events_between(&self, start: DateTime<Utc>, end: DateTime<Utc>) -> &[TimelineEvent] {
+        let from = self.events.partition_point(|e| e.timestamp < start);
+        let to = self.events.partition_point(|e| e.timestamp <= end).max(from);
+        &self.events[from..to]
+    }
+    
+    /// Get the events of one type in chronological order
+    pub fn events_of_type(&self, event_type: TimelineEventType) -> Vec<&TimelineEvent> {
+        self.events.iter().filter(|e| e.event_type == event_type).collect()
+    }
+    
+    /// Get the number of events
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+    
+    /// Check whether the timeline has no events
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+    
+    /// Render the timeline as text, one event per line
+    /// 
+    /// The first line gives the first and last timestamp. Each event line
+    /// starts with a track `width` characters wide holding the event's
+    /// `TimelineEventType::marker` at its position in time, followed by the
+    /// event's timestamp and description. Returns an empty string for an
+    /// empty timeline.
+    pub fn to_ascii_chart(&self, width: usize) -> String {
+        let (Some(first), Some(last)) = (self.events.first(), self.events.last()) else {
+            return String::new();
+        };
+        let width = width.max(1);
+        let span = (last.timestamp - first.timestamp).num_milliseconds();
+        
+        let mut chart = format!("{} .. {}\n", first.timestamp.to_rfc3339(), last.timestamp.to_rfc3339());
+        for event in &self.events {
+            let offset = (event.timestamp - first.timestamp).num_milliseconds();
+            let column = if span > 0 {
+                (offset as i128 * (width - 1) as i128 / span as i128) as usize
+            } else {
+                0
+            };
+            let track: String = (0..width)
+                .map(|i| if i == column { event.event_type.marker() } else { '.' })
+                .collect();
+            chart.push_str(&format!("{} {} {}\n", track, event.timestamp.to_rfc3339(), event.description));
+        }
+        chart
+    }
+}
+
+fn checkpoint_event(timestamp: DateTime<Utc>, hash: StateHash, transaction_index: usize) -> TimelineEvent {
+    TimelineEvent {
+        timestamp,
+        event_type: TimelineEventType::CheckpointCreated,
+        transaction_id: None,
+        description: format!("Checkpoint after {} transactions", transaction_index),
+        state_hash: hash,
+    }
+}
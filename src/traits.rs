@@ -1,16 +1,170 @@
 //! Core traits for the DTRE
 
+use std::any::Any;
+use std::collections::HashMap;
 use std::hash::Hash;
+use std::sync::Arc;
 use serde::{Serialize, de::DeserializeOwned};
 use chrono::{DateTime, Utc};
-use crate::error::{ValidationError, ProcessingError};
-use crate::types::Version;
+use crate::error::{ValidationError, ProcessingError, SerializationError, StateError};
+use crate::types::{AuditRecord, FieldChange, InvariantViolation, IterationStrategy, ReplayCostEstimate, StateInvariant, Version};
 use crate::context::ExecutionContext;
+use crate::rule_contract::{ContractDocumentation, RuleCondition};
+use crate::rule_set::SequentialRuleSet;
+use crate::side_effects::SideEffectQueue;
+use crate::state_manager::StatePatch;
 
 /// Trait for state objects that can be replayed deterministically
 pub trait State: Clone + Serialize + DeserializeOwned + Hash {
+    /// Version of the state schema, bumped whenever the serialized layout changes
+    const SCHEMA_VERSION: u32 = 1;
+    
+    /// Whether `normalize` can change the state
+    /// 
+    /// `StateHasher::hash` only copies a state to normalize it when this is
+    /// true, and hashes it in place otherwise. Debug builds check that hashing
+    /// in place gives the same hash as normalizing first, so a state that
+    /// overrides `normalize` without setting this fails loudly.
+    const NORMALIZES: bool = false;
+    
+    /// Whether `validate_field` checks declared fields on its own
+    /// 
+    /// When true, the state manager validates a new state with one
+    /// `validate_field` call per path of `RuleSet::affects_fields`. When
+    /// false, the default, it always runs `validate` once.
+    const FIELD_VALIDATION: bool = false;
+    
     /// Validate the state for consistency and correctness
     fn validate(&self) -> Result<(), ValidationError>;
+    
+    /// Validate only the checks that concern the field at `path`
+    /// 
+    /// `path` is one of the dot-separated paths from `RuleSet::affects_fields`
+    /// and may contain `*` segments, e.g. `accounts.*.balance`. For states
+    /// with `FIELD_VALIDATION` set, the state manager calls this for each
+    /// declared path instead of `validate` when a rule set declares its
+    /// fields, so a state must check here every invariant a write to `path`
+    /// can break. The default runs `validate`.
+    fn validate_field(&self, _path: &str) -> Result<(), ValidationError> {
+        self.validate()
+    }
+    
+    /// Migrate a state serialized under an older schema version to the current schema
+    /// 
+    /// `raw` is the state JSON as stored in the old checkpoint, see
+    /// `StateManager::restore_raw_checkpoint`. The default implementation
+    /// rejects every migration.
+    fn migrate(old_version: u32, _raw: serde_json::Value) -> Result<Self, StateError> {
+        Err(StateError::SchemaMismatch {
+            checkpoint_version: old_version,
+            current_version: Self::SCHEMA_VERSION,
+        })
+    }
+    
+    /// Order in which `StateHasher::hash` visits the entries of the state's maps
+    /// 
+    /// The default, `Insertion`, hashes the bincode serialization directly, so states
+    /// holding a `HashMap` can hash differently depending on how the map was built.
+    /// Return `Sorted` to hash the canonical JSON form instead; this requires the state
+    /// to be representable as JSON.
+    fn iteration_order() -> IterationStrategy {
+        IterationStrategy::Insertion
+    }
+    
+    /// Bring the state into a canonical form before it is hashed
+    /// 
+    /// States that are equal in business terms but differ in representation,
+    /// such as a history whose entries can be recorded in any order, should
+    /// canonicalize that representation here so they hash identically.
+    /// The state manager stores each new state in normalized form, and
+    /// `StateHasher::hash` normalizes a copy of every state it hashes when
+    /// `NORMALIZES` is true. Normalizing must be idempotent. The default
+    /// implementation does nothing.
+    /// 
+    /// Set `NORMALIZES` to true when overriding this method.
+    fn normalize(&mut self) {}
+    
+    /// Serialize the state to canonical JSON for hash verification outside Rust
+    /// 
+    /// The algorithm is:
+    /// 1. Serialize the state with `serde_json`.
+    /// 2. Sort the keys of every JSON object, recursively, in byte order of their UTF-8 encoding.
+    ///    Array order is preserved.
+    /// 3. Write the result compactly: no spaces or newlines between tokens,
+    ///    non-ASCII characters written as raw UTF-8, and numbers as `serde_json` formats them.
+    /// 
+    /// The canonical hash is the SHA-256 digest of the UTF-8 bytes of this string;
+    /// see `StateHash::from_canonical_json_bytes`. In Python the same string is
+    /// produced by `json.dumps(value, sort_keys=True, separators=(",", ":"), ensure_ascii=False)`
+    /// for states without floating point fields.
+    /// 
+    /// Fails if the state cannot be represented as JSON, e.g. a map with non-string keys.
+    fn canonical_json(&self) -> Result<String, SerializationError> {
+        crate::serialization::to_canonical_json(self)
+    }
+    
+    /// Apply field-level changes in place, see `StatePatch`
+    /// 
+    /// The default implementation patches the `serde_json` representation of
+    /// the state and deserializes the result into `self`, which is left
+    /// unchanged on error. Override it to write the changed fields directly.
+    fn apply_patch(&mut self, patch: &StatePatch) -> Result<(), StateError> {
+        let mut json = serde_json::to_value(&*self).map_err(|e| StateError::TransitionFailed {
+            reason: format!("State could not be serialized for patching: {}", e),
+        })?;
+        patch.apply_to_json(&mut json).map_err(|e| StateError::TransitionFailed {
+            reason: e.to_string(),
+        })?;
+        *self = serde_json::from_value(json).map_err(|e| StateError::TransitionFailed {
+            reason: format!("Patched state could not be deserialized: {}", e),
+        })?;
+        Ok(())
+    }
+    
+    /// List the fields that differ between this state and `other`
+    /// 
+    /// The default implementation compares the `serde_json` representations of
+    /// both states; see `FieldChange` for how paths are written. A state that
+    /// cannot be represented as JSON compares as `null`.
+    fn diff_fields(&self, other: &Self) -> Vec<FieldChange> {
+        let old = serde_json::to_value(self).unwrap_or(serde_json::Value::Null);
+        let new = serde_json::to_value(other).unwrap_or(serde_json::Value::Null);
+        crate::types::json_field_changes(&old, &new)
+    }
+    
+    /// List the named invariants of the state, checked by the state manager after every transition
+    /// 
+    /// Unlike the checks in `validate`, each invariant can be tested and
+    /// enforced on its own: a transition breaking a `Hard` invariant is
+    /// rejected, while a broken `Soft` invariant is only recorded in
+    /// `StateManager::invariant_warnings`. The default has none.
+    fn invariants(&self) -> Vec<StateInvariant<Self>> {
+        Vec::new()
+    }
+    
+    /// Check every invariant, returning those that do not hold in `invariants` order
+    fn check_invariants(&self) -> Vec<InvariantViolation> {
+        self.invariants()
+            .into_iter()
+            .filter(|invariant| !invariant.holds(self))
+            .map(|invariant| InvariantViolation {
+                invariant_name: invariant.name,
+                severity: invariant.severity,
+            })
+            .collect()
+    }
+    
+    /// Compute a derived value from this state
+    /// 
+    /// A convenience for chaining; see `StateAggregator` for sums, counts and
+    /// extremes over JSON paths.
+    fn aggregate<V, F>(&self, extractor: F) -> V
+    where
+        Self: Sized,
+        F: Fn(&Self) -> V,
+    {
+        extractor(self)
+    }
 }
 
 /// Trait for transaction events that can be processed
@@ -23,6 +177,39 @@ pub trait Transaction: Clone + Serialize + DeserializeOwned {
     
     /// Validate the transaction for completeness and correctness
     fn validate(&self) -> Result<(), ValidationError>;
+    
+    /// Get the rule set version this transaction was originally created under, if known
+    /// 
+    /// The default implementation returns `None`.
+    fn rule_version_hint(&self) -> Option<Version> {
+        None
+    }
+    
+    /// List the IDs of transactions that must be processed before this one
+    /// 
+    /// A refund, for example, depends on its original payment. Checked by
+    /// `TransactionDependencyGraph`; the default implementation returns no dependencies.
+    fn dependencies(&self) -> Vec<String> {
+        vec![]
+    }
+    
+    /// Get audit metadata such as the source system or batch ID
+    /// 
+    /// Processors copy it into `RuleApplication::application_metadata`; it is
+    /// never hashed into the state, so rule sets should not read it. The
+    /// default implementation returns `None`.
+    fn metadata(&self) -> Option<&HashMap<String, String>> {
+        None
+    }
+    
+    /// Get the concrete transaction for type-specific validation by a `ValidationRegistry`
+    /// 
+    /// Wrappers over several transaction types return the wrapped value;
+    /// other types can return `Some(self)`. The default implementation
+    /// returns `None`, so no type-specific validator applies.
+    fn as_any(&self) -> Option<&dyn Any> {
+        None
+    }
 }
 
 /// Trait for rule sets that process transactions
@@ -34,7 +221,215 @@ where
     /// Get the version of this rule set
     fn version(&self) -> Version;
     
+    /// Get the inclusive window of versions whose transactions this rule set can process
+    /// 
+    /// `None`, the default, means only transactions created for `version()` are supported.
+    fn supports_version_range(&self) -> Option<(Version, Version)> {
+        None
+    }
+    
+    /// Check whether this rule set can process transactions created for `version`
+    fn supports_version(&self, version: &Version) -> bool {
+        match self.supports_version_range() {
+            Some((min, max)) => min <= *version && *version <= max,
+            None => self.version() == *version,
+        }
+    }
+    
+    /// List the JSON paths of every state field this rule set may write
+    /// 
+    /// Paths are dot-separated, with `*` matching any single segment, e.g.
+    /// `accounts.*.balance`. The list must be exhaustive: a processor with
+    /// subtree hashing enabled only rehashes the top-level fields it names,
+    /// and the state manager only checks them with `State::validate_field`
+    /// for states with `State::FIELD_VALIDATION` set. When a list is given,
+    /// the state manager writes the rule's changes into the previous state
+    /// with `State::apply_patch` instead of replacing it.
+    /// `None`, the default, means any field may change.
+    fn affects_fields(&self) -> Option<Vec<String>> {
+        None
+    }
+    
+    /// Check business preconditions for a transaction against the current state
+    /// 
+    /// Called before `apply`. Unlike `Transaction::validate`, this has access to
+    /// the state and context. The default implementation accepts everything.
+    fn pre_validate(&self, _state: &S, _transaction: &T, _context: &ExecutionContext) -> Result<(), ValidationError> {
+        Ok(())
+    }
+    
+    /// Declare conditions the state and transaction must meet before `apply`
+    /// 
+    /// Checked only in debug builds with the `debug-contracts` feature, after
+    /// `pre_validate` accepts the transaction. The default declares none.
+    fn pre_conditions(&self) -> Vec<RuleCondition<S, T>> {
+        Vec::new()
+    }
+    
+    /// Declare conditions the state produced by `apply` must meet
+    /// 
+    /// Checked like `pre_conditions`, against the new state before it is
+    /// committed. The default declares none.
+    fn post_conditions(&self) -> Vec<RuleCondition<S, T>> {
+        Vec::new()
+    }
+    
+    /// Describe the pre- and post-conditions in human-readable form
+    fn documented_contract(&self) -> ContractDocumentation {
+        ContractDocumentation::new(self.version(), &self.pre_conditions(), &self.post_conditions())
+    }
+    
+    /// Identify the result of applying this rule set, for rules that are expensive but idempotent
+    /// 
+    /// Two calls returning the same key must produce the same new state, so
+    /// the key has to cover every part of the state, transaction and context
+    /// the rule reads. A processor with `with_rule_cache_capacity` reuses the
+    /// cached result for a known key without calling `pre_validate` or
+    /// `apply`. The default, `None`, never caches.
+    fn idempotency_key(&self, _state: &S, _transaction: &T, _context: &ExecutionContext) -> Option<u64> {
+        None
+    }
+    
     /// Apply this rule set to a state and transaction, producing a new state
     fn apply(&self, state: &S, transaction: &T, context: &ExecutionContext) -> Result<S, ProcessingError>;
+    
+    /// Apply this rule set, also returning the rule clauses that fired
+    /// 
+    /// The default implementation calls `apply` and returns an empty record;
+    /// wrap the rule set in an `InstrumentedRuleSet` to capture the clauses it
+    /// records through `RuleAuditRecorder`.
+    fn apply_with_audit(&self, state: &S, transaction: &T, context: &ExecutionContext) -> Result<(S, AuditRecord), ProcessingError> {
+        self.apply(state, transaction, context).map(|new_state| (new_state, AuditRecord::default()))
+    }
+    
+    /// Summarize a transaction in human-readable form for audit dashboards
+    /// 
+    /// Called with the state the transaction was applied to, after it was
+    /// applied successfully; processors store the result in
+    /// `RuleApplication::description`.
+    fn describe_transaction(&self, _state: &S, transaction: &T, _context: &ExecutionContext) -> String {
+        format!("Applied rule {} to transaction {}", self.version(), transaction.id())
+    }
+    
+    /// Enqueue side effects for a transaction that was applied successfully
+    /// 
+    /// Called with the new state only when a `SideEffectQueue` is attached to the
+    /// processor. Effects are collected, never executed during replay, and are
+    /// enqueued afresh every time the rule runs. The default enqueues nothing.
+    fn enqueue_side_effects(&self, _state: &S, _transaction: &T, _queue: &SideEffectQueue) {}
+    
+    /// Enqueue side effects for a transaction that took `before` to `after`
+    /// 
+    /// This is what the processor calls. The default passes `after` to
+    /// `enqueue_side_effects`; rule sets made of several steps override it to
+    /// give each step the state that step produced.
+    fn enqueue_transition_side_effects(
+        &self,
+        _before: &S,
+        after: &S,
+        transaction: &T,
+        _context: &ExecutionContext,
+        queue: &SideEffectQueue,
+    ) {
+        self.enqueue_side_effects(after, transaction, queue)
+    }
+    
+    /// Estimate the cost of replaying `transactions` from `state`
+    /// 
+    /// Checked against `ReplayEngineBuilder::with_cost_budget` before a replay
+    /// starts. The default is `ReplayCostEstimate::heuristic` with the
+    /// serialized size of `state`; override it for rules whose cost depends on
+    /// the transactions, such as ones scanning long histories.
+    fn replay_cost_estimate(&self, transactions: &[T], state: &S) -> ReplayCostEstimate {
+        ReplayCostEstimate::heuristic(transactions.len(), bincode::serialized_size(state).unwrap_or(0))
+    }
+    
+    /// Get a fixed snapshot of the rules to use for every call about one transaction
+    /// 
+    /// Rule sets whose rules can change between calls, like
+    /// `HotReloadableRuleSet`, return the rules active right now so that a
+    /// processor never mixes versions within one transaction. The default,
+    /// `None`, means the rule set never changes and is used directly.
+    fn pinned_snapshot(&self) -> Option<Arc<dyn RuleSet<S, T> + Send + Sync>> {
+        None
+    }
+    
+    /// Chain `next` after this rule set, applying it to the state this rule set produces
+    /// 
+    /// The composite's version is the higher of the two versions.
+    fn compose_sequential<R2: RuleSet<S, T>>(self, next: R2) -> SequentialRuleSet<S, T, Self, R2>
+    where
+        Self: Sized,
+    {
+        SequentialRuleSet::new(self, next)
+    }
+}
+
+/// References to rule sets, including `&dyn RuleSet`, are rule sets themselves
+impl<S, T, R> RuleSet<S, T> for &R
+where
+    S: State,
+    T: Transaction,
+    R: RuleSet<S, T> + ?Sized,
+{
+    fn version(&self) -> Version {
+        (**self).version()
+    }
+    
+    fn supports_version_range(&self) -> Option<(Version, Version)> {
+        (**self).supports_version_range()
+    }
+    
+    fn supports_version(&self, version: &Version) -> bool {
+        (**self).supports_version(version)
+    }
+    
+    fn affects_fields(&self) -> Option<Vec<String>> {
+        (**self).affects_fields()
+    }
+    
+    fn pre_validate(&self, state: &S, transaction: &T, context: &ExecutionContext) -> Result<(), ValidationError> {
+        (**self).pre_validate(state, transaction, context)
+    }
+    
+    fn pre_conditions(&self) -> Vec<RuleCondition<S, T>> {
+        (**self).pre_conditions()
+    }
+    
+    fn post_conditions(&self) -> Vec<RuleCondition<S, T>> {
+        (**self).post_conditions()
+    }
+    
+    fn idempotency_key(&self, state: &S, transaction: &T, context: &ExecutionContext) -> Option<u64> {
+        (**self).idempotency_key(state, transaction, context)
+    }
+    
+    fn apply(&self, state: &S, transaction: &T, context: &ExecutionContext) -> Result<S, ProcessingError> {
+        (**self).apply(state, transaction, context)
+    }
+    
+    fn apply_with_audit(&self, state: &S, transaction: &T, context: &ExecutionContext) -> Result<(S, AuditRecord), ProcessingError> {
+        (**self).apply_with_audit(state, transaction, context)
+    }
+    
+    fn describe_transaction(&self, state: &S, transaction: &T, context: &ExecutionContext) -> String {
+        (**self).describe_transaction(state, transaction, context)
+    }
+    
+    fn enqueue_side_effects(&self, state: &S, transaction: &T, queue: &SideEffectQueue) {
+        (**self).enqueue_side_effects(state, transaction, queue)
+    }
+    
+    fn enqueue_transition_side_effects(&self, before: &S, after: &S, transaction: &T, context: &ExecutionContext, queue: &SideEffectQueue) {
+        (**self).enqueue_transition_side_effects(before, after, transaction, context, queue)
+    }
+    
+    fn replay_cost_estimate(&self, transactions: &[T], state: &S) -> ReplayCostEstimate {
+        (**self).replay_cost_estimate(transactions, state)
+    }
+    
+    fn pinned_snapshot(&self) -> Option<Arc<dyn RuleSet<S, T> + Send + Sync>> {
+        (**self).pinned_snapshot()
+    }
 }
 
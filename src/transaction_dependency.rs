@@ -0,0 +1,232 @@
+//! Ordering constraints declared by transactions through `Transaction::dependencies`
+
+use crate::error::ProcessingError;
+use crate::traits::Transaction;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+/// Dependencies between the transactions of a sequence
+/// 
+/// Transaction IDs are expected to be unique; see
+/// `TransactionSequenceValidator::validate_no_duplicates`.
+#[derive(Debug, Clone, Default)]
+pub struct TransactionDependencyGraph {
+    /// Transaction IDs in sequence order
+    ids: Vec<String>,
+    /// Declared dependencies of each transaction
+    dependencies: HashMap<String, Vec<String>>,
+}
+
+impl TransactionDependencyGraph {
+    /// Build the graph of a sequence that is already in a valid order
+    /// 
+    /// # Errors
+    /// Returns `ProcessingError::UnsatisfiedDependency` for the first
+    /// dependency that is missing from the sequence or appears at or after
+    /// the transaction depending on it
+    pub fn build<T: Transaction>(transactions: &[T]) -> Result<Self, ProcessingError> {
+        let mut seen: HashSet<&str> = HashSet::new();
+        for transaction in transactions {
+            if let Some(depends_on) = transaction.dependencies().into_iter().find(|id| !seen.contains(id.as_str())) {
+                return Err(ProcessingError::UnsatisfiedDependency {
+                    transaction_id: transaction.id().to_string(),
+                    depends_on,
+                });
+            }
+            seen.insert(transaction.id());
+        }
+        
+        Ok(Self {
+            ids: transactions.iter().map(|t| t.id().to_string()).collect(),
+            dependencies: transactions
+                .iter()
+                .map(|t| (t.id().to_string(), t.dependencies()))
+                .collect(),
+        })
+    }
+    
+    /// Reorder transactions so that every one follows its dependencies
+    /// 
+    /// The sort is stable: whenever several transactions are ready, the one
+    /// earliest in the input goes first, so an already valid sequence comes
+    /// back unchanged.
+    /// 
+    /// # Errors
+    /// Returns `ProcessingError::UnsatisfiedDependency` for a dependency that
+    /// is missing from the sequence, or for the earliest transaction caught in
+    /// a dependency cycle
+    pub fn topological_sort<T: Transaction>(transactions: &[T]) -> Result<Vec<T>, ProcessingError> {
+        let index_of: HashMap<&str, usize> = transactions
+            .iter()
+            .enumerate()
+            .rev()
+            .map(|(index, t)| (t.id(), index))
+            .collect();
+        
+        let mut dependents: Vec<Vec<usize>> = vec![Vec::new(); transactions.len()];
+        let mut pending: Vec<usize> = vec![0; transactions.len()];
+        for (index, transaction) in transactions.iter().enumerate() {
+            for depends_on in transaction.dependencies() {
+                let Some(&dependency) = index_of.get(depends_on.as_str()) else {
+                    return Err(ProcessingError::UnsatisfiedDependency {
+                        transaction_id: transaction.id().to_string(),
+                        depends_on,
+                    });
+                };
+                dependents[dependency].push(index);
+                pending[index] += 1;
+            }
+        }
+        
+        let mut ready: BinaryHeap<Reverse<usize>> = (0..transactions.len())
+            .filter(|&index| pending[index] == 0)
+            .map(Reverse)
+            .collect();
+        let mut sorted = Vec::with_capacity(transactions.len());
+        let mut emitted = vec![false; transactions.len()];
+        while let Some(Reverse(index)) = ready.pop() {
+            sorted.push(transactions[index].clone());
+            emitted[index] = true;
+            for &dependent in &dependents[index] {
+                pending[dependent] -= 1;
+                if pending[dependent] == 0 {
+                    ready.push(Reverse(dependent));
+                }
+            }
+        }
+        
+        if let Some(blocked) = (0..transactions.len()).find(|&index| !emitted[index]) {
+            let transaction = &transactions[blocked];
+            let depends_on = transaction
+                .dependencies()
+                .into_iter()
+                .find(|id| !emitted[index_of[id.as_str()]])
+                .unwrap_or_default();
+            return Err(ProcessingError::UnsatisfiedDependency {
+                transaction_id: transaction.id().to_string(),
+                depends_on,
+            });
+        }
+        Ok(sorted)
+    }
+    
+    /// Get the declared dependencies of a transaction
+    /// 
+    /// Returns an empty slice for IDs not in the graph.
+    pub fn dependencies_of(&self, transaction_id: &str) -> &[String] {
+        self.dependencies.get(transaction_id).map_or(&[], Vec::as_slice)
+    }
+    
+    /// Get the IDs of the transactions that depend on `transaction_id`, in sequence order
+    pub fn dependents_of(&self, transaction_id: &str) -> Vec<&str> {
+        self.ids
+            .iter()
+            .filter(|id| self.dependencies_of(id).iter().any(|d| d == transaction_id))
+            .map(String::as_str)
+            .collect()
+    }
+    
+    /// Get the number of transactions in the graph
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+    
+    /// Check if the graph is empty
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ValidationError;
+    use chrono::{DateTime, Utc};
+    use serde::{Deserialize, Serialize};
+    
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    struct Step {
+        id: String,
+        after: Vec<String>,
+    }
+    
+    impl Transaction for Step {
+        fn id(&self) -> &str {
+            &self.id
+        }
+        
+        fn timestamp(&self) -> DateTime<Utc> {
+            DateTime::<Utc>::UNIX_EPOCH
+        }
+        
+        fn validate(&self) -> Result<(), ValidationError> {
+            Ok(())
+        }
+        
+        fn dependencies(&self) -> Vec<String> {
+            self.after.clone()
+        }
+    }
+    
+    fn step(id: &str, after: &[&str]) -> Step {
+        Step {
+            id: id.to_string(),
+            after: after.iter().map(|a| a.to_string()).collect(),
+        }
+    }
+    
+    fn ids(steps: &[Step]) -> Vec<&str> {
+        steps.iter().map(|s| s.id.as_str()).collect()
+    }
+    
+    #[test]
+    fn test_build_rejects_dependency_on_later_transaction() {
+        let ordered = vec![step("a", &[]), step("b", &["a"]), step("c", &["a", "b"])];
+        let graph = TransactionDependencyGraph::build(&ordered).unwrap();
+        assert_eq!(graph.len(), 3);
+        assert_eq!(graph.dependencies_of("c"), ["a".to_string(), "b".to_string()]);
+        assert_eq!(graph.dependents_of("a"), vec!["b", "c"]);
+        
+        let reversed = vec![step("b", &["a"]), step("a", &[])];
+        assert!(matches!(
+            TransactionDependencyGraph::build(&reversed),
+            Err(ProcessingError::UnsatisfiedDependency { transaction_id, depends_on })
+                if transaction_id == "b" && depends_on == "a"
+        ));
+    }
+    
+    #[test]
+    fn test_refund_without_its_payment_is_unsatisfied() {
+        let sequence = vec![step("payment-2", &[]), step("refund-1", &["payment-1"])];
+        for result in [
+            TransactionDependencyGraph::build(&sequence).map(|_| ()),
+            TransactionDependencyGraph::topological_sort(&sequence).map(|_| ()),
+        ] {
+            match result {
+                Err(ProcessingError::UnsatisfiedDependency { transaction_id, depends_on }) => {
+                    assert_eq!(transaction_id, "refund-1");
+                    assert_eq!(depends_on, "payment-1");
+                }
+                other => panic!("Expected an unsatisfied dependency, got {:?}", other),
+            }
+        }
+    }
+    
+    #[test]
+    fn test_topological_sort_is_stable() {
+        let steps = vec![step("c", &["b"]), step("x", &[]), step("b", &["a"]), step("a", &[])];
+        let sorted = TransactionDependencyGraph::topological_sort(&steps).unwrap();
+        assert_eq!(ids(&sorted), vec!["x", "a", "b", "c"]);
+        assert!(TransactionDependencyGraph::build(&sorted).is_ok());
+        
+        let already_ordered = vec![step("a", &[]), step("z", &[]), step("b", &["a"])];
+        assert_eq!(ids(&TransactionDependencyGraph::topological_sort(&already_ordered).unwrap()), vec!["a", "z", "b"]);
+        
+        let cycle = vec![step("a", &[]), step("p", &["q"]), step("q", &["p"])];
+        assert!(matches!(
+            TransactionDependencyGraph::topological_sort(&cycle),
+            Err(ProcessingError::UnsatisfiedDependency { transaction_id, depends_on })
+                if transaction_id == "p" && depends_on == "q"
+        ));
+    }
+}
@@ -1,17 +1,218 @@
 //! Transaction processing engine with rule application and execution tracing
 
-use crate::context::ExecutionContext;
-use crate::error::ProcessingError;
-use crate::state_manager::StateManager;
+#[cfg(feature = "debug-audit")]
+use crate::audit_log::{AuditLogEntry, AuditOperation};
+use crate::context::{ExecutionContext, ExecutionPhase};
+use crate::error::{ProcessingError, RuleError, RuleErrorContext, SerializationError, StateError, ValidationError};
+use crate::hasher::{NormalizedState, StateHasher, SubtreeHashes};
+use crate::logging::{DeterministicLogger, ExecutionTraceLog, LogEntry, LogLevel, TraceEventType};
+use crate::rate_limit::TokenBucket;
+use crate::id_normalizer::TransactionIdNormalizer;
+use crate::rule_cache::{LruCache, RuleApplicationCache};
+use crate::side_effects::SideEffectQueue;
+use crate::state_manager::{Checkpoint, PhaseTimings, StateDiff, StateManager};
+use crate::statistics::{ProcessingStatistics, StatisticsRecorder};
 use crate::traits::{RuleSet, State, Transaction};
-use crate::types::{ExecutionTrace, RuleApplication, StateTransition, StateTransitionInfo};
-use chrono::{DateTime, Utc};
+use crate::types::{
+    ExecutionTrace, InvariantSeverity, OverheadBreakdown, RuleApplication, StateHash, StateMutationRecord, StateTransition, StateTransitionInfo, WatermarkTracker,
+};
+use crate::validation_registry::{TypeValidators, ValidationRegistry};
+use chrono::{DateTime, Duration, Utc};
+use serde::de::DeserializeOwned;
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::mpsc::{SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+#[cfg(target_arch = "wasm32")]
+use web_time::Instant;
+
+/// Step-by-step account of how a transaction would be processed
+/// 
+/// Produced by `TransactionProcessor::explain`. Steps run in order and stop at
+/// the first failure; steps that were not reached hold an error saying so.
+#[derive(Debug)]
+pub struct ExplanationTrace<S> {
+    pub transaction_validated: Result<(), ValidationError>,
+    pub rule_pre_validated: Result<(), ValidationError>,
+    pub rule_applied: Result<StateTransition<S>, ProcessingError>,
+    pub invariants_checked: Result<(), RuleError>,
+    pub would_succeed: bool,
+    /// Human-readable description of each step, in order
+    pub explanation_steps: Vec<String>,
+}
+
+/// Outcome of one transaction, sent by `TransactionProcessor::process_transactions_streaming`
+#[derive(Debug, Clone)]
+pub struct TransactionResult<S> {
+    /// Position of the transaction in the processed slice
+    pub index: usize,
+    pub transaction_id: String,
+    pub succeeded: bool,
+    pub new_hash: Option<StateHash>,
+    /// State after the transaction, if it succeeded
+    pub new_state: Option<S>,
+    pub error: Option<ProcessingError>,
+}
+
+/// Immutable view of a processor's state at one point in time
+/// 
+/// Cloning shares the state through an `Arc`, so snapshots can be handed to
+/// reader threads cheaply while processing continues.
+#[derive(Debug, Clone)]
+pub struct ProcessorSnapshot<S> {
+    pub current_state: Arc<S>,
+    /// Hash of `current_state`
+    pub current_hash: StateHash,
+    pub transactions_processed: usize,
+    /// Wall-clock time the snapshot was taken; not part of the deterministic state
+    pub snapshot_time: DateTime<Utc>,
+}
+
+/// Speculative branch of a `TransactionProcessor`, created by `TransactionProcessor::fork`
+/// 
+/// Transactions are processed on the fork's own state. The parent is only
+/// changed by `merge_into`; dropping the fork or calling `discard` throws the
+/// work away. The fork shares the parent's state instead of copying it, and
+/// each side moves to a state of its own when it commits one.
+#[derive(Debug)]
+pub struct ForkedProcessor<S: State> {
+    /// Parent state at the time of the fork
+    base: Arc<S>,
+    base_hash: StateHash,
+    processor: TransactionProcessor<S>,
+}
+
+impl<S: State> ForkedProcessor<S> {
+    /// Process a single transaction on the fork; see `TransactionProcessor::process_transaction`
+    pub fn process_transaction<T, R>(
+        &mut self,
+        transaction: &T,
+        rule_set: &R,
+        context: &ExecutionContext,
+    ) -> Result<StateTransition<S>, ProcessingError>
+    where
+        T: Transaction,
+        R: RuleSet<S, T>,
+    {
+        self.processor.process_transaction(transaction, rule_set, context)
+    }
+    
+    /// Process transactions in order on the fork; see `TransactionProcessor::process_transactions`
+    pub fn process_transactions<T, R>(
+        &mut self,
+        transactions: &[T],
+        rule_set: &R,
+        context: &ExecutionContext,
+    ) -> Result<Vec<StateTransition<S>>, ProcessingError>
+    where
+        T: Transaction,
+        R: RuleSet<S, T>,
+    {
+        self.processor.process_transactions(transactions, rule_set, context)
+    }
+    
+    /// Get the fork's current state
+    pub fn current_state(&self) -> &S {
+        self.processor.current_state()
+    }
+    
+    /// Get the hash of the fork's current state
+    pub fn current_hash(&self) -> StateHash {
+        self.processor.current_hash()
+    }
+    
+    /// Get the trace of the work done on the fork since it was created
+    pub fn execution_trace(&self) -> &ExecutionTrace {
+        self.processor.execution_trace()
+    }
+    
+    /// Show what merging the fork would change, relative to the state it was forked from
+    pub fn diff(&self) -> StateDiff<S> {
+        StateDiff {
+            from_state: S::clone(&self.base),
+            to_state: self.processor.current_state().clone(),
+            from_hash: self.base_hash,
+            to_hash: self.processor.current_hash(),
+            index_diff: self.processor.transactions_processed() as i64,
+            transaction_range: None,
+        }
+    }
+    
+    /// Apply the fork's changes to `processor`
+    /// 
+    /// If the parent has not changed since the fork, it takes the fork's state;
+    /// otherwise both branches are combined with `StateDiff::merge`. The change
+    /// is recorded on the parent as a single mutation, so the fork's individual
+    /// transactions are not added to the parent's trace.
+    /// 
+    /// # Errors
+    /// Returns `StateError::MergeConflict` if the parent and the fork changed
+    /// the same field, and `StateError::TransitionFailed` if the merged state
+    /// is rejected.
+    pub fn merge_into(self, processor: &mut TransactionProcessor<S>) -> Result<(), StateError> {
+        if self.processor.current_hash() == self.base_hash {
+            return Ok(());
+        }
+        
+        let merged = if processor.current_hash() == self.base_hash {
+            self.processor.current_state().clone()
+        } else {
+            let ours = self.diff();
+            let theirs = StateDiff {
+                from_state: Arc::unwrap_or_clone(self.base),
+                to_state: processor.current_state().clone(),
+                from_hash: self.base_hash,
+                to_hash: processor.current_hash(),
+                index_diff: 0,
+                transaction_range: None,
+            };
+            StateDiff::merge(&theirs, &ours)?.to_state
+        };
+        
+        let description = format!("Merge of fork with {} transactions", self.processor.transactions_processed());
+        processor
+            .apply_mutation(&description, |_| Ok(merged))
+            .map_err(|e| StateError::TransitionFailed { reason: e.to_string() })?;
+        Ok(())
+    }
+    
+    /// Throw away the speculative work
+    pub fn discard(self) {}
+}
+
+impl<S: State> ProcessorSnapshot<S> {
+    /// Convert the snapshot to a checkpoint for resuming processing later
+    pub fn to_checkpoint(&self, timestamp: DateTime<Utc>) -> Checkpoint<S> {
+        Checkpoint {
+            state: S::clone(&self.current_state),
+            hash: self.current_hash,
+            transaction_index: self.transactions_processed,
+            timestamp,
+            state_schema_version: S::SCHEMA_VERSION,
+        }
+    }
+}
 
 /// Transaction processor that applies rules and generates execution traces
 #[derive(Debug)]
 pub struct TransactionProcessor<S: State> {
     state_manager: StateManager<S>,
     execution_trace: ExecutionTrace,
+    side_effect_queue: Option<SideEffectQueue>,
+    rate_limiter: Option<TokenBucket>,
+    logger: DeterministicLogger,
+    statistics: StatisticsRecorder,
+    max_transaction_count: Option<usize>,
+    max_timestamp_drift: Option<Duration>,
+    subtree_hashes: Option<SubtreeHashes>,
+    state_cache: Option<Arc<Mutex<RuleApplicationCache<S>>>>,
+    rule_cache: Option<LruCache<[u8; 32], (S, StateHash)>>,
+    id_normalizer: Option<TransactionIdNormalizer>,
+    processed_ids: Option<HashSet<String>>,
+    type_validators: Option<TypeValidators>,
+    dropped_results_count: usize,
 }
 
 impl<S: State> TransactionProcessor<S> {
@@ -23,20 +224,212 @@ impl<S: State> TransactionProcessor<S> {
                 reason: format!("Failed to initialize state manager: {}", e),
             })?;
         
-        Ok(Self {
+        Ok(Self::from_state_manager(state_manager))
+    }
+    
+    /// Wrap a state manager in a processor with an empty trace and the default configuration
+    fn from_state_manager(state_manager: StateManager<S>) -> Self {
+        Self {
             state_manager,
             execution_trace: ExecutionTrace {
                 transactions_processed: 0,
                 state_transitions: Vec::new(),
                 rule_applications: Vec::new(),
                 checkpoints: Vec::new(),
+                watermark: WatermarkTracker::new(),
+                mutations: Vec::new(),
+                skipped_transactions: Vec::new(),
+                skipped_indices: Vec::new(),
             },
-        })
+            side_effect_queue: None,
+            rate_limiter: None,
+            logger: DeterministicLogger::default(),
+            statistics: StatisticsRecorder::default(),
+            max_transaction_count: None,
+            max_timestamp_drift: None,
+            subtree_hashes: None,
+            state_cache: None,
+            rule_cache: None,
+            id_normalizer: None,
+            processed_ids: None,
+            type_validators: None,
+            dropped_results_count: 0,
+        }
+    }
+    
+    /// Attach a queue that collects side effects enqueued by the rule set
+    /// 
+    /// Effects are only collected; call `SideEffectQueue::drain` on a retained
+    /// handle after processing to execute them.
+    pub fn with_side_effect_queue(mut self, queue: SideEffectQueue) -> Self {
+        self.side_effect_queue = Some(queue);
+        self
+    }
+    
+    /// Also validate each transaction with the validator registered for its concrete type
+    /// 
+    /// Runs after `Transaction::validate`; a failure rejects the transaction
+    /// like any other validation error.
+    pub fn with_validation_registry<T: Transaction>(mut self, registry: ValidationRegistry<T>) -> Self {
+        self.type_validators = Some(registry.into_type_validators());
+        self
+    }
+    
+    /// Limit processing to at most `max_tps` transactions per second
+    /// 
+    /// `process_transaction` blocks the current thread until a token is
+    /// available. The `clock` is the time source for refilling the bucket; two
+    /// runs against the same time source consume tokens identically. Rate
+    /// limiting never affects the resulting states.
+    /// 
+    /// # Panics
+    /// Panics if `max_tps` is not a positive, finite number
+    pub fn with_rate_limit(self, max_tps: f64, clock: impl Fn() -> Instant + Send + Sync + 'static) -> Self {
+        self.with_token_bucket(TokenBucket::new(max_tps, clock))
+    }
+    
+    /// Limit processing with a preconfigured token bucket, e.g. one using a simulated clock
+    pub fn with_token_bucket(mut self, bucket: TokenBucket) -> Self {
+        self.rate_limiter = Some(bucket);
+        self
+    }
+    
+    /// Fail once more than `limit` transactions have been processed by this processor
+    /// 
+    /// The transaction that would exceed the limit is rejected with
+    /// `ProcessingError::TransactionLimitExceeded` and the state is left as it
+    /// was after the last accepted transaction.
+    pub fn with_max_transaction_count(mut self, limit: usize) -> Self {
+        self.max_transaction_count = Some(limit);
+        self
+    }
+    
+    /// Reject transactions whose new state serializes to more than `limit` bytes
+    /// 
+    /// See `StateManager::set_max_state_size_bytes`.
+    pub fn with_max_state_size_bytes(mut self, limit: usize) -> Self {
+        self.state_manager.set_max_state_size_bytes(limit);
+        self
+    }
+    
+    /// Record log entries at `level` and above; the default is `LogLevel::Info`
+    pub fn with_log_level(mut self, level: LogLevel) -> Self {
+        self.logger = DeterministicLogger::new(level);
+        self
+    }
+    
+    /// Tolerate transactions whose timestamp is up to `tolerance` before the previous one
+    /// 
+    /// Feeds from several clocks often deliver transactions slightly out of
+    /// order. A transaction up to `tolerance` behind the previous processed
+    /// transaction is processed normally. One up to twice the tolerance behind
+    /// is still processed in arrival order, with a `LogLevel::Warn` entry;
+    /// anything further behind is rejected with
+    /// `ProcessingError::ExcessiveTimestampDrift`. Without a tolerance,
+    /// backwards drift is only counted in the statistics.
+    pub fn with_max_timestamp_drift(mut self, tolerance: Duration) -> Self {
+        self.max_timestamp_drift = Some(tolerance);
+        self
+    }
+    
+    /// Keep a hash of each top-level state field, see `SubtreeHashes`
+    /// 
+    /// After each transaction only the fields the rule set declares through
+    /// `RuleSet::affects_fields` are rehashed.
+    pub fn with_subtree_hashing(mut self) -> Self {
+        self.subtree_hashes = Some(SubtreeHashes::new(self.state_manager.current_state()));
+        self
     }
     
+    /// Reuse rule application results from `cache` for (state, transaction) pairs seen before
+    /// 
+    /// On a hit the rule set's `pre_validate` and `apply` are skipped and the
+    /// cached state and hash are committed as they are: `State::validate`,
+    /// rule contracts, invariants and the state size limit are not checked
+    /// again, and the transition has no causality or audit record. Only share
+    /// a cache between processors with the same validation settings. A wrong
+    /// result from a hash collision is caught by `StateHasher::verify_transition`.
+    pub fn with_state_cache(mut self, cache: Arc<Mutex<RuleApplicationCache<S>>>) -> Self {
+        self.state_cache = Some(cache);
+        self
+    }
+    
+    /// Cache up to `n` rule outputs by `RuleSet::idempotency_key`, evicting the least recently used
+    /// 
+    /// Keys are combined with the rule set version. On a hit `pre_validate`
+    /// and `apply` are skipped and the cached state and hash are committed
+    /// without validating them again, as with `with_state_cache`. Hit rates
+    /// are reported in `ProcessingStatistics::rule_cache_hit_rate`; forks start
+    /// with an empty cache of the same capacity.
+    pub fn with_rule_cache_capacity(mut self, n: usize) -> Self {
+        self.rule_cache = Some(LruCache::new(n));
+        self
+    }
+    
+    /// Normalize transaction IDs before checking them for duplicates and recording them in the trace
+    /// 
+    /// `Transaction::id` itself is left as it is, so rule sets, errors and the
+    /// state manager's history still see the original ID.
+    pub fn with_id_normalizer(mut self, normalizer: TransactionIdNormalizer) -> Self {
+        self.id_normalizer = Some(normalizer);
+        self
+    }
+    
+    /// Reject transactions whose normalized ID was already processed successfully
+    /// 
+    /// Only transactions processed after enabling are remembered. Forks start
+    /// with the IDs processed so far.
+    pub fn with_deduplication(mut self, enabled: bool) -> Self {
+        self.processed_ids = enabled.then(HashSet::new);
+        self
+    }
+    
+    /// Get the ID a transaction is recorded under, after normalization
+    pub fn normalized_id(&self, id: &str) -> String {
+        match self.id_normalizer {
+            Some(normalizer) => normalizer.normalize(id),
+            None => id.to_string(),
+        }
+    }
+    
+    /// Record every internal operation from now on, for comparing replays
+    /// 
+    /// Each validation, guard, rule application, invariant check, hash and
+    /// checkpoint is appended to `audit_log`. Only for debugging: the log
+    /// grows with every transaction.
+    #[cfg(feature = "debug-audit")]
+    pub fn enable_audit_mode(&mut self) {
+        self.state_manager.enable_audit_mode();
+    }
+    
+    /// Get the operations recorded since audit mode was enabled
+    #[cfg(feature = "debug-audit")]
+    pub fn audit_log(&self) -> &[AuditLogEntry] {
+        self.state_manager.audit_log()
+    }
+    
+    /// Get the subtree hashes of the current state, if subtree hashing is enabled
+    pub fn subtree_hashes(&self) -> Option<&SubtreeHashes> {
+        self.subtree_hashes.as_ref()
+    }
+    
+    /// Get the number of rate limit tokens currently available
+    /// 
+    /// Returns `f64::INFINITY` when no rate limit is configured.
+    pub fn current_token_count(&self) -> f64 {
+        self.rate_limiter.as_ref().map_or(f64::INFINITY, TokenBucket::tokens)
+    }
+    
+    /// Get the attached side effect queue, if any
+    pub fn side_effect_queue(&self) -> Option<&SideEffectQueue> {
+        self.side_effect_queue.as_ref()
+    }
     
     /// Create a transaction processor from a checkpoint
-    pub fn from_checkpoint(checkpoint: &crate::state_manager::Checkpoint<S>) -> Result<Self, ProcessingError> {
+    /// 
+    /// Checkpoints hold no processor configuration, so the processor starts
+    /// with the defaults, as from `new`.
+    pub fn from_checkpoint(checkpoint: &Checkpoint<S>) -> Result<Self, ProcessingError> {
         let mut state_manager = StateManager::new(checkpoint.state.clone())
             .map_err(|e| ProcessingError::TransactionFailed {
                 transaction_id: "checkpoint".to_string(),
@@ -50,17 +443,78 @@ impl<S: State> TransactionProcessor<S> {
                 reason: format!("Failed to restore checkpoint: {}", e),
             })?;
         
-        Ok(Self {
-            state_manager,
-            execution_trace: ExecutionTrace {
-                transactions_processed: checkpoint.transaction_index,
-                state_transitions: Vec::new(),
-                rule_applications: Vec::new(),
-                checkpoints: Vec::new(),
-            },
-        })
+        let mut processor = Self::from_state_manager(state_manager);
+        processor.execution_trace.transactions_processed = checkpoint.transaction_index;
+        Ok(processor)
     }
+    
+    /// Rebuild a processor by replaying the transactions recorded in an NDJSON trace log
+    /// 
+    /// The log must have been written by `ExecutionTraceLog::save_ndjson` with
+    /// each transaction recorded through `ExecutionTraceLog::record_transaction`.
+    /// The transactions are replayed from `initial_state` with `rule_set`,
+    /// under the context rebuilt by `LogEntry::to_simulated_context` from the
+    /// first log entry carrying a random seed.
+    /// 
+    /// # Errors
+    /// Returns `ProcessingError::InvalidTraceLog` if the log cannot be read or
+    /// records no final hash, and `ProcessingError::TraceLogHashMismatch` if
+    /// the replay ends on a different hash than the log.
+    pub fn replay_from_trace_log<T, R>(log_path: &Path, initial_state: S, rule_set: &R) -> Result<Self, ProcessingError>
+    where
+        T: Transaction + DeserializeOwned,
+        R: RuleSet<S, T>,
+    {
+        let log = ExecutionTraceLog::load_ndjson(log_path).map_err(invalid_trace_log)?;
+        let logged_hash = logged_final_hash(&log)?;
+        let transactions: Vec<T> = log.transactions().map_err(invalid_trace_log)?;
+        let context = log.logs
+            .iter()
+            .find_map(LogEntry::to_simulated_context)
+            .ok_or_else(|| ProcessingError::InvalidTraceLog {
+                reason: "no entry carries a random seed".to_string(),
+            })?;
+        
+        let mut processor = Self::new(initial_state)?;
+        processor.process_transactions(&transactions, rule_set, &context)?;
+        
+        let actual_hash = processor.current_hash();
+        if actual_hash != logged_hash {
+            return Err(ProcessingError::TraceLogHashMismatch { logged_hash, actual_hash });
+        }
+        Ok(processor)
+    }
+    
+    /// Rebuild a processor from a trace log without replaying it
+    /// 
+    /// `final_state` is taken as the result of the logged run. It is not
+    /// validated, but it is rehashed, and both its hash and `final_hash` must
+    /// equal the last hash in the log. The trace holds the logged transaction
+    /// count but no transitions.
+    /// 
+    /// # Errors
+    /// Returns `ProcessingError::InvalidTraceLog` if the log cannot be read or
+    /// records no final hash, and `ProcessingError::TraceLogHashMismatch` if
+    /// `final_hash` or the hash of `final_state` differs from it.
+    pub fn from_trace_log_trusted(log_path: &Path, final_state: S, final_hash: StateHash) -> Result<Self, ProcessingError> {
+        let log = ExecutionTraceLog::load_ndjson(log_path).map_err(invalid_trace_log)?;
+        let logged_hash = logged_final_hash(&log)?;
+        let state_hash = StateHasher::new().hash(&final_state);
+        if let Some(actual_hash) = [final_hash, state_hash].into_iter().find(|hash| *hash != logged_hash) {
+            return Err(ProcessingError::TraceLogHashMismatch { logged_hash, actual_hash });
+        }
+        let transactions_processed = log.events_by_type(TraceEventType::TransactionCompleted).len();
+        
+        let mut processor = Self::from_state_manager(StateManager::trusted(final_state, transactions_processed));
+        processor.execution_trace.transactions_processed = transactions_processed;
+        Ok(processor)
+    }
+    
     /// Process a single transaction with the given rule set and context
+    /// 
+    /// The rule set sees the context in `ExecutionPhase::PreProcessing` during
+    /// `pre_validate` and in `ExecutionPhase::MainProcessing` during `apply`,
+    /// whatever phase the supplied context is in.
     pub fn process_transaction<T, R>(
         &mut self,
         transaction: &T,
@@ -71,35 +525,487 @@ impl<S: State> TransactionProcessor<S> {
         T: Transaction,
         R: RuleSet<S, T>,
     {
+        // Keep a reloadable rule set on one version for the whole transaction
+        if let Some(pinned) = rule_set.pinned_snapshot() {
+            return self.process_transaction(transaction, &pinned.as_ref(), context);
+        }
+        
+        // Reject the transaction outright once the size limit is reached
+        if let Some(limit) = self.max_transaction_count {
+            // State mutations also record transitions, so count rule applications
+            let processed = self.execution_trace.rule_applications.len();
+            if processed >= limit {
+                return Err(ProcessingError::TransactionLimitExceeded {
+                    limit,
+                    attempted: processed + 1,
+                    last_state_hash: self.current_hash(),
+                });
+            }
+        }
+        
+        // Wait for throughput capacity before doing any work
+        if let Some(limiter) = &mut self.rate_limiter {
+            limiter.acquire();
+        }
+        
+        // Time the processing itself, excluding any rate limit wait
+        let started = Instant::now();
+        let result = self.process_transaction_untimed(transaction, rule_set, context);
+        // Move a ticking test clock forward once per transaction, failed or not
+        context.tick_clock();
+        let duration_us = u64::try_from(started.elapsed().as_micros()).unwrap_or(u64::MAX);
+        self.statistics.record(rule_set.version(), duration_us, result.is_ok());
+        
+        result
+    }
+    
+    /// Validate, apply and trace a single transaction
+    fn process_transaction_untimed<T, R>(
+        &mut self,
+        transaction: &T,
+        rule_set: &R,
+        context: &ExecutionContext,
+    ) -> Result<StateTransition<S>, ProcessingError>
+    where
+        T: Transaction,
+        R: RuleSet<S, T>,
+    {
+        let transaction_id = self.normalized_id(transaction.id());
+        if self.processed_ids.as_ref().is_some_and(|ids| ids.contains(&transaction_id)) {
+            return Err(ProcessingError::TransactionFailed {
+                reason: format!("Duplicate transaction ID {}", transaction_id),
+                transaction_id,
+            });
+        }
+        self.check_timestamp_drift(transaction, context)?;
+        
+        // Warn when the rule set was not designed for the transaction's version
+        if let Some(hint) = transaction.rule_version_hint() {
+            if !rule_set.supports_version(&hint) {
+                self.logger.log(
+                    LogEntry::new(
+                        LogLevel::Warn,
+                        context.now(),
+                        format!(
+                            "Transaction {} was created for rule version {} but is processed by incompatible rule set {}",
+                            transaction.id(),
+                            hint,
+                            rule_set.version()
+                        ),
+                    )
+                    .with_transaction(transaction.id().to_string(), self.execution_trace.transactions_processed)
+                    .with_rule(rule_set.version()),
+                );
+            }
+        }
+        
         // Validate the transaction before processing
-        transaction.validate().map_err(|e| ProcessingError::TransactionFailed {
+        let validation = transaction.validate().and_then(|()| match &self.type_validators {
+            Some(validators) => validators.validate(transaction),
+            None => Ok(()),
+        });
+        #[cfg(feature = "debug-audit")]
+        self.state_manager.record_audit(AuditOperation::EvaluateTransactionValidate { ok: validation.is_ok() });
+        validation.map_err(|e| ProcessingError::TransactionFailed {
             transaction_id: transaction.id().to_string(),
             reason: format!("Transaction validation failed: {}", e),
         })?;
         
-        // Apply the transaction through the state manager
-        let transition = self.state_manager.apply_transaction(transaction, rule_set, context)?;
+        // Apply the transaction through the state manager, unless a cache has its result
+        let invariant_warnings = self.state_manager.invariant_warnings().len();
+        let rule_cache_key = self.rule_cache_key(transaction, rule_set, context);
+        let cached = match rule_cache_key {
+            Some(key) => self.apply_from_rule_cache(key, transaction),
+            None => None,
+        };
+        let mut transition = match cached.or_else(|| self.apply_from_cache(transaction, rule_set, context)) {
+            Some(transition) => transition,
+            None => {
+                let transition = self.state_manager
+                    .apply_transaction(transaction, rule_set, context)
+                    .map_err(|e| self.attach_rule_context(e, transaction, rule_set))?;
+                self.store_in_cache(&transition, transaction, rule_set, context);
+                if let (Some(key), Some(cache)) = (rule_cache_key, &mut self.rule_cache) {
+                    cache.put(key, (transition.to_state.clone(), transition.to_hash));
+                }
+                transition
+            }
+        };
         
-        // Record the state transition in the execution trace
+        // Record the state transition in the execution trace under the normalized ID
+        transition.transaction_id = transaction_id.clone();
         self.execution_trace.state_transitions.push(StateTransitionInfo {
             from_hash: transition.from_hash,
             to_hash: transition.to_hash,
             transaction_id: transition.transaction_id.clone(),
+            causality: transition.causality.clone(),
         });
         
         // Record the rule application in the execution trace
+        let started = Instant::now();
+        let description = rule_set.describe_transaction(&transition.from_state, transaction, context);
+        self.state_manager.phase_timings_mut().observer_callbacks += started.elapsed();
         self.execution_trace.rule_applications.push(RuleApplication {
             rule_version: rule_set.version(),
-            transaction_id: transaction.id().to_string(),
+            transaction_id: transaction_id.clone(),
             timestamp: transaction.timestamp(),
+            audit: transition.audit.clone(),
+            description,
+            application_metadata: transaction.metadata().cloned(),
         });
         
+        // Advance the timestamp watermark, warning about late arrivals
+        let index = self.execution_trace.transactions_processed;
+        let watermark = self.execution_trace.watermark.high_watermark_timestamp;
+        if self.execution_trace.watermark.observe(&transaction_id, transaction.timestamp()) {
+            self.logger.log(
+                LogEntry::new(
+                    LogLevel::Warn,
+                    context.now(),
+                    format!(
+                        "Transaction {} has timestamp {} before the watermark {}",
+                        transaction.id(),
+                        transaction.timestamp(),
+                        watermark.map(|w| w.to_string()).unwrap_or_default()
+                    ),
+                )
+                .with_transaction(transaction.id().to_string(), index),
+            );
+        }
+        
+        // Warn about soft invariants the new state breaks
+        for (_, violation) in &self.state_manager.invariant_warnings()[invariant_warnings..] {
+            self.logger.log(
+                LogEntry::new(
+                    LogLevel::Warn,
+                    context.now(),
+                    format!("Transaction {} violates soft invariant {}", transaction.id(), violation.invariant_name),
+                )
+                .with_transaction(transaction.id().to_string(), index)
+                .with_rule(rule_set.version()),
+            );
+        }
+        
         // Increment the transaction count
         self.execution_trace.transactions_processed += 1;
         
+        // Rehash only the subtrees the rule set may have written
+        if let Some(subtrees) = &mut self.subtree_hashes {
+            let started = Instant::now();
+            subtrees.update(&transition.to_state, rule_set.affects_fields().as_deref());
+            self.state_manager.phase_timings_mut().state_hashing += started.elapsed();
+        }
+        
+        // Collect side effects for the successful transaction without executing them
+        if let Some(queue) = &self.side_effect_queue {
+            let started = Instant::now();
+            rule_set.enqueue_transition_side_effects(&transition.from_state, &transition.to_state, transaction, context, queue);
+            self.state_manager.phase_timings_mut().observer_callbacks += started.elapsed();
+        }
+        
+        if let Some(ids) = &mut self.processed_ids {
+            ids.insert(transaction_id);
+        }
         Ok(transition)
     }
     
+    /// Get the rule cache key for applying `transaction` to the current state, if it is cacheable
+    fn rule_cache_key<T, R>(&self, transaction: &T, rule_set: &R, context: &ExecutionContext) -> Option<[u8; 32]>
+    where
+        T: Transaction,
+        R: RuleSet<S, T>,
+    {
+        self.rule_cache.as_ref()?;
+        let key = rule_set.idempotency_key(self.state_manager.current_state(), transaction, context)?;
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(rule_set.version().to_string().as_bytes());
+        hasher.update(b"\0");
+        hasher.update(&key.to_le_bytes());
+        Some(*hasher.finalize().as_bytes())
+    }
+    
+    /// Commit the rule output cached under `key`, if there is one
+    fn apply_from_rule_cache<T: Transaction>(&mut self, key: [u8; 32], transaction: &T) -> Option<StateTransition<S>> {
+        let cached = self.rule_cache.as_mut()?.get(&key).cloned();
+        self.statistics.record_rule_cache_lookup(cached.is_some());
+        let (new_state, to_hash) = cached?;
+        let from_hash = self.current_hash();
+        Some(self.state_manager.apply_cached(transaction.id(), from_hash, new_state, to_hash))
+    }
+    
+    /// Commit the cached result of applying `transaction` to the current state, if there is one
+    fn apply_from_cache<T, R>(&mut self, transaction: &T, rule_set: &R, context: &ExecutionContext) -> Option<StateTransition<S>>
+    where
+        T: Transaction,
+        R: RuleSet<S, T>,
+    {
+        let cache = self.state_cache.as_ref()?;
+        let started = Instant::now();
+        let transaction_hash = RuleApplicationCache::<S>::transaction_hash(transaction, rule_set, context);
+        self.state_manager.phase_timings_mut().serialization += started.elapsed();
+        let transaction_hash = transaction_hash?;
+        let from_hash = self.current_hash();
+        let (new_state, to_hash) = cache
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .get(from_hash, transaction_hash)?;
+        Some(self.state_manager.apply_cached(transaction.id(), from_hash, new_state, to_hash))
+    }
+    
+    /// Remember the result of a rule application for later lookups
+    fn store_in_cache<T, R>(&mut self, transition: &StateTransition<S>, transaction: &T, rule_set: &R, context: &ExecutionContext)
+    where
+        T: Transaction,
+        R: RuleSet<S, T>,
+    {
+        let Some(cache) = &self.state_cache else {
+            return;
+        };
+        let started = Instant::now();
+        let transaction_hash = RuleApplicationCache::<S>::transaction_hash(transaction, rule_set, context);
+        self.state_manager.phase_timings_mut().serialization += started.elapsed();
+        if let Some(transaction_hash) = transaction_hash {
+            cache.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).insert(
+                transition.from_hash,
+                transaction_hash,
+                transition.to_state.clone(),
+                transition.to_hash,
+            );
+        }
+    }
+    
+    /// Apply an out-of-band state change, such as a manual adjustment by an operator
+    /// 
+    /// The mutation bypasses every rule set. It is traced as a forced state
+    /// transition with `transaction_id` `MUTATION_{n}`, numbered from 0, and
+    /// listed in `ExecutionTrace::mutations`; no rule application is recorded
+    /// and `transactions_processed` is unchanged.
+    pub fn apply_mutation(
+        &mut self,
+        description: &str,
+        mutate: impl FnOnce(S) -> Result<S, StateError>,
+    ) -> Result<StateTransition<S>, ProcessingError> {
+        let mutation_id = format!("MUTATION_{}", self.execution_trace.mutations.len());
+        let transition = self.state_manager.apply_mutation(&mutation_id, mutate)?;
+        
+        self.execution_trace.state_transitions.push(StateTransitionInfo {
+            from_hash: transition.from_hash,
+            to_hash: transition.to_hash,
+            transaction_id: mutation_id.clone(),
+            causality: Default::default(),
+        });
+        self.execution_trace.mutations.push(StateMutationRecord {
+            mutation_id,
+            description: description.to_string(),
+            transaction_index: self.execution_trace.transactions_processed,
+            from_hash: transition.from_hash,
+            to_hash: transition.to_hash,
+        });
+        
+        // A mutation may touch any field
+        if let Some(subtrees) = &mut self.subtree_hashes {
+            subtrees.update(&transition.to_state, None);
+        }
+        
+        Ok(transition)
+    }
+    
+    /// Attach the transaction and rule version to a rule error that has no context yet
+    /// 
+    /// The clause is taken from a failed `RuleAuditRecorder::guard`, and the
+    /// snapshot hash is that of the unchanged current state.
+    fn attach_rule_context<T, R>(&self, error: ProcessingError, transaction: &T, rule_set: &R) -> ProcessingError
+    where
+        T: Transaction,
+        R: RuleSet<S, T>,
+    {
+        match error {
+            ProcessingError::Rule(rule_error) if rule_error.context().is_none() => {
+                let mut rule_context = RuleErrorContext::new()
+                    .with_transaction_id(transaction.id().to_string())
+                    .with_rule_version(rule_set.version())
+                    .with_state_snapshot_hash(self.state_manager.current_hash());
+                if let Some(clause) = rule_error.failed_clause() {
+                    rule_context = rule_context.with_rule_clause(clause.to_string());
+                }
+                ProcessingError::Rule(rule_error.with_context(rule_context))
+            }
+            other => other,
+        }
+    }
+    
+    /// Compare the transaction's timestamp with the previous processed transaction's
+    fn check_timestamp_drift<T: Transaction>(
+        &mut self,
+        transaction: &T,
+        context: &ExecutionContext,
+    ) -> Result<(), ProcessingError> {
+        let Some(previous) = self.execution_trace.rule_applications.last() else {
+            return Ok(());
+        };
+        let drift = previous.timestamp - transaction.timestamp();
+        if drift <= Duration::zero() {
+            return Ok(());
+        }
+        self.statistics.record_drift(drift);
+        
+        let Some(tolerance) = self.max_timestamp_drift else {
+            return Ok(());
+        };
+        if drift > tolerance * 2 {
+            return Err(ProcessingError::ExcessiveTimestampDrift {
+                transaction_id: transaction.id().to_string(),
+                drift_duration: drift,
+                tolerance,
+            });
+        }
+        if drift > tolerance {
+            self.logger.log(
+                LogEntry::new(
+                    LogLevel::Warn,
+                    context.now(),
+                    format!(
+                        "Transaction {} is {} behind the previous transaction, exceeding the drift tolerance {}",
+                        transaction.id(),
+                        drift,
+                        tolerance
+                    ),
+                )
+                .with_transaction(transaction.id().to_string(), self.execution_trace.transactions_processed),
+            );
+        }
+        Ok(())
+    }
+    
+    /// Explain whether a transaction would be accepted, without modifying state
+    /// 
+    /// This is a verbose dry run of `process_transaction`: it performs the same
+    /// checks against the current state and records what happened at each step.
+    pub fn explain<T, R>(
+        &self,
+        transaction: &T,
+        rule_set: &R,
+        context: &ExecutionContext,
+    ) -> ExplanationTrace<S>
+    where
+        T: Transaction,
+        R: RuleSet<S, T>,
+    {
+        if let Some(pinned) = rule_set.pinned_snapshot() {
+            return self.explain(transaction, &pinned.as_ref(), context);
+        }
+        
+        let state = self.state_manager.current_state();
+        let from_hash = self.state_manager.current_hash();
+        let describe_state = || {
+            format!(
+                "state at this point (hash {}): {}",
+                from_hash,
+                serde_json::to_string(state).unwrap_or_else(|e| format!("<unserializable: {}>", e))
+            )
+        };
+        let not_reached = |step: &str| format!("not attempted because {} failed", step);
+        
+        let mut trace = ExplanationTrace {
+            transaction_validated: Ok(()),
+            rule_pre_validated: Err(ValidationError::RuleViolated { rule: not_reached("transaction validation") }),
+            rule_applied: Err(ProcessingError::TransactionFailed {
+                transaction_id: transaction.id().to_string(),
+                reason: not_reached("an earlier step"),
+            }),
+            invariants_checked: Err(RuleError::InvariantViolated {
+                rule_version: rule_set.version(),
+                reason: not_reached("an earlier step"),
+            }),
+            would_succeed: false,
+            explanation_steps: Vec::new(),
+        };
+        let steps = &mut trace.explanation_steps;
+        
+        // Step 1: the transaction's own validation
+        trace.transaction_validated = transaction.validate();
+        match &trace.transaction_validated {
+            Ok(()) => steps.push(format!("Step 1 (transaction validation): transaction {} is valid", transaction.id())),
+            Err(e) => {
+                steps.push(format!("Step 1 (transaction validation) failed: {}", e));
+                steps.push(describe_state());
+                steps.push(format!("Transaction {} would be rejected at step 1", transaction.id()));
+                return trace;
+            }
+        }
+        
+        // Step 2: rule set preconditions
+        let pre_context = context.with_phase(ExecutionPhase::PreProcessing);
+        trace.rule_pre_validated = rule_set.pre_validate(state, transaction, &pre_context);
+        match &trace.rule_pre_validated {
+            Ok(()) => steps.push(format!("Step 2 (rule pre-validation): rule set {} accepted the preconditions", rule_set.version())),
+            Err(e) => {
+                steps.push(format!("Step 2 (rule pre-validation) failed under rule set {}: {}", rule_set.version(), e));
+                steps.push(describe_state());
+                steps.push(format!("Transaction {} would be rejected at step 2", transaction.id()));
+                return trace;
+            }
+        }
+        
+        // Step 3: rule application
+        let main_context = pre_context.advance_phase().into_causality_recording();
+        let (new_state, audit) = match rule_set.apply_with_audit(state, transaction, &main_context) {
+            Ok(applied) => applied,
+            Err(e) => {
+                steps.push(format!("Step 3 (rule application) failed under rule set {}: {}", rule_set.version(), e));
+                steps.push(describe_state());
+                steps.push(format!("Transaction {} would be rejected at step 3", transaction.id()));
+                trace.rule_applied = Err(e);
+                return trace;
+            }
+        };
+        let new_state = NormalizedState::new(new_state);
+        let to_hash = StateHasher::new().hash_normalized(&new_state);
+        let new_state = new_state.into_inner();
+        steps.push(format!("Step 3 (rule application): state would change from {} to {}", from_hash, to_hash));
+        
+        // Step 4: invariants of the resulting state; soft ones only warn, as in `process_transaction`
+        let violations = new_state.check_invariants();
+        trace.invariants_checked = new_state
+            .validate()
+            .map_err(|e| e.to_string())
+            .and_then(|()| match violations.iter().find(|v| v.severity == InvariantSeverity::Hard) {
+                Some(hard) => Err(format!("Hard invariant {} violated", hard.invariant_name)),
+                None => Ok(()),
+            })
+            .map_err(|reason| RuleError::InvariantViolated {
+                rule_version: rule_set.version(),
+                reason,
+            });
+        trace.rule_applied = Ok(StateTransition {
+            from_state: state.clone(),
+            to_state: new_state,
+            from_hash,
+            to_hash,
+            transaction_id: transaction.id().to_string(),
+            causality: main_context.causality().unwrap_or_default(),
+            audit,
+        });
+        match &trace.invariants_checked {
+            Ok(()) => {
+                steps.push("Step 4 (invariant check): resulting state is valid".to_string());
+                steps.extend(violations.iter().map(|v| {
+                    format!("Step 4 (invariant check): resulting state violates soft invariant {}", v.invariant_name)
+                }));
+            }
+            Err(e) => {
+                steps.push(format!("Step 4 (invariant check) failed: {}", e));
+                steps.push(describe_state());
+                steps.push(format!("Transaction {} would be rejected at step 4", transaction.id()));
+                return trace;
+            }
+        }
+        
+        steps.push(format!("Transaction {} would be accepted", transaction.id()));
+        trace.would_succeed = true;
+        trace
+    }
+    
     /// Process a sequence of transactions
     pub fn process_transactions<T, R>(
         &mut self,
@@ -122,6 +1028,119 @@ impl<S: State> TransactionProcessor<S> {
     }
     
     
+    /// Process a sequence of transactions, sending the outcome of each one to `sender`
+    /// 
+    /// Unlike `process_transactions`, a failed transaction does not stop
+    /// processing: its result carries the error and the state is left as it
+    /// was. Blocks whenever the channel is full, and returns once every
+    /// transaction was processed and its result sent.
+    /// 
+    /// # Errors
+    /// Returns `ProcessingError::StreamClosed` if the receiver hangs up;
+    /// transactions after the one whose result could not be sent are not
+    /// processed.
+    pub fn process_transactions_streaming<T, R>(
+        &mut self,
+        transactions: &[T],
+        rule_set: &R,
+        context: &ExecutionContext,
+        sender: SyncSender<TransactionResult<S>>,
+    ) -> Result<(), ProcessingError>
+    where
+        T: Transaction,
+        R: RuleSet<S, T>,
+    {
+        for (index, transaction) in transactions.iter().enumerate() {
+            let result = self.process_for_stream(index, transaction, rule_set, context);
+            sender.send(result).map_err(|_| ProcessingError::StreamClosed {
+                transaction_id: transaction.id().to_string(),
+            })?;
+        }
+        Ok(())
+    }
+    
+    /// Like `process_transactions_streaming`, but never waits for the receiver
+    /// 
+    /// Results that do not fit in the channel are dropped and counted in
+    /// `dropped_results_count`; processing continues regardless.
+    pub fn process_transactions_streaming_nonblocking<T, R>(
+        &mut self,
+        transactions: &[T],
+        rule_set: &R,
+        context: &ExecutionContext,
+        sender: SyncSender<TransactionResult<S>>,
+    ) -> Result<(), ProcessingError>
+    where
+        T: Transaction,
+        R: RuleSet<S, T>,
+    {
+        for (index, transaction) in transactions.iter().enumerate() {
+            let result = self.process_for_stream(index, transaction, rule_set, context);
+            match sender.try_send(result) {
+                Ok(()) => {}
+                Err(TrySendError::Full(_)) => self.dropped_results_count += 1,
+                Err(TrySendError::Disconnected(_)) => {
+                    return Err(ProcessingError::StreamClosed {
+                        transaction_id: transaction.id().to_string(),
+                    })
+                }
+            }
+        }
+        Ok(())
+    }
+    
+    /// Get the number of results the non-blocking streaming variant dropped because the channel was full
+    pub fn dropped_results_count(&self) -> usize {
+        self.dropped_results_count
+    }
+    
+    fn process_for_stream<T, R>(&mut self, index: usize, transaction: &T, rule_set: &R, context: &ExecutionContext) -> TransactionResult<S>
+    where
+        T: Transaction,
+        R: RuleSet<S, T>,
+    {
+        let (new_hash, new_state, error) = match self.process_transaction(transaction, rule_set, context) {
+            Ok(transition) => (Some(transition.to_hash), Some(transition.to_state), None),
+            Err(error) => (None, None, Some(error)),
+        };
+        TransactionResult {
+            index,
+            transaction_id: transaction.id().to_string(),
+            succeeded: error.is_none(),
+            new_hash,
+            new_state,
+            error,
+        }
+    }
+    
+    /// Process a sequence of transactions, building a fresh context for each one
+    /// 
+    /// The factory is called once per transaction, in order, and can derive the
+    /// context from the transaction (for example, setting the time to its
+    /// timestamp or loading facts that apply to it). For replays to stay
+    /// deterministic the factory must be a pure function of its input.
+    pub fn process_transactions_with_context_factory<T, R, F>(
+        &mut self,
+        transactions: &[T],
+        rule_set: &R,
+        context_factory: F,
+    ) -> Result<Vec<StateTransition<S>>, ProcessingError>
+    where
+        T: Transaction,
+        R: RuleSet<S, T>,
+        F: Fn(&T) -> ExecutionContext,
+    {
+        let mut transitions = Vec::with_capacity(transactions.len());
+        
+        for transaction in transactions {
+            let context = context_factory(transaction);
+            let transition = self.process_transaction(transaction, rule_set, &context)?;
+            transitions.push(transition);
+        }
+        
+        Ok(transitions)
+    }
+    
     /// Process a sequence of transactions with automatic checkpointing at specified intervals
     pub fn process_transactions_with_checkpoints<T, R>(
         &mut self,
@@ -142,18 +1161,17 @@ impl<S: State> TransactionProcessor<S> {
             
             // Create checkpoint at specified intervals
             if checkpoint_interval > 0 && (index + 1) % checkpoint_interval == 0 {
-                let checkpoint = self.create_checkpoint(transaction.timestamp());
-                
-                // Record checkpoint info in execution trace
-                self.execution_trace.checkpoints.push(crate::types::CheckpointInfo {
-                    transaction_index: checkpoint.transaction_index,
-                    hash: checkpoint.hash,
-                    timestamp: checkpoint.timestamp,
-                });
+                self.record_checkpoint(transaction.timestamp());
             }
         }
         
         Ok(transitions)
+    }
+    
+    /// Create a checkpoint and record it in the execution trace
+    pub(crate) fn record_checkpoint(&mut self, timestamp: DateTime<Utc>) {
+        let checkpoint = self.create_checkpoint(timestamp);
+        self.execution_trace.checkpoints.push(checkpoint.info());
     }
     /// Get the current state
     pub fn current_state(&self) -> &S {
@@ -161,7 +1179,7 @@ impl<S: State> TransactionProcessor<S> {
     }
     
     /// Get the current state hash
-    pub fn current_hash(&self) -> crate::types::StateHash {
+    pub fn current_hash(&self) -> StateHash {
         self.state_manager.current_hash()
     }
     
@@ -170,6 +1188,59 @@ impl<S: State> TransactionProcessor<S> {
         &self.execution_trace
     }
     
+    /// Get the timestamp watermark of the transactions processed so far
+    pub fn watermark(&self) -> &WatermarkTracker {
+        &self.execution_trace.watermark
+    }
+    
+    /// Get the log entries recorded while processing, such as out-of-order warnings
+    pub fn logger(&self) -> &DeterministicLogger {
+        &self.logger
+    }
+    
+    /// Start a speculative branch from the current state
+    /// 
+    /// The fork starts from the current state without copying it and
+    /// processes transactions on its own branch, so nothing it does is
+    /// visible here until `ForkedProcessor::merge_into` is called. It keeps the transaction count limit, drift tolerance, state
+    /// cache and validation registry, but collects no side effects and is not rate limited.
+    pub fn fork(&self) -> ForkedProcessor<S> {
+        let base = self.state_manager.shared_state();
+        let base_hash = self.state_manager.current_hash();
+        let mut processor = Self::from_state_manager(self.state_manager.fork_state());
+        processor.execution_trace.watermark = self.execution_trace.watermark.clone();
+        processor.max_transaction_count = self.max_transaction_count
+            .map(|limit| limit.saturating_sub(self.execution_trace.rule_applications.len()));
+        processor.max_timestamp_drift = self.max_timestamp_drift;
+        processor.state_cache = self.state_cache.clone();
+        processor.rule_cache = self.rule_cache.as_ref().map(|cache| LruCache::new(cache.capacity()));
+        processor.id_normalizer = self.id_normalizer;
+        processor.processed_ids = self.processed_ids.clone();
+        processor.type_validators = self.type_validators.clone();
+        ForkedProcessor { base, base_hash, processor }
+    }
+    
+    /// Take an immutable snapshot of the current state for concurrent readers
+    /// 
+    /// The state, hash and transaction count all describe the same point in
+    /// processing, since the processor cannot change while it is borrowed.
+    pub fn snapshot(&self) -> ProcessorSnapshot<S> {
+        ProcessorSnapshot {
+            current_state: self.state_manager.shared_state(),
+            current_hash: self.state_manager.current_hash(),
+            transactions_processed: self.execution_trace.transactions_processed,
+            snapshot_time: Utc::now(),
+        }
+    }
+    
+    /// Get aggregated outcome counts and processing latencies
+    /// 
+    /// Every call to `process_transaction` is counted, including failed ones,
+    /// under the version of the rule set it was processed with.
+    pub fn statistics(&self) -> ProcessingStatistics {
+        self.statistics.statistics()
+    }
+    
     /// Consume the processor and return the final state and execution trace
     pub fn into_result(self) -> (S, ExecutionTrace) {
         (self.state_manager.current_state().clone(), self.execution_trace)
@@ -180,8 +1251,21 @@ impl<S: State> TransactionProcessor<S> {
         self.execution_trace.transactions_processed
     }
     
+    /// Get the time spent in each phase of processing, see `OverheadBreakdown`
+    /// 
+    /// Transactions committed from the rule application cache skip rule
+    /// application, validation and hashing, so only their callbacks are timed.
+    pub fn overhead_breakdown(&self) -> OverheadBreakdown {
+        self.state_manager.overhead_breakdown()
+    }
+    
+    /// Get the unrounded time spent in each phase of processing, see `PhaseTimings`
+    pub fn phase_timings(&self) -> PhaseTimings {
+        self.state_manager.phase_timings()
+    }
+    
     /// Create a checkpoint at the current state
-    pub fn create_checkpoint(&mut self, timestamp: DateTime<Utc>) -> crate::state_manager::Checkpoint<S> {
+    pub fn create_checkpoint(&mut self, timestamp: DateTime<Utc>) -> Checkpoint<S> {
         self.state_manager.create_checkpoint(timestamp)
     }
     
@@ -196,6 +1280,18 @@ impl<S: State> TransactionProcessor<S> {
     }
 }
 
+/// Error for a trace log that cannot be read
+fn invalid_trace_log(error: SerializationError) -> ProcessingError {
+    ProcessingError::InvalidTraceLog { reason: error.to_string() }
+}
+
+/// Get the hash a trace log's run ended on
+fn logged_final_hash(log: &ExecutionTraceLog) -> Result<StateHash, ProcessingError> {
+    log.final_hash().ok_or_else(|| ProcessingError::InvalidTraceLog {
+        reason: "no final state hash is recorded".to_string(),
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1,51 +1,311 @@
 //! Core data types for the DTRE
 
+use chrono::{DateTime, Utc};
 use serde::{Serialize, Deserialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
 use std::fmt;
 
-/// Semantic version for rule sets
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
-pub struct Version {
-    pub major: u32,
-    pub minor: u32,
-    pub patch: u32,
+pub use dtre_core::{IterationStrategy, StateHash, Version, VersionConstraint};
+
+/// State after one transaction of a streaming replay
+/// 
+/// The terminal increment, yielded once every transaction has been
+/// processed, has an empty `transaction_id` and carries the final state.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IncrementalResult<S> {
+    pub transaction_id: String,
+    pub new_hash: StateHash,
+    pub new_state: S,
+    /// Version of the rule set that processed the transaction
+    pub rule_applied: Version,
+    /// Milliseconds since the replay started
+    pub elapsed_total_ms: u64,
+}
+
+/// Result of a replay operation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplayResult<S> {
+    pub final_state: S,
+    pub final_hash: StateHash,
+    pub execution_trace: ExecutionTrace,
+    pub performance_metrics: PerformanceMetrics,
 }
 
-impl Version {
-    /// Create a new version
-    pub fn new(major: u32, minor: u32, patch: u32) -> Self {
-        Self { major, minor, patch }
+impl<S: Serialize> ReplayResult<S> {
+    /// Reconcile the final state against expected ledger values
+    /// 
+    /// Keys are dot-separated JSON paths into the serialized final state (for
+    /// example `accounts.ACC001.balance`); numeric segments index into arrays.
+    /// Results are reported in path order.
+    pub fn reconcile(&self, expected_balances: &HashMap<String, i64>) -> ReconciliationReport {
+        let state = serde_json::to_value(&self.final_state).unwrap_or(serde_json::Value::Null);
+        let mut paths: Vec<&String> = expected_balances.keys().collect();
+        paths.sort();
+        
+        let mut report = ReconciliationReport {
+            matched: Vec::new(),
+            mismatched: Vec::new(),
+            missing: Vec::new(),
+        };
+        
+        for path in paths {
+            let expected = expected_balances[path];
+            match lookup_path(&state, path) {
+                Some(actual) if actual.as_i64() == Some(expected) => {
+                    report.matched.push(path.clone());
+                }
+                Some(actual) => report.mismatched.push(ReconciliationMismatch {
+                    field_path: path.clone(),
+                    expected: serde_json::Value::from(expected),
+                    actual: actual.clone(),
+                }),
+                None => report.missing.push(path.clone()),
+            }
+        }
+        
+        report
+    }
+    
+    /// Compute a derived value from the final state
+    pub fn aggregate<V, F>(&self, extractor: F) -> V
+    where
+        F: Fn(&S) -> V,
+    {
+        extractor(&self.final_state)
+    }
+    
+    /// Compute a SHA-256 checksum for detecting accidental changes to the result
+    /// 
+    /// The checksum covers the canonical JSON of the recorded final hash, the
+    /// number of transactions processed and a SHA-256 hash of the final
+    /// state's canonical JSON, recomputed on every call so that edits to
+    /// `final_state` are caught. The state itself never enters the checksum
+    /// input, so it can be stored next to results containing personal data.
+    /// Fails if the final state cannot be serialized.
+    pub fn checksum(&self) -> Result<[u8; 32], crate::error::SerializationError> {
+        let state_json = crate::serialization::to_canonical_json(&self.final_state)?;
+        let final_state_hash = StateHash::from_canonical_json_bytes(state_json.as_bytes());
+        let summary = serde_json::json!({
+            "final_hash": self.final_hash.to_string(),
+            "transactions_processed": self.execution_trace.transactions_processed,
+            "final_state_hash": final_state_hash.to_string(),
+        });
+        let summary_json = crate::serialization::to_canonical_json(&summary)?;
+        Ok(Sha256::digest(summary_json.as_bytes()).into())
     }
     
-    /// Check if this version is compatible with another version
-    pub fn is_compatible_with(&self, other: &Version) -> bool {
-        self.major == other.major
+    /// Get the checksum as a lowercase hex string
+    pub fn checksum_hex(&self) -> Result<String, crate::error::SerializationError> {
+        self.checksum().map(hex::encode)
+    }
+    
+    /// Check that the result still has the checksum recorded earlier
+    /// 
+    /// A result whose final state cannot be serialized never verifies.
+    pub fn verify_checksum(&self, claimed: [u8; 32]) -> bool {
+        self.checksum().is_ok_and(|checksum| checksum == claimed)
     }
 }
 
-impl fmt::Display for Version {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+/// Resolve a dot-separated path within a JSON value
+fn lookup_path<'a>(value: &'a serde_json::Value, path: &str) -> Option<&'a serde_json::Value> {
+    path.split('.').try_fold(value, |current, segment| match current {
+        serde_json::Value::Object(map) => map.get(segment),
+        serde_json::Value::Array(items) => segment.parse::<usize>().ok().and_then(|i| items.get(i)),
+        _ => None,
+    })
+}
+
+/// Outcome of reconciling a final state against expected ledger values
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReconciliationReport {
+    /// Paths whose values match the ledger
+    pub matched: Vec<String>,
+    /// Paths whose values differ from the ledger
+    pub mismatched: Vec<ReconciliationMismatch>,
+    /// Paths from the ledger that do not exist in the state
+    pub missing: Vec<String>,
+}
+
+impl ReconciliationReport {
+    /// Check whether every expected value was found and matched
+    pub fn is_clean(&self) -> bool {
+        self.mismatched.is_empty() && self.missing.is_empty()
     }
 }
 
-/// Cryptographic hash of a state
+/// A field whose final value disagrees with the ledger
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReconciliationMismatch {
+    pub field_path: String,
+    pub expected: serde_json::Value,
+    pub actual: serde_json::Value,
+}
+
+/// How strictly the state manager enforces a `StateInvariant`
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
-pub struct StateHash(pub [u8; 32]);
+pub enum InvariantSeverity {
+    /// A violation rejects the transition
+    Hard,
+    /// A violation is only recorded as a warning
+    Soft,
+}
+
+/// Check of a `StateInvariant`, returning true when the invariant holds
+pub type InvariantCheck<S> = Box<dyn Fn(&S) -> bool>;
 
-impl fmt::Display for StateHash {
+/// A named invariant of a state, see `State::invariants`
+pub struct StateInvariant<S> {
+    pub name: &'static str,
+    pub severity: InvariantSeverity,
+    pub check: InvariantCheck<S>,
+}
+
+impl<S> StateInvariant<S> {
+    /// Create an invariant from its check
+    pub fn new(name: &'static str, severity: InvariantSeverity, check: impl Fn(&S) -> bool + 'static) -> Self {
+        Self {
+            name,
+            severity,
+            check: Box::new(check),
+        }
+    }
+    
+    /// Check whether the invariant holds for a state
+    pub fn holds(&self, state: &S) -> bool {
+        (self.check)(state)
+    }
+}
+
+impl<S> fmt::Debug for StateInvariant<S> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", hex::encode(&self.0))
+        f.debug_struct("StateInvariant")
+            .field("name", &self.name)
+            .field("severity", &self.severity)
+            .finish_non_exhaustive()
     }
 }
 
-/// Result of a replay operation
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ReplayResult<S> {
-    pub final_state: S,
-    pub final_hash: StateHash,
-    pub execution_trace: ExecutionTrace,
-    pub performance_metrics: PerformanceMetrics,
+/// An invariant that does not hold, see `State::check_invariants`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub struct InvariantViolation {
+    pub invariant_name: &'static str,
+    pub severity: InvariantSeverity,
+}
+
+/// A single field that differs between two states
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldChange {
+    /// Dot-separated path of the field; array elements use their index as the segment
+    pub path: String,
+    pub kind: ChangeKind,
+}
+
+/// How a field differs between two states
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ChangeKind {
+    Added { new_value: serde_json::Value },
+    Removed { old_value: serde_json::Value },
+    Modified { old_value: serde_json::Value, new_value: serde_json::Value },
+}
+
+/// Compare two JSON values field by field
+/// 
+/// Objects are compared key by key and arrays index by index, so a value
+/// appended to an array is reported as `Added`. Any other differing values,
+/// including values whose JSON type changed, are reported as `Modified`.
+/// Changes are ordered by path.
+pub(crate) fn json_field_changes(old: &serde_json::Value, new: &serde_json::Value) -> Vec<FieldChange> {
+    let mut changes = Vec::new();
+    collect_field_changes("", old, new, &mut changes);
+    changes
+}
+
+fn collect_field_changes(path: &str, old: &serde_json::Value, new: &serde_json::Value, changes: &mut Vec<FieldChange>) {
+    use serde_json::Value;
+    
+    let child_path = |segment: &str| {
+        if path.is_empty() { segment.to_string() } else { format!("{}.{}", path, segment) }
+    };
+    
+    match (old, new) {
+        (Value::Object(old_map), Value::Object(new_map)) => {
+            let mut keys: Vec<&String> = old_map.keys().chain(new_map.keys()).collect();
+            keys.sort();
+            keys.dedup();
+            for key in keys {
+                let key_path = child_path(key);
+                match (old_map.get(key), new_map.get(key)) {
+                    (Some(old_value), Some(new_value)) => collect_field_changes(&key_path, old_value, new_value, changes),
+                    (Some(old_value), None) => changes.push(FieldChange {
+                        path: key_path,
+                        kind: ChangeKind::Removed { old_value: old_value.clone() },
+                    }),
+                    (None, Some(new_value)) => changes.push(FieldChange {
+                        path: key_path,
+                        kind: ChangeKind::Added { new_value: new_value.clone() },
+                    }),
+                    (None, None) => {}
+                }
+            }
+        }
+        (Value::Array(old_items), Value::Array(new_items)) => {
+            for index in 0..old_items.len().max(new_items.len()) {
+                let index_path = child_path(&index.to_string());
+                match (old_items.get(index), new_items.get(index)) {
+                    (Some(old_value), Some(new_value)) => collect_field_changes(&index_path, old_value, new_value, changes),
+                    (Some(old_value), None) => changes.push(FieldChange {
+                        path: index_path,
+                        kind: ChangeKind::Removed { old_value: old_value.clone() },
+                    }),
+                    (None, Some(new_value)) => changes.push(FieldChange {
+                        path: index_path,
+                        kind: ChangeKind::Added { new_value: new_value.clone() },
+                    }),
+                    (None, None) => {}
+                }
+            }
+        }
+        _ if old != new => changes.push(FieldChange {
+            path: path.to_string(),
+            kind: ChangeKind::Modified { old_value: old.clone(), new_value: new.clone() },
+        }),
+        _ => {}
+    }
+}
+
+/// Outcome of trying a new rule set on upcoming transactions without keeping the result
+#[derive(Debug, Clone)]
+pub struct MigrationDryRunResult<S> {
+    /// Whether the new rule set processed every transaction
+    pub would_succeed: bool,
+    /// State after the last transaction, when every transaction succeeded
+    pub simulated_final_state: Option<S>,
+    /// Hash of `simulated_final_state`
+    pub simulated_final_hash: Option<StateHash>,
+    /// `(index, transaction_id, error)` of the transaction that stopped the simulation
+    pub first_failing_transaction: Option<(usize, String, crate::error::ProcessingError)>,
+    /// Fees under the new rule set minus fees under the current one; positive
+    /// when users would pay more. Only set when a fee extractor was given and
+    /// both rule sets processed every transaction.
+    pub fee_delta: Option<i64>,
+}
+
+/// Outcome of resuming a replay from each of its checkpoints
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CheckpointValidationReport {
+    /// Checkpoints whose resumed replay reached the original final hash
+    pub valid_count: usize,
+    /// `(transaction_index, expected_final_hash, actual_final_hash)` for every other checkpoint
+    pub invalid: Vec<(usize, StateHash, StateHash)>,
+}
+
+impl CheckpointValidationReport {
+    /// Check whether every checkpoint reproduced the original final hash
+    pub fn is_valid(&self) -> bool {
+        self.invalid.is_empty()
+    }
 }
 
 /// Trace of execution for audit purposes
@@ -55,6 +315,140 @@ pub struct ExecutionTrace {
     pub state_transitions: Vec<StateTransitionInfo>,
     pub rule_applications: Vec<RuleApplication>,
     pub checkpoints: Vec<CheckpointInfo>,
+    #[serde(default)]
+    pub watermark: WatermarkTracker,
+    /// Out-of-band state changes, also listed in `state_transitions` under their `mutation_id`
+    #[serde(default)]
+    pub mutations: Vec<StateMutationRecord>,
+    /// IDs of transactions a recovery strategy skipped, in processing order
+    #[serde(default)]
+    pub skipped_transactions: Vec<String>,
+    /// Positions of the `skipped_transactions` in the replayed sequence
+    #[serde(default)]
+    pub skipped_indices: Vec<usize>,
+}
+
+/// An out-of-band state change applied between transactions without a rule set
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StateMutationRecord {
+    /// `MUTATION_{n}`, numbered from 0 in the order mutations were applied
+    pub mutation_id: String,
+    pub description: String,
+    /// Number of transactions processed before the mutation
+    pub transaction_index: usize,
+    pub from_hash: StateHash,
+    pub to_hash: StateHash,
+}
+
+/// Tracks the latest transaction timestamp processed so far
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WatermarkTracker {
+    /// Maximum transaction timestamp seen
+    pub high_watermark_timestamp: Option<DateTime<Utc>>,
+    /// ID of the transaction that set the current watermark
+    pub high_watermark_transaction_id: Option<String>,
+    /// Number of transactions whose timestamp was below the watermark when they arrived
+    pub out_of_order_count: usize,
+}
+
+impl ExecutionTrace {
+    /// Compute a derived value from the resulting state of each recorded transition
+    /// 
+    /// The trace only stores hashes, so the states come from `transitions`, as
+    /// returned by `TransactionProcessor::process_transactions`. Transitions
+    /// that this trace did not record are skipped; values are returned in the
+    /// order of `transitions`.
+    pub fn aggregate_over_transitions<S, V>(&self, transitions: &[StateTransition<S>], aggregator: impl Fn(&S) -> V) -> Vec<V> {
+        let recorded: std::collections::HashSet<(&str, StateHash, StateHash)> = self.state_transitions
+            .iter()
+            .map(|t| (t.transaction_id.as_str(), t.from_hash, t.to_hash))
+            .collect();
+        
+        transitions
+            .iter()
+            .filter(|t| recorded.contains(&(t.transaction_id.as_str(), t.from_hash, t.to_hash)))
+            .map(|t| aggregator(&t.to_state))
+            .collect()
+    }
+    
+    /// Get the recorded inputs of the transition produced by a transaction
+    pub fn causality_for(&self, transaction_id: &str) -> Option<&CausalityRecord> {
+        self.state_transitions
+            .iter()
+            .find(|t| t.transaction_id == transaction_id)
+            .map(|t| &t.causality)
+    }
+    
+    /// Get the description of every rule application, in processing order
+    pub fn descriptions(&self) -> Vec<String> {
+        self.rule_applications.iter().map(|a| a.description.clone()).collect()
+    }
+    
+    /// Get the description recorded for the first application of a transaction
+    pub fn description_for(&self, transaction_id: &str) -> Option<&str> {
+        self.rule_applications
+            .iter()
+            .find(|a| a.transaction_id == transaction_id)
+            .map(|a| a.description.as_str())
+    }
+    
+    /// Get the rule applications whose transaction metadata maps `key` to `value`
+    pub fn transactions_with_metadata(&self, key: &str, value: &str) -> Vec<&RuleApplication> {
+        self.rule_applications
+            .iter()
+            .filter(|a| a.application_metadata.as_ref().and_then(|m| m.get(key)).is_some_and(|v| v == value))
+            .collect()
+    }
+    
+    /// Check whether a recorded transition was a state mutation rather than a rule application
+    pub fn is_mutation(&self, transaction_id: &str) -> bool {
+        self.mutations.iter().any(|m| m.mutation_id == transaction_id)
+    }
+    
+    /// Order the recorded events chronologically, see `Timeline::from_trace`
+    pub fn to_timeline(&self) -> crate::timeline::Timeline {
+        crate::timeline::Timeline::from_trace(self)
+    }
+    
+    /// Look for unusual patterns that can point to bad input or corrupted data
+    /// 
+    /// Anomalies are reported in transaction order. Consecutive failures are
+    /// found among `skipped_transactions`, so a replay must skip failing
+    /// transactions with a recovery strategy for them to show up, and state
+    /// growth is measured between checkpoints.
+    pub fn detect_anomalies(&self, config: &crate::anomaly::AnomalyDetectionConfig) -> Vec<crate::anomaly::ReplayAnomaly> {
+        crate::anomaly::detect_anomalies(self, config)
+    }
+}
+
+impl WatermarkTracker {
+    /// Create an empty tracker
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Record a processed transaction, returning `true` if it arrived out of order
+    /// 
+    /// A transaction with the same timestamp as the watermark is in order and
+    /// takes over as the watermark transaction.
+    pub fn observe(&mut self, transaction_id: &str, timestamp: DateTime<Utc>) -> bool {
+        match self.high_watermark_timestamp {
+            Some(watermark) if timestamp < watermark => {
+                self.out_of_order_count += 1;
+                true
+            }
+            _ => {
+                self.high_watermark_timestamp = Some(timestamp);
+                self.high_watermark_transaction_id = Some(transaction_id.to_string());
+                false
+            }
+        }
+    }
+    
+    /// Check whether every transaction so far had a timestamp at or after the previous one
+    pub fn is_advancing(&self) -> bool {
+        self.out_of_order_count == 0
+    }
 }
 
 /// Information about a checkpoint
@@ -63,6 +457,111 @@ pub struct CheckpointInfo {
     pub transaction_index: usize,
     pub hash: StateHash,
     pub timestamp: chrono::DateTime<chrono::Utc>,
+    /// Schema version of the state when the checkpoint was taken
+    #[serde(default = "default_schema_version")]
+    pub state_schema_version: u32,
+    /// Size of the state's bincode encoding, 0 when unknown
+    #[serde(default)]
+    pub state_size_bytes: u64,
+}
+
+fn default_schema_version() -> u32 {
+    1
+}
+
+impl CheckpointInfo {
+    /// Format the checkpoint on one line, e.g. `[3] 1a2b3c4d @ 2024-01-01T00:00:00+00:00 (1)`
+    /// 
+    /// The hash is shortened to its first 8 hex digits.
+    pub fn to_summary_string(&self) -> String {
+        let hash = self.hash.to_string();
+        format!(
+            "[{}] {} @ {} ({})",
+            self.transaction_index,
+            &hash[..8],
+            self.timestamp.to_rfc3339(),
+            self.state_schema_version
+        )
+    }
+}
+
+/// One page of a larger listing
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PaginatedResult<T> {
+    pub items: Vec<T>,
+    /// Number of items across all pages
+    pub total: usize,
+    pub offset: usize,
+    pub limit: usize,
+    /// Whether items remain after this page
+    pub has_next: bool,
+}
+
+/// Predicted resource use of replaying a transaction sequence
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct ReplayCostEstimate {
+    pub estimated_cpu_ms: u64,
+    pub estimated_memory_bytes: u64,
+    pub estimated_io_ops: u32,
+}
+
+impl ReplayCostEstimate {
+    /// CPU time assumed per transaction by `heuristic`, in microseconds
+    pub const CPU_MICROS_PER_TRANSACTION: u64 = 50;
+    
+    /// Estimate from the transaction count alone
+    /// 
+    /// Assumes `CPU_MICROS_PER_TRANSACTION` of CPU time per transaction,
+    /// rounded up to whole milliseconds, and two copies of the state per
+    /// transaction for the transition it returns. Replays do no I/O.
+    pub fn heuristic(transaction_count: usize, state_size_bytes: u64) -> Self {
+        let transactions = transaction_count as u64;
+        Self {
+            estimated_cpu_ms: transactions.saturating_mul(Self::CPU_MICROS_PER_TRANSACTION).div_ceil(1_000),
+            estimated_memory_bytes: state_size_bytes.saturating_mul(transactions.saturating_mul(2).saturating_add(1)),
+            estimated_io_ops: 0,
+        }
+    }
+    
+    /// Add two estimates, saturating at the maximum of each field
+    pub fn saturating_add(&self, other: &ReplayCostEstimate) -> Self {
+        Self {
+            estimated_cpu_ms: self.estimated_cpu_ms.saturating_add(other.estimated_cpu_ms),
+            estimated_memory_bytes: self.estimated_memory_bytes.saturating_add(other.estimated_memory_bytes),
+            estimated_io_ops: self.estimated_io_ops.saturating_add(other.estimated_io_ops),
+        }
+    }
+}
+
+/// Limits on the estimated cost of a replay; `None` means unlimited
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct ReplayCostBudget {
+    pub max_cpu_ms: Option<u64>,
+    pub max_memory_bytes: Option<u64>,
+}
+
+impl ReplayCostBudget {
+    /// Check an estimate against the limits
+    /// 
+    /// # Errors
+    /// Returns `ProcessingError::CostBudgetExceeded` for the first limit the
+    /// estimate exceeds, checking CPU time before memory
+    pub fn check(&self, estimate: &ReplayCostEstimate) -> Result<(), crate::error::ProcessingError> {
+        let limits = [
+            ("cpu_ms", estimate.estimated_cpu_ms, self.max_cpu_ms),
+            ("memory_bytes", estimate.estimated_memory_bytes, self.max_memory_bytes),
+        ];
+        for (resource, estimated, limit) in limits {
+            if let Some(limit) = limit.filter(|limit| estimated > *limit) {
+                return Err(crate::error::ProcessingError::CostBudgetExceeded {
+                    resource: resource.to_string(),
+                    estimated,
+                    limit,
+                });
+            }
+        }
+        Ok(())
+    }
 }
 
 /// Information about a state transition
@@ -71,6 +570,9 @@ pub struct StateTransitionInfo {
     pub from_hash: StateHash,
     pub to_hash: StateHash,
     pub transaction_id: String,
+    /// Inputs the rule set read while producing the transition
+    #[serde(default)]
+    pub causality: CausalityRecord,
 }
 
 /// State transition with full state data
@@ -81,6 +583,52 @@ pub struct StateTransition<S> {
     pub from_hash: StateHash,
     pub to_hash: StateHash,
     pub transaction_id: String,
+    /// Inputs the rule set read while producing the transition
+    #[serde(default)]
+    pub causality: CausalityRecord,
+    /// Rule clauses recorded while producing the transition
+    #[serde(default)]
+    pub audit: AuditRecord,
+}
+
+/// External inputs that contributed to a state transition
+/// 
+/// Recorded by the execution context during `RuleSet::apply`. Keys and
+/// identifiers are listed once each, in the order they were first accessed.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CausalityRecord {
+    /// Keys of external facts read through `ExecutionContext::get_external_fact`
+    pub accessed_facts: Vec<String>,
+    /// Identifiers of entities resolved through `ExecutionContext::resolve_entity`
+    pub accessed_entities: Vec<String>,
+    /// Number of calls to `ExecutionContext::random`
+    pub random_draws: u32,
+}
+
+impl CausalityRecord {
+    /// Record that an external fact was read
+    pub fn record_fact(&mut self, key: &str) {
+        if !self.accessed_facts.iter().any(|k| k == key) {
+            self.accessed_facts.push(key.to_string());
+        }
+    }
+    
+    /// Record that an entity was resolved
+    pub fn record_entity(&mut self, entity_id: &str) {
+        if !self.accessed_entities.iter().any(|id| id == entity_id) {
+            self.accessed_entities.push(entity_id.to_string());
+        }
+    }
+    
+    /// Record a draw from the random number generator
+    pub fn record_random_draw(&mut self) {
+        self.random_draws = self.random_draws.saturating_add(1);
+    }
+    
+    /// Check whether no external inputs were accessed
+    pub fn is_empty(&self) -> bool {
+        self.accessed_facts.is_empty() && self.accessed_entities.is_empty() && self.random_draws == 0
+    }
 }
 
 /// Information about a rule application
@@ -89,6 +637,27 @@ pub struct RuleApplication {
     pub rule_version: Version,
     pub transaction_id: String,
     pub timestamp: chrono::DateTime<chrono::Utc>,
+    /// Rule clauses recorded while applying the transaction
+    #[serde(default)]
+    pub audit: AuditRecord,
+    /// Human-readable summary from `RuleSet::describe_transaction`
+    #[serde(default)]
+    pub description: String,
+    /// The transaction's `Transaction::metadata`
+    #[serde(default)]
+    pub application_metadata: Option<HashMap<String, String>>,
+}
+
+/// Rule clauses recorded by `RuleSet::apply_with_audit`
+/// 
+/// Empty unless the rule set is wrapped in an `InstrumentedRuleSet`, see
+/// `RuleAuditRecorder`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditRecord {
+    /// Clauses whose conditions were checked, in the order first checked
+    pub rule_clauses_evaluated: Vec<String>,
+    /// Clauses whose branches were taken, in the order first taken
+    pub rule_clauses_fired: Vec<String>,
 }
 
 /// Performance metrics for a replay
@@ -97,6 +666,164 @@ pub struct PerformanceMetrics {
     pub total_duration_ms: u64,
     pub transactions_per_second: f64,
     pub average_transaction_time_ms: f64,
+    /// Time spent in each phase of processing
+    #[serde(default)]
+    pub breakdown: OverheadBreakdown,
+}
+
+/// Time spent in each phase of processing transactions, in milliseconds
+/// 
+/// Each phase is timed across all transactions before being rounded down,
+/// so phases that take well under a millisecond per transaction still add up.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OverheadBreakdown {
+    /// Running the rule set's `pre_validate` and `apply`
+    pub rule_application_ms: u64,
+    /// Hashing states before and after each transaction and at checkpoints
+    pub state_hashing_ms: u64,
+    /// Validating transactions and the states they produce
+    pub state_validation_ms: u64,
+    pub checkpoint_creation_ms: u64,
+    /// Running `RuleSet::describe_transaction` and `RuleSet::enqueue_side_effects`
+    pub observer_callbacks_ms: u64,
+    /// Serializing new states for the size limit and transactions for the state cache
+    pub serialization_ms: u64,
+}
+
+impl OverheadBreakdown {
+    /// Get the name and time of each phase, in field order
+    fn phases(&self) -> [(&'static str, u64); 6] {
+        [
+            ("rule_application", self.rule_application_ms),
+            ("state_hashing", self.state_hashing_ms),
+            ("state_validation", self.state_validation_ms),
+            ("checkpoint_creation", self.checkpoint_creation_ms),
+            ("observer_callbacks", self.observer_callbacks_ms),
+            ("serialization", self.serialization_ms),
+        ]
+    }
+    
+    /// Get the total time of all phases
+    pub fn total_ms(&self) -> u64 {
+        self.phases().iter().map(|(_, ms)| ms).sum()
+    }
+    
+    /// Get the name of the phase that took the longest, such as `"state_hashing"`
+    /// 
+    /// Ties go to the phase declared first, so an all-zero breakdown
+    /// returns `"rule_application"`.
+    pub fn dominant_phase(&self) -> &'static str {
+        self.phases()
+            .into_iter()
+            .rev()
+            .max_by_key(|(_, ms)| *ms)
+            .map_or("rule_application", |(name, _)| name)
+    }
+    
+    /// Render each phase's time and share of the total, one phase per line
+    pub fn to_percentage_table(&self) -> String {
+        let total = self.total_ms();
+        let mut table = format!("{:<20} {:>10} {:>7}\n", "phase", "ms", "%");
+        for (name, ms) in self.phases() {
+            let percentage = if total > 0 { ms as f64 * 100.0 / total as f64 } else { 0.0 };
+            table.push_str(&format!("{:<20} {:>10} {:>6.1}%\n", name, ms, percentage));
+        }
+        table
+    }
+}
+
+/// Throughput of past replays, used by `ReplayEngine::estimate_duration`
+/// 
+/// Only record replays of the same engine configuration and rule set, since
+/// their throughput is what the estimate assumes.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PerformanceHistory {
+    /// Transactions per second of each recorded replay, in recording order
+    samples: Vec<f64>,
+}
+
+impl PerformanceHistory {
+    /// Create an empty history
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Record the throughput of a replay
+    /// 
+    /// Replays too short to measure have a throughput of zero and are ignored.
+    pub fn record(&mut self, metrics: &PerformanceMetrics) {
+        let tps = metrics.transactions_per_second;
+        if tps.is_finite() && tps > 0.0 {
+            self.samples.push(tps);
+        }
+    }
+    
+    /// Get the number of recorded replays
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+    
+    /// Check whether no replay was recorded
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+    
+    /// Get the mean throughput of the recorded replays, 0.0 when there are none
+    pub fn average_tps(&self) -> f64 {
+        if self.samples.is_empty() {
+            return 0.0;
+        }
+        self.samples.iter().sum::<f64>() / self.samples.len() as f64
+    }
+    
+    /// Get the throughput that `p` percent of the recorded replays were at or below
+    /// 
+    /// Uses the nearest-rank method with `p` clamped to 0..=100, so
+    /// `percentile_tps(5.0)` is the throughput of a slow run. Returns 0.0 when
+    /// no replay was recorded.
+    pub fn percentile_tps(&self, p: f64) -> f64 {
+        if self.samples.is_empty() {
+            return 0.0;
+        }
+        let mut sorted = self.samples.clone();
+        sorted.sort_by(f64::total_cmp);
+        let rank = (p.clamp(0.0, 100.0) / 100.0 * sorted.len() as f64).ceil() as usize;
+        sorted[rank.clamp(1, sorted.len()) - 1]
+    }
+}
+
+/// Expected duration of a replay, see `ReplayEngine::estimate_duration`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct DurationEstimate {
+    pub p50_ms: u64,
+    /// Duration that 95% of replays are expected to finish within
+    pub p95_ms: u64,
+    pub p99_ms: u64,
+    /// From 0.0 with no recorded replays up to 1.0 with 30 or more
+    pub confidence: f64,
+}
+
+impl DurationEstimate {
+    /// Number of recorded replays from which an estimate has full confidence
+    pub const FULL_CONFIDENCE_SAMPLES: usize = 30;
+    
+    /// Estimate how long `transaction_count` transactions take at the throughputs in `history`
+    pub fn from_history(transaction_count: usize, history: &PerformanceHistory) -> Self {
+        let duration_ms = |tps: f64| {
+            if tps > 0.0 {
+                (transaction_count as f64 / tps * 1000.0).round() as u64
+            } else {
+                0
+            }
+        };
+        Self {
+            p50_ms: duration_ms(history.percentile_tps(50.0)),
+            // Slower runs take longer, so the 95th percentile duration comes from the 5th percentile throughput
+            p95_ms: duration_ms(history.percentile_tps(5.0)),
+            p99_ms: duration_ms(history.percentile_tps(1.0)),
+            confidence: history.len().min(Self::FULL_CONFIDENCE_SAMPLES) as f64 / Self::FULL_CONFIDENCE_SAMPLES as f64,
+        }
+    }
 }
 
 /// Impact analysis comparing two replay results from different rule versions
@@ -0,0 +1,135 @@
+//! Validation rules registered per concrete transaction type
+
+use crate::error::ValidationError;
+use crate::traits::Transaction;
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::fmt;
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+/// Validator taking a transaction already known to be of its concrete type
+type ErasedValidator = Box<dyn Fn(&dyn Any) -> Result<(), ValidationError> + Send + Sync>;
+
+fn validate_concrete_type<T: Transaction>(
+    validators: &HashMap<TypeId, ErasedValidator>,
+    transaction: &T,
+) -> Result<(), ValidationError> {
+    match transaction.as_any() {
+        Some(inner) => validators.get(&inner.type_id()).map_or(Ok(()), |validator| validator(inner)),
+        None => Ok(()),
+    }
+}
+
+/// Validators keyed by the concrete transaction type they check
+/// 
+/// Shared between a `ValidationRegistry` and the processors it is attached to.
+#[derive(Clone, Default)]
+pub(crate) struct TypeValidators(Arc<HashMap<TypeId, ErasedValidator>>);
+
+impl TypeValidators {
+    /// Run the validator registered for the concrete type behind `Transaction::as_any`, if any
+    pub(crate) fn validate<T: Transaction>(&self, transaction: &T) -> Result<(), ValidationError> {
+        validate_concrete_type(&self.0, transaction)
+    }
+}
+
+impl fmt::Debug for TypeValidators {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TypeValidators").field("count", &self.0.len()).finish()
+    }
+}
+
+/// Validation rules for each concrete type behind a transaction type `T`
+/// 
+/// `T` is usually a wrapper over several transaction types, such as
+/// `AnyTransaction`, and the concrete type is found with
+/// `Transaction::as_any`. Types without a registered validator are only
+/// checked by `Transaction::validate`.
+pub struct ValidationRegistry<T> {
+    validators: HashMap<TypeId, ErasedValidator>,
+    _transaction: PhantomData<fn(&T)>,
+}
+
+impl<T: Transaction> ValidationRegistry<T> {
+    /// Create a registry without validators
+    pub fn new() -> Self {
+        Self {
+            validators: HashMap::new(),
+            _transaction: PhantomData,
+        }
+    }
+    
+    /// Register the validator for transactions of concrete type `Specific`, replacing any previous one
+    pub fn register_validator<Specific: 'static>(
+        &mut self,
+        validator: impl Fn(&Specific) -> Result<(), ValidationError> + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.validators.insert(
+            TypeId::of::<Specific>(),
+            Box::new(move |transaction: &dyn Any| match transaction.downcast_ref::<Specific>() {
+                Some(specific) => validator(specific),
+                None => Ok(()),
+            }),
+        );
+        self
+    }
+    
+    /// Check whether a validator is registered for concrete type `Specific`
+    pub fn has_validator<Specific: 'static>(&self) -> bool {
+        self.validators.contains_key(&TypeId::of::<Specific>())
+    }
+    
+    /// Validate a transaction with `Transaction::validate`, then with the validator for its concrete type
+    pub fn validate(&self, transaction: &T) -> Result<(), ValidationError> {
+        transaction.validate()?;
+        validate_concrete_type(&self.validators, transaction)
+    }
+    
+    /// Validate every transaction, collecting the failures with their index
+    pub fn validate_all(&self, transactions: &[T]) -> ValidationSummary {
+        let failed: Vec<(usize, ValidationError)> = transactions
+            .iter()
+            .enumerate()
+            .filter_map(|(index, transaction)| self.validate(transaction).err().map(|e| (index, e)))
+            .collect();
+        ValidationSummary {
+            total: transactions.len(),
+            passed: transactions.len() - failed.len(),
+            failed,
+        }
+    }
+    
+    /// Move the validators into a form processors can share
+    pub(crate) fn into_type_validators(self) -> TypeValidators {
+        TypeValidators(Arc::new(self.validators))
+    }
+}
+
+impl<T: Transaction> Default for ValidationRegistry<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> fmt::Debug for ValidationRegistry<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ValidationRegistry").field("validators", &self.validators.len()).finish()
+    }
+}
+
+/// Result of `ValidationRegistry::validate_all`
+#[derive(Debug)]
+pub struct ValidationSummary {
+    pub total: usize,
+    pub passed: usize,
+    /// Index and error of each transaction that failed validation
+    pub failed: Vec<(usize, ValidationError)>,
+}
+
+impl ValidationSummary {
+    /// Check whether every transaction passed
+    pub fn is_valid(&self) -> bool {
+        self.failed.is_empty()
+    }
+}
@@ -0,0 +1,193 @@
+//! `wasm-bindgen` wrappers for using replay results from JavaScript
+//! 
+//! Replays still run in Rust with concrete state, transaction and rule set
+//! types; these wrappers expose the inputs and results to JavaScript. Enabled
+//! by the `wasm` feature, which also exports `ExecutionContext`, `Version`
+//! and `StateHash` directly. `ReplayResult<S>` is generic over the state,
+//! which `wasm-bindgen` cannot export, so `WasmReplayResult` carries its
+//! final state as JSON instead.
+
+use crate::context::ExecutionContext;
+use crate::traits::State;
+use crate::types::{ReplayResult, StateHash, Version};
+use chrono::{DateTime, Utc};
+use wasm_bindgen::prelude::*;
+
+/// Semantic version exposed to JavaScript
+#[wasm_bindgen]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WasmVersion {
+    inner: Version,
+}
+
+#[wasm_bindgen]
+impl WasmVersion {
+    /// Create a version from its components
+    #[wasm_bindgen(constructor)]
+    pub fn new(major: u32, minor: u32, patch: u32) -> Self {
+        Self { inner: Version::new(major, minor, patch) }
+    }
+    
+    /// Parse a `major.minor.patch` string
+    #[wasm_bindgen(js_name = fromString)]
+    pub fn from_string(s: &str) -> Result<WasmVersion, String> {
+        Version::parse(s)
+            .map(|inner| Self { inner })
+            .ok_or_else(|| format!("Invalid version string: {}", s))
+    }
+    
+    #[wasm_bindgen(getter)]
+    pub fn major(&self) -> u32 {
+        self.inner.major
+    }
+    
+    #[wasm_bindgen(getter)]
+    pub fn minor(&self) -> u32 {
+        self.inner.minor
+    }
+    
+    #[wasm_bindgen(getter)]
+    pub fn patch(&self) -> u32 {
+        self.inner.patch
+    }
+    
+    /// Check if this version is compatible with another version
+    #[wasm_bindgen(js_name = isCompatibleWith)]
+    pub fn is_compatible_with(&self, other: &WasmVersion) -> bool {
+        self.inner.is_compatible_with(&other.inner)
+    }
+    
+    /// Compare two versions, returning -1, 0 or 1
+    pub fn compare(&self, other: &WasmVersion) -> i32 {
+        self.inner.cmp(&other.inner) as i32
+    }
+    
+    #[wasm_bindgen(js_name = toString)]
+    pub fn to_js_string(&self) -> String {
+        self.inner.to_string()
+    }
+}
+
+impl WasmVersion {
+    /// Get the wrapped version
+    pub fn inner(&self) -> &Version {
+        &self.inner
+    }
+}
+
+impl From<Version> for WasmVersion {
+    fn from(inner: Version) -> Self {
+        Self { inner }
+    }
+}
+
+/// Execution context exposed to JavaScript
+#[wasm_bindgen]
+#[derive(Debug, Clone)]
+pub struct WasmExecutionContext {
+    inner: ExecutionContext,
+}
+
+#[wasm_bindgen]
+impl WasmExecutionContext {
+    /// Create a context from a time in milliseconds since the Unix epoch and a random seed
+    #[wasm_bindgen(constructor)]
+    pub fn new(time_millis: f64, random_seed: u64) -> Result<WasmExecutionContext, String> {
+        let time = DateTime::<Utc>::from_timestamp_millis(time_millis as i64)
+            .ok_or_else(|| format!("Time out of range: {}", time_millis))?;
+        Ok(Self { inner: ExecutionContext::new(time, random_seed) })
+    }
+    
+    /// Time of the context in milliseconds since the Unix epoch
+    #[wasm_bindgen(getter, js_name = timeMillis)]
+    pub fn time_millis(&self) -> f64 {
+        self.inner.now().timestamp_millis() as f64
+    }
+    
+    #[wasm_bindgen(getter, js_name = randomSeed)]
+    pub fn random_seed(&self) -> u64 {
+        self.inner.random_seed()
+    }
+}
+
+impl WasmExecutionContext {
+    /// Get the wrapped context, e.g. to pass to a replay engine
+    pub fn inner(&self) -> &ExecutionContext {
+        &self.inner
+    }
+}
+
+/// Replay result exposed to JavaScript, with the final state as JSON
+#[wasm_bindgen]
+#[derive(Debug, Clone)]
+pub struct WasmReplayResult {
+    final_state_json: String,
+    final_hash: StateHash,
+    transactions_processed: usize,
+    total_duration_ms: u64,
+}
+
+#[wasm_bindgen]
+impl WasmReplayResult {
+    /// Final state hash as lowercase hex
+    #[wasm_bindgen(js_name = finalHashHex)]
+    pub fn final_hash_hex(&self) -> String {
+        self.final_hash.to_string()
+    }
+    
+    /// Final state serialized as JSON
+    #[wasm_bindgen(js_name = finalStateJson)]
+    pub fn final_state_json(&self) -> String {
+        self.final_state_json.clone()
+    }
+    
+    #[wasm_bindgen(getter, js_name = transactionsProcessed)]
+    pub fn transactions_processed(&self) -> usize {
+        self.transactions_processed
+    }
+    
+    #[wasm_bindgen(getter, js_name = totalDurationMs)]
+    pub fn total_duration_ms(&self) -> f64 {
+        self.total_duration_ms as f64
+    }
+}
+
+impl WasmReplayResult {
+    /// Wrap a replay result, serializing its final state to JSON
+    pub fn from_result<S: State>(result: &ReplayResult<S>) -> Result<Self, crate::error::SerializationError> {
+        let final_state_json = serde_json::to_string(&result.final_state).map_err(|e| {
+            crate::error::SerializationError::SerializationFailed { reason: e.to_string() }
+        })?;
+        Ok(Self {
+            final_state_json,
+            final_hash: result.final_hash,
+            transactions_processed: result.execution_trace.transactions_processed,
+            total_duration_ms: result.performance_metrics.total_duration_ms,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_version_from_string() {
+        let version = WasmVersion::from_string("1.2.3").unwrap();
+        assert_eq!((version.major(), version.minor(), version.patch()), (1, 2, 3));
+        assert_eq!(version.to_js_string(), "1.2.3");
+        assert_eq!(version.compare(&WasmVersion::new(1, 10, 0)), -1);
+        assert!(version.is_compatible_with(&WasmVersion::new(1, 0, 0)));
+        
+        assert!(WasmVersion::from_string("1.2").is_err());
+        assert!(WasmVersion::from_string("1.2.x").is_err());
+    }
+    
+    #[test]
+    fn test_execution_context_round_trip() {
+        let context = WasmExecutionContext::new(1_700_000_000_123.0, 42).unwrap();
+        assert_eq!(context.time_millis(), 1_700_000_000_123.0);
+        assert_eq!(context.random_seed(), 42);
+        assert_eq!(context.inner().now().timestamp_millis(), 1_700_000_000_123);
+    }
+}
@@ -13,8 +13,8 @@ use std::collections::HashMap;
 // For now, we'll duplicate the necessary types
 
 use dtre::{
-    ExecutionContext, ProcessingError, ReplayEngineBuilder, RuleSet, State,
-    Transaction, ValidationError, Version,
+    ExecutionContext, ExternalLedger, FixtureGenerationOptions, FixtureTypePaths, InvariantSeverity, InvariantViolation, ProcessingError, ReplayEngineBuilder, RuleAuditRecorder, RuleSet, RuleSetMetadata,
+    RuleSetRegistry, ShadowDiscrepancy, State, StateInvariant, Transaction, ValidationError, Version, VersionedRuleSet,
 };
 use serde::{Deserialize, Serialize};
 use std::hash::{Hash, Hasher};
@@ -82,8 +82,8 @@ impl Hash for BankingState {
     }
 }
 
-impl State for BankingState {
-    fn validate(&self) -> Result<(), ValidationError> {
+impl BankingState {
+    fn validate_accounts(&self) -> Result<(), ValidationError> {
         for (id, account) in &self.accounts {
             if id != &account.account_id {
                 return Err(ValidationError::InvalidState {
@@ -98,16 +98,63 @@ impl State for BankingState {
             }
         }
         
+        Ok(())
+    }
+    
+    fn validate_fees(&self) -> Result<(), ValidationError> {
         if self.total_fees_collected < 0 {
-            return Err(ValidationError::InvalidState {
-                reason: "Total fees cannot be negative".to_string(),
-            });
+            return Err(ValidationError::invalid_field("total_fees_collected", "Total fees cannot be negative"));
         }
         
         Ok(())
     }
 }
 
+impl State for BankingState {
+    const NORMALIZES: bool = true;
+    const FIELD_VALIDATION: bool = true;
+    
+    fn validate(&self) -> Result<(), ValidationError> {
+        self.validate_accounts()?;
+        self.validate_fees()
+    }
+    
+    fn validate_field(&self, path: &str) -> Result<(), ValidationError> {
+        match path.split('.').next() {
+            Some("accounts") => self.validate_accounts(),
+            Some("total_fees_collected") => self.validate_fees(),
+            _ => Ok(()),
+        }
+    }
+    
+    // Accounts live in a HashMap, so hash the sorted form to get the same hash in every process
+    fn iteration_order() -> dtre::IterationStrategy {
+        dtre::IterationStrategy::Sorted
+    }
+    
+    fn normalize(&mut self) {
+        self.transaction_history
+            .sort_by(|a, b| (a.timestamp, &a.transaction_id).cmp(&(b.timestamp, &b.transaction_id)));
+    }
+    
+    fn invariants(&self) -> Vec<StateInvariant<Self>> {
+        vec![
+            // Fees only come out of recorded transfers, so no money appears or disappears
+            StateInvariant::new("balance_conservation", InvariantSeverity::Hard, |state: &BankingState| {
+                state.total_fees_collected == state.transaction_history.iter().map(|record| record.fee).sum::<i64>()
+            }),
+            // Transfers recorded for a frozen account may still need to be settled by hand
+            StateInvariant::new("no_frozen_accounts_with_pending_transactions", InvariantSeverity::Soft, |state: &BankingState| {
+                state.accounts.values().filter(|a| a.status == AccountStatus::Frozen).all(|account| {
+                    !state.transaction_history.iter().any(|record| {
+                        record.from_account == account.account_id || record.to_account == account.account_id
+                    })
+                })
+            }),
+        ]
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TransferTransaction {
     pub id: String,
@@ -173,6 +220,7 @@ impl Transaction for TransferTransaction {
 // Rule Set Implementations
 // ============================================================================
 
+#[derive(Default)]
 pub struct TransferRulesV1;
 
 impl RuleSet<BankingState, TransferTransaction> for TransferRulesV1 {
@@ -180,6 +228,20 @@ impl RuleSet<BankingState, TransferTransaction> for TransferRulesV1 {
         Version::new(1, 0, 0)
     }
     
+    fn describe_transaction(
+        &self,
+        _state: &BankingState,
+        transaction: &TransferTransaction,
+        _context: &ExecutionContext,
+    ) -> String {
+        format!(
+            "Transfer ${:.2} from {} to {} (fee: $1.00)",
+            transaction.amount as f64 / 100.0,
+            transaction.from_account,
+            transaction.to_account
+        )
+    }
+    
     fn apply(
         &self,
         state: &BankingState,
@@ -404,11 +466,12 @@ impl RuleSet<BankingState, TransferTransaction> for TransferRulesV2 {
             });
         }
         
-        let fee = if transaction.amount < 10_000 {
+        let fee = if RuleAuditRecorder::evaluate("fee.flat_path", transaction.amount < 10_000) {
             50
-        } else if transaction.amount < 100_000 {
+        } else if RuleAuditRecorder::evaluate("fee.percentage_path", transaction.amount < 100_000) {
             transaction.amount / 100
         } else {
+            RuleAuditRecorder::record_clause("fee.reduced_percentage_path");
             transaction.amount / 200
         };
         
@@ -1044,3 +1107,1888 @@ fn test_currency_mismatch_handling() {
     
     assert!(result.is_err());
 }
+
+#[test]
+fn test_end_of_day_reconciliation() {
+    let transactions = create_test_transactions();
+    let result = ReplayEngineBuilder::new()
+        .with_initial_state(create_test_state())
+        .with_rule_set(TransferRulesV1)
+        .with_context(create_test_context())
+        .build()
+        .unwrap()
+        .replay(&transactions)
+        .unwrap();
+    
+    let mut ledger = HashMap::new();
+    ledger.insert("accounts.ACC001.balance".to_string(), 139_900);
+    ledger.insert("accounts.ACC002.balance".to_string(), 34_900);
+    ledger.insert("accounts.ACC003.balance".to_string(), 174_900);
+    ledger.insert("total_fees_collected".to_string(), 300);
+    ledger.insert("transaction_history.2.amount".to_string(), 50_000);
+    
+    let report = result.reconcile(&ledger);
+    assert!(report.is_clean(), "unexpected report: {:?}", report);
+    assert_eq!(report.matched.len(), 5);
+    
+    // A ledger that disagrees or references unknown accounts is reported
+    ledger.insert("accounts.ACC001.balance".to_string(), 140_000);
+    ledger.insert("accounts.ACC999.balance".to_string(), 0);
+    
+    let report = result.reconcile(&ledger);
+    assert!(!report.is_clean());
+    assert_eq!(report.mismatched.len(), 1);
+    assert_eq!(report.mismatched[0].field_path, "accounts.ACC001.balance");
+    assert_eq!(report.mismatched[0].expected, serde_json::json!(140_000));
+    assert_eq!(report.mismatched[0].actual, serde_json::json!(139_900));
+    assert_eq!(report.missing, vec!["accounts.ACC999.balance"]);
+}
+
+/// Core banking system mock reporting fixed account balances
+struct MockCoreBankingLedger {
+    balances: HashMap<String, i64>,
+}
+
+impl ExternalLedger<BankingState> for MockCoreBankingLedger {
+    fn balances(&self) -> HashMap<String, i64> {
+        self.balances.clone()
+    }
+    
+    fn extract_balances(&self, state: &BankingState) -> HashMap<String, i64> {
+        state.accounts.iter().map(|(id, account)| (id.clone(), account.balance)).collect()
+    }
+}
+
+#[test]
+fn test_verify_against_external_ledger() {
+    let engine = ReplayEngineBuilder::new()
+        .with_initial_state(create_test_state())
+        .with_rule_set(TransferRulesV1)
+        .with_context(create_test_context())
+        .build()
+        .unwrap();
+    let result = engine.replay(&create_test_transactions()).unwrap();
+    
+    let mut ledger = MockCoreBankingLedger {
+        balances: HashMap::from([
+            ("ACC001".to_string(), 139_900),
+            ("ACC002".to_string(), 34_900),
+            ("ACC003".to_string(), 174_900),
+        ]),
+    };
+    let verification = engine.verify_against_external_ledger(&result, &ledger);
+    assert!(verification.matches);
+    assert!(verification.discrepancies.is_empty());
+    assert_eq!(verification.total_discrepancy(), 0);
+    
+    // The core banking system holds $1 more on ACC002
+    ledger.balances.insert("ACC002".to_string(), 35_000);
+    let verification = engine.verify_against_external_ledger(&result, &ledger);
+    assert!(!verification.matches);
+    assert_eq!(verification.discrepancies.len(), 1);
+    let discrepancy = &verification.discrepancies[0];
+    assert_eq!(discrepancy.account_id, "ACC002");
+    assert_eq!(discrepancy.dtre_value, 34_900);
+    assert_eq!(discrepancy.ledger_value, 35_000);
+    assert_eq!(discrepancy.difference, -100);
+    assert_eq!(verification.total_discrepancy(), 100);
+    assert!(verification.is_within_tolerance(100));
+    assert!(!verification.is_within_tolerance(99));
+}
+
+#[test]
+fn test_state_invariants_severities() {
+    let state = create_test_state();
+    let invariants: Vec<(&str, InvariantSeverity)> = state.invariants().iter().map(|i| (i.name, i.severity)).collect();
+    assert_eq!(invariants, vec![
+        ("balance_conservation", InvariantSeverity::Hard),
+        ("no_frozen_accounts_with_pending_transactions", InvariantSeverity::Soft),
+    ]);
+    assert!(state.check_invariants().is_empty());
+    
+    let mut unbalanced = state.clone();
+    unbalanced.total_fees_collected = 100;
+    assert_eq!(unbalanced.check_invariants(), vec![InvariantViolation {
+        invariant_name: "balance_conservation",
+        severity: InvariantSeverity::Hard,
+    }]);
+}
+
+#[test]
+fn test_state_manager_enforces_invariants() {
+    use dtre::{LogLevel, TransactionProcessor};
+    
+    let transactions = create_test_transactions();
+    let context = create_test_context();
+    let mut processor = TransactionProcessor::new(create_test_state()).unwrap();
+    processor.process_transaction(&transactions[0], &TransferRulesV1, &context).unwrap();
+    
+    // Freezing an account with recorded transfers only warns
+    processor
+        .apply_mutation("Freeze ACC002", |mut state| {
+            state.accounts.get_mut("ACC002").unwrap().status = AccountStatus::Frozen;
+            Ok(state)
+        })
+        .unwrap();
+    processor.process_transaction(&transactions[2], &TransferRulesV1, &context).unwrap();
+    
+    let warnings = processor.state_manager().invariant_warnings();
+    assert_eq!(warnings.len(), 2);
+    assert_eq!(warnings[0].0, "MUTATION_0");
+    assert_eq!(warnings[1].0, "TXN003");
+    assert!(warnings.iter().all(|(_, v)| v.invariant_name == "no_frozen_accounts_with_pending_transactions"));
+    let logged = processor.logger().filter_by_level(LogLevel::Warn);
+    assert_eq!(logged.len(), 1);
+    assert!(logged[0].message.contains("no_frozen_accounts_with_pending_transactions"));
+    
+    // Collecting fees without a transfer breaks a hard invariant and is rejected
+    let hash = processor.current_hash();
+    let error = processor
+        .apply_mutation("Collect fee", |mut state| {
+            state.total_fees_collected += 100;
+            Ok(state)
+        })
+        .unwrap_err();
+    assert!(error.to_string().contains("balance_conservation"));
+    assert_eq!(processor.current_hash(), hash);
+}
+
+#[test]
+fn test_explain_insufficient_balance() {
+    use dtre::TransactionProcessor;
+    
+    let mut initial_state = create_test_state();
+    initial_state.accounts.get_mut("ACC001").unwrap().balance = 5_000;
+    let processor = TransactionProcessor::new(initial_state.clone()).unwrap();
+    let context = create_test_context();
+    
+    let transaction = TransferTransaction {
+        id: "TXN001".to_string(),
+        timestamp: DateTime::parse_from_rfc3339("2024-01-01T00:00:00Z").unwrap().with_timezone(&Utc),
+        from_account: "ACC001".to_string(),
+        to_account: "ACC002".to_string(),
+        amount: 10_000,
+        currency: "USD".to_string(),
+        description: "Should fail".to_string(),
+    };
+    
+    let trace = processor.explain(&transaction, &TransferRulesV1, &context);
+    
+    assert!(!trace.would_succeed);
+    assert!(trace.transaction_validated.is_ok());
+    assert!(trace.rule_pre_validated.is_ok());
+    assert!(trace.rule_applied.is_err());
+    assert!(trace.invariants_checked.is_err());
+    
+    let failed_step = trace.explanation_steps.iter()
+        .find(|step| step.contains("failed"))
+        .expect("a failing step is described");
+    assert!(failed_step.starts_with("Step 3 (rule application) failed"));
+    assert!(failed_step.contains("Insufficient balance: have 5000, need 10100"));
+    
+    // The state at the point of failure is included
+    let state_step = trace.explanation_steps.iter()
+        .find(|step| step.starts_with("state at this point"))
+        .unwrap();
+    assert!(state_step.contains("\"balance\":5000"));
+    assert_eq!(trace.explanation_steps.last().unwrap(), "Transaction TXN001 would be rejected at step 3");
+    
+    // Explaining does not modify the processor's state
+    assert_eq!(processor.current_state(), &initial_state);
+    assert_eq!(processor.transactions_processed(), 0);
+}
+
+#[test]
+fn test_explain_accepted_transfer() {
+    use dtre::TransactionProcessor;
+    
+    let processor = TransactionProcessor::new(create_test_state()).unwrap();
+    let transaction = create_test_transactions().remove(0);
+    
+    let trace = processor.explain(&transaction, &TransferRulesV1, &create_test_context());
+    
+    assert!(trace.would_succeed);
+    assert!(trace.invariants_checked.is_ok());
+    let transition = trace.rule_applied.as_ref().unwrap();
+    assert_eq!(transition.to_state.accounts["ACC001"].balance, 89_900);
+    assert_eq!(trace.explanation_steps.len(), 5);
+    assert_eq!(trace.explanation_steps.last().unwrap(), "Transaction TXN001 would be accepted");
+}
+
+#[test]
+fn test_explain_checks_state_invariants() {
+    use dtre::TransactionProcessor;
+    
+    let transactions = create_test_transactions();
+    let context = create_test_context();
+    let mut processor = TransactionProcessor::new(create_test_state()).unwrap();
+    processor.process_transaction(&transactions[0], &TransferRulesV1, &context).unwrap();
+    
+    // Refunding part of the collected fees breaks the hard balance conservation invariant
+    let mut refund = transactions[1].clone();
+    refund.amount = 50;
+    let trace = processor.explain(&refund, &FeeRefundRules, &context);
+    assert!(!trace.would_succeed);
+    assert!(trace.rule_applied.is_ok());
+    assert!(trace.invariants_checked.unwrap_err().to_string().contains("Hard invariant balance_conservation violated"));
+    assert_eq!(trace.explanation_steps.last().unwrap(), "Transaction TXN002 would be rejected at step 4");
+    
+    // Soft invariants only warn, as when the transaction is processed
+    processor
+        .apply_mutation("Freeze ACC002", |mut state| {
+            state.accounts.get_mut("ACC002").unwrap().status = AccountStatus::Frozen;
+            Ok(state)
+        })
+        .unwrap();
+    let trace = processor.explain(&transactions[2], &TransferRulesV1, &context);
+    assert!(trace.would_succeed);
+    assert!(trace.invariants_checked.is_ok());
+    assert!(trace.explanation_steps.contains(
+        &"Step 4 (invariant check): resulting state violates soft invariant no_frozen_accounts_with_pending_transactions".to_string()
+    ));
+    assert_eq!(trace.explanation_steps.last().unwrap(), "Transaction TXN003 would be accepted");
+}
+
+#[test]
+fn test_merge_diffs_touching_different_accounts() {
+    use dtre::{StateError, StateManager};
+    
+    let initial_state = create_test_state();
+    let manager = StateManager::new(initial_state.clone()).unwrap();
+    
+    // Partition one credits ACC001 and opens ACC004; partition two debits ACC003
+    let mut partition_a = initial_state.clone();
+    partition_a.accounts.get_mut("ACC001").unwrap().balance += 1_000;
+    partition_a.accounts.insert("ACC004".to_string(), BankAccount {
+        account_id: "ACC004".to_string(),
+        balance: 10,
+        currency: "USD".to_string(),
+        status: AccountStatus::Active,
+    });
+    let mut partition_b = initial_state.clone();
+    partition_b.accounts.get_mut("ACC003").unwrap().balance -= 2_000;
+    partition_b.accounts.get_mut("ACC003").unwrap().status = AccountStatus::Frozen;
+    
+    let diff_a = manager.calculate_diff(&initial_state, &partition_a);
+    let diff_b = manager.calculate_diff(&initial_state, &partition_b);
+    assert!(diff_a.conflicts_with(&diff_b).is_empty());
+    
+    let merged = dtre::StateDiff::merge(&diff_a, &diff_b).unwrap();
+    assert_eq!(merged.to_state.accounts["ACC001"].balance, 101_000);
+    assert_eq!(merged.to_state.accounts["ACC002"], initial_state.accounts["ACC002"]);
+    assert_eq!(merged.to_state.accounts["ACC003"].balance, 198_000);
+    assert_eq!(merged.to_state.accounts["ACC003"].status, AccountStatus::Frozen);
+    assert_eq!(merged.to_state.accounts["ACC004"].balance, 10);
+    assert_eq!(merged.from_hash, diff_a.from_hash);
+    assert_eq!(merged.to_hash, dtre::StateHasher::new().hash(&merged.to_state));
+    
+    // Merging is symmetric for disjoint diffs
+    let reversed = dtre::StateDiff::merge(&diff_b, &diff_a).unwrap();
+    assert_eq!(reversed.to_state, merged.to_state);
+    
+    // A diff of a different starting state cannot be merged
+    let other_start = manager.calculate_diff(&partition_a, &partition_b);
+    assert!(matches!(
+        dtre::StateDiff::merge(&diff_a, &other_start),
+        Err(StateError::Mismatch { .. })
+    ));
+}
+
+#[test]
+fn test_merge_diffs_both_collecting_fees_conflicts() {
+    use dtre::{StateError, StateManager};
+    
+    let initial_state = create_test_state();
+    let manager = StateManager::new(initial_state.clone()).unwrap();
+    
+    let mut partition_a = initial_state.clone();
+    partition_a.accounts.get_mut("ACC001").unwrap().balance -= 100;
+    partition_a.total_fees_collected += 100;
+    let mut partition_b = initial_state.clone();
+    partition_b.accounts.get_mut("ACC003").unwrap().balance -= 100;
+    partition_b.total_fees_collected += 100;
+    
+    let diff_a = manager.calculate_diff(&initial_state, &partition_a);
+    let diff_b = manager.calculate_diff(&initial_state, &partition_b);
+    assert_eq!(diff_a.conflicts_with(&diff_b), vec!["total_fees_collected"]);
+    
+    match dtre::StateDiff::merge(&diff_a, &diff_b) {
+        Err(StateError::MergeConflict { field_path }) => assert_eq!(field_path, "total_fees_collected"),
+        other => panic!("expected merge conflict, got {:?}", other.map(|diff| diff.to_hash)),
+    }
+    
+    // Replacing a whole account conflicts with changing one of its fields
+    let mut removed = initial_state.clone();
+    removed.accounts.remove("ACC001");
+    let diff_removed = manager.calculate_diff(&initial_state, &removed);
+    assert_eq!(diff_removed.conflicts_with(&diff_a), vec!["accounts.ACC001"]);
+}
+
+/// Banking state whose `Hash` is derived rather than written by hand
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, dtre::DeterministicHash)]
+pub struct DerivedBankingState {
+    pub balances: HashMap<String, u64>,
+    pub transaction_ids: Vec<String>,
+    pub total_fees_collected: u64,
+}
+
+impl State for DerivedBankingState {
+    fn validate(&self) -> Result<(), ValidationError> {
+        Ok(())
+    }
+    
+    fn iteration_order() -> dtre::IterationStrategy {
+        dtre::IterationStrategy::Sorted
+    }
+}
+
+/// The same state with a hand-rolled sorted `Hash`, as `BankingState` does it
+#[derive(Debug, Clone)]
+pub struct HandRolledBankingState {
+    pub balances: HashMap<String, u64>,
+    pub transaction_ids: Vec<String>,
+    pub total_fees_collected: u64,
+}
+
+impl Hash for HandRolledBankingState {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let mut sorted_balances: Vec<_> = self.balances.iter().collect();
+        sorted_balances.sort_by_key(|(id, _)| *id);
+        
+        for (id, balance) in sorted_balances {
+            id.hash(state);
+            balance.hash(state);
+        }
+        
+        self.transaction_ids.hash(state);
+        self.total_fees_collected.hash(state);
+    }
+}
+
+fn std_hash<T: Hash>(value: &T) -> u64 {
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn derived_state(account_ids: &[&str]) -> DerivedBankingState {
+    DerivedBankingState {
+        balances: account_ids
+            .iter()
+            .enumerate()
+            .map(|(i, id)| (id.to_string(), 1000 * (i as u64 + 1)))
+            .collect(),
+        transaction_ids: vec!["TX001".to_string(), "TX002".to_string()],
+        total_fees_collected: 25,
+    }
+}
+
+#[test]
+fn test_derived_hash_matches_hand_rolled() {
+    let derived = derived_state(&["ACC003", "ACC001", "ACC002"]);
+    let hand_rolled = HandRolledBankingState {
+        balances: derived.balances.clone(),
+        transaction_ids: derived.transaction_ids.clone(),
+        total_fees_collected: derived.total_fees_collected,
+    };
+    
+    assert_eq!(std_hash(&derived), std_hash(&hand_rolled));
+    
+    let mut changed = derived.clone();
+    changed.balances.insert("ACC001".to_string(), 1);
+    assert_ne!(std_hash(&changed), std_hash(&derived));
+}
+
+#[test]
+fn test_sorted_iteration_hash_ignores_insertion_order() {
+    let mut forward = derived_state(&[]);
+    let mut backward = derived_state(&[]);
+    for i in 0..64 {
+        forward.balances.insert(format!("ACC{:03}", i), i);
+    }
+    for i in (0..64).rev() {
+        backward.balances.insert(format!("ACC{:03}", i), i);
+    }
+    
+    let hasher = dtre::StateHasher::new();
+    assert_eq!(std_hash(&forward), std_hash(&backward));
+    assert_eq!(hasher.hash(&forward), hasher.hash(&backward));
+    
+    let sorted: Vec<&String> = dtre::iter_sorted(&forward.balances).map(|(id, _)| id).collect();
+    assert_eq!(sorted.first().map(|id| id.as_str()), Some("ACC000"));
+    assert_eq!(sorted.last().map(|id| id.as_str()), Some("ACC063"));
+}
+
+/// Domain events derived from changes to account balances
+#[derive(Debug, Clone, PartialEq)]
+pub enum BankingEvent {
+    BalanceDebited { account_id: String, amount: i64 },
+    BalanceCredited { account_id: String, amount: i64 },
+}
+
+fn balance_events(before: &BankingState, after: &BankingState) -> Vec<BankingEvent> {
+    let mut account_ids: Vec<&String> = after.accounts.keys().collect();
+    account_ids.sort();
+    
+    account_ids
+        .into_iter()
+        .filter_map(|account_id| {
+            let old_balance = before.accounts.get(account_id).map_or(0, |a| a.balance);
+            let change = after.accounts[account_id].balance - old_balance;
+            match change {
+                0 => None,
+                c if c < 0 => Some(BankingEvent::BalanceDebited { account_id: account_id.clone(), amount: -c }),
+                c => Some(BankingEvent::BalanceCredited { account_id: account_id.clone(), amount: c }),
+            }
+        })
+        .collect()
+}
+
+#[test]
+fn test_event_sourcing_adapter_single_transfer() {
+    use dtre::{EventSourcingAdapter, TransactionProcessor};
+    
+    let transactions = create_test_transactions();
+    let mut processor = TransactionProcessor::new(create_test_state()).unwrap();
+    let transitions = processor
+        .process_transactions(&transactions[..1], &TransferRulesV1, &create_test_context())
+        .unwrap();
+    
+    let adapter = EventSourcingAdapter::new(balance_events);
+    let events = adapter.extract_events(&transitions, processor.execution_trace());
+    
+    assert_eq!(events.len(), 2);
+    assert!(events.iter().all(|e| e.transaction_id == "TXN001"));
+    assert!(events.iter().all(|e| e.timestamp == transactions[0].timestamp));
+    assert_eq!(
+        events[0].event,
+        BankingEvent::BalanceDebited { account_id: "ACC001".to_string(), amount: 10_100 }
+    );
+    assert_eq!(
+        events[1].event,
+        BankingEvent::BalanceCredited { account_id: "ACC002".to_string(), amount: 10_000 }
+    );
+}
+
+#[test]
+fn test_event_stream_matches_extracted_events() {
+    use dtre::{EventSourcingAdapter, TransactionProcessor};
+    
+    let transactions = create_test_transactions();
+    let mut processor = TransactionProcessor::new(create_test_state()).unwrap();
+    let transitions = processor
+        .process_transactions(&transactions, &TransferRulesV1, &create_test_context())
+        .unwrap();
+    
+    let adapter = EventSourcingAdapter::new(balance_events);
+    let extracted = adapter.extract_events(&transitions, processor.execution_trace());
+    let streamed: Vec<_> = adapter.event_stream(&transitions, processor.execution_trace()).collect();
+    
+    assert_eq!(streamed, extracted);
+    assert_eq!(streamed.len(), 2 * transactions.len());
+    
+    let ids: Vec<&str> = streamed.iter().map(|e| e.transaction_id.as_str()).collect();
+    assert_eq!(ids, vec!["TXN001", "TXN001", "TXN002", "TXN002", "TXN003", "TXN003"]);
+}
+
+#[test]
+fn test_impact_matrix_across_three_versions() {
+    let mut initial_state = create_test_state();
+    initial_state.accounts.get_mut("ACC003").unwrap().balance = 5_000_000;
+    
+    // The final transfer is over the v2.0.0 limit, so only v2.0.0 rejects it
+    let mut transactions = create_test_transactions();
+    transactions.push(TransferTransaction {
+        id: "TXN004".to_string(),
+        timestamp: transactions[2].timestamp + chrono::Duration::seconds(60),
+        from_account: "ACC003".to_string(),
+        to_account: "ACC002".to_string(),
+        amount: 1_500_000,
+        currency: "USD".to_string(),
+        description: "Property purchase".to_string(),
+    });
+    
+    let engine = ReplayEngineBuilder::new()
+        .with_initial_state(initial_state)
+        .with_rule_set(TransferRulesV1)
+        .with_context(create_test_context())
+        .build()
+        .unwrap();
+    let matrix = engine
+        .compute_impact_matrix(&transactions, [&TransferRulesV1_1, &TransferRulesV2])
+        .unwrap();
+    
+    assert_eq!(matrix.size(), 3);
+    assert_eq!(matrix.versions(), &[Version::new(1, 0, 0), Version::new(1, 1, 0), Version::new(2, 0, 0)]);
+    
+    // Every version agrees with itself
+    for i in 0..matrix.size() {
+        assert!(matrix.pairwise_comparison(i, i).is_safe_migration());
+        assert_eq!(matrix.breaking_changes(i, i), 0);
+    }
+    
+    // v1.0 -> v1.1 only changes fees, v1.0 -> v2.0 also rejects a transfer
+    let to_v1_1 = matrix.pairwise_comparison(0, 1);
+    let to_v2 = matrix.pairwise_comparison(0, 2);
+    assert_eq!(to_v1_1.comparison_version, Version::new(1, 1, 0));
+    assert!(!to_v1_1.is_safe_migration());
+    assert_eq!(matrix.breaking_changes(0, 1), 0);
+    assert_eq!(matrix.breaking_changes(0, 2), 1);
+    assert!(matrix.breaking_changes(0, 1) < matrix.breaking_changes(0, 2));
+    assert!(to_v2
+        .differences
+        .iter()
+        .any(|d| d.transaction_id == "TXN004" && d.description.contains("rejected by 2.0.0")));
+    assert_eq!(to_v2.comparison_result.execution_trace.transactions_processed, 3);
+    
+    let path = matrix.safest_upgrade_path();
+    assert_eq!(path.first(), Some(&Version::new(1, 0, 0)));
+    assert_eq!(path.last(), Some(&Version::new(2, 0, 0)));
+    
+    let table = matrix.to_markdown_table();
+    assert_eq!(table.lines().count(), 5);
+    assert!(table.starts_with("| from \\ to | 1.0.0 | 1.1.0 | 2.0.0 |"));
+    assert!(table.contains("| 1.0.0 | identical | 4 differences, 0 breaking | 4 differences, 1 breaking |"));
+}
+
+#[path = "fixtures/bank_transfer_fixture.rs"]
+mod bank_transfer_fixture;
+
+#[test]
+fn test_generated_fixture_is_up_to_date() {
+    let engine = ReplayEngineBuilder::new()
+        .with_initial_state(create_test_state())
+        .with_rule_set(TransferRulesV1)
+        .with_context(create_test_context())
+        .build()
+        .unwrap();
+    let transactions = create_test_transactions();
+    let result = engine.replay(&transactions).unwrap();
+    
+    let mut fixture = Vec::new();
+    let options = FixtureGenerationOptions {
+        include_trace: true,
+        include_performance: false,
+        type_paths: Some(FixtureTypePaths {
+            state: "crate::BankingState",
+            transaction: "crate::TransferTransaction",
+            rule_set: "crate::TransferRulesV1",
+        }),
+    };
+    engine.generate_test_fixtures_with_options(&transactions, &result, &mut fixture, options).unwrap();
+    let fixture = String::from_utf8(fixture).unwrap();
+    if std::env::var_os("DTRE_REGENERATE_FIXTURES").is_some() {
+        std::fs::write(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/bank_transfer_fixture.rs"), &fixture).unwrap();
+    }
+    
+    // The checked-in fixture is compiled into this test binary, so its tests run unmodified
+    assert_eq!(fixture, include_str!("fixtures/bank_transfer_fixture.rs"));
+    assert_eq!(bank_transfer_fixture::EXPECTED_FINAL_HASH, result.final_hash.to_string());
+    assert!(fixture.contains("type FixtureState = crate::BankingState;"));
+}
+
+#[test]
+fn test_generated_fixture_keeps_engine_settings() {
+    let engine = ReplayEngineBuilder::new()
+        .with_initial_state(create_test_state())
+        .with_rule_set(TransferRulesV1)
+        .with_context(create_test_context())
+        .with_checkpoint_interval(2)
+        .with_deduplication(true)
+        .with_cost_budget(dtre::ReplayCostBudget { max_cpu_ms: Some(60_000), max_memory_bytes: None })
+        .build()
+        .unwrap();
+    let transactions = create_test_transactions();
+    let result = engine.replay(&transactions).unwrap();
+    
+    let mut fixture = Vec::new();
+    engine.generate_test_fixtures(&transactions, &result, &mut fixture).unwrap();
+    let fixture = String::from_utf8(fixture).unwrap();
+    assert!(fixture.contains("use dtre::{parse_fixture_json, ExecutionContext, ReplayCostBudget, ReplayEngine, ReplayEngineBuilder,"));
+    assert!(fixture.contains("        .with_checkpoint_interval(2)\n"));
+    assert!(fixture.contains("        .with_deduplication(true)\n"));
+    assert!(fixture.contains("        .with_cost_budget(ReplayCostBudget { max_cpu_ms: Some(60000), max_memory_bytes: None })\n"));
+    assert!(!fixture.contains("with_max_state_size_bytes"));
+}
+
+#[test]
+fn test_shadow_replay_reports_fee_discrepancies() {
+    let discrepancies = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let sink = discrepancies.clone();
+    let engine = ReplayEngineBuilder::new()
+        .with_initial_state(create_test_state())
+        .with_rule_set(TransferRulesV1)
+        .with_context(create_test_context())
+        .build()
+        .unwrap()
+        .with_shadow(TransferRulesV1_1)
+        .with_discrepancy_sink(move |discrepancy: ShadowDiscrepancy<BankingState>| {
+            sink.lock().unwrap().push(discrepancy);
+        });
+    
+    // At 10,000 the 1% fee of v1.1.0 equals the flat fee of v1.0.0
+    let mut transactions = create_test_transactions();
+    transactions[0].amount = 5_000;
+    let result = engine.replay(&transactions).unwrap();
+    
+    // The primary result is the one v1.0.0 alone produces
+    let primary = ReplayEngineBuilder::new()
+        .with_initial_state(create_test_state())
+        .with_rule_set(TransferRulesV1)
+        .with_context(create_test_context())
+        .build()
+        .unwrap()
+        .replay(&transactions)
+        .unwrap();
+    assert_eq!(result.final_hash, primary.final_hash);
+    
+    let discrepancies = discrepancies.lock().unwrap();
+    assert_eq!(discrepancies.len(), 3);
+    assert_eq!(engine.discrepancy_rate(), 1.0);
+    for (discrepancy, transaction) in discrepancies.iter().zip(&transactions) {
+        assert_eq!(discrepancy.transaction_id, transaction.id);
+        assert_ne!(Some(discrepancy.primary_hash), discrepancy.shadow_hash);
+        let primary_fee = discrepancy.primary_state.transaction_history.last().unwrap().fee;
+        let shadow_fee = discrepancy.shadow_state.as_ref().unwrap().transaction_history.last().unwrap().fee;
+        assert_ne!(primary_fee, shadow_fee);
+    }
+}
+
+#[test]
+fn test_validate_upgrade_path_across_three_versions() {
+    let mut registry = RuleSetRegistry::new();
+    let versions = [
+        (Version::new(1, 0, 0), Box::new(TransferRulesV1) as Box<dyn RuleSet<BankingState, TransferTransaction>>),
+        (Version::new(1, 1, 0), Box::new(TransferRulesV1_1)),
+        (Version::new(2, 0, 0), Box::new(TransferRulesV2)),
+    ];
+    for (version, rules) in versions {
+        let metadata = RuleSetMetadata::new(format!("transfers {}", version), String::new());
+        registry.register(VersionedRuleSet::new(version, rules, metadata)).unwrap();
+    }
+    
+    let mut initial_state = create_test_state();
+    initial_state.accounts.get_mut("ACC003").unwrap().balance = 5_000_000;
+    let mut transactions = create_test_transactions();
+    transactions.push(TransferTransaction {
+        id: "TXN004".to_string(),
+        timestamp: transactions[2].timestamp + chrono::Duration::seconds(60),
+        from_account: "ACC003".to_string(),
+        to_account: "ACC002".to_string(),
+        amount: 1_500_000,
+        currency: "USD".to_string(),
+        description: "Property purchase".to_string(),
+    });
+    
+    let path = [Version::new(1, 0, 0), Version::new(1, 1, 0), Version::new(2, 0, 0)];
+    let validation = registry.validate_upgrade_path(&path, &transactions, initial_state, &create_test_context());
+    
+    // No hop produces an invalid state, but v2.0.0 rejects the transfer over its limit
+    assert!(validation.all_valid);
+    assert_eq!(validation.per_hop_results.len(), 2);
+    assert_eq!(validation.first_breaking_hop, Some(1));
+    
+    let to_v1_1 = &validation.per_hop_results[0];
+    assert_eq!(to_v1_1.to_version, Version::new(1, 1, 0));
+    assert!(to_v1_1.invariants_held);
+    assert!(!to_v1_1.is_breaking());
+    
+    let to_v2 = &validation.per_hop_results[1];
+    assert!(to_v2.invariants_held);
+    assert!(to_v2.behavior_changed);
+    assert_eq!(to_v2.newly_rejected, vec!["TXN004".to_string()]);
+    assert!(to_v2.state_size_change < 0);
+}
+
+#[test]
+fn test_migration_contract_catches_v2_limit() {
+    use dtre::MigrationContract;
+    
+    let mut registry = RuleSetRegistry::new();
+    let v1 = Version::new(1, 0, 0);
+    let v2 = Version::new(2, 0, 0);
+    registry.register(VersionedRuleSet::new(
+        v1.clone(),
+        Box::new(TransferRulesV1),
+        RuleSetMetadata::new("transfers v1".to_string(), String::new()),
+    )).unwrap();
+    registry.register(VersionedRuleSet::new(
+        v2.clone(),
+        Box::new(TransferRulesV2),
+        RuleSetMetadata::new("transfers v2".to_string(), String::new()),
+    )).unwrap();
+    
+    let mut contract = MigrationContract::new();
+    contract
+        .require_processable("all transactions processed in v1 can also be processed in v2")
+        .add_invariant("balances stay non-negative", |state: &BankingState| {
+            state.accounts.values().all(|account| account.balance >= 0)
+        });
+    registry.set_migration_contract(&v1, &v2, contract);
+    
+    let mut initial_state = create_test_state();
+    initial_state.accounts.get_mut("ACC003").unwrap().balance = 5_000_000;
+    let mut transactions = create_test_transactions();
+    transactions.push(TransferTransaction {
+        id: "TXN004".to_string(),
+        timestamp: transactions[2].timestamp + chrono::Duration::seconds(60),
+        from_account: "ACC003".to_string(),
+        to_account: "ACC002".to_string(),
+        amount: 1_500_000,
+        currency: "USD".to_string(),
+        description: "Property purchase".to_string(),
+    });
+    
+    let result = registry.verify_migration_contract(&v1, &v2, &transactions, initial_state, &create_test_context());
+    
+    // Only the transfer over the v2.0.0 limit breaks the contract
+    assert!(!result.all_passed);
+    assert_eq!(result.violations.len(), 1);
+    assert_eq!(result.violations[0].transaction_id, "TXN004");
+    assert_eq!(result.violations[0].invariant, "all transactions processed in v1 can also be processed in v2");
+}
+
+/// Moves the amount without any checks and forgets the receiver's currency, which makes the state invalid
+struct CarelessTransferRules;
+
+impl RuleSet<BankingState, TransferTransaction> for CarelessTransferRules {
+    fn version(&self) -> Version {
+        Version::new(2, 0, 0)
+    }
+    
+    fn apply(
+        &self,
+        state: &BankingState,
+        transaction: &TransferTransaction,
+        _context: &ExecutionContext,
+    ) -> Result<BankingState, ProcessingError> {
+        let mut new_state = state.clone();
+        new_state.accounts.get_mut(&transaction.from_account).unwrap().balance -= transaction.amount;
+        let to = new_state.accounts.get_mut(&transaction.to_account).unwrap();
+        to.balance += transaction.amount;
+        to.currency.clear();
+        Ok(new_state)
+    }
+}
+
+#[test]
+fn test_migration_contract_reports_invalid_states() {
+    use dtre::MigrationContract;
+    
+    let mut registry = RuleSetRegistry::new();
+    let v1 = Version::new(1, 0, 0);
+    let v2 = Version::new(2, 0, 0);
+    registry.register(VersionedRuleSet::new(
+        v1.clone(),
+        Box::new(TransferRulesV1),
+        RuleSetMetadata::new("transfers v1".to_string(), String::new()),
+    )).unwrap();
+    registry.register(VersionedRuleSet::new(
+        v2.clone(),
+        Box::new(CarelessTransferRules),
+        RuleSetMetadata::new("careless transfers".to_string(), String::new()),
+    )).unwrap();
+    
+    let mut contract = MigrationContract::new();
+    contract.add_invariant("balances stay non-negative", |state: &BankingState| {
+        state.accounts.values().all(|account| account.balance >= 0)
+    });
+    registry.set_migration_contract(&v1, &v2, contract);
+    
+    // v1.0.0 rejects the overdraft, v2.0.0 produces an invalid state with a negative balance
+    let mut overdraft = create_test_transactions()[0].clone();
+    overdraft.amount = 1_000_000;
+    let result = registry.verify_migration_contract(&v1, &v2, &[overdraft], create_test_state(), &create_test_context());
+    
+    assert!(!result.all_passed);
+    let invariants: Vec<&str> = result.violations.iter().map(|violation| violation.invariant.as_str()).collect();
+    assert_eq!(invariants, ["States produced by rule set version 2.0.0 are valid", "balances stay non-negative"]);
+    assert!(result.violations.iter().all(|violation| violation.transaction_id == "TXN001"));
+}
+
+/// `TransferRulesV1` declaring its account checks as a contract
+struct ContractedTransferRules;
+
+impl RuleSet<BankingState, TransferTransaction> for ContractedTransferRules {
+    fn version(&self) -> Version {
+        TransferRulesV1.version()
+    }
+    
+    fn pre_conditions(&self) -> Vec<dtre::RuleCondition<BankingState, TransferTransaction>> {
+        vec![dtre::RuleCondition::new(
+            "sender account must be active",
+            |state: &BankingState, transaction: &TransferTransaction| {
+                state.accounts
+                    .get(&transaction.from_account)
+                    .is_some_and(|account| account.status == AccountStatus::Active)
+            },
+        )]
+    }
+    
+    fn post_conditions(&self) -> Vec<dtre::RuleCondition<BankingState, TransferTransaction>> {
+        vec![dtre::RuleCondition::new(
+            "sender balance stays non-negative",
+            |state: &BankingState, transaction: &TransferTransaction| state.accounts[&transaction.from_account].balance >= 0,
+        )]
+    }
+    
+    fn apply(
+        &self,
+        state: &BankingState,
+        transaction: &TransferTransaction,
+        context: &ExecutionContext,
+    ) -> Result<BankingState, ProcessingError> {
+        TransferRulesV1.apply(state, transaction, context)
+    }
+}
+
+#[test]
+fn test_rule_contract_pre_condition() {
+    let rules = ContractedTransferRules;
+    let documentation = rules.documented_contract();
+    assert_eq!(documentation.pre_conditions, vec!["sender account must be active".to_string()]);
+    assert_eq!(
+        documentation.to_string(),
+        "Rule set 1.0.0\nPre-conditions:\n  - sender account must be active\nPost-conditions:\n  - sender balance stays non-negative\n"
+    );
+    assert!(TransferRulesV1.documented_contract().is_empty());
+    
+    let transactions = create_test_transactions();
+    let mut frozen_sender = create_test_state();
+    frozen_sender.accounts.get_mut("ACC001").unwrap().status = AccountStatus::Frozen;
+    let sender_active = &rules.pre_conditions()[0];
+    assert!(sender_active.holds(&create_test_state(), &transactions[0]));
+    assert!(!sender_active.holds(&frozen_sender, &transactions[0]));
+}
+
+#[cfg(all(feature = "debug-contracts", debug_assertions))]
+#[test]
+fn test_rule_contract_violation_stops_processing() {
+    use dtre::{ConditionType, ContractViolationError, TransactionProcessor};
+    
+    let transactions = create_test_transactions();
+    let mut initial_state = create_test_state();
+    initial_state.accounts.get_mut("ACC001").unwrap().status = AccountStatus::Frozen;
+    let mut processor = TransactionProcessor::new(initial_state.clone()).unwrap();
+    
+    let error = processor
+        .process_transactions(&transactions[..1], &ContractedTransferRules, &create_test_context())
+        .unwrap_err();
+    match error {
+        ProcessingError::ContractViolation(violation) => assert_eq!(violation, ContractViolationError {
+            condition_type: ConditionType::Pre,
+            description: "sender account must be active".to_string(),
+            transaction_id: "TXN001".to_string(),
+        }),
+        other => panic!("expected a contract violation, got {:?}", other),
+    }
+    assert_eq!(processor.current_state(), &initial_state);
+    
+    // Transfers from active accounts meet the contract
+    let mut processor = TransactionProcessor::new(create_test_state()).unwrap();
+    processor
+        .process_transactions(&transactions, &ContractedTransferRules, &create_test_context())
+        .unwrap();
+}
+
+#[test]
+fn test_transfer_field_diff() {
+    use dtre::{ChangeKind, StateManager};
+    
+    let initial_state = create_test_state();
+    let transactions = create_test_transactions();
+    let context = create_test_context();
+    
+    let mut manager = StateManager::new(initial_state.clone()).unwrap();
+    manager.apply_transaction(&transactions[0], &TransferRulesV1, &context).unwrap();
+    let diff = manager.calculate_diff(&initial_state, manager.current_state());
+    
+    // Both balances and the fee total are modified, and the transfer is recorded in the history
+    let changes = diff.fields_changed();
+    let paths: Vec<&str> = changes.iter().map(|change| change.path.as_str()).collect();
+    assert_eq!(paths, vec![
+        "accounts.ACC001.balance",
+        "accounts.ACC002.balance",
+        "total_fees_collected",
+        "transaction_history.0",
+    ]);
+    assert_eq!(changes[0].kind, ChangeKind::Modified {
+        old_value: serde_json::json!(100_000),
+        new_value: serde_json::json!(89_900),
+    });
+    assert_eq!(changes[1].kind, ChangeKind::Modified {
+        old_value: serde_json::json!(50_000),
+        new_value: serde_json::json!(60_000),
+    });
+    assert_eq!(changes[2].kind, ChangeKind::Modified {
+        old_value: serde_json::json!(0),
+        new_value: serde_json::json!(100),
+    });
+    assert!(matches!(changes[3].kind, ChangeKind::Added { .. }));
+    
+    assert!(diff.has_changed_fields(&["accounts.ACC001"]));
+    assert!(diff.has_changed_fields(&["accounts.ACC003", "total_fees_collected"]));
+    assert!(!diff.has_changed_fields(&["accounts.ACC003"]));
+    assert!(!diff.has_changed_fields(&["accounts.ACC00"]));
+}
+
+#[test]
+fn test_tiered_fee_clauses_recorded() {
+    use dtre::{InstrumentedRuleSet, TransactionProcessor};
+    
+    let base_time = DateTime::parse_from_rfc3339("2024-01-01T00:00:00Z")
+        .unwrap()
+        .with_timezone(&Utc);
+    let transfer = |id: &str, amount: i64| TransferTransaction {
+        id: id.to_string(),
+        timestamp: base_time,
+        from_account: "ACC003".to_string(),
+        to_account: "ACC001".to_string(),
+        amount,
+        currency: "USD".to_string(),
+        description: "Tiered fee".to_string(),
+    };
+    let transactions = vec![transfer("SMALL", 5_000), transfer("MEDIUM", 50_000), transfer("LARGE", 100_000)];
+    
+    let rule_set = InstrumentedRuleSet::new(TransferRulesV2);
+    let mut processor = TransactionProcessor::new(create_test_state()).unwrap();
+    processor.process_transactions(&transactions, &rule_set, &create_test_context()).unwrap();
+    
+    let audits: Vec<_> = processor.execution_trace().rule_applications.iter().map(|a| &a.audit).collect();
+    assert_eq!(audits[0].rule_clauses_fired, vec!["fee.flat_path"]);
+    assert_eq!(audits[0].rule_clauses_evaluated, vec!["fee.flat_path"]);
+    assert_eq!(audits[1].rule_clauses_fired, vec!["fee.percentage_path"]);
+    assert_eq!(audits[1].rule_clauses_evaluated, vec!["fee.flat_path", "fee.percentage_path"]);
+    assert_eq!(audits[2].rule_clauses_fired, vec!["fee.reduced_percentage_path"]);
+    assert_eq!(
+        audits[2].rule_clauses_evaluated,
+        vec!["fee.flat_path", "fee.percentage_path", "fee.reduced_percentage_path"]
+    );
+    
+    // Without the wrapper the same rule set records nothing
+    let (_, audit) = TransferRulesV2
+        .apply_with_audit(&create_test_state(), &transactions[0], &create_test_context())
+        .unwrap();
+    assert!(audit.rule_clauses_fired.is_empty());
+}
+
+#[test]
+fn test_balance_total_drops_by_fees_at_each_transition() {
+    use dtre::{StateAggregator, TransactionProcessor};
+    
+    let total_balance = |state: &BankingState| StateAggregator::new(state).sum("accounts.*.balance");
+    let initial_state = create_test_state();
+    let initial_total = initial_state.aggregate(total_balance);
+    assert_eq!(initial_total, 350_000);
+    
+    let mut processor = TransactionProcessor::new(initial_state).unwrap();
+    let transitions = processor
+        .process_transactions(&create_test_transactions(), &TransferRulesV1_1, &create_test_context())
+        .unwrap();
+    let totals = processor.execution_trace().aggregate_over_transitions(&transitions, total_balance);
+    assert_eq!(totals.len(), 3);
+    
+    // Money only leaves the accounts as fees, so each step drops by exactly that step's fee
+    let mut previous_total = initial_total;
+    for (transition, total) in transitions.iter().zip(&totals) {
+        let fee = transition.to_state.total_fees_collected - transition.from_state.total_fees_collected;
+        assert!(fee > 0);
+        assert_eq!(previous_total - total, fee);
+        previous_total = *total;
+    }
+    assert_eq!(initial_total - totals[2], processor.current_state().total_fees_collected);
+    
+    let aggregator = StateAggregator::new(processor.current_state());
+    assert_eq!(aggregator.count("accounts.*"), 3);
+    assert_eq!(aggregator.count("transaction_history.*"), 3);
+    assert_eq!(aggregator.max("transaction_history.*.amount"), Some(serde_json::json!(50_000)));
+    
+    let engine = ReplayEngineBuilder::new()
+        .with_initial_state(create_test_state())
+        .with_rule_set(TransferRulesV1_1)
+        .with_context(create_test_context())
+        .build()
+        .unwrap();
+    let result = engine.replay(&create_test_transactions()).unwrap();
+    assert_eq!(result.aggregate(total_balance), totals[2]);
+}
+
+#[test]
+fn test_history_order_does_not_affect_hash() {
+    use dtre::{NormalizedState, StateHasher, TransactionProcessor};
+    
+    let mut processor = TransactionProcessor::new(create_test_state()).unwrap();
+    processor
+        .process_transactions(&create_test_transactions(), &TransferRulesV1, &create_test_context())
+        .unwrap();
+    let recorded = processor.current_state().clone();
+    let mut reordered = recorded.clone();
+    reordered.transaction_history.reverse();
+    assert_ne!(recorded.transaction_history, reordered.transaction_history);
+    
+    let hasher = StateHasher::new();
+    assert_eq!(hasher.hash(&recorded), hasher.hash(&reordered));
+    assert_eq!(hasher.hash(&reordered), processor.current_hash());
+    
+    let normalized = NormalizedState::new(reordered);
+    assert_eq!(normalized.transaction_history, recorded.transaction_history);
+    assert_eq!(hasher.hash_normalized(&normalized), processor.current_hash());
+    
+    // A transaction applied to the reordered state stores its result in normalized form
+    let late = TransferTransaction {
+        id: "TXN000".to_string(),
+        timestamp: DateTime::parse_from_rfc3339("2023-12-31T00:00:00Z").unwrap().with_timezone(&Utc),
+        from_account: "ACC001".to_string(),
+        to_account: "ACC003".to_string(),
+        amount: 1_000,
+        currency: "USD".to_string(),
+        description: "Backdated".to_string(),
+    };
+    processor.process_transaction(&late, &TransferRulesV1, &create_test_context()).unwrap();
+    let ids: Vec<&str> = processor.current_state().transaction_history.iter().map(|r| r.transaction_id.as_str()).collect();
+    assert_eq!(ids, vec!["TXN000", "TXN001", "TXN002", "TXN003"]);
+}
+
+/// Treats transfers described as heartbeats as keep-alives that leave the state untouched
+struct HeartbeatAwareRules;
+
+impl RuleSet<BankingState, TransferTransaction> for HeartbeatAwareRules {
+    fn version(&self) -> Version {
+        Version::new(1, 0, 0)
+    }
+    
+    fn apply(
+        &self,
+        state: &BankingState,
+        transaction: &TransferTransaction,
+        context: &ExecutionContext,
+    ) -> Result<BankingState, ProcessingError> {
+        if transaction.description == "Heartbeat" {
+            return Ok(state.clone());
+        }
+        TransferRulesV1.apply(state, transaction, context)
+    }
+}
+
+#[test]
+fn test_compaction_removes_heartbeats() {
+    use dtre::{StateHasher, TransactionLog, TransactionProcessor};
+    
+    let base_time = DateTime::parse_from_rfc3339("2024-01-01T00:00:00Z")
+        .unwrap()
+        .with_timezone(&Utc);
+    let transactions: Vec<TransferTransaction> = (0..15)
+        .map(|i| TransferTransaction {
+            id: format!("TXN{:03}", i),
+            timestamp: base_time + chrono::Duration::seconds(i * 60),
+            from_account: "ACC001".to_string(),
+            to_account: "ACC002".to_string(),
+            amount: 1_000,
+            currency: "USD".to_string(),
+            // Every third transaction is a heartbeat
+            description: if i % 3 == 2 { "Heartbeat" } else { "Transfer" }.to_string(),
+        })
+        .collect();
+    
+    let initial_state = create_test_state();
+    let context = create_test_context();
+    let compacted = TransactionLog::compact(&transactions, &HeartbeatAwareRules, &context, &initial_state).unwrap();
+    
+    assert_eq!(compacted.transactions.len(), 10);
+    assert_eq!(compacted.removed_count, 5);
+    assert!(compacted.transactions.iter().all(|t| t.description == "Transfer"));
+    
+    let mut processor = TransactionProcessor::new(initial_state).unwrap();
+    processor.process_transactions(&transactions, &HeartbeatAwareRules, &context).unwrap();
+    assert_eq!(compacted.final_hash, processor.current_hash());
+    assert_eq!(StateHasher::new().hash(processor.current_state()), compacted.final_hash);
+}
+
+/// Moves balances without recording history or fees, and declares that it only writes balances
+struct BalanceOnlyRules;
+
+impl RuleSet<BankingState, TransferTransaction> for BalanceOnlyRules {
+    fn version(&self) -> Version {
+        Version::new(1, 0, 0)
+    }
+    
+    fn affects_fields(&self) -> Option<Vec<String>> {
+        Some(vec!["accounts.*.balance".to_string()])
+    }
+    
+    fn apply(
+        &self,
+        state: &BankingState,
+        transaction: &TransferTransaction,
+        _context: &ExecutionContext,
+    ) -> Result<BankingState, ProcessingError> {
+        let mut new_state = state.clone();
+        new_state.accounts.get_mut(&transaction.from_account).unwrap().balance -= transaction.amount;
+        new_state.accounts.get_mut(&transaction.to_account).unwrap().balance += transaction.amount;
+        Ok(new_state)
+    }
+}
+
+#[test]
+fn test_affects_fields_limits_subtree_rehashing() {
+    use dtre::TransactionProcessor;
+    
+    let transactions = create_test_transactions();
+    let context = create_test_context();
+    let mut processor = TransactionProcessor::new(create_test_state()).unwrap().with_subtree_hashing();
+    let initial = processor.subtree_hashes().unwrap().clone();
+    assert_eq!(initial.last_recomputed(), ["accounts", "total_fees_collected", "transaction_history"]);
+    
+    processor.process_transaction(&transactions[0], &BalanceOnlyRules, &context).unwrap();
+    let after_balance_move = processor.subtree_hashes().unwrap().clone();
+    assert_eq!(after_balance_move.last_recomputed(), ["accounts"]);
+    assert_ne!(after_balance_move.get("accounts"), initial.get("accounts"));
+    assert_eq!(after_balance_move.get("transaction_history"), initial.get("transaction_history"));
+    
+    // Without a declaration every subtree is rehashed and matches a fresh computation
+    processor.process_transaction(&transactions[1], &TransferRulesV1, &context).unwrap();
+    let subtrees = processor.subtree_hashes().unwrap();
+    assert_eq!(subtrees.last_recomputed(), ["accounts", "total_fees_collected", "transaction_history"]);
+    assert_eq!(*subtrees, dtre::SubtreeHashes::new(processor.current_state()));
+}
+
+#[test]
+fn test_reproducibility_bundle_round_trip() {
+    use dtre::ReproducibilityBundle;
+    
+    let transactions = create_test_transactions();
+    let engine = ReplayEngineBuilder::new()
+        .with_initial_state(create_test_state())
+        .with_rule_set(TransferRulesV1)
+        .with_context(create_test_context())
+        .build()
+        .unwrap();
+    let bundle = engine.export_reproducibility_bundle(&transactions).unwrap();
+    assert_eq!(bundle.expected_final_hash(), engine.replay(&transactions).unwrap().final_hash);
+    
+    // Exporting the same replay twice produces the same archive
+    let again = engine.export_reproducibility_bundle(&transactions).unwrap();
+    assert_eq!(bundle.as_bytes(), again.as_bytes());
+    
+    let path = std::env::temp_dir().join(format!("dtre-reproducibility-bundle-{}.zip", std::process::id()));
+    bundle.write_to(&path).unwrap();
+    let verified = ReproducibilityBundle::verify::<BankingState, TransferTransaction, _>(&path, &TransferRulesV1);
+    let other_version = ReproducibilityBundle::verify::<BankingState, TransferTransaction, _>(&path, &TransferRulesV1_1);
+    std::fs::remove_file(&path).unwrap();
+    
+    assert!(verified.unwrap());
+    assert!(!other_version.unwrap());
+    
+    let missing = ReproducibilityBundle::verify::<BankingState, TransferTransaction, _>(&path, &TransferRulesV1);
+    assert!(matches!(missing, Err(ProcessingError::TransactionFailed { transaction_id, .. }) if transaction_id == "bundle"));
+}
+
+#[test]
+fn test_replay_with_manual_balance_adjustment() {
+    use dtre::{ReplayItem, StateHasher};
+    
+    let transactions = create_test_transactions();
+    let context = create_test_context();
+    let engine = ReplayEngineBuilder::new()
+        .with_initial_state(create_test_state())
+        .with_rule_set(TransferRulesV1)
+        .with_context(context.clone())
+        .build()
+        .unwrap();
+    
+    let double_acc002 = |mut state: BankingState| {
+        state.accounts.get_mut("ACC002").unwrap().balance *= 2;
+        Ok(state)
+    };
+    let result = engine
+        .replay_with_mutations(vec![
+            ReplayItem::Transaction(transactions[0].clone()),
+            ReplayItem::mutation("Operator doubles ACC002", double_acc002),
+            ReplayItem::Transaction(transactions[1].clone()),
+        ])
+        .unwrap();
+    
+    // Apply the same steps by hand
+    let after_first = TransferRulesV1.apply(&create_test_state(), &transactions[0], &context).unwrap();
+    let adjusted = double_acc002(after_first).unwrap();
+    let expected = TransferRulesV1.apply(&adjusted, &transactions[1], &context).unwrap();
+    assert_eq!(result.final_state, expected);
+    assert_eq!(result.final_hash, StateHasher::new().hash(&expected));
+    
+    let trace = &result.execution_trace;
+    assert_eq!(trace.transactions_processed, 2);
+    assert_eq!(trace.rule_applications.len(), 2);
+    let ids: Vec<_> = trace.state_transitions.iter().map(|t| t.transaction_id.as_str()).collect();
+    assert_eq!(ids, vec!["TXN001", "MUTATION_0", "TXN002"]);
+    assert!(trace.is_mutation("MUTATION_0"));
+    assert!(!trace.is_mutation("TXN001"));
+    assert_eq!(trace.mutations[0].description, "Operator doubles ACC002");
+    assert_eq!(trace.mutations[0].transaction_index, 1);
+}
+
+#[test]
+fn test_rule_applications_carry_descriptions() {
+    let transactions = create_test_transactions();
+    let engine = ReplayEngineBuilder::new()
+        .with_initial_state(create_test_state())
+        .with_rule_set(TransferRulesV1)
+        .with_context(create_test_context())
+        .build()
+        .unwrap();
+    
+    let trace = engine.replay(&transactions).unwrap().execution_trace;
+    let descriptions = trace.descriptions();
+    assert_eq!(descriptions.len(), 3);
+    assert!(descriptions.iter().all(|d| !d.is_empty()));
+    assert_eq!(trace.description_for("TXN001"), Some("Transfer $100.00 from ACC001 to ACC002 (fee: $1.00)"));
+    assert_eq!(trace.description_for("missing"), None);
+    
+    // Rule sets without an override fall back to the generic description
+    let engine = ReplayEngineBuilder::new()
+        .with_initial_state(create_test_state())
+        .with_rule_set(TransferRulesV1_1)
+        .with_context(create_test_context())
+        .build()
+        .unwrap();
+    let trace = engine.replay(&transactions).unwrap().execution_trace;
+    assert_eq!(
+        trace.description_for(&transactions[1].id),
+        Some(format!("Applied rule 1.1.0 to transaction {}", transactions[1].id).as_str())
+    );
+}
+
+#[test]
+fn test_disjoint_account_transfers_are_independent() {
+    use dtre::{IndependentPartition, TransactionSequenceSplitter};
+    
+    let base_time = DateTime::parse_from_rfc3339("2024-01-01T00:00:00Z")
+        .unwrap()
+        .with_timezone(&Utc);
+    let transfer = |id: &str, from: &str, to: &str, minutes: i64| TransferTransaction {
+        id: id.to_string(),
+        timestamp: base_time + chrono::Duration::seconds(minutes * 60),
+        from_account: from.to_string(),
+        to_account: to.to_string(),
+        amount: 1_000,
+        currency: "USD".to_string(),
+        description: "Transfer".to_string(),
+    };
+    let transactions = vec![
+        transfer("TX001", "ACC001", "ACC002", 1),
+        transfer("TX002", "ACC003", "ACC004", 2),
+        transfer("TX003", "ACC002", "ACC001", 3),
+    ];
+    
+    let partitions = TransactionSequenceSplitter::split_by_entity_independence(&transactions, |t| {
+        vec![t.from_account.clone(), t.to_account.clone()]
+    });
+    assert_eq!(partitions.len(), 2);
+    assert_eq!(partitions[0].transactions.iter().map(|t| t.id.as_str()).collect::<Vec<_>>(), vec!["TX001", "TX003"]);
+    assert_eq!(partitions[1].entities.iter().collect::<Vec<_>>(), vec!["ACC003", "ACC004"]);
+    assert!(IndependentPartition::verify_independence(&partitions));
+    
+    let (pre, post) = TransactionSequenceSplitter::split_at_watermark(&transactions, base_time + chrono::Duration::seconds(150));
+    assert_eq!(pre.transactions.len(), 2);
+    assert_eq!(post.transactions[0].id, "TX003");
+}
+
+#[test]
+fn test_binary_checkpoint_is_smaller_than_json() {
+    use dtre::{Checkpoint, StateManager};
+    
+    let accounts = (0..10)
+        .map(|i| {
+            let account_id = format!("ACC{:03}", i);
+            (account_id.clone(), BankAccount {
+                account_id,
+                balance: 10_000 * (i + 1),
+                currency: "USD".to_string(),
+                status: AccountStatus::Active,
+            })
+        })
+        .collect();
+    let base_time = DateTime::parse_from_rfc3339("2024-01-01T00:00:00Z")
+        .unwrap()
+        .with_timezone(&Utc);
+    let transaction_history = (0..100)
+        .map(|i| TransactionRecord {
+            transaction_id: format!("tx{:03}", i),
+            timestamp: base_time + chrono::Duration::seconds(i * 60),
+            from_account: format!("ACC{:03}", i % 10),
+            to_account: format!("ACC{:03}", (i + 1) % 10),
+            amount: 100 + i,
+            fee: 1,
+            rule_version: Version::new(1, 0, 0),
+        })
+        .collect();
+    let state = BankingState {
+        accounts,
+        transaction_history,
+        total_fees_collected: 100,
+    };
+    
+    let mut manager = StateManager::new(state).unwrap();
+    let checkpoint = manager.create_checkpoint(base_time);
+    
+    let bytes = checkpoint.to_bytes().unwrap();
+    let json = serde_json::to_vec(&checkpoint).unwrap();
+    assert!(bytes.len() < json.len(), "binary {} bytes, JSON {} bytes", bytes.len(), json.len());
+    assert_eq!(checkpoint.binary_size(), bytes.len());
+    
+    let decoded = Checkpoint::<BankingState>::from_bytes(&bytes).unwrap();
+    assert_eq!(decoded.state, checkpoint.state);
+    assert!(decoded.verify_integrity().is_ok());
+}
+
+#[test]
+fn test_merge_states_of_diverged_branches() {
+    use dtre::{MergeStrategy, StateManager};
+    
+    let replay = || {
+        ReplayEngineBuilder::new()
+            .with_initial_state(create_test_state())
+            .with_rule_set(TransferRulesV1)
+            .with_context(create_test_context())
+            .build()
+            .unwrap()
+            .replay(&create_test_transactions())
+            .unwrap()
+            .final_state
+    };
+    let site_a = replay();
+    let site_b = replay();
+    let manager = StateManager::new(create_test_state()).unwrap();
+    
+    // Branches that processed the same transactions merge back to the same state
+    for strategy in [MergeStrategy::last_write_wins(), MergeStrategy::sum_numerics(), MergeStrategy::max_numerics()] {
+        assert_eq!(manager.merge_states(&site_a, &site_b, strategy).unwrap(), site_a);
+    }
+    
+    let mut fees_a = site_a.clone();
+    fees_a.total_fees_collected = 300;
+    let mut fees_b = site_a.clone();
+    fees_b.total_fees_collected = 450;
+    let summed = manager.merge_states(&fees_a, &fees_b, MergeStrategy::sum_numerics()).unwrap();
+    assert_eq!(summed.total_fees_collected, 750);
+    assert_eq!(summed.accounts, site_a.accounts);
+    let latest = manager.merge_states(&fees_a, &fees_b, MergeStrategy::last_write_wins()).unwrap();
+    assert_eq!(latest.total_fees_collected, 450);
+    
+    // Validation is opt-in
+    let mut negative = fees_a.clone();
+    negative.total_fees_collected = -1_000;
+    let unchecked = manager.merge_states(&negative, &fees_b, MergeStrategy::sum_numerics()).unwrap();
+    assert_eq!(unchecked.total_fees_collected, -550);
+    assert!(manager
+        .merge_states(&negative, &fees_b, MergeStrategy::sum_numerics().with_merge_validate(true))
+        .is_err());
+}
+
+#[test]
+fn test_dry_run_migration_reports_limit_rejection() {
+    let engine = ReplayEngineBuilder::new()
+        .with_initial_state(create_test_state())
+        .with_rule_set(TransferRulesV1)
+        .with_context(create_test_context())
+        .build()
+        .unwrap();
+    let before = engine.replay(&create_test_transactions()).unwrap();
+    
+    let mut upcoming = create_test_transactions();
+    upcoming.insert(1, TransferTransaction {
+        id: "TXN_LARGE".to_string(),
+        amount: 1_500_000,
+        description: "Over the v2 limit".to_string(),
+        ..upcoming[0].clone()
+    });
+    let dry_run = engine.dry_run_migration(&upcoming, &TransferRulesV2).unwrap();
+    assert!(!dry_run.would_succeed);
+    assert!(dry_run.simulated_final_state.is_none());
+    match dry_run.first_failing_transaction {
+        Some((1, id, ProcessingError::TransactionFailed { reason, .. })) => {
+            assert_eq!(id, "TXN_LARGE");
+            assert!(reason.contains("exceeds limit"));
+        }
+        other => panic!("expected the limit rejection, got {:?}", other),
+    }
+    
+    // The engine still replays from its original initial state
+    assert_eq!(engine.replay(&create_test_transactions()).unwrap().final_hash, before.final_hash);
+    
+    let with_fees = engine
+        .dry_run_migration_with_fees(&create_test_transactions(), &TransferRulesV2, |state| state.total_fees_collected)
+        .unwrap();
+    assert!(with_fees.would_succeed);
+    let v2_fees = with_fees.simulated_final_state.unwrap().total_fees_collected;
+    assert_eq!(with_fees.fee_delta, Some(v2_fees - before.final_state.total_fees_collected));
+}
+
+#[test]
+fn test_replay_checksum_tracks_final_state() {
+    let replay = || {
+        ReplayEngineBuilder::new()
+            .with_initial_state(create_test_state())
+            .with_rule_set(TransferRulesV1)
+            .with_context(create_test_context())
+            .build()
+            .unwrap()
+            .replay(&create_test_transactions())
+            .unwrap()
+    };
+    let first = replay();
+    let second = replay();
+    let checksum = first.checksum().unwrap();
+    assert_eq!(second.checksum().unwrap(), checksum);
+    assert_eq!(first.checksum_hex().unwrap(), hex::encode(checksum));
+    assert!(second.verify_checksum(checksum));
+    
+    let edits: [fn(&mut BankingState); 3] = [
+        |state| state.accounts.get_mut("ACC002").unwrap().balance += 1,
+        |state| state.accounts.get_mut("ACC003").unwrap().status = AccountStatus::Frozen,
+        |state| state.transaction_history[0].fee = 0,
+    ];
+    for edit in edits {
+        let mut edited = first.clone();
+        edit(&mut edited.final_state);
+        assert!(!edited.verify_checksum(checksum));
+    }
+    
+    // A final state without a JSON form has no checksum rather than a placeholder one
+    let unserializable = dtre::ReplayResult {
+        final_state: std::collections::BTreeMap::from([((1u8, 2u8), 3i64)]),
+        final_hash: first.final_hash,
+        execution_trace: first.execution_trace.clone(),
+        performance_metrics: first.performance_metrics.clone(),
+    };
+    assert!(unserializable.checksum().is_err());
+    assert!(!unserializable.verify_checksum(checksum));
+}
+
+/// Refunds the transfer amount from collected fees, and declares that it only writes fees
+struct FeeRefundRules;
+
+impl RuleSet<BankingState, TransferTransaction> for FeeRefundRules {
+    fn version(&self) -> Version {
+        Version::new(1, 0, 0)
+    }
+    
+    fn affects_fields(&self) -> Option<Vec<String>> {
+        Some(vec!["total_fees_collected".to_string()])
+    }
+    
+    fn apply(
+        &self,
+        state: &BankingState,
+        transaction: &TransferTransaction,
+        _context: &ExecutionContext,
+    ) -> Result<BankingState, ProcessingError> {
+        let mut new_state = state.clone();
+        new_state.total_fees_collected -= transaction.amount;
+        Ok(new_state)
+    }
+}
+
+#[test]
+fn test_negative_fees_fail_field_validation() {
+    use dtre::StateManager;
+    
+    let mut state = create_test_state();
+    state.total_fees_collected = -1;
+    let error = state.validate().unwrap_err();
+    assert!(matches!(
+        &error,
+        ValidationError::FieldValidation { field_path, .. } if field_path == "total_fees_collected"
+    ));
+    assert_eq!(error.field_path(), Some("total_fees_collected"));
+    assert!(state.validate_field("accounts.*.balance").is_ok());
+    
+    // A rule set declaring its fields has only those fields validated
+    let mut manager = StateManager::new(create_test_state()).unwrap();
+    let transactions = create_test_transactions();
+    let context = create_test_context();
+    let error = manager.apply_transaction(&transactions[0], &FeeRefundRules, &context).unwrap_err();
+    assert!(error.to_string().contains("Invalid field total_fees_collected"));
+    assert_eq!(manager.current_state().total_fees_collected, 0);
+    
+    manager.apply_transaction(&transactions[0], &BalanceOnlyRules, &context).unwrap();
+}
+
+/// Transfers like v1 but then doubles every balance by mistake
+struct BalanceDoublingRules;
+
+impl RuleSet<BankingState, TransferTransaction> for BalanceDoublingRules {
+    fn version(&self) -> Version {
+        Version::new(1, 0, 1)
+    }
+    
+    fn apply(
+        &self,
+        state: &BankingState,
+        transaction: &TransferTransaction,
+        context: &ExecutionContext,
+    ) -> Result<BankingState, ProcessingError> {
+        let mut new_state = TransferRulesV1.apply(state, transaction, context)?;
+        for account in new_state.accounts.values_mut() {
+            account.balance *= 2;
+        }
+        Ok(new_state)
+    }
+}
+
+#[test]
+fn test_conservation_post_condition_rejects_buggy_rule() {
+    use dtre::StateManager;
+    
+    let total_balance = |state: &BankingState| state.accounts.values().map(|a| a.balance).sum::<i64>();
+    let transactions = create_test_transactions();
+    let context = create_test_context();
+    let mut manager = StateManager::new(create_test_state()).unwrap();
+    
+    // Balances may only shrink by the fees the transfer collected
+    let conserves = |before: BankingState| {
+        move |after: &BankingState| {
+            let fee_collected = after.total_fees_collected - before.total_fees_collected;
+            (total_balance(&before) - total_balance(after)).abs() <= fee_collected
+        }
+    };
+    
+    let before = manager.current_state().clone();
+    manager
+        .apply_conditional_named(&transactions[0], &TransferRulesV1, &context, "balance_conservation", conserves(before))
+        .unwrap();
+    let hash_after_valid_transfer = manager.current_hash();
+    
+    let before = manager.current_state().clone();
+    let result = manager.apply_conditional_named(
+        &transactions[1],
+        &BalanceDoublingRules,
+        &context,
+        "balance_conservation",
+        conserves(before.clone()),
+    );
+    assert!(matches!(
+        result,
+        Err(ProcessingError::PostConditionFailed { ref condition_name }) if condition_name == "balance_conservation"
+    ));
+    assert_eq!(manager.current_hash(), hash_after_valid_transfer);
+    assert_eq!(manager.current_state(), &before);
+    assert_eq!(manager.transaction_count(), 1);
+    
+    let unnamed = manager.apply_conditional(&transactions[1], &BalanceDoublingRules, &context, conserves(before));
+    assert!(matches!(unnamed, Err(ProcessingError::PostConditionFailed { ref condition_name }) if condition_name == "post_condition"));
+}
+
+#[test]
+fn test_timeline_of_three_transfers() {
+    use dtre::TimelineEventType;
+    
+    let engine = ReplayEngineBuilder::new()
+        .with_initial_state(create_test_state())
+        .with_rule_set(TransferRulesV1)
+        .with_context(create_test_context())
+        .build()
+        .unwrap();
+    let result = engine.replay(&create_test_transactions()).unwrap();
+    let timeline = result.execution_trace.to_timeline();
+    
+    let processed = timeline.events_of_type(TimelineEventType::TransactionProcessed);
+    let ids: Vec<_> = processed.iter().map(|e| e.transaction_id.as_deref().unwrap()).collect();
+    assert_eq!(ids, vec!["TXN001", "TXN002", "TXN003"]);
+    assert!(processed.windows(2).all(|pair| pair[0].timestamp <= pair[1].timestamp));
+    assert_eq!(processed[2].state_hash, result.final_hash);
+    
+    let first = processed[0].timestamp;
+    assert_eq!(timeline.events_between(first, first + chrono::Duration::seconds(60)).len(), 2);
+    assert!(timeline.events_between(first + chrono::Duration::seconds(1), first + chrono::Duration::seconds(59)).is_empty());
+    
+    let chart = timeline.to_ascii_chart(80);
+    assert!(!chart.is_empty());
+    let tracks: Vec<&str> = chart.lines().skip(1).map(|line| &line[..80]).collect();
+    assert_eq!(tracks.len(), timeline.len());
+    assert_eq!(tracks.iter().map(|track| track.matches('*').count()).sum::<usize>(), 3);
+    assert!(tracks[0].starts_with('*') && tracks[2].ends_with('*'));
+}
+
+#[test]
+fn test_diff_first_and_last_checkpoints() {
+    use dtre::{StateError, TransactionProcessor};
+    
+    let context = create_test_context();
+    let mut processor = TransactionProcessor::new(create_test_state()).unwrap();
+    let first = processor.create_checkpoint(context.now());
+    processor
+        .process_transactions_with_checkpoints(&create_test_transactions(), &TransferRulesV1, &context, 1)
+        .unwrap();
+    let manager = processor.state_manager();
+    let last = manager.list_checkpoints().last().unwrap().clone();
+    
+    let diff = manager.diff_checkpoints(first.hash, last.hash).unwrap();
+    assert_eq!(diff.index_diff, 3);
+    assert!(diff.has_changed_fields(&["accounts.ACC001", "accounts.ACC002", "accounts.ACC003"]));
+    let changed_accounts: std::collections::BTreeSet<_> = diff
+        .fields_changed()
+        .into_iter()
+        .filter_map(|change| change.path.strip_prefix("accounts.").map(|rest| rest[..6].to_string()))
+        .collect();
+    assert_eq!(changed_accounts.into_iter().collect::<Vec<_>>(), vec!["ACC001", "ACC002", "ACC003"]);
+    assert_eq!(
+        diff.summary_line(),
+        "[tx 0 → tx 3]: 3 accounts modified, total_fees_collected changed by +300, 3 transaction_history entries added"
+    );
+    
+    let to_current = manager.diff_checkpoint_and_current(first.hash).unwrap();
+    assert_eq!(to_current.to_hash, manager.current_hash());
+    assert_eq!(to_current.transaction_range, Some((0, 3)));
+    
+    let unknown = dtre::StateHash([7; 32]);
+    assert!(matches!(manager.diff_checkpoints(first.hash, unknown), Err(StateError::CheckpointError { .. })));
+}
+
+#[test]
+fn test_state_patch_matches_direct_assignment() {
+    use dtre::StatePatch;
+    
+    let before = create_test_state();
+    let after = TransferRulesV1.apply(&before, &create_test_transactions()[0], &create_test_context()).unwrap();
+    let patch = StatePatch::from_diff(&before, &after);
+    let mut patched = before.clone();
+    patched.apply_patch(&patch).unwrap();
+    assert_eq!(patched, after);
+    assert!(StatePatch::from_diff(&after, &after).is_empty());
+    
+    // Patches round-trip through serialization
+    let restored: StatePatch = serde_json::from_str(&serde_json::to_string(&patch).unwrap()).unwrap();
+    let mut patched = before.clone();
+    patched.apply_patch(&restored).unwrap();
+    assert_eq!(patched, after);
+    
+    let mut single_account = before.clone();
+    single_account.accounts.get_mut("ACC002").unwrap().balance += 500;
+    let patch = StatePatch::from_diff(&before, &single_account);
+    assert!(patch.size_bytes() < serde_json::to_string(&single_account).unwrap().len());
+    let mut patched = before.clone();
+    patched.apply_patch(&patch).unwrap();
+    assert_eq!(patched, single_account);
+}
+
+/// Checks and moves the transfer amount, without fees
+struct TransferValidationRules;
+
+impl RuleSet<BankingState, TransferTransaction> for TransferValidationRules {
+    fn version(&self) -> Version {
+        Version::new(1, 0, 0)
+    }
+    
+    fn apply(
+        &self,
+        state: &BankingState,
+        transaction: &TransferTransaction,
+        _context: &ExecutionContext,
+    ) -> Result<BankingState, ProcessingError> {
+        let fail = |reason: &str| ProcessingError::TransactionFailed {
+            transaction_id: transaction.id.clone(),
+            reason: reason.to_string(),
+        };
+        let from = state.accounts.get(&transaction.from_account).ok_or_else(|| fail("Source account not found"))?;
+        if !state.accounts.contains_key(&transaction.to_account) {
+            return Err(fail("Destination account not found"));
+        }
+        if from.balance < transaction.amount {
+            return Err(fail("Insufficient balance"));
+        }
+        
+        let mut new_state = state.clone();
+        new_state.accounts.get_mut(&transaction.from_account).unwrap().balance -= transaction.amount;
+        new_state.accounts.get_mut(&transaction.to_account).unwrap().balance += transaction.amount;
+        Ok(new_state)
+    }
+}
+
+/// Charges a 1% fee to the sender, counting how often it runs
+#[derive(Default)]
+struct FeeCalculationRules {
+    applications: std::sync::atomic::AtomicUsize,
+}
+
+impl RuleSet<BankingState, TransferTransaction> for FeeCalculationRules {
+    fn version(&self) -> Version {
+        Version::new(1, 2, 0)
+    }
+    
+    fn apply(
+        &self,
+        state: &BankingState,
+        transaction: &TransferTransaction,
+        _context: &ExecutionContext,
+    ) -> Result<BankingState, ProcessingError> {
+        self.applications.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        let fee = transaction.amount / 100;
+        let mut new_state = state.clone();
+        new_state.accounts.get_mut(&transaction.from_account).unwrap().balance -= fee;
+        new_state.total_fees_collected += fee;
+        Ok(new_state)
+    }
+}
+
+#[test]
+fn test_sequential_validation_then_fee_rules() {
+    let fee_rules = FeeCalculationRules::default();
+    let rules = TransferValidationRules.compose_sequential(&fee_rules);
+    assert_eq!(rules.version(), Version::new(1, 2, 0));
+    assert_eq!(rules.first_version(), Version::new(1, 0, 0));
+    assert_eq!(rules.second_version(), Version::new(1, 2, 0));
+    
+    let context = create_test_context();
+    let transactions = create_test_transactions();
+    let state = create_test_state();
+    
+    let after = rules.apply(&state, &transactions[0], &context).unwrap();
+    assert_eq!(after.accounts["ACC001"].balance, 100_000 - 10_000 - 100);
+    assert_eq!(after.accounts["ACC002"].balance, 50_000 + 10_000);
+    assert_eq!(after.total_fees_collected, 100);
+    assert_eq!(fee_rules.applications.load(std::sync::atomic::Ordering::SeqCst), 1);
+    
+    let mut overdraft = transactions[0].clone();
+    overdraft.amount = 1_000_000;
+    let result = rules.apply(&state, &overdraft, &context);
+    assert!(matches!(result, Err(ProcessingError::TransactionFailed { ref reason, .. }) if reason == "Insufficient balance"));
+    assert_eq!(fee_rules.applications.load(std::sync::atomic::Ordering::SeqCst), 1);
+}
+
+#[cfg(feature = "test-utils")]
+mod harness_tests {
+    use super::*;
+    use dtre::testing::RuleSetTestHarness;
+    
+    fn transfer(id: &str, from: &str, to: &str, amount: i64) -> TransferTransaction {
+        TransferTransaction {
+            id: id.to_string(),
+            timestamp: create_test_context().now(),
+            from_account: from.to_string(),
+            to_account: to.to_string(),
+            amount,
+            currency: "USD".to_string(),
+            description: "Harness transfer".to_string(),
+        }
+    }
+    
+    fn failed_with(reason: &'static str) -> impl Fn(&ProcessingError) -> bool {
+        move |error| matches!(error, ProcessingError::TransactionFailed { reason: r, .. } if r.contains(reason))
+    }
+    
+    fn harness(initial_state: BankingState) -> RuleSetTestHarness<BankingState, TransferTransaction, TransferRulesV1> {
+        RuleSetTestHarness::new(TransferRulesV1)
+            .with_initial_state(initial_state)
+            .with_context(create_test_context())
+    }
+    
+    #[test]
+    fn test_transfer_rules_v1_with_harness() {
+        let mut wrong_currency = transfer("TXN006", "ACC001", "ACC002", 1_000);
+        wrong_currency.currency = "EUR".to_string();
+        let mut no_currency = transfer("TXN007", "ACC001", "ACC002", 1_000);
+        no_currency.currency = String::new();
+        
+        harness(create_test_state())
+            .expect_success(transfer("TXN001", "ACC001", "ACC002", 10_000))
+            .assert_state(|state| {
+                state.accounts["ACC001"].balance == 89_900
+                    && state.accounts["ACC002"].balance == 60_000
+                    && state.total_fees_collected == 100
+                    && state.transaction_history.len() == 1
+            })
+            // Transaction validation
+            .expect_failure(transfer("", "ACC001", "ACC002", 1_000), failed_with("Transaction ID is empty"))
+            .assert_hash_unchanged()
+            .expect_failure(transfer("TXN002", "", "ACC002", 1_000), failed_with("From account is empty"))
+            .expect_failure(transfer("TXN002", "ACC001", "", 1_000), failed_with("To account is empty"))
+            .expect_failure(transfer("TXN002", "ACC001", "ACC001", 1_000), failed_with("same account"))
+            .expect_failure(transfer("TXN002", "ACC001", "ACC002", 0), failed_with("Amount must be positive"))
+            .expect_failure(no_currency, failed_with("Currency is empty"))
+            // Account lookups
+            .expect_failure(transfer("TXN003", "ACC999", "ACC002", 1_000), failed_with("Source account ACC999 not found"))
+            .expect_failure(transfer("TXN004", "ACC001", "ACC999", 1_000), failed_with("Destination account ACC999 not found"))
+            // Balance and currency
+            .expect_failure(transfer("TXN005", "ACC002", "ACC001", 60_000), failed_with("Insufficient balance"))
+            .assert_hash_unchanged()
+            .expect_failure(wrong_currency, failed_with("Currency mismatch"))
+            .assert_state(|state| state.total_fees_collected == 100);
+    }
+    
+    #[test]
+    fn test_transfer_rules_v1_rejects_inactive_accounts_with_harness() {
+        let mut initial_state = create_test_state();
+        initial_state.accounts.get_mut("ACC001").unwrap().status = AccountStatus::Frozen;
+        initial_state.accounts.get_mut("ACC003").unwrap().status = AccountStatus::Closed;
+        
+        harness(initial_state)
+            .expect_failure(transfer("TXN001", "ACC001", "ACC002", 1_000), failed_with("Source account ACC001 is not active"))
+            .assert_hash_unchanged()
+            .expect_failure(transfer("TXN002", "ACC002", "ACC003", 1_000), failed_with("Destination account ACC003 is not active"))
+            .assert_state(|state| state.transaction_history.is_empty());
+    }
+}
+
+#[cfg(feature = "test-utils")]
+mod property_macro_tests {
+    use super::*;
+    use dtre::{assert_deterministic, assert_invariant_preserving};
+    use proptest::prelude::*;
+    
+    const ACCOUNT_IDS: [&str; 4] = ["ACC001", "ACC002", "ACC003", "ACC999"];
+    
+    fn banking_states() -> impl Strategy<Value = BankingState> {
+        (0i64..300_000, 0i64..300_000, 0i64..300_000, any::<bool>()).prop_map(|(first, second, third, frozen)| {
+            let mut state = create_test_state();
+            state.accounts.get_mut("ACC001").unwrap().balance = first;
+            state.accounts.get_mut("ACC002").unwrap().balance = second;
+            state.accounts.get_mut("ACC003").unwrap().balance = third;
+            if frozen {
+                state.accounts.get_mut("ACC003").unwrap().status = AccountStatus::Frozen;
+            }
+            state
+        })
+    }
+    
+    fn transfers() -> impl Strategy<Value = TransferTransaction> {
+        (0usize..4, 0usize..4, -1_000i64..200_000, prop::sample::select(vec!["USD", "EUR"])).prop_map(
+            |(from, to, amount, currency)| TransferTransaction {
+                id: "TXN001".to_string(),
+                timestamp: create_test_context().now(),
+                from_account: ACCOUNT_IDS[from].to_string(),
+                to_account: ACCOUNT_IDS[to].to_string(),
+                amount,
+                currency: currency.to_string(),
+                description: "Generated transfer".to_string(),
+            },
+        )
+    }
+    
+    /// Money only moves between accounts and the collected fees
+    fn balance_conservation(before: &BankingState, after: &BankingState) -> bool {
+        let total = |state: &BankingState| state.accounts.values().map(|a| a.balance).sum::<i64>() + state.total_fees_collected;
+        total(before) == total(after)
+    }
+    
+    assert_deterministic!(test_transfer_rules_v1_are_deterministic, TransferRulesV1, banking_states(), transfers());
+    assert_deterministic!(test_transfer_rules_v1_1_are_deterministic, TransferRulesV1_1, banking_states(), transfers());
+    assert_deterministic!(test_transfer_rules_v2_are_deterministic, TransferRulesV2, banking_states(), transfers());
+    
+    assert_invariant_preserving!(test_transfer_rules_v1_conserve_balance, TransferRulesV1, balance_conservation, banking_states(), transfers());
+    assert_invariant_preserving!(test_transfer_rules_v1_1_conserve_balance, TransferRulesV1_1, balance_conservation, banking_states(), transfers());
+    assert_invariant_preserving!(test_transfer_rules_v2_conserve_balance, TransferRulesV2, balance_conservation, banking_states(), transfers());
+    
+    #[test]
+    #[should_panic(expected = "broke the invariant")]
+    fn test_balance_doubling_rules_break_conservation() {
+        dtre::testing::run_property(50, banking_states(), transfers(), |state, transaction| {
+            dtre::testing::check_invariant_preserving(
+                &BalanceDoublingRules,
+                state,
+                transaction,
+                &dtre::testing::default_context(),
+                balance_conservation,
+            )
+        });
+    }
+}
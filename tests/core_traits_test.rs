@@ -92,11 +92,16 @@ fn arb_replay_result() -> impl Strategy<Value = ReplayResult<TestState>> {
                 state_transitions: vec![],
                 rule_applications: vec![],
                 checkpoints: vec![],
+                watermark: WatermarkTracker::new(),
+                mutations: Vec::new(),
+                skipped_transactions: Vec::new(),
+                skipped_indices: Vec::new(),
             },
             performance_metrics: PerformanceMetrics {
                 total_duration_ms: duration,
                 transactions_per_second: tps,
                 average_transaction_time_ms: avg_time,
+                breakdown: Default::default(),
             },
         }
     })
@@ -1,6 +1,6 @@
 use dtre::{
-    ErrorContext, StateMismatchDetail, FieldDiff, ValidationDetail,
-    ProcessingError, ValidationError, StateError, StateHash, Version
+    DTREError, ErrorContext, StateMismatchDetail, FieldDiff, ValidationDetail,
+    ProcessingError, ValidationError, StateError, StateHash, Version, RecoveryHint, RuleError
 };
 use proptest::prelude::*;
 
@@ -218,3 +218,135 @@ proptest! {
         );
     }
 }
+
+#[cfg(test)]
+mod unified_error_tests {
+    use super::*;
+    
+    fn validation_detail(rule: &str) -> ValidationDetail {
+        ValidationDetail {
+            violated_rules: vec![rule.to_string()],
+            field: Some("balance".to_string()),
+            expected_constraint: Some(">= 0".to_string()),
+            actual_value: Some("-5".to_string()),
+            context: ErrorContext::new(),
+        }
+    }
+    
+    #[test]
+    fn test_processing_error_converts_to_processing_variant() {
+        let error: DTREError = ProcessingError::TransactionFailed {
+            transaction_id: "tx1".to_string(),
+            reason: "insufficient funds".to_string(),
+        }
+        .into();
+        
+        match &error {
+            DTREError::Processing(ProcessingError::TransactionFailed { transaction_id, reason }) => {
+                assert_eq!(transaction_id, "tx1");
+                assert_eq!(reason, "insufficient funds");
+            }
+            other => panic!("expected processing error, got {:?}", other),
+        }
+        assert_eq!(error.error_code(), "PROCESSING_TRANSACTION_FAILED");
+        assert!(error.is_deterministic());
+        assert!(!error.is_retryable());
+    }
+    
+    #[test]
+    fn test_error_classification() {
+        let non_deterministic: DTREError = ProcessingError::NonDeterministicOperation {
+            operation: "SystemTime::now".to_string(),
+            location: "rule v1".to_string(),
+        }
+        .into();
+        assert!(!non_deterministic.is_deterministic());
+        assert_eq!(non_deterministic.error_code(), "PROCESSING_NON_DETERMINISTIC_OPERATION");
+        
+        let missing_entity: DTREError = ProcessingError::ExternalEntityNotFound {
+            entity_id: "fx-rate".to_string(),
+        }
+        .into();
+        assert!(missing_entity.is_retryable());
+        
+        let invalid_state: DTREError = ValidationError::InvalidState { reason: "negative".to_string() }.into();
+        assert_eq!(invalid_state.error_code(), "VALIDATION_INVALID_STATE");
+        assert!(!invalid_state.is_retryable());
+        
+        let checkpoint: DTREError = StateError::CheckpointError { reason: "unavailable".to_string() }.into();
+        assert!(checkpoint.is_retryable());
+        assert_eq!(checkpoint.error_code(), "STATE_CHECKPOINT_ERROR");
+    }
+    
+    #[test]
+    fn test_pre_validation_failure_chain() {
+        let error = ProcessingError::PreValidationFailed {
+            rule_version: Version::new(1, 0, 0),
+            detail: Box::new(validation_detail("non_negative_balance")),
+        };
+        
+        let chain = DTREError::from(error.clone()).chain();
+        assert_eq!(chain.len(), 2);
+        assert!(matches!(chain[0], DTREError::Processing(ProcessingError::PreValidationFailed { .. })));
+        match &chain[1] {
+            DTREError::Validation(validation) => {
+                assert_eq!(validation.details().unwrap().violated_rules, vec!["non_negative_balance"]);
+            }
+            other => panic!("expected validation error, got {:?}", other),
+        }
+        
+        assert!(error.into_validation_error().is_ok());
+        
+        let unrelated = ProcessingError::InvalidRange { start: 3, end: 1, len: 2 };
+        assert!(matches!(unrelated.into_validation_error(), Err(ProcessingError::InvalidRange { .. })));
+        
+        let single = DTREError::from(StateError::MergeConflict { field_path: "total".to_string() }).chain();
+        assert_eq!(single.len(), 1);
+    }
+}
+
+#[cfg(test)]
+mod recovery_hint_tests {
+    use super::*;
+    
+    #[test]
+    fn test_recovery_hints() {
+        let insufficient = ProcessingError::TransactionFailed {
+            transaction_id: "tx1".to_string(),
+            reason: "insufficient balance".to_string(),
+        };
+        assert_eq!(
+            insufficient.recovery_hint(),
+            Some(RecoveryHint::FixTransaction { suggestion: "insufficient balance".to_string() })
+        );
+        
+        let non_deterministic = ProcessingError::NonDeterministicOperation {
+            operation: "SystemTime::now".to_string(),
+            location: "rule v1".to_string(),
+        };
+        assert_eq!(non_deterministic.recovery_hint(), None);
+        
+        // Only registered versions are suggested
+        let registered = [Version::new(1, 0, 0), Version::new(2, 0, 0), Version::new(1, 3, 0)];
+        assert_eq!(
+            non_deterministic.recovery_hint_with_versions(&registered),
+            Some(RecoveryHint::UpgradeRuleSet { to_version: Version::new(2, 0, 0) })
+        );
+        
+        let invariant = ProcessingError::Rule(RuleError::InvariantViolated {
+            rule_version: Version::new(1, 2, 3),
+            reason: "negative total".to_string(),
+        });
+        assert_eq!(invariant.recovery_hint(), None);
+        assert_eq!(
+            invariant.recovery_hint_with_versions(&registered),
+            Some(RecoveryHint::UpgradeRuleSet { to_version: Version::new(1, 3, 0) })
+        );
+        assert_eq!(invariant.recovery_hint_with_versions(&registered[..1]), None);
+        
+        let missing_entity = ProcessingError::ExternalEntityNotFound { entity_id: "fx-rate".to_string() };
+        assert!(matches!(missing_entity.recovery_hint(), Some(RecoveryHint::RetryWithDelay(_))));
+        
+        assert_eq!(ProcessingError::SigningFailed { reason: "no key".to_string() }.recovery_hint(), None);
+    }
+}
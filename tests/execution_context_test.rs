@@ -1,4 +1,4 @@
-use dtre::{ExecutionContext, DeterministicTime, SeededRandom, ExternalFacts};
+use dtre::{ExecutionContext, DeterministicTime, SeededRandom, ExternalFacts, ReproducibilityConfig};
 use chrono::{DateTime, Utc, TimeZone};
 use proptest::prelude::*;
 
@@ -234,6 +234,7 @@ fn arbitrary_operation() -> impl Strategy<Value = Operation> {
         Just(Operation::EnvironmentVariable),
         Just(Operation::ThreadSpawn),
         Just(Operation::ProcessSpawn),
+        Just(Operation::UuidWithoutSeed),
     ]
 }
 
@@ -319,6 +320,10 @@ proptest! {
                     prop_assert!(error_msg.contains("process_spawn"), 
                         "Error should identify process_spawn: {}", error_msg);
                 }
+                Operation::UuidWithoutSeed => {
+                    prop_assert!(error_msg.contains("unseeded_uuid"), 
+                        "Error should identify unseeded_uuid: {}", error_msg);
+                }
             }
         }
     }
@@ -361,6 +366,52 @@ mod non_determinism_tests {
         let result = guard_permissive.validate(&Operation::SystemTime, || 42);
         assert_eq!(result.unwrap(), 42);
     }
+    
+    #[test]
+    fn test_instrument_records_nondeterministic_attempts() {
+        let mut guard = NonDeterminismGuard::new().with_audit_trail(2);
+        let counter = std::cell::Cell::new(0);
+        let next = || {
+            counter.set(counter.get() + 1);
+            counter.get()
+        };
+        
+        for _ in 0..3 {
+            let result = guard.instrument("counter", next);
+            assert!(matches!(
+                result,
+                Err(dtre::ProcessingError::NonDeterministicOperation { ref operation, ref location })
+                    if operation == "counter" && location.contains("execution_context_test.rs")
+            ));
+        }
+        
+        // Only the two most recent attempts are kept
+        let trail = guard.audit_trail();
+        assert_eq!(trail.len(), 2);
+        assert!(trail.iter().all(|attempt| attempt.operation == "counter"));
+        assert_eq!(trail[0].detected_values, ("3".to_string(), "4".to_string()));
+        assert_eq!(trail[1].detected_values, ("5".to_string(), "6".to_string()));
+        assert!(trail[0].detected_at <= trail[1].detected_at);
+        
+        // Deterministic operations pass and are not recorded
+        assert_eq!(guard.instrument("constant", || 42).unwrap(), 42);
+        assert_eq!(guard.audit_trail().len(), 2);
+    }
+    
+    #[test]
+    fn test_instrument_allows_differing_results_when_not_strict() {
+        let mut guard = NonDeterminismGuard::with_strict_mode(false).with_audit_trail(1);
+        let counter = std::cell::Cell::new(0);
+        let next = || {
+            counter.set(counter.get() + 1);
+            counter.get()
+        };
+        
+        assert_eq!(guard.instrument("counter", next).unwrap(), 1);
+        assert_eq!(guard.instrument("counter", next).unwrap(), 3);
+        assert_eq!(guard.audit_trail().len(), 1);
+        assert_eq!(guard.audit_trail()[0].detected_values, ("3".to_string(), "4".to_string()));
+    }
 }
 
 // Property tests for external entity resolution and ordering
@@ -608,6 +659,25 @@ mod external_entity_tests {
         assert!(result.is_err());
     }
     
+    #[test]
+    fn test_entity_resolver_batch_resolve() {
+        let mut resolver = ExternalEntityResolver::with_capacity(2);
+        resolver.batch_register(["alice", "bob"].map(|id| {
+            (id.to_string(), TestEntity { id: id.to_string(), value: 7 })
+        }));
+        
+        let result = resolver.batch_resolve::<TestEntity>(&["alice", "missing_id", "bob"]);
+        assert_eq!(result.not_found, vec!["missing_id"]);
+        assert_eq!(result.resolved.len(), 2);
+        assert_eq!(result.resolved["alice"].id, "alice");
+        assert_eq!(result.resolved["bob"].id, "bob");
+        assert!(!result.is_complete());
+        
+        assert!(resolver.contains_all::<TestEntity>(&["alice", "bob"]));
+        assert!(!resolver.contains_all::<TestEntity>(&["alice", "missing_id"]));
+        assert!(!resolver.contains_all::<TestAccount>(&["alice"]));
+    }
+    
     #[test]
     fn test_ordering_rules_basic() {
         let mut rules = OrderingRules::new();
@@ -703,3 +773,452 @@ mod external_entity_tests {
         assert!(result.is_err());
     }
 }
+
+#[cfg(test)]
+mod reproducibility_tests {
+    use super::*;
+    
+    #[test]
+    fn test_random_seed_getter() {
+        let ctx = ExecutionContext::new(Utc.timestamp_opt(1000000, 0).unwrap(), 98765);
+        assert_eq!(ctx.random_seed(), 98765);
+    }
+    
+    #[test]
+    fn test_fingerprint_reflects_configuration() {
+        let time = Utc.timestamp_opt(1000000, 0).unwrap();
+        
+        let ctx = ExecutionContext::builder()
+            .with_time(time)
+            .with_random_seed(42)
+            .with_external_fact("rate".to_string(), 1.5f64)
+            .with_external_entity("E1".to_string(), 7u32)
+            .with_ordering("accounts".to_string(), vec!["A".to_string(), "B".to_string()])
+            .build();
+        
+        let fingerprint = ctx.fingerprint();
+        assert_eq!(fingerprint.time, time);
+        assert_eq!(fingerprint.random_seed, 42);
+        assert_eq!(fingerprint.external_fact_count, 1);
+        assert_eq!(fingerprint.entity_count, 1);
+        assert_eq!(fingerprint.ordering_rule_count, 1);
+        
+        // Same configuration produces the same fingerprint
+        assert_eq!(fingerprint, ctx.clone().fingerprint());
+        
+        // A different seed changes the hash
+        let other = ExecutionContext::new(time, 43);
+        assert_ne!(fingerprint.context_hash, other.fingerprint().context_hash);
+    }
+    
+    #[test]
+    fn test_reproducibility_config_round_trip() {
+        let time = Utc.timestamp_opt(1000000, 0).unwrap();
+        
+        let ctx = ExecutionContext::builder()
+            .with_time(time)
+            .with_random_seed(1234)
+            .with_ordering("accounts".to_string(), vec!["ACC002".to_string(), "ACC001".to_string()])
+            .with_ordering("traders".to_string(), vec!["T1".to_string()])
+            .build();
+        
+        let config = ctx.to_reproducibility_config();
+        let json = serde_json::to_string(&config).unwrap();
+        let restored_config: ReproducibilityConfig = serde_json::from_str(&json).unwrap();
+        assert_eq!(config, restored_config);
+        
+        let restored = ExecutionContext::from_reproducibility_config(restored_config);
+        assert_eq!(ctx.fingerprint(), restored.fingerprint());
+        assert_eq!(
+            restored.ordering_rules().get_ordering("accounts"),
+            Some(&vec!["ACC002".to_string(), "ACC001".to_string()])
+        );
+    }
+}
+
+#[cfg(all(test, feature = "uuid"))]
+mod uuid_generation_tests {
+    use super::*;
+    
+    #[test]
+    fn test_same_seed_produces_same_uuid_sequence() {
+        let time = Utc.timestamp_opt(1_000_000, 0).unwrap();
+        let mut first = ExecutionContext::new(time, 42);
+        let mut second = ExecutionContext::new(time, 42);
+        
+        let first_ids: Vec<_> = (0..5).map(|_| first.generate_uuid()).collect();
+        let second_ids: Vec<_> = (0..5).map(|_| second.generate_uuid()).collect();
+        
+        assert_eq!(first_ids, second_ids);
+        assert_eq!(first_ids[0].get_version_num(), 4);
+        
+        // Each call advances the generator
+        let unique: std::collections::HashSet<_> = first_ids.iter().collect();
+        assert_eq!(unique.len(), 5);
+    }
+    
+    #[test]
+    fn test_different_seeds_produce_different_uuids() {
+        let time = Utc.timestamp_opt(1_000_000, 0).unwrap();
+        let mut first = ExecutionContext::new(time, 1);
+        let mut second = ExecutionContext::new(time, 2);
+        
+        assert_ne!(first.generate_uuid(), second.generate_uuid());
+    }
+    
+    #[test]
+    fn test_namespaced_uuid_is_stable() {
+        let time = Utc.timestamp_opt(1_000_000, 0).unwrap();
+        let first = ExecutionContext::new(time, 1);
+        let second = ExecutionContext::new(time, 2);
+        
+        let id = first.generate_uuid_namespaced("fees", "tx1");
+        assert_eq!(id, second.generate_uuid_namespaced("fees", "tx1"));
+        assert_eq!(id.get_version_num(), 5);
+        assert_ne!(id, first.generate_uuid_namespaced("fees", "tx2"));
+        assert_ne!(id, first.generate_uuid_namespaced("refunds", "tx1"));
+    }
+    
+    #[test]
+    fn test_guard_rejects_unseeded_uuid() {
+        let guard = NonDeterminismGuard::new();
+        assert!(guard.check_operation(&Operation::UuidWithoutSeed).is_err());
+        assert!(NonDeterminismGuard::with_strict_mode(false)
+            .check_operation(&Operation::UuidWithoutSeed)
+            .is_ok());
+    }
+}
+
+use dtre::ExecutionPhase;
+
+#[cfg(test)]
+mod execution_phase_tests {
+    use super::*;
+    
+    #[test]
+    fn test_advance_phase_walks_through_phases() {
+        let context = ExecutionContext::new(Utc.timestamp_opt(1_000_000, 0).unwrap(), 42);
+        assert_eq!(context.current_phase(), ExecutionPhase::PreProcessing);
+        
+        let main = context.advance_phase();
+        assert_eq!(main.current_phase(), ExecutionPhase::MainProcessing);
+        
+        let post = main.advance_phase();
+        assert_eq!(post.current_phase(), ExecutionPhase::PostProcessing);
+        assert_eq!(post.advance_phase().current_phase(), ExecutionPhase::PostProcessing);
+        
+        // Advancing returns a new context and leaves the original untouched
+        assert_eq!(context.current_phase(), ExecutionPhase::PreProcessing);
+        assert_eq!(post.now(), context.now());
+        assert_eq!(post.random_seed(), context.random_seed());
+    }
+    
+    #[test]
+    fn test_phase_change_shares_facts_with_original() {
+        let context = ExecutionContext::builder()
+            .with_time(Utc.timestamp_opt(1_000_000, 0).unwrap())
+            .with_external_fact("rate".to_string(), 5u32)
+            .build();
+        
+        let main = context.advance_phase();
+        assert!(std::ptr::eq(main.external_facts(), context.external_facts()));
+        assert!(std::ptr::eq(main.entity_resolver(), context.entity_resolver()));
+        assert!(std::ptr::eq(main.ordering_rules(), context.ordering_rules()));
+        assert_eq!(main.get_external_fact::<u32>("rate"), Some(&5));
+    }
+}
+
+use dtre::ExternalFactsSnapshot;
+
+#[cfg(test)]
+mod fact_persistence_tests {
+    use super::*;
+    
+    #[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+    struct FeeSchedule {
+        flat_fee: i64,
+        percentage_bps: u32,
+    }
+    
+    fn registered() -> ExternalFacts {
+        let mut facts = ExternalFacts::new();
+        facts.register_type::<i64>("i64").unwrap();
+        facts.register_type::<FeeSchedule>("fee_schedule").unwrap();
+        facts
+    }
+    
+    #[test]
+    fn test_json_round_trip_restores_typed_facts() {
+        let mut facts = registered();
+        facts.insert("rate".to_string(), 125i64);
+        facts.insert("fees".to_string(), FeeSchedule { flat_fee: 100, percentage_bps: 25 });
+        
+        let json = facts.to_json().unwrap();
+        let text = serde_json::to_string(&json).unwrap();
+        
+        // A later session registers the same types and loads the saved document
+        let restored = registered().from_json(serde_json::from_str(&text).unwrap()).unwrap();
+        assert_eq!(restored.len(), 2);
+        assert_eq!(restored.get::<i64>("rate"), Some(&125));
+        assert_eq!(
+            restored.get::<FeeSchedule>("fees"),
+            Some(&FeeSchedule { flat_fee: 100, percentage_bps: 25 })
+        );
+        
+        // Registrations carry over, so the restored facts serialize identically
+        assert_eq!(restored.to_json().unwrap(), json);
+    }
+    
+    #[test]
+    fn test_snapshot_records_types_and_skips_unregistered() {
+        let mut facts = registered();
+        facts.insert("rate".to_string(), 125i64);
+        facts.insert("label".to_string(), "unregistered".to_string());
+        
+        let snapshot: ExternalFactsSnapshot = facts.snapshot().unwrap();
+        assert_eq!(snapshot.facts["rate"].type_name, "i64");
+        assert_eq!(snapshot.skipped, vec!["label"]);
+        
+        let restored = registered().from_snapshot(snapshot.clone()).unwrap();
+        assert_eq!(restored.get::<i64>("rate"), Some(&125));
+        assert!(!restored.contains_key("label"));
+        
+        // Loading without the type registered is an error
+        assert!(ExternalFacts::new().from_snapshot(snapshot).is_err());
+        
+        // JSON export has no place to list skipped facts, so it refuses them
+        assert!(facts.to_json().is_err());
+    }
+    
+    #[test]
+    fn test_type_name_identifies_one_type() {
+        let mut facts = registered();
+        assert!(matches!(
+            facts.register_type::<u32>("i64"),
+            Err(ValidationError::DuplicateFactType { type_name }) if type_name == "i64"
+        ));
+        
+        // Re-registering the same type under its name is allowed
+        assert!(facts.register_type::<i64>("i64").is_ok());
+        
+        facts.insert("rate".to_string(), 125i64);
+        let restored = facts.from_snapshot(facts.snapshot().unwrap()).unwrap();
+        assert_eq!(restored.get::<i64>("rate"), Some(&125));
+    }
+}
+
+#[cfg(test)]
+mod child_context_tests {
+    use super::*;
+    
+    fn parent() -> ExecutionContext {
+        ExecutionContext::new(Utc.timestamp_opt(1000000, 0).unwrap(), 42)
+    }
+    
+    #[test]
+    fn test_advanced_seed_offsets_parent_seed() {
+        let context = parent();
+        let child = context.clone_with_advanced_seed(7);
+        
+        assert_eq!(child.random_seed(), 49);
+        assert_eq!(child.now(), context.now());
+        assert_eq!(context.clone_with_advanced_seed(u64::MAX).random_seed(), 41);
+    }
+    
+    #[test]
+    fn test_child_does_not_advance_parent_rng() {
+        let mut context = parent();
+        let mut untouched = parent();
+        
+        let mut child = context.clone_with_advanced_seed(1);
+        child.random().next_u64();
+        let _ = context.clone_with_namespaced_seed("fees");
+        
+        assert_eq!(context.random().next_u64(), untouched.random().next_u64());
+    }
+    
+    #[test]
+    fn test_namespaced_seed_is_stable_per_namespace() {
+        let context = parent();
+        
+        let fees_a = context.clone_with_namespaced_seed("fees");
+        let fees_b = context.clone_with_namespaced_seed("fees");
+        let limits = context.clone_with_namespaced_seed("limits");
+        
+        assert_eq!(fees_a.random_seed(), fees_b.random_seed());
+        assert_ne!(fees_a.random_seed(), limits.random_seed());
+        assert_ne!(fees_a.random_seed(), context.random_seed());
+        
+        // Different parents give different children for the same namespace
+        let other = ExecutionContext::new(Utc.timestamp_opt(1000000, 0).unwrap(), 43);
+        assert_ne!(other.clone_with_namespaced_seed("fees").random_seed(), fees_a.random_seed());
+    }
+}
+
+use dtre::{ConflictResolution, MergeOptions};
+use dtre::error::ValidationError;
+
+#[cfg(test)]
+mod merge_tests {
+    use super::*;
+    
+    fn base() -> ExecutionContext {
+        ExecutionContext::builder()
+            .with_time(Utc.timestamp_opt(1000000, 0).unwrap())
+            .with_random_seed(1)
+            .with_external_fact("fx_rate".to_string(), 100i64)
+            .with_external_fact("fee_bps".to_string(), 25i64)
+            .with_external_entity("acct-1".to_string(), "base".to_string())
+            .build()
+    }
+    
+    fn tenant() -> ExecutionContext {
+        ExecutionContext::builder()
+            .with_time(Utc.timestamp_opt(2000000, 0).unwrap())
+            .with_random_seed(2)
+            .with_external_fact("fx_rate".to_string(), 110i64)
+            .with_external_entity("acct-2".to_string(), "tenant".to_string())
+            .build()
+    }
+    
+    #[test]
+    fn test_merge_errors_on_duplicate_fact() {
+        let result = ExecutionContext::merge(base(), tenant(), MergeOptions::default());
+        
+        match result {
+            Err(ValidationError::DuplicateFact { key }) => assert_eq!(key, "fx_rate"),
+            other => panic!("expected DuplicateFact, got {:?}", other.map(|_| ())),
+        }
+    }
+    
+    #[test]
+    fn test_merge_override_keeps_override_values() {
+        let options = MergeOptions {
+            fact_conflict: ConflictResolution::Override,
+            entity_conflict: ConflictResolution::Error,
+        };
+        let merged = ExecutionContext::merge(base(), tenant(), options).unwrap();
+        
+        assert_eq!(merged.now(), Utc.timestamp_opt(2000000, 0).unwrap());
+        assert_eq!(merged.random_seed(), 2);
+        assert_eq!(merged.get_external_fact::<i64>("fx_rate"), Some(&110));
+        assert_eq!(merged.get_external_fact::<i64>("fee_bps"), Some(&25));
+        assert_eq!(merged.resolve_entity::<String>("acct-1").unwrap(), "base");
+        assert_eq!(merged.resolve_entity::<String>("acct-2").unwrap(), "tenant");
+    }
+    
+    #[test]
+    fn test_merge_entity_conflicts_and_ordering_union() {
+        let with_entity = ExecutionContext::builder()
+            .with_time(Utc.timestamp_opt(2000000, 0).unwrap())
+            .with_random_seed(2)
+            .with_external_entity("acct-1".to_string(), "tenant".to_string())
+            .build();
+        let options = MergeOptions {
+            fact_conflict: ConflictResolution::Override,
+            entity_conflict: ConflictResolution::Error,
+        };
+        assert!(matches!(
+            ExecutionContext::merge(base(), with_entity.clone(), options),
+            Err(ValidationError::DuplicateEntity { entity_id }) if entity_id == "acct-1"
+        ));
+        
+        let mut base_orderings = dtre::OrderingRules::new();
+        base_orderings.add_ordering("accounts".to_string(), vec!["a".to_string(), "b".to_string()]);
+        base_orderings.add_ordering("fees".to_string(), vec!["x".to_string()]);
+        let mut tenant_orderings = dtre::OrderingRules::new();
+        tenant_orderings.add_ordering("accounts".to_string(), vec!["b".to_string(), "a".to_string()]);
+        
+        let merged = ExecutionContext::merge(
+            ExecutionContext::builder().with_ordering_rules(base_orderings).build(),
+            ExecutionContext::builder().with_ordering_rules(tenant_orderings).build(),
+            MergeOptions::default(),
+        ).unwrap();
+        
+        assert_eq!(merged.ordering_rules().len(), 2);
+        assert_eq!(merged.ordering_rules().get_ordering("accounts").unwrap(), &vec!["b".to_string(), "a".to_string()]);
+        assert_eq!(merged.ordering_rules().get_ordering("fees").unwrap(), &vec!["x".to_string()]);
+    }
+}
+
+use dtre::ManualClock;
+
+#[cfg(test)]
+mod clock_tests {
+    use super::*;
+    use chrono::Duration;
+    
+    #[test]
+    fn test_manual_clock_drives_context_time() {
+        let start = Utc.timestamp_opt(1000000, 0).unwrap();
+        let clock = ManualClock::new(start);
+        let context = ExecutionContext::builder()
+            .with_time(Utc.timestamp_opt(5, 0).unwrap())
+            .with_manual_clock(clock.clone())
+            .build();
+        assert_eq!(context.now(), start);
+        
+        clock.tick(Duration::hours(2));
+        assert_eq!(context.now(), start + Duration::hours(2));
+        assert_eq!(context.clone().now(), start + Duration::hours(2));
+        
+        let later = Utc.timestamp_opt(9000000, 0).unwrap();
+        clock.set(later);
+        assert_eq!(context.now(), later);
+        
+        // Without a tick configured, advancing is a no-op
+        context.tick_clock();
+        assert_eq!(context.now(), later);
+    }
+    
+    #[test]
+    fn test_with_tick_advances_only_when_told() {
+        let start = Utc.timestamp_opt(1000000, 0).unwrap();
+        let time = DeterministicTime::new(start).with_tick(Duration::days(1));
+        assert_eq!(time.current(), start);
+        assert_eq!(time.current(), start);
+        
+        time.advance();
+        time.advance();
+        assert_eq!(time.current(), start + Duration::days(2));
+        assert_eq!(time.manual_clock().now(), start + Duration::days(2));
+        
+        // with_time detaches from the clock again
+        let frozen = time.with_time(start);
+        time.advance();
+        assert_eq!(frozen.current(), start);
+        
+        let context = ExecutionContext::builder()
+            .with_time(start)
+            .with_tick_per_transaction(Duration::minutes(5))
+            .build();
+        context.tick_clock();
+        assert_eq!(context.now(), start + Duration::minutes(5));
+    }
+}
+
+#[cfg(test)]
+mod debug_mode_tests {
+    use super::*;
+    use dtre::{DebugMode, ExecutionPhase};
+    
+    #[test]
+    fn test_debug_messages_are_captured_only_when_enabled() {
+        let context = ExecutionContext::new(Utc.timestamp_opt(1000000, 0).unwrap(), 42);
+        assert_eq!(context.debug_mode(), DebugMode::Disabled);
+        context.debug_println("dropped");
+        assert!(context.debug_log().is_empty());
+        
+        // Derived contexts share the log with the context they came from
+        let debugging = context.with_debug_mode(DebugMode::Enabled);
+        debugging.debug_println("first");
+        debugging.with_phase(ExecutionPhase::MainProcessing).debug_println("second");
+        assert_eq!(debugging.debug_log(), vec!["first", "second"]);
+        assert!(context.debug_log().is_empty());
+        
+        let silenced = debugging.with_debug_mode(DebugMode::Disabled);
+        silenced.debug_println("third");
+        assert!(silenced.debug_log().is_empty());
+        assert_eq!(debugging.debug_log().len(), 2);
+    }
+}
@@ -0,0 +1,129 @@
+//! Replay fixture generated by `ReplayEngine::generate_test_fixtures`
+//!
+//! Rule set version 1.0.0. Regenerate the fixture instead of editing it.
+
+use dtre::{parse_fixture_json, ExecutionContext, ReplayEngine, ReplayEngineBuilder, ReproducibilityConfig, State};
+
+type FixtureState = crate::BankingState;
+type FixtureTransaction = crate::TransferTransaction;
+type FixtureRuleSet = crate::TransferRulesV1;
+
+const INITIAL_STATE_JSON: &str = r#"{
+  "accounts": {
+    "ACC001": {
+      "account_id": "ACC001",
+      "balance": 100000,
+      "currency": "USD",
+      "status": "Active"
+    },
+    "ACC002": {
+      "account_id": "ACC002",
+      "balance": 50000,
+      "currency": "USD",
+      "status": "Active"
+    },
+    "ACC003": {
+      "account_id": "ACC003",
+      "balance": 200000,
+      "currency": "USD",
+      "status": "Active"
+    }
+  },
+  "total_fees_collected": 0,
+  "transaction_history": []
+}"#;
+
+const TRANSACTIONS_JSON: &str = r#"[
+  {
+    "amount": 10000,
+    "currency": "USD",
+    "description": "Payment for services",
+    "from_account": "ACC001",
+    "id": "TXN001",
+    "timestamp": "2024-01-01T00:00:00Z",
+    "to_account": "ACC002"
+  },
+  {
+    "amount": 25000,
+    "currency": "USD",
+    "description": "Rent payment",
+    "from_account": "ACC002",
+    "id": "TXN002",
+    "timestamp": "2024-01-01T00:01:00Z",
+    "to_account": "ACC003"
+  },
+  {
+    "amount": 50000,
+    "currency": "USD",
+    "description": "Refund",
+    "from_account": "ACC003",
+    "id": "TXN003",
+    "timestamp": "2024-01-01T00:02:00Z",
+    "to_account": "ACC001"
+  }
+]"#;
+
+const CONTEXT_JSON: &str = r#"{
+  "custom_orderings": {},
+  "enforce_stable_ordering": true,
+  "random_seed": 42,
+  "time": "2024-01-01T00:00:00Z"
+}"#;
+
+pub const EXPECTED_FINAL_HASH: &str = "be375bd02db6c149a2f046bd55723bf82b0243883b99f1503ef80a39c182fb7d";
+
+pub const EXPECTED_TRANSACTIONS_PROCESSED: usize = 3;
+
+pub const EXPECTED_APPLICATION_ORDER: &[&str] = &[
+    "TXN001",
+    "TXN002",
+    "TXN003",
+];
+
+fn initial_state() -> FixtureState {
+    parse_fixture_json(INITIAL_STATE_JSON).expect("fixture initial state")
+}
+
+fn transactions() -> Vec<FixtureTransaction> {
+    parse_fixture_json(TRANSACTIONS_JSON).expect("fixture transactions")
+}
+
+fn engine() -> ReplayEngine<FixtureState, FixtureTransaction, FixtureRuleSet> {
+    let config: ReproducibilityConfig = parse_fixture_json(CONTEXT_JSON).expect("fixture context");
+    ReplayEngineBuilder::new()
+        .with_initial_state(initial_state())
+        .with_rule_set(FixtureRuleSet::default())
+        .with_context(ExecutionContext::from_reproducibility_config(config))
+        .build()
+        .expect("fixture engine")
+}
+
+#[test]
+fn fixture_replay_is_deterministic() {
+    let engine = engine();
+    let first = engine.replay(&transactions()).unwrap();
+    let second = engine.replay(&transactions()).unwrap();
+    assert_eq!(first.final_hash, second.final_hash);
+    assert_eq!(first.execution_trace.state_transitions.len(), second.execution_trace.state_transitions.len());
+}
+
+#[test]
+fn fixture_states_are_valid() {
+    initial_state().validate().unwrap();
+    let result = engine().replay(&transactions()).unwrap();
+    result.final_state.validate().unwrap();
+}
+
+#[test]
+fn fixture_final_hash_matches() {
+    let result = engine().replay(&transactions()).unwrap();
+    assert_eq!(result.final_hash.to_string(), EXPECTED_FINAL_HASH);
+}
+
+#[test]
+fn fixture_trace_matches() {
+    let trace = engine().replay(&transactions()).unwrap().execution_trace;
+    assert_eq!(trace.transactions_processed, EXPECTED_TRANSACTIONS_PROCESSED);
+    let order: Vec<&str> = trace.rule_applications.iter().map(|a| a.transaction_id.as_str()).collect();
+    assert_eq!(order, EXPECTED_APPLICATION_ORDER);
+}
@@ -271,3 +271,167 @@ proptest! {
         prop_assert_eq!(logger.len(), entries.len());
     }
 }
+
+#[cfg(test)]
+mod simulated_replay_tests {
+    use super::*;
+    use dtre::{
+        ExecutionContext, ProcessingError, RuleSet, SimulatedTransaction, State, Transaction,
+        TransactionProcessor, ValidationError,
+    };
+    use serde::{Deserialize, Serialize};
+    use std::cell::RefCell;
+    
+    #[derive(Debug, Clone, Hash, Serialize, Deserialize)]
+    struct Ledger {
+        balance: i64,
+        last_deposit: Option<String>,
+    }
+    
+    impl State for Ledger {
+        fn validate(&self) -> Result<(), ValidationError> {
+            Ok(())
+        }
+    }
+    
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    struct Deposit {
+        id: String,
+        amount: i64,
+        timestamp: DateTime<Utc>,
+    }
+    
+    impl Transaction for Deposit {
+        fn id(&self) -> &str {
+            &self.id
+        }
+        
+        fn timestamp(&self) -> DateTime<Utc> {
+            self.timestamp
+        }
+        
+        fn validate(&self) -> Result<(), ValidationError> {
+            Ok(())
+        }
+    }
+    
+    /// Deposits with a fee drawn from the seeded random source
+    struct DepositRules;
+    
+    impl DepositRules {
+        fn deposit(state: &Ledger, id: &str, amount: i64, context: &ExecutionContext) -> Ledger {
+            let fee = (context.random_seed() % 7) as i64;
+            Ledger {
+                balance: state.balance + amount - fee,
+                last_deposit: Some(id.to_string()),
+            }
+        }
+    }
+    
+    impl RuleSet<Ledger, Deposit> for DepositRules {
+        fn version(&self) -> Version {
+            Version::new(1, 0, 0)
+        }
+        
+        fn apply(&self, state: &Ledger, transaction: &Deposit, context: &ExecutionContext) -> Result<Ledger, ProcessingError> {
+            Ok(Self::deposit(state, &transaction.id, transaction.amount, context))
+        }
+    }
+    
+    impl RuleSet<Ledger, SimulatedTransaction> for DepositRules {
+        fn version(&self) -> Version {
+            Version::new(1, 0, 0)
+        }
+        
+        fn apply(&self, state: &Ledger, transaction: &SimulatedTransaction, context: &ExecutionContext) -> Result<Ledger, ProcessingError> {
+            let amount = transaction.get("amount").and_then(|a| a.parse().ok()).ok_or_else(|| {
+                ProcessingError::TransactionFailed {
+                    transaction_id: transaction.id.clone(),
+                    reason: "Captured event has no amount".to_string(),
+                }
+            })?;
+            Ok(Self::deposit(state, &transaction.id, amount, context))
+        }
+    }
+    
+    fn initial() -> Ledger {
+        Ledger { balance: 0, last_deposit: None }
+    }
+    
+    /// Run deposits in "production", capturing a trace log of the run
+    fn capture(deposits: &[Deposit], seed: u64) -> ExecutionTraceLog {
+        let start = Utc.timestamp_opt(1_700_000_000, 0).unwrap();
+        let context = ExecutionContext::new(start, seed);
+        let mut processor = TransactionProcessor::new(initial()).unwrap();
+        let mut log = ExecutionTraceLog::new(start);
+        log.add_log(
+            LogEntry::new(LogLevel::Info, start, "Replay started".to_string())
+                .with_metadata("random_seed".to_string(), seed.to_string()),
+        );
+        
+        for (index, deposit) in deposits.iter().enumerate() {
+            let event = |event_type, data| TraceEvent {
+                timestamp: deposit.timestamp,
+                event_type,
+                transaction_id: Some(deposit.id.clone()),
+                transaction_index: Some(index),
+                state_hash_before: None,
+                state_hash_after: None,
+                data,
+            };
+            log.add_event(event(TraceEventType::TransactionStarted, vec![("amount".to_string(), deposit.amount.to_string())]));
+            processor.process_transaction(deposit, &DepositRules, &context).unwrap();
+            log.add_event(TraceEvent {
+                state_hash_after: Some(processor.current_hash()),
+                ..event(TraceEventType::TransactionCompleted, Vec::new())
+            });
+        }
+        log
+    }
+    
+    #[test]
+    fn test_replaying_captured_log_reproduces_hash() {
+        let deposits: Vec<Deposit> = (0..5)
+            .map(|i| Deposit {
+                id: format!("dep-{}", i),
+                amount: 100 * (i + 1),
+                timestamp: Utc.timestamp_opt(1_700_000_000 + i, 0).unwrap(),
+            })
+            .collect();
+        let log = capture(&deposits, 45);
+        
+        let fired = RefCell::new(Vec::new());
+        DeterministicLogger::replay_log(&log, |event| fired.borrow_mut().push(event.event_type));
+        assert_eq!(fired.borrow().len(), 10);
+        assert_eq!(fired.borrow()[0], TraceEventType::TransactionStarted);
+        assert_eq!(fired.borrow()[1], TraceEventType::TransactionCompleted);
+        
+        let simulated = log.to_simulated_transactions();
+        assert_eq!(simulated.len(), 5);
+        assert_eq!(simulated[2].get("amount"), Some("300"));
+        assert_eq!(simulated[2].transaction_index, Some(2));
+        
+        let context = log.logs[0].to_simulated_context().expect("entry records the seed");
+        assert_eq!(context.random_seed(), 45);
+        assert_eq!(context.now(), log.start_time);
+        
+        let mut processor = TransactionProcessor::new(initial()).unwrap();
+        processor.process_transactions(&simulated, &DepositRules, &context).unwrap();
+        let captured_hash = log.events_by_type(TraceEventType::TransactionCompleted).last().unwrap().state_hash_after;
+        assert_eq!(Some(processor.current_hash()), captured_hash);
+    }
+    
+    #[test]
+    fn test_simulated_context_needs_seed() {
+        let time = Utc.timestamp_opt(1_700_000_000, 0).unwrap();
+        let bare = LogEntry::new(LogLevel::Info, time, "no seed".to_string());
+        assert!(bare.to_simulated_context().is_none());
+        
+        let context = bare
+            .with_metadata("random_seed".to_string(), "9".to_string())
+            .with_metadata("fact.region".to_string(), "eu".to_string())
+            .to_simulated_context()
+            .unwrap();
+        assert_eq!(context.get_external_fact::<String>("region").map(String::as_str), Some("eu"));
+    }
+}
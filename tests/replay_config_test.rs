@@ -0,0 +1,157 @@
+#![cfg(feature = "toml")]
+
+use dtre::{
+    ExecutionContext, LogLevel, ProcessingError, ReplayConfig, ReplayEngine, ReplayEngineBuilder, RuleSet, State,
+    Transaction, ValidationError, Version,
+};
+use chrono::{DateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Hash)]
+struct CounterState {
+    value: i64,
+}
+
+impl State for CounterState {
+    fn validate(&self) -> Result<(), ValidationError> {
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct IncrementTransaction {
+    id: String,
+    timestamp: DateTime<Utc>,
+}
+
+impl Transaction for IncrementTransaction {
+    fn id(&self) -> &str {
+        &self.id
+    }
+    
+    fn timestamp(&self) -> DateTime<Utc> {
+        self.timestamp
+    }
+    
+    fn validate(&self) -> Result<(), ValidationError> {
+        Ok(())
+    }
+}
+
+struct ScaledIncrementRules;
+
+impl RuleSet<CounterState, IncrementTransaction> for ScaledIncrementRules {
+    fn version(&self) -> Version {
+        Version::new(1, 0, 0)
+    }
+    
+    fn apply(
+        &self,
+        state: &CounterState,
+        _transaction: &IncrementTransaction,
+        context: &ExecutionContext,
+    ) -> Result<CounterState, ProcessingError> {
+        let step = context
+            .get_external_fact::<serde_json::Value>("step")
+            .and_then(|v| v.as_i64())
+            .unwrap_or(1);
+        Ok(CounterState { value: state.value + step })
+    }
+}
+
+fn sample_config() -> ReplayConfig {
+    let mut config = ReplayConfig::new(Utc.timestamp_opt(1_700_000_000, 0).unwrap(), 42);
+    config.checkpoint_interval = Some(5);
+    config.max_state_size_bytes = Some(4096);
+    config.deduplication_enabled = true;
+    config.log_level = LogLevel::Debug;
+    config.dry_run_checkpoints = true;
+    config.external_facts.insert("step".to_string(), serde_json::json!(3));
+    config
+}
+
+#[test]
+fn test_toml_round_trip() {
+    let config = sample_config();
+    let toml = config.to_toml();
+    let parsed = ReplayConfig::from_toml(&toml).unwrap();
+    
+    assert_eq!(parsed, config);
+}
+
+#[test]
+fn test_toml_defaults_for_optional_fields() {
+    let toml = r#"
+        random_seed = 7
+        replay_time = "2024-01-01T00:00:00Z"
+    "#;
+    let config = ReplayConfig::from_toml(toml).unwrap();
+    
+    assert_eq!(config.random_seed, 7);
+    assert_eq!(config.checkpoint_interval, None);
+    assert!(!config.deduplication_enabled);
+    assert!(!config.dry_run_checkpoints);
+    assert_eq!(config.log_level, LogLevel::Info);
+    assert!(config.external_facts.is_empty());
+}
+
+#[test]
+fn test_invalid_toml_is_rejected() {
+    assert!(ReplayConfig::from_toml("random_seed = \"not a number\"").is_err());
+}
+
+#[test]
+fn test_builder_from_round_tripped_toml_matches_manual_builder() {
+    let config = sample_config();
+    let parsed = ReplayConfig::from_toml(&config.to_toml()).unwrap();
+    
+    let from_config = ReplayEngineBuilder::from_config(&parsed)
+        .with_initial_state(CounterState { value: 0 })
+        .with_rule_set(ScaledIncrementRules)
+        .build()
+        .unwrap();
+    
+    let manual_context = ExecutionContext::builder()
+        .with_time(Utc.timestamp_opt(1_700_000_000, 0).unwrap())
+        .with_random_seed(42)
+        .with_external_fact("step".to_string(), serde_json::json!(3))
+        .build();
+    let manual = ReplayEngine::builder()
+        .with_initial_state(CounterState { value: 0 })
+        .with_rule_set(ScaledIncrementRules)
+        .with_context(manual_context)
+        .with_checkpoint_interval(5)
+        .with_max_state_size_bytes(4096)
+        .with_deduplication(true)
+        .with_log_level(LogLevel::Debug)
+        .with_dry_run_checkpoints(true)
+        .build()
+        .unwrap();
+    
+    assert_eq!(from_config.checkpoint_interval(), manual.checkpoint_interval());
+    assert_eq!(from_config.max_state_size_bytes(), manual.max_state_size_bytes());
+    assert_eq!(from_config.deduplication_enabled(), manual.deduplication_enabled());
+    assert_eq!(from_config.log_level(), manual.log_level());
+    assert_eq!(from_config.dry_run_checkpoints(), manual.dry_run_checkpoints());
+    assert_eq!(from_config.context().fingerprint(), manual.context().fingerprint());
+    
+    let transactions: Vec<IncrementTransaction> = (0..4)
+        .map(|i| IncrementTransaction {
+            id: format!("tx{}", i),
+            timestamp: Utc.timestamp_opt(1_700_000_000 + i, 0).unwrap(),
+        })
+        .collect();
+    let config_result = from_config.replay(&transactions).unwrap();
+    let manual_result = manual.replay(&transactions).unwrap();
+    assert_eq!(config_result.final_state.value, 12);
+    assert_eq!(config_result.final_hash, manual_result.final_hash);
+}
+
+#[cfg(feature = "yaml")]
+#[test]
+fn test_yaml_round_trip() {
+    let config = sample_config();
+    let parsed = ReplayConfig::from_yaml(&config.to_yaml()).unwrap();
+    
+    assert_eq!(parsed, config);
+}
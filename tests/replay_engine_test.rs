@@ -268,6 +268,7 @@ proptest! {
     /// For any transaction sequence, parallel execution should produce identical results 
     /// to sequential execution.
     #[test]
+    #[allow(deprecated)]
     fn property_parallel_execution_determinism(
         initial_state in arbitrary_test_state(),
         transactions in prop::collection::vec(arbitrary_test_transaction(), 1..50),
@@ -470,6 +471,7 @@ mod unit_tests {
     }
     
     #[test]
+    #[allow(deprecated)]
     fn test_parallel_execution_matches_sequential() {
         let initial_state = TestState {
             balance: 1000,
@@ -538,6 +540,7 @@ mod unit_tests {
     }
     
     #[test]
+    #[allow(deprecated)]
     fn test_parallel_execution_with_large_transaction_set() {
         let initial_state = TestState {
             balance: 0,
@@ -586,6 +589,7 @@ mod unit_tests {
     }
     
     #[test]
+    #[allow(deprecated)]
     fn test_parallel_execution_with_small_transaction_set_uses_sequential() {
         let initial_state = TestState {
             balance: 100,
@@ -708,6 +712,7 @@ proptest! {
             hash: partial_result.final_hash,  // Use the final hash, not checkpoint hash
             transaction_index: first_half.len(),  // Use the actual number of transactions processed
             timestamp: time,
+            state_schema_version: TestState::SCHEMA_VERSION,
         };
         
         // Resume from checkpoint with remaining transactions
@@ -871,3 +876,1418 @@ proptest! {
         }
     }
 }
+
+#[cfg(test)]
+mod pre_flight_validation_tests {
+    use super::*;
+    use dtre::TransactionSequenceValidator;
+    
+    #[test]
+    fn test_pre_flight_validation_rejects_duplicates() {
+        let time = Utc.timestamp_opt(1_000_000, 0).unwrap();
+        let engine = ReplayEngine::builder()
+            .with_initial_state(TestState { balance: 100, transaction_count: 0 })
+            .with_rule_set(TestRuleSet { version: Version::new(1, 0, 0) })
+            .with_time_and_seed(time, 42)
+            .with_pre_flight_validation(TransactionSequenceValidator::new())
+            .build()
+            .unwrap();
+        
+        let duplicated = vec![
+            TestTransaction { id: "tx1".to_string(), amount: 10, timestamp: time },
+            TestTransaction { id: "tx1".to_string(), amount: 10, timestamp: time },
+        ];
+        let result = engine.replay(&duplicated);
+        assert!(matches!(result, Err(ProcessingError::PreFlightValidationFailed { .. })));
+        
+        // Out-of-order timestamps only warn, so replay proceeds
+        let out_of_order = vec![
+            TestTransaction { id: "tx1".to_string(), amount: 10, timestamp: time },
+            TestTransaction { id: "tx2".to_string(), amount: 10, timestamp: time - chrono::Duration::seconds(5) },
+        ];
+        let result = engine.replay(&out_of_order).unwrap();
+        assert_eq!(result.final_state.balance, 120);
+    }
+}
+
+#[cfg(test)]
+mod baseline_comparison_tests {
+    use super::*;
+    use dtre::{ComparisonTolerance, ReplayEngineBuilder};
+    
+    #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+    struct LedgerState {
+        balance: i64,
+        total_fees_collected: i64,
+    }
+    
+    impl Hash for LedgerState {
+        fn hash<H: Hasher>(&self, state: &mut H) {
+            self.balance.hash(state);
+            self.total_fees_collected.hash(state);
+        }
+    }
+    
+    impl State for LedgerState {
+        fn validate(&self) -> Result<(), ValidationError> {
+            Ok(())
+        }
+    }
+    
+    /// Credits each deposit in full and charges a flat fee on top; deposits
+    /// above `limit` are rejected
+    #[derive(Clone, Debug)]
+    struct FeeRules {
+        version: Version,
+        fee: i64,
+        limit: i64,
+    }
+    
+    impl RuleSet<LedgerState, TestTransaction> for FeeRules {
+        fn version(&self) -> Version {
+            self.version.clone()
+        }
+        
+        fn apply(
+            &self,
+            state: &LedgerState,
+            transaction: &TestTransaction,
+            _context: &ExecutionContext,
+        ) -> Result<LedgerState, ProcessingError> {
+            if transaction.amount > self.limit {
+                return Err(ProcessingError::RuleApplicationFailed {
+                    rule_version: self.version(),
+                    details: "deposit over limit".to_string(),
+                });
+            }
+            Ok(LedgerState {
+                balance: state.balance + transaction.amount,
+                total_fees_collected: state.total_fees_collected + self.fee,
+            })
+        }
+    }
+    
+    fn engine(rules: FeeRules) -> ReplayEngine<LedgerState, TestTransaction, FeeRules> {
+        ReplayEngineBuilder::new()
+            .with_initial_state(LedgerState { balance: 0, total_fees_collected: 0 })
+            .with_rule_set(rules)
+            .with_time_and_seed(Utc.timestamp_opt(1_000_000, 0).unwrap(), 42)
+            .build()
+            .unwrap()
+    }
+    
+    fn deposits(amounts: &[i64]) -> Vec<TestTransaction> {
+        amounts
+            .iter()
+            .enumerate()
+            .map(|(i, &amount)| TestTransaction {
+                id: format!("tx{}", i),
+                amount,
+                timestamp: Utc.timestamp_opt(1_000_000 + i as i64, 0).unwrap(),
+            })
+            .collect()
+    }
+    
+    fn rules(major: u32, fee: i64, limit: i64) -> FeeRules {
+        FeeRules { version: Version::new(major, 0, 0), fee, limit }
+    }
+    
+    #[test]
+    fn test_ignore_fields_lets_fee_migration_pass() {
+        let engine = engine(rules(1, 1, 1000));
+        let transactions = deposits(&[10, 20, 30]);
+        let new_rules = rules(2, 5, 1000);
+        
+        let exact = engine
+            .compare_with_baseline(&transactions, &new_rules, ComparisonTolerance::ExactMatch)
+            .unwrap();
+        assert!(!exact.passed);
+        assert_eq!(exact.diverging_transactions, vec![0, 1, 2]);
+        assert_eq!(exact.exit_code(), 1);
+        assert!(exact.performance_comparison.is_some());
+        
+        let tolerant = engine
+            .compare_with_baseline(
+                &transactions,
+                &new_rules,
+                ComparisonTolerance::IgnoreFields(vec!["total_fees_collected".to_string()]),
+            )
+            .unwrap();
+        assert!(tolerant.passed);
+        assert!(tolerant.diverging_transactions.is_empty());
+        assert_eq!(tolerant.exit_code(), 0);
+        assert_eq!(tolerant.comparison_final_state.total_fees_collected, 15);
+    }
+    
+    #[test]
+    fn test_new_and_resolved_failures() {
+        let engine = engine(rules(1, 0, 100));
+        let transactions = deposits(&[50, 150, 80]);
+        
+        // A stricter limit rejects tx0 and tx2, which succeeded before
+        let stricter = engine
+            .compare_with_baseline(&transactions, &rules(2, 0, 40), ComparisonTolerance::IgnorePerformance)
+            .unwrap();
+        assert_eq!(stricter.new_failures, vec!["tx0", "tx2"]);
+        assert!(stricter.resolved_failures.is_empty());
+        assert_eq!(stricter.exit_code(), 2);
+        assert!(stricter.performance_comparison.is_none());
+        
+        // A looser limit accepts tx1, which failed before
+        let looser = engine
+            .compare_with_baseline(&transactions, &rules(2, 0, 1000), ComparisonTolerance::IgnorePerformance)
+            .unwrap();
+        assert!(looser.new_failures.is_empty());
+        assert_eq!(looser.resolved_failures, vec!["tx1"]);
+        assert_eq!(looser.diverging_transactions, vec![1, 2]);
+        assert_eq!(looser.exit_code(), 1);
+    }
+}
+
+#[cfg(test)]
+mod replay_range_tests {
+    use super::*;
+    
+    fn engine() -> ReplayEngine<TestState, TestTransaction, TestRuleSet> {
+        ReplayEngine::new(
+            TestState { balance: 0, transaction_count: 0 },
+            TestRuleSet { version: Version::new(1, 0, 0) },
+            ExecutionContext::new(Utc.timestamp_opt(1_000_000, 0).unwrap(), 42),
+        )
+    }
+    
+    fn transactions(count: i64) -> Vec<TestTransaction> {
+        (0..count)
+            .map(|i| TestTransaction {
+                id: format!("tx{}", i),
+                amount: i + 1,
+                timestamp: Utc.timestamp_opt(1_000_000 + i, 0).unwrap(),
+            })
+            .collect()
+    }
+    
+    #[test]
+    fn test_full_range_matches_replay() {
+        let engine = engine();
+        let txns = transactions(50);
+        
+        let full = engine.replay(&txns).unwrap();
+        let ranged = engine.replay_range(&txns, 0, txns.len()).unwrap();
+        
+        assert_eq!(ranged.final_hash, full.final_hash);
+        assert_eq!(ranged.final_state, full.final_state);
+        assert_eq!(ranged.execution_trace.transactions_processed, 50);
+    }
+    
+    #[test]
+    fn test_partitioned_replay_from_checkpoint() {
+        let engine = engine();
+        let txns = transactions(1000);
+        
+        // Build a checkpoint at index 500 by replaying the first partition
+        let mut processor = dtre::TransactionProcessor::new(engine.initial_state().clone()).unwrap();
+        processor.process_transactions(&txns[..500], engine.rule_set(), engine.context()).unwrap();
+        let checkpoint = processor.create_checkpoint(txns[499].timestamp);
+        
+        let partition = engine.replay_range_from_checkpoint(&checkpoint, &txns, 500, 750).unwrap();
+        let expected = engine.replay(&txns[..750]).unwrap();
+        
+        assert_eq!(partition.final_hash, expected.final_hash);
+        assert_eq!(partition.execution_trace.state_transitions.len(), 250);
+        assert_eq!(partition.execution_trace.state_transitions[0].transaction_id, "tx500");
+    }
+    
+    #[test]
+    fn test_empty_and_invalid_ranges() {
+        let engine = engine();
+        let txns = transactions(10);
+        
+        let empty = engine.replay_range(&txns, 5, 5).unwrap();
+        assert_eq!(empty.final_state, *engine.initial_state());
+        
+        assert!(matches!(
+            engine.replay_range(&txns, 6, 5),
+            Err(ProcessingError::InvalidRange { start: 6, end: 5, len: 10 })
+        ));
+        assert!(matches!(
+            engine.replay_range(&txns, 0, 11),
+            Err(ProcessingError::InvalidRange { end: 11, .. })
+        ));
+    }
+}
+
+#[cfg(test)]
+mod heterogeneous_transaction_tests {
+    use super::*;
+    use dtre::{AnyTransaction, TransactionDispatcher};
+    
+    #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Hash)]
+    struct AccountState {
+        balance: i64,
+        closed: bool,
+    }
+    
+    impl State for AccountState {
+        fn validate(&self) -> Result<(), ValidationError> {
+            if self.balance < 0 {
+                return Err(ValidationError::InvalidState {
+                    reason: "Balance cannot be negative".to_string(),
+                });
+            }
+            Ok(())
+        }
+    }
+    
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    struct Deposit {
+        id: String,
+        amount: i64,
+        timestamp: DateTime<Utc>,
+    }
+    
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    struct Withdrawal {
+        id: String,
+        amount: i64,
+        fee: i64,
+        timestamp: DateTime<Utc>,
+    }
+    
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    struct Closure {
+        id: String,
+        timestamp: DateTime<Utc>,
+    }
+    
+    macro_rules! impl_transaction {
+        ($ty:ty) => {
+            impl Transaction for $ty {
+                fn id(&self) -> &str {
+                    &self.id
+                }
+                
+                fn timestamp(&self) -> DateTime<Utc> {
+                    self.timestamp
+                }
+                
+                fn validate(&self) -> Result<(), ValidationError> {
+                    Ok(())
+                }
+            }
+        };
+    }
+    
+    impl_transaction!(Deposit);
+    impl_transaction!(Withdrawal);
+    impl_transaction!(Closure);
+    
+    struct DepositRules;
+    struct WithdrawalRules;
+    struct ClosureRules;
+    
+    fn ensure_open(state: &AccountState, tx: &AnyTransaction<AccountState>) -> Result<(), ProcessingError> {
+        if state.closed {
+            return Err(ProcessingError::TransactionFailed {
+                transaction_id: tx.id().to_string(),
+                reason: "account is closed".to_string(),
+            });
+        }
+        Ok(())
+    }
+    
+    impl RuleSet<AccountState, AnyTransaction<AccountState>> for DepositRules {
+        fn version(&self) -> Version {
+            Version::new(1, 0, 0)
+        }
+        
+        fn apply(
+            &self,
+            state: &AccountState,
+            transaction: &AnyTransaction<AccountState>,
+            _context: &ExecutionContext,
+        ) -> Result<AccountState, ProcessingError> {
+            ensure_open(state, transaction)?;
+            let deposit: Deposit = transaction.decode()?;
+            Ok(AccountState { balance: state.balance + deposit.amount, ..state.clone() })
+        }
+    }
+    
+    impl RuleSet<AccountState, AnyTransaction<AccountState>> for WithdrawalRules {
+        fn version(&self) -> Version {
+            Version::new(1, 0, 0)
+        }
+        
+        fn apply(
+            &self,
+            state: &AccountState,
+            transaction: &AnyTransaction<AccountState>,
+            _context: &ExecutionContext,
+        ) -> Result<AccountState, ProcessingError> {
+            ensure_open(state, transaction)?;
+            let withdrawal: Withdrawal = transaction.decode()?;
+            Ok(AccountState { balance: state.balance - withdrawal.amount - withdrawal.fee, ..state.clone() })
+        }
+    }
+    
+    impl RuleSet<AccountState, AnyTransaction<AccountState>> for ClosureRules {
+        fn version(&self) -> Version {
+            Version::new(1, 0, 0)
+        }
+        
+        fn apply(
+            &self,
+            state: &AccountState,
+            transaction: &AnyTransaction<AccountState>,
+            _context: &ExecutionContext,
+        ) -> Result<AccountState, ProcessingError> {
+            ensure_open(state, transaction)?;
+            Ok(AccountState { balance: 0, closed: true })
+        }
+    }
+    
+    fn dispatcher() -> TransactionDispatcher<AccountState> {
+        let mut dispatcher = TransactionDispatcher::new(Version::new(1, 0, 0));
+        dispatcher.register_type("deposit", DepositRules);
+        dispatcher.register_type("withdrawal", WithdrawalRules);
+        dispatcher.register_type("closure", ClosureRules);
+        dispatcher
+    }
+    
+    fn at(offset: i64) -> DateTime<Utc> {
+        Utc.timestamp_opt(1_000_000 + offset, 0).unwrap()
+    }
+    
+    fn mixed_log() -> Vec<AnyTransaction<AccountState>> {
+        vec![
+            AnyTransaction::new("deposit", Deposit { id: "tx1".to_string(), amount: 500, timestamp: at(0) }),
+            AnyTransaction::new("withdrawal", Withdrawal { id: "tx2".to_string(), amount: 200, fee: 5, timestamp: at(1) }),
+            AnyTransaction::new("deposit", Deposit { id: "tx3".to_string(), amount: 50, timestamp: at(2) }),
+            AnyTransaction::new("closure", Closure { id: "tx4".to_string(), timestamp: at(3) }),
+        ]
+    }
+    
+    fn engine() -> ReplayEngine<AccountState, AnyTransaction<AccountState>, TransactionDispatcher<AccountState>> {
+        ReplayEngine::new(
+            AccountState { balance: 0, closed: false },
+            dispatcher(),
+            ExecutionContext::new(at(0), 42),
+        )
+    }
+    
+    #[test]
+    fn test_three_transaction_types_in_one_sequence() {
+        let engine = engine();
+        let log = mixed_log();
+        
+        // Stop before the closure to observe the running balance
+        let before_closure = engine.replay(&log[..3]).unwrap();
+        assert_eq!(before_closure.final_state.balance, 345);
+        
+        let result = engine.replay(&log).unwrap();
+        assert_eq!(result.final_state, AccountState { balance: 0, closed: true });
+        assert_eq!(result.execution_trace.transactions_processed, 4);
+        assert_eq!(engine.rule_set().registered_types(), vec!["closure", "deposit", "withdrawal"]);
+    }
+    
+    #[test]
+    fn test_serialized_log_replays_identically() {
+        let engine = engine();
+        let log = mixed_log();
+        
+        let json = serde_json::to_string(&log).unwrap();
+        let restored: Vec<AnyTransaction<AccountState>> = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored[1].type_tag(), "withdrawal");
+        
+        let original = engine.replay(&log).unwrap();
+        let replayed = engine.replay(&restored).unwrap();
+        assert_eq!(replayed.final_hash, original.final_hash);
+    }
+    
+    #[test]
+    fn test_serialized_payload_must_match_outer_id_and_timestamp() {
+        let deposit = AnyTransaction::<AccountState>::new("deposit", Deposit { id: "tx1".to_string(), amount: 5, timestamp: at(0) });
+        let mut json = serde_json::to_value(&deposit).unwrap();
+        let restored: AnyTransaction<AccountState> = serde_json::from_value(json.clone()).unwrap();
+        assert!(restored.validate().is_ok());
+        
+        json["transaction"]["id"] = serde_json::json!("tx2");
+        let tampered: AnyTransaction<AccountState> = serde_json::from_value(json.clone()).unwrap();
+        assert!(matches!(tampered.validate(), Err(ValidationError::InvalidTransaction { .. })));
+        assert!(tampered.decode::<Deposit>().is_err());
+        
+        json["transaction"]["id"] = serde_json::json!("tx1");
+        json["transaction"]["timestamp"] = serde_json::to_value(at(60)).unwrap();
+        let retimed: AnyTransaction<AccountState> = serde_json::from_value(json).unwrap();
+        assert!(matches!(retimed.validate(), Err(ValidationError::InvalidTransaction { .. })));
+        assert!(retimed.decode::<Deposit>().is_err());
+    }
+    
+    #[test]
+    fn test_unregistered_type_fails_replay() {
+        let engine = engine();
+        let mut log = mixed_log();
+        log.insert(1, AnyTransaction::new("chargeback", Deposit { id: "tx9".to_string(), amount: 1, timestamp: at(0) }));
+        
+        match engine.replay(&log) {
+            Err(ProcessingError::UnregisteredTransactionType { transaction_id, type_tag }) => {
+                assert_eq!(transaction_id, "tx9");
+                assert_eq!(type_tag, "chargeback");
+            }
+            other => panic!("expected unregistered type error, got {:?}", other),
+        }
+    }
+}
+
+#[cfg(test)]
+mod transaction_limit_tests {
+    use super::*;
+    use dtre::{ReplayEngineBuilder, TransactionProcessor};
+    
+    fn rule_set() -> TestRuleSet {
+        TestRuleSet { version: Version::new(1, 0, 0) }
+    }
+    
+    fn context() -> ExecutionContext {
+        ExecutionContext::new(Utc.timestamp_opt(1_000_000, 0).unwrap(), 42)
+    }
+    
+    fn initial_state() -> TestState {
+        TestState { balance: 0, transaction_count: 0 }
+    }
+    
+    fn transactions(count: i64) -> Vec<TestTransaction> {
+        (0..count)
+            .map(|i| TestTransaction {
+                id: format!("tx{}", i),
+                amount: i + 1,
+                timestamp: Utc.timestamp_opt(1_000_000 + i, 0).unwrap(),
+            })
+            .collect()
+    }
+    
+    #[test]
+    fn test_processor_stops_at_limit() {
+        let txns = transactions(10);
+        let mut processor = TransactionProcessor::new(initial_state())
+            .unwrap()
+            .with_max_transaction_count(5);
+        
+        let result = processor.process_transactions(&txns, &rule_set(), &context());
+        
+        let mut expected = TransactionProcessor::new(initial_state()).unwrap();
+        expected.process_transactions(&txns[..5], &rule_set(), &context()).unwrap();
+        
+        match result {
+            Err(ProcessingError::TransactionLimitExceeded { limit, attempted, last_state_hash }) => {
+                assert_eq!(limit, 5);
+                assert_eq!(attempted, 6);
+                assert_eq!(last_state_hash, expected.current_hash());
+            }
+            other => panic!("expected TransactionLimitExceeded, got {:?}", other.map(|t| t.len())),
+        }
+        assert_eq!(processor.current_state(), expected.current_state());
+        assert_eq!(processor.transactions_processed(), 5);
+    }
+    
+    #[test]
+    fn test_mutations_do_not_count_towards_limit() {
+        let txns = transactions(3);
+        let mut processor = TransactionProcessor::new(initial_state())
+            .unwrap()
+            .with_max_transaction_count(2);
+        processor.apply_mutation("reset", |_| Ok(initial_state())).unwrap();
+        processor.process_transactions(&txns[..2], &rule_set(), &context()).unwrap();
+        
+        match processor.process_transaction(&txns[2], &rule_set(), &context()) {
+            Err(ProcessingError::TransactionLimitExceeded { limit, attempted, .. }) => {
+                assert_eq!(limit, 2);
+                assert_eq!(attempted, 3);
+            }
+            other => panic!("expected TransactionLimitExceeded, got {:?}", other.map(|t| t.to_hash)),
+        }
+    }
+    
+    #[test]
+    fn test_engine_enforces_max_state_size() {
+        // The test state serializes to 16 bytes
+        let engine = ReplayEngineBuilder::new()
+            .with_initial_state(initial_state())
+            .with_rule_set(rule_set())
+            .with_context(context())
+            .with_max_state_size_bytes(8)
+            .build()
+            .unwrap();
+        
+        match engine.replay(&transactions(3)) {
+            Err(ProcessingError::StateSizeLimitExceeded { transaction_id, size, limit }) => {
+                assert_eq!(transaction_id, "tx0");
+                assert_eq!(size, 16);
+                assert_eq!(limit, 8);
+            }
+            other => panic!("expected StateSizeLimitExceeded, got {:?}", other.map(|r| r.final_hash)),
+        }
+        
+        let roomy = ReplayEngineBuilder::new()
+            .with_initial_state(initial_state())
+            .with_rule_set(rule_set())
+            .with_context(context())
+            .with_max_state_size_bytes(16)
+            .build()
+            .unwrap();
+        assert!(roomy.replay(&transactions(3)).is_ok());
+    }
+    
+    #[test]
+    fn test_engine_enforces_limit() {
+        let engine = ReplayEngineBuilder::new()
+            .with_initial_state(initial_state())
+            .with_rule_set(rule_set())
+            .with_context(context())
+            .with_max_transaction_count(5)
+            .build()
+            .unwrap();
+        assert_eq!(engine.max_transaction_count(), Some(5));
+        
+        let txns = transactions(10);
+        let within_limit = engine.replay(&txns[..5]).unwrap();
+        
+        match engine.replay(&txns) {
+            Err(ProcessingError::TransactionLimitExceeded { attempted, last_state_hash, .. }) => {
+                assert_eq!(attempted, 6);
+                assert_eq!(last_state_hash, within_limit.final_hash);
+            }
+            other => panic!("expected TransactionLimitExceeded, got {:?}", other.map(|r| r.final_hash)),
+        }
+    }
+    
+    #[test]
+    fn test_engine_deduplicates_when_enabled() {
+        let mut txns = transactions(3);
+        txns[2].id = "tx0".to_string();
+        let builder = || {
+            ReplayEngineBuilder::new()
+                .with_initial_state(initial_state())
+                .with_rule_set(rule_set())
+                .with_context(context())
+        };
+        
+        assert!(builder().build().unwrap().replay(&txns).is_ok());
+        match builder().with_deduplication(true).build().unwrap().replay(&txns) {
+            Err(ProcessingError::TransactionFailed { transaction_id, reason }) => {
+                assert_eq!(transaction_id, "tx0");
+                assert!(reason.contains("Duplicate"));
+            }
+            other => panic!("expected a duplicate transaction error, got {:?}", other.map(|r| r.final_hash)),
+        }
+    }
+    
+    #[test]
+    fn test_estimated_memory_usage_grows_with_transactions() {
+        let engine = ReplayEngine::new(initial_state(), rule_set(), context());
+        
+        let empty = engine.estimated_memory_usage(0);
+        let small = engine.estimated_memory_usage(10);
+        let large = engine.estimated_memory_usage(1000);
+        
+        assert!(empty > 0);
+        assert!(small > empty);
+        assert!(large > small);
+        assert_eq!(engine.estimated_memory_usage(usize::MAX), usize::MAX);
+    }
+}
+
+#[cfg(test)]
+mod chunked_parallel_replay_tests {
+    use super::*;
+    
+    fn engine() -> ReplayEngine<TestState, TestTransaction, TestRuleSet> {
+        ReplayEngine::new(
+            TestState { balance: 0, transaction_count: 0 },
+            TestRuleSet { version: Version::new(1, 0, 0) },
+            ExecutionContext::new(Utc.timestamp_opt(1_000_000, 0).unwrap(), 42),
+        )
+    }
+    
+    fn transactions(count: i64) -> Vec<TestTransaction> {
+        (0..count)
+            .map(|i| TestTransaction {
+                id: format!("tx{}", i),
+                amount: i + 1,
+                timestamp: Utc.timestamp_opt(1_000_000 + i, 0).unwrap(),
+            })
+            .collect()
+    }
+    
+    #[test]
+    fn test_chunked_replay_matches_sequential() {
+        let engine = engine();
+        let txns = transactions(250);
+        let sequential = engine.replay(&txns).unwrap();
+        
+        for chunk_size in [0, 1, 7, 100, 250, 1000] {
+            let chunked = engine.replay_parallel_chunked(&txns, chunk_size).unwrap();
+            assert_eq!(chunked.final_state, sequential.final_state, "chunk size {}", chunk_size);
+            assert_eq!(chunked.final_hash, sequential.final_hash, "chunk size {}", chunk_size);
+            assert_eq!(chunked.execution_trace.transactions_processed, 250);
+            assert_eq!(
+                chunked.execution_trace.state_transitions.len(),
+                sequential.execution_trace.state_transitions.len()
+            );
+        }
+    }
+    
+    #[test]
+    fn test_chunked_replay_records_checkpoints_like_replay() {
+        let engine = ReplayEngine::with_checkpointing(
+            TestState { balance: 0, transaction_count: 0 },
+            TestRuleSet { version: Version::new(1, 0, 0) },
+            ExecutionContext::new(Utc.timestamp_opt(1_000_000, 0).unwrap(), 42),
+            10,
+        );
+        let txns = transactions(35);
+        
+        let sequential = engine.replay(&txns).unwrap();
+        let chunked = engine.replay_parallel_chunked(&txns, 4).unwrap();
+        
+        let indices = |result: &dtre::ReplayResult<TestState>| -> Vec<usize> {
+            result.execution_trace.checkpoints.iter().map(|c| c.transaction_index).collect()
+        };
+        assert_eq!(indices(&chunked), vec![10, 20, 30]);
+        assert_eq!(indices(&chunked), indices(&sequential));
+    }
+    
+    #[test]
+    fn test_chunked_replay_of_empty_sequence() {
+        let result = engine().replay_parallel_chunked(&[], 0).unwrap();
+        assert_eq!(result.final_state.balance, 0);
+        assert_eq!(result.execution_trace.transactions_processed, 0);
+    }
+}
+
+#[cfg(test)]
+mod audit_bundle_tests {
+    use super::*;
+    use dtre::{AuditBundleConfig, SigningAlgorithm};
+    
+    fn engine() -> ReplayEngine<TestState, TestTransaction, TestRuleSet> {
+        ReplayEngine::new(
+            TestState { balance: 0, transaction_count: 0 },
+            TestRuleSet { version: Version::new(1, 0, 0) },
+            ExecutionContext::new(Utc.timestamp_opt(1_000_000, 0).unwrap(), 42),
+        )
+    }
+    
+    fn transactions(count: i64) -> Vec<TestTransaction> {
+        (0..count)
+            .map(|i| TestTransaction {
+                id: format!("tx{}", i),
+                amount: i + 1,
+                timestamp: Utc.timestamp_opt(1_000_000 + i, 0).unwrap(),
+            })
+            .collect()
+    }
+    
+    fn configs() -> Vec<AuditBundleConfig> {
+        vec![
+            AuditBundleConfig { signing_key: b"regulator shared secret".to_vec(), algorithm: SigningAlgorithm::HmacSha256 },
+            AuditBundleConfig { signing_key: vec![7; 32], algorithm: SigningAlgorithm::Ed25519 },
+        ]
+    }
+    
+    #[test]
+    fn test_audit_bundle_verifies() {
+        for config in configs() {
+            let bundle = engine().audit_replay(&transactions(20), &config).unwrap();
+            
+            assert!(bundle.verify_signature(&config), "{:?}", config.algorithm);
+            assert_eq!(bundle.timestamp, Utc.timestamp_opt(1_000_000, 0).unwrap());
+            assert!(bundle.signed_payload_json.contains(&bundle.replay_result.final_hash.to_string()));
+            assert!(bundle.signed_payload_json.contains(&bundle.merkle_root.to_string()));
+            
+            let other_key = AuditBundleConfig { signing_key: vec![9; 32], algorithm: config.algorithm };
+            assert!(!bundle.verify_signature(&other_key));
+        }
+    }
+    
+    #[test]
+    fn test_tampered_payload_fails_verification() {
+        for config in configs() {
+            let bundle = engine().audit_replay(&transactions(5), &config).unwrap();
+            
+            for index in 0..bundle.signed_payload_json.len() {
+                let mut bytes = bundle.signed_payload_json.clone().into_bytes();
+                bytes[index] ^= 0x01;
+                let mut tampered = bundle.clone();
+                tampered.signed_payload_json = String::from_utf8(bytes).unwrap();
+                assert!(!tampered.verify_signature(&config), "byte {} was not covered", index);
+            }
+        }
+    }
+    
+    #[test]
+    fn test_identical_replays_produce_identical_signatures() {
+        for config in configs() {
+            let first = engine().audit_replay(&transactions(30), &config).unwrap();
+            let second = engine().audit_replay(&transactions(30), &config).unwrap();
+            
+            assert_eq!(first.signature, second.signature);
+            assert_eq!(first.signed_payload_json, second.signed_payload_json);
+            assert_eq!(first.merkle_root, second.merkle_root);
+            
+            let different = engine().audit_replay(&transactions(31), &config).unwrap();
+            assert_ne!(different.signature, first.signature);
+        }
+    }
+    
+    #[test]
+    fn test_invalid_ed25519_key_is_rejected() {
+        let config = AuditBundleConfig { signing_key: vec![1; 16], algorithm: SigningAlgorithm::Ed25519 };
+        let result = engine().audit_replay(&transactions(3), &config);
+        assert!(matches!(result, Err(ProcessingError::SigningFailed { .. })));
+    }
+    
+    #[test]
+    fn test_audit_bundle_to_file() {
+        let config = &configs()[0];
+        let bundle = engine().audit_replay(&transactions(3), config).unwrap();
+        let path = std::env::temp_dir().join(format!("dtre-audit-bundle-{}.json", std::process::id()));
+        
+        bundle.to_file(&path).unwrap();
+        let written: dtre::AuditBundle<TestState> = serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        
+        assert!(written.verify_signature(config));
+        assert_eq!(written.replay_result.final_hash, bundle.replay_result.final_hash);
+    }
+}
+
+#[cfg(test)]
+mod checkpoint_dry_run_tests {
+    use super::*;
+    use dtre::{ReplayEngineBuilder, StateHasher, TransactionProcessor};
+    
+    fn builder() -> ReplayEngineBuilder<TestState, TestTransaction, TestRuleSet> {
+        ReplayEngine::builder()
+            .with_initial_state(TestState { balance: 0, transaction_count: 0 })
+            .with_rule_set(TestRuleSet { version: Version::new(1, 0, 0) })
+            .with_time_and_seed(Utc.timestamp_opt(1_000_000, 0).unwrap(), 42)
+            .with_checkpoint_interval(2)
+    }
+    
+    fn transactions(count: i64) -> Vec<TestTransaction> {
+        (0..count)
+            .map(|i| TestTransaction {
+                id: format!("tx{}", i),
+                amount: i + 1,
+                timestamp: Utc.timestamp_opt(1_000_000 + i, 0).unwrap(),
+            })
+            .collect()
+    }
+    
+    #[test]
+    fn test_dry_run_reports_all_checkpoints_valid() {
+        let engine = builder().with_dry_run_checkpoints(true).build().unwrap();
+        let (result, report) = engine.replay_with_checkpoint_validation(&transactions(10)).unwrap();
+        let report = report.expect("dry run is enabled");
+        
+        assert_eq!(result.execution_trace.checkpoints.len(), 5);
+        assert_eq!(report.valid_count, 5);
+        assert!(report.is_valid());
+        
+        // Without the option no checkpoints are replayed
+        let engine = builder().build().unwrap();
+        let (plain_result, report) = engine.replay_with_checkpoint_validation(&transactions(10)).unwrap();
+        assert!(report.is_none());
+        assert_eq!(plain_result.final_hash, result.final_hash);
+    }
+    
+    #[test]
+    fn test_dry_run_catches_corrupted_checkpoints() {
+        let engine = builder().with_dry_run_checkpoints(true).build().unwrap();
+        let transactions = transactions(10);
+        let expected_final_hash = engine.replay(&transactions).unwrap().final_hash;
+        
+        let mut processor = TransactionProcessor::new(engine.initial_state().clone()).unwrap();
+        processor
+            .process_transactions_with_checkpoints(&transactions, engine.rule_set(), engine.context(), 2)
+            .unwrap();
+        let mut checkpoints = processor.state_manager().checkpoints().to_vec();
+        
+        // Corrupt every other checkpoint's state, re-hashing it so the checkpoint still looks intact
+        for checkpoint in checkpoints.iter_mut().step_by(2) {
+            checkpoint.state.balance += 1;
+            checkpoint.hash = StateHasher::new().hash(&checkpoint.state);
+        }
+        // Corrupt one more without re-hashing, so restoring it fails
+        checkpoints[1].state.balance += 1;
+        
+        let report = engine.validate_checkpoints(&checkpoints, &transactions, expected_final_hash);
+        
+        assert_eq!(report.valid_count, 1);
+        let invalid_indices: Vec<usize> = report.invalid.iter().map(|(index, _, _)| *index).collect();
+        assert_eq!(invalid_indices, vec![2, 4, 6, 10]);
+        for (_, expected, actual) in &report.invalid {
+            assert_eq!(*expected, expected_final_hash);
+            assert_ne!(*actual, expected_final_hash);
+        }
+    }
+}
+
+#[cfg(test)]
+mod cost_budget_tests {
+    use super::*;
+    use dtre::{ReplayCostBudget, ReplayCostEstimate};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    
+    /// Claims every transaction takes an hour of CPU and counts the transactions it applies
+    struct ExpensiveRuleSet {
+        applied: Arc<AtomicUsize>,
+    }
+    
+    impl RuleSet<TestState, TestTransaction> for ExpensiveRuleSet {
+        fn version(&self) -> Version {
+            Version::new(1, 0, 0)
+        }
+        
+        fn apply(&self, state: &TestState, transaction: &TestTransaction, _context: &ExecutionContext) -> Result<TestState, ProcessingError> {
+            self.applied.fetch_add(1, Ordering::SeqCst);
+            Ok(TestState {
+                balance: state.balance + transaction.amount,
+                transaction_count: state.transaction_count + 1,
+            })
+        }
+        
+        fn replay_cost_estimate(&self, transactions: &[TestTransaction], _state: &TestState) -> ReplayCostEstimate {
+            ReplayCostEstimate {
+                estimated_cpu_ms: 3_600_000 * transactions.len() as u64,
+                estimated_memory_bytes: 1_024,
+                estimated_io_ops: 0,
+            }
+        }
+    }
+    
+    fn transactions(count: usize) -> Vec<TestTransaction> {
+        (0..count)
+            .map(|i| TestTransaction {
+                id: format!("tx{}", i),
+                amount: 1,
+                timestamp: Utc.timestamp_opt(1_000_000 + i as i64, 0).unwrap(),
+            })
+            .collect()
+    }
+    
+    #[test]
+    fn test_expensive_estimate_is_rejected_before_processing() {
+        let applied = Arc::new(AtomicUsize::new(0));
+        let engine = ReplayEngine::builder()
+            .with_initial_state(TestState { balance: 0, transaction_count: 0 })
+            .with_rule_set(ExpensiveRuleSet { applied: applied.clone() })
+            .with_time_and_seed(Utc.timestamp_opt(1_000_000, 0).unwrap(), 42)
+            .with_cost_budget(ReplayCostBudget { max_cpu_ms: Some(1), max_memory_bytes: None })
+            .build()
+            .unwrap();
+        
+        match engine.replay(&transactions(3)) {
+            Err(ProcessingError::CostBudgetExceeded { resource, estimated, limit }) => {
+                assert_eq!(resource, "cpu_ms");
+                assert_eq!(estimated, 10_800_000);
+                assert_eq!(limit, 1);
+            }
+            other => panic!("Expected the budget to be exceeded, got {:?}", other),
+        }
+        assert_eq!(applied.load(Ordering::SeqCst), 0);
+        
+        // An empty sequence costs nothing and fits the budget
+        assert!(engine.replay(&[]).is_ok());
+    }
+    
+    #[test]
+    fn test_default_estimate_scales_with_transaction_count() {
+        let rule_set = TestRuleSet { version: Version::new(1, 0, 0) };
+        let state = TestState { balance: 0, transaction_count: 0 };
+        let small = rule_set.replay_cost_estimate(&transactions(10), &state);
+        let large = rule_set.replay_cost_estimate(&transactions(100_000), &state);
+        assert_eq!(small.estimated_cpu_ms, 1);
+        assert_eq!(large.estimated_cpu_ms, 5_000);
+        assert!(large.estimated_memory_bytes > small.estimated_memory_bytes);
+        
+        let engine = ReplayEngine::builder()
+            .with_initial_state(state)
+            .with_rule_set(rule_set)
+            .with_time_and_seed(Utc.timestamp_opt(1_000_000, 0).unwrap(), 42)
+            .with_cost_budget(ReplayCostBudget { max_cpu_ms: Some(10), max_memory_bytes: None })
+            .build()
+            .unwrap();
+        assert!(engine.replay(&transactions(100)).is_ok());
+        assert!(matches!(
+            engine.replay(&transactions(1_000)),
+            Err(ProcessingError::CostBudgetExceeded { .. })
+        ));
+    }
+}
+
+#[cfg(test)]
+mod streaming_tests {
+    use super::*;
+    use dtre::{IncrementalResult, StateHash};
+    use futures::channel::mpsc;
+    use futures::StreamExt;
+    
+    fn transactions(count: usize) -> Vec<TestTransaction> {
+        (0..count)
+            .map(|i| TestTransaction {
+                id: format!("tx{}", i),
+                amount: 10 + i as i64,
+                timestamp: Utc.timestamp_opt(1_000_000 + i as i64, 0).unwrap(),
+            })
+            .collect()
+    }
+    
+    fn engine(max_transaction_count: Option<usize>) -> ReplayEngine<TestState, TestTransaction, TestRuleSet> {
+        let builder = ReplayEngine::builder()
+            .with_initial_state(TestState { balance: 0, transaction_count: 0 })
+            .with_rule_set(TestRuleSet { version: Version::new(1, 2, 0) })
+            .with_time_and_seed(Utc.timestamp_opt(1_000_000, 0).unwrap(), 42);
+        match max_transaction_count {
+            Some(limit) => builder.with_max_transaction_count(limit),
+            None => builder,
+        }
+        .build()
+        .unwrap()
+    }
+    
+    #[test]
+    fn test_streamed_hashes_match_trace() {
+        let engine = engine(None);
+        let transactions = transactions(5);
+        let increments: Vec<IncrementalResult<TestState>> = engine
+            .replay_streaming(&transactions)
+            .collect::<Result<_, _>>()
+            .unwrap();
+        let result = engine.replay(&transactions).unwrap();
+        
+        assert_eq!(increments.len(), transactions.len() + 1);
+        let streamed: Vec<(&str, StateHash)> = increments[..5]
+            .iter()
+            .map(|increment| (increment.transaction_id.as_str(), increment.new_hash))
+            .collect();
+        let traced: Vec<(&str, StateHash)> = result.execution_trace.state_transitions
+            .iter()
+            .map(|transition| (transition.transaction_id.as_str(), transition.to_hash))
+            .collect();
+        assert_eq!(streamed, traced);
+        assert!(increments.iter().all(|increment| increment.rule_applied == Version::new(1, 2, 0)));
+        assert_eq!(increments[2].new_state.transaction_count, 3);
+        
+        let terminal = increments.last().unwrap();
+        assert!(terminal.transaction_id.is_empty());
+        assert_eq!(terminal.new_hash, result.final_hash);
+        assert_eq!(terminal.new_state, result.final_state);
+    }
+    
+    #[test]
+    fn test_stream_ends_after_first_error() {
+        let engine = engine(Some(2));
+        let transactions = transactions(4);
+        let items: Vec<_> = engine.replay_streaming(&transactions).collect();
+        
+        assert_eq!(items.len(), 3);
+        assert!(items[..2].iter().all(|item| item.is_ok()));
+        assert!(items[2].is_err());
+    }
+    
+    #[test]
+    fn test_async_stream_matches_iterator() {
+        let engine = engine(None);
+        let transactions = transactions(3);
+        let (sender, receiver) = mpsc::channel(1);
+        let ((), received) = futures::executor::block_on(async {
+            futures::join!(
+                engine.replay_streaming_async(&transactions, sender),
+                receiver.collect::<Vec<_>>()
+            )
+        });
+        
+        let received: Vec<StateHash> = received.into_iter().map(|item| item.unwrap().new_hash).collect();
+        let streamed: Vec<StateHash> = engine
+            .replay_streaming(&transactions)
+            .map(|item| item.unwrap().new_hash)
+            .collect();
+        assert_eq!(received, streamed);
+        assert_eq!(received.len(), 4);
+    }
+}
+
+#[cfg(test)]
+mod debug_mode_tests {
+    use super::*;
+    use dtre::DebugMode;
+    
+    /// Adds the amount and prints the new balance for debugging
+    struct ChattyRuleSet;
+    
+    impl RuleSet<TestState, TestTransaction> for ChattyRuleSet {
+        fn version(&self) -> Version {
+            Version::new(1, 0, 0)
+        }
+        
+        fn apply(&self, state: &TestState, transaction: &TestTransaction, context: &ExecutionContext) -> Result<TestState, ProcessingError> {
+            let balance = state.balance + transaction.amount;
+            context.debug_println(&format!("{}: balance {}", transaction.id, balance));
+            Ok(TestState {
+                balance,
+                transaction_count: state.transaction_count + 1,
+            })
+        }
+    }
+    
+    fn engine(mode: Option<DebugMode>) -> ReplayEngine<TestState, TestTransaction, ChattyRuleSet> {
+        let builder = ReplayEngine::builder()
+            .with_initial_state(TestState { balance: 0, transaction_count: 0 })
+            .with_rule_set(ChattyRuleSet)
+            .with_time_and_seed(Utc.timestamp_opt(1_000_000, 0).unwrap(), 42);
+        match mode {
+            Some(mode) => builder.with_debug_mode(mode),
+            None => builder,
+        }
+        .build()
+        .unwrap()
+    }
+    
+    #[test]
+    fn test_builder_debug_mode_captures_rule_output() {
+        let transactions: Vec<TestTransaction> = (0..2)
+            .map(|i| TestTransaction {
+                id: format!("tx{}", i),
+                amount: 5,
+                timestamp: Utc.timestamp_opt(1_000_000 + i, 0).unwrap(),
+            })
+            .collect();
+        
+        let debugging = engine(Some(DebugMode::Enabled));
+        let debug_result = debugging.replay(&transactions).unwrap();
+        assert_eq!(debugging.context().debug_log(), vec!["tx0: balance 5", "tx1: balance 10"]);
+        
+        let quiet = engine(None);
+        let quiet_result = quiet.replay(&transactions).unwrap();
+        assert!(quiet.context().debug_log().is_empty());
+        assert_eq!(debug_result.final_hash, quiet_result.final_hash);
+    }
+}
+
+#[cfg(test)]
+mod ticking_clock_tests {
+    use super::*;
+    use chrono::Duration;
+    
+    /// Records the time each transaction sees as the balance
+    struct ClockReadingRuleSet;
+    
+    impl RuleSet<TestState, TestTransaction> for ClockReadingRuleSet {
+        fn version(&self) -> Version {
+            Version::new(1, 0, 0)
+        }
+        
+        fn apply(&self, state: &TestState, _transaction: &TestTransaction, context: &ExecutionContext) -> Result<TestState, ProcessingError> {
+            Ok(TestState {
+                balance: context.now().timestamp(),
+                transaction_count: state.transaction_count + 1,
+            })
+        }
+    }
+    
+    #[test]
+    fn test_replaying_twice_starts_from_the_same_time() {
+        let start = Utc.timestamp_opt(1_000_000, 0).unwrap();
+        let context = ExecutionContext::builder()
+            .with_time(start)
+            .with_tick_per_transaction(Duration::seconds(60))
+            .build();
+        let engine = ReplayEngine::builder()
+            .with_initial_state(TestState { balance: 0, transaction_count: 0 })
+            .with_rule_set(ClockReadingRuleSet)
+            .with_context(context)
+            .build()
+            .unwrap();
+        let transactions: Vec<TestTransaction> = (0..3)
+            .map(|i| TestTransaction {
+                id: format!("tx{}", i),
+                amount: 0,
+                timestamp: start,
+            })
+            .collect();
+        
+        let first = engine.replay(&transactions).unwrap();
+        let second = engine.replay(&transactions).unwrap();
+        assert_eq!(first.final_state.balance, (start + Duration::seconds(120)).timestamp());
+        assert_eq!(first.final_hash, second.final_hash);
+        assert_eq!(engine.context().now(), start);
+    }
+    
+    #[test]
+    fn test_shadow_sees_the_time_the_primary_saw() {
+        let start = Utc.timestamp_opt(1_000_000, 0).unwrap();
+        let context = ExecutionContext::builder()
+            .with_time(start)
+            .with_tick_per_transaction(Duration::seconds(60))
+            .build();
+        let engine = ReplayEngine::builder()
+            .with_initial_state(TestState { balance: 0, transaction_count: 0 })
+            .with_rule_set(ClockReadingRuleSet)
+            .with_context(context)
+            .build()
+            .unwrap()
+            .with_shadow(ClockReadingRuleSet);
+        let transactions: Vec<TestTransaction> = (0..3)
+            .map(|i| TestTransaction {
+                id: format!("tx{}", i),
+                amount: 0,
+                timestamp: start,
+            })
+            .collect();
+        
+        engine.replay(&transactions).unwrap();
+        assert_eq!(engine.discrepancy_rate(), 0.0);
+    }
+}
+
+#[cfg(test)]
+mod recovery_strategy_tests {
+    use super::*;
+    use dtre::{RecoveryAction, RecoveryHint};
+    
+    /// Rejects withdrawals that would overdraw the balance
+    struct OverdraftRuleSet;
+    
+    impl RuleSet<TestState, TestTransaction> for OverdraftRuleSet {
+        fn version(&self) -> Version {
+            Version::new(1, 0, 0)
+        }
+        
+        fn apply(&self, state: &TestState, transaction: &TestTransaction, _context: &ExecutionContext) -> Result<TestState, ProcessingError> {
+            if state.balance + transaction.amount < 0 {
+                return Err(ProcessingError::TransactionFailed {
+                    transaction_id: transaction.id.clone(),
+                    reason: "insufficient balance".to_string(),
+                });
+            }
+            Ok(TestState {
+                balance: state.balance + transaction.amount,
+                transaction_count: state.transaction_count + 1,
+            })
+        }
+    }
+    
+    fn transactions(amounts: &[i64]) -> Vec<TestTransaction> {
+        amounts
+            .iter()
+            .enumerate()
+            .map(|(i, &amount)| TestTransaction {
+                id: format!("tx{}", i),
+                amount,
+                timestamp: Utc.timestamp_opt(1_000_000 + i as i64, 0).unwrap(),
+            })
+            .collect()
+    }
+    
+    fn engine() -> ReplayEngine<TestState, TestTransaction, OverdraftRuleSet> {
+        ReplayEngine::builder()
+            .with_initial_state(TestState { balance: 0, transaction_count: 0 })
+            .with_rule_set(OverdraftRuleSet)
+            .with_time_and_seed(Utc.timestamp_opt(1_000_000, 0).unwrap(), 42)
+            .build()
+            .unwrap()
+    }
+    
+    #[test]
+    fn test_skip_strategy_continues_past_insufficient_balance() {
+        let transactions = transactions(&[10, -50, 5, -100, 1]);
+        let result = engine()
+            .replay_with_recovery_strategy(&transactions, |error| match error.recovery_hint() {
+                Some(RecoveryHint::FixTransaction { .. }) => RecoveryAction::Skip,
+                _ => RecoveryAction::Abort,
+            })
+            .unwrap();
+        
+        assert_eq!(result.final_state.balance, 16);
+        assert_eq!(result.execution_trace.transactions_processed, 3);
+        assert_eq!(result.execution_trace.skipped_transactions, vec!["tx1", "tx3"]);
+    }
+    
+    #[test]
+    fn test_abort_and_exhausted_retries_return_the_error() {
+        let transactions = transactions(&[10, -50, 5]);
+        let engine = engine();
+        
+        let aborted = engine.replay_with_recovery_strategy(&transactions, |_| RecoveryAction::Abort);
+        assert!(matches!(aborted, Err(ProcessingError::TransactionFailed { ref transaction_id, .. }) if transaction_id == "tx1"));
+        
+        let retried = engine.replay_with_recovery_strategy(&transactions, |_| RecoveryAction::Retry(2));
+        assert!(retried.is_err());
+    }
+}
+
+#[cfg(test)]
+mod learned_ordering_tests {
+    use super::*;
+    use dtre::OrderingRules;
+    
+    fn transactions(ids: &[&str]) -> Vec<TestTransaction> {
+        ids.iter()
+            .enumerate()
+            .map(|(i, id)| TestTransaction {
+                id: id.to_string(),
+                amount: 1,
+                timestamp: Utc.timestamp_opt(1_000_000 + i as i64, 0).unwrap(),
+            })
+            .collect()
+    }
+    
+    fn engine(context: ExecutionContext) -> ReplayEngine<TestState, TestTransaction, TestRuleSet> {
+        ReplayEngine::builder()
+            .with_initial_state(TestState { balance: 0, transaction_count: 0 })
+            .with_rule_set(TestRuleSet { version: Version::new(1, 0, 0) })
+            .with_context(context)
+            .build()
+            .unwrap()
+    }
+    
+    #[test]
+    fn test_learned_ordering_is_enforced_on_later_replays() {
+        let time = Utc.timestamp_opt(1_000_000, 0).unwrap();
+        let sequence = transactions(&["a", "b", "c"]);
+        let first = engine(ExecutionContext::new(time, 42)).replay(&sequence).unwrap();
+        
+        // Round-trip through JSON as a caller persisting the rules would
+        let learned = OrderingRules::from_execution_trace(&first.execution_trace);
+        let learned = OrderingRules::load_from_json(learned.save_to_json()).unwrap();
+        let context = ExecutionContext::builder()
+            .with_time(time)
+            .with_random_seed(42)
+            .with_ordering_rules(learned)
+            .build();
+        let engine = engine(context);
+        
+        assert_eq!(engine.replay(&sequence).unwrap().final_hash, first.final_hash);
+        assert!(engine.replay(&transactions(&["a", "c"])).is_ok());
+        assert!(matches!(
+            engine.replay(&transactions(&["b", "a", "c"])),
+            Err(ProcessingError::OrderingViolation { .. })
+        ));
+    }
+    
+    #[test]
+    fn test_merge_from_traces_rejects_disagreement() {
+        let time = Utc.timestamp_opt(1_000_000, 0).unwrap();
+        let engine = engine(ExecutionContext::new(time, 42));
+        let abc = engine.replay(&transactions(&["a", "b", "c"])).unwrap().execution_trace;
+        let bdc = engine.replay(&transactions(&["b", "d", "c"])).unwrap().execution_trace;
+        let cb = engine.replay(&transactions(&["c", "b"])).unwrap().execution_trace;
+        
+        let merged = OrderingRules::merge_from_traces(&[&abc, &bdc]).unwrap();
+        assert_eq!(
+            merged.get_ordering(OrderingRules::TRANSACTIONS).unwrap(),
+            &vec!["a".to_string(), "b".to_string(), "d".to_string(), "c".to_string()]
+        );
+        assert!(OrderingRules::merge_from_traces(&[&abc, &cb]).is_err());
+    }
+}
+
+#[cfg(test)]
+mod duration_estimate_tests {
+    use super::*;
+    use dtre::{OverheadBreakdown, PerformanceHistory, PerformanceMetrics};
+    
+    fn metrics(transactions_per_second: f64) -> PerformanceMetrics {
+        PerformanceMetrics {
+            total_duration_ms: (500.0 / transactions_per_second * 1000.0) as u64,
+            transactions_per_second,
+            average_transaction_time_ms: 1000.0 / transactions_per_second,
+            breakdown: OverheadBreakdown::default(),
+        }
+    }
+    
+    #[test]
+    fn test_estimate_duration_from_100_tps_history() {
+        let engine = ReplayEngine::new(
+            TestState { balance: 0, transaction_count: 0 },
+            TestRuleSet { version: Version::new(1, 0, 0) },
+            ExecutionContext::new(Utc.timestamp_opt(1_000_000, 0).unwrap(), 42),
+        );
+        let mut history = PerformanceHistory::new();
+        for tps in [98.0, 99.0, 100.0, 100.0, 100.0, 101.0, 102.0] {
+            history.record(&metrics(tps));
+        }
+        history.record(&metrics(0.0));
+        assert_eq!(history.len(), 7);
+        assert!((history.average_tps() - 100.0).abs() < 1e-9);
+        assert_eq!(history.percentile_tps(0.0), 98.0);
+        assert_eq!(history.percentile_tps(100.0), 102.0);
+        
+        let estimate = engine.estimate_duration(1000, &history);
+        assert_eq!(estimate.p50_ms, 10_000);
+        assert!(estimate.p95_ms >= estimate.p50_ms && estimate.p99_ms >= estimate.p95_ms);
+        assert!((estimate.p99_ms as f64 - 10_000.0).abs() < 500.0);
+        assert!(estimate.confidence > 0.0 && estimate.confidence < 1.0);
+        
+        let unknown = engine.estimate_duration(1000, &PerformanceHistory::new());
+        assert_eq!(unknown.p50_ms, 0);
+        assert_eq!(unknown.confidence, 0.0);
+    }
+}
+
+#[cfg(feature = "profiling")]
+mod profiling_tests {
+    use super::*;
+    use std::time::Duration;
+    
+    /// Sleeps for as many milliseconds as the transaction amount before adding it
+    struct SleepingRuleSet;
+    
+    impl RuleSet<TestState, TestTransaction> for SleepingRuleSet {
+        fn version(&self) -> Version {
+            Version::new(1, 0, 0)
+        }
+        
+        fn apply(&self, state: &TestState, transaction: &TestTransaction, _context: &ExecutionContext) -> Result<TestState, ProcessingError> {
+            std::thread::sleep(Duration::from_millis(transaction.amount as u64));
+            Ok(TestState {
+                balance: state.balance + transaction.amount,
+                transaction_count: state.transaction_count + 1,
+            })
+        }
+    }
+    
+    #[test]
+    fn test_profile_reports_top_n_slowest_in_descending_order() {
+        let context = ExecutionContext::new(Utc.timestamp_opt(1000000, 0).unwrap(), 42);
+        let engine = ReplayEngine::new(TestState { balance: 0, transaction_count: 0 }, SleepingRuleSet, context);
+        let transactions: Vec<TestTransaction> = [3, 7, 1, 10, 4, 2, 9, 5, 8, 6]
+            .iter()
+            .enumerate()
+            .map(|(i, &amount)| TestTransaction {
+                id: format!("tx {}", i),
+                amount,
+                timestamp: Utc.timestamp_opt(1000000 + i as i64, 0).unwrap(),
+            })
+            .collect();
+        
+        let report = engine.profile(&transactions, 3);
+        
+        assert_eq!(report.slowest_applications.len(), 3);
+        assert!(report.slowest_applications.windows(2).all(|pair| pair[0].duration_us >= pair[1].duration_us));
+        assert!(report.slowest_applications[0].duration_us >= 10_000);
+        assert!(report.total_duration_ms >= 55);
+        assert_eq!(report.percentile_durations.len(), 4);
+        assert!(report.percentile_durations[&50] <= report.percentile_durations[&99]);
+        
+        let flamegraph = report.to_flamegraph_data();
+        assert_eq!(flamegraph.lines().count(), 3);
+        let first = &report.slowest_applications[0];
+        assert_eq!(
+            flamegraph.lines().next().unwrap(),
+            format!("replay;rule_set_1.0.0;{} {}", first.transaction_id.replace(' ', "_"), first.duration_us)
+        );
+    }
+}
@@ -1,5 +1,5 @@
 use dtre::{
-    BalanceDifference, DiffAnalyzer, ReplayResult, ResultComparator, State,
+    BalanceDifference, DiffAnalyzer, ReplayResult, ReplayResultComparator, ResultComparator, State,
     ValidationError,
 };
 use proptest::prelude::*;
@@ -29,7 +29,7 @@ impl State for TestState {
 
 // Helper to create a replay result
 fn create_replay_result(balance: i64, count: u32, tx_count: usize) -> ReplayResult<TestState> {
-    use dtre::{ExecutionTrace, PerformanceMetrics, StateHasher};
+    use dtre::{ExecutionTrace, PerformanceMetrics, StateHasher, WatermarkTracker};
 
     let state = TestState { balance, count };
     let hasher = StateHasher::new();
@@ -43,11 +43,16 @@ fn create_replay_result(balance: i64, count: u32, tx_count: usize) -> ReplayResu
             state_transitions: vec![],
             rule_applications: vec![],
             checkpoints: vec![],
+            watermark: WatermarkTracker::new(),
+            mutations: Vec::new(),
+            skipped_transactions: Vec::new(),
+            skipped_indices: Vec::new(),
         },
         performance_metrics: PerformanceMetrics {
             total_duration_ms: 100,
             transactions_per_second: 10.0,
             average_transaction_time_ms: 10.0,
+            breakdown: Default::default(),
         },
     }
 }
@@ -318,4 +323,60 @@ mod unit_tests {
 
         assert_eq!(largest.len(), 0);
     }
+
+    // Replay result with a checkpoint taken at the given wall-clock time
+    fn create_timed_result(taken_at: chrono::DateTime<chrono::Utc>, duration_ms: u64) -> ReplayResult<TestState> {
+        use dtre::CheckpointInfo;
+
+        let mut result = create_replay_result(100, 5, 10);
+        result.execution_trace.checkpoints.push(CheckpointInfo {
+            transaction_index: 5,
+            hash: result.final_hash,
+            timestamp: taken_at,
+            state_schema_version: 1,
+            state_size_bytes: 0,
+        });
+        result.performance_metrics.total_duration_ms = duration_ms;
+        result
+    }
+
+    #[test]
+    fn test_functional_equivalence_ignores_timestamps() {
+        let first_run = create_timed_result(chrono::Utc::now(), 100);
+        let second_run = create_timed_result(chrono::Utc::now() + chrono::Duration::hours(1), 250);
+
+        let strict = ReplayResultComparator::new().compare_functionally(&first_run, &second_run);
+        assert!(!strict.equivalent);
+        assert_eq!(strict.differing_fields, vec![
+            "execution_trace.checkpoints.0.timestamp".to_string(),
+            "performance_metrics.total_duration_ms".to_string(),
+        ]);
+
+        let comparator = ReplayResultComparator::new()
+            .ignore_fields(&["performance_metrics"])
+            .ignore_type::<chrono::DateTime<chrono::Utc>>();
+        let result = comparator.compare_functionally(&first_run, &second_run);
+        assert!(result.equivalent);
+        assert!(result.differing_fields.is_empty());
+        assert!(result.ignored_fields.contains(&"execution_trace.checkpoints.0.timestamp".to_string()));
+        assert!(result.ignored_fields.contains(&"performance_metrics".to_string()));
+        assert!(comparator.functionally_equivalent(&first_run, &second_run));
+
+        // Other fields are still compared
+        let different_state = create_replay_result(150, 5, 10);
+        assert!(!comparator.functionally_equivalent(&first_run, &different_state));
+    }
+
+    #[test]
+    fn test_functional_equivalence_ignore_patterns() {
+        let first_run = create_timed_result(chrono::Utc::now(), 100);
+        let second_run = create_timed_result(chrono::Utc::now() + chrono::Duration::hours(1), 100);
+
+        let comparator = ReplayResultComparator::new().ignore_patterns(&[r"\.timestamp$"]).unwrap();
+        let result = comparator.compare_functionally(&first_run, &second_run);
+        assert!(result.equivalent);
+        assert_eq!(result.ignored_fields, vec!["execution_trace.checkpoints.0.timestamp".to_string()]);
+
+        assert!(ReplayResultComparator::new().ignore_patterns(&["(unclosed"]).is_err());
+    }
 }
@@ -1,5 +1,5 @@
 use dtre::{
-    RuleSetRegistry, VersionedRuleSet, RuleSetMetadata, Version,
+    RuleSetRegistry, VersionedRuleSet, RuleSetMetadata, Version, VersionConstraint,
     State, Transaction, RuleSet, ExecutionContext,
     ProcessingError, ValidationError,
 };
@@ -336,3 +336,324 @@ mod unit_tests {
         }
     }
 }
+
+#[cfg(test)]
+mod version_compatibility_tests {
+    use super::*;
+    use dtre::{LogLevel, TransactionProcessor};
+    
+    // Rule set that accepts transactions from a window of earlier versions
+    struct RangedRuleSet {
+        version: Version,
+        range: (Version, Version),
+    }
+    
+    impl RuleSet<TestState, TestTransaction> for RangedRuleSet {
+        fn version(&self) -> Version {
+            self.version.clone()
+        }
+        
+        fn supports_version_range(&self) -> Option<(Version, Version)> {
+            Some(self.range.clone())
+        }
+        
+        fn apply(
+            &self,
+            state: &TestState,
+            _transaction: &TestTransaction,
+            _context: &ExecutionContext,
+        ) -> Result<TestState, ProcessingError> {
+            Ok(TestState { value: state.value + 1 })
+        }
+    }
+    
+    // Transaction that records the rule version it was created under
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    struct HintedTransaction {
+        id: String,
+        timestamp: DateTime<Utc>,
+        created_with: Version,
+    }
+    
+    impl Transaction for HintedTransaction {
+        fn id(&self) -> &str {
+            &self.id
+        }
+        
+        fn timestamp(&self) -> DateTime<Utc> {
+            self.timestamp
+        }
+        
+        fn validate(&self) -> Result<(), ValidationError> {
+            Ok(())
+        }
+        
+        fn rule_version_hint(&self) -> Option<Version> {
+            Some(self.created_with.clone())
+        }
+    }
+    
+    impl RuleSet<TestState, HintedTransaction> for RangedRuleSet {
+        fn version(&self) -> Version {
+            self.version.clone()
+        }
+        
+        fn supports_version_range(&self) -> Option<(Version, Version)> {
+            Some(self.range.clone())
+        }
+        
+        fn apply(
+            &self,
+            state: &TestState,
+            _transaction: &HintedTransaction,
+            _context: &ExecutionContext,
+        ) -> Result<TestState, ProcessingError> {
+            Ok(TestState { value: state.value + 1 })
+        }
+    }
+    
+    fn ranged_rule_set() -> RangedRuleSet {
+        RangedRuleSet {
+            version: Version::new(1, 5, 0),
+            range: (Version::new(1, 0, 0), Version::new(1, 5, 0)),
+        }
+    }
+    
+    #[test]
+    fn test_version_ordering() {
+        assert!(Version::new(1, 0, 0) < Version::new(1, 0, 1));
+        assert!(Version::new(1, 9, 9) < Version::new(2, 0, 0));
+        assert!(Version::new(1, 10, 0) > Version::new(1, 9, 0));
+    }
+    
+    #[test]
+    fn test_version_constraints() {
+        let constraint = |s: &str| VersionConstraint::parse(s).unwrap();
+        let version = Version::new(1, 5, 0);
+        assert!(version.satisfies(&constraint("^1.2.0")));
+        assert!(!version.satisfies(&constraint("^2.0.0")));
+        assert!(version.satisfies(&constraint(">=1.0.0 <2.0.0")));
+        assert!(!version.satisfies(&constraint("~1.2.0")));
+        assert!(Version::new(1, 2, 9).satisfies(&constraint("~1.2.0")));
+        assert!(!Version::new(0, 3, 0).satisfies(&constraint("^0.2.1")));
+        assert!(version.satisfies(&constraint("=1.5.0")));
+        assert!(version.satisfies(&constraint("*")));
+        
+        assert!(VersionConstraint::parse("").is_none());
+        assert!(VersionConstraint::parse("!1.0.0").is_none());
+        assert!(VersionConstraint::parse("^1.2").is_none());
+        
+        assert!(constraint("^1.2.0").is_compatible_with(&constraint(">=1.9.0")));
+        assert!(!constraint("^1.2.0").is_compatible_with(&constraint(">=2.0.0")));
+        assert!(!constraint("~1.2.0").is_compatible_with(&constraint("<1.2.0")));
+    }
+    
+    #[test]
+    fn test_resolve_satisfying_picks_highest_version() {
+        let mut registry: RuleSetRegistry<TestState, TestTransaction> = RuleSetRegistry::new();
+        for (major, minor) in [(1, 2), (1, 7), (2, 0)] {
+            let metadata = RuleSetMetadata::new(format!("v{}.{}", major, minor), "Plain".to_string());
+            registry.register(create_versioned_rule_set(Version::new(major, minor, 0), metadata, 1)).unwrap();
+        }
+        
+        let resolved = registry.resolve_satisfying(&VersionConstraint::parse("^1.0.0").unwrap());
+        assert_eq!(resolved.map(|rs| rs.version()), Some(Version::new(1, 7, 0)));
+        assert!(registry.resolve_satisfying(&VersionConstraint::parse(">=3.0.0").unwrap()).is_none());
+    }
+    
+    #[test]
+    fn test_supports_version_defaults_to_exact_match() {
+        let rules = TestRuleSet { version: Version::new(1, 2, 0), increment_by: 1 };
+        
+        assert_eq!(RuleSet::<TestState, TestTransaction>::supports_version_range(&rules), None);
+        assert!(RuleSet::<TestState, TestTransaction>::supports_version(&rules, &Version::new(1, 2, 0)));
+        assert!(!RuleSet::<TestState, TestTransaction>::supports_version(&rules, &Version::new(1, 1, 0)));
+    }
+    
+    #[test]
+    fn test_find_compatible_within_range() {
+        let mut registry: RuleSetRegistry<TestState, TestTransaction> = RuleSetRegistry::new();
+        let metadata = RuleSetMetadata::new("Ranged".to_string(), "Supports 1.0.0 to 1.5.0".to_string());
+        registry.register(VersionedRuleSet::new(Version::new(1, 5, 0), Box::new(ranged_rule_set()), metadata)).unwrap();
+        
+        let exact = RuleSetMetadata::new("Exact".to_string(), "Only 2.0.0".to_string());
+        registry.register(create_versioned_rule_set(Version::new(2, 0, 0), exact, 1)).unwrap();
+        
+        let compatible = registry.find_compatible(&Version::new(1, 3, 0));
+        assert_eq!(compatible.len(), 1);
+        assert_eq!(compatible[0].version(), Version::new(1, 5, 0));
+        
+        assert!(registry.find_compatible(&Version::new(1, 6, 0)).is_empty());
+        
+        let for_v2 = registry.find_compatible(&Version::new(2, 0, 0));
+        assert_eq!(for_v2.len(), 1);
+        assert_eq!(for_v2[0].version(), Version::new(2, 0, 0));
+    }
+    
+    #[test]
+    fn test_find_compatible_is_ordered_by_version() {
+        let mut registry: RuleSetRegistry<TestState, TestTransaction> = RuleSetRegistry::new();
+        for minor in [4, 2, 3] {
+            let rules = RangedRuleSet {
+                version: Version::new(1, minor, 0),
+                range: (Version::new(1, 0, 0), Version::new(1, minor, 0)),
+            };
+            let metadata = RuleSetMetadata::new(format!("v1.{}", minor), "Ranged".to_string());
+            registry.register(VersionedRuleSet::new(Version::new(1, minor, 0), Box::new(rules), metadata)).unwrap();
+        }
+        
+        let versions: Vec<Version> = registry
+            .find_compatible(&Version::new(1, 1, 0))
+            .iter()
+            .map(|rs| rs.version())
+            .collect();
+        assert_eq!(versions, vec![Version::new(1, 2, 0), Version::new(1, 3, 0), Version::new(1, 4, 0)]);
+    }
+    
+    #[test]
+    fn test_processor_warns_on_incompatible_hint() {
+        let mut processor = TransactionProcessor::new(TestState { value: 0 }).unwrap();
+        let rule_set = ranged_rule_set();
+        let context = ExecutionContext::new(Utc.timestamp_opt(0, 0).unwrap(), 0);
+        
+        let supported = HintedTransaction {
+            id: "tx1".to_string(),
+            timestamp: Utc.timestamp_opt(1, 0).unwrap(),
+            created_with: Version::new(1, 3, 0),
+        };
+        let unsupported = HintedTransaction {
+            id: "tx2".to_string(),
+            timestamp: Utc.timestamp_opt(2, 0).unwrap(),
+            created_with: Version::new(1, 6, 0),
+        };
+        
+        processor.process_transaction(&supported, &rule_set, &context).unwrap();
+        assert!(processor.logger().filter_by_level(LogLevel::Warn).is_empty());
+        
+        // Incompatible transactions are still processed, but flagged
+        processor.process_transaction(&unsupported, &rule_set, &context).unwrap();
+        assert_eq!(processor.current_state().value, 2);
+        
+        let warnings = processor.logger().filter_by_level(LogLevel::Warn);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].transaction_id.as_deref(), Some("tx2"));
+        assert_eq!(warnings[0].rule_version, Some(Version::new(1, 5, 0)));
+        assert!(warnings[0].message.contains("1.6.0"));
+    }
+}
+
+#[cfg(test)]
+mod hot_reload_tests {
+    use super::*;
+    use dtre::{HotReloadableRuleSet, RuleError, TransactionProcessor};
+    use std::sync::{Arc, OnceLock};
+    
+    fn batch(prefix: &str, count: i64) -> Vec<TestTransaction> {
+        (0..count)
+            .map(|i| TestTransaction {
+                id: format!("{}{}", prefix, i),
+                timestamp: Utc.timestamp_opt(i, 0).unwrap(),
+            })
+            .collect()
+    }
+    
+    #[test]
+    fn test_reload_between_batches() {
+        let rules = HotReloadableRuleSet::new(TestRuleSet { version: Version::new(1, 0, 0), increment_by: 1 });
+        let handle = rules.clone();
+        let mut processor = TransactionProcessor::new(TestState { value: 0 }).unwrap();
+        let context = ExecutionContext::new(Utc.timestamp_opt(0, 0).unwrap(), 0);
+        
+        processor.process_transactions(&batch("a", 3), &rules, &context).unwrap();
+        assert_eq!(processor.current_state().value, 3);
+        
+        let reloaded = handle.reload(TestRuleSet { version: Version::new(2, 0, 0), increment_by: 10 }).unwrap();
+        assert_eq!(reloaded, Version::new(2, 0, 0));
+        assert_eq!(rules.version(), Version::new(2, 0, 0));
+        
+        processor.process_transactions(&batch("b", 2), &rules, &context).unwrap();
+        assert_eq!(processor.current_state().value, 23);
+        
+        let versions: Vec<(String, Version)> = processor
+            .execution_trace()
+            .rule_applications
+            .iter()
+            .map(|app| (app.transaction_id.clone(), app.rule_version.clone()))
+            .collect();
+        assert_eq!(versions[2], ("a2".to_string(), Version::new(1, 0, 0)));
+        assert_eq!(versions[3], ("b0".to_string(), Version::new(2, 0, 0)));
+        
+        assert_eq!(rules.version_history(), vec![Version::new(1, 0, 0), Version::new(2, 0, 0)]);
+    }
+    
+    #[test]
+    fn test_reload_rejects_previously_loaded_version() {
+        let rules = HotReloadableRuleSet::new(TestRuleSet { version: Version::new(1, 0, 0), increment_by: 1 });
+        rules.reload(TestRuleSet { version: Version::new(1, 1, 0), increment_by: 2 }).unwrap();
+        
+        let result = rules.reload(TestRuleSet { version: Version::new(1, 0, 0), increment_by: 5 });
+        assert!(matches!(result, Err(RuleError::VersionConflict { .. })));
+        
+        // The active rules are unchanged by the rejected reload
+        assert_eq!(rules.version(), Version::new(1, 1, 0));
+        assert_eq!(rules.version_history().len(), 2);
+    }
+    
+    #[test]
+    fn test_reload_from_another_thread() {
+        let rules = HotReloadableRuleSet::new(TestRuleSet { version: Version::new(1, 0, 0), increment_by: 1 });
+        let handle = rules.clone();
+        
+        std::thread::spawn(move || {
+            handle.reload(TestRuleSet { version: Version::new(3, 0, 0), increment_by: 3 }).unwrap();
+        })
+        .join()
+        .unwrap();
+        
+        let tx = &batch("c", 1)[0];
+        let context = ExecutionContext::new(Utc.timestamp_opt(0, 0).unwrap(), 0);
+        let state = rules.apply(&TestState { value: 0 }, tx, &context).unwrap();
+        assert_eq!(state.value, 3);
+    }
+    
+    /// Reloads to version 2.0.0 from inside its own `apply`
+    struct ReloadingRuleSet {
+        handle: Arc<OnceLock<HotReloadableRuleSet<TestState, TestTransaction>>>,
+    }
+    
+    impl RuleSet<TestState, TestTransaction> for ReloadingRuleSet {
+        fn version(&self) -> Version {
+            Version::new(1, 0, 0)
+        }
+        
+        fn apply(
+            &self,
+            state: &TestState,
+            _transaction: &TestTransaction,
+            _context: &ExecutionContext,
+        ) -> Result<TestState, ProcessingError> {
+            if let Some(handle) = self.handle.get() {
+                handle.reload(TestRuleSet { version: Version::new(2, 0, 0), increment_by: 10 }).unwrap();
+            }
+            Ok(TestState { value: state.value + 1 })
+        }
+    }
+    
+    #[test]
+    fn test_reload_during_transaction_keeps_one_version() {
+        let handle = Arc::new(OnceLock::new());
+        let rules = HotReloadableRuleSet::new(ReloadingRuleSet { handle: Arc::clone(&handle) });
+        handle.set(rules.clone()).ok().unwrap();
+        let mut processor = TransactionProcessor::new(TestState { value: 0 }).unwrap();
+        let context = ExecutionContext::new(Utc.timestamp_opt(0, 0).unwrap(), 0);
+        
+        processor.process_transactions(&batch("a", 2), &rules, &context).unwrap();
+        assert_eq!(processor.current_state().value, 11);
+        
+        // The reload inside a0's apply does not leak into a0's description or version
+        let applications = &processor.execution_trace().rule_applications;
+        assert_eq!(applications[0].rule_version, Version::new(1, 0, 0));
+        assert_eq!(applications[0].description, "Applied rule 1.0.0 to transaction a0");
+        assert_eq!(applications[1].rule_version, Version::new(2, 0, 0));
+    }
+}
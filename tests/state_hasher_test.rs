@@ -1,4 +1,4 @@
-use dtre::{StateHasher, State, StateHash};
+use dtre::{NormalizedState, StateHasher, State, StateHash};
 use proptest::prelude::*;
 use serde::{Serialize, Deserialize};
 use std::hash::{Hash, Hasher};
@@ -271,4 +271,328 @@ mod unit_tests {
         assert_eq!(chain2.0.len(), 32);
         assert_ne!(chain1, chain2);
     }
+    
+    #[test]
+    fn test_hash_in_place_matches_normalized_hash() {
+        let hasher = StateHasher::new();
+        let state = TestState { balance: 7, counter: 3, name: "in_place".to_string() };
+        
+        assert!(!TestState::NORMALIZES);
+        assert_eq!(hasher.hash(&state), hasher.hash_normalized(&NormalizedState::new(state.clone())));
+    }
+    
+    /// Sorts its entries when normalized but does not declare it
+    #[derive(Debug, Clone, Serialize, Deserialize, Hash)]
+    struct UndeclaredNormalization {
+        entries: Vec<i64>,
+    }
+    
+    impl State for UndeclaredNormalization {
+        fn validate(&self) -> Result<(), ValidationError> {
+            Ok(())
+        }
+        
+        fn normalize(&mut self) {
+            self.entries.sort();
+        }
+    }
+    
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "without setting State::NORMALIZES")]
+    fn test_undeclared_normalization_is_caught_in_debug_builds() {
+        StateHasher::new().hash(&UndeclaredNormalization { entries: vec![2, 1] });
+    }
+}
+
+#[cfg(test)]
+mod trace_verification_tests {
+    use super::*;
+    use chrono::{DateTime, TimeZone, Utc};
+    use dtre::{ExecutionContext, ProcessingError, RuleSet, Transaction, TransactionProcessor, Version};
+    
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    struct Deposit {
+        id: String,
+        amount: i64,
+        timestamp: DateTime<Utc>,
+    }
+    
+    impl Transaction for Deposit {
+        fn id(&self) -> &str {
+            &self.id
+        }
+        
+        fn timestamp(&self) -> DateTime<Utc> {
+            self.timestamp
+        }
+        
+        fn validate(&self) -> Result<(), ValidationError> {
+            Ok(())
+        }
+    }
+    
+    struct DepositRules;
+    
+    impl RuleSet<TestState, Deposit> for DepositRules {
+        fn version(&self) -> Version {
+            Version::new(1, 0, 0)
+        }
+        
+        fn apply(&self, state: &TestState, tx: &Deposit, _context: &ExecutionContext) -> Result<TestState, ProcessingError> {
+            Ok(TestState {
+                balance: state.balance + tx.amount,
+                counter: state.counter + 1,
+                name: state.name.clone(),
+            })
+        }
+    }
+    
+    fn setup() -> (TestState, Vec<Deposit>, ExecutionContext) {
+        let initial = TestState { balance: 0, counter: 0, name: "audit".to_string() };
+        let deposits = (0..5)
+            .map(|i| Deposit {
+                id: format!("tx{}", i),
+                amount: 10 * (i + 1),
+                timestamp: Utc.timestamp_opt(1_000_000 + i, 0).unwrap(),
+            })
+            .collect();
+        let context = ExecutionContext::new(Utc.timestamp_opt(1_000_000, 0).unwrap(), 7);
+        (initial, deposits, context)
+    }
+    
+    #[test]
+    fn test_untampered_trace_verifies() {
+        let (initial, deposits, context) = setup();
+        let mut processor = TransactionProcessor::new(initial.clone()).unwrap();
+        let transitions = processor.process_transactions(&deposits, &DepositRules, &context).unwrap();
+        
+        let hasher = StateHasher::new();
+        for (transition, deposit) in transitions.iter().zip(&deposits) {
+            assert!(hasher.verify_transition(transition, deposit, &DepositRules, &context).unwrap());
+        }
+        
+        let report = hasher
+            .verify_trace(processor.execution_trace(), &deposits, &DepositRules, &context, &initial)
+            .unwrap();
+        assert!(report.is_valid());
+        assert_eq!(report.verified_transitions, 5);
+    }
+    
+    #[test]
+    fn test_tampering_any_to_hash_fails_verification() {
+        let (initial, deposits, context) = setup();
+        let mut processor = TransactionProcessor::new(initial.clone()).unwrap();
+        let transitions = processor.process_transactions(&deposits, &DepositRules, &context).unwrap();
+        let hasher = StateHasher::new();
+        
+        for index in 0..deposits.len() {
+            let mut trace = processor.execution_trace().clone();
+            trace.state_transitions[index].to_hash.0[0] ^= 0xff;
+            
+            let report = hasher.verify_trace(&trace, &deposits, &DepositRules, &context, &initial).unwrap();
+            assert!(!report.is_valid());
+            assert_eq!(report.failures.len(), 1);
+            assert_eq!(report.failures[0].transition_index, index);
+            assert_eq!(report.verified_transitions, deposits.len() - 1);
+            
+            let mut transition = transitions[index].clone();
+            transition.to_hash = trace.state_transitions[index].to_hash;
+            assert!(!hasher.verify_transition(&transition, &deposits[index], &DepositRules, &context).unwrap());
+        }
+    }
+    
+    #[test]
+    fn test_fabricated_from_state_fails_verification() {
+        let (initial, deposits, context) = setup();
+        let mut processor = TransactionProcessor::new(initial).unwrap();
+        let mut transition = processor.process_transaction(&deposits[0], &DepositRules, &context).unwrap();
+        
+        // Pretend the account started richer while keeping the recorded hashes
+        transition.from_state.balance = 1_000;
+        assert!(!StateHasher::new().verify_transition(&transition, &deposits[0], &DepositRules, &context).unwrap());
+    }
+}
+
+#[cfg(test)]
+mod canonical_json_tests {
+    use super::*;
+    use std::collections::HashMap;
+    
+    #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+    struct LedgerState {
+        owner: String,
+        balances: HashMap<String, i64>,
+        limits: Vec<HashMap<String, i64>>,
+    }
+    
+    impl Hash for LedgerState {
+        fn hash<H: Hasher>(&self, state: &mut H) {
+            self.owner.hash(state);
+            let mut balances: Vec<_> = self.balances.iter().collect();
+            balances.sort();
+            balances.hash(state);
+        }
+    }
+    
+    impl State for LedgerState {
+        fn validate(&self) -> Result<(), ValidationError> {
+            Ok(())
+        }
+    }
+    
+    fn ledger(order: &[(&str, i64)]) -> LedgerState {
+        let mut balances = HashMap::new();
+        for (account, balance) in order {
+            balances.insert(account.to_string(), *balance);
+        }
+        let mut limit = HashMap::new();
+        limit.insert("y".to_string(), 1);
+        limit.insert("x".to_string(), 2);
+        
+        LedgerState {
+            owner: "alice".to_string(),
+            balances,
+            limits: vec![limit],
+        }
+    }
+    
+    #[test]
+    fn test_canonical_json_is_independent_of_insertion_order() {
+        let forward = ledger(&[("amy", 1), ("bob", 2), ("zed", 3)]);
+        let expected = forward.canonical_json().unwrap();
+        
+        for _ in 0..20 {
+            let reversed = ledger(&[("zed", 3), ("bob", 2), ("amy", 1)]);
+            assert_eq!(reversed.canonical_json().unwrap(), expected);
+        }
+        
+        assert_eq!(
+            expected,
+            r#"{"balances":{"amy":1,"bob":2,"zed":3},"limits":[{"x":2,"y":1}],"owner":"alice"}"#
+        );
+    }
+    
+    #[test]
+    fn test_canonical_hash_is_sha256_of_canonical_json() {
+        let hasher = StateHasher::new();
+        let state = ledger(&[("bob", 2), ("zed", 3), ("amy", 1)]);
+        let json = state.canonical_json().unwrap();
+        
+        // Reference digest computed independently with Python's hashlib
+        let expected = "d2e027e8ab7c8155a81d7016e5b757acc9a935aa42d601843c446b3d3d040b95";
+        assert_eq!(hasher.hash_canonical_json(&json).to_string(), expected);
+        assert_eq!(StateHash::from_canonical_json_bytes(json.as_bytes()).to_string(), expected);
+        assert_eq!(hasher.hash_canonical(&state).unwrap().to_string(), expected);
+    }
+    
+    #[test]
+    fn test_canonical_json_rejects_non_string_keys() {
+        #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+        struct KeyedState {
+            entries: HashMap<(i32, i32), i64>,
+        }
+        
+        impl Hash for KeyedState {
+            fn hash<H: Hasher>(&self, state: &mut H) {
+                self.entries.len().hash(state);
+            }
+        }
+        
+        impl State for KeyedState {
+            fn validate(&self) -> Result<(), ValidationError> {
+                Ok(())
+            }
+        }
+        
+        let mut entries = HashMap::new();
+        entries.insert((1, 2), 3);
+        assert!(KeyedState { entries }.canonical_json().is_err());
+    }
+}
+
+use dtre::{hash_state, to_bincode_bytes, IterationStrategy};
+
+#[cfg(test)]
+mod core_encoding_tests {
+    use super::*;
+    use std::collections::BTreeMap;
+    
+    #[derive(Debug, Serialize)]
+    enum Event {
+        Opened,
+        Renamed(String),
+        Moved(i32, i32),
+        Limited { daily: u64, note: Option<char> },
+    }
+    
+    #[derive(Debug, Serialize)]
+    struct Marker;
+    
+    #[derive(Debug, Serialize)]
+    struct Wrapper(u16);
+    
+    #[derive(Debug, Serialize)]
+    struct Everything {
+        flag: bool,
+        small: i8,
+        wide: i128,
+        unsigned: u128,
+        ratio: f64,
+        initial: char,
+        name: String,
+        missing: Option<u32>,
+        present: Option<Wrapper>,
+        marker: Marker,
+        unit: (),
+        pair: (u8, i64),
+        events: Vec<Event>,
+        totals: BTreeMap<String, i64>,
+    }
+    
+    #[test]
+    fn test_bincode_layout_matches_bincode() {
+        let value = Everything {
+            flag: true,
+            small: -3,
+            wide: -170_141_183_460_469_231_731_687_303_715_884_105_728,
+            unsigned: u128::MAX,
+            ratio: 0.1,
+            initial: 'é',
+            name: "ledger".to_string(),
+            missing: None,
+            present: Some(Wrapper(7)),
+            marker: Marker,
+            unit: (),
+            pair: (9, -9),
+            events: vec![
+                Event::Opened,
+                Event::Renamed("savings".to_string()),
+                Event::Moved(-1, 2),
+                Event::Limited { daily: 500, note: Some('€') },
+            ],
+            totals: [("amy".to_string(), 1), ("bob".to_string(), -2)].into_iter().collect(),
+        };
+        
+        assert_eq!(to_bincode_bytes(&value).unwrap(), bincode::serialize(&value).unwrap());
+    }
+    
+    #[test]
+    fn test_core_hash_matches_state_hasher_for_both_orders() {
+        let state = TestState { balance: 42, counter: 7, name: "alice".to_string() };
+        let hasher = StateHasher::new();
+        
+        assert_eq!(hash_state(&state, IterationStrategy::Insertion).unwrap(), hasher.hash(&state));
+        assert_eq!(
+            hash_state(&state, IterationStrategy::Sorted).unwrap(),
+            dtre::hash_bytes(state.canonical_json().unwrap().as_bytes())
+        );
+    }
+    
+    proptest! {
+        #[test]
+        fn property_core_hash_matches_state_hasher(state in arb_test_state()) {
+            prop_assert_eq!(hash_state(&state, IterationStrategy::Insertion).unwrap(), StateHasher::new().hash(&state));
+        }
+    }
 }
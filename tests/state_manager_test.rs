@@ -552,4 +552,756 @@ mod unit_tests {
         assert_ne!(diff.from_hash, diff.to_hash);
         assert!(!manager.compare_states(&state1, &state2));
     }
+    
+    #[test]
+    fn test_declared_fields_fall_back_to_full_validation() {
+        /// Adds the amount and declares that it only writes the balance
+        struct BalanceRuleSet;
+        
+        impl RuleSet<TestState, TestTransaction> for BalanceRuleSet {
+            fn version(&self) -> Version {
+                Version::new(1, 0, 0)
+            }
+            
+            fn apply(&self, state: &TestState, transaction: &TestTransaction, context: &ExecutionContext) -> Result<TestState, ProcessingError> {
+                TestRuleSet.apply(state, transaction, context)
+            }
+            
+            fn affects_fields(&self) -> Option<Vec<String>> {
+                Some(vec!["balance".to_string()])
+            }
+        }
+        
+        let state = TestState { balance: 10, counter: 0, name: "test".to_string() };
+        assert!(state.validate_field("balance").is_ok());
+        
+        let mut manager = StateManager::new(state).unwrap();
+        let overdraft = TestTransaction {
+            id: "tx1".to_string(),
+            amount: -20,
+            timestamp: Utc.timestamp_opt(1_000_000, 0).unwrap(),
+        };
+        let context = ExecutionContext::new(Utc.timestamp_opt(1_000_000, 0).unwrap(), 42);
+        let error = manager.apply_transaction(&overdraft, &BalanceRuleSet, &context).unwrap_err();
+        assert!(error.to_string().contains("Balance cannot be negative"));
+        assert_eq!(manager.current_state().balance, 10);
+    }
+    
+    #[test]
+    fn test_declared_fields_validate_once_without_field_validation() {
+        thread_local! {
+            static VALIDATIONS: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+        }
+        
+        #[derive(Debug, Clone, Hash, Serialize, Deserialize)]
+        struct CountedState {
+            balance: i64,
+            deposits: u32,
+            label: String,
+        }
+        
+        impl State for CountedState {
+            fn validate(&self) -> Result<(), ValidationError> {
+                VALIDATIONS.with(|count| count.set(count.get() + 1));
+                Ok(())
+            }
+        }
+        
+        /// Deposits the amount and declares every field it writes
+        struct DepositRuleSet;
+        
+        impl RuleSet<CountedState, TestTransaction> for DepositRuleSet {
+            fn version(&self) -> Version {
+                Version::new(1, 0, 0)
+            }
+            
+            fn apply(&self, state: &CountedState, transaction: &TestTransaction, _context: &ExecutionContext) -> Result<CountedState, ProcessingError> {
+                Ok(CountedState {
+                    balance: state.balance + transaction.amount,
+                    deposits: state.deposits + 1,
+                    label: transaction.id.clone(),
+                })
+            }
+            
+            fn affects_fields(&self) -> Option<Vec<String>> {
+                Some(vec!["balance".to_string(), "deposits".to_string(), "label".to_string()])
+            }
+        }
+        
+        let mut manager = StateManager::new(CountedState { balance: 0, deposits: 0, label: String::new() }).unwrap();
+        let deposit = TestTransaction {
+            id: "tx1".to_string(),
+            amount: 5,
+            timestamp: Utc.timestamp_opt(1_000_000, 0).unwrap(),
+        };
+        let context = ExecutionContext::new(Utc.timestamp_opt(1_000_000, 0).unwrap(), 42);
+        let before = VALIDATIONS.with(|count| count.get());
+        manager.apply_transaction(&deposit, &DepositRuleSet, &context).unwrap();
+        assert_eq!(VALIDATIONS.with(|count| count.get()) - before, 1);
+    }
+    
+    #[test]
+    fn test_declared_fields_commit_the_patched_state() {
+        thread_local! {
+            static PATCHES: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+        }
+        
+        #[derive(Debug, Clone, Hash, Serialize, Deserialize)]
+        struct PatchedState {
+            balance: i64,
+            label: String,
+        }
+        
+        impl State for PatchedState {
+            fn validate(&self) -> Result<(), ValidationError> {
+                Ok(())
+            }
+            
+            fn apply_patch(&mut self, patch: &StatePatch) -> Result<(), StateError> {
+                PATCHES.with(|count| count.set(count.get() + 1));
+                let mut json = serde_json::to_value(&*self).unwrap();
+                patch.apply_to_json(&mut json).map_err(|e| StateError::TransitionFailed { reason: e.to_string() })?;
+                *self = serde_json::from_value(json).unwrap();
+                Ok(())
+            }
+        }
+        
+        /// Deposits the amount, declaring the written fields only if `declared`
+        struct DepositRuleSet {
+            declared: bool,
+        }
+        
+        impl RuleSet<PatchedState, TestTransaction> for DepositRuleSet {
+            fn version(&self) -> Version {
+                Version::new(1, 0, 0)
+            }
+            
+            fn apply(&self, state: &PatchedState, transaction: &TestTransaction, _context: &ExecutionContext) -> Result<PatchedState, ProcessingError> {
+                Ok(PatchedState {
+                    balance: state.balance + transaction.amount,
+                    label: state.label.clone(),
+                })
+            }
+            
+            fn affects_fields(&self) -> Option<Vec<String>> {
+                self.declared.then(|| vec!["balance".to_string()])
+            }
+        }
+        
+        let mut manager = StateManager::new(PatchedState { balance: 10, label: "savings".to_string() }).unwrap();
+        let deposit = TestTransaction {
+            id: "tx1".to_string(),
+            amount: 5,
+            timestamp: Utc.timestamp_opt(1_000_000, 0).unwrap(),
+        };
+        let context = ExecutionContext::new(Utc.timestamp_opt(1_000_000, 0).unwrap(), 42);
+        
+        let transition = manager.apply_transaction(&deposit, &DepositRuleSet { declared: true }, &context).unwrap();
+        assert_eq!(PATCHES.with(|count| count.get()), 1);
+        assert_eq!(manager.current_state().balance, 15);
+        assert_eq!(manager.current_hash(), transition.to_hash);
+        
+        manager.apply_transaction(&deposit, &DepositRuleSet { declared: false }, &context).unwrap();
+        assert_eq!(PATCHES.with(|count| count.get()), 1);
+        assert_eq!(manager.current_state().balance, 20);
+    }
+    
+    #[test]
+    fn test_patch_of_state_without_json_form_fails_to_apply() {
+        // JSON objects only have string keys
+        #[derive(Debug, Clone, Default, Hash, Serialize, Deserialize)]
+        struct GridState {
+            cells: std::collections::BTreeMap<(u8, u8), i64>,
+        }
+        
+        impl State for GridState {
+            fn validate(&self) -> Result<(), ValidationError> {
+                Ok(())
+            }
+        }
+        
+        let before = GridState::default();
+        let mut after = before.clone();
+        after.cells.insert((1, 2), 3);
+        let patch = StatePatch::from_diff(&before, &after);
+        
+        let mut target = serde_json::json!({ "cells": {} });
+        assert!(matches!(patch.apply_to_json(&mut target), Err(SerializationError::SerializationFailed { .. })));
+        assert_eq!(target, serde_json::json!({ "cells": {} }));
+        assert!(matches!(before.clone().apply_patch(&patch), Err(StateError::TransitionFailed { .. })));
+    }
+}
+
+#[cfg(test)]
+mod history_replay_tests {
+    use super::*;
+    
+    fn make_transactions(count: usize) -> Vec<TestTransaction> {
+        (0..count)
+            .map(|i| TestTransaction {
+                id: format!("tx{}", i),
+                amount: (i as i64 % 7) + 1,
+                timestamp: Utc.timestamp_opt(1_000_000 + i as i64, 0).unwrap(),
+            })
+            .collect()
+    }
+    
+    #[test]
+    fn test_history_replay_matches_trace_checkpoints() {
+        let state = TestState {
+            balance: 1000,
+            counter: 0,
+            name: "history".to_string(),
+        };
+        let transactions = make_transactions(100);
+        let context = ExecutionContext::new(Utc.timestamp_opt(1_000_000, 0).unwrap(), 42);
+        
+        let mut processor = TransactionProcessor::new(state).unwrap();
+        processor
+            .process_transactions_with_checkpoints(&transactions, &TestRuleSet, &context, 10)
+            .unwrap();
+        
+        let history = processor
+            .state_manager()
+            .history_replay(&transactions, &TestRuleSet, &context)
+            .unwrap();
+        
+        let trace_checkpoints = &processor.execution_trace().checkpoints;
+        assert_eq!(history.len(), 10);
+        assert_eq!(trace_checkpoints.len(), 10);
+        
+        let hasher = StateHasher::new();
+        for (entry, info) in history.entries().iter().zip(trace_checkpoints.iter()) {
+            assert_eq!(entry.transaction_index, info.transaction_index);
+            assert_eq!(entry.hash, info.hash);
+            assert_eq!(hasher.hash(&entry.state), info.hash);
+        }
+        
+        assert_eq!(history.iter_states().count(), 10);
+        assert_eq!(history.state_at_closest_to(33).counter, 30);
+        assert_eq!(history.state_at_closest_to(1000).counter, 100);
+    }
+    
+    #[test]
+    fn test_history_replay_without_checkpoints_is_empty() {
+        let state = TestState {
+            balance: 1000,
+            counter: 0,
+            name: "history".to_string(),
+        };
+        let manager = StateManager::new(state).unwrap();
+        let context = ExecutionContext::new(Utc.timestamp_opt(1_000_000, 0).unwrap(), 42);
+        
+        let history = manager
+            .history_replay(&make_transactions(5), &TestRuleSet, &context)
+            .unwrap();
+        assert!(history.is_empty());
+    }
+    
+    #[test]
+    fn test_history_replay_detects_divergent_log() {
+        let state = TestState {
+            balance: 1000,
+            counter: 0,
+            name: "history".to_string(),
+        };
+        let transactions = make_transactions(20);
+        let context = ExecutionContext::new(Utc.timestamp_opt(1_000_000, 0).unwrap(), 42);
+        
+        let mut processor = TransactionProcessor::new(state).unwrap();
+        processor
+            .process_transactions_with_checkpoints(&transactions, &TestRuleSet, &context, 10)
+            .unwrap();
+        
+        let mut tampered = transactions.clone();
+        tampered[15].amount += 1;
+        
+        let result = processor
+            .state_manager()
+            .history_replay(&tampered, &TestRuleSet, &context);
+        assert!(result.is_err());
+    }
+}
+
+#[cfg(test)]
+mod schema_version_tests {
+    use super::*;
+    
+    /// Second revision of the state schema that renamed `balance` to `funds`
+    #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Hash)]
+    struct StateV2 {
+        funds: i64,
+    }
+    
+    impl State for StateV2 {
+        const SCHEMA_VERSION: u32 = 2;
+        
+        fn validate(&self) -> Result<(), ValidationError> {
+            Ok(())
+        }
+    }
+    
+    /// Same layout as `StateV2` but knows how to upgrade version 1 checkpoints, which stored `balance`
+    #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Hash)]
+    struct MigratingStateV2 {
+        funds: i64,
+    }
+    
+    impl State for MigratingStateV2 {
+        const SCHEMA_VERSION: u32 = 2;
+        
+        fn validate(&self) -> Result<(), ValidationError> {
+            Ok(())
+        }
+        
+        fn migrate(old_version: u32, raw: serde_json::Value) -> Result<Self, StateError> {
+            match old_version {
+                1 => Ok(MigratingStateV2 {
+                    funds: raw["balance"].as_i64().unwrap_or_default() * 100,
+                }),
+                _ => Err(StateError::SchemaMismatch {
+                    checkpoint_version: old_version,
+                    current_version: Self::SCHEMA_VERSION,
+                }),
+            }
+        }
+    }
+    
+    fn v1_checkpoint<S: State>(state: S) -> Checkpoint<S> {
+        Checkpoint {
+            hash: StateHasher::new().hash(&state),
+            state,
+            transaction_index: 3,
+            timestamp: Utc.timestamp_opt(1_000_000, 0).unwrap(),
+            state_schema_version: 1,
+        }
+    }
+    
+    /// A version 1 checkpoint as stored, before `balance` was renamed
+    fn v1_raw_checkpoint() -> RawCheckpoint {
+        let json = r#"{"state":{"balance":7},"hash":[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0],"transaction_index":3,"timestamp":"2020-01-01T00:00:00Z","state_schema_version":1}"#;
+        RawCheckpoint::from_json_bytes(json.as_bytes()).unwrap()
+    }
+    
+    #[test]
+    fn test_default_schema_version() {
+        assert_eq!(TestState::SCHEMA_VERSION, 1);
+        assert_eq!(StateManager::<TestState>::SCHEMA_VERSION, 1);
+        assert_eq!(StateManager::<StateV2>::SCHEMA_VERSION, 2);
+        
+        let mut manager = StateManager::new(StateV2 { funds: 5 }).unwrap();
+        assert_eq!(manager.create_checkpoint(Utc::now()).state_schema_version, 2);
+    }
+    
+    #[test]
+    fn test_restore_old_schema_fails_without_migration() {
+        let mut manager = StateManager::new(StateV2 { funds: 0 }).unwrap();
+        let result = manager.restore_checkpoint(&v1_checkpoint(StateV2 { funds: 7 }));
+        
+        match result {
+            Err(StateError::SchemaMismatch { checkpoint_version, current_version }) => {
+                assert_eq!(checkpoint_version, 1);
+                assert_eq!(current_version, 2);
+            }
+            other => panic!("Expected SchemaMismatch, got {:?}", other),
+        }
+        
+        let result = manager.restore_raw_checkpoint(&v1_raw_checkpoint());
+        assert!(matches!(result, Err(StateError::SchemaMismatch { checkpoint_version: 1, current_version: 2 })));
+        assert_eq!(manager.current_state().funds, 0);
+    }
+    
+    #[test]
+    fn test_restore_old_schema_succeeds_with_migration() {
+        let mut manager = StateManager::new(MigratingStateV2 { funds: 0 }).unwrap();
+        
+        // A decoded checkpoint has already lost the old layout
+        let result = manager.restore_checkpoint(&v1_checkpoint(MigratingStateV2 { funds: 7 }));
+        assert!(matches!(result, Err(StateError::SchemaMismatch { checkpoint_version: 1, current_version: 2 })));
+        
+        manager.restore_raw_checkpoint(&v1_raw_checkpoint()).unwrap();
+        assert_eq!(manager.current_state().funds, 700);
+        assert_eq!(manager.transaction_count(), 3);
+    }
+    
+    #[test]
+    fn test_checkpoint_without_schema_version_deserializes_as_v1() {
+        let json = r#"{"state":{"funds":1},"hash":[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0],"transaction_index":0,"timestamp":"2020-01-01T00:00:00Z"}"#;
+        let checkpoint: Checkpoint<StateV2> = serde_json::from_str(json).unwrap();
+        assert_eq!(checkpoint.state_schema_version, 1);
+    }
+}
+
+#[cfg(test)]
+mod checkpoint_integrity_tests {
+    use super::*;
+    
+    fn manager() -> StateManager<TestState> {
+        StateManager::new(TestState { balance: 100, counter: 0, name: "alice".to_string() }).unwrap()
+    }
+    
+    #[test]
+    fn test_intact_checkpoint_verifies() {
+        let mut manager = manager();
+        let checkpoint = manager.create_checkpoint(Utc.timestamp_opt(0, 0).unwrap());
+        
+        assert!(checkpoint.verify_integrity().is_ok());
+        
+        // A serialization round trip preserves integrity
+        let restored: Checkpoint<TestState> = serde_json::from_str(&serde_json::to_string(&checkpoint).unwrap()).unwrap();
+        assert!(restored.verify_integrity().is_ok());
+    }
+    
+    #[test]
+    fn test_modified_checkpoint_fails_integrity() {
+        let mut manager = manager();
+        let mut checkpoint = manager.create_checkpoint(Utc.timestamp_opt(0, 0).unwrap());
+        let recorded = checkpoint.hash;
+        checkpoint.state.balance = 1_000_000;
+        
+        match checkpoint.verify_integrity() {
+            Err(StateError::CheckpointIntegrityFailed { recorded_hash, computed_hash }) => {
+                assert_eq!(recorded_hash, recorded);
+                assert_eq!(computed_hash, StateHasher::new().hash(&checkpoint.state));
+            }
+            other => panic!("expected integrity failure, got {:?}", other),
+        }
+    }
+    
+    #[test]
+    fn test_restore_rejects_corrupt_checkpoint_without_modifying_state() {
+        let mut manager = manager();
+        let mut checkpoint = manager.create_checkpoint(Utc.timestamp_opt(0, 0).unwrap());
+        checkpoint.state.balance = 1_000_000;
+        checkpoint.transaction_index = 99;
+        
+        let tx = TestTransaction {
+            id: "tx1".to_string(),
+            amount: 25,
+            timestamp: Utc.timestamp_opt(1, 0).unwrap(),
+        };
+        let context = ExecutionContext::new(Utc.timestamp_opt(0, 0).unwrap(), 42);
+        manager.apply_transaction(&tx, &TestRuleSet, &context).unwrap();
+        let hash_before = manager.current_hash();
+        
+        let result = manager.restore_checkpoint(&checkpoint);
+        assert!(matches!(result, Err(StateError::CheckpointIntegrityFailed { .. })));
+        assert_eq!(manager.current_state().balance, 125);
+        assert_eq!(manager.current_hash(), hash_before);
+        assert_eq!(manager.transaction_count(), 1);
+    }
+    
+    #[test]
+    fn test_verify_all_checkpoints_reports_each() {
+        let mut manager = manager();
+        let context = ExecutionContext::new(Utc.timestamp_opt(0, 0).unwrap(), 42);
+        for i in 0..3 {
+            let tx = TestTransaction {
+                id: format!("tx{}", i),
+                amount: 10,
+                timestamp: Utc.timestamp_opt(i, 0).unwrap(),
+            };
+            manager.apply_transaction(&tx, &TestRuleSet, &context).unwrap();
+            manager.create_checkpoint(Utc.timestamp_opt(i, 0).unwrap());
+        }
+        
+        let results = manager.verify_all_checkpoints();
+        assert_eq!(results.len(), 3);
+        assert!(results.iter().all(Result::is_ok));
+    }
+}
+
+#[cfg(test)]
+mod checkpoint_migration_tests {
+    use super::*;
+    use std::path::PathBuf;
+    
+    /// Second revision of an account state: `owner` replaced the old `name` field
+    /// and `currency` was added
+    #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Hash)]
+    struct AccountStateV2 {
+        balance: i64,
+        owner: String,
+        currency: String,
+    }
+    
+    impl State for AccountStateV2 {
+        const SCHEMA_VERSION: u32 = 2;
+        
+        fn validate(&self) -> Result<(), ValidationError> {
+            if self.owner.is_empty() {
+                return Err(ValidationError::InvalidState { reason: "owner is required".to_string() });
+            }
+            Ok(())
+        }
+    }
+    
+    fn migrate_v1(old_version: u32, mut raw: serde_json::Value) -> Result<AccountStateV2, StateError> {
+        if old_version != 1 {
+            return Err(StateError::SchemaMismatch { checkpoint_version: old_version, current_version: 2 });
+        }
+        let fields = raw.as_object_mut().ok_or_else(|| StateError::CheckpointError {
+            reason: "state is not an object".to_string(),
+        })?;
+        let name = fields.remove("name").unwrap_or_default();
+        fields.insert("owner".to_string(), name);
+        fields.insert("currency".to_string(), serde_json::json!("USD"));
+        serde_json::from_value(raw).map_err(|e| StateError::CheckpointError { reason: e.to_string() })
+    }
+    
+    fn v1_checkpoint_json(name: &str) -> String {
+        format!(
+            r#"{{"state":{{"balance":250,"name":"{}"}},"hash":{:?},"transaction_index":4,"timestamp":"2024-01-01T00:00:00Z","state_schema_version":1}}"#,
+            name,
+            [7u8; 32]
+        )
+    }
+    
+    fn scratch_directory(name: &str) -> PathBuf {
+        let directory = std::env::temp_dir().join(format!("dtre-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+        std::fs::create_dir_all(&directory).unwrap();
+        directory
+    }
+    
+    #[test]
+    fn test_migrate_v1_checkpoint_to_v2() {
+        let raw = RawCheckpoint::from_json_bytes(v1_checkpoint_json("alice").as_bytes()).unwrap();
+        assert_eq!(raw.state_schema_version, 1);
+        
+        let checkpoint = CheckpointMigrator::new(migrate_v1).migrate(&raw).unwrap();
+        
+        assert_eq!(checkpoint.state, AccountStateV2 {
+            balance: 250,
+            owner: "alice".to_string(),
+            currency: "USD".to_string(),
+        });
+        assert_eq!(checkpoint.state_schema_version, 2);
+        assert_eq!(checkpoint.transaction_index, 4);
+        assert!(checkpoint.verify_integrity().is_ok());
+        
+        let json = serde_json::to_value(&checkpoint).unwrap();
+        assert!(json["state"].get("name").is_none());
+    }
+    
+    #[test]
+    fn test_migrated_state_must_validate() {
+        let raw = RawCheckpoint::from_json_bytes(v1_checkpoint_json("").as_bytes()).unwrap();
+        let result = CheckpointMigrator::new(migrate_v1).migrate(&raw);
+        assert!(matches!(result, Err(StateError::CheckpointError { .. })));
+    }
+    
+    #[test]
+    fn test_default_migrator_uses_state_migrate() {
+        let raw = RawCheckpoint::from_json_bytes(v1_checkpoint_json("alice").as_bytes()).unwrap();
+        let result = CheckpointMigrator::<AccountStateV2>::from_state_migrations().migrate(&raw);
+        assert!(matches!(result, Err(StateError::SchemaMismatch { checkpoint_version: 1, current_version: 2 })));
+    }
+    
+    #[test]
+    fn test_migrate_directory_keeps_backups() {
+        let directory = scratch_directory("checkpoint-migration");
+        std::fs::write(directory.join("a.json"), v1_checkpoint_json("alice")).unwrap();
+        std::fs::write(directory.join("b.json"), v1_checkpoint_json("")).unwrap();
+        std::fs::write(directory.join("notes.txt"), "not a checkpoint").unwrap();
+        
+        let migrator = CheckpointMigrator::new(migrate_v1);
+        let report = migrator.migrate_directory(&directory).unwrap();
+        
+        assert_eq!(report.migrated, vec![directory.join("a.json")]);
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(report.failed[0].0, directory.join("b.json"));
+        assert!(!report.is_success());
+        
+        // The original is kept as a backup and the file now holds the v2 checkpoint
+        let backup = std::fs::read_to_string(directory.join("a.json.backup")).unwrap();
+        assert_eq!(backup, v1_checkpoint_json("alice"));
+        let migrated: Checkpoint<AccountStateV2> =
+            serde_json::from_slice(&std::fs::read(directory.join("a.json")).unwrap()).unwrap();
+        assert_eq!(migrated.state.owner, "alice");
+        assert_eq!(migrated.state_schema_version, 2);
+        
+        // The failed file is untouched and a second run finds the migrated file current
+        assert!(!directory.join("b.json.backup").exists());
+        let rerun = migrator.migrate_directory(&directory).unwrap();
+        assert_eq!(rerun.up_to_date, vec![directory.join("a.json")]);
+        assert!(rerun.migrated.is_empty());
+        
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
+
+#[cfg(test)]
+mod watch_tests {
+    use super::*;
+    
+    fn deposit(index: usize, amount: i64) -> TestTransaction {
+        TestTransaction {
+            id: format!("tx{}", index),
+            amount,
+            timestamp: Utc.timestamp_opt(1000 + index as i64, 0).unwrap(),
+        }
+    }
+    
+    #[test]
+    fn test_watchers_receive_state_changes() {
+        let context = ExecutionContext::new(Utc.timestamp_opt(1000, 0).unwrap(), 42);
+        let mut manager = StateManager::new(TestState { balance: 0, counter: 0, name: "watched".to_string() }).unwrap();
+        let all = manager.watch();
+        let above_threshold = manager.watch_for(|s: &TestState| s.balance > 200_000);
+        let dropped = manager.watch();
+        drop(dropped);
+        
+        for index in 0..5 {
+            manager.apply_transaction(&deposit(index, 50_000), &TestRuleSet, &context).unwrap();
+        }
+        
+        let events: Vec<StateChangeEvent> = all.try_iter().collect();
+        assert_eq!(events.len(), 5);
+        assert_eq!(events[4].transaction_id, "tx4");
+        assert_eq!(events[4].new_hash, manager.current_hash());
+        
+        // Only the fifth deposit takes the balance past 200,000
+        let crossed: Vec<StateChangeEvent> = above_threshold.try_iter().collect();
+        assert_eq!(crossed.len(), 1);
+        assert_eq!(crossed[0].transaction_id, "tx4");
+        
+        // A failed transaction sends nothing
+        assert!(manager.apply_transaction(&deposit(5, -1_000_000), &TestRuleSet, &context).is_err());
+        assert!(all.try_recv().is_err());
+    }
+}
+
+#[cfg(test)]
+mod versioned_snapshot_tests {
+    use super::*;
+    use std::path::PathBuf;
+    
+    fn snapshot_path(machine: &str) -> PathBuf {
+        let directory = std::env::temp_dir().join(format!("dtre-snapshot-{}-{}", machine, std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        directory.join("nightly.zip")
+    }
+    
+    fn manager_after_deposits() -> StateManager<TestState> {
+        let context = ExecutionContext::new(Utc.timestamp_opt(1000, 0).unwrap(), 42);
+        let mut manager = StateManager::new(TestState { balance: 0, counter: 0, name: "ledger".to_string() }).unwrap();
+        for index in 0..3 {
+            let deposit = TestTransaction {
+                id: format!("tx{}", index),
+                amount: 100 * (index + 1),
+                timestamp: Utc.timestamp_opt(1000 + index, 0).unwrap(),
+            };
+            manager.apply_transaction(&deposit, &TestRuleSet, &context).unwrap();
+        }
+        manager
+    }
+    
+    #[test]
+    fn test_snapshot_archive_opens_on_another_machine() {
+        let manager = manager_after_deposits();
+        let snapshot = manager.take_versioned_snapshot("nightly", &TestRuleSet, 3);
+        assert_eq!(snapshot.rule_set_version, Version::new(1, 0, 0));
+        assert_eq!(snapshot.schema_version, SNAPSHOT_SCHEMA_VERSION);
+        assert!(snapshot.created_by.ends_with(&format!(":{}", std::process::id())));
+        
+        // Ship the archive's bytes to a different location, as a copy to another host would
+        let origin = snapshot_path("origin");
+        snapshot.to_archive(&origin).unwrap();
+        let destination = snapshot_path("destination");
+        std::fs::write(&destination, std::fs::read(&origin).unwrap()).unwrap();
+        
+        let restored = VersionedSnapshot::<TestState>::from_archive(&destination).unwrap();
+        assert_eq!(restored.snapshot_name, "nightly");
+        assert_eq!(restored.created_by, snapshot.created_by);
+        assert_eq!(restored.transaction_index, 3);
+        assert_eq!(restored.state, *manager.current_state());
+        assert_eq!(restored.hash, manager.current_hash());
+        assert_eq!(StateHasher::new().hash(&restored.state), manager.current_hash());
+        
+        let mut recovered = StateManager::new(TestState { balance: 0, counter: 0, name: "empty".to_string() }).unwrap();
+        recovered.restore_checkpoint(&restored.checkpoint).unwrap();
+        assert_eq!(recovered.current_hash(), manager.current_hash());
+    }
+    
+    #[test]
+    fn test_tampered_snapshot_archive_is_rejected() {
+        let mut snapshot = manager_after_deposits().take_versioned_snapshot("tampered", &TestRuleSet, 3);
+        snapshot.checkpoint.state.balance += 1;
+        let path = snapshot_path("tampered");
+        snapshot.to_archive(&path).unwrap();
+        
+        // The checksums match the written files, but the state no longer hashes to the recorded hash
+        let result = VersionedSnapshot::<TestState>::from_archive(&path);
+        assert!(matches!(result, Err(StateError::CheckpointIntegrityFailed { .. })));
+        
+        std::fs::write(&path, b"not a zip archive").unwrap();
+        let result = VersionedSnapshot::<TestState>::from_archive(&path);
+        assert!(matches!(result, Err(StateError::CheckpointError { .. })));
+    }
+}
+
+#[cfg(test)]
+mod anomaly_tests {
+    use super::*;
+    
+    fn deposits(count: i64, spacing_ms: i64) -> Vec<TestTransaction> {
+        (0..count)
+            .map(|index| TestTransaction {
+                id: format!("tx{}", index),
+                amount: 100,
+                timestamp: Utc.timestamp_opt(1000, 0).unwrap() + chrono::Duration::milliseconds(index * spacing_ms),
+            })
+            .collect()
+    }
+    
+    fn initial_state() -> TestState {
+        TestState { balance: 0, counter: 0, name: "anomalies".to_string() }
+    }
+    
+    #[test]
+    fn test_consecutive_failures_are_reported_after_the_first() {
+        let context = ExecutionContext::new(Utc.timestamp_opt(1000, 0).unwrap(), 42);
+        let engine = ReplayEngine::new(initial_state(), FailingRuleSet, context);
+        let result = engine
+            .replay_with_recovery_strategy(&deposits(4, 1000), |_| RecoveryAction::Skip)
+            .unwrap();
+        assert_eq!(result.execution_trace.skipped_indices, vec![0, 1, 2, 3]);
+        
+        let config = AnomalyDetectionConfig { max_consecutive_failures: 1, ..Default::default() };
+        let anomalies = result.execution_trace.detect_anomalies(&config);
+        
+        // One anomaly for the whole run, at the second failure
+        assert_eq!(anomalies.len(), 1);
+        assert_eq!(anomalies[0].anomaly_type, AnomalyType::ConsecutiveFailures);
+        assert_eq!(anomalies[0].transaction_index, 1);
+        assert_eq!(anomalies[0].severity, AnomalySeverity::High);
+        assert!(anomalies[0].description.contains("tx1"));
+        
+        // The default tolerates three failures in a row
+        assert_eq!(result.execution_trace.detect_anomalies(&AnomalyDetectionConfig::default()).len(), 1);
+    }
+    
+    #[test]
+    fn test_hash_changes_and_bursts_are_reported() {
+        let context = ExecutionContext::new(Utc.timestamp_opt(1000, 0).unwrap(), 42);
+        let result = ReplayEngine::new(initial_state(), TestRuleSet, context)
+            .replay(&deposits(3, 10))
+            .unwrap();
+        
+        assert!(result.execution_trace.detect_anomalies(&AnomalyDetectionConfig::default()).is_empty());
+        
+        let config = AnomalyDetectionConfig {
+            max_state_hash_change_rate: 0.9,
+            min_transaction_interval_ms: 50,
+            ..Default::default()
+        };
+        let anomalies = result.execution_trace.detect_anomalies(&config);
+        let kinds: Vec<(AnomalyType, usize)> = anomalies.iter().map(|a| (a.anomaly_type, a.transaction_index)).collect();
+        assert_eq!(kinds, vec![
+            (AnomalyType::TransactionBurst, 1),
+            (AnomalyType::ExcessiveHashChanges, 2),
+            (AnomalyType::TransactionBurst, 2),
+        ]);
+    }
 }
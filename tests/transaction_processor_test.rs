@@ -465,3 +465,1849 @@ mod unit_tests {
         assert_eq!(trace.state_transitions[2].transaction_id, "tx3");
     }
 }
+
+#[cfg(test)]
+mod pre_validation_tests {
+    use super::*;
+    
+    /// Rejects withdrawals larger than the balance in `pre_validate`, and
+    /// rejects zero-amount transactions inside `apply`
+    struct GuardedRuleSet;
+    
+    impl RuleSet<TestState, TestTransaction> for GuardedRuleSet {
+        fn version(&self) -> Version {
+            Version::new(1, 2, 0)
+        }
+        
+        fn pre_validate(
+            &self,
+            state: &TestState,
+            transaction: &TestTransaction,
+            _context: &ExecutionContext,
+        ) -> Result<(), ValidationError> {
+            if state.balance + transaction.amount < 0 {
+                return Err(ValidationError::RuleViolated {
+                    rule: "insufficient balance".to_string(),
+                });
+            }
+            Ok(())
+        }
+        
+        fn apply(
+            &self,
+            state: &TestState,
+            transaction: &TestTransaction,
+            _context: &ExecutionContext,
+        ) -> Result<TestState, ProcessingError> {
+            if transaction.amount == 0 {
+                return Err(ProcessingError::RuleApplicationFailed {
+                    rule_version: self.version(),
+                    details: "zero amount".to_string(),
+                });
+            }
+            Ok(TestState {
+                balance: state.balance + transaction.amount,
+                transaction_count: state.transaction_count + 1,
+            })
+        }
+    }
+    
+    fn transaction(id: &str, amount: i64) -> TestTransaction {
+        TestTransaction {
+            id: id.to_string(),
+            amount,
+            timestamp: Utc.timestamp_opt(1000000, 0).unwrap(),
+        }
+    }
+    
+    #[test]
+    fn test_pre_validate_failure_is_distinguishable_from_apply_failure() {
+        let state = TestState {
+            balance: 100,
+            transaction_count: 0,
+        };
+        let mut processor = TransactionProcessor::new(state).unwrap();
+        let context = ExecutionContext::new(Utc.timestamp_opt(1000000, 0).unwrap(), 42);
+        
+        let pre_validation = processor.process_transaction(&transaction("tx1", -500), &GuardedRuleSet, &context);
+        match pre_validation {
+            Err(ProcessingError::PreValidationFailed { rule_version, detail }) => {
+                assert_eq!(rule_version, Version::new(1, 2, 0));
+                assert_eq!(detail.context.transaction_id.as_deref(), Some("tx1"));
+                assert!(detail.violated_rules[0].contains("insufficient balance"));
+            }
+            other => panic!("Expected PreValidationFailed, got {:?}", other),
+        }
+        
+        let apply_failure = processor.process_transaction(&transaction("tx2", 0), &GuardedRuleSet, &context);
+        assert!(matches!(apply_failure, Err(ProcessingError::RuleApplicationFailed { .. })));
+        
+        // Neither failure changes the state
+        assert_eq!(processor.current_state().balance, 100);
+        assert_eq!(processor.transactions_processed(), 0);
+    }
+    
+    #[test]
+    fn test_default_pre_validate_accepts_everything() {
+        let state = TestState {
+            balance: 100,
+            transaction_count: 0,
+        };
+        let mut processor = TransactionProcessor::new(state).unwrap();
+        let context = ExecutionContext::new(Utc.timestamp_opt(1000000, 0).unwrap(), 42);
+        let rule_set = TestRuleSet {
+            version: Version::new(1, 0, 0),
+        };
+        
+        processor.process_transaction(&transaction("tx1", 25), &rule_set, &context).unwrap();
+        assert_eq!(processor.current_state().balance, 125);
+    }
+}
+
+#[cfg(test)]
+mod side_effect_tests {
+    use super::*;
+    use dtre::{SideEffect, SideEffectQueue};
+    use futures::{executor::block_on, StreamExt};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    
+    struct Notify {
+        executed: Arc<AtomicUsize>,
+    }
+    
+    #[async_trait::async_trait]
+    impl SideEffect for Notify {
+        async fn execute(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+            self.executed.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+    }
+    
+    struct NotifyingRuleSet {
+        executed: Arc<AtomicUsize>,
+    }
+    
+    impl RuleSet<TestState, TestTransaction> for NotifyingRuleSet {
+        fn version(&self) -> Version {
+            Version::new(1, 0, 0)
+        }
+        
+        fn apply(
+            &self,
+            state: &TestState,
+            transaction: &TestTransaction,
+            _context: &ExecutionContext,
+        ) -> Result<TestState, ProcessingError> {
+            Ok(TestState {
+                balance: state.balance + transaction.amount,
+                transaction_count: state.transaction_count + 1,
+            })
+        }
+        
+        fn enqueue_side_effects(&self, _state: &TestState, transaction: &TestTransaction, queue: &SideEffectQueue) {
+            queue.enqueue(transaction.id(), Notify { executed: Arc::clone(&self.executed) });
+        }
+    }
+    
+    fn transactions() -> Vec<TestTransaction> {
+        (1..=3)
+            .map(|i| TestTransaction {
+                id: format!("tx{}", i),
+                amount: 10,
+                timestamp: Utc.timestamp_opt(1000000 + i, 0).unwrap(),
+            })
+            .collect()
+    }
+    
+    #[test]
+    fn test_side_effects_collected_not_executed_during_processing() {
+        let executed = Arc::new(AtomicUsize::new(0));
+        let rule_set = NotifyingRuleSet { executed: Arc::clone(&executed) };
+        let context = ExecutionContext::new(Utc.timestamp_opt(1000000, 0).unwrap(), 42);
+        let queue = SideEffectQueue::new();
+        
+        let mut processor = TransactionProcessor::new(TestState { balance: 0, transaction_count: 0 })
+            .unwrap()
+            .with_side_effect_queue(queue.clone());
+        processor.process_transactions(&transactions(), &rule_set, &context).unwrap();
+        
+        assert_eq!(queue.len(), 3);
+        assert_eq!(queue.pending_transactions(), vec!["tx1", "tx2", "tx3"]);
+        assert_eq!(executed.load(Ordering::SeqCst), 0);
+        
+        let results: Vec<_> = block_on(queue.drain().collect());
+        assert_eq!(results.len(), 3);
+        assert!(results.iter().all(|r| r.is_success()));
+        assert_eq!(executed.load(Ordering::SeqCst), 3);
+        assert!(queue.is_empty());
+    }
+    
+    #[test]
+    fn test_replay_enqueues_effects_afresh() {
+        let executed = Arc::new(AtomicUsize::new(0));
+        let rule_set = NotifyingRuleSet { executed: Arc::clone(&executed) };
+        let context = ExecutionContext::new(Utc.timestamp_opt(1000000, 0).unwrap(), 42);
+        let queue = SideEffectQueue::new();
+        
+        for _ in 0..2 {
+            let mut processor = TransactionProcessor::new(TestState { balance: 0, transaction_count: 0 })
+                .unwrap()
+                .with_side_effect_queue(queue.clone());
+            processor.process_transactions(&transactions(), &rule_set, &context).unwrap();
+        }
+        
+        assert_eq!(queue.len(), 6);
+        
+        // Without a queue attached, the hook is never invoked
+        let mut processor = TransactionProcessor::new(TestState { balance: 0, transaction_count: 0 }).unwrap();
+        processor.process_transactions(&transactions(), &rule_set, &context).unwrap();
+        assert_eq!(queue.len(), 6);
+    }
+}
+
+#[cfg(test)]
+mod execution_phase_tests {
+    use super::*;
+    use dtre::ExecutionPhase;
+    
+    /// Only caps withdrawals while validating; while applying it instead
+    /// rejects zero amounts. Mismatched phases are reported as errors.
+    struct PhaseAwareRuleSet;
+    
+    impl RuleSet<TestState, TestTransaction> for PhaseAwareRuleSet {
+        fn version(&self) -> Version {
+            Version::new(1, 0, 0)
+        }
+        
+        fn pre_validate(
+            &self,
+            _state: &TestState,
+            _transaction: &TestTransaction,
+            context: &ExecutionContext,
+        ) -> Result<(), ValidationError> {
+            match context.current_phase() {
+                ExecutionPhase::PreProcessing => Ok(()),
+                phase => Err(ValidationError::RuleViolated {
+                    rule: format!("pre_validate called in {:?}", phase),
+                }),
+            }
+        }
+        
+        fn apply(
+            &self,
+            state: &TestState,
+            transaction: &TestTransaction,
+            context: &ExecutionContext,
+        ) -> Result<TestState, ProcessingError> {
+            match context.current_phase() {
+                ExecutionPhase::PreProcessing if transaction.amount < -100 => {
+                    Err(ProcessingError::TransactionFailed {
+                        transaction_id: transaction.id.clone(),
+                        reason: "withdrawal limit exceeded".to_string(),
+                    })
+                }
+                ExecutionPhase::MainProcessing if transaction.amount == 0 => {
+                    Err(ProcessingError::RuleApplicationFailed {
+                        rule_version: self.version(),
+                        details: "zero amount".to_string(),
+                    })
+                }
+                ExecutionPhase::PostProcessing => Err(ProcessingError::TransactionFailed {
+                    transaction_id: transaction.id.clone(),
+                    reason: "apply called after processing".to_string(),
+                }),
+                _ => Ok(TestState {
+                    balance: state.balance + transaction.amount,
+                    transaction_count: state.transaction_count + 1,
+                }),
+            }
+        }
+    }
+    
+    fn transaction(id: &str, amount: i64) -> TestTransaction {
+        TestTransaction {
+            id: id.to_string(),
+            amount,
+            timestamp: Utc.timestamp_opt(1000000, 0).unwrap(),
+        }
+    }
+    
+    #[test]
+    fn test_rule_errors_depend_on_phase() {
+        let context = ExecutionContext::new(Utc.timestamp_opt(1000000, 0).unwrap(), 42);
+        let state = TestState { balance: 500, transaction_count: 0 };
+        let rules = PhaseAwareRuleSet;
+        
+        let pre = context.with_phase(ExecutionPhase::PreProcessing);
+        let main = context.with_phase(ExecutionPhase::MainProcessing);
+        
+        let large = transaction("tx1", -200);
+        assert!(matches!(rules.apply(&state, &large, &pre), Err(ProcessingError::TransactionFailed { .. })));
+        assert!(rules.apply(&state, &large, &main).is_ok());
+        
+        let zero = transaction("tx2", 0);
+        assert!(rules.apply(&state, &zero, &pre).is_ok());
+        assert!(matches!(rules.apply(&state, &zero, &main), Err(ProcessingError::RuleApplicationFailed { .. })));
+    }
+    
+    #[test]
+    fn test_processor_advances_phases() {
+        // Even a context already in post-processing is reset for each hook
+        let context = ExecutionContext::new(Utc.timestamp_opt(1000000, 0).unwrap(), 42)
+            .with_phase(ExecutionPhase::PostProcessing);
+        let mut processor = TransactionProcessor::new(TestState { balance: 500, transaction_count: 0 }).unwrap();
+        
+        processor.process_transaction(&transaction("tx1", -200), &PhaseAwareRuleSet, &context).unwrap();
+        assert_eq!(processor.current_state().balance, 300);
+        
+        let result = processor.process_transaction(&transaction("tx2", 0), &PhaseAwareRuleSet, &context);
+        assert!(matches!(result, Err(ProcessingError::RuleApplicationFailed { .. })));
+    }
+}
+
+#[cfg(test)]
+mod context_factory_tests {
+    use super::*;
+    
+    /// Rejects any transaction whose timestamp differs from the context time
+    struct TimeCheckingRuleSet;
+    
+    impl RuleSet<TestState, TestTransaction> for TimeCheckingRuleSet {
+        fn version(&self) -> Version {
+            Version::new(1, 0, 0)
+        }
+        
+        fn apply(
+            &self,
+            state: &TestState,
+            transaction: &TestTransaction,
+            context: &ExecutionContext,
+        ) -> Result<TestState, ProcessingError> {
+            if context.now() != transaction.timestamp {
+                return Err(ProcessingError::TransactionFailed {
+                    transaction_id: transaction.id.clone(),
+                    reason: format!("observed time {} instead of {}", context.now(), transaction.timestamp),
+                });
+            }
+            Ok(TestState {
+                balance: state.balance + transaction.amount,
+                transaction_count: state.transaction_count + 1,
+            })
+        }
+    }
+    
+    fn transactions() -> Vec<TestTransaction> {
+        (0..5)
+            .map(|i| TestTransaction {
+                id: format!("tx{}", i),
+                amount: 10,
+                timestamp: Utc.timestamp_opt(1000000 + i * 3600, 0).unwrap(),
+            })
+            .collect()
+    }
+    
+    #[test]
+    fn test_factory_context_tracks_transaction_time() {
+        let mut processor = TransactionProcessor::new(TestState { balance: 0, transaction_count: 0 }).unwrap();
+        
+        let transitions = processor
+            .process_transactions_with_context_factory(&transactions(), &TimeCheckingRuleSet, |tx| {
+                ExecutionContext::new(tx.timestamp(), 42)
+            })
+            .unwrap();
+        
+        assert_eq!(transitions.len(), 5);
+        assert_eq!(processor.current_state().balance, 50);
+    }
+    
+    #[test]
+    fn test_shared_context_observes_a_single_time() {
+        let mut processor = TransactionProcessor::new(TestState { balance: 0, transaction_count: 0 }).unwrap();
+        let context = ExecutionContext::new(Utc.timestamp_opt(1000000, 0).unwrap(), 42);
+        
+        // Only the first transaction matches the fixed context time
+        let result = processor.process_transactions(&transactions(), &TimeCheckingRuleSet, &context);
+        assert!(result.is_err());
+        assert_eq!(processor.transactions_processed(), 1);
+    }
+}
+
+#[cfg(test)]
+mod rate_limit_tests {
+    use super::*;
+    use dtre::TokenBucket;
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, Instant};
+    
+    /// Runs transactions through a rate-limited processor on a simulated clock,
+    /// returning the total simulated time and every requested sleep
+    fn run_simulated(count: usize, max_tps: f64) -> (Duration, Vec<Duration>) {
+        let base = Instant::now();
+        let sleeps = Arc::new(Mutex::new(Vec::new()));
+        let clock_sleeps = Arc::clone(&sleeps);
+        let recorded_sleeps = Arc::clone(&sleeps);
+        
+        let bucket = TokenBucket::with_sleep(
+            max_tps,
+            move || base + clock_sleeps.lock().unwrap().iter().sum::<Duration>(),
+            move |wait| recorded_sleeps.lock().unwrap().push(wait),
+        );
+        
+        let mut processor = TransactionProcessor::new(TestState { balance: 0, transaction_count: 0 })
+            .unwrap()
+            .with_token_bucket(bucket);
+        let rule_set = TestRuleSet { version: Version::new(1, 0, 0) };
+        let context = ExecutionContext::new(Utc.timestamp_opt(1000000, 0).unwrap(), 42);
+        
+        for i in 0..count {
+            let tx = TestTransaction {
+                id: format!("tx{}", i),
+                amount: 1,
+                timestamp: Utc.timestamp_opt(1000000 + i as i64, 0).unwrap(),
+            };
+            processor.process_transaction(&tx, &rule_set, &context).unwrap();
+            assert!(processor.current_token_count() < 1.0);
+        }
+        assert_eq!(processor.current_state().balance, count as i64);
+        
+        let sleeps = sleeps.lock().unwrap().clone();
+        (sleeps.iter().sum(), sleeps)
+    }
+    
+    #[test]
+    fn test_thousand_transactions_at_hundred_tps_take_ten_seconds() {
+        let (elapsed, _) = run_simulated(1000, 100.0);
+        let seconds = elapsed.as_secs_f64();
+        assert!((9.9..=10.1).contains(&seconds), "simulated time was {}s", seconds);
+    }
+    
+    #[test]
+    fn test_token_consumption_is_reproducible() {
+        let (first_elapsed, first_sleeps) = run_simulated(200, 50.0);
+        let (second_elapsed, second_sleeps) = run_simulated(200, 50.0);
+        
+        assert_eq!(first_sleeps, second_sleeps);
+        assert_eq!(first_elapsed, second_elapsed);
+    }
+    
+    #[test]
+    fn test_unlimited_processor_reports_infinite_tokens() {
+        let processor = TransactionProcessor::new(TestState { balance: 0, transaction_count: 0 }).unwrap();
+        assert_eq!(processor.current_token_count(), f64::INFINITY);
+        
+        let limited = processor.with_rate_limit(10.0, Instant::now);
+        assert_eq!(limited.current_token_count(), 1.0);
+    }
+}
+
+#[cfg(test)]
+mod watermark_tests {
+    use super::*;
+    use dtre::LogLevel;
+    
+    fn transactions(count: i64) -> Vec<TestTransaction> {
+        (0..count)
+            .map(|i| TestTransaction {
+                id: format!("tx{}", i),
+                amount: 1,
+                timestamp: Utc.timestamp_opt(1000000 + i * 60, 0).unwrap(),
+            })
+            .collect()
+    }
+    
+    fn process(transactions: &[TestTransaction]) -> TransactionProcessor<TestState> {
+        let mut processor = TransactionProcessor::new(TestState { balance: 0, transaction_count: 0 }).unwrap();
+        let rule_set = TestRuleSet { version: Version::new(1, 0, 0) };
+        let context = ExecutionContext::new(Utc.timestamp_opt(1000000, 0).unwrap(), 42);
+        processor.process_transactions(transactions, &rule_set, &context).unwrap();
+        processor
+    }
+    
+    #[test]
+    fn test_ordered_sequence_advances_watermark() {
+        let txs = transactions(5);
+        let processor = process(&txs);
+        let watermark = &processor.execution_trace().watermark;
+        
+        assert!(watermark.is_advancing());
+        assert_eq!(watermark.out_of_order_count, 0);
+        assert_eq!(watermark.high_watermark_timestamp, Some(txs[4].timestamp));
+        assert_eq!(watermark.high_watermark_transaction_id.as_deref(), Some("tx4"));
+        assert!(processor.logger().is_empty());
+    }
+    
+    #[test]
+    fn test_reversed_sequence_counts_out_of_order() {
+        let n = 10;
+        let mut txs = transactions(n);
+        txs.reverse();
+        let processor = process(&txs);
+        let watermark = processor.watermark();
+        
+        assert!(!watermark.is_advancing());
+        assert_eq!(watermark.out_of_order_count, (n - 1) as usize);
+        
+        // The first (latest) transaction holds the watermark throughout
+        assert_eq!(watermark.high_watermark_transaction_id.as_deref(), Some("tx9"));
+        assert_eq!(watermark.high_watermark_timestamp, Some(txs[0].timestamp));
+        
+        let warnings = processor.logger().filter_by_level(LogLevel::Warn);
+        assert_eq!(warnings.len(), (n - 1) as usize);
+        assert_eq!(warnings[0].transaction_id.as_deref(), Some("tx8"));
+        assert_eq!(warnings[0].transaction_index, Some(1));
+    }
+    
+    #[test]
+    fn test_log_level_filters_out_of_order_warnings() {
+        let mut txs = transactions(3);
+        txs.reverse();
+        let mut processor = TransactionProcessor::new(TestState { balance: 100, transaction_count: 0 })
+            .unwrap()
+            .with_log_level(LogLevel::Error);
+        let context = ExecutionContext::new(Utc.timestamp_opt(1000000, 0).unwrap(), 42);
+        processor.process_transactions(&txs, &TestRuleSet { version: Version::new(1, 0, 0) }, &context).unwrap();
+        
+        assert_eq!(processor.watermark().out_of_order_count, 2);
+        assert!(processor.logger().is_empty());
+    }
+    
+    #[test]
+    fn test_equal_timestamps_are_in_order() {
+        let mut txs = transactions(3);
+        for tx in &mut txs {
+            tx.timestamp = Utc.timestamp_opt(1000000, 0).unwrap();
+        }
+        let processor = process(&txs);
+        
+        assert!(processor.watermark().is_advancing());
+        assert_eq!(processor.watermark().high_watermark_transaction_id.as_deref(), Some("tx2"));
+    }
+}
+
+#[cfg(test)]
+mod timestamp_drift_tests {
+    use super::*;
+    use dtre::LogLevel;
+    
+    fn transaction(id: &str, seconds: i64) -> TestTransaction {
+        TestTransaction {
+            id: id.to_string(),
+            amount: 1,
+            timestamp: Utc.timestamp_opt(1000000 + seconds, 0).unwrap(),
+        }
+    }
+    
+    fn processor(tolerance_secs: i64) -> TransactionProcessor<TestState> {
+        TransactionProcessor::new(TestState { balance: 0, transaction_count: 0 })
+            .unwrap()
+            .with_max_timestamp_drift(chrono::Duration::seconds(tolerance_secs))
+    }
+    
+    fn drift_warnings(processor: &TransactionProcessor<TestState>) -> usize {
+        processor.logger().filter_by_level(LogLevel::Warn)
+            .iter()
+            .filter(|entry| entry.message.contains("drift tolerance"))
+            .count()
+    }
+    
+    #[test]
+    fn test_drift_within_tolerance_is_processed() {
+        let mut processor = processor(10);
+        let rule_set = TestRuleSet { version: Version::new(1, 0, 0) };
+        let context = ExecutionContext::new(Utc.timestamp_opt(1000000, 0).unwrap(), 42);
+        let txs = vec![transaction("tx0", 60), transaction("tx1", 55), transaction("tx2", 120)];
+        
+        processor.process_transactions(&txs, &rule_set, &context).unwrap();
+        
+        // The late arrival is still reported against the watermark
+        assert_eq!(processor.transactions_processed(), 3);
+        assert_eq!(processor.logger().filter_by_level(LogLevel::Warn).len(), 1);
+        assert_eq!(drift_warnings(&processor), 0);
+        
+        let stats = processor.statistics();
+        assert_eq!(stats.out_of_order_count, 1);
+        assert_eq!(stats.max_observed_drift, Some(chrono::Duration::seconds(5)));
+    }
+    
+    #[test]
+    fn test_drift_beyond_tolerance_warns() {
+        let mut processor = processor(10);
+        let rule_set = TestRuleSet { version: Version::new(1, 0, 0) };
+        let context = ExecutionContext::new(Utc.timestamp_opt(1000000, 0).unwrap(), 42);
+        let txs = vec![transaction("tx0", 60), transaction("tx1", 45)];
+        
+        processor.process_transactions(&txs, &rule_set, &context).unwrap();
+        
+        assert_eq!(processor.transactions_processed(), 2);
+        assert_eq!(drift_warnings(&processor), 1);
+        assert_eq!(processor.statistics().max_observed_drift, Some(chrono::Duration::seconds(15)));
+    }
+    
+    #[test]
+    fn test_drift_beyond_twice_tolerance_fails() {
+        let mut processor = processor(10);
+        let rule_set = TestRuleSet { version: Version::new(1, 0, 0) };
+        let context = ExecutionContext::new(Utc.timestamp_opt(1000000, 0).unwrap(), 42);
+        processor.process_transaction(&transaction("tx0", 60), &rule_set, &context).unwrap();
+        let hash_before = processor.current_hash();
+        
+        let result = processor.process_transaction(&transaction("tx1", 35), &rule_set, &context);
+        
+        match result {
+            Err(ProcessingError::ExcessiveTimestampDrift { transaction_id, drift_duration, tolerance }) => {
+                assert_eq!(transaction_id, "tx1");
+                assert_eq!(drift_duration, chrono::Duration::seconds(25));
+                assert_eq!(tolerance, chrono::Duration::seconds(10));
+            }
+            other => panic!("Expected ExcessiveTimestampDrift, got {:?}", other),
+        }
+        assert_eq!(processor.current_hash(), hash_before);
+        assert_eq!(processor.statistics().total_failed, 1);
+    }
+    
+    #[test]
+    fn test_drift_counted_without_tolerance() {
+        let mut processor = TransactionProcessor::new(TestState { balance: 0, transaction_count: 0 }).unwrap();
+        let rule_set = TestRuleSet { version: Version::new(1, 0, 0) };
+        let context = ExecutionContext::new(Utc.timestamp_opt(1000000, 0).unwrap(), 42);
+        let txs = vec![transaction("tx0", 3600), transaction("tx1", 0)];
+        
+        processor.process_transactions(&txs, &rule_set, &context).unwrap();
+        
+        assert_eq!(processor.statistics().out_of_order_count, 1);
+        assert_eq!(processor.statistics().max_observed_drift, Some(chrono::Duration::hours(1)));
+    }
+}
+
+#[cfg(test)]
+mod statistics_tests {
+    use super::*;
+    
+    #[test]
+    fn test_statistics_for_thousand_transactions() {
+        let mut processor = TransactionProcessor::new(TestState { balance: 0, transaction_count: 0 }).unwrap();
+        let v1 = TestRuleSet { version: Version::new(1, 0, 0) };
+        let v2 = TestRuleSet { version: Version::new(2, 0, 0) };
+        let context = ExecutionContext::new(Utc.timestamp_opt(1000000, 0).unwrap(), 42);
+        
+        for i in 0..1000i64 {
+            // Every tenth transaction would overdraw the balance and is rejected
+            let amount = if i % 10 == 9 { -1_000_000_000 } else { 10 };
+            let tx = TestTransaction {
+                id: format!("tx{}", i),
+                amount,
+                timestamp: Utc.timestamp_opt(1000000 + i, 0).unwrap(),
+            };
+            let rule_set = if i < 600 { &v1 } else { &v2 };
+            let _ = processor.process_transaction(&tx, rule_set, &context);
+        }
+        
+        let stats = processor.statistics();
+        assert_eq!(stats.total_processed, 1000);
+        assert_eq!(stats.total_failed, 100);
+        assert_eq!(stats.success_rate, (1000 - 100) as f64 / 1000.0);
+        assert_eq!(processor.transactions_processed(), 900);
+        
+        assert!(stats.processing_time_p50_us <= stats.processing_time_p95_us);
+        assert!(stats.processing_time_p95_us <= stats.processing_time_p99_us);
+        assert!(stats.processing_time_p99_us < 1_000_000);
+        
+        let v1_stats = &stats.by_rule_version[&Version::new(1, 0, 0)];
+        let v2_stats = &stats.by_rule_version[&Version::new(2, 0, 0)];
+        assert_eq!(v1_stats.total_processed, 600);
+        assert_eq!(v1_stats.total_failed, 60);
+        assert_eq!(v2_stats.total_processed, 400);
+        assert_eq!(v2_stats.success_rate, 0.9);
+        assert!(v1_stats.processing_time_p50_us <= v1_stats.processing_time_p99_us);
+        
+        let json = stats.to_json();
+        assert_eq!(json["total_failed"], 100);
+        assert_eq!(json["by_rule_version"]["1.0.0"]["total_processed"], 600);
+    }
+    
+    #[test]
+    fn test_statistics_empty_before_processing() {
+        let processor = TransactionProcessor::new(TestState { balance: 0, transaction_count: 0 }).unwrap();
+        let stats = processor.statistics();
+        
+        assert_eq!(stats.total_processed, 0);
+        assert_eq!(stats.processing_time_p99_us, 0);
+        assert!(stats.by_rule_version.is_empty());
+    }
+}
+
+#[cfg(test)]
+mod causality_tests {
+    use super::*;
+    use dtre::CausalityRecord;
+    
+    #[derive(Debug, Clone)]
+    struct Account {
+        overdraft: i64,
+    }
+    
+    // Rule set that reads facts, resolves an entity and draws randomness
+    struct FactDrivenRuleSet;
+    
+    impl RuleSet<TestState, TestTransaction> for FactDrivenRuleSet {
+        fn version(&self) -> Version {
+            Version::new(1, 0, 0)
+        }
+        
+        fn apply(
+            &self,
+            state: &TestState,
+            transaction: &TestTransaction,
+            context: &ExecutionContext,
+        ) -> Result<TestState, ProcessingError> {
+            let rate = *context.get_external_fact::<i64>("rate").unwrap_or(&1);
+            let limit = *context.get_external_fact::<i64>("limit").unwrap_or(&i64::MAX);
+            // Reading a fact twice is only recorded once
+            let _ = context.get_external_fact::<i64>("rate");
+            
+            let mut bonus = 0;
+            if transaction.amount > 100 {
+                let account: &Account = context.resolve_entity("acct-1")?;
+                let mut scratch = context.clone();
+                bonus = account.overdraft + scratch.random().gen_range(0..1);
+            }
+            
+            Ok(TestState {
+                balance: (state.balance + transaction.amount * rate + bonus).min(limit),
+                transaction_count: state.transaction_count + 1,
+            })
+        }
+    }
+    
+    fn context() -> ExecutionContext {
+        ExecutionContext::builder()
+            .with_time(Utc.timestamp_opt(1000000, 0).unwrap())
+            .with_random_seed(42)
+            .with_external_fact("rate".to_string(), 2i64)
+            .with_external_fact("limit".to_string(), 10_000i64)
+            .with_external_entity("acct-1".to_string(), Account { overdraft: 5 })
+            .build()
+    }
+    
+    fn tx(id: &str, amount: i64) -> TestTransaction {
+        TestTransaction {
+            id: id.to_string(),
+            amount,
+            timestamp: Utc.timestamp_opt(1000000, 0).unwrap(),
+        }
+    }
+    
+    #[test]
+    fn test_fact_accesses_are_recorded_in_order() {
+        let mut processor = TransactionProcessor::new(TestState { balance: 0, transaction_count: 0 }).unwrap();
+        let transition = processor.process_transaction(&tx("tx1", 10), &FactDrivenRuleSet, &context()).unwrap();
+        
+        assert_eq!(transition.causality.accessed_facts, vec!["rate", "limit"]);
+        assert!(transition.causality.accessed_entities.is_empty());
+        assert_eq!(transition.causality.random_draws, 0);
+        
+        let recorded = processor.execution_trace().causality_for("tx1").unwrap();
+        assert_eq!(recorded, &transition.causality);
+    }
+    
+    #[test]
+    fn test_entities_and_random_draws_are_recorded_per_transaction() {
+        let mut processor = TransactionProcessor::new(TestState { balance: 0, transaction_count: 0 }).unwrap();
+        let context = context();
+        processor.process_transaction(&tx("small", 10), &FactDrivenRuleSet, &context).unwrap();
+        processor.process_transaction(&tx("large", 500), &FactDrivenRuleSet, &context).unwrap();
+        
+        let trace = processor.execution_trace();
+        assert_eq!(
+            trace.causality_for("large"),
+            Some(&CausalityRecord {
+                accessed_facts: vec!["rate".to_string(), "limit".to_string()],
+                accessed_entities: vec!["acct-1".to_string()],
+                random_draws: 1,
+            })
+        );
+        
+        // Each transaction starts with an empty record
+        assert!(trace.causality_for("small").unwrap().accessed_entities.is_empty());
+        assert_eq!(trace.causality_for("missing"), None);
+        
+        // The caller's context is never recording
+        assert_eq!(context.causality(), None);
+    }
+    
+    #[test]
+    fn test_rule_without_inputs_has_empty_causality() {
+        let mut processor = TransactionProcessor::new(TestState { balance: 0, transaction_count: 0 }).unwrap();
+        let rule_set = TestRuleSet { version: Version::new(1, 0, 0) };
+        let transition = processor.process_transaction(&tx("tx1", 10), &rule_set, &context()).unwrap();
+        
+        assert!(transition.causality.is_empty());
+    }
+}
+
+#[cfg(test)]
+mod snapshot_tests {
+    use super::*;
+    use dtre::ProcessorSnapshot;
+    
+    fn transactions(count: i64) -> Vec<TestTransaction> {
+        (0..count)
+            .map(|i| TestTransaction {
+                id: format!("tx{}", i),
+                amount: 10,
+                timestamp: Utc.timestamp_opt(1000000 + i, 0).unwrap(),
+            })
+            .collect()
+    }
+    
+    fn assert_send_sync<T: Send + Sync>() {}
+    
+    #[test]
+    fn test_snapshot_mid_replay() {
+        let mut processor = TransactionProcessor::new(TestState { balance: 100, transaction_count: 0 }).unwrap();
+        let rule_set = TestRuleSet { version: Version::new(1, 0, 0) };
+        let context = ExecutionContext::new(Utc.timestamp_opt(1000000, 0).unwrap(), 42);
+        let txs = transactions(10);
+        
+        processor.process_transactions(&txs[..4], &rule_set, &context).unwrap();
+        let snapshot = processor.snapshot();
+        processor.process_transactions(&txs[4..], &rule_set, &context).unwrap();
+        
+        // The snapshot still reflects the first four transactions
+        assert_eq!(snapshot.current_state.balance, 140);
+        assert_eq!(snapshot.transactions_processed, 4);
+        assert_eq!(snapshot.current_hash, dtre::StateHasher::new().hash(snapshot.current_state.as_ref()));
+        assert_eq!(processor.current_state().balance, 200);
+        
+        // Readers on other threads share the same state
+        assert_send_sync::<ProcessorSnapshot<TestState>>();
+        let reader = snapshot.clone();
+        let balance = std::thread::spawn(move || reader.current_state.balance).join().unwrap();
+        assert_eq!(balance, 140);
+        assert_eq!(std::sync::Arc::strong_count(&snapshot.current_state), 1);
+    }
+    
+    #[test]
+    fn test_snapshot_to_checkpoint_resumes_processing() {
+        let mut processor = TransactionProcessor::new(TestState { balance: 0, transaction_count: 0 }).unwrap();
+        let rule_set = TestRuleSet { version: Version::new(1, 0, 0) };
+        let context = ExecutionContext::new(Utc.timestamp_opt(1000000, 0).unwrap(), 42);
+        let txs = transactions(6);
+        
+        processor.process_transactions(&txs[..3], &rule_set, &context).unwrap();
+        let checkpoint = processor.snapshot().to_checkpoint(txs[2].timestamp);
+        assert!(checkpoint.verify_integrity().is_ok());
+        assert_eq!(checkpoint.transaction_index, 3);
+        
+        let mut resumed = TransactionProcessor::from_checkpoint(&checkpoint).unwrap();
+        resumed.process_transactions(&txs[3..], &rule_set, &context).unwrap();
+        processor.process_transactions(&txs[3..], &rule_set, &context).unwrap();
+        assert_eq!(resumed.current_hash(), processor.current_hash());
+    }
+}
+
+#[cfg(test)]
+mod rule_error_context_tests {
+    use super::*;
+    use dtre::{RuleAuditRecorder, RuleError, RuleErrorContext};
+    
+    /// Rejects transactions above a limit through a guard clause
+    struct LimitRuleSet;
+    
+    impl RuleSet<TestState, TestTransaction> for LimitRuleSet {
+        fn version(&self) -> Version {
+            Version::new(2, 1, 0)
+        }
+        
+        fn apply(
+            &self,
+            state: &TestState,
+            transaction: &TestTransaction,
+            _context: &ExecutionContext,
+        ) -> Result<TestState, ProcessingError> {
+            RuleAuditRecorder::guard("limits.max_amount", transaction.amount <= 1_000, "amount above 1,000")?;
+            Ok(TestState {
+                balance: state.balance + transaction.amount,
+                transaction_count: state.transaction_count + 1,
+            })
+        }
+    }
+    
+    fn transaction(id: &str, amount: i64) -> TestTransaction {
+        TestTransaction {
+            id: id.to_string(),
+            amount,
+            timestamp: Utc.timestamp_opt(1000000, 0).unwrap(),
+        }
+    }
+    
+    #[test]
+    fn test_processor_attaches_context_to_guard_errors() {
+        let mut processor = TransactionProcessor::new(TestState { balance: 0, transaction_count: 0 }).unwrap();
+        let context = ExecutionContext::new(Utc.timestamp_opt(1000000, 0).unwrap(), 42);
+        processor.process_transaction(&transaction("tx1", 500), &LimitRuleSet, &context).unwrap();
+        let hash_before = processor.current_hash();
+        
+        let error = processor.process_transaction(&transaction("tx2", 5_000), &LimitRuleSet, &context).unwrap_err();
+        let rule_error = match error {
+            ProcessingError::Rule(rule_error) => rule_error,
+            other => panic!("Expected a rule error, got {:?}", other),
+        };
+        let rule_context = rule_error.context().expect("processor should attach context");
+        assert_eq!(rule_context.transaction_id.as_deref(), Some("tx2"));
+        assert_eq!(rule_context.rule_clause.as_deref(), Some("limits.max_amount"));
+        assert_eq!(rule_context.rule_version, Some(Version::new(2, 1, 0)));
+        assert_eq!(rule_context.state_snapshot_hash, Some(hash_before));
+        assert!(rule_error.to_string().contains("amount above 1,000"));
+    }
+    
+    #[test]
+    fn test_existing_context_is_kept() {
+        struct PreContextualized;
+        
+        impl RuleSet<TestState, TestTransaction> for PreContextualized {
+            fn version(&self) -> Version {
+                Version::new(1, 0, 0)
+            }
+            
+            fn apply(&self, _: &TestState, _: &TestTransaction, _: &ExecutionContext) -> Result<TestState, ProcessingError> {
+                let context = RuleErrorContext::new().with_rule_clause("custom".to_string());
+                Err(RuleError::RegistrationFailed { reason: "nope".to_string() }.with_context(context).into())
+            }
+        }
+        
+        let mut processor = TransactionProcessor::new(TestState { balance: 0, transaction_count: 0 }).unwrap();
+        let context = ExecutionContext::new(Utc.timestamp_opt(1000000, 0).unwrap(), 42);
+        match processor.process_transaction(&transaction("tx1", 1), &PreContextualized, &context) {
+            Err(ProcessingError::Rule(rule_error)) => {
+                let rule_context = rule_error.context().unwrap();
+                assert_eq!(rule_context.rule_clause.as_deref(), Some("custom"));
+                assert_eq!(rule_context.transaction_id, None);
+            }
+            other => panic!("Expected a rule error, got {:?}", other),
+        }
+    }
+}
+
+#[cfg(feature = "debug-audit")]
+mod audit_mode_tests {
+    use super::*;
+    use dtre::{AuditLog, AuditOperation};
+    
+    fn audited_replay(amounts: &[i64]) -> AuditLog {
+        let mut processor = TransactionProcessor::new(TestState { balance: 100, transaction_count: 0 }).unwrap();
+        processor.enable_audit_mode();
+        let rule_set = TestRuleSet { version: Version::new(1, 0, 0) };
+        let context = ExecutionContext::new(Utc.timestamp_opt(1000000, 0).unwrap(), 42);
+        let transactions: Vec<TestTransaction> = amounts
+            .iter()
+            .enumerate()
+            .map(|(i, &amount)| TestTransaction {
+                id: format!("tx{}", i),
+                amount,
+                timestamp: Utc.timestamp_opt(1000000 + i as i64, 0).unwrap(),
+            })
+            .collect();
+        processor.process_transactions_with_checkpoints(&transactions, &rule_set, &context, 2).unwrap();
+        AuditLog::from(processor.audit_log())
+    }
+    
+    #[test]
+    fn test_identical_replays_produce_identical_audit_logs() {
+        let first = audited_replay(&[10, 20, 30]);
+        let second = audited_replay(&[10, 20, 30]);
+        assert!(!first.entries().is_empty());
+        assert_eq!(first.find_first_divergence(&second), None);
+        
+        let operations: Vec<&AuditOperation> = first.entries().iter().map(|e| &e.operation).collect();
+        assert_eq!(operations[0], &AuditOperation::EvaluateTransactionValidate { ok: true });
+        assert!(operations.contains(&&AuditOperation::CheckpointCreated { index: 2 }));
+        assert!(first.entries().iter().enumerate().all(|(i, e)| e.sequence == i as u64));
+        
+        let different = audited_replay(&[10, 25, 30]);
+        assert!(first.find_first_divergence(&different).is_some());
+    }
+    
+    #[test]
+    fn test_audit_log_is_empty_unless_enabled() {
+        let mut processor = TransactionProcessor::new(TestState { balance: 100, transaction_count: 0 }).unwrap();
+        let rule_set = TestRuleSet { version: Version::new(1, 0, 0) };
+        let context = ExecutionContext::new(Utc.timestamp_opt(1000000, 0).unwrap(), 42);
+        let transaction = TestTransaction { id: "tx1".to_string(), amount: 5, timestamp: Utc.timestamp_opt(1000000, 0).unwrap() };
+        processor.process_transaction(&transaction, &rule_set, &context).unwrap();
+        assert!(processor.audit_log().is_empty());
+    }
+}
+
+#[cfg(test)]
+mod clock_tick_tests {
+    use super::*;
+    use chrono::Duration;
+    
+    /// Savings account accruing interest up to the context's current time
+    #[derive(Debug, Clone, Hash, Serialize, Deserialize, PartialEq)]
+    struct Savings {
+        balance_cents: i64,
+        accrued_through: DateTime<Utc>,
+    }
+    
+    impl State for Savings {
+        fn validate(&self) -> Result<(), ValidationError> {
+            Ok(())
+        }
+    }
+    
+    /// Daily interest at 10 basis points, rounded down to the cent
+    struct DailyInterestRules;
+    
+    impl RuleSet<Savings, TestTransaction> for DailyInterestRules {
+        fn version(&self) -> Version {
+            Version::new(1, 0, 0)
+        }
+        
+        fn apply(&self, state: &Savings, _: &TestTransaction, context: &ExecutionContext) -> Result<Savings, ProcessingError> {
+            let mut balance_cents = state.balance_cents;
+            for _ in 0..(context.now() - state.accrued_through).num_days() {
+                balance_cents += balance_cents * 10 / 10_000;
+            }
+            Ok(Savings { balance_cents, accrued_through: context.now() })
+        }
+    }
+    
+    #[test]
+    fn test_daily_interest_over_thirty_simulated_days() {
+        let opened = Utc.timestamp_opt(1000000, 0).unwrap();
+        let initial = Savings { balance_cents: 1_000_000, accrued_through: opened };
+        let mut processor = TransactionProcessor::new(initial).unwrap();
+        
+        // The first accrual runs a day after the account was opened
+        let context = ExecutionContext::builder()
+            .with_time(opened + Duration::days(1))
+            .with_tick_per_transaction(Duration::days(1))
+            .build();
+        for day in 1..=30 {
+            let accrual = TestTransaction {
+                id: format!("accrual-{}", day),
+                amount: 0,
+                timestamp: opened + Duration::days(day),
+            };
+            processor.process_transaction(&accrual, &DailyInterestRules, &context).unwrap();
+        }
+        
+        let mut expected = 1_000_000i64;
+        for _ in 0..30 {
+            expected += expected * 10 / 10_000;
+        }
+        let state = processor.current_state();
+        assert_eq!(state.balance_cents, expected);
+        assert_eq!(state.accrued_through, opened + Duration::days(30));
+        assert_eq!(context.now(), opened + Duration::days(31));
+    }
+}
+
+#[cfg(test)]
+mod state_cache_tests {
+    use super::*;
+    use dtre::{RuleApplicationCache, StateHasher};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+    
+    /// Pays a transaction's amount into an account and counts its applications
+    struct CountingSalaryRules {
+        applied: AtomicUsize,
+    }
+    
+    impl RuleSet<TestState, TestTransaction> for CountingSalaryRules {
+        fn version(&self) -> Version {
+            Version::new(1, 0, 0)
+        }
+        
+        fn apply(&self, state: &TestState, transaction: &TestTransaction, _: &ExecutionContext) -> Result<TestState, ProcessingError> {
+            self.applied.fetch_add(1, Ordering::SeqCst);
+            Ok(TestState {
+                balance: state.balance + transaction.amount,
+                transaction_count: state.transaction_count + 1,
+            })
+        }
+    }
+    
+    fn salary() -> TestTransaction {
+        TestTransaction {
+            id: "salary-2024-01".to_string(),
+            amount: 3_500,
+            timestamp: Utc.timestamp_opt(1000000, 0).unwrap(),
+        }
+    }
+    
+    #[test]
+    fn test_identical_salary_payments_hit_cache() {
+        let cache = Arc::new(Mutex::new(RuleApplicationCache::new(16)));
+        let rules = CountingSalaryRules { applied: AtomicUsize::new(0) };
+        let context = ExecutionContext::new(Utc.timestamp_opt(1000000, 0).unwrap(), 42);
+        
+        // One processor per employee account, all opened with the same state
+        let mut final_hashes = Vec::new();
+        for _ in 0..100 {
+            let initial = TestState { balance: 0, transaction_count: 0 };
+            let mut processor = TransactionProcessor::new(initial).unwrap().with_state_cache(cache.clone());
+            processor.process_transaction(&salary(), &rules, &context).unwrap();
+            assert_eq!(processor.current_state().balance, 3_500);
+            assert_eq!(processor.transactions_processed(), 1);
+            final_hashes.push(processor.current_hash());
+        }
+        
+        let cache = cache.lock().unwrap();
+        assert_eq!(cache.hits(), 99);
+        assert_eq!(cache.misses(), 1);
+        assert!((cache.hit_rate() - 0.99).abs() < 1e-9);
+        assert_eq!(rules.applied.load(Ordering::SeqCst), 1);
+        assert!(final_hashes.iter().all(|hash| *hash == final_hashes[0]));
+    }
+    
+    #[test]
+    fn test_cache_entries_are_keyed_by_context() {
+        let cache = Arc::new(Mutex::new(RuleApplicationCache::new(16)));
+        let rules = CountingSalaryRules { applied: AtomicUsize::new(0) };
+        let january = ExecutionContext::new(Utc.timestamp_opt(1000000, 0).unwrap(), 42);
+        let reseeded = ExecutionContext::new(Utc.timestamp_opt(1000000, 0).unwrap(), 7);
+        
+        for context in [&january, &reseeded, &january] {
+            let initial = TestState { balance: 0, transaction_count: 0 };
+            let mut processor = TransactionProcessor::new(initial).unwrap().with_state_cache(cache.clone());
+            processor.process_transaction(&salary(), &rules, context).unwrap();
+        }
+        
+        let cache = cache.lock().unwrap();
+        assert_eq!((cache.hits(), cache.misses()), (1, 2));
+        assert_eq!(rules.applied.load(Ordering::SeqCst), 2);
+    }
+    
+    #[test]
+    fn test_results_under_unserializable_facts_are_not_cached() {
+        let cache = Arc::new(Mutex::new(RuleApplicationCache::new(16)));
+        let rules = CountingSalaryRules { applied: AtomicUsize::new(0) };
+        let context_with_rate = |rate: i64| {
+            ExecutionContext::builder()
+                .with_time(Utc.timestamp_opt(1000000, 0).unwrap())
+                .with_random_seed(42)
+                .with_external_fact("bonus_rate".to_string(), rate)
+                .build()
+        };
+        let low = context_with_rate(1);
+        let high = context_with_rate(2);
+        assert!(RuleApplicationCache::<TestState>::transaction_hash(&salary(), &rules, &low).is_none());
+        
+        for context in [&low, &high, &low] {
+            let initial = TestState { balance: 0, transaction_count: 0 };
+            let mut processor = TransactionProcessor::new(initial).unwrap().with_state_cache(cache.clone());
+            processor.process_transaction(&salary(), &rules, context).unwrap();
+        }
+        
+        let cache = cache.lock().unwrap();
+        assert!(cache.is_empty());
+        assert_eq!(cache.hits(), 0);
+        assert_eq!(rules.applied.load(Ordering::SeqCst), 3);
+    }
+    
+    #[test]
+    fn test_colliding_cache_entry_fails_verification() {
+        let rules = CountingSalaryRules { applied: AtomicUsize::new(0) };
+        let context = ExecutionContext::new(Utc.timestamp_opt(1000000, 0).unwrap(), 42);
+        let initial = TestState { balance: 0, transaction_count: 0 };
+        let hasher = StateHasher::new();
+        
+        // Plant a wrong result under the key of the real transaction
+        let wrong = TestState { balance: 1, transaction_count: 1 };
+        let mut cache = RuleApplicationCache::new(16);
+        let transaction_hash = RuleApplicationCache::<TestState>::transaction_hash(&salary(), &rules, &context).unwrap();
+        cache.insert(hasher.hash(&initial), transaction_hash, wrong.clone(), hasher.hash(&wrong));
+        
+        let mut processor = TransactionProcessor::new(initial).unwrap().with_state_cache(Arc::new(Mutex::new(cache)));
+        let transition = processor.process_transaction(&salary(), &rules, &context).unwrap();
+        assert_eq!(transition.to_state, wrong);
+        assert_eq!(rules.applied.load(Ordering::SeqCst), 0);
+        assert!(!hasher.verify_transition(&transition, &salary(), &rules, &context).unwrap());
+    }
+}
+
+#[cfg(test)]
+mod fork_tests {
+    use super::*;
+    use dtre::StateError;
+    
+    fn transaction(id: &str, amount: i64) -> TestTransaction {
+        TestTransaction {
+            id: id.to_string(),
+            amount,
+            timestamp: Utc.timestamp_opt(1000000, 0).unwrap(),
+        }
+    }
+    
+    fn processor() -> TransactionProcessor<TestState> {
+        TransactionProcessor::new(TestState { balance: 100, transaction_count: 0 }).unwrap()
+    }
+    
+    #[test]
+    fn test_discarded_fork_leaves_parent_unchanged() {
+        let mut parent = processor();
+        let rule_set = TestRuleSet { version: Version::new(1, 0, 0) };
+        let context = ExecutionContext::new(Utc.timestamp_opt(1000000, 0).unwrap(), 42);
+        parent.process_transaction(&transaction("tx0", 10), &rule_set, &context).unwrap();
+        let state_before = parent.current_state().clone();
+        let hash_before = parent.current_hash();
+        
+        let mut fork = parent.fork();
+        fork.process_transaction(&transaction("tx1", 5), &rule_set, &context).unwrap();
+        assert!(fork.process_transaction(&transaction("tx2", -1000), &rule_set, &context).is_err());
+        assert_eq!(fork.current_state().balance, 115);
+        assert_eq!(fork.diff().from_hash, hash_before);
+        fork.discard();
+        
+        assert_eq!(parent.current_state(), &state_before);
+        assert_eq!(parent.current_hash(), hash_before);
+        assert_eq!(parent.transactions_processed(), 1);
+        assert!(parent.execution_trace().mutations.is_empty());
+    }
+    
+    #[test]
+    fn test_merge_into_unchanged_parent_takes_fork_state() {
+        let mut parent = processor();
+        let rule_set = TestRuleSet { version: Version::new(1, 0, 0) };
+        let context = ExecutionContext::new(Utc.timestamp_opt(1000000, 0).unwrap(), 42);
+        
+        // The fork reads the parent's state until it commits one of its own
+        let mut fork = parent.fork();
+        assert!(std::ptr::eq(fork.current_state(), parent.current_state()));
+        fork.process_transaction(&transaction("tx1", 5), &rule_set, &context).unwrap();
+        assert!(!std::ptr::eq(fork.current_state(), parent.current_state()));
+        let fork_hash = fork.current_hash();
+        fork.merge_into(&mut parent).unwrap();
+        
+        assert_eq!(parent.current_hash(), fork_hash);
+        assert_eq!(parent.execution_trace().mutations.len(), 1);
+    }
+    
+    #[test]
+    fn test_merge_into_diverged_parent_reports_conflict() {
+        let mut parent = processor();
+        let rule_set = TestRuleSet { version: Version::new(1, 0, 0) };
+        let context = ExecutionContext::new(Utc.timestamp_opt(1000000, 0).unwrap(), 42);
+        
+        let mut fork = parent.fork();
+        fork.process_transaction(&transaction("tx1", 5), &rule_set, &context).unwrap();
+        parent.process_transaction(&transaction("tx2", 7), &rule_set, &context).unwrap();
+        let hash_before = parent.current_hash();
+        
+        assert!(matches!(fork.merge_into(&mut parent), Err(StateError::MergeConflict { .. })));
+        assert_eq!(parent.current_hash(), hash_before);
+    }
+    
+    #[test]
+    fn test_fork_keeps_remaining_transaction_limit() {
+        let mut parent = processor().with_max_transaction_count(2);
+        let rule_set = TestRuleSet { version: Version::new(1, 0, 0) };
+        let context = ExecutionContext::new(Utc.timestamp_opt(1000000, 0).unwrap(), 42);
+        parent.process_transaction(&transaction("tx0", 10), &rule_set, &context).unwrap();
+        parent.apply_mutation("Fee waiver", |state| Ok(TestState { balance: state.balance + 1, ..state })).unwrap();
+        
+        // The mutation does not use up the limit, so the fork has one transaction left
+        let mut fork = parent.fork();
+        fork.process_transaction(&transaction("tx1", 5), &rule_set, &context).unwrap();
+        assert!(matches!(
+            fork.process_transaction(&transaction("tx2", 5), &rule_set, &context),
+            Err(ProcessingError::TransactionLimitExceeded { limit: 1, .. })
+        ));
+    }
+}
+
+#[cfg(test)]
+mod trace_log_replay_tests {
+    use super::*;
+    use dtre::{ExecutionTraceLog, LogEntry, LogLevel, StateHasher};
+    
+    fn recorded_run(path: &std::path::Path) -> TransactionProcessor<TestState> {
+        let time = Utc.timestamp_opt(1000000, 0).unwrap();
+        let rule_set = TestRuleSet { version: Version::new(1, 0, 0) };
+        let context = ExecutionContext::new(time, 42);
+        let mut processor = TransactionProcessor::new(TestState { balance: 100, transaction_count: 0 }).unwrap();
+        
+        let mut log = ExecutionTraceLog::new(time);
+        log.add_log(
+            LogEntry::new(LogLevel::Info, time, "replay started".to_string())
+                .with_metadata("random_seed".to_string(), "42".to_string()),
+        );
+        for index in 0..5 {
+            let transaction = TestTransaction {
+                id: format!("tx{}", index),
+                amount: index as i64 * 10 - 15,
+                timestamp: Utc.timestamp_opt(1000000 + index as i64, 0).unwrap(),
+            };
+            let transition = processor.process_transaction(&transaction, &rule_set, &context).unwrap();
+            log.record_transaction(index, &transaction, transition.from_hash, transition.to_hash).unwrap();
+        }
+        log.complete(time);
+        log.save_ndjson(path).unwrap();
+        processor
+    }
+    
+    #[test]
+    fn test_replay_from_trace_log_reproduces_final_hash() {
+        let path = std::env::temp_dir().join(format!("dtre-trace-log-replay-{}.ndjson", std::process::id()));
+        let original = recorded_run(&path);
+        let rule_set = TestRuleSet { version: Version::new(1, 0, 0) };
+        
+        let replayed = TransactionProcessor::replay_from_trace_log::<TestTransaction, _>(
+            &path,
+            TestState { balance: 100, transaction_count: 0 },
+            &rule_set,
+        ).unwrap();
+        
+        assert_eq!(replayed.current_hash(), original.current_hash());
+        assert_eq!(replayed.current_state(), original.current_state());
+        assert_eq!(replayed.transactions_processed(), 5);
+        
+        // A different initial state no longer reaches the logged hash
+        let diverged = TransactionProcessor::replay_from_trace_log::<TestTransaction, _>(
+            &path,
+            TestState { balance: 200, transaction_count: 0 },
+            &rule_set,
+        );
+        assert!(matches!(diverged, Err(ProcessingError::TraceLogHashMismatch { .. })));
+        
+        let trusted = TransactionProcessor::from_trace_log_trusted(
+            &path,
+            original.current_state().clone(),
+            original.current_hash(),
+        ).unwrap();
+        assert_eq!(trusted.current_hash(), original.current_hash());
+        assert_eq!(trusted.execution_trace().transactions_processed, 5);
+        
+        // The state itself must hash to the logged hash
+        let wrong_state = TransactionProcessor::from_trace_log_trusted(
+            &path,
+            TestState { balance: 0, transaction_count: 5 },
+            original.current_hash(),
+        );
+        assert!(matches!(
+            wrong_state,
+            Err(ProcessingError::TraceLogHashMismatch { logged_hash, .. }) if logged_hash == original.current_hash()
+        ));
+        
+        std::fs::remove_file(&path).unwrap();
+    }
+    
+    #[test]
+    fn test_trace_log_without_final_hash_is_rejected() {
+        let path = std::env::temp_dir().join(format!("dtre-trace-log-empty-{}.ndjson", std::process::id()));
+        let time = Utc.timestamp_opt(1000000, 0).unwrap();
+        let mut log = ExecutionTraceLog::new(time);
+        log.add_log(
+            LogEntry::new(LogLevel::Info, time, "replay started".to_string())
+                .with_metadata("random_seed".to_string(), "42".to_string()),
+        );
+        log.complete(time);
+        log.save_ndjson(&path).unwrap();
+        
+        let initial = TestState { balance: 100, transaction_count: 0 };
+        let replayed = TransactionProcessor::replay_from_trace_log::<TestTransaction, _>(
+            &path,
+            initial.clone(),
+            &TestRuleSet { version: Version::new(1, 0, 0) },
+        );
+        assert!(matches!(replayed, Err(ProcessingError::InvalidTraceLog { .. })));
+        let hash = StateHasher::new().hash(&initial);
+        let trusted = TransactionProcessor::from_trace_log_trusted(&path, initial, hash);
+        assert!(matches!(trusted, Err(ProcessingError::InvalidTraceLog { .. })));
+        
+        std::fs::remove_file(&path).unwrap();
+    }
+}
+
+#[cfg(test)]
+mod overhead_breakdown_tests {
+    use super::*;
+    use dtre::OverheadBreakdown;
+    
+    #[test]
+    fn test_trivial_rule_is_cheaper_than_hashing() {
+        let rule_set = TestRuleSet { version: Version::new(1, 0, 0) };
+        let context = ExecutionContext::new(Utc.timestamp_opt(1000000, 0).unwrap(), 42);
+        let mut processor = TransactionProcessor::new(TestState { balance: 0, transaction_count: 0 }).unwrap();
+        
+        for index in 0..5000 {
+            let transaction = TestTransaction {
+                id: format!("tx{}", index),
+                amount: 1,
+                timestamp: Utc.timestamp_opt(1000000 + index, 0).unwrap(),
+            };
+            processor.process_transaction(&transaction, &rule_set, &context).unwrap();
+        }
+        
+        // Compare unrounded times, since a trivial rule may take well under a millisecond in total
+        let timings = processor.phase_timings();
+        assert!(timings.rule_application < timings.state_hashing, "{:?}", timings);
+        assert_eq!(timings.checkpoint_creation, std::time::Duration::ZERO);
+        
+        let breakdown = processor.overhead_breakdown();
+        assert_eq!(breakdown, timings.breakdown());
+        
+        let table = breakdown.to_percentage_table();
+        assert_eq!(table.lines().count(), 7);
+        assert!(table.lines().any(|line| line.starts_with("state_hashing")));
+    }
+    
+    #[test]
+    fn test_serialization_times_the_state_size_check() {
+        let rule_set = TestRuleSet { version: Version::new(1, 0, 0) };
+        let context = ExecutionContext::new(Utc.timestamp_opt(1000000, 0).unwrap(), 42);
+        let transaction = TestTransaction {
+            id: "tx1".to_string(),
+            amount: 1,
+            timestamp: Utc.timestamp_opt(1000000, 0).unwrap(),
+        };
+        
+        let mut processor = TransactionProcessor::new(TestState { balance: 0, transaction_count: 0 }).unwrap();
+        processor.process_transaction(&transaction, &rule_set, &context).unwrap();
+        assert_eq!(processor.phase_timings().serialization, std::time::Duration::ZERO);
+        
+        let mut processor = TransactionProcessor::new(TestState { balance: 0, transaction_count: 0 })
+            .unwrap()
+            .with_max_state_size_bytes(1024);
+        processor.process_transaction(&transaction, &rule_set, &context).unwrap();
+        assert!(processor.phase_timings().serialization > std::time::Duration::ZERO);
+    }
+    
+    #[test]
+    fn test_dominant_phase() {
+        let breakdown = OverheadBreakdown {
+            rule_application_ms: 5,
+            serialization_ms: 12,
+            ..Default::default()
+        };
+        assert_eq!(breakdown.dominant_phase(), "serialization");
+        assert_eq!(OverheadBreakdown::default().dominant_phase(), "rule_application");
+        assert!(breakdown.to_percentage_table().contains("70.6%"));
+    }
+}
+
+#[cfg(test)]
+mod metadata_tests {
+    use super::*;
+    use std::collections::HashMap;
+    
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    struct TaggedTransaction {
+        id: String,
+        amount: i64,
+        timestamp: DateTime<Utc>,
+        metadata: HashMap<String, String>,
+    }
+    
+    impl Transaction for TaggedTransaction {
+        fn id(&self) -> &str {
+            &self.id
+        }
+        
+        fn timestamp(&self) -> DateTime<Utc> {
+            self.timestamp
+        }
+        
+        fn validate(&self) -> Result<(), ValidationError> {
+            Ok(())
+        }
+        
+        fn metadata(&self) -> Option<&HashMap<String, String>> {
+            Some(&self.metadata)
+        }
+    }
+    
+    struct TaggedRuleSet;
+    
+    impl RuleSet<TestState, TaggedTransaction> for TaggedRuleSet {
+        fn version(&self) -> Version {
+            Version::new(1, 0, 0)
+        }
+        
+        fn apply(
+            &self,
+            state: &TestState,
+            transaction: &TaggedTransaction,
+            _context: &ExecutionContext,
+        ) -> Result<TestState, ProcessingError> {
+            Ok(TestState {
+                balance: state.balance + transaction.amount,
+                transaction_count: state.transaction_count + 1,
+            })
+        }
+    }
+    
+    fn transaction(id: &str, batch: &str) -> TaggedTransaction {
+        TaggedTransaction {
+            id: id.to_string(),
+            amount: 25,
+            timestamp: Utc.timestamp_opt(1000000, 0).unwrap(),
+            metadata: HashMap::from([
+                ("batch_id".to_string(), batch.to_string()),
+                ("source".to_string(), "ledger".to_string()),
+            ]),
+        }
+    }
+    
+    #[test]
+    fn test_metadata_does_not_affect_state_hash() {
+        let context = ExecutionContext::new(Utc.timestamp_opt(1000000, 0).unwrap(), 42);
+        let mut first = TransactionProcessor::new(TestState { balance: 0, transaction_count: 0 }).unwrap();
+        let mut second = TransactionProcessor::new(TestState { balance: 0, transaction_count: 0 }).unwrap();
+        
+        let a = first.process_transaction(&transaction("tx1", "batch-a"), &TaggedRuleSet, &context).unwrap();
+        let b = second.process_transaction(&transaction("tx1", "batch-b"), &TaggedRuleSet, &context).unwrap();
+        assert_eq!(a.to_hash, b.to_hash);
+        
+        let trace = first.execution_trace();
+        assert_eq!(trace.transactions_with_metadata("batch_id", "batch-a").len(), 1);
+        assert!(trace.transactions_with_metadata("batch_id", "batch-b").is_empty());
+        assert_eq!(
+            trace.rule_applications[0].application_metadata.as_ref().unwrap()["source"],
+            "ledger"
+        );
+    }
+}
+
+#[cfg(test)]
+mod validation_registry_tests {
+    use super::*;
+    use dtre::{AnyTransaction, ValidationRegistry};
+    
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    struct TransferTransaction {
+        id: String,
+        from_account: String,
+        to_account: String,
+        amount: i64,
+        timestamp: DateTime<Utc>,
+    }
+    
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    struct HeartbeatTransaction {
+        id: String,
+        timestamp: DateTime<Utc>,
+    }
+    
+    macro_rules! impl_transaction {
+        ($ty:ty) => {
+            impl Transaction for $ty {
+                fn id(&self) -> &str {
+                    &self.id
+                }
+                
+                fn timestamp(&self) -> DateTime<Utc> {
+                    self.timestamp
+                }
+                
+                fn validate(&self) -> Result<(), ValidationError> {
+                    Ok(())
+                }
+            }
+        };
+    }
+    
+    impl_transaction!(TransferTransaction);
+    impl_transaction!(HeartbeatTransaction);
+    
+    type Mixed = AnyTransaction<TestState>;
+    
+    struct MixedRuleSet;
+    
+    impl RuleSet<TestState, Mixed> for MixedRuleSet {
+        fn version(&self) -> Version {
+            Version::new(1, 0, 0)
+        }
+        
+        fn apply(&self, state: &TestState, transaction: &Mixed, _context: &ExecutionContext) -> Result<TestState, ProcessingError> {
+            let amount = transaction.downcast_ref::<TransferTransaction>().map_or(0, |t| t.amount);
+            Ok(TestState {
+                balance: state.balance + amount,
+                transaction_count: state.transaction_count + 1,
+            })
+        }
+    }
+    
+    fn transfer(id: &str, to_account: &str, amount: i64) -> Mixed {
+        AnyTransaction::new("transfer", TransferTransaction {
+            id: id.to_string(),
+            from_account: "alice".to_string(),
+            to_account: to_account.to_string(),
+            amount,
+            timestamp: Utc.timestamp_opt(1000000, 0).unwrap(),
+        })
+    }
+    
+    fn heartbeat(id: &str) -> Mixed {
+        AnyTransaction::new("heartbeat", HeartbeatTransaction {
+            id: id.to_string(),
+            timestamp: Utc.timestamp_opt(1000000, 0).unwrap(),
+        })
+    }
+    
+    fn registry() -> ValidationRegistry<Mixed> {
+        let mut registry = ValidationRegistry::new();
+        registry
+            .register_validator(|t: &TransferTransaction| {
+                if t.amount <= 0 || t.from_account == t.to_account {
+                    return Err(ValidationError::InvalidTransaction {
+                        reason: format!("Invalid transfer {}", t.id),
+                    });
+                }
+                Ok(())
+            })
+            .register_validator(|_: &HeartbeatTransaction| Ok(()));
+        registry
+    }
+    
+    #[test]
+    fn test_validators_apply_per_transaction_type() {
+        let transactions = vec![
+            transfer("t1", "bob", 100),
+            heartbeat("h1"),
+            transfer("t2", "bob", 0),
+            transfer("t3", "alice", 50),
+        ];
+        
+        let summary = registry().validate_all(&transactions);
+        assert_eq!(summary.total, 4);
+        assert_eq!(summary.passed, 2);
+        assert_eq!(summary.failed.iter().map(|(index, _)| *index).collect::<Vec<_>>(), vec![2, 3]);
+        
+        let context = ExecutionContext::new(Utc.timestamp_opt(1000000, 0).unwrap(), 42);
+        let mut processor = TransactionProcessor::new(TestState { balance: 0, transaction_count: 0 })
+            .unwrap()
+            .with_validation_registry(registry());
+        let outcomes: Vec<bool> = transactions
+            .iter()
+            .map(|t| processor.process_transaction(t, &MixedRuleSet, &context).is_ok())
+            .collect();
+        assert_eq!(outcomes, vec![true, true, false, false]);
+        assert_eq!(processor.current_state().balance, 100);
+        
+        // Without the registry only Transaction::validate runs
+        let mut lenient = TransactionProcessor::new(TestState { balance: 0, transaction_count: 0 }).unwrap();
+        assert!(lenient.process_transaction(&transactions[3], &MixedRuleSet, &context).is_ok());
+    }
+}
+
+#[cfg(test)]
+mod streaming_tests {
+    use super::*;
+    use std::sync::mpsc;
+    
+    fn transactions(count: usize) -> Vec<TestTransaction> {
+        (0..count)
+            .map(|index| TestTransaction {
+                id: format!("tx{}", index),
+                // The fourth transaction would overdraw the balance
+                amount: if index == 3 { -1_000 } else { 10 },
+                timestamp: Utc.timestamp_opt(1000000 + index as i64, 0).unwrap(),
+            })
+            .collect()
+    }
+    
+    fn rule_set() -> TestRuleSet {
+        TestRuleSet { version: Version::new(1, 0, 0) }
+    }
+    
+    #[test]
+    fn test_streaming_delivers_every_result_through_a_small_channel() {
+        let mut processor = TransactionProcessor::new(TestState { balance: 100, transaction_count: 0 }).unwrap();
+        let context = ExecutionContext::new(Utc.timestamp_opt(1000000, 0).unwrap(), 42);
+        let (sender, receiver) = mpsc::sync_channel(2);
+        
+        let results = std::thread::scope(|scope| {
+            let consumer = scope.spawn(move || receiver.iter().collect::<Vec<_>>());
+            processor.process_transactions_streaming(&transactions(10), &rule_set(), &context, sender).unwrap();
+            consumer.join().unwrap()
+        });
+        
+        assert_eq!(results.len(), 10);
+        assert!(results.iter().enumerate().all(|(index, result)| result.index == index));
+        assert!(!results[3].succeeded);
+        assert!(results[3].new_hash.is_none());
+        assert!(matches!(results[3].error, Some(ProcessingError::TransactionFailed { .. })));
+        assert_eq!(results.iter().filter(|result| result.succeeded).count(), 9);
+        assert_eq!(results[9].new_hash, Some(processor.current_hash()));
+        assert_eq!(results[9].new_state.as_ref(), Some(processor.current_state()));
+        assert_eq!(processor.current_state().balance, 190);
+    }
+    
+    #[test]
+    fn test_nonblocking_streaming_drops_results_when_full() {
+        let mut processor = TransactionProcessor::new(TestState { balance: 100, transaction_count: 0 }).unwrap();
+        let context = ExecutionContext::new(Utc.timestamp_opt(1000000, 0).unwrap(), 42);
+        let (sender, receiver) = mpsc::sync_channel(2);
+        
+        processor
+            .process_transactions_streaming_nonblocking(&transactions(10), &rule_set(), &context, sender)
+            .unwrap();
+        
+        let received: Vec<usize> = receiver.iter().map(|result| result.index).collect();
+        assert_eq!(received, vec![0, 1]);
+        assert_eq!(processor.dropped_results_count(), 8);
+        assert_eq!(processor.transactions_processed(), 9);
+    }
+    
+    #[test]
+    fn test_streaming_stops_when_the_receiver_hangs_up() {
+        let mut processor = TransactionProcessor::new(TestState { balance: 100, transaction_count: 0 }).unwrap();
+        let context = ExecutionContext::new(Utc.timestamp_opt(1000000, 0).unwrap(), 42);
+        let (sender, receiver) = mpsc::sync_channel(2);
+        drop(receiver);
+        
+        let result = processor.process_transactions_streaming(&transactions(10), &rule_set(), &context, sender);
+        assert!(matches!(
+            result,
+            Err(ProcessingError::StreamClosed { ref transaction_id }) if transaction_id == "tx0"
+        ));
+        assert_eq!(processor.transactions_processed(), 1);
+    }
+}
+
+#[cfg(test)]
+mod rule_cache_tests {
+    use super::*;
+    use std::collections::hash_map::DefaultHasher;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    
+    /// Rule set keyed by the balance and amount, counting how often it is applied
+    struct IdempotentRuleSet {
+        applications: AtomicUsize,
+    }
+    
+    impl RuleSet<TestState, TestTransaction> for IdempotentRuleSet {
+        fn version(&self) -> Version {
+            Version::new(1, 0, 0)
+        }
+        
+        fn idempotency_key(&self, state: &TestState, transaction: &TestTransaction, _context: &ExecutionContext) -> Option<u64> {
+            let mut hasher = DefaultHasher::new();
+            (state.balance, state.transaction_count, transaction.amount).hash(&mut hasher);
+            Some(hasher.finish())
+        }
+        
+        fn apply(
+            &self,
+            state: &TestState,
+            transaction: &TestTransaction,
+            _context: &ExecutionContext,
+        ) -> Result<TestState, ProcessingError> {
+            self.applications.fetch_add(1, Ordering::SeqCst);
+            Ok(TestState {
+                balance: state.balance + transaction.amount,
+                transaction_count: state.transaction_count + 1,
+            })
+        }
+    }
+    
+    #[test]
+    fn test_same_state_and_transaction_hits_the_cache() {
+        let initial = TestState { balance: 100, transaction_count: 0 };
+        let mut processor = TransactionProcessor::new(initial.clone()).unwrap().with_rule_cache_capacity(4);
+        let context = ExecutionContext::new(Utc.timestamp_opt(1000000, 0).unwrap(), 42);
+        let rule_set = IdempotentRuleSet { applications: AtomicUsize::new(0) };
+        let transaction = TestTransaction {
+            id: "tx1".to_string(),
+            amount: 50,
+            timestamp: Utc.timestamp_opt(1000000, 0).unwrap(),
+        };
+        
+        let first = processor.process_transaction(&transaction, &rule_set, &context).unwrap();
+        processor.apply_mutation("reset", |_| Ok(initial.clone())).unwrap();
+        let second = processor.process_transaction(&transaction, &rule_set, &context).unwrap();
+        
+        assert_eq!(rule_set.applications.load(Ordering::SeqCst), 1);
+        assert_eq!(first.to_hash, second.to_hash);
+        assert_eq!(processor.current_state().balance, 150);
+        assert_eq!(processor.statistics().rule_cache_hit_rate, 0.5);
+    }
+    
+    #[test]
+    fn test_processor_without_rule_cache_always_applies() {
+        let mut processor = TransactionProcessor::new(TestState { balance: 100, transaction_count: 0 }).unwrap();
+        let context = ExecutionContext::new(Utc.timestamp_opt(1000000, 0).unwrap(), 42);
+        let rule_set = IdempotentRuleSet { applications: AtomicUsize::new(0) };
+        let transaction = TestTransaction {
+            id: "tx1".to_string(),
+            amount: 50,
+            timestamp: Utc.timestamp_opt(1000000, 0).unwrap(),
+        };
+        
+        processor.process_transaction(&transaction, &rule_set, &context).unwrap();
+        processor.apply_mutation("reset", |_| Ok(TestState { balance: 100, transaction_count: 0 })).unwrap();
+        processor.process_transaction(&transaction, &rule_set, &context).unwrap();
+        
+        assert_eq!(rule_set.applications.load(Ordering::SeqCst), 2);
+        assert_eq!(processor.statistics().rule_cache_hit_rate, 0.0);
+    }
+}
+
+#[cfg(test)]
+mod id_normalizer_tests {
+    use super::*;
+    use dtre::TransactionIdNormalizer;
+    
+    fn transaction(id: &str, seconds: i64) -> TestTransaction {
+        TestTransaction {
+            id: id.to_string(),
+            amount: 10,
+            timestamp: Utc.timestamp_opt(seconds, 0).unwrap(),
+        }
+    }
+    
+    #[test]
+    fn test_normalizers() {
+        assert_eq!(TransactionIdNormalizer::Lowercase.normalize(" TXN-001 "), " txn-001 ");
+        assert_eq!(TransactionIdNormalizer::Uppercase.normalize(" txn-001 "), " TXN-001 ");
+        assert_eq!(TransactionIdNormalizer::Trim.normalize(" TXN-001 "), "TXN-001");
+        assert_eq!(TransactionIdNormalizer::TrimLowercase.normalize(" TXN-001 "), "txn-001");
+    }
+    
+    #[test]
+    fn test_trimmed_lowercase_ids_are_traced_and_deduplicated() {
+        let mut processor = TransactionProcessor::new(TestState { balance: 100, transaction_count: 0 })
+            .unwrap()
+            .with_id_normalizer(TransactionIdNormalizer::TrimLowercase)
+            .with_deduplication(true);
+        let context = ExecutionContext::new(Utc.timestamp_opt(1000000, 0).unwrap(), 42);
+        let rule_set = TestRuleSet { version: Version::new(1, 0, 0) };
+        let first = transaction("  TXN-001  ", 1000000);
+        
+        let transition = processor.process_transaction(&first, &rule_set, &context).unwrap();
+        assert_eq!(first.id(), "  TXN-001  ");
+        assert_eq!(transition.transaction_id, "txn-001");
+        let trace = processor.execution_trace();
+        assert_eq!(trace.state_transitions[0].transaction_id, "txn-001");
+        assert_eq!(trace.rule_applications[0].transaction_id, "txn-001");
+        
+        let result = processor.process_transaction(&transaction("TXN-001", 1000001), &rule_set, &context);
+        assert!(matches!(
+            result,
+            Err(ProcessingError::TransactionFailed { ref transaction_id, ref reason })
+                if transaction_id == "txn-001" && reason.contains("Duplicate")
+        ));
+        assert_eq!(processor.transactions_processed(), 1);
+        assert_eq!(processor.current_state().balance, 110);
+    }
+    
+    #[test]
+    fn test_ids_differing_only_in_case_are_distinct_without_normalizer() {
+        let mut processor = TransactionProcessor::new(TestState { balance: 100, transaction_count: 0 })
+            .unwrap()
+            .with_deduplication(true);
+        let context = ExecutionContext::new(Utc.timestamp_opt(1000000, 0).unwrap(), 42);
+        let rule_set = TestRuleSet { version: Version::new(1, 0, 0) };
+        
+        processor.process_transaction(&transaction("TXN-001", 1000000), &rule_set, &context).unwrap();
+        processor.process_transaction(&transaction("txn-001", 1000001), &rule_set, &context).unwrap();
+        assert!(processor.process_transaction(&transaction("TXN-001", 1000002), &rule_set, &context).is_err());
+        assert_eq!(processor.transactions_processed(), 2);
+    }
+}
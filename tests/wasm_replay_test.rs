@@ -0,0 +1,86 @@
+//! Replay tests run in a headless browser with `wasm-pack test --headless --firefox -- --features wasm`
+#![cfg(all(target_arch = "wasm32", feature = "wasm"))]
+
+use dtre::error::{ProcessingError, ValidationError};
+use dtre::wasm_api::{WasmExecutionContext, WasmReplayResult, WasmVersion};
+use dtre::{ExecutionContext, ReplayEngine, RuleSet, State, StateHasher, Transaction, Version};
+use chrono::{DateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen_test::*;
+
+wasm_bindgen_test_configure!(run_in_browser);
+
+#[derive(Debug, Clone, Hash, Serialize, Deserialize, PartialEq)]
+struct TestState {
+    balance: i64,
+}
+
+impl State for TestState {
+    fn validate(&self) -> Result<(), ValidationError> {
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct TestTransaction {
+    id: String,
+    amount: i64,
+    timestamp: DateTime<Utc>,
+}
+
+impl Transaction for TestTransaction {
+    fn id(&self) -> &str {
+        &self.id
+    }
+    
+    fn timestamp(&self) -> DateTime<Utc> {
+        self.timestamp
+    }
+    
+    fn validate(&self) -> Result<(), ValidationError> {
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug)]
+struct TestRuleSet;
+
+impl RuleSet<TestState, TestTransaction> for TestRuleSet {
+    fn version(&self) -> Version {
+        Version::new(1, 0, 0)
+    }
+    
+    fn apply(
+        &self,
+        state: &TestState,
+        transaction: &TestTransaction,
+        _context: &ExecutionContext,
+    ) -> Result<TestState, ProcessingError> {
+        Ok(TestState { balance: state.balance + transaction.amount })
+    }
+}
+
+#[wasm_bindgen_test]
+fn test_simple_replay_produces_expected_final_hash() {
+    let context = WasmExecutionContext::new(1_700_000_000_000.0, 42).unwrap();
+    let engine = ReplayEngine::new(TestState { balance: 100 }, TestRuleSet, context.inner().clone());
+    
+    let timestamp = Utc.timestamp_opt(1_700_000_000, 0).unwrap();
+    let transactions: Vec<TestTransaction> = (1..=3)
+        .map(|i| TestTransaction { id: format!("tx{}", i), amount: i * 10, timestamp })
+        .collect();
+    
+    let result = engine.replay(&transactions).unwrap();
+    let wasm_result = WasmReplayResult::from_result(&result).unwrap();
+    
+    let expected_hash = StateHasher::new().hash(&TestState { balance: 160 });
+    assert_eq!(wasm_result.final_hash_hex(), expected_hash.to_string());
+    assert_eq!(wasm_result.final_state_json(), r#"{"balance":160}"#);
+    assert_eq!(wasm_result.transactions_processed(), 3);
+}
+
+#[wasm_bindgen_test]
+fn test_version_from_string() {
+    let version = WasmVersion::from_string("2.1.0").unwrap();
+    assert_eq!(version.inner(), &Version::new(2, 1, 0));
+}